@@ -17,8 +17,14 @@ use crate::{
     cache::load_cache,
     candy_machine::{CANDY_MACHINE_ID, *},
     common::*,
-    mint::mint,
+    fee_payers::{fee_payer_funding, print_swept, sweep_fee_payers, FeePayerPool},
+    journal::{EntryStatus, Journal},
+    mint::{
+        estimate_mint_cost, mint, resolve_in_flight, write_receipts, InFlightEntry, MintReceipt,
+        ReceiptWriter, SendOptions,
+    },
     pdas::get_metadata_pda,
+    rate_limit::throttled_client_with_commitment,
     utils::*,
 };
 
@@ -28,6 +34,8 @@ pub struct AirdropArgs {
     pub cache: String,
     pub candy_machine: Option<String>,
     pub airdrop_list: String,
+    pub receipts: String,
+    pub fee_payers: Option<usize>,
 }
 
 pub async fn process_airdrop(args: AirdropArgs) -> Result<()> {
@@ -99,36 +107,112 @@ pub async fn process_airdrop(args: AirdropArgs) -> Result<()> {
         );
     }
 
+    // parallel mints are paid round-robin by the fee payers of the pool
+    let fee_payers = match args.fee_payers {
+        Some(size) => {
+            let pool = FeePayerPool::load_or_create(size)?;
+            let rpc_client = throttled_client_with_commitment(&sugar_config.rpc_url);
+            // the wallet signs each mint as the authority
+            let cost = estimate_mint_cost(&rpc_client, None)? + 5_000;
+            pool.fund(
+                &sugar_config,
+                fee_payer_funding(&rpc_client, airdrop_total, pool.len(), cost)?,
+            )
+            .await?;
+            Some(Arc::new(pool))
+        }
+        None => None,
+    };
+
     info!("Minting NFT from candy machine: {}", &candy_machine_id);
     info!("Candy machine program id: {:?}", CANDY_MACHINE_ID);
 
+    let receipts = Arc::new(ReceiptWriter::open(&args.receipts)?);
+    let journal = Arc::new(Journal::open("airdrop", &candy_machine_id)?);
+    let mut resent = 0;
+    let rpc_client = throttled_client_with_commitment(&sugar_config.rpc_url);
+
     let pb = progress_bar_with_style(airdrop_total);
     let mut tasks = Vec::new();
     let semaphore = Arc::new(Semaphore::new(10));
     let config = Arc::new(sugar_config);
 
     for (address, num) in airdrop_list.drain() {
-        for _i in 0..num {
+        for i in 0..num {
+            let key = format!("{}:{}", address, i);
+
+            // mints of an interrupted run are only sent again when their transaction
+            // did not land and can no longer land
+            let landed = match journal.status(&key) {
+                Some(EntryStatus::Completed) => Some(journal.detail(&key).unwrap_or_default()),
+                Some(EntryStatus::Pending) => {
+                    match resolve_in_flight(&rpc_client, journal.detail(&key)).await? {
+                        Some(signature) => {
+                            journal.mark_completed(&key, Some(signature.to_string()))?;
+                            Some(signature.to_string())
+                        }
+                        None => {
+                            resent += 1;
+                            None
+                        }
+                    }
+                }
+                None => None,
+            };
+
+            if let Some(signature) = landed {
+                airdrop_results
+                    .lock()
+                    .unwrap()
+                    .entry(address)
+                    .or_insert_with(Vec::new)
+                    .push(TransactionResult {
+                        signature,
+                        status: true,
+                    });
+                pb.inc(1);
+                continue;
+            }
+
             let results = airdrop_results.clone();
+            let journal = journal.clone();
             let config = config.clone();
+            let receipts = receipts.clone();
             let permit = Arc::clone(&semaphore).acquire_owned().await.unwrap();
             let candy_machine_state = candy_machine_state.clone();
             let target = address.0;
             let pb = pb.clone();
+            let fee_payer = fee_payers.as_ref().map(|pool| pool.next());
 
             // Start tasks
             tasks.push(tokio::spawn(async move {
                 let _permit = permit;
+                journal.mark_pending(&key)?;
                 let res = mint(
-                    config,
+                    Arc::clone(&config),
                     candy_pubkey,
                     candy_machine_state,
                     collection_update_authority,
                     target,
+                    None,
+                    SendOptions {
+                        fee_payer,
+                        in_flight: Some(InFlightEntry {
+                            journal: journal.clone(),
+                            key: key.clone(),
+                        }),
+                    },
                 )
                 .await;
                 pb.inc(1);
 
+                if let Ok((signature, mint)) = &res {
+                    if let Err(err) = journal.mark_completed(&key, Some(signature.to_string())) {
+                        warn!("Failed to update the airdrop journal: {}", err);
+                    }
+                    receipts.append(MintReceipt::new(None, *mint, target, *signature));
+                }
+
                 let mut results = results.lock().unwrap();
                 results.entry(address).or_insert_with(Vec::new);
                 let signatures = results.get_mut(&address).unwrap();
@@ -141,14 +225,6 @@ pub async fn process_airdrop(args: AirdropArgs) -> Result<()> {
                         });
                     }
                     Err(err) => {
-                        // Assume timeouts succeed to avoid sending double to a recipient.
-                        if err.to_string().contains("Transaction was not confirmed in") {
-                            signatures.push(TransactionResult {
-                                signature: "RPC timeout: unknown if transaction succeeded"
-                                    .to_string(),
-                                status: true,
-                            });
-                        }
                         signatures.push(TransactionResult {
                             signature: err.to_string(),
                             status: false,
@@ -173,19 +249,44 @@ pub async fn process_airdrop(args: AirdropArgs) -> Result<()> {
     }
 
     write_airdrop_results(&airdrop_results.lock().unwrap())?;
+    write_receipts(
+        &rpc_client,
+        &receipts,
+        Some((&candy_pubkey, candy_machine_state.as_ref())),
+        &args.receipts,
+    )?;
+
+    if fee_payers.is_some() {
+        let swept = sweep_fee_payers(&config)?;
+        print_swept(&config.keypair.pubkey(), swept);
+    }
+
+    // the results file now records every mint of this run
+    if let Ok(journal) = Arc::try_unwrap(journal) {
+        journal.finish()?;
+    }
+
+    if resent > 0 {
+        println!(
+            "{}{} mint(s) of the previous run did not land and were sent again.",
+            WARNING_EMOJI, resent
+        );
+    }
+
     if error_count > 0 {
         pb.abandon_with_message(format!(
             "{} {} items failed.",
             style("Some of the items failed to mint.").red().bold(),
             error_count
         ));
-        return Err(anyhow!(
+        return Err(SugarError::PartialFailure(format!(
             "{} {}/{} {}",
             style("Minted").red().bold(),
             airdrop_total - error_count,
             airdrop_total,
             style("of the items").red().bold()
-        ));
+        ))
+        .into());
     }
     pb.finish();
 
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+use anchor_client::solana_sdk::native_token::LAMPORTS_PER_SOL;
+use console::style;
+use tabled::{builder::Builder, settings::Style};
+
+use crate::{
+    analytics::{fetch_mint_transactions, TimeRange},
+    cache::load_cache,
+    candy_machine::CANDY_MACHINE_ID,
+    common::*,
+};
+
+/// Log message of the candy guard when a transaction is bot-taxed.
+const BOT_TAX_LOG: &str = "Candy Guard Botting is taxed at ";
+
+pub struct BotTaxArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_machine: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+}
+
+/// Outcome of a mint transaction.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MintOutcome {
+    Minted,
+    BotTaxed { reason: String, lamports: u64 },
+    Failed,
+}
+
+/// Classify a mint transaction from its log messages.
+pub fn classify_mint(logs: &[String], failed: bool) -> MintOutcome {
+    if let Some(log) = logs.iter().find(|log| log.contains(BOT_TAX_LOG)) {
+        let lamports = log
+            .split(BOT_TAX_LOG)
+            .nth(1)
+            .and_then(|tax| tax.split_whitespace().next())
+            .and_then(|lamports| lamports.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        let reason = log
+            .split("Error Code: ")
+            .nth(1)
+            .and_then(|code| code.split('.').next())
+            .unwrap_or("Unknown")
+            .to_string();
+
+        MintOutcome::BotTaxed { reason, lamports }
+    } else if failed {
+        MintOutcome::Failed
+    } else {
+        MintOutcome::Minted
+    }
+}
+
+pub fn process_bot_tax(args: BotTaxArgs) -> Result<()> {
+    let range = TimeRange::parse(args.start, args.end)?;
+
+    let candy_machine_id = match args.candy_machine {
+        Some(candy_machine) => candy_machine,
+        None => load_cache(&args.cache, false)?.program.candy_machine,
+    };
+
+    let candy_machine_id = Pubkey::from_str(&candy_machine_id)
+        .map_err(|_| anyhow!("Failed to parse candy machine id: {}", candy_machine_id))?;
+
+    println!(
+        "{} {}Scanning mint transactions ({})",
+        style("[1/1]").bold().dim(),
+        LOOKING_GLASS_EMOJI,
+        range.describe()
+    );
+
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(CANDY_MACHINE_ID);
+
+    let transactions = fetch_mint_transactions(&program.rpc(), &candy_machine_id, &range)?;
+
+    let mut minted = 0;
+    let mut failed = 0;
+    let mut taxed: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+
+    for transaction in &transactions {
+        match classify_mint(&transaction.logs, transaction.failed) {
+            MintOutcome::Minted => minted += 1,
+            MintOutcome::Failed => failed += 1,
+            MintOutcome::BotTaxed { reason, lamports } => {
+                let entry = taxed.entry(reason).or_default();
+                entry.0 += 1;
+                entry.1 += lamports;
+            }
+        }
+    }
+
+    println!(
+        "\n{} {}",
+        style("Candy machine ID:").bold(),
+        candy_machine_id
+    );
+    println!(
+        "{} {}",
+        style("Mint transactions:").bold(),
+        transactions.len()
+    );
+
+    let mut builder = Builder::default();
+    builder.push_record(["Outcome", "Transactions", "Bot tax (SOL)"]);
+    builder.push_record(["Minted".to_string(), minted.to_string(), "-".to_string()]);
+
+    let mut total_taxed = 0;
+    let mut total_lamports = 0;
+
+    for (reason, (count, lamports)) in &taxed {
+        total_taxed += count;
+        total_lamports += lamports;
+        builder.push_record([
+            format!("Bot-taxed: {}", reason),
+            count.to_string(),
+            (*lamports as f64 / LAMPORTS_PER_SOL as f64).to_string(),
+        ]);
+    }
+
+    builder.push_record(["Failed".to_string(), failed.to_string(), "-".to_string()]);
+
+    let mut table = builder.build();
+    table.with(Style::psql());
+    println!("\n{}", table);
+
+    println!(
+        "\n{} {} transaction(s) bot-taxed, ◎ {} earned",
+        style("Bot tax:").bold(),
+        total_taxed,
+        total_lamports as f64 / LAMPORTS_PER_SOL as f64
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_classify_bot_taxed_mint() {
+    let logs = vec![
+        "Program log: Instruction: MintV2".to_string(),
+        "Program log: AnchorError occurred. Error Code: MintNotLive. Error Number: 6031. \
+            Error Message: Mint is not live., Candy Guard Botting is taxed at 10000000 lamports"
+            .to_string(),
+    ];
+
+    assert_eq!(
+        classify_mint(&logs, false),
+        MintOutcome::BotTaxed {
+            reason: "MintNotLive".to_string(),
+            lamports: 10_000_000
+        }
+    );
+    assert_eq!(classify_mint(&logs[..1], false), MintOutcome::Minted);
+    assert_eq!(classify_mint(&logs[..1], true), MintOutcome::Failed);
+}
@@ -0,0 +1,292 @@
+use std::{collections::BTreeMap, fs, io::Write};
+
+use anchor_client::solana_sdk::native_token::LAMPORTS_PER_SOL;
+use chrono::{TimeZone, Utc};
+use console::style;
+use mpl_candy_guard::state::{CandyGuardData, GuardSet, DATA_OFFSET};
+use tabled::{builder::Builder, settings::Style};
+
+use crate::{
+    analytics::{classify_mint, fetch_mint_transactions, MintOutcome, MintTransaction, TimeRange},
+    cache::load_cache,
+    candy_machine::CANDY_MACHINE_ID,
+    common::*,
+    mint::merge_guards,
+};
+
+/// Name used in the report for mints without a guard group.
+const DEFAULT_GROUP: &str = "default";
+
+/// Header of the earnings CSV file.
+const EARNINGS_HEADER: &str = "date,group,mints,sol,token_mint,token_amount";
+
+pub struct EarningsArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_guard: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub csv: Option<String>,
+}
+
+/// Proceeds of the mints of a guard group in a single day.
+#[derive(Debug, Default)]
+struct Earnings {
+    mints: u64,
+    lamports: u64,
+    token_mint: Option<Pubkey>,
+    token_amount: u128,
+}
+
+pub fn process_earnings(args: EarningsArgs) -> Result<()> {
+    let range = TimeRange::parse(args.start, args.end)?;
+
+    let candy_guard_id = match args.candy_guard {
+        Some(candy_guard) => candy_guard,
+        None => load_cache(&args.cache, false)?.program.candy_guard,
+    };
+
+    if candy_guard_id.is_empty() {
+        return Err(anyhow!("Missing candy guard id."));
+    }
+
+    let candy_guard_id = Pubkey::from_str(&candy_guard_id)
+        .map_err(|_| anyhow!("Failed to parse candy guard id: {}", candy_guard_id))?;
+
+    println!(
+        "{} {}Scanning mint transactions ({})",
+        style("[1/1]").bold().dim(),
+        LOOKING_GLASS_EMOJI,
+        range.describe()
+    );
+
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(CANDY_MACHINE_ID);
+    let rpc_client = program.rpc();
+
+    let account_data = rpc_client.get_account_data(&candy_guard_id)?;
+    let candy_guard_data = CandyGuardData::load(&account_data[DATA_OFFSET..])?;
+
+    let mut guard_sets: HashMap<String, GuardSet> = HashMap::new();
+    guard_sets.insert(DEFAULT_GROUP.to_string(), candy_guard_data.default.clone());
+
+    for group in candy_guard_data.groups.unwrap_or_default() {
+        guard_sets.insert(
+            group.label,
+            merge_guards(candy_guard_data.default.clone(), group.guards),
+        );
+    }
+
+    let transactions = fetch_mint_transactions(&rpc_client, &candy_guard_id, &range)?;
+    let earnings = aggregate_earnings(&transactions, &guard_sets);
+
+    println!("\n{} {}", style("Candy guard ID:").bold(), candy_guard_id);
+
+    let mut builder = Builder::default();
+    builder.push_record(["Date", "Group", "Mints", "SOL", "Token", "Token amount"]);
+
+    let mut total_mints = 0;
+    let mut total_lamports = 0;
+
+    for ((date, group), entry) in &earnings {
+        total_mints += entry.mints;
+        total_lamports += entry.lamports;
+        builder.push_record([
+            date.clone(),
+            group.clone(),
+            entry.mints.to_string(),
+            (entry.lamports as f64 / LAMPORTS_PER_SOL as f64).to_string(),
+            entry
+                .token_mint
+                .map(|mint| mint.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            entry.token_amount.to_string(),
+        ]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::psql());
+    println!("\n{}", table);
+
+    println!(
+        "\n{} {} mint(s), ◎ {}",
+        style("Total:").bold(),
+        total_mints,
+        total_lamports as f64 / LAMPORTS_PER_SOL as f64
+    );
+
+    if let Some(csv) = args.csv {
+        let mut file = fs::File::create(&csv)?;
+        writeln!(file, "{}", EARNINGS_HEADER)?;
+
+        for ((date, group), entry) in &earnings {
+            writeln!(
+                file,
+                "{},{},{},{},{},{}",
+                date,
+                group,
+                entry.mints,
+                entry.lamports as f64 / LAMPORTS_PER_SOL as f64,
+                entry
+                    .token_mint
+                    .map(|mint| mint.to_string())
+                    .unwrap_or_default(),
+                entry.token_amount
+            )?;
+        }
+
+        println!("\nEarnings saved to '{}'", csv);
+    }
+
+    Ok(())
+}
+
+/// Sums the proceeds of the successful mints by day and guard group.
+fn aggregate_earnings(
+    transactions: &[MintTransaction],
+    guard_sets: &HashMap<String, GuardSet>,
+) -> BTreeMap<(String, String), Earnings> {
+    let mut earnings: BTreeMap<(String, String), Earnings> = BTreeMap::new();
+
+    for transaction in transactions {
+        if classify_mint(&transaction.logs, transaction.failed) != MintOutcome::Minted {
+            continue;
+        }
+
+        let group = transaction
+            .label
+            .clone()
+            .unwrap_or_else(|| DEFAULT_GROUP.to_string());
+
+        let guards = match guard_sets.get(&group) {
+            Some(guards) => guards,
+            None => {
+                warn!(
+                    "Skipping transaction {} from unknown group '{}'",
+                    transaction.signature, group
+                );
+                continue;
+            }
+        };
+
+        let date = Utc
+            .timestamp_opt(transaction.block_time, 0)
+            .single()
+            .map(|date| date.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+
+        let entry = earnings.entry((date, group)).or_default();
+        entry.mints += 1;
+
+        if let Some(sol_payment) = &guards.sol_payment {
+            entry.lamports += transaction.balance_change(&sol_payment.destination).max(0) as u64;
+        }
+
+        if let Some(token_payment) = &guards.token_payment {
+            entry.token_mint = Some(token_payment.mint);
+            entry.token_amount += transaction
+                .token_balance_change(&token_payment.destination_ata)
+                .max(0) as u128;
+        }
+    }
+
+    earnings
+}
+
+#[test]
+fn test_aggregate_earnings() {
+    use crate::config::{GuardSet as ConfigGuardSet, SolPayment, TokenPayment};
+
+    let destination = Pubkey::new_unique();
+    let destination_ata = Pubkey::new_unique();
+    let token_mint = Pubkey::new_unique();
+    let minter = Pubkey::new_unique();
+
+    let mut guard_sets = HashMap::new();
+    guard_sets.insert(
+        DEFAULT_GROUP.to_string(),
+        ConfigGuardSet {
+            sol_payment: Some(SolPayment {
+                value: 1.0,
+                destination,
+            }),
+            ..Default::default()
+        }
+        .to_guard_format()
+        .unwrap(),
+    );
+    guard_sets.insert(
+        "wl".to_string(),
+        ConfigGuardSet {
+            token_payment: Some(TokenPayment {
+                amount: 5,
+                mint: token_mint,
+                destination_ata,
+            }),
+            ..Default::default()
+        }
+        .to_guard_format()
+        .unwrap(),
+    );
+
+    let token_balance = |amount: u64| -> solana_transaction_status::UiTransactionTokenBalance {
+        serde_json::from_value(json!({
+            "accountIndex": 2,
+            "mint": token_mint.to_string(),
+            "uiTokenAmount": {
+                "uiAmount": amount as f64,
+                "decimals": 0,
+                "amount": amount.to_string(),
+                "uiAmountString": amount.to_string(),
+            },
+        }))
+        .unwrap()
+    };
+
+    let transaction = |label: Option<&str>, block_time: i64, failed: bool| MintTransaction {
+        signature: String::new(),
+        block_time,
+        failed,
+        label: label.map(|label| label.to_string()),
+        logs: Vec::new(),
+        account_keys: vec![minter, destination, destination_ata],
+        pre_balances: vec![3 * LAMPORTS_PER_SOL, 0, 0],
+        post_balances: vec![2 * LAMPORTS_PER_SOL, LAMPORTS_PER_SOL, 0],
+        pre_token_balances: vec![token_balance(10)],
+        post_token_balances: vec![token_balance(15)],
+    };
+
+    // 2023-01-01 and 2023-01-02 (UTC)
+    let day_one = 1672531200;
+    let day_two = day_one + 86400;
+
+    let transactions = vec![
+        transaction(None, day_one, false),
+        transaction(None, day_one + 60, false),
+        transaction(None, day_two, false),
+        transaction(Some("wl"), day_two, false),
+        // failed and unknown group transactions are not counted
+        transaction(None, day_two, true),
+        transaction(Some("unknown"), day_two, false),
+    ];
+
+    let earnings = aggregate_earnings(&transactions, &guard_sets);
+    assert_eq!(earnings.len(), 3);
+
+    let default_day_one = &earnings[&("2023-01-01".to_string(), DEFAULT_GROUP.to_string())];
+    assert_eq!(default_day_one.mints, 2);
+    assert_eq!(default_day_one.lamports, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(default_day_one.token_mint, None);
+
+    let default_day_two = &earnings[&("2023-01-02".to_string(), DEFAULT_GROUP.to_string())];
+    assert_eq!(default_day_two.mints, 1);
+    assert_eq!(default_day_two.lamports, LAMPORTS_PER_SOL);
+
+    let wl = &earnings[&("2023-01-02".to_string(), "wl".to_string())];
+    assert_eq!(wl.mints, 1);
+    assert_eq!(wl.lamports, 0);
+    assert_eq!(wl.token_mint, Some(token_mint));
+    assert_eq!(wl.token_amount, 5);
+}
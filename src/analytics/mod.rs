@@ -0,0 +1,7 @@
+pub mod bot_tax;
+pub mod earnings;
+pub mod transactions;
+
+pub use bot_tax::*;
+pub use earnings::*;
+pub use transactions::*;
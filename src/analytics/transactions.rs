@@ -0,0 +1,263 @@
+use anchor_client::solana_sdk::transaction::VersionedTransaction;
+use borsh::BorshDeserialize;
+use chrono::{TimeZone, Utc};
+use dateparser::DateTimeUtc;
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::RpcTransactionConfig,
+};
+use solana_transaction_status::{UiTransactionEncoding, UiTransactionTokenBalance};
+
+use crate::{common::*, utils::*};
+
+/// Maximum number of signatures returned by the RPC per request.
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+/// Log message of the candy guard mint instructions.
+const MINT_INSTRUCTION_LOG: &str = "Program log: Instruction: Mint";
+
+/// Time range (unix timestamps) of the transactions to scan.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TimeRange {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+}
+
+impl TimeRange {
+    pub fn parse(start: Option<String>, end: Option<String>) -> Result<Self> {
+        let parse_date = |date: String| -> Result<i64> {
+            Ok(date
+                .parse::<DateTimeUtc>()
+                .map_err(|_| anyhow!("Invalid date: {}", date))?
+                .0
+                .timestamp())
+        };
+
+        let range = TimeRange {
+            start: start.map(parse_date).transpose()?,
+            end: end.map(parse_date).transpose()?,
+        };
+
+        if let (Some(start), Some(end)) = (range.start, range.end) {
+            if start > end {
+                return Err(anyhow!("The start date must be before the end date"));
+            }
+        }
+
+        Ok(range)
+    }
+
+    pub fn contains(&self, timestamp: i64) -> bool {
+        self.start.map_or(true, |start| timestamp >= start)
+            && self.end.map_or(true, |end| timestamp <= end)
+    }
+
+    /// Human readable description of the range.
+    pub fn describe(&self) -> String {
+        let format = |timestamp: i64| {
+            Utc.timestamp_opt(timestamp, 0)
+                .single()
+                .map(|date| date.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                .unwrap_or_else(|| timestamp.to_string())
+        };
+
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => format!("{} to {}", format(start), format(end)),
+            (Some(start), None) => format!("since {}", format(start)),
+            (None, Some(end)) => format!("until {}", format(end)),
+            (None, None) => "all time".to_string(),
+        }
+    }
+}
+
+/// Details of a mint transaction used by the analytics reports.
+pub struct MintTransaction {
+    pub signature: String,
+    pub block_time: i64,
+    pub failed: bool,
+    /// Label of the guard group used to mint, if any.
+    pub label: Option<String>,
+    pub logs: Vec<String>,
+    pub account_keys: Vec<Pubkey>,
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    pub pre_token_balances: Vec<UiTransactionTokenBalance>,
+    pub post_token_balances: Vec<UiTransactionTokenBalance>,
+}
+
+impl MintTransaction {
+    /// Return the lamports change of the specified account.
+    pub fn balance_change(&self, account: &Pubkey) -> i64 {
+        self.account_keys
+            .iter()
+            .position(|key| key == account)
+            .map(|index| self.post_balances[index] as i64 - self.pre_balances[index] as i64)
+            .unwrap_or(0)
+    }
+
+    /// Return the token amount change of the specified token account.
+    pub fn token_balance_change(&self, token_account: &Pubkey) -> i128 {
+        let index = match self
+            .account_keys
+            .iter()
+            .position(|key| key == token_account)
+        {
+            Some(index) => index as u8,
+            None => return 0,
+        };
+
+        let amount = |balances: &[UiTransactionTokenBalance]| -> i128 {
+            balances
+                .iter()
+                .find(|balance| balance.account_index == index)
+                .and_then(|balance| balance.ui_token_amount.amount.parse::<i128>().ok())
+                .unwrap_or(0)
+        };
+
+        amount(&self.post_token_balances) - amount(&self.pre_token_balances)
+    }
+}
+
+/// Fetch the mint transactions of the specified address (most recent first) that
+/// happened within the time range.
+pub fn fetch_mint_transactions(
+    rpc_client: &RpcClient,
+    address: &Pubkey,
+    range: &TimeRange,
+) -> Result<Vec<MintTransaction>> {
+    let pb = spinner_with_style();
+    pb.set_message("Fetching signatures...");
+
+    let mut signatures = Vec::new();
+    let mut before = None;
+
+    'pages: loop {
+        let page = rpc_client.get_signatures_for_address_with_config(
+            address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(SIGNATURES_PAGE_SIZE),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?;
+
+        let last = match page.last() {
+            Some(last) => Signature::from_str(&last.signature)?,
+            None => break,
+        };
+        let page_len = page.len();
+
+        for status in page {
+            let block_time = match status.block_time {
+                Some(block_time) => block_time,
+                None => continue,
+            };
+
+            // signatures are sorted from the most recent
+            if range.start.map_or(false, |start| block_time < start) {
+                break 'pages;
+            }
+
+            if range.contains(block_time) {
+                signatures.push((status.signature, block_time));
+            }
+        }
+
+        if page_len < SIGNATURES_PAGE_SIZE {
+            break;
+        }
+
+        before = Some(last);
+        pb.set_message(format!(
+            "Fetching signatures... ({} found)",
+            signatures.len()
+        ));
+    }
+
+    pb.finish_and_clear();
+
+    let pb = progress_bar_with_style(signatures.len() as u64);
+    pb.set_message("Fetching transactions");
+
+    let mut transactions = Vec::new();
+
+    for (signature, block_time) in signatures {
+        let transaction = rpc_client.get_transaction_with_config(
+            &Signature::from_str(&signature)?,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        pb.inc(1);
+
+        let meta = match transaction.transaction.meta {
+            Some(meta) => meta,
+            None => continue,
+        };
+
+        let logs: Vec<String> = Option::from(meta.log_messages).unwrap_or_default();
+
+        if !logs.iter().any(|log| log.starts_with(MINT_INSTRUCTION_LOG)) {
+            continue;
+        }
+
+        let decoded: Option<VersionedTransaction> = transaction.transaction.transaction.decode();
+        let label = decoded.as_ref().and_then(find_guard_label);
+        let account_keys = decoded
+            .map(|tx| tx.message.static_account_keys().to_vec())
+            .unwrap_or_default();
+
+        transactions.push(MintTransaction {
+            signature,
+            block_time,
+            failed: meta.err.is_some(),
+            label,
+            logs,
+            account_keys,
+            pre_balances: meta.pre_balances,
+            post_balances: meta.post_balances,
+            pre_token_balances: Option::from(meta.pre_token_balances).unwrap_or_default(),
+            post_token_balances: Option::from(meta.post_token_balances).unwrap_or_default(),
+        });
+    }
+
+    pb.finish_and_clear();
+
+    Ok(transactions)
+}
+
+/// Return the group label of the candy guard mint instruction of the transaction.
+fn find_guard_label(transaction: &VersionedTransaction) -> Option<String> {
+    let account_keys = transaction.message.static_account_keys();
+
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .filter(|ix| account_keys.get(ix.program_id_index as usize) == Some(&mpl_candy_guard::ID))
+        .find_map(|ix| {
+            // skips the instruction discriminator; mint instructions take the
+            // mint arguments followed by the group label
+            let data = ix.data.get(8..)?;
+            <(Vec<u8>, Option<String>)>::try_from_slice(data)
+                .ok()
+                .and_then(|(_, label)| label)
+        })
+}
+
+#[test]
+fn test_time_range() {
+    assert!(TimeRange::parse(
+        Some("2023-01-02".to_string()),
+        Some("2023-01-01".to_string())
+    )
+    .is_err());
+
+    let range = TimeRange::parse(Some("2023-01-01T00:00:00Z".to_string()), None).unwrap();
+    assert!(!range.contains(1672531199));
+    assert!(range.contains(1672531200));
+    assert!(TimeRange::default().contains(0));
+}
@@ -0,0 +1,209 @@
+use anchor_client::solana_sdk::program_pack::Pack;
+use console::style;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token::state::Account as TokenAccount;
+use tabled::{builder::Builder, settings::Style};
+
+use crate::{
+    ata::TOKEN_2022_PROGRAM_ID,
+    candy_machine::CANDY_MACHINE_ID,
+    common::*,
+    config::{get_config_data, GuardSet},
+    utils::*,
+};
+
+pub struct AtaCheckArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub config: String,
+}
+
+/// Destination token account referenced by a payment guard of the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtaReference {
+    /// Guard set of the guard ("default" or the label of a group).
+    pub scope: String,
+    pub guard: &'static str,
+    pub mint: Pubkey,
+    pub destination_ata: Pubkey,
+}
+
+/// Returns the destination token accounts of the tokenPayment, freezeTokenPayment and
+/// token2022Payment guards of a guard set.
+pub fn ata_references(scope: &str, guard_set: &GuardSet) -> Vec<AtaReference> {
+    let reference = |guard, mint, destination_ata| AtaReference {
+        scope: scope.to_string(),
+        guard,
+        mint,
+        destination_ata,
+    };
+    let mut references = Vec::new();
+
+    if let Some(token_payment) = &guard_set.token_payment {
+        references.push(reference(
+            "tokenPayment",
+            token_payment.mint,
+            token_payment.destination_ata,
+        ));
+    }
+
+    if let Some(freeze_token_payment) = &guard_set.freeze_token_payment {
+        references.push(reference(
+            "freezeTokenPayment",
+            freeze_token_payment.mint,
+            freeze_token_payment.destination_ata,
+        ));
+    }
+
+    if let Some(token2022_payment) = &guard_set.token2022_payment {
+        references.push(reference(
+            "token2022Payment",
+            token2022_payment.mint,
+            token2022_payment.destination_ata,
+        ));
+    }
+
+    references
+}
+
+pub fn process_ata_check(args: AtaCheckArgs) -> Result<()> {
+    println!(
+        "{} {}Checking the token accounts of '{}'",
+        style("[1/1]").bold().dim(),
+        LOOKING_GLASS_EMOJI,
+        args.config
+    );
+
+    let config_data = get_config_data(&args.config)?;
+    let mut references = Vec::new();
+
+    if let Some(guards) = &config_data.guards {
+        references.extend(ata_references("default", &guards.default));
+
+        for group in guards.groups.iter().flatten() {
+            references.extend(ata_references(&group.label, &group.guards));
+        }
+    }
+
+    if references.is_empty() {
+        println!(
+            "\n{}",
+            style("The config has no token payment guards.").dim()
+        );
+        return Ok(());
+    }
+
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(CANDY_MACHINE_ID);
+    let rpc = program.rpc();
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let mut builder = Builder::default();
+    builder.push_record(["guard set", "guard", "destination", "owner", "status"]);
+    let mut invalid = 0;
+
+    for reference in &references {
+        let account = rpc
+            .get_account_with_commitment(&reference.destination_ata, rpc.commitment())?
+            .value;
+
+        let (owner, status) = match account {
+            None => (None, Err("does not exist".to_string())),
+            Some(account)
+                if (account.owner != spl_token::ID && account.owner != TOKEN_2022_PROGRAM_ID)
+                    || account.data.len() < TokenAccount::LEN =>
+            {
+                (None, Err("not a token account".to_string()))
+            }
+            Some(account) => {
+                // token-2022 accounts may have extensions after the base account data
+                let token_account =
+                    TokenAccount::unpack_from_slice(&account.data[..TokenAccount::LEN])?;
+                let ata = get_associated_token_address_with_program_id(
+                    &token_account.owner,
+                    &reference.mint,
+                    &account.owner,
+                );
+
+                let status = if token_account.mint != reference.mint {
+                    Err(format!("holds tokens of mint {}", token_account.mint))
+                } else if ata != reference.destination_ata {
+                    Ok("valid (not an associated token account)".to_string())
+                } else {
+                    Ok("valid".to_string())
+                };
+
+                (Some(token_account.owner), status)
+            }
+        };
+
+        let status = match status {
+            Ok(status) => style(status).green().to_string(),
+            Err(status) => {
+                invalid += 1;
+                style(status).red().to_string()
+            }
+        };
+
+        builder.push_record([
+            reference.scope.clone(),
+            reference.guard.to_string(),
+            reference.destination_ata.to_string(),
+            owner.map_or("-".to_string(), |owner| owner.to_string()),
+            status,
+        ]);
+    }
+
+    pb.finish_and_clear();
+
+    let mut table = builder.build();
+    table.with(Style::psql());
+    println!("\n{table}\n");
+
+    if invalid > 0 {
+        return Err(anyhow!(
+            "{} destination token account(s) are invalid, create them with 'sugar ata create \
+             --mint <MINT> --owner <WALLET>'.",
+            invalid
+        ));
+    }
+
+    println!(
+        "{}",
+        style("All destination token accounts are valid.")
+            .green()
+            .bold()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_payment_ata_references() {
+    use crate::config::{FreezeTokenPayment, TokenPayment};
+
+    let mint = Pubkey::new_unique();
+    let destination_ata = Pubkey::new_unique();
+    let guard_set = GuardSet {
+        token_payment: Some(TokenPayment {
+            amount: 1,
+            mint,
+            destination_ata,
+        }),
+        freeze_token_payment: Some(FreezeTokenPayment {
+            amount: 1,
+            mint,
+            destination_ata,
+        }),
+        ..Default::default()
+    };
+
+    let references = ata_references("public", &guard_set);
+
+    assert_eq!(references.len(), 2);
+    assert_eq!(references[1].guard, "freezeTokenPayment");
+    assert_eq!(references[0].scope, "public");
+}
@@ -0,0 +1,104 @@
+use console::style;
+use solana_program::pubkey;
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id, instruction::create_associated_token_account,
+};
+
+use crate::{candy_machine::CANDY_MACHINE_ID, common::*, dry_run::SendOrPlan, utils::*};
+
+/// Token-2022 program, used by the mints of the token2022Payment guard.
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PZnBSbcuwbG");
+
+pub struct AtaCreateArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub mint: String,
+    pub owner: Option<String>,
+}
+
+pub fn process_ata_create(args: AtaCreateArgs) -> Result<()> {
+    let mint = Pubkey::from_str(&args.mint)
+        .map_err(|_| anyhow!("Failed to parse mint address: {}", args.mint))?;
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(CANDY_MACHINE_ID);
+    let payer = program.payer();
+
+    let owner = match &args.owner {
+        Some(owner) => {
+            Pubkey::from_str(owner).map_err(|_| anyhow!("Failed to parse owner: {}", owner))?
+        }
+        None => payer,
+    };
+
+    println!(
+        "{} {}Looking up mint {}",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI,
+        mint
+    );
+
+    let rpc = program.rpc();
+    // the mint account owner is the token program (spl-token or token-2022)
+    let token_program = rpc
+        .get_account_with_commitment(&mint, rpc.commitment())?
+        .value
+        .ok_or_else(|| {
+            anyhow!(
+                "Mint {} does not exist: check the mint address and the RPC cluster.",
+                mint
+            )
+        })?
+        .owner;
+
+    if token_program != spl_token::ID && token_program != TOKEN_2022_PROGRAM_ID {
+        return Err(anyhow!("Account {} is not a token mint.", mint));
+    }
+
+    let ata = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+
+    println!(
+        "\n{} {}Creating associated token account",
+        style("[2/2]").bold().dim(),
+        PAPER_EMOJI
+    );
+
+    if rpc
+        .get_account_with_commitment(&ata, rpc.commitment())?
+        .value
+        .is_some()
+    {
+        println!(
+            "{}",
+            style(format!(
+                "The associated token account {ata} already exists."
+            ))
+            .dim()
+        );
+    } else {
+        let pb = spinner_with_style();
+        pb.set_message("Sending create account transaction...");
+
+        let signature = program
+            .request()
+            .instruction(create_associated_token_account(
+                &payer,
+                &owner,
+                &mint,
+                &token_program,
+            ))
+            .send_or_plan("create token account")?;
+
+        pb.finish_and_clear();
+        println!("{} {}", style("Signature:").bold(), signature);
+    }
+
+    println!("{} {}", style("Owner:").bold(), owner);
+    println!("{} {}", style("Associated token account:").bold(), ata);
+    println!(
+        "\nUse it as the \"destinationAta\" of the tokenPayment guard to receive the payments."
+    );
+
+    Ok(())
+}
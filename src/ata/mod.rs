@@ -0,0 +1,5 @@
+pub mod check;
+pub mod create;
+
+pub use check::*;
+pub use create::*;
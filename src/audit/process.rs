@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use console::style;
+use mpl_candy_machine_core::CandyMachineData;
+use mpl_token_metadata::state::{Creator, Metadata};
+use tabled::{builder::Builder, settings::Style};
+
+use crate::{
+    cache::load_cache, candy_machine::get_candy_machine_state, common::*,
+    pdas::find_candy_machine_creator_pda, rate_limit::throttled_client_with_timeout,
+    setup::get_rpc_url, utils::*,
+};
+
+/// Timeout for the GPA call (in seconds).
+const DEFAULT_TIMEOUT: u64 = 300;
+
+pub struct AuditArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_machine: Option<String>,
+    pub timeout: Option<u64>,
+}
+
+pub fn process_audit(args: AuditArgs) -> Result<()> {
+    println!(
+        "{} {}Loading candy machine",
+        style("[1/3]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let candy_machine_id = match args.candy_machine {
+        Some(candy_machine) => candy_machine,
+        None => load_cache(&args.cache, false)?.program.candy_machine,
+    };
+
+    let candy_machine_id = Pubkey::from_str(&candy_machine_id)
+        .map_err(|_| anyhow!("Failed to parse candy machine id: {}", candy_machine_id))?;
+
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url.clone())?;
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+    let candy_machine = get_candy_machine_state(&sugar_config, &candy_machine_id)?;
+    pb.finish_with_message("Done");
+
+    println!(
+        "\n{} {}Getting minted NFTs for candy machine {}",
+        style("[2/3]").bold().dim(),
+        LOOKING_GLASS_EMOJI,
+        candy_machine_id
+    );
+
+    let pb = spinner_with_style();
+    pb.set_message("Loading...");
+
+    let client = throttled_client_with_timeout(
+        &get_rpc_url(args.rpc_url),
+        Duration::from_secs(args.timeout.unwrap_or(DEFAULT_TIMEOUT)),
+    );
+    let (creator, _) = find_candy_machine_creator_pda(&candy_machine_id);
+    let minted = get_cm_creator_metadata(&client, &creator.to_string(), 0)?;
+
+    pb.finish_with_message(format!("Found {} NFTs", minted.len()));
+
+    println!(
+        "\n{} {}Checking royalties",
+        style("[3/3]").bold().dim(),
+        PAPER_EMOJI
+    );
+
+    let mut builder = Builder::default();
+    builder.push_record(["Mint", "Name", "Issue"]);
+    let mut mismatched = 0;
+
+    for (_, metadata) in &minted {
+        let issues = audit_metadata(metadata, &candy_machine.data, &creator);
+
+        if !issues.is_empty() {
+            mismatched += 1;
+        }
+
+        for issue in issues {
+            builder.push_record([
+                metadata.mint.to_string(),
+                metadata.data.name.trim_matches(char::from(0)).to_string(),
+                issue,
+            ]);
+        }
+    }
+
+    if mismatched == 0 {
+        println!(
+            "\n{}All {} NFTs match the royalties of the candy machine.",
+            COMPLETE_EMOJI,
+            minted.len()
+        );
+        return Ok(());
+    }
+
+    let mut table = builder.build();
+    table.with(Style::psql());
+    println!("\n{}", table);
+
+    Err(anyhow!(
+        "{} of {} NFTs do not match the royalties of the candy machine",
+        mismatched,
+        minted.len()
+    ))
+}
+
+/// Return the differences between the royalties of a minted NFT and the ones of the
+/// candy machine. The candy machine creator is expected as the first creator, followed
+/// by the creators of the candy machine.
+pub fn audit_metadata(
+    metadata: &Metadata,
+    candy_machine_data: &CandyMachineData,
+    candy_machine_creator: &Pubkey,
+) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    if metadata.data.seller_fee_basis_points != candy_machine_data.seller_fee_basis_points {
+        issues.push(format!(
+            "seller fee basis points is {} (expected {})",
+            metadata.data.seller_fee_basis_points, candy_machine_data.seller_fee_basis_points
+        ));
+    }
+
+    let creators: Vec<&Creator> = metadata
+        .data
+        .creators
+        .iter()
+        .flatten()
+        .filter(|creator| creator.address != *candy_machine_creator)
+        .collect();
+
+    if creators.len() != candy_machine_data.creators.len() {
+        issues.push(format!(
+            "{} creators (expected {})",
+            creators.len(),
+            candy_machine_data.creators.len()
+        ));
+        return issues;
+    }
+
+    for (creator, expected) in creators.iter().zip(&candy_machine_data.creators) {
+        if creator.address != expected.address {
+            issues.push(format!(
+                "creator {} (expected {})",
+                creator.address, expected.address
+            ));
+        } else if creator.share != expected.percentage_share {
+            issues.push(format!(
+                "creator {} share is {}% (expected {}%)",
+                creator.address, creator.share, expected.percentage_share
+            ));
+        }
+    }
+
+    issues
+}
+
+#[test]
+fn test_audit_metadata() {
+    use mpl_token_metadata::state::{Data, Key};
+
+    let candy_machine_creator = Pubkey::new_unique();
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+
+    let candy_machine_data = CandyMachineData {
+        items_available: 10,
+        symbol: String::new(),
+        seller_fee_basis_points: 500,
+        max_supply: 0,
+        is_mutable: true,
+        creators: vec![
+            mpl_candy_machine_core::Creator {
+                address: first,
+                verified: false,
+                percentage_share: 60,
+            },
+            mpl_candy_machine_core::Creator {
+                address: second,
+                verified: false,
+                percentage_share: 40,
+            },
+        ],
+        config_line_settings: None,
+        hidden_settings: None,
+    };
+
+    let creator = |address: Pubkey, share: u8| Creator {
+        address,
+        verified: false,
+        share,
+    };
+
+    let metadata = |seller_fee_basis_points: u16, creators: Vec<Creator>| Metadata {
+        key: Key::MetadataV1,
+        update_authority: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        data: Data {
+            name: "Item #1".to_string(),
+            symbol: String::new(),
+            uri: String::new(),
+            seller_fee_basis_points,
+            creators: Some(creators),
+        },
+        primary_sale_happened: false,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: None,
+        collection: None,
+        uses: None,
+        collection_details: None,
+        programmable_config: None,
+    };
+
+    // the candy machine creator is not part of the candy machine creators
+    let matching = metadata(
+        500,
+        vec![
+            creator(candy_machine_creator, 0),
+            creator(first, 60),
+            creator(second, 40),
+        ],
+    );
+    assert!(audit_metadata(&matching, &candy_machine_data, &candy_machine_creator).is_empty());
+
+    let updated = metadata(
+        1000,
+        vec![
+            creator(candy_machine_creator, 0),
+            creator(first, 40),
+            creator(Pubkey::new_unique(), 60),
+        ],
+    );
+    let issues = audit_metadata(&updated, &candy_machine_data, &candy_machine_creator);
+    assert_eq!(issues.len(), 3);
+    assert!(issues[0].starts_with("seller fee basis points is 1000"));
+    assert!(issues[1].ends_with("share is 40% (expected 60%)"));
+    assert!(issues[2].ends_with(&format!("(expected {})", second)));
+
+    let removed = metadata(500, vec![creator(first, 100)]);
+    assert_eq!(
+        audit_metadata(&removed, &candy_machine_data, &candy_machine_creator),
+        vec!["1 creators (expected 2)".to_string()]
+    );
+}
@@ -0,0 +1,295 @@
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, Utc};
+use console::style;
+use mpl_candy_guard::state::{CandyGuardData, DATA_OFFSET};
+use tokio::time::sleep;
+
+use crate::{
+    cache::load_cache,
+    candy_machine::get_candy_machine_state,
+    common::*,
+    freeze::{process_thaw, process_unlock_funds, ThawArgs, UnlockFundsArgs},
+    guard::{process_guard_unwrap, GuardUnwrapArgs},
+    mint::merge_guards,
+    utils::*,
+    withdraw::{process_withdraw, WithdrawArgs},
+};
+
+/// Default interval between checks of the candy machine (in seconds).
+const DEFAULT_INTERVAL: u64 = 60;
+
+pub struct AutotaskArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub config: String,
+    pub candy_machine: Option<String>,
+    pub candy_guard: Option<String>,
+    pub label: Option<String>,
+    pub unwrap: bool,
+    pub thaw: bool,
+    pub withdraw: bool,
+    pub webhook: Option<String>,
+    pub interval: Option<u64>,
+}
+
+/// Reason why the mint is over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MintEnd {
+    SoldOut,
+    EndDate(i64),
+}
+
+impl MintEnd {
+    fn describe(&self) -> String {
+        match self {
+            MintEnd::SoldOut => "sold out".to_string(),
+            MintEnd::EndDate(date) => format!("end date reached ({})", format_timestamp(*date)),
+        }
+    }
+}
+
+pub async fn process_autotask(args: AutotaskArgs) -> Result<()> {
+    if !(args.unwrap || args.thaw || args.withdraw || args.webhook.is_some()) {
+        return Err(anyhow!(
+            "No actions configured, use --unwrap, --thaw, --withdraw or --webhook"
+        ));
+    }
+
+    let cache = if args.candy_machine.is_none() || args.candy_guard.is_none() {
+        Some(load_cache(&args.cache, false)?)
+    } else {
+        None
+    };
+
+    let candy_machine = args
+        .candy_machine
+        .clone()
+        .or_else(|| cache.as_ref().map(|c| c.program.candy_machine.clone()))
+        .unwrap_or_default();
+    let candy_machine_id = Pubkey::from_str(&candy_machine)
+        .map_err(|_| anyhow!("Failed to parse candy machine id: {}", candy_machine))?;
+
+    let candy_guard = args
+        .candy_guard
+        .clone()
+        .or_else(|| cache.as_ref().map(|c| c.program.candy_guard.clone()))
+        .filter(|candy_guard| !candy_guard.is_empty());
+    let candy_guard_id = candy_guard
+        .as_ref()
+        .map(|candy_guard| {
+            Pubkey::from_str(candy_guard)
+                .map_err(|_| anyhow!("Failed to parse candy guard id: {}", candy_guard))
+        })
+        .transpose()?;
+
+    if candy_guard_id.is_none() && (args.unwrap || args.thaw) {
+        return Err(anyhow!(
+            "A candy guard is required to unwrap or thaw, use --candy-guard"
+        ));
+    }
+
+    let interval = Duration::from_secs(args.interval.unwrap_or(DEFAULT_INTERVAL));
+    let sugar_config = sugar_setup(args.keypair.clone(), args.rpc_url.clone())?;
+
+    println!(
+        "{} {}Watching candy machine {}",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI,
+        candy_machine_id
+    );
+
+    let pb = spinner_with_style();
+    pb.enable_steady_tick(120);
+
+    let mint_end = loop {
+        let state = get_candy_machine_state(&sugar_config, &candy_machine_id)?;
+
+        if state.items_redeemed >= state.data.items_available {
+            break MintEnd::SoldOut;
+        }
+
+        if let Some(candy_guard_id) = &candy_guard_id {
+            let client = setup_client(&sugar_config)?;
+            let program = client.program(mpl_candy_guard::ID);
+            let account_data = program.rpc().get_account_data(candy_guard_id)?;
+            let candy_guard_data = CandyGuardData::load(&account_data[DATA_OFFSET..])?;
+
+            if let Some(end_date) = get_end_date(&candy_guard_data) {
+                if Utc::now().timestamp() >= end_date {
+                    break MintEnd::EndDate(end_date);
+                }
+            }
+        }
+
+        pb.set_message(format!(
+            "Minted {}/{} items, next check in {}s",
+            state.items_redeemed,
+            state.data.items_available,
+            interval.as_secs()
+        ));
+
+        sleep(interval).await;
+    };
+
+    pb.finish_and_clear();
+
+    println!(
+        "\n{} {}Mint is over: {}",
+        style("[2/2]").bold().dim(),
+        COMPLETE_EMOJI,
+        mint_end.describe()
+    );
+
+    let mut completed = Vec::new();
+    let result = run_actions(&args, &candy_machine, &candy_guard, &mut completed).await;
+
+    if let Some(webhook) = &args.webhook {
+        let mut message = format!(
+            "Candy machine {} {}.",
+            candy_machine_id,
+            mint_end.describe()
+        );
+
+        if !completed.is_empty() {
+            message.push_str(&format!(" Completed: {}.", completed.join(", ")));
+        }
+
+        if let Err(error) = &result {
+            message.push_str(&format!(" Failed: {}", error));
+        }
+
+        if let Err(error) = notify(webhook, &message).await {
+            println!("{}Failed to send notification: {}", WARNING_EMOJI, error);
+        }
+    }
+
+    result?;
+
+    println!("\n{}Autotask completed.", COMPLETE_EMOJI);
+
+    Ok(())
+}
+
+/// Return the date after which no group can mint, if every group has an end date.
+pub fn get_end_date(candy_guard_data: &CandyGuardData) -> Option<i64> {
+    match &candy_guard_data.groups {
+        Some(groups) if !groups.is_empty() => groups
+            .iter()
+            .map(|group| {
+                merge_guards(candy_guard_data.default.clone(), group.guards.clone())
+                    .end_date
+                    .map(|end_date| end_date.date)
+            })
+            .collect::<Option<Vec<i64>>>()
+            .and_then(|dates| dates.into_iter().max()),
+        _ => candy_guard_data
+            .default
+            .end_date
+            .as_ref()
+            .map(|end_date| end_date.date),
+    }
+}
+
+async fn run_actions(
+    args: &AutotaskArgs,
+    candy_machine: &str,
+    candy_guard: &Option<String>,
+    completed: &mut Vec<&'static str>,
+) -> Result<()> {
+    if args.unwrap {
+        println!("\n{} sugar guard unwrap\n", style(">>>").magenta());
+
+        process_guard_unwrap(GuardUnwrapArgs {
+            keypair: args.keypair.clone(),
+            rpc_url: args.rpc_url.clone(),
+            cache: args.cache.clone(),
+            candy_machine: Some(candy_machine.to_string()),
+            candy_guard: candy_guard.clone(),
+        })?;
+
+        completed.push("guard unwrap");
+    }
+
+    if args.thaw {
+        println!("\n{} sugar freeze thaw --all\n", style(">>>").magenta());
+
+        process_thaw(ThawArgs {
+            keypair: args.keypair.clone(),
+            rpc_url: args.rpc_url.clone(),
+            cache: args.cache.clone(),
+            config: args.config.clone(),
+            all: true,
+            nft_mint: None,
+            candy_guard: candy_guard.clone(),
+            candy_machine: Some(candy_machine.to_string()),
+            destination: None,
+            label: args.label.clone(),
+            use_cache: false,
+            timeout: None,
+            token: false,
+        })
+        .await?;
+
+        completed.push("thaw");
+
+        println!("\n{} sugar freeze unlock-funds\n", style(">>>").magenta());
+
+        process_unlock_funds(UnlockFundsArgs {
+            keypair: args.keypair.clone(),
+            rpc_url: args.rpc_url.clone(),
+            cache: args.cache.clone(),
+            config: args.config.clone(),
+            candy_guard: candy_guard.clone(),
+            candy_machine: Some(candy_machine.to_string()),
+            destination: None,
+            label: args.label.clone(),
+            token: false,
+        })?;
+
+        completed.push("unlock funds");
+    }
+
+    if args.withdraw {
+        println!("\n{} sugar withdraw\n", style(">>>").magenta());
+
+        process_withdraw(WithdrawArgs {
+            candy_machine: Some(candy_machine.to_string()),
+            keypair: args.keypair.clone(),
+            rpc_url: args.rpc_url.clone(),
+            list: false,
+            authority: None,
+            destination: None,
+            min_balance: None,
+            created_after: None,
+            created_before: None,
+            ids_file: None,
+        })?;
+
+        completed.push("withdraw");
+    }
+
+    Ok(())
+}
+
+/// Post the message to the webhook, using the fields expected by Slack and Discord.
+async fn notify(webhook: &str, message: &str) -> Result<()> {
+    let response = HttpClient::new()
+        .post(webhook)
+        .json(&json!({ "text": message, "content": message }))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Webhook returned status {}", response.status()));
+    }
+
+    Ok(())
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    NaiveDateTime::from_timestamp_opt(timestamp, 0)
+        .map(|date| date.format("%a, %B %e %Y %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
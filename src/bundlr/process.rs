@@ -4,8 +4,13 @@ use console::style;
 use data_encoding::BASE64URL;
 
 use crate::{
-    candy_machine::CANDY_MACHINE_ID, cli::BundlrAction, common::*, config::*,
-    upload::methods::BundlrMethod, utils::*,
+    cache::load_cache,
+    cli::BundlrAction,
+    common::*,
+    config::*,
+    dry_run::is_dry_run,
+    upload::{get_asset_pairs, get_upload_indices, methods::BundlrMethod, DataType},
+    utils::*,
 };
 
 // The minimum amount required for withdraw.
@@ -19,16 +24,15 @@ pub struct BundlrArgs {
 
 pub async fn process_bundlr(args: BundlrArgs) -> Result<()> {
     let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
-    let client = setup_client(&sugar_config)?;
 
     // retrieving balance
 
     println!(
         "{} {}Retrieving balance",
-        style(if let BundlrAction::Withdraw = args.action {
-            "[1/2]"
-        } else {
+        style(if let BundlrAction::Balance = args.action {
             "[1/1]"
+        } else {
+            "[1/2]"
         })
         .bold()
         .dim(),
@@ -38,25 +42,17 @@ pub async fn process_bundlr(args: BundlrArgs) -> Result<()> {
     let pb = spinner_with_style();
     pb.set_message("Connecting...");
 
-    let program = client.program(CANDY_MACHINE_ID);
-    let solana_cluster: Cluster = get_cluster(program.rpc())?;
-
     let http_client = reqwest::Client::new();
+    let bundlr_node = BundlrMethod::get_bundlr_node(&sugar_config)?;
     let keypair = sugar_config.keypair;
     let address = keypair.pubkey().to_string();
-    let bundlr_node = match solana_cluster {
-        Cluster::Devnet => BUNDLR_DEVNET,
-        Cluster::Mainnet => BUNDLR_MAINNET,
-        Cluster::Unknown | Cluster::Localnet => {
-            return Err(anyhow!("Bundlr is only supported on devnet or mainnet"));
-        }
-    };
 
     let balance = BundlrMethod::get_bundlr_balance(&http_client, &address, bundlr_node).await?;
 
     pb.finish_and_clear();
 
     println!("\nFunding address:");
+    println!("  -> node: {}", bundlr_node);
     println!("  -> pubkey: {}", address);
     println!(
         "  -> lamports: {} (◎ {})",
@@ -64,6 +60,83 @@ pub async fn process_bundlr(args: BundlrArgs) -> Result<()> {
         balance as f64 / LAMPORTS_PER_SOL as f64
     );
 
+    // estimating the upload cost
+
+    if let BundlrAction::Cost {
+        assets_dir,
+        config,
+        cache,
+    } = &args.action
+    {
+        println!(
+            "\n{} {}Estimating upload cost",
+            style("[2/2]").bold().dim(),
+            PAYMENT_EMOJI
+        );
+
+        let config_data = get_config_data(config)?;
+        let asset_pairs = get_asset_pairs(assets_dir, config_data.collection_path.as_deref())?;
+
+        // the cache is only used to find the pending assets and it is not saved
+        let mut cache = load_cache(cache, true)?;
+        if asset_pairs.get(&COLLECTION_INDEX).is_none() {
+            cache.items.remove(COLLECTION_CACHE_KEY);
+        }
+        let indices = get_upload_indices(&asset_pairs, &mut cache)?;
+
+        let total_size = BundlrMethod::get_upload_size(
+            &config_data,
+            &asset_pairs,
+            vec![
+                (DataType::Image, &indices.image),
+                (DataType::Animation, &indices.animation),
+                (DataType::Metadata, &indices.metadata),
+            ],
+        )?;
+
+        let fee = if total_size > 0 {
+            let pb = spinner_with_style();
+            pb.set_message("Connecting...");
+            let fee = BundlrMethod::get_bundlr_fee(&http_client, bundlr_node, total_size).await?;
+            pb.finish_and_clear();
+            fee
+        } else {
+            0
+        };
+
+        println!("\nPending upload:");
+        println!("  -> images: {}", indices.image.len());
+        if !indices.animation.is_empty() {
+            println!("  -> animation: {}", indices.animation.len());
+        }
+        println!("  -> metadata: {}", indices.metadata.len());
+        println!("  -> size: {} bytes", total_size);
+        println!(
+            "  -> estimated cost: {} lamports (◎ {})",
+            fee,
+            fee as f64 / LAMPORTS_PER_SOL as f64
+        );
+
+        if fee > balance {
+            let shortfall = fee - balance;
+            println!(
+                "  -> shortfall: {} lamports (◎ {})",
+                shortfall,
+                shortfall as f64 / LAMPORTS_PER_SOL as f64
+            );
+        }
+
+        if config_data.upload_method != UploadMethod::Bundlr {
+            println!(
+                "\n{}{}",
+                WARNING_EMOJI,
+                style("The upload method in the config file is not Bundlr.")
+                    .yellow()
+                    .bold()
+            );
+        }
+    }
+
     // withdrawing funds
 
     if let BundlrAction::Withdraw = args.action {
@@ -75,6 +148,12 @@ pub async fn process_bundlr(args: BundlrArgs) -> Result<()> {
 
         if balance == 0 {
             println!("\nNo funds to withdraw.");
+        } else if is_dry_run() {
+            println!(
+                "\n{} lamports (◎ {}) would be withdrawn.",
+                balance.saturating_sub(LIMIT),
+                balance.saturating_sub(LIMIT) as f64 / LAMPORTS_PER_SOL as f64
+            );
         } else if (balance - LIMIT) > 0 {
             let pb = spinner_with_style();
             pb.set_message("Connecting...");
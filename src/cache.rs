@@ -1,45 +1,134 @@
 use std::{
+    fs,
+    io::Write,
     ops::{Deref, DerefMut},
     path::Path,
 };
 
 use anchor_client::solana_sdk::pubkey::Pubkey;
 use anyhow::Result;
+use chrono::{SecondsFormat, Utc};
 use mpl_candy_machine_core::ConfigLine;
 use serde::{Deserialize, Serialize};
 
-use crate::{common::*, pdas::find_candy_machine_creator_pda};
+use crate::{
+    cache_encryption::{CacheKey, EncryptedCache},
+    common::*,
+    dry_run::is_dry_run,
+    lock::lock_file,
+    pdas::find_candy_machine_creator_pda,
+};
+
+/// Current version of the cache file layout.
+pub const CACHE_VERSION: u32 = 2;
+/// Number of previous versions of the cache file kept as backups.
+pub const CACHE_BACKUPS: usize = 3;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Cache {
+    /// Version of the cache file layout (missing on caches created before versioning).
+    #[serde(default)]
+    pub version: u32,
     pub program: CacheProgram,
     pub items: CacheItems,
     #[serde(skip_deserializing, skip_serializing)]
     pub file_path: String,
+    /// Key of the cache file when it is encrypted at rest.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub encryption: Option<CacheKey>,
 }
 
 impl Cache {
     pub fn new() -> Self {
         Cache {
+            version: CACHE_VERSION,
             program: CacheProgram::new(),
             items: CacheItems::new(),
             file_path: String::new(),
+            encryption: None,
         }
     }
 
+    /// Writes the cache to a temporary file which then replaces the cache file, so an
+    /// interrupted write never corrupts it. The previous versions of the file are kept
+    /// as `<file>.bak-1` (most recent) to `<file>.bak-N`.
     pub fn write_to_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        let f = File::create(path)?;
-        serde_json::to_writer_pretty(f, &self)?;
+        let path = path.as_ref();
+        let tmp_path = with_suffix(path, ".tmp");
+
+        let mut f = File::create(&tmp_path)?;
+
+        if let Some(key) = &self.encryption {
+            let content = serde_json::to_vec(&self)?;
+            serde_json::to_writer_pretty(&mut f, &key.encrypt(&content)?)?;
+        } else {
+            serde_json::to_writer_pretty(&mut f, &self)?;
+        }
+
+        f.flush()?;
+        f.sync_all()?;
+
+        if path.exists() {
+            rotate_backups(path)?;
+        }
+
+        fs::rename(&tmp_path, path)?;
 
         Ok(())
     }
 
     pub fn sync_file(&mut self) -> Result<()> {
+        // a dry run leaves the cache file as it was
+        if is_dry_run() {
+            return Ok(());
+        }
+
         let file_path = self.file_path.clone();
+        lock_file(&file_path)?;
         self.write_to_file(Path::new(&file_path))
     }
 }
 
+/// Path with a suffix appended to the file name (e.g. `cache.json.tmp`).
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+/// Shifts the backups of the cache file and copies the current file as the most
+/// recent one; the file itself is left in place until it is replaced.
+fn rotate_backups(path: &Path) -> Result<()> {
+    if CACHE_BACKUPS == 0 {
+        return Ok(());
+    }
+
+    for index in (1..CACHE_BACKUPS).rev() {
+        let backup = with_suffix(path, &format!(".bak-{index}"));
+
+        if backup.exists() {
+            fs::rename(&backup, with_suffix(path, &format!(".bak-{}", index + 1)))?;
+        }
+    }
+
+    fs::copy(path, with_suffix(path, ".bak-1"))?;
+
+    Ok(())
+}
+
+/// Removes the backups of the cache file.
+pub fn remove_backups(path: &Path) -> Result<()> {
+    for index in 1..=CACHE_BACKUPS {
+        let backup = with_suffix(path, &format!(".bak-{index}"));
+
+        if backup.exists() {
+            fs::remove_file(backup)?;
+        }
+    }
+
+    Ok(())
+}
+
 impl Default for Cache {
     fn default() -> Self {
         Self::new()
@@ -56,6 +145,28 @@ pub struct CacheProgram {
     pub candy_machine_creator: String,
     #[serde(rename = "collectionMint")]
     pub collection_mint: String,
+    /// Seed of the order of the config lines (deploy --shuffle).
+    #[serde(
+        rename = "shuffleSeed",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub shuffle_seed: Option<String>,
+    /// Commitment of the shuffle seed, made before the blockhash mixed into it is known.
+    #[serde(
+        rename = "shuffleCommitment",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub shuffle_commitment: Option<ShuffleCommitment>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ShuffleCommitment {
+    /// SHA-256 hash (hex) of the seed.
+    pub hash: String,
+    /// Slot at which the seed was committed.
+    pub slot: u64,
 }
 
 impl CacheProgram {
@@ -65,6 +176,8 @@ impl CacheProgram {
             candy_guard: String::new(),
             candy_machine_creator: String::new(),
             collection_mint: String::new(),
+            shuffle_seed: None,
+            shuffle_commitment: None,
         }
     }
 
@@ -76,6 +189,8 @@ impl CacheProgram {
             candy_guard: String::new(),
             candy_machine_creator: candy_machine_creator_pda.to_string(),
             collection_mint: String::new(),
+            shuffle_seed: None,
+            shuffle_commitment: None,
         }
     }
 }
@@ -128,9 +243,72 @@ pub struct CacheItem {
     pub animation_hash: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub animation_link: Option<String>,
+    /// Last stage completed by the item.
+    #[serde(default)]
+    pub status: ItemStatus,
+    #[serde(default, skip_serializing_if = "ItemTimestamps::is_empty")]
+    pub timestamps: ItemTimestamps,
+}
+
+/// Processing stage of a cache item.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum ItemStatus {
+    #[default]
+    Pending,
+    Uploaded,
+    Deployed,
+    Verified,
+    Revealed,
+}
+
+/// Time (RFC 3339) when each stage was completed.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ItemTimestamps {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uploaded: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revealed: Option<String>,
+}
+
+impl ItemTimestamps {
+    pub fn is_empty(&self) -> bool {
+        self.uploaded.is_none()
+            && self.deployed.is_none()
+            && self.verified.is_none()
+            && self.revealed.is_none()
+    }
 }
 
 impl CacheItem {
+    /// Sets the stage of the item, recording when it was completed. Moving the item back
+    /// to pending (e.g. the asset changed) clears the timestamps.
+    pub fn set_status(&mut self, status: ItemStatus) {
+        let now = Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+
+        match status {
+            ItemStatus::Pending => self.timestamps = ItemTimestamps::default(),
+            ItemStatus::Uploaded => self.timestamps.uploaded = now,
+            ItemStatus::Deployed => self.timestamps.deployed = now,
+            ItemStatus::Verified => self.timestamps.verified = now,
+            ItemStatus::Revealed => self.timestamps.revealed = now,
+        }
+
+        self.status = status;
+    }
+
+    /// Items of hidden settings drops revealed by previous versions only have the
+    /// `on_chain` flag set.
+    pub fn is_revealed(&self) -> bool {
+        self.status == ItemStatus::Revealed || self.on_chain
+    }
+
     pub fn to_config_line(&self) -> Option<ConfigLine> {
         if !self.on_chain {
             Some(ConfigLine {
@@ -170,7 +348,31 @@ pub fn load_cache(cache_file_path: &str, create: bool) -> Result<Cache> {
             }
         };
 
-        let mut cache: Cache = match serde_json::from_reader(file) {
+        let mut value: Value = match serde_json::from_reader(file) {
+            Ok(value) => value,
+            Err(err) => {
+                let error = CacheError::CacheFileWrongFormat(err.to_string()).into();
+                error!("{:?}", error);
+                return Err(error);
+            }
+        };
+
+        // encrypted caches are decrypted transparently, keeping the key to encrypt
+        // the updates
+        let encryption = if value.get("ciphertext").is_some() {
+            let encrypted: EncryptedCache = serde_json::from_value(value)
+                .map_err(|err| CacheError::CacheFileWrongFormat(err.to_string()))?;
+            let key = CacheKey::for_file(&encrypted)?;
+            value = serde_json::from_slice(&key.decrypt(&encrypted)?)
+                .map_err(|err| CacheError::CacheFileWrongFormat(err.to_string()))?;
+            Some(key)
+        } else {
+            None
+        };
+
+        let version = migrate_cache(&mut value)?;
+
+        let mut cache: Cache = match serde_json::from_value(value) {
             Ok(cache) => cache,
             Err(err) => {
                 let error = CacheError::CacheFileWrongFormat(err.to_string()).into();
@@ -179,7 +381,153 @@ pub fn load_cache(cache_file_path: &str, create: bool) -> Result<Cache> {
             }
         };
         cache.file_path = path_to_string(cache_file_path)?;
+        cache.encryption = encryption;
+
+        if version < CACHE_VERSION {
+            info!(
+                "Cache migrated from version {} to version {}",
+                version, CACHE_VERSION
+            );
+        }
 
         Ok(cache)
     }
 }
+
+/// Upgrades the layout of a cache file to the current version, returning the version
+/// of the file. Caches created by a newer version of sugar are rejected.
+pub fn migrate_cache(cache: &mut Value) -> Result<u32> {
+    let version = cache["version"].as_u64().unwrap_or_default() as u32;
+
+    if version > CACHE_VERSION {
+        let error = CacheError::UnsupportedVersion(version, CACHE_VERSION).into();
+        error!("{:?}", error);
+        return Err(error);
+    }
+
+    if version < 1 {
+        migrate_v0(cache)?;
+    }
+
+    if version < 2 {
+        migrate_v1(cache);
+    }
+
+    cache["version"] = json!(CACHE_VERSION);
+
+    Ok(version)
+}
+
+/// Unversioned caches: candy machine v2 caches do not have the candy guard and
+/// older releases did not write the hashes, collection mint and animation fields.
+fn migrate_v0(cache: &mut Value) -> Result<()> {
+    let root = cache
+        .as_object_mut()
+        .ok_or_else(|| CacheError::CacheFileWrongFormat("expected a JSON object".to_string()))?;
+
+    let program = root.entry("program").or_insert_with(|| json!({}));
+
+    if let Some(program) = program.as_object_mut() {
+        for field in [
+            "candyMachine",
+            "candyGuard",
+            "candyMachineCreator",
+            "collectionMint",
+        ] {
+            program.entry(field).or_insert_with(|| json!(""));
+        }
+    }
+
+    let items = root.entry("items").or_insert_with(|| json!({}));
+
+    if let Some(items) = items.as_object_mut() {
+        for item in items.values_mut().filter_map(Value::as_object_mut) {
+            for field in [
+                "name",
+                "image_hash",
+                "image_link",
+                "metadata_hash",
+                "metadata_link",
+            ] {
+                if item.get(field).map_or(true, Value::is_null) {
+                    item.insert(field.to_string(), json!(""));
+                }
+            }
+
+            if item.get("onChain").map_or(true, Value::is_null) {
+                item.insert("onChain".to_string(), json!(false));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Version 1 caches only track whether the item is on-chain: the status is derived
+/// from the flag and the links.
+fn migrate_v1(cache: &mut Value) {
+    if let Some(items) = cache["items"].as_object_mut() {
+        for item in items.values_mut().filter_map(Value::as_object_mut) {
+            let uploaded = ["image_link", "metadata_link"]
+                .iter()
+                .all(|field| item[*field].as_str().map_or(false, |link| !link.is_empty()));
+
+            let status = if item["onChain"].as_bool().unwrap_or_default() {
+                ItemStatus::Deployed
+            } else if uploaded {
+                ItemStatus::Uploaded
+            } else {
+                ItemStatus::Pending
+            };
+
+            item.insert("status".to_string(), json!(status));
+        }
+    }
+}
+
+#[test]
+fn test_cache_migration() {
+    let mut legacy = json!({
+        "program": {
+            "candyMachine": "",
+            "candyMachineCreator": ""
+        },
+        "items": {
+            "0": {
+                "name": "Item 0",
+                "image_link": "https://example.com/0.png",
+                "metadata_link": "https://example.com/0.json"
+            }
+        }
+    });
+
+    assert_eq!(migrate_cache(&mut legacy).unwrap(), 0);
+
+    let cache: Cache = serde_json::from_value(legacy).unwrap();
+    assert_eq!(cache.version, CACHE_VERSION);
+    assert!(!cache.items["0"].on_chain);
+    assert!(cache.items["0"].animation_link.is_none());
+    assert_eq!(cache.items["0"].status, ItemStatus::Uploaded);
+
+    let mut future = json!({ "version": CACHE_VERSION + 1 });
+    assert!(migrate_cache(&mut future).is_err());
+}
+
+#[test]
+fn test_cache_backup_rotation() {
+    let dir = std::env::temp_dir().join(format!("sugar-cache-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("cache.json");
+    let mut cache = Cache::new();
+
+    for _ in 0..(CACHE_BACKUPS + 2) {
+        cache.write_to_file(&path).unwrap();
+    }
+
+    assert!(path.exists());
+    assert!(!with_suffix(&path, ".tmp").exists());
+    assert!(with_suffix(&path, &format!(".bak-{CACHE_BACKUPS}")).exists());
+    assert!(!with_suffix(&path, &format!(".bak-{}", CACHE_BACKUPS + 1)).exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
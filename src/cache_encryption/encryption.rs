@@ -0,0 +1,258 @@
+use std::{env, fmt, fs, sync::Mutex};
+
+use anyhow::{anyhow, Result};
+use data_encoding::HEXLOWER;
+use dialoguer::Password;
+use lazy_static::lazy_static;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    digest::{digest, SHA256},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    keypair::{derive_key_bytes, PBKDF2_ITERATIONS, SALT_LEN},
+    utils::{ensure_interactive, get_dialoguer_theme},
+};
+
+/// Environment variable with the passphrase of encrypted cache files.
+pub const CACHE_PASSPHRASE_ENV: &str = "SUGAR_CACHE_PASSPHRASE";
+
+/// Environment variable with the path of the key file of encrypted cache files.
+pub const CACHE_KEYFILE_ENV: &str = "SUGAR_CACHE_KEYFILE";
+
+/// Version of the encrypted cache file format.
+const ENCRYPTED_CACHE_VERSION: u8 = 1;
+
+lazy_static! {
+    /// Passphrase entered at the prompt, reused when the cache file is loaded again by the
+    /// same process (e.g. by each step of `launch`).
+    static ref PROMPTED_PASSPHRASE: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// Source of the encryption key of a cache file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheKdf {
+    /// Key derived from a passphrase (PBKDF2-HMAC-SHA256).
+    Passphrase,
+    /// Key derived from the content of a key file (SHA-256).
+    Keyfile,
+}
+
+/// Cache file encrypted at rest (AES-256-GCM).
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EncryptedCache {
+    pub version: u8,
+    pub kdf: CacheKdf,
+    pub iterations: u32,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Encryption key of a cache file, derived once and reused for every write.
+#[derive(Clone)]
+pub struct CacheKey {
+    kdf: CacheKdf,
+    iterations: u32,
+    salt: Vec<u8>,
+    key: [u8; 32],
+}
+
+impl fmt::Debug for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheKey").field("kdf", &self.kdf).finish()
+    }
+}
+
+impl CacheKey {
+    /// Creates a new key from a key file, or from a passphrase read from the environment
+    /// or prompted for.
+    pub fn new(keyfile: Option<&str>) -> Result<Self> {
+        let keyfile = keyfile
+            .map(String::from)
+            .or_else(|| env::var(CACHE_KEYFILE_ENV).ok());
+
+        if let Some(keyfile) = keyfile {
+            return Ok(CacheKey {
+                kdf: CacheKdf::Keyfile,
+                iterations: 0,
+                salt: Vec::new(),
+                key: read_keyfile(&keyfile)?,
+            });
+        }
+
+        let passphrase = read_cache_passphrase("New cache passphrase", true)?;
+
+        if passphrase.is_empty() {
+            return Err(anyhow!("The passphrase cannot be empty."));
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| anyhow!("Failed to generate salt"))?;
+
+        Ok(CacheKey {
+            kdf: CacheKdf::Passphrase,
+            iterations: PBKDF2_ITERATIONS,
+            salt: salt.to_vec(),
+            key: derive_key_bytes(&passphrase, &salt, PBKDF2_ITERATIONS)?,
+        })
+    }
+
+    /// Derives the key of an encrypted cache file.
+    pub fn for_file(encrypted: &EncryptedCache) -> Result<Self> {
+        let key = match encrypted.kdf {
+            CacheKdf::Keyfile => {
+                let keyfile = env::var(CACHE_KEYFILE_ENV).map_err(|_| {
+                    anyhow!(
+                        "The cache file is encrypted with a key file: set the {} environment variable",
+                        CACHE_KEYFILE_ENV
+                    )
+                })?;
+                read_keyfile(&keyfile)?
+            }
+            CacheKdf::Passphrase => {
+                let passphrase = read_cache_passphrase("Cache passphrase", false)?;
+                derive_key_bytes(&passphrase, &decode(&encrypted.salt)?, encrypted.iterations)?
+            }
+        };
+
+        Ok(CacheKey {
+            kdf: encrypted.kdf,
+            iterations: encrypted.iterations,
+            salt: decode(&encrypted.salt)?,
+            key,
+        })
+    }
+
+    fn cipher(&self) -> Result<LessSafeKey> {
+        let key = UnboundKey::new(&AES_256_GCM, &self.key)
+            .map_err(|_| anyhow!("Failed to create encryption key"))?;
+        Ok(LessSafeKey::new(key))
+    }
+
+    /// Encrypts the content of a cache file.
+    pub fn encrypt(&self, content: &[u8]) -> Result<EncryptedCache> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("Failed to generate nonce"))?;
+
+        let mut in_out = content.to_vec();
+        self.cipher()?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .map_err(|_| anyhow!("Failed to encrypt cache"))?;
+
+        Ok(EncryptedCache {
+            version: ENCRYPTED_CACHE_VERSION,
+            kdf: self.kdf,
+            iterations: self.iterations,
+            salt: HEXLOWER.encode(&self.salt),
+            nonce: HEXLOWER.encode(&nonce),
+            ciphertext: HEXLOWER.encode(&in_out),
+        })
+    }
+
+    /// Decrypts the content of an encrypted cache file.
+    pub fn decrypt(&self, encrypted: &EncryptedCache) -> Result<Vec<u8>> {
+        if encrypted.version != ENCRYPTED_CACHE_VERSION {
+            return Err(anyhow!(
+                "Unsupported encrypted cache version: {}",
+                encrypted.version
+            ));
+        }
+
+        let nonce: [u8; NONCE_LEN] = decode(&encrypted.nonce)?
+            .try_into()
+            .map_err(|_| anyhow!("Invalid encrypted cache file"))?;
+        let mut in_out = decode(&encrypted.ciphertext)?;
+
+        let content = self
+            .cipher()?
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .map_err(|_| anyhow!("Failed to decrypt cache: invalid passphrase or key file"))?;
+
+        Ok(content.to_vec())
+    }
+}
+
+fn decode(value: &str) -> Result<Vec<u8>> {
+    HEXLOWER
+        .decode(value.as_bytes())
+        .map_err(|_| anyhow!("Invalid encrypted cache file"))
+}
+
+fn read_keyfile(path: &str) -> Result<[u8; 32]> {
+    let path = shellexpand::tilde(path).to_string();
+    let content =
+        fs::read(&path).map_err(|e| anyhow!("Failed to read key file '{}': {}", path, e))?;
+
+    if content.is_empty() {
+        return Err(anyhow!("Key file '{}' is empty", path));
+    }
+
+    digest(&SHA256, &content)
+        .as_ref()
+        .try_into()
+        .map_err(|_| anyhow!("Failed to derive key from key file"))
+}
+
+/// Returns the cache passphrase from the environment or prompts the user for it, once per
+/// process.
+fn read_cache_passphrase(prompt: &str, confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = env::var(CACHE_PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+
+    let mut prompted = PROMPTED_PASSPHRASE.lock().unwrap();
+
+    if let Some(passphrase) = prompted.as_ref() {
+        return Ok(passphrase.clone());
+    }
+
+    ensure_interactive(prompt)?;
+
+    let theme = get_dialoguer_theme();
+    let mut password = Password::with_theme(&theme);
+    password.with_prompt(prompt);
+
+    if confirm {
+        password.with_confirmation("Confirm passphrase", "Passphrases do not match");
+    }
+
+    let passphrase = password.interact()?;
+    *prompted = Some(passphrase.clone());
+
+    Ok(passphrase)
+}
+
+#[test]
+fn test_encrypt_decrypt_cache() {
+    let key = CacheKey {
+        kdf: CacheKdf::Passphrase,
+        iterations: 1,
+        salt: vec![0; SALT_LEN],
+        key: derive_key_bytes("sugar", &[0; SALT_LEN], 1).unwrap(),
+    };
+
+    let encrypted = key.encrypt(b"{\"items\":{}}").unwrap();
+    assert_eq!(key.decrypt(&encrypted).unwrap(), b"{\"items\":{}}");
+
+    let other = CacheKey {
+        key: derive_key_bytes("salt", &[0; SALT_LEN], 1).unwrap(),
+        ..key
+    };
+    assert!(other.decrypt(&encrypted).is_err());
+}
@@ -0,0 +1,5 @@
+pub mod encryption;
+pub mod process;
+
+pub use encryption::*;
+pub use process::*;
@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use console::style;
+
+use crate::{
+    cache::{load_cache, remove_backups},
+    cache_encryption::CacheKey,
+    dry_run::is_dry_run,
+};
+
+pub struct CacheEncryptArgs {
+    pub cache: String,
+    pub keyfile: Option<String>,
+}
+
+pub struct CacheDecryptArgs {
+    pub cache: String,
+}
+
+pub fn process_cache_encrypt(args: CacheEncryptArgs) -> Result<()> {
+    let mut cache = load_cache(&args.cache, false)?;
+
+    if cache.encryption.is_some() {
+        return Err(anyhow!("Cache file '{}' is already encrypted.", args.cache));
+    }
+
+    cache.encryption = Some(CacheKey::new(args.keyfile.as_deref())?);
+    cache.sync_file()?;
+    // backups hold the plaintext versions of the cache
+    if !is_dry_run() {
+        remove_backups(Path::new(&args.cache))?;
+    }
+
+    println!("{} {}", style("Encrypted cache file:").bold(), args.cache);
+    println!(
+        "\n{}",
+        style("Commands decrypt it transparently, keep the passphrase or key file safe.").dim()
+    );
+
+    Ok(())
+}
+
+pub fn process_cache_decrypt(args: CacheDecryptArgs) -> Result<()> {
+    let mut cache = load_cache(&args.cache, false)?;
+
+    if cache.encryption.is_none() {
+        return Err(anyhow!("Cache file '{}' is not encrypted.", args.cache));
+    }
+
+    cache.encryption = None;
+    cache.sync_file()?;
+
+    println!("{} {}", style("Decrypted cache file:").bold(), args.cache);
+
+    Ok(())
+}
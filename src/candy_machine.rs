@@ -2,9 +2,12 @@ use anchor_client::{solana_sdk::pubkey::Pubkey, ClientError};
 use anchor_lang::AnchorDeserialize;
 use anyhow::{anyhow, Result};
 pub use mpl_candy_machine_core::ID as CANDY_MACHINE_ID;
-use mpl_candy_machine_core::{CandyMachine, CandyMachineData};
+use mpl_candy_machine_core::{constants::HIDDEN_SECTION, CandyMachine, CandyMachineData};
 
-use crate::{config::data::SugarConfig, pdas::get_metadata_pda, setup::setup_client};
+use crate::{
+    config::data::SugarConfig, constants::STRING_LEN_SIZE, pdas::get_metadata_pda,
+    setup::setup_client,
+};
 
 // To test a custom candy machine program, comment the mpl_candy_machine::ID line
 // above and use the following lines to declare the id to use:
@@ -60,3 +63,102 @@ pub fn load_candy_machine(
 
     Ok((candy_machine, rule_set))
 }
+
+/// Read the name and uri (without the prefixes) of the config line at `index` from the
+/// candy machine account data.
+pub fn read_config_line(
+    data: &[u8],
+    candy_machine_data: &CandyMachineData,
+    index: usize,
+) -> Result<(String, String)> {
+    let config_line_settings = candy_machine_data
+        .config_line_settings
+        .as_ref()
+        .ok_or_else(|| anyhow!("Missing config line settings"))?;
+
+    let name_start =
+        HIDDEN_SECTION + STRING_LEN_SIZE + candy_machine_data.get_config_line_size() * index;
+    let name_end = name_start + config_line_settings.name_length as usize;
+    let uri_end = name_end + config_line_settings.uri_length as usize;
+
+    if uri_end > data.len() {
+        return Err(anyhow!("Config line {} is out of the account data", index));
+    }
+
+    let decode = |bytes: &[u8], field: &str| -> Result<String> {
+        Ok(String::from_utf8(bytes.to_vec())
+            .map_err(|_| anyhow!("Failed to decode {} for item {}", field, index))?
+            .trim_matches(char::from(0))
+            .to_string())
+    };
+
+    Ok((
+        decode(&data[name_start..name_end], "name")?,
+        decode(&data[name_end..uri_end], "uri")?,
+    ))
+}
+
+/// Indicates whether the config line at `index` was loaded, from the bitmask that follows
+/// the config lines in the candy machine account data. Lines can be loaded with an empty
+/// name or uri (when the prefix holds the whole value), so their content is not checked.
+pub fn is_config_line_loaded(
+    data: &[u8],
+    candy_machine_data: &CandyMachineData,
+    index: usize,
+) -> Result<bool> {
+    let bitmask_start = HIDDEN_SECTION
+        + STRING_LEN_SIZE
+        + candy_machine_data.items_available as usize * candy_machine_data.get_config_line_size();
+    let position = bitmask_start + index / 8;
+
+    let byte = data.get(position).ok_or_else(|| {
+        anyhow!(
+            "Loaded bitmask of item {} is out of the account data",
+            index
+        )
+    })?;
+
+    Ok(byte & (0b1000_0000 >> (index % 8)) != 0)
+}
+
+#[test]
+fn test_is_config_line_loaded() {
+    use mpl_candy_machine_core::ConfigLineSettings;
+
+    let candy_machine_data = CandyMachineData {
+        items_available: 10,
+        symbol: String::new(),
+        seller_fee_basis_points: 0,
+        max_supply: 0,
+        is_mutable: true,
+        creators: Vec::new(),
+        config_line_settings: Some(ConfigLineSettings {
+            prefix_name: "Item #$ID+1$".to_string(),
+            name_length: 0,
+            prefix_uri: "https://arweave.net/".to_string(),
+            uri_length: 4,
+            is_sequential: false,
+        }),
+        hidden_settings: None,
+    };
+
+    let line_size = candy_machine_data.get_config_line_size();
+    let bitmask_start = HIDDEN_SECTION + STRING_LEN_SIZE + 10 * line_size;
+    let mut data = vec![0u8; bitmask_start + 2];
+
+    // item 0 is loaded with an empty name, item 9 is loaded in the second byte
+    let uri_start = HIDDEN_SECTION + STRING_LEN_SIZE;
+    data[uri_start..uri_start + 4].copy_from_slice(b"abcd");
+    data[bitmask_start] = 0b1000_0000;
+    data[bitmask_start + 1] = 0b0100_0000;
+
+    let (name, uri) = read_config_line(&data, &candy_machine_data, 0).unwrap();
+    assert_eq!((name.as_str(), uri.as_str()), ("", "abcd"));
+
+    let loaded: Vec<usize> = (0..10)
+        .filter(|index| is_config_line_loaded(&data, &candy_machine_data, *index).unwrap())
+        .collect();
+    assert_eq!(loaded, vec![0, 9]);
+
+    assert!(is_config_line_loaded(&data[..bitmask_start], &candy_machine_data, 0).is_err());
+}
@@ -1,11 +1,20 @@
 use clap::{Parser, Subcommand};
+use clap_complete::Shell;
 
 use crate::{
     config::TokenStandard,
+    confirmation::Commitment,
     constants::{
         DEFAULT_AIRDROP_LIST, DEFAULT_AIRDROP_LIST_HELP, DEFAULT_ASSETS, DEFAULT_CACHE,
-        DEFAULT_CONFIG,
+        DEFAULT_CONFIG, DEFAULT_FAILURES, DEFAULT_LAYERS, DEFAULT_MANIFEST, DEFAULT_PROOF,
+        DEFAULT_RARITY, DEFAULT_RECEIPTS, DEFAULT_SNAPSHOT, DEFAULT_TRAITS, DEFAULT_WORKSPACE,
     },
+    costs::COSTS_FILE,
+    create_config::ConfigTemplate,
+    launch::Hook,
+    rate_limit::{MethodWeight, RpcProvider},
+    self_update::ReleaseChannel,
+    token::TokenGuard,
 };
 
 #[derive(Parser)]
@@ -15,12 +24,155 @@ pub struct Cli {
     #[clap(short, long, global = true)]
     pub log_level: Option<String>,
 
+    /// Automatically accept all confirmation prompts
+    #[clap(short = 'y', long, global = true)]
+    pub yes: bool,
+
+    /// Fail instead of prompting when user input is required
+    #[clap(long, global = true)]
+    pub no_input: bool,
+
+    /// Request SOL from the faucet when the payer balance is too low (devnet only)
+    #[clap(long, global = true)]
+    pub airdrop_if_needed: bool,
+
+    /// Name of the profile (from the "profiles" section of the config file) to use
+    #[clap(long, global = true)]
+    pub profile: Option<String>,
+
+    /// RPC provider tier used to limit the request rate: public, helius, quicknode or
+    /// unlimited (detected from the RPC url if not specified)
+    #[clap(long, global = true)]
+    pub rpc_provider: Option<RpcProvider>,
+
+    /// Maximum number of RPC requests per second, overrides the provider preset
+    #[clap(long, global = true)]
+    pub rps: Option<f64>,
+
+    /// Weight of an RPC method in the request rate (e.g. "getProgramAccounts=10");
+    /// can be specified multiple times
+    #[clap(long = "rpc-weight", global = true)]
+    pub rpc_weights: Vec<MethodWeight>,
+
+    /// Warn when the programs deployed on the cluster are incompatible with this version
+    /// (also enabled by the SUGAR_CHECK_PROGRAMS environment variable)
+    #[clap(long, global = true)]
+    pub check_programs: bool,
+
+    /// Commitment level of the transactions: processed, confirmed or finalized; with
+    /// processed, deploy and mint do not wait for each transaction to be finalized
+    #[clap(long, global = true)]
+    pub commitment: Option<Commitment>,
+
+    /// Print the transactions (with their size and fees), the accounts and the uploads
+    /// of a command without sending them
+    #[clap(long, global = true)]
+    pub dry_run: bool,
+
+    /// Run with a keypair other than the expected authority pinned in the config file
+    #[clap(long, global = true)]
+    pub i_know_what_im_doing: bool,
+
+    /// Remove the locks of the cache and config files left by another sugar process
+    #[clap(long, global = true)]
+    pub force_unlock: bool,
+
+    /// Path to the cost ledger file, where the commands record their costs
+    #[clap(long, global = true, default_value = COSTS_FILE)]
+    pub costs_file: String,
+
     #[clap(subcommand)]
     pub command: Commands,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
+    /// Analyze the mint transactions of a candy machine
+    Analytics {
+        #[clap(subcommand)]
+        command: AnalyticsCommand,
+    },
+
+    /// Manage the associated token accounts of the payment guards
+    Ata {
+        #[clap(subcommand)]
+        command: AtaCommand,
+    },
+
+    /// Check that the royalties of the minted NFTs match the candy machine
+    Audit {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy machine.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// RPC timeout to retrieve the mint list (in seconds).
+        #[clap(short, long)]
+        timeout: Option<u64>,
+    },
+
+    /// Watch a candy machine and run the post-mint actions when it sells out or ends
+    Autotask {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Path to the config file
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Address of the candy machine.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Address of the candy guard.
+        #[clap(long)]
+        candy_guard: Option<String>,
+
+        /// Label of the group with the freeze guard
+        #[clap(long)]
+        label: Option<String>,
+
+        /// Unwrap the candy guard from the candy machine
+        #[clap(long)]
+        unwrap: bool,
+
+        /// Thaw all NFTs and unlock the freeze escrow funds
+        #[clap(long)]
+        thaw: bool,
+
+        /// Withdraw the rent of the candy machine
+        #[clap(long)]
+        withdraw: bool,
+
+        /// Webhook URL (Slack or Discord) to notify when the mint is over
+        #[clap(long)]
+        webhook: Option<String>,
+
+        /// Interval between checks of the candy machine (in seconds), defaults to 60
+        #[clap(long)]
+        interval: Option<u64>,
+    },
+
     /// Interact with the bundlr network
     Bundlr {
         /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
@@ -35,18 +187,52 @@ pub enum Commands {
         action: BundlrAction,
     },
 
+    /// Encrypt or decrypt the cache file (e.g. the reveal mapping of hidden drops)
+    Cache {
+        #[clap(subcommand)]
+        command: CacheCommand,
+    },
+
     /// Manage the collection on the candy machine
     Collection {
         #[clap(subcommand)]
         command: CollectionSubcommands,
     },
 
+    /// Generate the completion script of a shell (bash, zsh, fish, powershell or elvish)
+    Completions {
+        /// Shell to generate the completions for
+        shell: Shell,
+    },
+
     /// Manage candy machine configuration
     Config {
         #[clap(subcommand)]
         command: ConfigSubcommands,
     },
 
+    /// Summarize the costs of the launch recorded by each command
+    Costs {
+        /// Only show the costs of commands starting with this name (e.g. "guard")
+        #[clap(long)]
+        command: Option<String>,
+    },
+
+    /// Decode and print the fields of a candy machine, candy guard, freeze escrow or
+    /// metadata account
+    Decode {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Address of the account
+        account: String,
+    },
+
     /// Deploy cache items into candy machine config on-chain
     Deploy {
         /// Path to the config file, defaults to "config.json"
@@ -68,6 +254,44 @@ pub enum Commands {
         /// The optional collection address where the candymachine will mint the tokens to
         #[clap(long)]
         collection_mint: Option<String>,
+
+        /// Deploy all candy machines listed in the workspace manifest, using the config and
+        /// cache files of each entry
+        #[clap(long, conflicts_with_all = &["collection_mint", "config", "cache"])]
+        all: bool,
+
+        /// Path to the workspace manifest, used with --all
+        #[clap(long, default_value = DEFAULT_WORKSPACE)]
+        workspace: String,
+
+        /// Write the config lines of these indices (e.g. "0-999,1500"), even if already on-chain
+        #[clap(long, conflicts_with = "all")]
+        indices: Option<String>,
+
+        /// Pay the config line transactions with a pool of this many funded fee payers
+        #[clap(long, conflicts_with = "all")]
+        fee_payers: Option<usize>,
+
+        /// Shuffle the order of the config lines with this seed: the first run commits to it,
+        /// the next one mixes it with a later blockhash
+        #[clap(long, conflicts_with = "all")]
+        shuffle: Option<String>,
+
+        /// Append the new items of the cache to a candy machine with room for them
+        #[clap(long, conflicts_with_all = &["all", "shuffle"])]
+        append: bool,
+    },
+
+    /// Commit to the asset order of a hidden drop and verify the reveal against it
+    Fairness {
+        #[clap(subcommand)]
+        command: FairnessCommand,
+    },
+
+    /// Manage the fee payer pool used by airdrop and deploy
+    FeePayers {
+        #[clap(subcommand)]
+        command: FeePayersCommand,
     },
 
     /// Manage freeze guard actions
@@ -76,6 +300,29 @@ pub enum Commands {
         command: FreezeCommand,
     },
 
+    /// Generate assets from layers of trait images
+    Generate {
+        /// Path to the directory with the trait layers
+        #[clap(long, default_value = DEFAULT_LAYERS)]
+        layers: String,
+
+        /// Path to the traits config file, defaults to "traits.json"
+        #[clap(short, long, default_value = DEFAULT_TRAITS)]
+        traits: String,
+
+        /// Path to the directory where the assets are created
+        #[clap(short, long, default_value = DEFAULT_ASSETS)]
+        output: String,
+
+        /// Number of assets to generate, overrides the traits config
+        #[clap(short, long)]
+        number: Option<u64>,
+
+        /// Seed of the random generator, overrides the traits config
+        #[clap(long)]
+        seed: Option<u64>,
+    },
+
     /// Manage guards on the candy machine
     Guard {
         #[clap(subcommand)]
@@ -95,6 +342,73 @@ pub enum Commands {
         /// Compare a provided hash with a cache file to check integrity.
         #[clap(long)]
         compare: Option<String>,
+
+        /// Compare the hash with the on-chain hidden settings and update the candy machine if needed.
+        #[clap(long)]
+        update: bool,
+
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Address of candy machine to update [defaults to cache value].
+        #[clap(long)]
+        candy_machine: Option<String>,
+    },
+
+    /// Export the transaction history of a candy machine and its guard to a CSV file
+    History {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy machine [defaults to cache value]
+        candy_machine: Option<String>,
+
+        /// Address of the candy guard, required once the candy machine is closed [defaults to
+        /// the mint authority of the candy machine]
+        #[clap(long)]
+        candy_guard: Option<String>,
+
+        /// Path of the CSV file
+        #[clap(short, long, default_value = "history.csv")]
+        output: String,
+    },
+
+    /// Rebuild the cache file from an existing candy machine
+    Import {
+        /// Address of the candy machine to import
+        candy_machine: String,
+
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file to create, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+    },
+
+    /// Manage keypair files
+    Keypair {
+        #[clap(subcommand)]
+        command: KeypairCommand,
     },
 
     /// Create a candy machine deployment from assets
@@ -124,8 +438,77 @@ pub enum Commands {
         strict: bool,
 
         /// Skip collection validate prompt
-        #[clap(long)]
+        #[clap(long, alias = "skip-collection")]
         skip_collection_prompt: bool,
+
+        /// Skip the validate stage
+        #[clap(long)]
+        skip_validate: bool,
+
+        /// Skip the verify stage
+        #[clap(long)]
+        skip_verify: bool,
+
+        /// Address of an existing collection mint to use instead of creating one
+        #[clap(long)]
+        collection_mint: Option<String>,
+
+        /// Command to run between stages, in the format <before|after>-<stage>=<command>
+        /// (e.g. "before-upload=./generate.sh"); can be specified multiple times
+        #[clap(long = "hook")]
+        hooks: Vec<Hook>,
+
+        /// Stop the launch when a warning is found instead of prompting to continue
+        #[clap(long)]
+        stop_on_warning: bool,
+
+        /// Withdraw the leftover Bundlr balance after the launch completes
+        #[clap(long)]
+        withdraw_bundlr: bool,
+    },
+
+    /// Set up a local validator to test sugar against localhost
+    Localnet {
+        #[clap(subcommand)]
+        command: LocalnetCommand,
+    },
+
+    /// List the files to publish with their links (custom upload method)
+    Manifest {
+        #[clap(subcommand)]
+        command: ManifestCommand,
+    },
+
+    /// Generate and manage metadata files
+    Metadata {
+        #[clap(subcommand)]
+        command: MetadataCommand,
+    },
+
+    /// Migrate a candy machine v2 to a candy machine v3 with a candy guard
+    Migrate {
+        /// Address of the candy machine v2 to migrate
+        candy_machine: String,
+
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the config file to create, defaults to "config.json"
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Path to the cache file to create, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Deploy the candy machine v3 and its guards after translating the settings
+        #[clap(long)]
+        deploy: bool,
     },
 
     /// Mint one NFT from candy machine
@@ -142,6 +525,10 @@ pub enum Commands {
         #[clap(long, default_value = DEFAULT_CACHE)]
         cache: String,
 
+        /// Path to the config file with the postMint hook, defaults to "config.json"
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
         /// Amount of NFTs to be minted in bulk
         #[clap(short, long)]
         number: Option<u64>,
@@ -153,6 +540,30 @@ pub enum Commands {
         /// Address of candy machine to mint from.
         #[clap(long)]
         candy_machine: Option<String>,
+
+        /// Path to the CSV file where mint receipts are appended
+        #[clap(long, default_value = DEFAULT_RECEIPTS)]
+        receipts: String,
+
+        /// Label of the guard group to mint from
+        #[clap(long)]
+        group: Option<String>,
+
+        /// Path to the JSON file with the addresses of the allowList guard
+        #[clap(long)]
+        allowlist: Option<String>,
+
+        /// Freeze period (in seconds) to initialize the freeze escrow, if needed
+        #[clap(long)]
+        freeze_period: Option<u64>,
+
+        /// Mint address of the NFT used by the nftGate or nftPayment guards
+        #[clap(long)]
+        nft_mint: Option<String>,
+
+        /// Path to the keypair of the thirdPartySigner guard co-signer
+        #[clap(long)]
+        signer_keypair: Option<String>,
     },
     /// Airdrop NFTs from candy machine
     Airdrop {
@@ -175,10 +586,24 @@ pub enum Commands {
         /// List of airdrop targets.
         #[clap(long, default_value = DEFAULT_AIRDROP_LIST, help = DEFAULT_AIRDROP_LIST_HELP)]
         airdrop_list: String,
+
+        /// Path to the CSV file where mint receipts are appended
+        #[clap(long, default_value = DEFAULT_RECEIPTS)]
+        receipts: String,
+
+        /// Pay the mints with a pool of this many funded fee payers
+        #[clap(long)]
+        fee_payers: Option<usize>,
     },
 
-    /// Reveal the NFTs from a hidden settings candy machine
-    Reveal {
+    /// Print editions from a master edition NFT
+    Editions {
+        #[clap(subcommand)]
+        command: EditionsCommand,
+    },
+
+    /// Audit the guards of the config for common mistakes before launching
+    Preflight {
         /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
         #[clap(short, long)]
         keypair: Option<String>,
@@ -187,21 +612,40 @@ pub enum Commands {
         #[clap(short, long)]
         rpc_url: Option<String>,
 
+        /// Path to the config file, defaults to "config.json"
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
         /// Path to the cache file, defaults to "cache.json"
         #[clap(long, default_value = DEFAULT_CACHE)]
         cache: String,
+    },
 
-        /// Path to the config file
-        #[clap(short, long, default_value = DEFAULT_CONFIG)]
-        config: String,
+    /// Calculate the rarity score and rank of each item
+    Rarity {
+        /// Path to the directory with the assets
+        #[clap(default_value = DEFAULT_ASSETS)]
+        assets_dir: String,
 
-        /// RPC timeout to retrieve the mint list (in seconds).
-        #[clap(short, long)]
-        timeout: Option<u64>,
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Fetch the metadata from the links of the cache file instead of the assets
+        #[clap(long)]
+        from_cache: bool,
+
+        /// Path of the rarity report (JSON, or CSV for a ".csv" extension)
+        #[clap(short, long, default_value = DEFAULT_RARITY)]
+        output: String,
     },
 
-    /// Show the on-chain config of an existing candy machine
-    Show {
+    /// Retry the items of a failures report left by upload or deploy
+    Retry {
+        /// Path to the failures report, defaults to "failures.json"
+        #[clap(long, default_value = DEFAULT_FAILURES)]
+        from: String,
+
         /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
         #[clap(short, long)]
         keypair: Option<String>,
@@ -209,9 +653,116 @@ pub enum Commands {
         /// RPC Url
         #[clap(short, long)]
         rpc_url: Option<String>,
+    },
 
-        /// Path to the cache file, defaults to "cache.json"
-        #[clap(long, default_value = DEFAULT_CACHE)]
+    /// Reveal the NFTs from a hidden settings candy machine
+    Reveal {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Path to the config file
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// RPC timeout to retrieve the mint list (in seconds).
+        #[clap(short, long)]
+        timeout: Option<u64>,
+
+        /// Reveal only these indices (e.g. "0-999,1500"), even if already revealed
+        #[clap(long)]
+        indices: Option<String>,
+
+        /// Sign the metadata as creator in the same transaction as the reveal update
+        #[clap(long)]
+        sign: bool,
+    },
+
+    /// Change the update authority or rule set of all minted NFTs
+    Rotate {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy machine.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Address of the new update authority
+        #[clap(long)]
+        new_update_authority: Option<String>,
+
+        /// Address of the new rule set (pNFTs only)
+        #[clap(long)]
+        rule_set: Option<String>,
+
+        /// Maximum number of transactions sent per second
+        #[clap(long, default_value = "5")]
+        rate_limit: u64,
+
+        /// Path of the signed audit log, defaults to "sugar-rotate-<timestamp>.json"
+        #[clap(long)]
+        audit_log: Option<String>,
+
+        /// RPC timeout to retrieve the mint list (in seconds).
+        #[clap(short, long)]
+        timeout: Option<u64>,
+    },
+
+    /// Update sugar to the latest release of a channel
+    SelfUpdate {
+        /// Release channel: stable or beta (includes pre-releases)
+        #[clap(long, default_value = "stable")]
+        channel: ReleaseChannel,
+
+        /// Only check whether a newer release is available
+        #[clap(long)]
+        check: bool,
+
+        /// Reinstall the latest release even if it is not newer
+        #[clap(long)]
+        force: bool,
+
+        /// Public key (base58 or hex) to verify the ed25519 signature of the binary, instead
+        /// of the release key of this build
+        #[clap(long)]
+        public_key: Option<String>,
+    },
+
+    /// Plan, expand and reclaim Shadow Drive storage
+    Shdw {
+        #[clap(subcommand)]
+        command: ShdwCommand,
+    },
+
+    /// Show the on-chain config of an existing candy machine
+    Show {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
         cache: String,
 
         /// Address of candy machine
@@ -220,6 +771,31 @@ pub enum Commands {
         /// Display a list of unminted indices
         #[clap(long)]
         unminted: bool,
+
+        /// Display the guards and the status of the freeze escrows
+        #[clap(long)]
+        guards: bool,
+
+        /// Path to the config file compared with --diff
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Highlight the differences between the on-chain state and the config file,
+        /// failing (exit code 7) when they differ
+        #[clap(long)]
+        diff: bool,
+
+        /// Show a summary of all candy machines listed in the workspace manifest, using the
+        /// cache file of each entry
+        #[clap(
+            long,
+            conflicts_with_all = &["candy_machine", "unminted", "guards", "diff", "config", "cache"]
+        )]
+        all: bool,
+
+        /// Path to the workspace manifest, used with --all
+        #[clap(long, default_value = DEFAULT_WORKSPACE)]
+        workspace: String,
     },
 
     /// Sign one or all NFTs from candy machine
@@ -245,6 +821,93 @@ pub enum Commands {
         candy_machine_id: Option<String>,
     },
 
+    /// Snapshot the holders of the NFTs minted from a candy machine
+    Snapshot {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy machine, instead of the one of the cache
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Path of the snapshot file
+        #[clap(short, long, default_value = DEFAULT_SNAPSHOT)]
+        output: String,
+    },
+
+    /// Generate poster images for animation-only assets
+    Thumbnails {
+        /// Path to the directory with the assets
+        #[clap(default_value = DEFAULT_ASSETS)]
+        assets_dir: String,
+
+        /// Image used as poster when a frame cannot be extracted from the animation
+        #[clap(long)]
+        template: Option<String>,
+
+        /// Path to the ffmpeg executable used to extract video frames
+        #[clap(long, default_value = "ffmpeg")]
+        ffmpeg: String,
+    },
+
+    /// Manage the SPL tokens used by the token guards
+    Token {
+        #[clap(subcommand)]
+        command: TokenCommand,
+    },
+
+    /// Update the metadata of already minted NFTs
+    UpdateMetadata {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Path to the config file, defaults to "config.json"
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Address of the candy machine.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Update the URIs of the NFTs from the cache file, matching items by name
+        #[clap(long)]
+        from_cache: bool,
+
+        /// Path to a JSON file mapping mint addresses to new name, uri and creators
+        #[clap(long, conflicts_with = "from_cache")]
+        mapping: Option<String>,
+
+        /// Update the creators of the NFTs from the config file
+        #[clap(long)]
+        update_creators: bool,
+
+        /// Maximum number of updates sent in parallel
+        #[clap(long, default_value = "10")]
+        batch_size: usize,
+
+        /// RPC timeout to retrieve the mint list (in seconds).
+        #[clap(short, long)]
+        timeout: Option<u64>,
+    },
+
     /// Upload assets to storage and creates the cache config
     Upload {
         /// Path to the directory with the assets to upload
@@ -266,6 +929,30 @@ pub enum Commands {
         /// Path to the cache file
         #[clap(long, default_value = DEFAULT_CACHE)]
         cache: String,
+
+        /// Stop the upload when assets with identical images or attributes are found
+        #[clap(long)]
+        fail_on_duplicates: bool,
+
+        /// Maximum upload bandwidth in KB/s (Pinata, nft.storage and SDrive)
+        #[clap(long)]
+        max_bandwidth: Option<u64>,
+
+        /// Number of times each upload request is retried on connection errors or 5xx responses
+        #[clap(long, default_value = "3")]
+        max_retries: usize,
+
+        /// Fetch the uploaded files and compare them with the local files
+        #[clap(long)]
+        verify: bool,
+
+        /// Number of assets (randomly selected) to verify, defaults to all assets
+        #[clap(long, requires = "verify")]
+        verify_sample: Option<usize>,
+
+        /// Upload only these indices (e.g. "0-999,1500"), even if their files did not change
+        #[clap(long)]
+        indices: Option<String>,
     },
 
     /// Validate JSON metadata files
@@ -274,6 +961,14 @@ pub enum Commands {
         #[clap(default_value = DEFAULT_ASSETS)]
         assets_dir: String,
 
+        /// Path to the config file, used to check the symbol, royalties and creators
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Path to a JSON Schema file that the attributes and properties must satisfy
+        #[clap(long)]
+        schema: Option<String>,
+
         /// Strict mode: validate against JSON metadata standard exactly
         #[clap(long)]
         strict: bool,
@@ -281,6 +976,14 @@ pub enum Commands {
         /// Skip collection prompt
         #[clap(long)]
         skip_collection_prompt: bool,
+
+        /// Path of a JSON report with every violation found (file, rule and message)
+        #[clap(long)]
+        report: Option<String>,
+
+        /// Stop the validation after this number of errors (1 to fail fast, 0 for no limit)
+        #[clap(long)]
+        max_errors: Option<usize>,
     },
 
     /// Verify uploaded data
@@ -296,6 +999,32 @@ pub enum Commands {
         /// Path to the cache file, defaults to "cache.json"
         #[clap(long, default_value = DEFAULT_CACHE)]
         cache: String,
+
+        /// Re-write the invalid config lines from the cache
+        #[clap(long)]
+        repair: bool,
+
+        /// Verify only these indices (e.g. "0-999,1500")
+        #[clap(long)]
+        indices: Option<String>,
+
+        /// Check that the links of the cache items can be fetched, instead of the
+        /// on-chain config lines
+        #[clap(long, conflicts_with = "repair")]
+        links: bool,
+
+        /// Path of the broken links report (JSON), used with --links
+        #[clap(long, requires = "links")]
+        report: Option<String>,
+
+        /// Check that every minted NFT is a verified member of the collection of the
+        /// candy machine, instead of the config lines
+        #[clap(long, conflicts_with_all = &["repair", "links"])]
+        collection: bool,
+
+        /// Set and verify the collection of the NFTs found by --collection
+        #[clap(long, requires = "collection")]
+        fix: bool,
     },
 
     /// Withdraw funds a from candy machine account closing it
@@ -321,6 +1050,230 @@ pub enum Commands {
         /// Defaults to keypair.pubkey.
         #[clap(long)]
         authority: Option<String>,
+
+        /// Wallet to transfer the reclaimed funds to, defaults to the keypair
+        #[clap(long)]
+        destination: Option<String>,
+
+        /// Only include candy machines with at least this balance (in SOL)
+        #[clap(long)]
+        min_balance: Option<f64>,
+
+        /// Only include candy machines created on or after this date
+        #[clap(long)]
+        created_after: Option<String>,
+
+        /// Only include candy machines created on or before this date
+        #[clap(long)]
+        created_before: Option<String>,
+
+        /// Path to a file with the candy machine ids to include (one per line)
+        #[clap(long)]
+        ids_file: Option<String>,
+    },
+}
+
+impl Commands {
+    /// Returns the path of the config file used by the command, if any.
+    pub fn config_path(&self) -> Option<&str> {
+        match self {
+            Commands::Autotask { config, .. }
+            | Commands::Deploy { config, .. }
+            | Commands::Hash { config, .. }
+            | Commands::Launch { config, .. }
+            | Commands::Mint { config, .. }
+            | Commands::Preflight { config, .. }
+            | Commands::Reveal { config, .. }
+            | Commands::Show { config, .. }
+            | Commands::UpdateMetadata { config, .. }
+            | Commands::Upload { config, .. }
+            | Commands::Validate { config, .. } => Some(config),
+            Commands::Config { command } => match command {
+                ConfigSubcommands::Create { config, .. } => config.as_deref(),
+                ConfigSubcommands::Update { config, .. } => Some(config),
+                ConfigSubcommands::Get { config, .. }
+                | ConfigSubcommands::Set {
+                    config,
+                    path: Some(_),
+                    ..
+                } => Some(config),
+                ConfigSubcommands::Set { .. } => None,
+            },
+            Commands::Ata {
+                command: AtaCommand::Check { config, .. },
+            } => Some(config),
+            Commands::Bundlr {
+                action: BundlrAction::Cost { config, .. },
+                ..
+            } => Some(config),
+            Commands::Collection {
+                command: CollectionSubcommands::Set { config, .. },
+            } => Some(config),
+            Commands::Fairness {
+                command: FairnessCommand::Prove { config, .. },
+            } => Some(config),
+            Commands::Manifest {
+                command: ManifestCommand::Export { config, .. },
+            } => Some(config),
+            Commands::Token {
+                command: TokenCommand::Create { config, .. },
+            } => Some(config),
+            Commands::Shdw { command } => match command {
+                ShdwCommand::Plan { config, .. } | ShdwCommand::Reclaim { config, .. } => {
+                    Some(config)
+                }
+                ShdwCommand::List { .. } => None,
+            },
+            Commands::Guard { command } => match command {
+                GuardCommand::Add { config, .. } | GuardCommand::Update { config, .. } => {
+                    Some(config)
+                }
+                GuardCommand::Allocation {
+                    command: GuardAllocationCommand::Reset { config, .. },
+                } => Some(config),
+                _ => None,
+            },
+            Commands::Freeze { command } => match command {
+                FreezeCommand::Initialize { config, .. }
+                | FreezeCommand::Thaw { config, .. }
+                | FreezeCommand::UnlockFunds { config, .. } => Some(config),
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum EditionsCommand {
+    /// Mint print editions of a master edition to a list of recipients
+    Mint {
+        /// Mint address of the master edition NFT
+        #[clap(long)]
+        master: String,
+
+        /// Number of editions to mint for each recipient
+        #[clap(long, default_value = "1")]
+        count: u64,
+
+        /// Path to a file with one recipient address per line, defaults to the keypair address
+        #[clap(long)]
+        recipients: Option<String>,
+
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the CSV file where mint receipts are appended
+        #[clap(long, default_value = DEFAULT_RECEIPTS)]
+        receipts: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Encrypt the cache file with a passphrase or key file
+    Encrypt {
+        /// Path to the cache file
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Path to a key file used instead of a passphrase
+        #[clap(long)]
+        keyfile: Option<String>,
+    },
+
+    /// Decrypt the cache file
+    Decrypt {
+        /// Path to the cache file
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum FairnessCommand {
+    /// Commit to the order of the uploaded assets before the mint
+    Prove {
+        /// Path to the config file, defaults to "config.json"
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Path of the proof file, defaults to "fairness.json"
+        #[clap(long, default_value = DEFAULT_PROOF)]
+        proof: String,
+    },
+
+    /// Verify that the revealed NFTs match the committed order
+    Verify {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path of the proof file, defaults to "fairness.json"
+        #[clap(long, default_value = DEFAULT_PROOF)]
+        proof: String,
+
+        /// Commitment published before the mint
+        #[clap(long)]
+        commitment: Option<String>,
+
+        /// Address of the candy machine, defaults to the one in the proof
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Path of the verification report (JSON)
+        #[clap(long)]
+        report: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum FeePayersCommand {
+    /// Show the fee payers of the pool and their balances
+    Show {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+    },
+
+    /// Return the leftover SOL of the fee payers to the wallet
+    Sweep {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum KeypairCommand {
+    /// Encrypt a keypair file with a passphrase
+    Encrypt {
+        /// Path to the keypair file to encrypt (or "prompt://" to use a seed phrase)
+        keypair: String,
+
+        /// Path of the encrypted keypair file
+        #[clap(short, long)]
+        output: String,
     },
 }
 
@@ -328,6 +1281,20 @@ pub enum Commands {
 pub enum BundlrAction {
     /// Retrieve the balance on bundlr
     Balance,
+    /// Estimate the cost of uploading the pending assets
+    Cost {
+        /// Path to the directory with the assets
+        #[clap(default_value = DEFAULT_ASSETS)]
+        assets_dir: String,
+
+        /// Path to the config file
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Path to the cache file
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+    },
     /// Withdraw funds from bundlr
     Withdraw,
 }
@@ -340,6 +1307,11 @@ pub enum ConfigSubcommands {
         #[clap(short, long)]
         config: Option<String>,
 
+        /// Generate the config from a preset without prompts [possible values: free-mint,
+        /// sol-payment-with-allowlist, token-gated, hidden-reveal]
+        #[clap(short, long)]
+        template: Option<ConfigTemplate>,
+
         /// RPC Url
         #[clap(short, long)]
         rpc_url: Option<String>,
@@ -377,9 +1349,46 @@ pub enum ConfigSubcommands {
         /// Address of candy machine to update.
         #[clap(long)]
         candy_machine: Option<String>,
+
+        /// Update only the price (in SOL) of the default guard set
+        #[clap(long)]
+        price: Option<f64>,
+
+        /// Update only the go live date of the default guard set (e.g. "2024-07-01 18:00 CET"
+        /// or "in 2h")
+        #[clap(long)]
+        go_live_date: Option<String>,
+
+        /// Update only the number of items available (hidden settings only)
+        #[clap(long)]
+        items_available: Option<u64>,
+
+        /// Update only the symbol
+        #[clap(long)]
+        symbol: Option<String>,
     },
-    /// Set specific candy machine config values
+    /// Print the value of a field of the config file
+    Get {
+        /// Path to the config file, defaults to "config.json"
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Dot path of the field (e.g. "guards.default.solPayment.value")
+        path: String,
+    },
+    /// Set specific candy machine config values, or a field of the config file
     Set {
+        /// Path to the config file, defaults to "config.json"
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Dot path of the config file field to set (e.g. "guards.default.solPayment.value")
+        #[clap(requires = "value", conflicts_with_all = &["token_standard", "rule_set"])]
+        path: Option<String>,
+
+        /// Value of the field, parsed as JSON (anything else is set as a string)
+        value: Option<String>,
+
         /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
         #[clap(short, long)]
         keypair: Option<String>,
@@ -422,23 +1431,192 @@ pub enum CollectionSubcommands {
         #[clap(long, default_value = DEFAULT_CACHE)]
         cache: String,
 
-        /// Path to the config file
-        #[clap(short, long, default_value = DEFAULT_CONFIG)]
-        config: String,
-
-        /// Address of candy machine to update.
+        /// Path to the config file
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Address of candy machine to update.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Address of collection mint to set the candy machine to.
+        collection_mint: String,
+    },
+    /// Verify the collection of the NFTs of a hash list, batching the verifications
+    VerifyItems {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Path to the hash list: a JSON array of mint addresses or one address per line
+        #[clap(long)]
+        hash_list: String,
+
+        /// Address of the collection mint, defaults to the one from the cache
+        #[clap(long)]
+        collection_mint: Option<String>,
+
+        /// Number of verifications per transaction (up to 10)
+        #[clap(long, default_value = "5")]
+        batch_size: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GuardAllocationCommand {
+    /// Initialize the allocation tracker required before minting
+    Init {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy machine.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Address of the candy guard.
+        #[clap(long)]
+        candy_guard: Option<String>,
+
+        /// Label of the guard group (defaults to the default guard set)
+        #[clap(long)]
+        group: Option<String>,
+    },
+    /// Move the guard set to a new allocation id (updating the config and the candy guard)
+    /// and initialize its tracker
+    Reset {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Path to the config file
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Address of the candy machine.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Address of the candy guard.
+        #[clap(long)]
+        candy_guard: Option<String>,
+
+        /// Label of the guard group (defaults to the default guard set)
+        #[clap(long)]
+        group: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GuardCommand {
+    /// Add a candy guard on a candy machine
+    Add {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Path to the config file
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Address of the candy machine.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Address of the candy guard.
+        #[clap(long)]
+        candy_guard: Option<String>,
+
+        /// Read the guards from a guard file instead of the config file
+        #[clap(long)]
+        from_file: Option<String>,
+    },
+    /// Initialize or reset the tracker of an allocation guard
+    Allocation {
+        #[clap(subcommand)]
+        command: GuardAllocationCommand,
+    },
+    /// Export the on-chain guards of a candy guard to a guard file
+    Export {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy guard.
+        #[clap(long)]
+        candy_guard: Option<String>,
+
+        /// Path of the guard file
+        #[clap(short, long, default_value = "guards.json")]
+        output: String,
+    },
+    /// Show how many mints a wallet has left in each guard set
+    Limits {
+        /// Address of the wallet
+        wallet: String,
+
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy machine.
         #[clap(long)]
         candy_machine: Option<String>,
 
-        /// Address of collection mint to set the candy machine to.
-        collection_mint: String,
+        /// Address of the candy guard.
+        #[clap(long)]
+        candy_guard: Option<String>,
     },
-}
-
-#[derive(Subcommand)]
-pub enum GuardCommand {
-    /// Add a candy guard on a candy machine
-    Add {
+    /// Remove a candy guard from a candy machine
+    Remove {
         /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
         #[clap(short, long)]
         keypair: Option<String>,
@@ -451,10 +1629,6 @@ pub enum GuardCommand {
         #[clap(long, default_value = DEFAULT_CACHE)]
         cache: String,
 
-        /// Path to the config file
-        #[clap(short, long, default_value = DEFAULT_CONFIG)]
-        config: String,
-
         /// Address of the candy machine.
         #[clap(long)]
         candy_machine: Option<String>,
@@ -463,8 +1637,8 @@ pub enum GuardCommand {
         #[clap(long)]
         candy_guard: Option<String>,
     },
-    /// Remove a candy guard from a candy machine
-    Remove {
+    /// Restore the candy guard configuration saved before the last update
+    Rollback {
         /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
         #[clap(short, long)]
         keypair: Option<String>,
@@ -477,10 +1651,6 @@ pub enum GuardCommand {
         #[clap(long, default_value = DEFAULT_CACHE)]
         cache: String,
 
-        /// Address of the candy machine.
-        #[clap(long)]
-        candy_machine: Option<String>,
-
         /// Address of the candy guard.
         #[clap(long)]
         candy_guard: Option<String>,
@@ -524,6 +1694,10 @@ pub enum GuardCommand {
         /// Address of the candy guard.
         #[clap(long)]
         candy_guard: Option<String>,
+
+        /// Read the guards from a guard file instead of the config file
+        #[clap(long)]
+        from_file: Option<String>,
     },
     /// Withdraw funds from a candy guard account closing it
     Withdraw {
@@ -539,6 +1713,50 @@ pub enum GuardCommand {
         #[clap(long, default_value = DEFAULT_CACHE)]
         cache: String,
 
+        /// Address of the candy guard.
+        #[clap(long)]
+        candy_guard: Option<String>,
+    },
+    /// Set the candy guard as the mint authority of the candy machine
+    Wrap {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy machine.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Address of the candy guard.
+        #[clap(long)]
+        candy_guard: Option<String>,
+    },
+    /// Remove the candy guard as the mint authority of the candy machine
+    Unwrap {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy machine.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
         /// Address of the candy guard.
         #[clap(long)]
         candy_guard: Option<String>,
@@ -673,3 +1891,276 @@ pub enum FreezeCommand {
         token: bool,
     },
 }
+
+#[derive(Subcommand)]
+pub enum LocalnetCommand {
+    /// Print (or run) the solana-test-validator command cloning the required programs
+    Setup {
+        /// Cluster to clone the programs from
+        #[clap(long, default_value = "mainnet-beta")]
+        url: String,
+
+        /// Ledger directory of the local validator
+        #[clap(long)]
+        ledger: Option<String>,
+
+        /// Reset the ledger to genesis before starting the validator
+        #[clap(long)]
+        reset: bool,
+
+        /// Start the local validator instead of only printing the command
+        #[clap(long)]
+        run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ManifestCommand {
+    /// Export the media and generated metadata files with the links from the cache
+    Export {
+        /// Path to the directory with the assets
+        #[clap(default_value = DEFAULT_ASSETS)]
+        assets_dir: String,
+
+        /// Path to the config file
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Output directory of the manifest and the generated metadata files
+        #[clap(short, long, default_value = DEFAULT_MANIFEST)]
+        output: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ShdwCommand {
+    /// List the storage accounts of the keypair that can be reused
+    List {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+    },
+
+    /// Calculate the storage required by the assets and compare it with the storage account
+    Plan {
+        /// Path to the directory with the assets
+        #[clap(default_value = DEFAULT_ASSETS)]
+        assets_dir: String,
+
+        /// Path to the config file
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Extra storage (percentage) over the size of the assets, defaults to 10
+        #[clap(long)]
+        margin: Option<u8>,
+
+        /// Expand the storage account when it does not have enough space
+        #[clap(long)]
+        expand: bool,
+    },
+
+    /// Reduce the storage account to its current usage, reclaiming the unused SHDW
+    Reclaim {
+        /// Path to the config file
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Extra storage (percentage) kept over the current usage, defaults to 10
+        #[clap(long)]
+        margin: Option<u8>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MetadataCommand {
+    /// Generate metadata files from a template with variables
+    Generate {
+        /// Path to the metadata template, supporting {index}, {number} and {name} placeholders
+        #[clap(long)]
+        template: String,
+
+        /// Number of metadata files to generate
+        #[clap(long)]
+        count: u64,
+
+        /// Path to the directory where the metadata files are written
+        #[clap(long, default_value = DEFAULT_ASSETS)]
+        output: String,
+
+        /// Name of the items, followed by the item number (used for the {name} placeholder)
+        #[clap(long)]
+        name: Option<String>,
+
+        /// Path to a CSV file with the attributes of each item (one column per trait type)
+        #[clap(long)]
+        attributes: Option<String>,
+
+        /// Replace existing metadata files in the output directory
+        #[clap(long)]
+        overwrite: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AtaCommand {
+    /// Create the associated token account of a wallet, e.g. the tokenPayment destination
+    Create {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Address of the token mint
+        #[clap(long)]
+        mint: String,
+
+        /// Wallet owning the token account, defaults to the keypair wallet
+        #[clap(long)]
+        owner: Option<String>,
+    },
+
+    /// Check the destination token accounts referenced by the guards of the config
+    Check {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the config file, defaults to "config.json"
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AnalyticsCommand {
+    /// Report successful mints and bot-taxed attempts by guard failure reason
+    BotTax {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy machine.
+        #[clap(long)]
+        candy_machine: Option<String>,
+
+        /// Only include transactions after this date
+        #[clap(long)]
+        start: Option<String>,
+
+        /// Only include transactions before this date
+        #[clap(long)]
+        end: Option<String>,
+    },
+    /// Sum the sol and token payments received from mints by guard group and day
+    Earnings {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the cache file, defaults to "cache.json"
+        #[clap(long, default_value = DEFAULT_CACHE)]
+        cache: String,
+
+        /// Address of the candy guard.
+        #[clap(long)]
+        candy_guard: Option<String>,
+
+        /// Only include transactions after this date
+        #[clap(long)]
+        start: Option<String>,
+
+        /// Only include transactions before this date
+        #[clap(long)]
+        end: Option<String>,
+
+        /// Path to a CSV file to export the report
+        #[clap(long)]
+        csv: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TokenCommand {
+    /// Create an SPL token (with metadata) and set it as the mint of a token guard
+    Create {
+        /// Path to the keypair file, uses Sol config or defaults to "~/.config/solana/id.json"
+        #[clap(short, long)]
+        keypair: Option<String>,
+
+        /// RPC Url
+        #[clap(short, long)]
+        rpc_url: Option<String>,
+
+        /// Path to the config file, defaults to "config.json"
+        #[clap(short, long, default_value = DEFAULT_CONFIG)]
+        config: String,
+
+        /// Number of decimals of the token
+        #[clap(long, default_value = "9")]
+        decimals: u8,
+
+        /// Number of tokens (in whole units) minted to the keypair wallet
+        #[clap(long, default_value = "0")]
+        supply: u64,
+
+        /// Path to a JSON file with the name, symbol and uri of the token metadata
+        #[clap(long)]
+        metadata: Option<String>,
+
+        /// Guard to set the mint of (tokenPayment, tokenGate, tokenBurn or freezeTokenPayment)
+        #[clap(long, default_value = "tokenPayment")]
+        guard: TokenGuard,
+
+        /// Label of the group with the guard, defaults to the default guard set
+        #[clap(long)]
+        group: Option<String>,
+
+        /// Amount required by the guard (in base units), needed to add a new guard
+        #[clap(long)]
+        amount: Option<u64>,
+    },
+}
@@ -1,3 +1,5 @@
 pub mod set;
+pub mod verify_items;
 
 pub use set::*;
+pub use verify_items::*;
@@ -20,9 +20,10 @@ use crate::{
     candy_machine::{CANDY_MACHINE_ID, *},
     common::*,
     config::get_config_data,
+    dry_run::SendOrPlan,
     hash::hash_and_update,
     pdas::*,
-    update::{process_update, UpdateArgs},
+    update::{process_update, UpdateArgs, UpdateFields},
     utils::{assert_correct_authority, spinner_with_style},
 };
 
@@ -122,7 +123,7 @@ pub fn process_set_collection(args: SetCollectionArgs) -> Result<()> {
     // If a candy machine id wasn't manually specified we are operating on the candy machine in the cache
     // and so need to update the cache file.
     if args.candy_machine.is_none() {
-        cache.items.shift_remove("-1");
+        cache.items.shift_remove(COLLECTION_CACHE_KEY);
         cache.program.collection_mint = collection_mint_pubkey.to_string();
         cache.sync_file()?;
 
@@ -148,6 +149,7 @@ pub fn process_set_collection(args: SetCollectionArgs) -> Result<()> {
                 new_authority: None,
                 config: args.config,
                 candy_machine: Some(candy_machine_id),
+                fields: UpdateFields::default(),
             };
 
             process_update(update_args)?;
@@ -245,7 +247,7 @@ pub fn set_collection<C: Deref<Target = impl Signer> + Clone>(
         })
         .args(nft_instruction::SetCollectionV2);
 
-    let sig = builder.send()?;
+    let sig = builder.send_or_plan("set collection")?;
 
     Ok(sig)
 }
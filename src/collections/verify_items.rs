@@ -0,0 +1,355 @@
+use std::{collections::HashSet, fs};
+
+use anchor_client::solana_sdk::instruction::Instruction;
+use anyhow::Result;
+use console::style;
+use mpl_token_metadata::{
+    instruction::{
+        set_and_verify_collection, set_and_verify_sized_collection_item, verify_collection,
+        verify_sized_collection_item,
+    },
+    state::{Metadata, TokenMetadataAccount},
+};
+
+use crate::{
+    cache::load_cache,
+    candy_machine::CANDY_MACHINE_ID,
+    common::*,
+    dry_run::SendOrPlan,
+    pdas::*,
+    rate_limit::throttled_client_with_commitment,
+    utils::{progress_bar_with_style, spinner_with_style},
+    verify::{collection_issue, CollectionIssue},
+};
+
+/// Maximum number of verifications that fit in a transaction.
+const MAX_VERIFY_BATCH_SIZE: usize = 10;
+/// Maximum number of accounts per `getMultipleAccounts` request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+pub struct VerifyItemsArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub hash_list: String,
+    pub collection_mint: Option<String>,
+    pub batch_size: usize,
+}
+
+pub fn process_verify_items(args: VerifyItemsArgs) -> Result<()> {
+    check_batch_size(args.batch_size)?;
+
+    // the collection mint specified takes precedence over the one from the cache
+    let collection_mint = match args.collection_mint {
+        Some(collection_mint) => collection_mint,
+        None => load_cache(&args.cache, false)?.program.collection_mint,
+    };
+    let collection_mint = Pubkey::from_str(&collection_mint)
+        .map_err(|_| anyhow!("Failed to parse collection mint id: {}", collection_mint))?;
+
+    let mints = load_hash_list(&args.hash_list)?;
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(CANDY_MACHINE_ID);
+    let payer = sugar_config.keypair.pubkey();
+
+    println!(
+        "{} {}Loading collection",
+        style("[1/3]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+    println!("{} {}", style("Collection mint:").bold(), collection_mint);
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let (collection_metadata_pubkey, collection_metadata) =
+        get_metadata_pda(&collection_mint, &program)?;
+
+    pb.finish_with_message("Done");
+
+    if collection_metadata.update_authority != payer {
+        return Err(anyhow!(
+            "The keypair {} is not the update authority {} of the collection",
+            payer,
+            collection_metadata.update_authority
+        ));
+    }
+
+    println!(
+        "\n{} {}Checking {} NFTs",
+        style("[2/3]").bold().dim(),
+        LOOKING_GLASS_EMOJI,
+        mints.len()
+    );
+
+    let collection_master_edition = find_master_edition_pda(&collection_mint);
+    // sized collections keep track of the number of verified items
+    let sized = collection_metadata.collection_details.is_some();
+
+    let pb = progress_bar_with_style(mints.len() as u64);
+    let mut pending: Vec<(Pubkey, Instruction)> = Vec::new();
+    let mut skipped: Vec<(Pubkey, String)> = Vec::new();
+    let mut verified = 0;
+    let rpc_client = throttled_client_with_commitment(&sugar_config.rpc_url);
+
+    for chunk in mints.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let metadata_pubkeys: Vec<Pubkey> = chunk.iter().map(find_metadata_pda).collect();
+        let accounts = rpc_client.get_multiple_accounts(&metadata_pubkeys)?;
+
+        for ((mint, metadata_pubkey), account) in chunk.iter().zip(metadata_pubkeys).zip(accounts) {
+            pb.inc(1);
+
+            let metadata = match account.map(|account| Metadata::safe_deserialize(&account.data)) {
+                Some(Ok(metadata)) => metadata,
+                _ => {
+                    skipped.push((*mint, "metadata account not found".to_string()));
+                    continue;
+                }
+            };
+
+            let ix = match collection_issue(&metadata.collection, &collection_mint) {
+                None => {
+                    verified += 1;
+                    continue;
+                }
+                Some(CollectionIssue::Unverified) => {
+                    let verify = if sized {
+                        verify_sized_collection_item
+                    } else {
+                        verify_collection
+                    };
+                    verify(
+                        mpl_token_metadata::ID,
+                        metadata_pubkey,
+                        payer,
+                        payer,
+                        collection_mint,
+                        collection_metadata_pubkey,
+                        collection_master_edition,
+                        None,
+                    )
+                }
+                Some(issue) if !issue.is_fixable() => {
+                    skipped.push((*mint, issue.to_string()));
+                    continue;
+                }
+                // the update authority of the item must sign to set its collection
+                Some(issue) if metadata.update_authority != payer => {
+                    skipped.push((
+                        *mint,
+                        format!("{issue}, update authority {}", metadata.update_authority),
+                    ));
+                    continue;
+                }
+                Some(_) => {
+                    let set_and_verify = if sized {
+                        set_and_verify_sized_collection_item
+                    } else {
+                        set_and_verify_collection
+                    };
+                    set_and_verify(
+                        mpl_token_metadata::ID,
+                        metadata_pubkey,
+                        payer,
+                        payer,
+                        payer,
+                        collection_mint,
+                        collection_metadata_pubkey,
+                        collection_master_edition,
+                        None,
+                    )
+                }
+            };
+
+            pending.push((*mint, ix));
+        }
+    }
+
+    pb.finish_with_message(format!(
+        "{} verified, {} to verify, {} skipped",
+        verified,
+        pending.len(),
+        skipped.len()
+    ));
+
+    println!(
+        "\n{} {}Verifying collection membership",
+        style("[3/3]").bold().dim(),
+        PAPER_EMOJI
+    );
+
+    let mut failed: Vec<(Pubkey, String)> = Vec::new();
+
+    if pending.is_empty() {
+        println!("\nNo NFTs to verify.");
+    } else {
+        let batches = verification_batches(&pending, args.batch_size);
+        println!(
+            "\nSending {} transaction(s) with up to {} verification(s) each:",
+            batches.len(),
+            args.batch_size
+        );
+        let pb = progress_bar_with_style(pending.len() as u64);
+
+        for (mints, instructions) in batches {
+            let request = instructions
+                .into_iter()
+                .fold(program.request(), |request, ix| request.instruction(ix));
+
+            if let Err(err) = request.send_or_plan("verify collection items") {
+                error!("Failed to verify collection items: {}", err);
+                failed.extend(mints.iter().map(|mint| (*mint, err.to_string())));
+            }
+
+            pb.inc(mints.len() as u64);
+        }
+
+        pb.finish();
+    }
+
+    for (mint, reason) in &skipped {
+        println!("{}Skipped {}: {}", WARNING_EMOJI, mint, reason);
+    }
+
+    for (mint, reason) in &failed {
+        println!("{}Failed {}: {}", ERROR_EMOJI, mint, reason);
+    }
+
+    let fixed = pending.len() - failed.len();
+
+    if !failed.is_empty() || !skipped.is_empty() {
+        return Err(anyhow!(
+            "{} of {} NFTs are not verified members of the collection ({} fixed)",
+            failed.len() + skipped.len(),
+            mints.len(),
+            fixed
+        ));
+    }
+
+    println!(
+        "\n{}All {} NFTs are verified members of the collection ({} fixed).",
+        COMPLETE_EMOJI,
+        mints.len(),
+        fixed
+    );
+
+    Ok(())
+}
+
+fn check_batch_size(batch_size: usize) -> Result<()> {
+    if batch_size == 0 || batch_size > MAX_VERIFY_BATCH_SIZE {
+        return Err(anyhow!(
+            "The batch size must be between 1 and {}",
+            MAX_VERIFY_BATCH_SIZE
+        ));
+    }
+
+    Ok(())
+}
+
+/// Groups the pending verifications in transactions of up to `batch_size` instructions,
+/// returning the mints and the instructions of each transaction.
+fn verification_batches(
+    pending: &[(Pubkey, Instruction)],
+    batch_size: usize,
+) -> Vec<(Vec<Pubkey>, Vec<Instruction>)> {
+    pending
+        .chunks(batch_size)
+        .map(|batch| batch.iter().cloned().unzip())
+        .collect()
+}
+
+/// Load the hash list: a JSON array of mint addresses or a file with one address per
+/// line (empty lines and lines starting with `#` are ignored).
+pub fn load_hash_list(path: &str) -> Result<Vec<Pubkey>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read hash list '{}': {}", path, e))?;
+
+    let addresses: Vec<String> = match serde_json::from_str(&content) {
+        Ok(addresses) => addresses,
+        Err(_) => content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect(),
+    };
+
+    let mut mints: Vec<Pubkey> = Vec::with_capacity(addresses.len());
+    let mut seen = HashSet::new();
+
+    for address in addresses {
+        let mint = Pubkey::from_str(address.trim())
+            .map_err(|_| anyhow!("Invalid mint '{}' in '{}'", address, path))?;
+
+        if seen.insert(mint) {
+            mints.push(mint);
+        }
+    }
+
+    Ok(mints)
+}
+
+#[test]
+fn test_load_hash_list() {
+    let dir = std::env::temp_dir().join(format!("sugar-hash-list-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+
+    // duplicated mints are verified once
+    let json = dir.join("hash_list.json");
+    fs::write(
+        &json,
+        json!([first.to_string(), second.to_string(), first.to_string()]).to_string(),
+    )
+    .unwrap();
+    assert_eq!(
+        load_hash_list(json.to_str().unwrap()).unwrap(),
+        vec![first, second]
+    );
+
+    let lines = dir.join("hash_list.txt");
+    fs::write(&lines, format!("# mints\n{first}\n\n  {second}  \n")).unwrap();
+    assert_eq!(
+        load_hash_list(lines.to_str().unwrap()).unwrap(),
+        vec![first, second]
+    );
+
+    fs::write(&lines, format!("{first}\nnot-a-mint\n")).unwrap();
+    assert!(load_hash_list(lines.to_str().unwrap()).is_err());
+    assert!(load_hash_list(dir.join("missing.json").to_str().unwrap()).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_verification_batches() {
+    assert!(check_batch_size(0).is_err());
+    assert!(check_batch_size(MAX_VERIFY_BATCH_SIZE + 1).is_err());
+    assert!(check_batch_size(MAX_VERIFY_BATCH_SIZE).is_ok());
+
+    let pending: Vec<(Pubkey, Instruction)> = (0..25)
+        .map(|_| {
+            let mint = Pubkey::new_unique();
+            (
+                mint,
+                Instruction::new_with_bytes(mpl_token_metadata::ID, &[], vec![]),
+            )
+        })
+        .collect();
+
+    let batches = verification_batches(&pending, MAX_VERIFY_BATCH_SIZE);
+    assert_eq!(
+        batches
+            .iter()
+            .map(|(mints, _)| mints.len())
+            .collect::<Vec<_>>(),
+        vec![10, 10, 5]
+    );
+    assert!(batches.iter().all(|(mints, ixs)| mints.len() == ixs.len()));
+    // the mints keep the order of the hash list
+    assert_eq!(batches[2].0[0], pending[20].0);
+}
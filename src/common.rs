@@ -28,9 +28,9 @@ pub use serde_json::{json, Value};
 pub use tracing::{debug, error, info, warn};
 
 pub use crate::{
-    cache::{Cache, CacheItem},
+    cache::{Cache, CacheItem, ItemStatus, ItemTimestamps},
     constants::*,
     errors::*,
     parse::path_to_string,
-    setup::{setup_client, sugar_setup},
+    setup::{setup_client, sugar_setup, sugar_setup_read_only},
 };
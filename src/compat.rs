@@ -0,0 +1,300 @@
+use std::{
+    fs,
+    io::Read,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anchor_client::solana_sdk::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use console::style;
+use flate2::read::ZlibDecoder;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use solana_client::rpc_client::RpcClient;
+
+use crate::{
+    candy_machine::CANDY_MACHINE_ID,
+    common::*,
+    core_candy_machine::{CORE_CANDY_GUARD_ID, CORE_CANDY_MACHINE_ID},
+    journal::STATE_DIR,
+    rate_limit::throttled_client,
+};
+
+/// File with the program hashes seen on each cluster, to detect program upgrades.
+pub const PROGRAMS_FILE: &str = "programs.json";
+
+/// Environment variable enabling the check (same as `--check-programs`).
+pub const CHECK_PROGRAMS_ENV: &str = "SUGAR_CHECK_PROGRAMS";
+
+/// Indicates whether the program check is pending (it runs once per command).
+static CHECK_PROGRAMS: AtomicBool = AtomicBool::new(false);
+
+/// A program used by sugar and the version of its crate sugar was built with.
+struct ProgramInfo {
+    name: &'static str,
+    id: Pubkey,
+    built_for: Option<&'static str>,
+}
+
+fn programs() -> [ProgramInfo; 4] {
+    [
+        ProgramInfo {
+            name: "Candy Machine Core",
+            id: CANDY_MACHINE_ID,
+            built_for: Some("1.0.3"),
+        },
+        ProgramInfo {
+            name: "Candy Guard",
+            id: mpl_candy_guard::ID,
+            built_for: Some("1.1.0"),
+        },
+        ProgramInfo {
+            name: "Core Candy Machine",
+            id: CORE_CANDY_MACHINE_ID,
+            built_for: None,
+        },
+        ProgramInfo {
+            name: "Core Candy Guard",
+            id: CORE_CANDY_GUARD_ID,
+            built_for: None,
+        },
+    ]
+}
+
+/// Hash and version of a deployed program.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeployedProgram {
+    /// SHA-256 of the program binary.
+    pub hash: String,
+    /// Slot of the last deployment.
+    pub slot: u64,
+    /// Version of the IDL published on-chain.
+    pub version: Option<String>,
+}
+
+/// Enables the check of the deployed programs, based on the `--check-programs` global
+/// flag or the `SUGAR_CHECK_PROGRAMS` environment variable.
+pub fn set_program_check(check_programs: bool) {
+    let from_env = std::env::var(CHECK_PROGRAMS_ENV)
+        .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+
+    CHECK_PROGRAMS.store(check_programs || from_env, Ordering::SeqCst);
+}
+
+/// Compares the programs deployed on the cluster with the versions sugar was built for,
+/// warning about incompatibilities. Only the RPC node of the config is queried; nothing
+/// is reported anywhere else.
+pub fn check_program_versions(rpc_url: &str) {
+    if !CHECK_PROGRAMS.swap(false, Ordering::SeqCst) {
+        return;
+    }
+
+    if let Err(err) = check_programs(rpc_url) {
+        warn!("Failed to check the deployed programs: {}", err);
+        println!(
+            "{}",
+            style(format!("Could not check the deployed programs: {err}")).dim()
+        );
+    }
+}
+
+fn check_programs(rpc_url: &str) -> Result<()> {
+    let rpc_client = throttled_client(rpc_url);
+    let cluster = rpc_client.get_genesis_hash()?.to_string();
+
+    let mut known = load_known_programs()?;
+    let seen = known.entry(cluster).or_default();
+    let mut warnings = Vec::new();
+
+    for program in programs() {
+        let deployed = match deployed_program(&rpc_client, &program.id)? {
+            Some(deployed) => deployed,
+            None => {
+                // the core programs are optional
+                if program.built_for.is_some() {
+                    warnings.push(format!(
+                        "{} ({}) is not deployed on this cluster.",
+                        program.name, program.id
+                    ));
+                }
+                continue;
+            }
+        };
+
+        debug!(
+            "{} ({}): hash {}, slot {}, version {:?}",
+            program.name, program.id, deployed.hash, deployed.slot, deployed.version
+        );
+
+        if let (Some(built_for), Some(version)) = (program.built_for, &deployed.version) {
+            if !compatible(built_for, version) {
+                warnings.push(format!(
+                    "{} is at version {} but sugar {} was built for version {}.",
+                    program.name,
+                    version,
+                    env!("CARGO_PKG_VERSION"),
+                    built_for
+                ));
+            }
+        }
+
+        let key = program.id.to_string();
+
+        if let Some(previous) = seen.get(&key) {
+            if previous.hash != deployed.hash {
+                warnings.push(format!(
+                    "{} was upgraded (slot {}) since the last check.",
+                    program.name, deployed.slot
+                ));
+            }
+        }
+
+        seen.insert(key, deployed);
+    }
+
+    save_known_programs(&known)?;
+
+    if !warnings.is_empty() {
+        println!(
+            "\n{}",
+            style("WARNING: the deployed programs may be incompatible with this sugar version")
+                .red()
+                .bold()
+        );
+        for warning in &warnings {
+            println!("  {} {}", style("->").red(), warning);
+        }
+        println!(
+            "{}\n",
+            style("Commands can fail with deserialization errors, run 'sugar self-update'.").red()
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the hash, deployment slot and IDL version of a program, or `None` if the
+/// program is not deployed.
+fn deployed_program(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+) -> Result<Option<DeployedProgram>> {
+    let account = match rpc_client
+        .get_account_with_commitment(program_id, CommitmentConfig::confirmed())?
+        .value
+    {
+        Some(account) if account.executable => account,
+        _ => return Ok(None),
+    };
+
+    let (binary, slot) = if account.owner == bpf_loader_upgradeable::id() {
+        let programdata_address = match bincode::deserialize(&account.data)? {
+            UpgradeableLoaderState::Program {
+                programdata_address,
+            } => programdata_address,
+            _ => return Err(anyhow!("Invalid program account {}", program_id)),
+        };
+
+        let programdata = rpc_client.get_account_data(&programdata_address)?;
+        let offset = UpgradeableLoaderState::size_of_programdata_metadata();
+        let slot = match bincode::deserialize(&programdata)? {
+            UpgradeableLoaderState::ProgramData { slot, .. } => slot,
+            _ => {
+                return Err(anyhow!(
+                    "Invalid program data account {}",
+                    programdata_address
+                ))
+            }
+        };
+
+        (programdata[offset..].to_vec(), slot)
+    } else {
+        (account.data, 0)
+    };
+
+    Ok(Some(DeployedProgram {
+        hash: program_hash(&binary),
+        slot,
+        version: idl_version(rpc_client, program_id),
+    }))
+}
+
+/// Hash of the program binary, ignoring the zero padding of the program data account.
+fn program_hash(binary: &[u8]) -> String {
+    let length = binary
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(0, |index| index + 1);
+
+    hex::encode(Sha256::digest(&binary[..length]))
+}
+
+/// Returns the version of the Anchor IDL published by the program, if any.
+fn idl_version(rpc_client: &RpcClient, program_id: &Pubkey) -> Option<String> {
+    let base = Pubkey::find_program_address(&[], program_id).0;
+    let address = Pubkey::create_with_seed(&base, "anchor:idl", program_id).ok()?;
+    let data = rpc_client.get_account_data(&address).ok()?;
+
+    // discriminator (8) + authority (32) + length (4) + zlib compressed IDL
+    let length = u32::from_le_bytes(data.get(40..44)?.try_into().ok()?) as usize;
+    let compressed = data.get(44..44 + length)?;
+
+    let mut idl = String::new();
+    ZlibDecoder::new(compressed).read_to_string(&mut idl).ok()?;
+
+    let idl: Value = serde_json::from_str(&idl).ok()?;
+    idl["version"].as_str().map(String::from)
+}
+
+/// Versions are compatible when they have the same major version and the deployed
+/// program is not newer in the minor version (0.x versions must match the minor).
+fn compatible(built_for: &str, deployed: &str) -> bool {
+    let parse = |version: &str| -> Option<(u64, u64)> {
+        let mut parts = version.trim_start_matches('v').split('.');
+        Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+    };
+
+    match (parse(built_for), parse(deployed)) {
+        (Some((0, minor)), Some((0, deployed_minor))) => minor == deployed_minor,
+        (Some((major, minor)), Some((deployed_major, deployed_minor))) => {
+            major == deployed_major && deployed_minor <= minor
+        }
+        // unknown version format, nothing to compare
+        _ => true,
+    }
+}
+
+type KnownPrograms = HashMap<String, HashMap<String, DeployedProgram>>;
+
+fn load_known_programs() -> Result<KnownPrograms> {
+    let path = Path::new(STATE_DIR).join(PROGRAMS_FILE);
+
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    // a corrupted file only loses the upgrade detection
+    Ok(serde_json::from_str(&fs::read_to_string(path)?).unwrap_or_default())
+}
+
+fn save_known_programs(known: &KnownPrograms) -> Result<()> {
+    fs::create_dir_all(STATE_DIR)?;
+    fs::write(
+        Path::new(STATE_DIR).join(PROGRAMS_FILE),
+        serde_json::to_string_pretty(known)?,
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn test_program_compatibility() {
+    assert!(compatible("1.1.0", "1.1.4"));
+    assert!(compatible("1.1.0", "1.0.0"));
+    assert!(!compatible("1.1.0", "1.2.0"));
+    assert!(!compatible("1.1.0", "2.0.0"));
+    assert!(!compatible("0.2.0", "0.3.0"));
+    assert!(compatible("1.1.0", "unknown"));
+
+    assert_eq!(program_hash(&[1, 2, 0, 0]), program_hash(&[1, 2]));
+}
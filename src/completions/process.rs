@@ -0,0 +1,40 @@
+use std::io::{self, Write};
+
+use clap::CommandFactory;
+use clap_complete::{generate, Shell};
+
+use crate::{cli::Cli, common::*};
+
+/// Name of the binary the completions are registered for.
+const BIN_NAME: &str = "sugar";
+
+pub struct CompletionsArgs {
+    pub shell: Shell,
+}
+
+/// Writes the completion script of the shell to stdout.
+pub fn process_completions(args: CompletionsArgs) -> Result<()> {
+    write_completions(args.shell, &mut io::stdout());
+
+    Ok(())
+}
+
+fn write_completions(shell: Shell, out: &mut dyn Write) {
+    generate(shell, &mut Cli::command(), BIN_NAME, out);
+}
+
+#[test]
+fn test_completions() {
+    // fails on conflicting or invalid arguments of any subcommand
+    Cli::command().debug_assert();
+
+    for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+        let mut script = Vec::new();
+        write_completions(shell, &mut script);
+        let script = String::from_utf8(script).unwrap();
+
+        for value in ["deploy", "rollback", "--dry-run", "--costs-file"] {
+            assert!(script.contains(value), "{shell}: {value}");
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::config::errors::ConfigError;
+
+/// Name of the config value with the profile of the credentials file.
+pub const CREDENTIALS_KEY: &str = "credentials";
+
+/// Environment variable with the path of the credentials file, which defaults to
+/// "~/.config/sugar/credentials.toml".
+pub const CREDENTIALS_ENV: &str = "SUGAR_CREDENTIALS";
+
+/// Secrets of the upload methods, kept outside of the project config so it can be
+/// committed. Each section of the credentials file is a profile:
+///
+/// ```toml
+/// [mainnet]
+/// pinata_jwt = "..."
+/// aws_access_key_id = "..."
+/// aws_secret_access_key = "..."
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StorageCredentials {
+    pub aws_access_key_id: Option<String>,
+    pub aws_secret_access_key: Option<String>,
+    pub pinata_jwt: Option<String>,
+    pub pinning_access_token: Option<String>,
+    pub sdrive_api_key: Option<String>,
+    pub nft_storage_auth_token: Option<String>,
+    pub shdw_storage_account: Option<String>,
+}
+
+pub fn credentials_path() -> PathBuf {
+    match std::env::var(CREDENTIALS_ENV) {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => dirs::home_dir()
+            .expect("Couldn't find home dir.")
+            .join(".config/sugar/credentials.toml"),
+    }
+}
+
+/// Loads a profile of the credentials file.
+pub fn load_credentials(profile: &str) -> Result<StorageCredentials, ConfigError> {
+    let path = credentials_path();
+    let content = fs::read_to_string(&path).map_err(|e| {
+        ConfigError::CredentialsError(format!("failed to read '{}': {}", path.display(), e))
+    })?;
+
+    parse_credentials(&content, profile)
+        .map_err(|e| ConfigError::CredentialsError(format!("'{}': {}", path.display(), e)))
+}
+
+fn parse_credentials(content: &str, profile: &str) -> Result<StorageCredentials, String> {
+    let mut profiles: HashMap<String, StorageCredentials> =
+        toml::from_str(content).map_err(|e| e.to_string())?;
+
+    profiles
+        .remove(profile)
+        .ok_or_else(|| format!("profile '{profile}' not found"))
+}
+
+/// Fills the secrets of the upload methods in a raw config value from the credentials
+/// profile it references, if any. The secrets take precedence over the config values.
+pub fn apply_credentials(config: &mut Value) -> Result<(), ConfigError> {
+    let profile = match config.get(CREDENTIALS_KEY) {
+        Some(Value::String(profile)) => profile.clone(),
+        Some(Value::Null) | None => return Ok(()),
+        Some(_) => {
+            return Err(ConfigError::ParseError(format!(
+                "'{CREDENTIALS_KEY}' must be the name of a credentials profile"
+            )))
+        }
+    };
+
+    insert_credentials(config, load_credentials(&profile)?);
+
+    Ok(())
+}
+
+fn insert_credentials(config: &mut Value, credentials: StorageCredentials) {
+    let values = [
+        (None, "sdriveApiKey", credentials.sdrive_api_key),
+        (
+            None,
+            "nftStorageAuthToken",
+            credentials.nft_storage_auth_token,
+        ),
+        (None, "shdwStorageAccount", credentials.shdw_storage_account),
+        (Some("pinataConfig"), "jwt", credentials.pinata_jwt),
+        (
+            Some("pinningServiceConfig"),
+            "accessToken",
+            credentials.pinning_access_token,
+        ),
+        (
+            Some("awsConfig"),
+            "accessKeyId",
+            credentials.aws_access_key_id,
+        ),
+        (
+            Some("awsConfig"),
+            "secretAccessKey",
+            credentials.aws_secret_access_key,
+        ),
+    ];
+
+    for (section, key, value) in values {
+        let value = match value {
+            Some(value) => value,
+            None => continue,
+        };
+
+        // the other settings of the upload method remain in the config file
+        let target = match section {
+            Some(section) => match config.get_mut(section) {
+                Some(target) if target.is_object() => target,
+                _ => continue,
+            },
+            None => &mut *config,
+        };
+
+        if let Value::Object(map) = target {
+            map.insert(key.to_string(), json!(value));
+        }
+    }
+}
+
+#[test]
+fn test_credentials_profile() {
+    let content = r#"
+        [default]
+        sdrive_api_key = "sdrive"
+
+        [mainnet]
+        pinata_jwt = "jwt"
+        aws_access_key_id = "key"
+    "#;
+
+    let credentials = parse_credentials(content, "mainnet").unwrap();
+    assert_eq!(credentials.pinata_jwt.as_deref(), Some("jwt"));
+    assert!(parse_credentials(content, "devnet").is_err());
+
+    let mut config = json!({
+        "credentials": "mainnet",
+        "pinataConfig": {
+            "apiGateway": "https://api.pinata.cloud",
+            "contentGateway": "https://gateway.pinata.cloud"
+        }
+    });
+    insert_credentials(&mut config, credentials);
+
+    assert_eq!(config["pinataConfig"]["jwt"], "jwt");
+    // the AWS keys are ignored without an AWS config
+    assert!(config.get("awsConfig").is_none());
+}
@@ -10,12 +10,17 @@ pub use anyhow::{anyhow, Result};
 use chrono::prelude::*;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use super::CandyGuardData;
-use crate::config::errors::*;
+use super::{parse_date, CandyGuardData};
+use crate::{
+    config::errors::*,
+    core_candy_machine::{Attribute, Plugin},
+};
 
 pub struct SugarConfig {
     pub keypair: Keypair,
     pub rpc_url: String,
+    /// Whether the keypair is an ephemeral one, used when reading on-chain data only.
+    pub read_only: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -32,6 +37,10 @@ pub struct ConfigData {
     #[serde(default)]
     pub token_standard: TokenStandard,
 
+    /// Asset standard: Token Metadata NFTs or Metaplex Core assets.
+    #[serde(default)]
+    pub standard: AssetStandard,
+
     /// Number of assets available
     pub number: u64,
 
@@ -58,6 +67,9 @@ pub struct ConfigData {
     #[serde(serialize_with = "to_option_string")]
     pub rule_set: Option<Pubkey>,
 
+    // Bundlr specific configuration
+    pub bundlr_config: Option<BundlrConfig>,
+
     // AWS specific configuration
     pub aws_config: Option<AwsConfig>,
 
@@ -69,28 +81,165 @@ pub struct ConfigData {
     #[serde(serialize_with = "to_option_string")]
     pub nft_storage_auth_token: Option<String>,
 
+    /// Upload API compatible with nft.storage (the classic API is being sunset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nft_storage_config: Option<NftStorageConfig>,
+
     // Shadow Drive specific configuration
     #[serde(serialize_with = "to_option_string")]
     pub shdw_storage_account: Option<String>,
 
+    /// Shadow Drive storage planning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shdw_config: Option<ShdwConfig>,
+
     // Pinata specific configuration
     pub pinata_config: Option<PinataConfig>,
 
+    /// IPFS Pinning Service API configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinning_service_config: Option<PinningServiceConfig>,
+
+    /// Template of the links of the files uploaded by an external pipeline (custom
+    /// upload method), e.g. "https://cdn.example.com/{hash}{ext}"
+    #[serde(default)]
+    pub uri_template: Option<String>,
+
     /// Hidden setttings
     pub hidden_settings: Option<HiddenSettings>,
 
     /// Guards configuration
     pub guards: Option<CandyGuardData>,
 
+    /// Path to the collection metadata file (or a directory containing a
+    /// `collection.json` file) when not stored in the assets folder
+    pub collection_path: Option<String>,
+
+    /// Settings of the collection NFT created by sugar, when different from the items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<CollectionConfig>,
+
     pub max_edition_supply: Option<u64>,
+
+    /// Image optimizations applied before upload
+    pub optimizations: Option<OptimizationConfig>,
+
+    /// Values of the config injected into every metadata file when it is uploaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_overrides: Option<MetadataOverridesConfig>,
+
+    /// Plugins of the Core collection, inherited by its assets (Core standard only)
+    #[serde(default)]
+    pub plugins: Option<Vec<CorePlugin>>,
+
+    /// External commands run at lifecycle events, receiving a JSON payload on stdin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<HooksConfig>,
+
+    /// Name of the profile of the credentials file (~/.config/sugar/credentials.toml)
+    /// with the secrets of the upload method
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials: Option<String>,
+
+    /// Authority expected to sign the transactions; commands run with a different
+    /// keypair require the `--i-know-what-im-doing` flag
+    #[serde(
+        default,
+        deserialize_with = "to_option_pubkey",
+        serialize_with = "to_option_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expected_authority: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizationConfig {
+    /// Maximum width or height of the images; larger images are resized.
+    pub max_dimension: Option<u32>,
+    /// Quality (1-100) used to compress JPEG and WebP images.
+    pub quality: Option<u8>,
+    /// Indicates whether images are converted to WebP.
+    #[serde(default)]
+    pub webp: bool,
+}
+
+/// Commands (run by the shell) at lifecycle events of sugar, e.g. to record the minted
+/// items in a database. Each command receives the details of the event as a JSON object
+/// on stdin.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HooksConfig {
+    /// Command run before the files are uploaded; a failure aborts the upload.
+    pub pre_upload: Option<String>,
+    /// Command run after the candy machine is deployed.
+    pub post_deploy: Option<String>,
+    /// Command run after the items are minted.
+    pub post_mint: Option<String>,
+}
+
+/// Fields of the metadata files rewritten from the config before they are uploaded, so
+/// a change of the config applies to every item without editing the files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataOverridesConfig {
+    /// Value of the `external_url` of every item.
+    pub external_url: Option<String>,
+    /// Indicates whether `seller_fee_basis_points` is set to the value of the config.
+    #[serde(default)]
+    pub seller_fee_basis_points: bool,
+    /// Indicates whether the `properties.creators` are set to the creators of the config.
+    #[serde(default)]
+    pub creators: bool,
+}
+
+/// Settings of the collection NFT; unset values fall back to the collection metadata
+/// file, the symbol of the config and no royalties.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionConfig {
+    /// Name of the collection (required with `uri`).
+    pub name: Option<String>,
+    /// Link of an already uploaded collection metadata; no collection files are uploaded.
+    pub uri: Option<String>,
+    /// Path of the collection image, replacing the one next to the metadata file.
+    pub image: Option<String>,
+    /// Symbol of the collection.
+    pub symbol: Option<String>,
+    /// Royalties of the collection NFT (0-10000).
+    pub seller_fee_basis_points: Option<u16>,
+    /// Creators of the collection NFT; the payer is added as the only creator if unset.
+    pub creators: Option<Vec<Creator>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AwsConfig {
     pub bucket: String,
     pub profile: String,
     pub directory: String,
     pub domain: Option<String>,
+    /// ARN of the role assumed (STS) with the profile credentials
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role_arn: Option<String>,
+    /// Layout of the object keys, supporting {directory}, {type} and {name} placeholders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_layout: Option<String>,
+    /// Value of the Cache-Control header of the uploaded objects
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+    /// CloudFront distribution domain used for the links instead of the S3 url
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloudfront_domain: Option<String>,
+    /// Region of the bucket, read from the AWS profile if not specified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Access key from the credentials file, used instead of the profile credentials
+    #[serde(default, skip_serializing)]
+    pub access_key_id: Option<String>,
+    /// Secret key from the credentials file
+    #[serde(default, skip_serializing)]
+    pub secret_access_key: Option<String>,
 }
 
 impl AwsConfig {
@@ -105,10 +254,57 @@ impl AwsConfig {
             profile,
             directory,
             domain,
+            role_arn: None,
+            key_layout: None,
+            cache_control: None,
+            cloudfront_domain: None,
+            region: None,
+            access_key_id: None,
+            secret_access_key: None,
         }
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NftStorageConfig {
+    /// Endpoint of an upload API compatible with the nft.storage `/upload` request,
+    /// defaults to the classic nft.storage API
+    pub api_url: Option<String>,
+    /// Gateway used in the links of the uploaded files
+    pub gateway_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShdwConfig {
+    /// Extra storage reserved over the size of the assets (percentage, default 10)
+    pub margin: Option<u8>,
+    /// Expand the storage account when it does not have enough space for the upload
+    #[serde(default)]
+    pub auto_expand: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundlrConfig {
+    #[serde(default)]
+    pub funding_mode: BundlrFundingMode,
+}
+
+/// How the Bundlr balance is funded when it is not enough for an upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BundlrFundingMode {
+    /// Funds the shortfall plus a 30% padding.
+    #[default]
+    Padded,
+    /// Funds exactly the shortfall.
+    Auto,
+    /// Never funds the balance automatically.
+    Manual,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PinataConfig {
@@ -129,6 +325,20 @@ impl PinataConfig {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinningServiceConfig {
+    /// Endpoint of the IPFS Pinning Service API
+    pub endpoint: String,
+    #[serde(serialize_with = "to_string")]
+    pub access_token: String,
+    /// API of the IPFS node providing the content (e.g. "http://127.0.0.1:5001")
+    pub ipfs_api_url: Option<String>,
+    /// Multiaddrs of the nodes providing the content
+    pub origins: Option<Vec<String>>,
+    pub parallel_limit: Option<u16>,
+}
+
 pub fn to_string<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
 where
     T: Display,
@@ -173,15 +383,19 @@ pub fn parse_string_as_date(go_live_date: &str) -> Result<String> {
 
 pub fn go_live_date_as_timestamp(go_live_date: &Option<String>) -> Result<Option<i64>> {
     if let Some(go_live_date) = go_live_date {
-        let date = dateparser::parse(go_live_date)?;
-        Ok(Some(date.timestamp()))
+        Ok(Some(parse_date(go_live_date)?.timestamp()))
     } else {
         Ok(None)
     }
 }
 
 pub fn price_as_lamports(price: f64) -> u64 {
-    (price * LAMPORTS_PER_SOL as f64) as u64
+    // rounded, as prices such as 2.01 are not exact in binary (2.00999...)
+    (price * LAMPORTS_PER_SOL as f64).round() as u64
+}
+
+pub fn lamports_as_price(lamports: u64) -> f64 {
+    lamports as f64 / LAMPORTS_PER_SOL as f64
 }
 
 fn to_pubkey<'de, D>(deserializer: D) -> Result<Pubkey, D::Error>
@@ -228,11 +442,47 @@ pub enum UploadMethod {
     Pinata,
     #[serde(rename = "sdrive")]
     Sdrive,
+    /// Any provider implementing the IPFS Pinning Service API.
+    PinningService,
+    /// Files are uploaded by an external pipeline, links are generated from `uriTemplate`.
+    Custom,
+    /// Storage provider registered with [`register_storage`](crate::upload::register_storage).
+    #[serde(untagged, deserialize_with = "to_registered_storage")]
+    Plugin(String),
+}
+
+/// Accepts only the names of registered storage providers, so that a misspelled upload
+/// method is reported when the config file is parsed.
+fn to_registered_storage<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name: String = Deserialize::deserialize(deserializer)?;
+
+    if crate::upload::registered_storages().contains(&name) {
+        Ok(name)
+    } else {
+        Err(serde::de::Error::custom(ConfigError::InvalidUploadMethod(
+            name,
+        )))
+    }
+}
+
+impl UploadMethod {
+    /// Upload method with the name used in the config file; names that are not built-in
+    /// upload methods refer to storage providers, registered or not.
+    pub fn from_name(name: &str) -> UploadMethod {
+        serde_json::from_value(serde_json::Value::String(name.to_string()))
+            .unwrap_or_else(|_| UploadMethod::Plugin(name.to_string()))
+    }
 }
 
 impl Display for UploadMethod {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            UploadMethod::Plugin(name) => write!(f, "{}", name),
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
@@ -327,3 +577,68 @@ impl FromStr for TokenStandard {
         }
     }
 }
+
+/// Standard of the assets minted by the candy machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum AssetStandard {
+    /// Token Metadata NFTs (candy machine core).
+    #[serde(rename = "token-metadata")]
+    #[default]
+    TokenMetadata,
+    /// Metaplex Core assets (core candy machine).
+    #[serde(rename = "core")]
+    Core,
+}
+
+/// Plugins added to the Core collection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CorePlugin {
+    PermanentFreezeDelegate {
+        frozen: bool,
+    },
+    PermanentTransferDelegate,
+    PermanentBurnDelegate,
+    Attributes {
+        #[serde(rename = "attributeList")]
+        attribute_list: Vec<CoreAttribute>,
+    },
+}
+
+impl CorePlugin {
+    pub fn to_core_format(&self) -> Plugin {
+        match self {
+            CorePlugin::PermanentFreezeDelegate { frozen } => {
+                Plugin::PermanentFreezeDelegate { frozen: *frozen }
+            }
+            CorePlugin::PermanentTransferDelegate => Plugin::PermanentTransferDelegate,
+            CorePlugin::PermanentBurnDelegate => Plugin::PermanentBurnDelegate,
+            CorePlugin::Attributes { attribute_list } => Plugin::Attributes {
+                attribute_list: attribute_list
+                    .iter()
+                    .map(|attribute| Attribute {
+                        key: attribute.key.clone(),
+                        value: attribute.value.clone(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreAttribute {
+    pub key: String,
+    pub value: String,
+}
+
+#[test]
+fn test_price_round_trip() {
+    assert_eq!(price_as_lamports(2.01), 2_010_000_000);
+    assert_eq!(price_as_lamports(4.1), 4_100_000_000);
+    assert_eq!(price_as_lamports(0.000000001), 1);
+
+    for lamports in [1, 290_000_000, 1_100_000_000, 123_456_789_012] {
+        assert_eq!(price_as_lamports(lamports_as_price(lamports)), lamports);
+    }
+}
@@ -0,0 +1,232 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, FixedOffset, Local, TimeZone, Utc};
+use console::style;
+use mpl_candy_guard::{
+    guards::{EndDate, StartDate},
+    state::{CandyGuardData, GuardSet},
+};
+
+// Time zone abbreviations accepted after a date, with their offset from UTC (in hours).
+const ZONE_ABBREVIATIONS: &[(&str, f32)] = &[
+    ("UTC", 0.0),
+    ("GMT", 0.0),
+    ("Z", 0.0),
+    ("WET", 0.0),
+    ("WEST", 1.0),
+    ("BST", 1.0),
+    ("CET", 1.0),
+    ("CEST", 2.0),
+    ("EET", 2.0),
+    ("EEST", 3.0),
+    ("MSK", 3.0),
+    ("SGT", 8.0),
+    ("HKT", 8.0),
+    ("JST", 9.0),
+    ("KST", 9.0),
+    ("AEST", 10.0),
+    ("AEDT", 11.0),
+    ("NZST", 12.0),
+    ("NZDT", 13.0),
+    ("EST", -5.0),
+    ("EDT", -4.0),
+    ("CDT", -5.0),
+    ("MST", -7.0),
+    ("MDT", -6.0),
+    ("PST", -8.0),
+    ("PDT", -7.0),
+    ("AKST", -9.0),
+    ("AKDT", -8.0),
+    ("HST", -10.0),
+];
+
+// Time zone abbreviations used by more than one zone (e.g. CST is both China and US
+// Central Standard Time), rejected instead of guessing.
+const AMBIGUOUS_ZONES: &[&str] = &["CST", "IST", "AST"];
+
+/// Parses a date of the config file or command line. Besides the formats supported by
+/// `dateparser` (dates without a time zone use the local time zone), it accepts:
+///   - a time zone abbreviation after the date, e.g. "2024-07-01 18:00 CET";
+///   - a date relative to now, e.g. "now", "in 2h" or "in 1d 12h".
+pub fn parse_date(value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Some(offset) = parse_relative(value)? {
+        return Ok(Utc::now() + offset);
+    }
+
+    if let Some((date, zone)) = value.rsplit_once(' ') {
+        if AMBIGUOUS_ZONES.contains(&zone.to_uppercase().as_str()) {
+            return Err(anyhow!(
+                "Ambiguous time zone '{}' in date '{}', use a UTC offset instead (e.g. \
+                '2024-07-01T18:00:00-06:00')",
+                zone,
+                value
+            ));
+        }
+
+        if let Some(offset) = zone_offset(zone) {
+            return dateparser::parse_with_timezone(date.trim(), &offset)
+                .map_err(|e| anyhow!("Invalid date '{}': {}", value, e));
+        }
+    }
+
+    dateparser::parse(value).map_err(|e| anyhow!("Invalid date '{}': {}", value, e))
+}
+
+/// Whether the date is relative to now ("in 2h"), changing each time it is parsed.
+pub fn is_relative_date(value: &str) -> bool {
+    matches!(parse_relative(value.trim()), Ok(Some(_)))
+}
+
+/// Parses a date relative to now ("now", "in 2h", "+30m"), returning `None` when the
+/// value is not a relative date.
+fn parse_relative(value: &str) -> Result<Option<Duration>> {
+    let lowercase = value.to_lowercase();
+
+    if lowercase == "now" {
+        return Ok(Some(Duration::zero()));
+    }
+
+    let relative = match lowercase
+        .strip_prefix("in ")
+        .or_else(|| lowercase.strip_prefix('+'))
+    {
+        Some(relative) => relative.replace(' ', ""),
+        None => return Ok(None),
+    };
+
+    if relative.is_empty() {
+        return Err(anyhow!("Invalid relative date '{}'", value));
+    }
+
+    let mut total = Duration::zero();
+    let mut rest = relative.as_str();
+
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let letters = rest[digits..]
+            .find(|c: char| c.is_ascii_digit())
+            .map_or(rest.len(), |end| digits + end);
+
+        let amount: i64 = rest[..digits]
+            .parse()
+            .map_err(|_| anyhow!("Invalid relative date '{}'", value))?;
+
+        total = total
+            + match &rest[digits..letters] {
+                "s" | "sec" | "secs" | "second" | "seconds" => Duration::seconds(amount),
+                "m" | "min" | "mins" | "minute" | "minutes" => Duration::minutes(amount),
+                "h" | "hr" | "hrs" | "hour" | "hours" => Duration::hours(amount),
+                "d" | "day" | "days" => Duration::days(amount),
+                "w" | "week" | "weeks" => Duration::weeks(amount),
+                unit => {
+                    return Err(anyhow!(
+                        "Invalid unit '{}' in relative date '{}'",
+                        unit,
+                        value
+                    ))
+                }
+            };
+
+        rest = &rest[letters..];
+    }
+
+    Ok(Some(total))
+}
+
+/// Returns the offset of a time zone abbreviation (e.g. "CET").
+fn zone_offset(zone: &str) -> Option<FixedOffset> {
+    let zone = zone.to_uppercase();
+
+    ZONE_ABBREVIATIONS
+        .iter()
+        .find(|(abbreviation, _)| *abbreviation == zone)
+        .and_then(|(_, hours)| FixedOffset::east_opt((hours * 3600.0) as i32))
+}
+
+/// Formats a timestamp in UTC and in the local time zone.
+pub fn format_timestamp(timestamp: i64) -> String {
+    match Utc.timestamp_opt(timestamp, 0).single() {
+        Some(date) => format!(
+            "{} ({} local)",
+            date.format("%a %B %e %Y %H:%M:%S UTC"),
+            date.with_timezone(&Local)
+                .format("%a %B %e %Y %H:%M:%S %:z")
+        ),
+        None => "<parse error>".to_string(),
+    }
+}
+
+/// Formats a timestamp as a date accepted by the guard config (RFC 3339, in UTC).
+pub fn timestamp_as_date(timestamp: i64) -> String {
+    match Utc.timestamp_opt(timestamp, 0).single() {
+        Some(date) => date.to_rfc3339(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// Prints the start and end dates of the guard sets, warning when a start date is
+/// already in the past (the guard would not restrict minting).
+pub fn print_guard_dates(data: &CandyGuardData) {
+    print_guard_set_dates("default", &data.default);
+
+    if let Some(groups) = &data.groups {
+        for group in groups {
+            print_guard_set_dates(&group.label, &group.guards);
+        }
+    }
+}
+
+fn print_guard_set_dates(label: &str, guard_set: &GuardSet) {
+    if let Some(StartDate { date }) = guard_set.start_date {
+        println!(
+            "{} {}",
+            style(format!("Start date ({label}):")).bold(),
+            format_timestamp(date)
+        );
+
+        if date < Utc::now().timestamp() {
+            println!(
+                "{}",
+                style(format!(
+                    "Warning: the start date of '{label}' is in the past, minting is already open"
+                ))
+                .yellow()
+                .bold()
+            );
+        }
+    }
+
+    if let Some(EndDate { date }) = guard_set.end_date {
+        println!(
+            "{} {}",
+            style(format!("End date ({label}):")).bold(),
+            format_timestamp(date)
+        );
+    }
+}
+
+#[test]
+fn test_parse_dates() {
+    let date = parse_date("2024-07-01 18:00 CET").unwrap();
+    assert_eq!(date.to_rfc3339(), "2024-07-01T17:00:00+00:00");
+
+    let date = parse_date("2024-07-01T18:00:00Z").unwrap();
+    assert_eq!(date.to_rfc3339(), "2024-07-01T18:00:00+00:00");
+
+    assert_eq!(parse_relative("in 2h").unwrap(), Some(Duration::hours(2)));
+    assert_eq!(
+        parse_relative("in 1d 12h").unwrap(),
+        Some(Duration::hours(36))
+    );
+    assert_eq!(parse_relative("+30m").unwrap(), Some(Duration::minutes(30)));
+    assert_eq!(parse_relative("2024-07-01").unwrap(), None);
+    assert!(parse_relative("in 2 fortnights").is_err());
+
+    assert!(is_relative_date("in 2h"));
+    assert!(!is_relative_date("2024-07-01 18:00 CET"));
+    assert!(parse_date("2024-07-01 18:00 CST").is_err());
+    assert!(parse_date("2024-07-01 18:00 ist").is_err());
+}
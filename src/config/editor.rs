@@ -0,0 +1,499 @@
+use std::fs;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use crate::{config::ConfigData, lock::lock_file};
+
+pub struct ConfigGetArgs {
+    pub config: String,
+    pub path: String,
+}
+
+pub struct ConfigSetArgs {
+    pub config: String,
+    pub path: String,
+    pub value: String,
+}
+
+/// Prints the value of a field of the config file: strings and numbers are printed as
+/// they are, objects and arrays as JSON.
+pub fn process_config_get(args: ConfigGetArgs) -> Result<()> {
+    let text = read_config(&args.config)?;
+    let value = get_path(&text, &args.path)?;
+
+    match value {
+        Value::String(value) => println!("{value}"),
+        Value::Object(_) | Value::Array(_) => println!("{}", serde_json::to_string_pretty(&value)?),
+        _ => println!("{value}"),
+    }
+
+    Ok(())
+}
+
+/// Sets the value of a field of the config file, keeping the formatting of the rest of
+/// the file. The value is parsed as JSON, falling back to a string.
+pub fn process_config_set(args: ConfigSetArgs) -> Result<()> {
+    lock_file(&args.config)?;
+    let text = read_config(&args.config)?;
+    let value = serde_json::from_str(&args.value).unwrap_or(Value::String(args.value));
+    let updated = set_path(&text, &args.path, &value)?;
+
+    // the file is only written if it is still a valid config
+    serde_json::from_str::<ConfigData>(&updated)
+        .map_err(|e| anyhow!("Invalid value for '{}': {}", args.path, e))?;
+
+    fs::write(&args.config, updated)
+        .map_err(|e| anyhow!("Failed to write '{}': {}", args.config, e))?;
+
+    Ok(())
+}
+
+fn read_config(config: &str) -> Result<String> {
+    fs::read_to_string(config).map_err(|e| anyhow!("Failed to read '{}': {}", config, e))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Parses a dot path such as "guards.groups[0].guards.solPayment.value" (array indices
+/// can also be written as "groups.0").
+fn parse_path(path: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+
+    for part in path.split('.') {
+        let (key, indices) = match part.find('[') {
+            Some(start) => part.split_at(start),
+            None => (part, ""),
+        };
+
+        if !key.is_empty() {
+            segments.push(match key.parse() {
+                Ok(index) => Segment::Index(index),
+                Err(_) => Segment::Key(key.to_string()),
+            });
+        } else if indices.is_empty() {
+            return Err(anyhow!("Invalid path '{}': empty field name", path));
+        }
+
+        for index in indices.split_terminator(']') {
+            let index = index
+                .strip_prefix('[')
+                .and_then(|index| index.parse().ok())
+                .ok_or_else(|| anyhow!("Invalid path '{}': bad array index", path))?;
+            segments.push(Segment::Index(index));
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Returns the value at the path of a JSON document.
+pub fn get_path(text: &str, path: &str) -> Result<Value> {
+    let root: Value = serde_json::from_str(text)?;
+    let mut value = &root;
+
+    for segment in parse_path(path)? {
+        value = match (&segment, value) {
+            (Segment::Key(key), Value::Object(map)) => map.get(key),
+            (Segment::Index(index), Value::Array(items)) => items.get(*index),
+            (Segment::Index(index), Value::Object(map)) => map.get(&index.to_string()),
+            _ => None,
+        }
+        .ok_or_else(|| anyhow!("Field '{}' not found in the config.", path))?;
+    }
+
+    Ok(value.clone())
+}
+
+/// Location of the value to set in the document.
+#[derive(Debug, PartialEq, Eq)]
+enum Target {
+    /// Replace the bytes of an existing value; the new value is nested in objects with
+    /// the `wrap` keys (when the path goes through a null value).
+    Replace {
+        start: usize,
+        end: usize,
+        wrap: Vec<String>,
+    },
+    /// Add a member to an object, after `at` (the end of the last member or the opening
+    /// brace of an empty object).
+    Insert {
+        at: usize,
+        empty: bool,
+        close: usize,
+        key: String,
+        wrap: Vec<String>,
+    },
+}
+
+/// Sets the value at the path of a JSON document, only rewriting the bytes of that value
+/// so the formatting and the order of the other fields are preserved.
+pub fn set_path(text: &str, path: &str, value: &Value) -> Result<String> {
+    let segments = parse_path(path)?;
+    // validates the document before scanning it
+    serde_json::from_str::<Value>(text)?;
+
+    let mut scanner = Scanner {
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    let target = scanner.find(&segments)?;
+    let unit = indent_unit(text);
+
+    let nest = |wrap: &[String]| {
+        wrap.iter().rev().fold(
+            value.clone(),
+            |value, key| serde_json::json!({ key: value }),
+        )
+    };
+
+    Ok(match target {
+        Target::Replace { start, end, wrap } => {
+            let indent = line_indent(text, start);
+            format!(
+                "{}{}{}",
+                &text[..start],
+                format_value(&nest(&wrap), &indent, &unit)?,
+                &text[end..]
+            )
+        }
+        Target::Insert {
+            at,
+            empty,
+            close,
+            key,
+            wrap,
+        } => {
+            let parent_indent = line_indent(text, close);
+            let indent = format!("{parent_indent}{unit}");
+            let member = format!(
+                "{}: {}",
+                serde_json::to_string(&key)?,
+                format_value(&nest(&wrap), &indent, &unit)?
+            );
+
+            if empty {
+                format!(
+                    "{}\n{indent}{member}\n{parent_indent}{}",
+                    &text[..at],
+                    &text[close..]
+                )
+            } else {
+                format!("{},\n{indent}{member}{}", &text[..at], &text[at..])
+            }
+        }
+    })
+}
+
+/// Serializes a value, indenting the lines of objects and arrays to the given level.
+fn format_value(value: &Value, indent: &str, unit: &str) -> Result<String> {
+    let pretty = serde_json::to_string_pretty(value)?;
+
+    Ok(pretty
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            // serde_json indents with two spaces
+            let depth = (line.len() - line.trim_start().len()) / 2;
+            let line = line.trim_start();
+
+            if index == 0 {
+                line.to_string()
+            } else {
+                format!("{indent}{}{line}", unit.repeat(depth))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Whitespace at the start of the line containing the position.
+fn line_indent(text: &str, pos: usize) -> String {
+    let line_start = text[..pos].rfind('\n').map_or(0, |index| index + 1);
+    text[line_start..]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect()
+}
+
+/// Indentation used by the document, defaults to two spaces.
+fn indent_unit(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            line.chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect::<String>()
+        })
+        .find(|indent| !indent.is_empty())
+        .unwrap_or_else(|| "  ".to_string())
+}
+
+/// Byte scanner of a (valid) JSON document.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .map_or(false, |byte| byte.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(anyhow!("Invalid JSON at byte {}", self.pos))
+        }
+    }
+
+    /// Reads a string, returning its decoded content.
+    fn string(&mut self) -> Result<String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        self.expect(b'"')?;
+
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            self.pos += 1;
+
+            match byte {
+                b'\\' => self.pos += 1,
+                b'"' => {
+                    let raw = std::str::from_utf8(&self.bytes[start..self.pos])?;
+                    return Ok(serde_json::from_str(raw)?);
+                }
+                _ => (),
+            }
+        }
+
+        Err(anyhow!("Unterminated string at byte {}", start))
+    }
+
+    /// Skips a value, returning its start and end positions.
+    fn skip_value(&mut self) -> Result<(usize, usize)> {
+        self.skip_whitespace();
+        let start = self.pos;
+
+        match self.peek() {
+            Some(b'"') => {
+                self.string()?;
+            }
+            Some(open @ (b'{' | b'[')) => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+
+                if self.peek() == Some(close) {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        if open == b'{' {
+                            self.string()?;
+                            self.expect(b':')?;
+                        }
+                        self.skip_value()?;
+
+                        match self.peek() {
+                            Some(b',') => self.pos += 1,
+                            Some(byte) if byte == close => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err(anyhow!("Invalid JSON at byte {}", self.pos)),
+                        }
+                    }
+                }
+            }
+            Some(_) => {
+                // number, true, false or null
+                while self.bytes.get(self.pos).map_or(false, |&byte| {
+                    !byte.is_ascii_whitespace() && !matches!(byte, b',' | b'}' | b']')
+                }) {
+                    self.pos += 1;
+                }
+            }
+            None => return Err(anyhow!("Unexpected end of JSON")),
+        }
+
+        Ok((start, self.pos))
+    }
+
+    /// Finds the location of the value at the path, starting at the current value.
+    fn find(&mut self, path: &[Segment]) -> Result<Target> {
+        let segment = match path.first() {
+            Some(segment) => segment,
+            None => {
+                let (start, end) = self.skip_value()?;
+                return Ok(Target::Replace {
+                    start,
+                    end,
+                    wrap: Vec::new(),
+                });
+            }
+        };
+
+        match (segment, self.peek()) {
+            (Segment::Key(_), Some(b'{')) => self.find_member(path),
+            (Segment::Index(index), Some(b'{')) => {
+                let mut path = path.to_vec();
+                path[0] = Segment::Key(index.to_string());
+                self.find_member(&path)
+            }
+            (Segment::Index(index), Some(b'[')) => self.find_element(*index, &path[1..]),
+            (Segment::Key(_), Some(b'n')) => {
+                // a null value is replaced by the objects of the path
+                let (start, end) = self.skip_value()?;
+                Ok(Target::Replace {
+                    start,
+                    end,
+                    wrap: wrap_keys(path)?,
+                })
+            }
+            _ => Err(anyhow!(
+                "Invalid path: the value at byte {} is not an object or array",
+                self.pos
+            )),
+        }
+    }
+
+    fn find_member(&mut self, path: &[Segment]) -> Result<Target> {
+        let key = match &path[0] {
+            Segment::Key(key) => key,
+            Segment::Index(_) => unreachable!("indices of objects are converted to keys"),
+        };
+
+        let open = self.pos;
+        self.expect(b'{')?;
+        let mut last_end = None;
+
+        loop {
+            if self.peek() == Some(b'}') {
+                return Ok(Target::Insert {
+                    at: last_end.unwrap_or(open + 1),
+                    empty: last_end.is_none(),
+                    close: self.pos,
+                    key: key.clone(),
+                    wrap: wrap_keys(&path[1..])?,
+                });
+            }
+
+            if self.string()? == *key {
+                self.expect(b':')?;
+                return self.find(&path[1..]);
+            }
+
+            self.expect(b':')?;
+            last_end = Some(self.skip_value()?.1);
+
+            if self.peek() == Some(b',') {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn find_element(&mut self, index: usize, path: &[Segment]) -> Result<Target> {
+        self.expect(b'[')?;
+        let mut current = 0;
+
+        loop {
+            if self.peek() == Some(b']') {
+                return Err(anyhow!(
+                    "Invalid path: index {} is out of bounds (length {})",
+                    index,
+                    current
+                ));
+            }
+
+            if current == index {
+                return self.find(path);
+            }
+
+            self.skip_value()?;
+            current += 1;
+
+            if self.peek() == Some(b',') {
+                self.pos += 1;
+            }
+        }
+    }
+}
+
+/// Keys of the objects created for the rest of a path that does not exist yet.
+fn wrap_keys(path: &[Segment]) -> Result<Vec<String>> {
+    path.iter()
+        .map(|segment| match segment {
+            Segment::Key(key) => Ok(key.clone()),
+            Segment::Index(index) => Err(anyhow!(
+                "Invalid path: index {} of an array that does not exist",
+                index
+            )),
+        })
+        .collect()
+}
+
+#[test]
+fn test_config_paths() {
+    let text = r#"{
+    "number": 10,
+    "symbol": "TEST",
+    "guards": {
+        "default": {
+            "solPayment": { "value": 1, "destination": "abc" }
+        },
+        "groups": [
+            { "label": "OG", "guards": {} }
+        ]
+    }
+}"#;
+
+    assert_eq!(
+        get_path(text, "guards.default.solPayment.value").unwrap(),
+        serde_json::json!(1)
+    );
+    assert_eq!(
+        get_path(text, "guards.groups[0].label").unwrap(),
+        serde_json::json!("OG")
+    );
+
+    // only the value changes
+    let updated = set_path(
+        text,
+        "guards.default.solPayment.value",
+        &serde_json::json!(1.5),
+    )
+    .unwrap();
+    assert_eq!(updated, text.replace("\"value\": 1,", "\"value\": 1.5,"));
+
+    // new members are added at the end of the object
+    let updated = set_path(text, "isMutable", &serde_json::json!(false)).unwrap();
+    assert!(updated.ends_with("    },\n    \"isMutable\": false\n}"));
+
+    let updated = set_path(
+        text,
+        "guards.groups.0.guards.botTax",
+        &serde_json::json!({ "value": 0.01, "lastInstruction": true }),
+    )
+    .unwrap();
+    let value: Value = serde_json::from_str(&updated).unwrap();
+    assert_eq!(
+        value["guards"]["groups"][0]["guards"]["botTax"]["value"],
+        serde_json::json!(0.01)
+    );
+
+    assert!(set_path(text, "guards.groups[3].label", &serde_json::json!("x")).is_err());
+}
@@ -22,4 +22,7 @@ pub enum ConfigError {
 
     #[error("Invalid token standard '{0}'")]
     InvalidTokenStandard(String),
+
+    #[error("Could not load the credentials ({0})")]
+    CredentialsError(String),
 }
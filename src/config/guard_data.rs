@@ -1,10 +1,13 @@
 use anchor_lang::prelude::Pubkey;
 use anyhow::{anyhow, Result};
-use dateparser::DateTimeUtc;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 
-use super::{data::price_as_lamports, to_pubkey, to_string};
+use super::{
+    data::{lamports_as_price, price_as_lamports},
+    date::{is_relative_date, parse_date, timestamp_as_date},
+    to_pubkey, to_string,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct CandyGuardData {
@@ -31,6 +34,35 @@ impl CandyGuardData {
             groups,
         })
     }
+
+    /// Replaces the relative start and end dates ("in 2h") with the dates they resolve to
+    /// now, returning the path (e.g. "default.startDate.date") and value of each one.
+    pub fn resolve_relative_dates(&mut self) -> Result<Vec<(String, String)>> {
+        let mut resolved = Vec::new();
+        resolved.extend(self.default.resolve_relative_dates("default")?);
+
+        if let Some(groups) = &mut self.groups {
+            for (index, group) in groups.iter_mut().enumerate() {
+                resolved.extend(
+                    group
+                        .guards
+                        .resolve_relative_dates(&format!("groups[{index}].guards"))?,
+                );
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    pub fn from_guard_format(data: &mpl_candy_guard::state::CandyGuardData) -> Self {
+        Self {
+            default: GuardSet::from_guard_format(&data.default),
+            groups: data
+                .groups
+                .as_ref()
+                .map(|groups| groups.iter().map(Group::from_guard_format).collect()),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -46,6 +78,13 @@ impl Group {
             guards: self.guards.to_guard_format()?,
         })
     }
+
+    pub fn from_guard_format(group: &mpl_candy_guard::state::Group) -> Self {
+        Self {
+            label: group.label.clone(),
+            guards: GuardSet::from_guard_format(&group.guards),
+        }
+    }
 }
 
 /// The set of guards available.
@@ -97,6 +136,26 @@ pub struct GuardSet {
 }
 
 impl GuardSet {
+    fn resolve_relative_dates(&mut self, path: &str) -> Result<Vec<(String, String)>> {
+        let mut resolved = Vec::new();
+
+        if let Some(start_date) = &mut self.start_date {
+            if is_relative_date(&start_date.date) {
+                start_date.date = timestamp_as_date(parse_date(&start_date.date)?.timestamp());
+                resolved.push((format!("{path}.startDate.date"), start_date.date.clone()));
+            }
+        }
+
+        if let Some(end_date) = &mut self.end_date {
+            if is_relative_date(&end_date.date) {
+                end_date.date = timestamp_as_date(parse_date(&end_date.date)?.timestamp());
+                resolved.push((format!("{path}.endDate.date"), end_date.date.clone()));
+            }
+        }
+
+        Ok(resolved)
+    }
+
     pub fn to_guard_format(&self) -> Result<mpl_candy_guard::guards::GuardSet> {
         // bot tax
         let bot_tax = if let Some(bot_tax) = &self.bot_tax {
@@ -249,6 +308,83 @@ impl GuardSet {
             token2022_payment,
         })
     }
+
+    pub fn from_guard_format(guard_set: &mpl_candy_guard::guards::GuardSet) -> Self {
+        Self {
+            bot_tax: guard_set.bot_tax.as_ref().map(BotTax::from_guard_format),
+            sol_payment: guard_set
+                .sol_payment
+                .as_ref()
+                .map(SolPayment::from_guard_format),
+            token_payment: guard_set
+                .token_payment
+                .as_ref()
+                .map(TokenPayment::from_guard_format),
+            start_date: guard_set
+                .start_date
+                .as_ref()
+                .map(StartDate::from_guard_format),
+            third_party_signer: guard_set
+                .third_party_signer
+                .as_ref()
+                .map(ThirdPartySigner::from_guard_format),
+            token_gate: guard_set
+                .token_gate
+                .as_ref()
+                .map(TokenGate::from_guard_format),
+            gatekeeper: guard_set
+                .gatekeeper
+                .as_ref()
+                .map(Gatekeeper::from_guard_format),
+            end_date: guard_set.end_date.as_ref().map(EndDate::from_guard_format),
+            allow_list: guard_set
+                .allow_list
+                .as_ref()
+                .map(AllowList::from_guard_format),
+            mint_limit: guard_set
+                .mint_limit
+                .as_ref()
+                .map(MintLimit::from_guard_format),
+            nft_payment: guard_set
+                .nft_payment
+                .as_ref()
+                .map(NftPayment::from_guard_format),
+            redeemed_amount: guard_set
+                .redeemed_amount
+                .as_ref()
+                .map(RedeemedAmount::from_guard_format),
+            address_gate: guard_set
+                .address_gate
+                .as_ref()
+                .map(AddressGate::from_guard_format),
+            nft_gate: guard_set.nft_gate.as_ref().map(NftGate::from_guard_format),
+            nft_burn: guard_set.nft_burn.as_ref().map(NftBurn::from_guard_format),
+            token_burn: guard_set
+                .token_burn
+                .as_ref()
+                .map(TokenBurn::from_guard_format),
+            freeze_sol_payment: guard_set
+                .freeze_sol_payment
+                .as_ref()
+                .map(FreezeSolPayment::from_guard_format),
+            freeze_token_payment: guard_set
+                .freeze_token_payment
+                .as_ref()
+                .map(FreezeTokenPayment::from_guard_format),
+            program_gate: guard_set
+                .program_gate
+                .as_ref()
+                .map(ProgramGate::from_guard_format),
+            allocation: guard_set
+                .allocation
+                .as_ref()
+                .map(Allocation::from_guard_format),
+            token2022_payment: guard_set
+                .token2022_payment
+                .as_ref()
+                .map(Token2022Payment::from_guard_format),
+        }
+    }
 }
 
 // Address guard
@@ -266,6 +402,12 @@ impl AddressGate {
             address: self.address,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::AddressGate) -> Self {
+        Self {
+            address: guard.address,
+        }
+    }
 }
 
 // Alow List guard
@@ -283,6 +425,12 @@ impl AllowList {
             .map_err(|_| anyhow!("Invalid merkle root value: {}", self.merkle_root))?;
         Ok(mpl_candy_guard::guards::AllowList { merkle_root: root })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::AllowList) -> Self {
+        Self {
+            merkle_root: hex::encode(guard.merkle_root),
+        }
+    }
 }
 
 // Bot Tax guard
@@ -302,6 +450,13 @@ impl BotTax {
             last_instruction: self.last_instruction,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::BotTax) -> Self {
+        Self {
+            value: lamports_as_price(guard.lamports),
+            last_instruction: guard.last_instruction,
+        }
+    }
 }
 
 // End Date guard
@@ -313,10 +468,16 @@ pub struct EndDate {
 
 impl EndDate {
     pub fn to_guard_format(&self) -> Result<mpl_candy_guard::guards::EndDate> {
-        let timestamp = self.date.parse::<DateTimeUtc>()?.0.timestamp();
+        let timestamp = parse_date(&self.date)?.timestamp();
 
         Ok(mpl_candy_guard::guards::EndDate { date: timestamp })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::EndDate) -> Self {
+        Self {
+            date: timestamp_as_date(guard.date),
+        }
+    }
 }
 
 // Gatekeeper guard
@@ -338,6 +499,13 @@ impl Gatekeeper {
             expire_on_use: self.expire_on_use,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::Gatekeeper) -> Self {
+        Self {
+            gatekeeper_network: guard.gatekeeper_network,
+            expire_on_use: guard.expire_on_use,
+        }
+    }
 }
 
 // Mint Limit guard
@@ -356,6 +524,13 @@ impl MintLimit {
             limit: self.limit,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::MintLimit) -> Self {
+        Self {
+            id: guard.id,
+            limit: guard.limit,
+        }
+    }
 }
 
 // Nft Burn guard
@@ -374,6 +549,12 @@ impl NftBurn {
             required_collection: self.required_collection,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::NftBurn) -> Self {
+        Self {
+            required_collection: guard.required_collection,
+        }
+    }
 }
 
 // Nft Gate guard
@@ -392,6 +573,12 @@ impl NftGate {
             required_collection: self.required_collection,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::NftGate) -> Self {
+        Self {
+            required_collection: guard.required_collection,
+        }
+    }
 }
 
 // Nft Payment guard
@@ -415,6 +602,13 @@ impl NftPayment {
             destination: self.destination,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::NftPayment) -> Self {
+        Self {
+            required_collection: guard.required_collection,
+            destination: guard.destination,
+        }
+    }
 }
 
 // Redeemed Amount guard
@@ -430,6 +624,12 @@ impl RedeemedAmount {
             maximum: self.maximum,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::RedeemedAmount) -> Self {
+        Self {
+            maximum: guard.maximum,
+        }
+    }
 }
 
 // Sol Payment guard
@@ -450,6 +650,13 @@ impl SolPayment {
             destination: self.destination,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::SolPayment) -> Self {
+        Self {
+            value: lamports_as_price(guard.lamports),
+            destination: guard.destination,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -459,9 +666,15 @@ pub struct StartDate {
 
 impl StartDate {
     pub fn to_guard_format(&self) -> Result<mpl_candy_guard::guards::StartDate> {
-        let timestamp = self.date.parse::<DateTimeUtc>()?.0.timestamp();
+        let timestamp = parse_date(&self.date)?.timestamp();
         Ok(mpl_candy_guard::guards::StartDate { date: timestamp })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::StartDate) -> Self {
+        Self {
+            date: timestamp_as_date(guard.date),
+        }
+    }
 }
 
 // Third Party Signer guard
@@ -480,6 +693,12 @@ impl ThirdPartySigner {
             signer_key: self.signer_key,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::ThirdPartySigner) -> Self {
+        Self {
+            signer_key: guard.signer_key,
+        }
+    }
 }
 
 // Token Burn guard
@@ -500,6 +719,13 @@ impl TokenBurn {
             mint: self.mint,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::TokenBurn) -> Self {
+        Self {
+            amount: guard.amount,
+            mint: guard.mint,
+        }
+    }
 }
 
 // Token Gate guard
@@ -520,6 +746,13 @@ impl TokenGate {
             mint: self.mint,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::TokenGate) -> Self {
+        Self {
+            amount: guard.amount,
+            mint: guard.mint,
+        }
+    }
 }
 
 // Token Payment guard
@@ -546,6 +779,14 @@ impl TokenPayment {
             destination_ata: self.destination_ata,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::TokenPayment) -> Self {
+        Self {
+            amount: guard.amount,
+            mint: guard.mint,
+            destination_ata: guard.destination_ata,
+        }
+    }
 }
 
 // Freeze Sol Payment guard
@@ -567,6 +808,13 @@ impl FreezeSolPayment {
             destination: self.destination,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::FreezeSolPayment) -> Self {
+        Self {
+            value: lamports_as_price(guard.lamports),
+            destination: guard.destination,
+        }
+    }
 }
 
 // Freeze Token Payment guard
@@ -593,6 +841,14 @@ impl FreezeTokenPayment {
             destination_ata: self.destination_ata,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::FreezeTokenPayment) -> Self {
+        Self {
+            amount: guard.amount,
+            mint: guard.mint,
+            destination_ata: guard.destination_ata,
+        }
+    }
 }
 
 // ProgramGate
@@ -611,6 +867,12 @@ impl ProgramGate {
             additional: self.additional.clone(),
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::ProgramGate) -> Self {
+        Self {
+            additional: guard.additional.clone(),
+        }
+    }
 }
 
 // Allocation
@@ -629,6 +891,13 @@ impl Allocation {
             limit: self.limit,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::Allocation) -> Self {
+        Self {
+            id: guard.id,
+            limit: guard.limit,
+        }
+    }
 }
 
 // Token2022 Payment guard
@@ -655,4 +924,12 @@ impl Token2022Payment {
             destination_ata: self.destination_ata,
         })
     }
+
+    pub fn from_guard_format(guard: &mpl_candy_guard::guards::Token2022Payment) -> Self {
+        Self {
+            amount: guard.amount,
+            mint: guard.mint,
+            destination_ata: guard.destination_ata,
+        }
+    }
 }
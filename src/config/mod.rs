@@ -1,15 +1,23 @@
+pub mod credentials;
 pub mod data;
+pub mod date;
+pub mod editor;
 pub mod errors;
 pub mod guard_data;
 pub mod parser;
+pub mod profile;
 
 use std::{fmt::Display, str::FromStr};
 
 use anchor_lang::prelude::Pubkey;
+pub use credentials::*;
 pub use data::*;
+pub use date::*;
+pub use editor::*;
 pub use errors::*;
 pub use guard_data::*;
 pub use parser::*;
+pub use profile::*;
 use serde::{Deserialize, Deserializer, Serializer};
 use solana_program::native_token::LAMPORTS_PER_SOL;
 
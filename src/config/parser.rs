@@ -6,7 +6,9 @@ use std::{
 use anyhow::Result;
 use tracing::error;
 
-use crate::config::{data::*, errors::ConfigError};
+use crate::config::{
+    credentials::apply_credentials, data::*, errors::ConfigError, profile::apply_active_profile,
+};
 
 pub fn get_config_data(config_path: &str) -> Result<ConfigData, ConfigError> {
     // checks that the config file exists and it is readable
@@ -29,7 +31,24 @@ pub fn get_config_data(config_path: &str) -> Result<ConfigData, ConfigError> {
         return Err(error);
     }
 
-    let config_data: ConfigData = match serde_json::from_reader(f) {
+    let mut config: serde_json::Value = match serde_json::from_reader(f) {
+        Ok(config) => config,
+        Err(err) => {
+            let error = ConfigError::ParseError(err.to_string());
+            error!("{:?}", error);
+            return Err(error);
+        }
+    };
+
+    // values from the selected profile take precedence
+    apply_active_profile(&mut config);
+    // the profile can select the credentials as well
+    if let Err(error) = apply_credentials(&mut config) {
+        error!("{:?}", error);
+        return Err(error);
+    }
+
+    let config_data: ConfigData = match serde_json::from_value(config) {
         Ok(config_data) => config_data,
         Err(err) => {
             let error = ConfigError::ParseError(err.to_string());
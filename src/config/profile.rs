@@ -0,0 +1,144 @@
+use std::{fs::File, sync::RwLock};
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use serde_json::{Map, Value};
+
+use crate::config::errors::ConfigError;
+
+/// Name of the config section holding the profiles.
+pub const PROFILES_KEY: &str = "profiles";
+
+/// Name of the built-in profile pointing to a local validator.
+pub const LOCALNET_PROFILE: &str = "localnet";
+
+/// RPC url of a local validator (`solana-test-validator`).
+pub const LOCALNET_RPC_URL: &str = "http://127.0.0.1:8899";
+
+/// Profile keys used to set up the client instead of overriding config values.
+const RPC_URL_KEY: &str = "rpcUrl";
+const KEYPAIR_KEY: &str = "keypair";
+
+lazy_static! {
+    static ref ACTIVE_PROFILE: RwLock<Option<ConfigProfile>> = RwLock::new(None);
+}
+
+/// Per-environment settings selected with the `--profile` option.
+///
+/// A profile can specify the `rpcUrl` and `keypair` to use, and any other config
+/// value (e.g., `uploadMethod` or `guards`), which overrides the value at the top
+/// level of the config file. Object values are merged, so it is possible to override
+/// a single guard setting.
+#[derive(Debug, Clone)]
+pub struct ConfigProfile {
+    pub name: String,
+    pub rpc_url: Option<String>,
+    pub keypair: Option<String>,
+    pub overrides: Map<String, Value>,
+}
+
+/// Loads the profile `name` from the config file and makes it the active profile.
+///
+/// The `localnet` profile is always available: when it is not defined in the config
+/// file (or it does not specify an `rpcUrl`), it points to a local validator.
+pub fn select_profile(name: &str, config_path: &str) -> Result<()> {
+    let localnet = name == LOCALNET_PROFILE;
+    let config: Value = match File::open(config_path) {
+        Ok(f) => serde_json::from_reader(f).map_err(|e| ConfigError::ParseError(e.to_string()))?,
+        Err(_) if localnet => Value::Null,
+        Err(_) => return Err(ConfigError::MissingFileError(config_path.to_string()).into()),
+    };
+
+    let mut overrides = match config.get(PROFILES_KEY).and_then(|p| p.get(name)) {
+        Some(Value::Object(profile)) => profile.clone(),
+        Some(_) => return Err(anyhow!("Profile '{name}' must be a JSON object")),
+        None if localnet => Map::new(),
+        None => {
+            return Err(anyhow!(
+                "Profile '{name}' not found in the '{PROFILES_KEY}' section of '{config_path}'"
+            ))
+        }
+    };
+
+    let as_string = |value: Option<Value>| -> Result<Option<String>> {
+        match value {
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(_) => Err(anyhow!(
+                "Invalid value in profile '{name}': expected a string"
+            )),
+            None => Ok(None),
+        }
+    };
+
+    let profile = ConfigProfile {
+        name: name.to_string(),
+        rpc_url: as_string(overrides.remove(RPC_URL_KEY))?
+            .or_else(|| localnet.then(|| LOCALNET_RPC_URL.to_string())),
+        keypair: as_string(overrides.remove(KEYPAIR_KEY))?,
+        overrides,
+    };
+
+    *ACTIVE_PROFILE.write().unwrap() = Some(profile);
+
+    Ok(())
+}
+
+/// Returns the active profile, if any.
+pub fn active_profile() -> Option<ConfigProfile> {
+    ACTIVE_PROFILE.read().unwrap().clone()
+}
+
+/// Applies the overrides of the active profile (if any) to a raw config value.
+pub fn apply_active_profile(config: &mut Value) {
+    if let Some(profile) = active_profile() {
+        for (key, value) in profile.overrides {
+            match config.get_mut(&key) {
+                Some(current) => merge(current, value),
+                None => {
+                    if let Value::Object(map) = config {
+                        map.insert(key, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Merges `value` into `target`: objects are merged recursively, any other value
+/// replaces the target.
+fn merge(target: &mut Value, value: Value) {
+    match (target, value) {
+        (Value::Object(target), Value::Object(value)) => {
+            for (key, value) in value {
+                match target.get_mut(&key) {
+                    Some(current) => merge(current, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
+#[test]
+fn test_merge_profile_overrides() {
+    let mut config = serde_json::json!({
+        "uploadMethod": "bundlr",
+        "guards": { "default": { "solPayment": { "value": 1, "destination": "abc" } } }
+    });
+    let profile = serde_json::json!({
+        "uploadMethod": "aws",
+        "guards": { "default": { "solPayment": { "value": 2 } } }
+    });
+
+    merge(&mut config, profile);
+
+    assert_eq!(config["uploadMethod"], "aws");
+    assert_eq!(config["guards"]["default"]["solPayment"]["value"], 2);
+    assert_eq!(
+        config["guards"]["default"]["solPayment"]["destination"],
+        "abc"
+    );
+}
@@ -0,0 +1,327 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicU8, Ordering},
+    time::{Duration, Instant},
+};
+
+use anchor_client::solana_sdk::clock::MAX_PROCESSING_AGE;
+use solana_client::rpc_client::RpcClient;
+use solana_transaction_status::TransactionStatus;
+use tokio::{
+    sync::mpsc::{self, error::TryRecvError},
+    task::JoinHandle,
+};
+
+use crate::{common::*, rate_limit::throttled_client};
+
+/// Interval between the status checks of the tracked transactions.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Time after which a transaction without status is considered dropped when the block
+/// height cannot be retrieved: its blockhash has expired by then, so it can no longer land.
+const FINALIZE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Maximum number of times the transactions that are not finalized are sent again.
+pub const MAX_RESENDS: usize = 3;
+
+/// Maximum number of signatures per `getSignatureStatuses` request.
+const MAX_SIGNATURES: usize = 256;
+
+/// Commitment level used to send and confirm transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Commitment {
+    Processed,
+    #[default]
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn config(&self) -> CommitmentConfig {
+        match self {
+            Commitment::Processed => CommitmentConfig::processed(),
+            Commitment::Confirmed => CommitmentConfig::confirmed(),
+            Commitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+impl FromStr for Commitment {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "processed" => Ok(Commitment::Processed),
+            "confirmed" => Ok(Commitment::Confirmed),
+            "finalized" => Ok(Commitment::Finalized),
+            _ => Err(anyhow!(
+                "Invalid commitment '{s}': expected processed, confirmed or finalized"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Commitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Commitment::Processed => write!(f, "processed"),
+            Commitment::Confirmed => write!(f, "confirmed"),
+            Commitment::Finalized => write!(f, "finalized"),
+        }
+    }
+}
+
+static COMMITMENT: AtomicU8 = AtomicU8::new(Commitment::Confirmed as u8);
+
+/// Sets the commitment level from the `--commitment` global option.
+pub fn set_commitment(commitment: Option<Commitment>) {
+    COMMITMENT.store(commitment.unwrap_or_default() as u8, Ordering::SeqCst);
+}
+
+pub fn commitment() -> Commitment {
+    match COMMITMENT.load(Ordering::SeqCst) {
+        0 => Commitment::Processed,
+        2 => Commitment::Finalized,
+        _ => Commitment::Confirmed,
+    }
+}
+
+/// Outcome of the tracked transactions, identified by their keys.
+#[derive(Debug)]
+pub struct Confirmations<K> {
+    pub finalized: Vec<K>,
+    /// Transactions that failed or were dropped before being finalized.
+    pub failed: Vec<K>,
+}
+
+/// Tracks in the background the transactions sent with the processed commitment until
+/// they are finalized, so the sender does not wait for each one of them.
+pub struct ConfirmationTracker<K> {
+    sender: mpsc::UnboundedSender<(Signature, K)>,
+    handle: JoinHandle<Confirmations<K>>,
+}
+
+impl<K: Send + 'static> ConfirmationTracker<K> {
+    pub fn start(rpc_url: String) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let handle = tokio::spawn(track(throttled_client(&rpc_url), receiver));
+
+        ConfirmationTracker { sender, handle }
+    }
+
+    pub fn track(&self, signature: Signature, key: K) {
+        // the receiver is only dropped after finish
+        let _ = self.sender.send((signature, key));
+    }
+
+    /// Waits until every tracked transaction is finalized or dropped.
+    pub async fn finish(self) -> Result<Confirmations<K>> {
+        drop(self.sender);
+        Ok(self.handle.await?)
+    }
+}
+
+/// Stage of a tracked transaction.
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Finalized,
+    Failed,
+    Dropped,
+    Pending,
+}
+
+/// Returns the stage of a transaction from its status. Transactions that landed are
+/// waited for until they are finalized, while the ones without status are dropped once
+/// their blockhash expired (`last_valid_height` is the last block height at which it was
+/// valid); the timeout only applies when the block height is unknown.
+fn outcome(
+    status: Option<&TransactionStatus>,
+    last_valid_height: Option<u64>,
+    block_height: Option<u64>,
+    elapsed: Duration,
+) -> Outcome {
+    match status {
+        Some(status) if status.err.is_some() => Outcome::Failed,
+        Some(status) if status.satisfies_commitment(CommitmentConfig::finalized()) => {
+            Outcome::Finalized
+        }
+        Some(_) => Outcome::Pending,
+        None => {
+            let expired = match (last_valid_height, block_height) {
+                (Some(last_valid_height), Some(block_height)) => block_height > last_valid_height,
+                _ => elapsed > FINALIZE_TIMEOUT,
+            };
+
+            if expired {
+                Outcome::Dropped
+            } else {
+                Outcome::Pending
+            }
+        }
+    }
+}
+
+async fn track<K>(
+    rpc_client: RpcClient,
+    mut receiver: mpsc::UnboundedReceiver<(Signature, K)>,
+) -> Confirmations<K> {
+    let mut confirmations = Confirmations {
+        finalized: Vec::new(),
+        failed: Vec::new(),
+    };
+    let mut pending: Vec<(Signature, K, Instant, Option<u64>)> = Vec::new();
+    let mut open = true;
+
+    while open || !pending.is_empty() {
+        // the blockhash of a transaction was retrieved before it was sent, so it is no
+        // longer valid after MAX_PROCESSING_AGE blocks from now
+        let block_height = match rpc_client.get_block_height() {
+            Ok(block_height) => Some(block_height),
+            Err(err) => {
+                debug!("Failed to get the block height: {}", err);
+                None
+            }
+        };
+        let last_valid_height = block_height.map(|height| height + MAX_PROCESSING_AGE as u64);
+
+        loop {
+            match receiver.try_recv() {
+                Ok((signature, key)) => {
+                    pending.push((signature, key, Instant::now(), last_valid_height))
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    open = false;
+                    break;
+                }
+            }
+        }
+
+        let signatures: Vec<Signature> = pending.iter().map(|(signature, ..)| *signature).collect();
+        let mut statuses = Vec::with_capacity(signatures.len());
+
+        for chunk in signatures.chunks(MAX_SIGNATURES) {
+            match rpc_client.get_signature_statuses(chunk) {
+                Ok(response) => statuses.extend(response.value),
+                Err(err) => {
+                    // the statuses are requested again on the next check
+                    debug!("Failed to get the signature statuses: {}", err);
+                    break;
+                }
+            }
+        }
+
+        if statuses.len() == pending.len() {
+            let mut remaining = Vec::new();
+
+            for ((signature, key, sent, last_valid_height), status) in
+                pending.drain(..).zip(statuses)
+            {
+                match outcome(
+                    status.as_ref(),
+                    last_valid_height,
+                    block_height,
+                    sent.elapsed(),
+                ) {
+                    Outcome::Finalized => confirmations.finalized.push(key),
+                    Outcome::Failed => {
+                        warn!(
+                            "Transaction {} failed: {:?}",
+                            signature,
+                            status.and_then(|status| status.err)
+                        );
+                        confirmations.failed.push(key);
+                    }
+                    Outcome::Dropped => {
+                        warn!(
+                            "Transaction {} was dropped before being finalized",
+                            signature
+                        );
+                        confirmations.failed.push(key);
+                    }
+                    Outcome::Pending => remaining.push((signature, key, sent, last_valid_height)),
+                }
+            }
+
+            pending = remaining;
+        }
+
+        if open || !pending.is_empty() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    confirmations
+}
+
+#[test]
+fn test_commitment_levels() {
+    assert_eq!(
+        Commitment::from_str("Processed").unwrap(),
+        Commitment::Processed
+    );
+    assert!(Commitment::from_str("max").is_err());
+
+    for level in [
+        Commitment::Processed,
+        Commitment::Confirmed,
+        Commitment::Finalized,
+    ] {
+        set_commitment(Some(level));
+        assert_eq!(commitment(), level);
+        assert_eq!(Commitment::from_str(&level.to_string()).unwrap(), level);
+    }
+
+    set_commitment(None);
+    assert_eq!(commitment(), Commitment::Confirmed);
+}
+
+#[test]
+fn test_transaction_outcomes() {
+    use anchor_client::solana_sdk::transaction::TransactionError;
+
+    let status = |confirmations: Option<usize>, err: Option<TransactionError>| TransactionStatus {
+        slot: 1,
+        confirmations,
+        status: err.clone().map_or(Ok(()), Err),
+        err,
+        confirmation_status: None,
+    };
+    let long_ago = FINALIZE_TIMEOUT * 2;
+
+    let finalized = status(None, None);
+    assert_eq!(
+        outcome(Some(&finalized), None, None, Duration::ZERO),
+        Outcome::Finalized
+    );
+
+    let failed = status(None, Some(TransactionError::AccountNotFound));
+    assert_eq!(
+        outcome(Some(&failed), None, None, Duration::ZERO),
+        Outcome::Failed
+    );
+
+    // transactions that landed are not dropped, however long they take to finalize
+    let confirmed = status(Some(1), None);
+    assert_eq!(
+        outcome(Some(&confirmed), Some(100), Some(200), long_ago),
+        Outcome::Pending
+    );
+
+    // without status, only an expired blockhash drops the transaction
+    assert_eq!(
+        outcome(None, Some(100), Some(50), long_ago),
+        Outcome::Pending
+    );
+    assert_eq!(
+        outcome(None, Some(100), Some(101), Duration::ZERO),
+        Outcome::Dropped
+    );
+
+    // the timeout applies when the block height is unknown
+    assert_eq!(
+        outcome(None, None, Some(50), Duration::ZERO),
+        Outcome::Pending
+    );
+    assert_eq!(outcome(None, Some(100), None, long_ago), Outcome::Dropped);
+}
@@ -27,6 +27,24 @@ pub const PARALLEL_LIMIT: usize = 45;
 /// Default path for assets folder.
 pub const DEFAULT_ASSETS: &str = "assets";
 
+/// Default path for the trait layers folder.
+pub const DEFAULT_LAYERS: &str = "layers";
+
+/// Default path for the trait layers config file.
+pub const DEFAULT_TRAITS: &str = "traits.json";
+
+/// Name of the (optional) file in the assets folder specifying the order of the assets.
+pub const ASSET_ORDER_FILE: &str = "order.json";
+
+/// Name of the file in the state folder caching the hashes of the assets.
+pub const HASH_CACHE_FILE: &str = "hashes.json";
+
+/// Index of the collection asset.
+pub const COLLECTION_INDEX: isize = -1;
+
+/// Key of the collection item in the cache file.
+pub const COLLECTION_CACHE_KEY: &str = "-1";
+
 /// Default path for cache file.
 pub const DEFAULT_CACHE: &str = "cache.json";
 
@@ -34,9 +52,30 @@ pub const DEFAULT_CACHE: &str = "cache.json";
 pub const DEFAULT_AIRDROP_LIST: &str = "airdrop_list.json";
 pub const DEFAULT_AIRDROP_LIST_HELP: &str = "Path to airdrop targets list, format: \n{\n\"address1\": number_of_tokens,\n\"address2\": number_of_tokens\n}\n";
 
+/// Default path for the mint receipts file.
+pub const DEFAULT_RECEIPTS: &str = "mint_receipts.csv";
+
+/// Default path for the report of the items that failed to upload or deploy.
+pub const DEFAULT_FAILURES: &str = "failures.json";
+
+/// Default path for the fairness proof of hidden drops.
+pub const DEFAULT_PROOF: &str = "fairness.json";
+
+/// Default path for the holders snapshot.
+pub const DEFAULT_SNAPSHOT: &str = "snapshot.json";
+
+/// Default path for the rarity report.
+pub const DEFAULT_RARITY: &str = "rarity.json";
+
+/// Default output directory of the manifest export.
+pub const DEFAULT_MANIFEST: &str = "manifest";
+
 /// Default path for config file.
 pub const DEFAULT_CONFIG: &str = "config.json";
 
+/// Default path for the workspace manifest.
+pub const DEFAULT_WORKSPACE: &str = "workspace.json";
+
 /// Default path for keypair file.
 pub const DEFAULT_KEYPATH: &str = "~/.config/solana/id.json";
 
@@ -46,6 +85,12 @@ pub const BUNDLR_DEVNET: &str = "https://devnet.bundlr.network";
 /// Bundlr mainnet endpoint.
 pub const BUNDLR_MAINNET: &str = "https://node1.bundlr.network";
 
+/// Default gateway of `ipfs://` links.
+pub const IPFS_GATEWAY: &str = "https://ipfs.io/ipfs";
+
+/// Default gateway of `ar://` links.
+pub const ARWEAVE_GATEWAY: &str = "https://arweave.net";
+
 pub const CIVIC_NETWORK: &str = "ignREusXmGrscGNUesoU9mxfds9AiYTezUKex2PsZV6";
 
 pub const ENCORE_NETWORK: &str = "tibePmPaoTgrs929rWpu755EXaxC7M3SthVCf6GzjZt";
@@ -0,0 +1,429 @@
+use anchor_client::solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use anyhow::{anyhow, Result};
+use mpl_candy_machine_core::{
+    AccountVersion, CandyMachine, CandyMachineData, ConfigLineSettings, HiddenSettings,
+};
+use mpl_token_metadata::state::{MAX_NAME_LENGTH, MAX_URI_LENGTH};
+use sha2::{Digest, Sha256};
+use solana_program::pubkey;
+
+use crate::{
+    candy_machine::CANDY_MACHINE_ID,
+    config::{ConfigData, SugarConfig},
+    constants::STRING_LEN_SIZE,
+    setup::setup_client,
+};
+
+/// Core candy machine program id (mints Metaplex Core assets).
+pub const CORE_CANDY_MACHINE_ID: Pubkey = pubkey!("CMACYFENjoBMHzapRXyo1JZkVS6EtaDDzkjMrmQLvr4J");
+
+/// Core candy guard program id. Its guard set has a different layout than the one of the
+/// candy guard program, so sugar does not read or write the guards of core candy guards.
+pub const CORE_CANDY_GUARD_ID: Pubkey = pubkey!("CMAGAKJ67e9hRZgfC5SFTbZH8MgEmtqazKXjmkaJjWTJ");
+
+/// Metaplex Core program id.
+pub const MPL_CORE_ID: Pubkey = pubkey!("CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d");
+
+/// Discriminator of the `CreateCollectionV1` instruction of the Core program.
+const CREATE_COLLECTION_V1: u8 = 1;
+
+/// Offset of the config lines in a core candy machine account.
+pub const CORE_HIDDEN_SECTION: usize = 8 // discriminator
+    + 32 // authority
+    + 32 // mint authority
+    + 32 // collection
+    + 8 // items redeemed
+    + 8 // items available
+    + 8 // max supply
+    + 1 // is mutable
+    + 1 // option (config lines settings)
+    + STRING_LEN_SIZE + MAX_NAME_LENGTH // prefix name
+    + 4 // name length
+    + STRING_LEN_SIZE + MAX_URI_LENGTH // prefix uri
+    + 4 // uri length
+    + 1 // is sequential
+    + 1 // option (hidden setting)
+    + STRING_LEN_SIZE + MAX_NAME_LENGTH // name
+    + STRING_LEN_SIZE + MAX_URI_LENGTH // uri
+    + 32; // hash
+
+/// Core candy machine account; the asset details (royalties, creators) are stored
+/// as plugins of the Core collection instead.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CoreCandyMachine {
+    pub authority: Pubkey,
+    pub mint_authority: Pubkey,
+    pub collection_mint: Pubkey,
+    pub items_redeemed: u64,
+    pub data: CoreCandyMachineData,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CoreCandyMachineData {
+    pub items_available: u64,
+    pub max_supply: u64,
+    pub is_mutable: bool,
+    pub config_line_settings: Option<ConfigLineSettings>,
+    pub hidden_settings: Option<HiddenSettings>,
+}
+
+impl CoreCandyMachineData {
+    /// Candy machine data with the fields shared by both standards, so the config
+    /// lines can be generated and read the same way.
+    pub fn to_candy_format(&self) -> CandyMachineData {
+        CandyMachineData {
+            items_available: self.items_available,
+            symbol: String::new(),
+            seller_fee_basis_points: 0,
+            max_supply: self.max_supply,
+            is_mutable: self.is_mutable,
+            creators: Vec::new(),
+            config_line_settings: self.config_line_settings.clone(),
+            hidden_settings: self.hidden_settings.clone(),
+        }
+    }
+
+    pub fn get_space_for_candy(&self) -> Result<usize> {
+        let items_available = self.items_available as usize;
+
+        Ok(if self.hidden_settings.is_some() {
+            CORE_HIDDEN_SECTION
+        } else {
+            CORE_HIDDEN_SECTION
+                + 4
+                + items_available * self.to_candy_format().get_config_line_size()
+                + (items_available / 8 + 1)
+                + 4
+                + items_available * 4
+        })
+    }
+}
+
+impl CoreCandyMachine {
+    /// Candy machine view used by the commands shared between both standards.
+    pub fn to_candy_format(&self) -> CandyMachine {
+        CandyMachine {
+            version: AccountVersion::V2,
+            token_standard: 0,
+            features: [0; 6],
+            authority: self.authority,
+            mint_authority: self.mint_authority,
+            collection_mint: self.collection_mint,
+            items_redeemed: self.items_redeemed,
+            data: self.data.to_candy_format(),
+        }
+    }
+}
+
+// Core program types used to create the collection.
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum RuleSet {
+    None,
+    ProgramAllowList(Vec<Pubkey>),
+    ProgramDenyList(Vec<Pubkey>),
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RoyaltyCreator {
+    pub address: Pubkey,
+    pub percentage: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct Attribute {
+    pub key: String,
+    pub value: String,
+}
+
+/// Plugins of the Core program; the order of the variants matches the program.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum Plugin {
+    Royalties {
+        basis_points: u16,
+        creators: Vec<RoyaltyCreator>,
+        rule_set: RuleSet,
+    },
+    FreezeDelegate {
+        frozen: bool,
+    },
+    BurnDelegate,
+    TransferDelegate,
+    UpdateDelegate {
+        additional_delegates: Vec<Pubkey>,
+    },
+    PermanentFreezeDelegate {
+        frozen: bool,
+    },
+    Attributes {
+        attribute_list: Vec<Attribute>,
+    },
+    PermanentTransferDelegate,
+    PermanentBurnDelegate,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum PluginAuthority {
+    None,
+    Owner,
+    UpdateAuthority,
+    Address { address: Pubkey },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PluginAuthorityPair {
+    pub plugin: Plugin,
+    pub authority: Option<PluginAuthority>,
+}
+
+#[derive(AnchorSerialize)]
+struct CreateCollectionV1Args {
+    name: String,
+    uri: String,
+    plugins: Option<Vec<PluginAuthorityPair>>,
+}
+
+/// Royalties plugin with the seller fee and creators of the config, followed by the
+/// plugins listed in the config.
+pub fn collection_plugins(config_data: &ConfigData) -> Vec<PluginAuthorityPair> {
+    // the collection settings take precedence, since the royalties of the collection
+    // apply to all of its assets
+    let collection = config_data.collection.as_ref();
+    let basis_points = collection
+        .and_then(|collection| collection.seller_fee_basis_points)
+        .unwrap_or(config_data.seller_fee_basis_points);
+    let creators = collection
+        .and_then(|collection| collection.creators.as_ref())
+        .filter(|creators| !creators.is_empty())
+        .unwrap_or(&config_data.creators);
+
+    let mut plugins = vec![PluginAuthorityPair {
+        plugin: Plugin::Royalties {
+            basis_points,
+            creators: creators
+                .iter()
+                .map(|creator| RoyaltyCreator {
+                    address: creator.address,
+                    percentage: creator.share,
+                })
+                .collect(),
+            rule_set: RuleSet::None,
+        },
+        authority: None,
+    }];
+
+    for plugin in config_data.plugins.iter().flatten() {
+        plugins.push(PluginAuthorityPair {
+            plugin: plugin.to_core_format(),
+            authority: None,
+        });
+    }
+
+    plugins
+}
+
+/// Anchor instruction discriminator.
+fn discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("global:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+fn instruction_data<T: AnchorSerialize>(name: &str, args: &T) -> Result<Vec<u8>> {
+    let mut data = discriminator(name).to_vec();
+    args.serialize(&mut data)?;
+    Ok(data)
+}
+
+pub fn find_core_authority_pda(candy_machine: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"candy_machine", candy_machine.as_ref()],
+        &CORE_CANDY_MACHINE_ID,
+    )
+    .0
+}
+
+/// Returns the program that owns the candy machine account.
+pub fn get_candy_machine_program(
+    sugar_config: &SugarConfig,
+    candy_machine_id: &Pubkey,
+) -> Result<Pubkey> {
+    let client = setup_client(sugar_config)?;
+    let program = client.program(CANDY_MACHINE_ID);
+    let account = program
+        .rpc()
+        .get_account(candy_machine_id)
+        .map_err(|_| anyhow!("Candy Machine does not exist!"))?;
+
+    if account.owner == CANDY_MACHINE_ID || account.owner == CORE_CANDY_MACHINE_ID {
+        Ok(account.owner)
+    } else {
+        Err(anyhow!(
+            "Account {} is not a candy machine (owner {})",
+            candy_machine_id,
+            account.owner
+        ))
+    }
+}
+
+pub fn get_core_candy_machine_state(
+    sugar_config: &SugarConfig,
+    candy_machine_id: &Pubkey,
+) -> Result<CoreCandyMachine> {
+    let client = setup_client(sugar_config)?;
+    let program = client.program(CORE_CANDY_MACHINE_ID);
+    let data = program
+        .rpc()
+        .get_account_data(candy_machine_id)
+        .map_err(|_| anyhow!("Candy Machine does not exist!"))?;
+
+    CoreCandyMachine::deserialize(&mut &data[8..]).map_err(|e| {
+        anyhow!(
+            "Failed to deserialize Candy Machine account {}: {}",
+            candy_machine_id,
+            e
+        )
+    })
+}
+
+/// Instruction to create a Core collection.
+pub fn create_collection_ix(
+    collection: &Pubkey,
+    payer: &Pubkey,
+    name: String,
+    uri: String,
+    plugins: Vec<PluginAuthorityPair>,
+) -> Result<Instruction> {
+    let mut data = vec![CREATE_COLLECTION_V1];
+    CreateCollectionV1Args {
+        name,
+        uri,
+        plugins: Some(plugins),
+    }
+    .serialize(&mut data)?;
+
+    Ok(Instruction {
+        program_id: MPL_CORE_ID,
+        accounts: vec![
+            AccountMeta::new(*collection, true),
+            // update authority defaults to the payer
+            AccountMeta::new_readonly(MPL_CORE_ID, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    })
+}
+
+/// Instruction to initialize a core candy machine; the account must have been created.
+pub fn initialize_ix(
+    candy_machine: &Pubkey,
+    authority: &Pubkey,
+    collection: &Pubkey,
+    data: CoreCandyMachineData,
+) -> Result<Instruction> {
+    Ok(Instruction {
+        program_id: CORE_CANDY_MACHINE_ID,
+        accounts: vec![
+            AccountMeta::new(*candy_machine, false),
+            AccountMeta::new(find_core_authority_pda(candy_machine), false),
+            AccountMeta::new_readonly(*authority, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*collection, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(MPL_CORE_ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+        ],
+        data: instruction_data("initialize", &data)?,
+    })
+}
+
+/// Instruction to mint an asset directly from the core candy machine (the payer
+/// must be the mint authority).
+pub fn mint_asset_ix(
+    candy_machine_id: &Pubkey,
+    collection: &Pubkey,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    asset: &Pubkey,
+) -> Result<Instruction> {
+    // mint asset args with no additional plugins
+    let plugins: Vec<PluginAuthorityPair> = Vec::new();
+
+    Ok(Instruction {
+        program_id: CORE_CANDY_MACHINE_ID,
+        accounts: vec![
+            AccountMeta::new(*candy_machine_id, false),
+            AccountMeta::new(find_core_authority_pda(candy_machine_id), false),
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new(*asset, true),
+            AccountMeta::new(*collection, false),
+            AccountMeta::new_readonly(MPL_CORE_ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+            AccountMeta::new_readonly(sysvar::slot_hashes::ID, false),
+        ],
+        data: instruction_data("mint_asset", &plugins)?,
+    })
+}
+
+#[test]
+fn test_core_instruction_data() {
+    // anchor discriminator of the `initialize` instruction
+    assert_eq!(
+        discriminator("initialize"),
+        [175, 175, 109, 31, 13, 152, 155, 237]
+    );
+
+    let pair = PluginAuthorityPair {
+        plugin: Plugin::PermanentBurnDelegate,
+        authority: None,
+    };
+    // plugin variant index followed by the (empty) authority option
+    assert_eq!(pair.try_to_vec().unwrap(), vec![8, 0]);
+}
+
+#[test]
+fn test_collection_royalties() {
+    use crate::config::{CollectionConfig, Creator};
+
+    let creator = |share| Creator {
+        address: Pubkey::new_unique(),
+        share,
+    };
+    let royalties = |config_data: &ConfigData| match &collection_plugins(config_data)[0].plugin {
+        Plugin::Royalties {
+            basis_points,
+            creators,
+            ..
+        } => (*basis_points, creators.len()),
+        _ => panic!("the first plugin must be the royalties"),
+    };
+
+    let mut config_data = ConfigData {
+        seller_fee_basis_points: 500,
+        creators: vec![creator(100)],
+        ..ConfigData::default()
+    };
+    assert_eq!(royalties(&config_data), (500, 1));
+
+    // unset collection settings fall back to the config
+    config_data.collection = Some(CollectionConfig::default());
+    assert_eq!(royalties(&config_data), (500, 1));
+
+    config_data.collection = Some(CollectionConfig {
+        seller_fee_basis_points: Some(250),
+        creators: Some(vec![creator(60), creator(40)]),
+        ..CollectionConfig::default()
+    });
+    assert_eq!(royalties(&config_data), (250, 2));
+}
@@ -0,0 +1,289 @@
+use std::{collections::HashSet, fs, sync::Mutex};
+
+use anchor_client::solana_sdk::transaction::VersionedTransaction;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::{common::*, config::SugarConfig, dry_run::is_dry_run, rate_limit::throttled_client};
+
+/// Ledger file with the costs of the commands.
+pub const COSTS_FILE: &str = "costs.json";
+
+lazy_static! {
+    static ref COMMAND: Mutex<Option<String>> = Mutex::new(None);
+    static ref COSTS_PATH: Mutex<String> = Mutex::new(COSTS_FILE.to_string());
+    static ref TRACKER: Mutex<Option<CostTracker>> = Mutex::new(None);
+}
+
+/// Type of expenditure recorded in the ledger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CostKind {
+    /// Funding of the storage provider (e.g. Bundlr).
+    Storage,
+    /// Rent of the accounts created by sugar (e.g. the candy machine).
+    Rent,
+    /// Transaction and priority fees, and the rent of any other account.
+    Fees,
+    /// Funds returned to the wallet (e.g. withdrawing a candy machine).
+    Refund,
+}
+
+/// Entry of the cost ledger, amounts are in lamports (negative for refunds).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostEntry {
+    pub timestamp: String,
+    pub command: String,
+    pub wallet: String,
+    pub kind: CostKind,
+    pub lamports: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Transactions sent by the command and the costs recorded so far.
+struct CostTracker {
+    command: String,
+    wallet: Pubkey,
+    rpc_url: String,
+    /// Signature and description of the transactions sent.
+    transactions: Vec<(Signature, String)>,
+    entries: Vec<CostEntry>,
+}
+
+impl CostTracker {
+    fn entry(&self, kind: CostKind, lamports: i64, detail: Option<String>) -> CostEntry {
+        CostEntry {
+            timestamp: Utc::now().to_rfc3339(),
+            command: self.command.clone(),
+            wallet: self.wallet.to_string(),
+            kind,
+            lamports,
+            detail,
+        }
+    }
+}
+
+/// Sets the name of the command running, used as the key of its costs.
+pub fn set_cost_command(command: &str) {
+    *COMMAND.lock().unwrap() = Some(command.to_string());
+}
+
+/// Sets the path of the ledger file where the costs are recorded.
+pub fn set_costs_file(path: &str) {
+    *COSTS_PATH.lock().unwrap() = path.to_string();
+}
+
+/// Starts tracking the costs of the command for the wallet of the config. Only the first
+/// wallet of a command is tracked.
+pub fn start_cost_tracking(sugar_config: &SugarConfig) {
+    // a dry run does not spend anything
+    if is_dry_run() {
+        return;
+    }
+
+    let command = match COMMAND.lock().unwrap().clone() {
+        Some(command) => command,
+        None => return,
+    };
+
+    let mut tracker = TRACKER.lock().unwrap();
+
+    if tracker.is_none() {
+        *tracker = Some(CostTracker {
+            command,
+            wallet: sugar_config.keypair.pubkey(),
+            rpc_url: sugar_config.rpc_url.clone(),
+            transactions: Vec::new(),
+            entries: Vec::new(),
+        });
+    }
+}
+
+/// Records a known expenditure (in lamports) of the command that is not a transaction
+/// fee or rent (e.g. the funding of the storage).
+pub fn record_cost(kind: CostKind, lamports: u64, detail: &str) {
+    if let Some(tracker) = TRACKER.lock().unwrap().as_mut() {
+        let entry = tracker.entry(kind, lamports as i64, Some(detail.to_string()));
+        tracker.entries.push(entry);
+    }
+}
+
+/// Records a transaction sent by the command: its fee, the rent of the accounts it
+/// created and the funds it returned to the wallet are read when the command finishes.
+pub fn record_transaction(signature: &Signature, description: &str) {
+    if let Some(tracker) = TRACKER.lock().unwrap().as_mut() {
+        tracker
+            .transactions
+            .push((*signature, description.to_string()));
+    }
+}
+
+/// Stops tracking the costs of the command: the costs of the transactions sent are
+/// itemized by description and appended to the ledger file, with the other costs
+/// recorded. Commands that sent nothing leave the ledger as it was.
+pub fn finish_cost_tracking() -> Result<()> {
+    let mut tracker = match TRACKER.lock().unwrap().take() {
+        Some(tracker) => tracker,
+        None => return Ok(()),
+    };
+
+    if tracker.transactions.is_empty() && tracker.entries.is_empty() {
+        return Ok(());
+    }
+
+    let rpc_client = throttled_client(&tracker.rpc_url);
+    let mut seen = HashSet::new();
+    // totals by kind and description, in the order the transactions were sent
+    let mut totals: IndexMap<(CostKind, String), i64> = IndexMap::new();
+
+    for (signature, description) in &tracker.transactions {
+        // a transaction sent again (e.g. a resumed mint) only pays once
+        if !seen.insert(*signature) {
+            continue;
+        }
+
+        let transaction = match rpc_client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        ) {
+            Ok(transaction) => transaction,
+            Err(err) => {
+                warn!(
+                    "Could not read the costs of transaction {}: {}",
+                    signature, err
+                );
+                continue;
+            }
+        };
+
+        let meta = match transaction.transaction.meta {
+            Some(meta) => meta,
+            None => continue,
+        };
+        let decoded: Option<VersionedTransaction> = transaction.transaction.transaction.decode();
+        let account_keys = decoded
+            .map(|tx| tx.message.static_account_keys().to_vec())
+            .unwrap_or_default();
+
+        for (kind, lamports) in transaction_costs(
+            &account_keys,
+            meta.fee,
+            &meta.pre_balances,
+            &meta.post_balances,
+            &tracker.wallet,
+        ) {
+            *totals.entry((kind, description.clone())).or_default() += lamports;
+        }
+    }
+
+    for ((kind, description), lamports) in totals {
+        if lamports != 0 {
+            let entry = tracker.entry(kind, lamports, Some(description));
+            tracker.entries.push(entry);
+        }
+    }
+
+    if tracker.entries.is_empty() {
+        return Ok(());
+    }
+
+    let path = COSTS_PATH.lock().unwrap().clone();
+    let mut ledger = load_costs(&path)?;
+    ledger.extend(tracker.entries);
+    fs::write(&path, serde_json::to_string_pretty(&ledger)?)
+        .map_err(|e| anyhow!("Failed to write cost ledger '{}': {}", path, e))?;
+
+    Ok(())
+}
+
+/// Costs of a transaction: its fee, the rent of the accounts it created (accounts without
+/// a balance before the transaction) and the funds returned to the wallet (as a negative
+/// refund, e.g. when closing an account). Other transfers (e.g. guard payments) are not
+/// costs of the launch.
+fn transaction_costs(
+    account_keys: &[Pubkey],
+    fee: u64,
+    pre_balances: &[u64],
+    post_balances: &[u64],
+    wallet: &Pubkey,
+) -> Vec<(CostKind, i64)> {
+    let rent: u64 = pre_balances
+        .iter()
+        .zip(post_balances)
+        .filter(|(pre, post)| **pre == 0 && **post > 0)
+        .map(|(_, post)| *post)
+        .sum();
+
+    let mut costs = vec![(CostKind::Fees, fee as i64), (CostKind::Rent, rent as i64)];
+
+    if let Some(index) = account_keys.iter().position(|key| key == wallet) {
+        if let (Some(pre), Some(post)) = (pre_balances.get(index), post_balances.get(index)) {
+            // the first account pays the fee
+            let fee_paid = if index == 0 { fee as i64 } else { 0 };
+            let returned = *post as i64 - *pre as i64 + fee_paid;
+
+            if returned > 0 {
+                costs.push((CostKind::Refund, -returned));
+            }
+        }
+    }
+
+    costs
+}
+
+/// Returns the entries of a ledger file.
+pub fn load_costs(path: &str) -> Result<Vec<CostEntry>> {
+    if !Path::new(path).exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| anyhow!("Failed to parse cost ledger '{}': {}", path, e))
+}
+
+#[test]
+fn test_transaction_costs() {
+    let wallet = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let program = Pubkey::new_unique();
+
+    // creating an account: the wallet pays the fee and the rent
+    let costs = transaction_costs(
+        &[wallet, account, program],
+        10_000,
+        &[1_000_000_000, 0, 1],
+        &[1_000_000_000 - 10_000 - 2_000_000, 2_000_000, 1],
+        &wallet,
+    );
+    assert_eq!(
+        costs,
+        vec![(CostKind::Fees, 10_000), (CostKind::Rent, 2_000_000)]
+    );
+
+    // closing an account: the rent returns to the wallet
+    let costs = transaction_costs(
+        &[wallet, account, program],
+        5_000,
+        &[1_000_000_000, 2_000_000, 1],
+        &[1_000_000_000 - 5_000 + 2_000_000, 0, 1],
+        &wallet,
+    );
+    assert_eq!(
+        costs,
+        vec![
+            (CostKind::Fees, 5_000),
+            (CostKind::Rent, 0),
+            (CostKind::Refund, -2_000_000)
+        ]
+    );
+}
@@ -0,0 +1,5 @@
+pub mod ledger;
+pub mod process;
+
+pub use ledger::*;
+pub use process::*;
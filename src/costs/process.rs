@@ -0,0 +1,110 @@
+use console::style;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use tabled::{
+    builder::Builder,
+    settings::{object::Columns, Alignment, Modify, Style},
+};
+
+use crate::{common::*, costs::*};
+
+pub struct CostsArgs {
+    pub file: String,
+    pub command: Option<String>,
+}
+
+pub fn process_costs(args: CostsArgs) -> Result<()> {
+    let entries: Vec<CostEntry> = load_costs(&args.file)?
+        .into_iter()
+        .filter(|entry| {
+            args.command
+                .as_ref()
+                .map_or(true, |command| entry.command.starts_with(command.as_str()))
+        })
+        .collect();
+
+    if entries.is_empty() {
+        println!("No costs recorded in '{}'.", args.file);
+        return Ok(());
+    }
+
+    // totals by command and kind, in the order the commands were first run
+    let mut totals: IndexMap<String, HashMap<CostKind, i64>> = IndexMap::new();
+
+    for entry in &entries {
+        *totals
+            .entry(entry.command.clone())
+            .or_default()
+            .entry(entry.kind)
+            .or_default() += entry.lamports;
+    }
+
+    let kinds = [
+        CostKind::Storage,
+        CostKind::Rent,
+        CostKind::Fees,
+        CostKind::Refund,
+    ];
+
+    let mut builder = Builder::default();
+    builder.push_record(["command", "storage", "rent", "fees", "refund", "total"]);
+
+    for (command, kind_totals) in &totals {
+        let mut record = vec![command.clone()];
+        record.extend(
+            kinds
+                .iter()
+                .map(|kind| format_sol(kind_totals.get(kind).copied().unwrap_or_default())),
+        );
+        record.push(format_sol(kind_totals.values().sum()));
+        builder.push_record(record);
+    }
+
+    let mut record = vec!["total".to_string()];
+    record.extend(kinds.iter().map(|kind| {
+        format_sol(
+            entries
+                .iter()
+                .filter(|entry| entry.kind == *kind)
+                .map(|entry| entry.lamports)
+                .sum(),
+        )
+    }));
+    let total: i64 = entries.iter().map(|entry| entry.lamports).sum();
+    record.push(format_sol(total));
+    builder.push_record(record);
+
+    let mut table = builder.build();
+    table
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(1..)).with(Alignment::right()));
+
+    println!("{table}");
+    println!(
+        "\n{} {}",
+        style("Total cost:").bold(),
+        style(format!(
+            "◎ {} ({} lamports)",
+            total as f64 / LAMPORTS_PER_SOL as f64,
+            total
+        ))
+        .green()
+        .bold()
+    );
+
+    Ok(())
+}
+
+fn format_sol(lamports: i64) -> String {
+    if lamports == 0 {
+        "-".to_string()
+    } else {
+        format!("◎ {:.6}", lamports as f64 / LAMPORTS_PER_SOL as f64)
+    }
+}
+
+#[test]
+fn test_cost_format() {
+    assert_eq!(format_sol(0), "-");
+    assert_eq!(format_sol(1_500_000_000), "◎ 1.500000");
+    assert_eq!(format_sol(-5000), "◎ -0.000005");
+}
@@ -1,3 +1,5 @@
 pub mod process;
+pub mod templates;
 
 pub use process::*;
+pub use templates::*;
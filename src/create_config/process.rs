@@ -12,10 +12,14 @@ use dialoguer::{Confirm, Input, MultiSelect, Select};
 use url::Url;
 
 use crate::{
-    config::{AwsConfig, ConfigData, Creator, HiddenSettings, PinataConfig, UploadMethod},
+    config::{
+        AwsConfig, ConfigData, Creator, HiddenSettings, NftStorageConfig, PinataConfig,
+        PinningServiceConfig, UploadMethod,
+    },
     constants::*,
-    upload::list_files,
-    utils::get_dialoguer_theme,
+    create_config::{create_config_from_template, ConfigTemplate},
+    upload::{get_asset_order, list_files},
+    utils::{ensure_interactive, get_dialoguer_theme},
     validate::Metadata,
 };
 
@@ -31,9 +35,16 @@ pub struct CreateConfigArgs {
     pub rpc_url: Option<String>,
     pub config: Option<String>,
     pub assets_dir: String,
+    pub template: Option<ConfigTemplate>,
 }
 
 pub fn process_create_config(args: CreateConfigArgs) -> Result<()> {
+    if let Some(template) = args.template {
+        return create_config_from_template(template, args);
+    }
+
+    ensure_interactive("interactive config creation")?;
+
     let mut config_data: ConfigData = ConfigData::default();
     let theme = get_dialoguer_theme();
 
@@ -96,21 +107,31 @@ pub fn process_create_config(args: CreateConfigArgs) -> Result<()> {
 
     // checks if we have an assets dir and count the number of files
     // assumes 0 in case of error since assets_dir is optional
-    let num_files = match list_files(&args.assets_dir, false) {
-        Ok(number) => number.len(),
-        _ => 0,
-    };
+    let files = list_files(&args.assets_dir, false).unwrap_or_default();
+    let num_files = files.len();
 
     let mut symbol: String = INVALID_SYMBOL.to_string();
     let mut seller_fee = INVALID_SELLER_FEE;
 
     if num_files > 0 {
+        let metadata_filenames = files
+            .iter()
+            .filter_map(|f| f.file_name().to_str().map(String::from))
+            .filter(|f| f.to_lowercase().ends_with(".json"))
+            .collect::<Vec<String>>();
+
+        // named assets do not have a '0.json' file, so we use the first one in order
+        let first_metadata = get_asset_order(&args.assets_dir, &metadata_filenames)
+            .ok()
+            .and_then(|order| order.first().map(|name| format!("{name}.json")))
+            .unwrap_or_else(|| DEFAULT_METADATA.to_string());
+
         println!("\nFound metadata file(s) in folder '{}':", args.assets_dir);
-        println!("  -> Loading values from file '{}'", DEFAULT_METADATA);
+        println!("  -> Loading values from file '{}'", first_metadata);
 
         // loads the default values from the first metadata file
         let metadata_file = PathBuf::from(&args.assets_dir)
-            .join(DEFAULT_METADATA)
+            .join(&first_metadata)
             .to_str()
             .expect("Failed to convert metadata path from unicode.")
             .to_string();
@@ -309,7 +330,16 @@ pub fn process_create_config(args: CreateConfigArgs) -> Result<()> {
     };
 
     // upload method
-    let upload_options = vec!["Bundlr", "AWS", "NFT Storage", "SHDW", "Pinata", "SDrive"];
+    let upload_options = vec![
+        "Bundlr",
+        "AWS",
+        "NFT Storage",
+        "SHDW",
+        "Pinata",
+        "SDrive",
+        "IPFS Pinning Service API",
+        "Custom (URI template)",
+    ];
     config_data.upload_method = match Select::with_theme(&theme)
         .with_prompt("What upload method do you want to use?")
         .items(&upload_options)
@@ -323,9 +353,23 @@ pub fn process_create_config(args: CreateConfigArgs) -> Result<()> {
         3 => UploadMethod::SHDW,
         4 => UploadMethod::Pinata,
         5 => UploadMethod::Sdrive,
+        6 => UploadMethod::PinningService,
+        7 => UploadMethod::Custom,
         _ => UploadMethod::Bundlr,
     };
 
+    if config_data.upload_method == UploadMethod::Custom {
+        config_data.uri_template = Some(
+            Input::with_theme(&theme)
+                .with_prompt(
+                    "What is the URI template of your storage? Use {hash}, {ext}, {name} and {index} placeholders.",
+                )
+                .default(String::from("https://cdn.example.com/{hash}{ext}"))
+                .interact()
+                .unwrap(),
+        );
+    }
+
     if config_data.upload_method == UploadMethod::AWS {
         let bucket: String = Input::with_theme(&theme)
             .with_prompt("What is the AWS S3 bucket name?")
@@ -369,6 +413,25 @@ pub fn process_create_config(args: CreateConfigArgs) -> Result<()> {
                 .interact()
                 .unwrap(),
         );
+
+        let api_url: String = Input::with_theme(&theme)
+            .with_prompt("What is the upload API? (the classic nft.storage API is being sunset)")
+            .default(String::from("https://api.nft.storage"))
+            .interact()
+            .unwrap();
+
+        if api_url != "https://api.nft.storage" {
+            let gateway_url = Input::with_theme(&theme)
+                .with_prompt("What is the gateway for content retrieval?")
+                .default(String::from("https://nftstorage.link/ipfs"))
+                .interact()
+                .unwrap();
+
+            config_data.nft_storage_config = Some(NftStorageConfig {
+                api_url: Some(api_url),
+                gateway_url: Some(gateway_url),
+            });
+        }
     }
 
     if config_data.upload_method == UploadMethod::Sdrive {
@@ -390,6 +453,38 @@ pub fn process_create_config(args: CreateConfigArgs) -> Result<()> {
         );
     }
 
+    if config_data.upload_method == UploadMethod::PinningService {
+        let endpoint = Input::with_theme(&theme)
+            .with_prompt("What is the endpoint of the pinning service API?")
+            .interact()
+            .unwrap();
+
+        let access_token = Input::with_theme(&theme)
+            .with_prompt("What is your pinning service access token?")
+            .interact()
+            .unwrap();
+
+        let ipfs_api_url: String = Input::with_theme(&theme)
+            .with_prompt(
+                "What is the API of the IPFS node providing the content? (leave blank for none)",
+            )
+            .allow_empty(true)
+            .interact()
+            .unwrap();
+
+        config_data.pinning_service_config = Some(PinningServiceConfig {
+            endpoint,
+            access_token,
+            ipfs_api_url: if ipfs_api_url.is_empty() {
+                None
+            } else {
+                Some(ipfs_api_url)
+            },
+            origins: None,
+            parallel_limit: None,
+        });
+    }
+
     if config_data.upload_method == UploadMethod::Pinata {
         let jwt: String = Input::with_theme(&theme)
             .with_prompt("What is your Pinata JWT authentication?")
@@ -0,0 +1,270 @@
+use std::fmt::{self, Display};
+
+use chrono::{Duration, Utc};
+use console::style;
+
+use crate::{
+    common::*,
+    config::{
+        timestamp_as_date, AllowList, BotTax, CandyGuardData, ConfigData, Creator, Group, GuardSet,
+        HiddenSettings, MintLimit, SolPayment, StartDate, TokenGate,
+    },
+    create_config::CreateConfigArgs,
+    upload::list_files,
+};
+
+/// Royalties of the templates (5%).
+const TEMPLATE_SELLER_FEE: u16 = 500;
+
+/// Placeholder merkle root of the allow list, replaced by the root of the wallets
+/// allowed to mint.
+const PLACEHOLDER_MERKLE_ROOT: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Placeholder uri of the metadata shown before the reveal.
+const PLACEHOLDER_URI: &str = "https://example.com/placeholder.json";
+
+/// Built-in presets of `config create`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigTemplate {
+    FreeMint,
+    SolPaymentWithAllowlist,
+    TokenGated,
+    HiddenReveal,
+}
+
+impl ConfigTemplate {
+    pub const ALL: [ConfigTemplate; 4] = [
+        ConfigTemplate::FreeMint,
+        ConfigTemplate::SolPaymentWithAllowlist,
+        ConfigTemplate::TokenGated,
+        ConfigTemplate::HiddenReveal,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigTemplate::FreeMint => "free-mint",
+            ConfigTemplate::SolPaymentWithAllowlist => "sol-payment-with-allowlist",
+            ConfigTemplate::TokenGated => "token-gated",
+            ConfigTemplate::HiddenReveal => "hidden-reveal",
+        }
+    }
+
+    /// Values of the generated config that must be replaced before deploying.
+    pub fn placeholders(&self) -> &'static [&'static str] {
+        match self {
+            ConfigTemplate::FreeMint => &["guards.default.startDate"],
+            ConfigTemplate::SolPaymentWithAllowlist => &[
+                "guards.groups[wl].allowList.merkleRoot",
+                "guards.groups[*].startDate",
+                "guards.groups[*].solPayment.value",
+            ],
+            ConfigTemplate::TokenGated => {
+                &["guards.default.tokenGate.mint", "guards.default.startDate"]
+            }
+            ConfigTemplate::HiddenReveal => &[
+                "hiddenSettings.name",
+                "hiddenSettings.uri",
+                "guards.default.startDate",
+                "guards.default.solPayment.value",
+            ],
+        }
+    }
+
+    /// Returns a complete config for `number` assets paying royalties and mint proceeds
+    /// to the wallet.
+    pub fn config_data(&self, wallet: Pubkey, number: u64) -> ConfigData {
+        // a week from now, on the hour
+        let start = (Utc::now() + Duration::days(7)).timestamp() / 3600 * 3600;
+        let start_date = |offset: i64| {
+            Some(StartDate {
+                date: timestamp_as_date(start + offset),
+            })
+        };
+        let sol_payment = |value: f64| {
+            Some(SolPayment {
+                value,
+                destination: wallet,
+            })
+        };
+        let mint_limit = |id: u8, limit: u16| Some(MintLimit { id, limit });
+
+        let bot_tax = Some(BotTax {
+            value: 0.01,
+            last_instruction: true,
+        });
+
+        let mut hidden_settings = None;
+
+        let guards = match self {
+            ConfigTemplate::FreeMint => CandyGuardData {
+                default: GuardSet {
+                    bot_tax,
+                    start_date: start_date(0),
+                    mint_limit: mint_limit(1, 1),
+                    ..Default::default()
+                },
+                groups: None,
+            },
+            ConfigTemplate::SolPaymentWithAllowlist => CandyGuardData {
+                default: GuardSet {
+                    bot_tax,
+                    ..Default::default()
+                },
+                groups: Some(vec![
+                    Group {
+                        label: "wl".to_string(),
+                        guards: GuardSet {
+                            start_date: start_date(0),
+                            allow_list: Some(AllowList {
+                                merkle_root: PLACEHOLDER_MERKLE_ROOT.to_string(),
+                            }),
+                            sol_payment: sol_payment(0.5),
+                            mint_limit: mint_limit(1, 2),
+                            ..Default::default()
+                        },
+                    },
+                    Group {
+                        label: "public".to_string(),
+                        guards: GuardSet {
+                            // the public sale opens a day after the allow list
+                            start_date: start_date(24 * 3600),
+                            sol_payment: sol_payment(1.0),
+                            ..Default::default()
+                        },
+                    },
+                ]),
+            },
+            ConfigTemplate::TokenGated => CandyGuardData {
+                default: GuardSet {
+                    bot_tax,
+                    start_date: start_date(0),
+                    token_gate: Some(TokenGate {
+                        amount: 1,
+                        mint: Pubkey::default(),
+                    }),
+                    mint_limit: mint_limit(1, 1),
+                    ..Default::default()
+                },
+                groups: None,
+            },
+            ConfigTemplate::HiddenReveal => {
+                // the hash is set by 'sugar hash' (or deploy) from the cache file
+                hidden_settings = Some(HiddenSettings::new(
+                    "Mystery #".to_string(),
+                    PLACEHOLDER_URI.to_string(),
+                    String::new(),
+                ));
+
+                CandyGuardData {
+                    default: GuardSet {
+                        bot_tax,
+                        start_date: start_date(0),
+                        sol_payment: sol_payment(1.0),
+                        ..Default::default()
+                    },
+                    groups: None,
+                }
+            }
+        };
+
+        ConfigData {
+            number,
+            seller_fee_basis_points: TEMPLATE_SELLER_FEE,
+            is_mutable: true,
+            creators: vec![Creator {
+                address: wallet,
+                share: 100,
+            }],
+            hidden_settings,
+            guards: Some(guards),
+            ..Default::default()
+        }
+    }
+}
+
+impl Display for ConfigTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ConfigTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ConfigTemplate::ALL
+            .into_iter()
+            .find(|template| template.name() == s.to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = ConfigTemplate::ALL.iter().map(|t| t.name()).collect();
+                anyhow!(
+                    "Invalid template '{s}': expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Writes the config of a template without prompting, so it can be used in scripts.
+pub fn create_config_from_template(template: ConfigTemplate, args: CreateConfigArgs) -> Result<()> {
+    let file_path = args.config.unwrap_or_else(|| DEFAULT_CONFIG.to_string());
+
+    if Path::new(&file_path).exists() {
+        return Err(anyhow!(
+            "The file \"{}\" already exists, remove it or use --config to write the \
+            template to a different file.",
+            file_path
+        ));
+    }
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+
+    // assumes 0 in case of error since assets_dir is optional
+    let num_files = list_files(&args.assets_dir, false)
+        .unwrap_or_default()
+        .len();
+    let number = (num_files / 2) as u64;
+
+    let config_data = template.config_data(sugar_config.keypair.pubkey(), number);
+
+    let file = File::create(&file_path)?;
+    serde_json::to_writer_pretty(file, &config_data)?;
+
+    println!(
+        "{} {}",
+        style(format!(
+            "Generated config file \"{}\" from the '{}' template.",
+            file_path, template
+        ))
+        .magenta()
+        .bold(),
+        CONFETTI_EMOJI
+    );
+
+    if number == 0 {
+        println!(
+            "\nNo assets found in \"{}\", remember to set the number of items.",
+            args.assets_dir
+        );
+    }
+
+    println!("\nReplace the placeholder values before deploying:");
+    for placeholder in template.placeholders() {
+        println!("  -> {}", placeholder);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_template_guards() {
+    for template in ConfigTemplate::ALL {
+        assert_eq!(ConfigTemplate::from_str(template.name()).unwrap(), template);
+
+        let config_data = template.config_data(Pubkey::new_unique(), 10);
+        assert!(config_data.guards.unwrap().to_guard_format().is_ok());
+    }
+
+    assert!(ConfigTemplate::from_str("dutch-auction").is_err());
+}
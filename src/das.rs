@@ -0,0 +1,185 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_client::RpcClient,
+    rpc_request::{RpcError, RpcRequest},
+};
+
+use crate::common::*;
+
+/// DAS (Digital Asset Standard) method to list the assets of a creator.
+pub const GET_ASSETS_BY_CREATOR: &str = "getAssetsByCreator";
+
+/// Maximum number of assets returned per page.
+const PAGE_LIMIT: usize = 1000;
+
+/// JSON-RPC error code of a method not supported by the endpoint.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Indicates that the RPC endpoint does not support the DAS API, so further calls
+/// go straight to the RPC scans.
+static DAS_UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Deserialize)]
+struct AssetList {
+    items: Vec<DasAsset>,
+}
+
+/// Asset returned by the DAS API; only the fields used by sugar are deserialized.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DasAsset {
+    /// Mint address of the asset.
+    pub id: String,
+    #[serde(default)]
+    pub creators: Vec<DasCreator>,
+    #[serde(default)]
+    pub burnt: bool,
+    #[serde(default)]
+    pub compression: Option<DasCompression>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DasCreator {
+    pub address: String,
+    #[serde(default)]
+    pub verified: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DasCompression {
+    #[serde(default)]
+    pub compressed: bool,
+}
+
+/// Returns the mints of the (uncompressed, not burnt) assets with the verified `creator`
+/// at `position` in the creators array, or `None` when the endpoint lacks DAS support.
+pub fn get_creator_mints(
+    client: &RpcClient,
+    creator: &Pubkey,
+    position: usize,
+) -> Option<Vec<Pubkey>> {
+    if DAS_UNAVAILABLE.load(Ordering::SeqCst) {
+        return None;
+    }
+
+    let params = json!({
+        "creatorAddress": creator.to_string(),
+        "onlyVerified": true,
+    });
+
+    let assets = match get_all_pages(client, GET_ASSETS_BY_CREATOR, params) {
+        Ok(assets) => assets,
+        Err(err) if is_method_not_found(&err) => {
+            info!("DAS API not available, falling back to RPC scan: {}", err);
+            DAS_UNAVAILABLE.store(true, Ordering::SeqCst);
+            return None;
+        }
+        Err(err) => {
+            // other errors (e.g., timeouts) only affect this request
+            warn!("DAS request failed, falling back to RPC scan: {}", err);
+            return None;
+        }
+    };
+
+    let creator = creator.to_string();
+
+    let mints = assets
+        .into_iter()
+        .filter(|asset| {
+            !asset.burnt
+                && !asset
+                    .compression
+                    .as_ref()
+                    .map(|compression| compression.compressed)
+                    .unwrap_or(false)
+                && asset
+                    .creators
+                    .get(position)
+                    .map(|c| c.address == creator && c.verified)
+                    .unwrap_or(false)
+        })
+        .filter_map(|asset| Pubkey::from_str(&asset.id).ok())
+        .collect();
+
+    Some(mints)
+}
+
+/// Indicates whether the endpoint rejected the request because it does not implement
+/// the method.
+fn is_method_not_found(err: &ClientError) -> bool {
+    matches!(
+        err.kind(),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) if *code == METHOD_NOT_FOUND
+    )
+}
+
+/// Fetch every page of a DAS method.
+fn get_all_pages(
+    client: &RpcClient,
+    method: &'static str,
+    params: Value,
+) -> Result<Vec<DasAsset>, ClientError> {
+    let mut assets = Vec::new();
+    let mut page = 1;
+
+    loop {
+        let mut params = params.clone();
+        params["page"] = json!(page);
+        params["limit"] = json!(PAGE_LIMIT);
+
+        let list: AssetList = client.send(RpcRequest::Custom { method }, params)?;
+
+        let count = list.items.len();
+        assets.extend(list.items);
+
+        if count < PAGE_LIMIT {
+            break;
+        }
+
+        page += 1;
+    }
+
+    debug!("Fetched {} assets with {}", assets.len(), method);
+
+    Ok(assets)
+}
+
+#[test]
+fn test_deserialize_das_asset() {
+    let asset: DasAsset = serde_json::from_value(json!({
+        "interface": "V1_NFT",
+        "id": "JEGruwYE13mhX2wi2MGrPmeLiVyZtbBptmVy9vG3pXRC",
+        "creators": [
+            { "address": "8hN3NmPsZoAzg1VqWSP5GZB3Y8gVTzkdpG4zmvYQqxXP", "share": 0, "verified": true }
+        ],
+        "compression": { "compressed": false, "tree": "" },
+        "burnt": false
+    }))
+    .unwrap();
+
+    assert_eq!(asset.creators.len(), 1);
+    assert!(asset.creators[0].verified);
+    assert!(!asset.compression.unwrap().compressed);
+}
+
+#[test]
+fn test_method_not_found() {
+    use solana_client::rpc_request::RpcResponseErrorData;
+
+    let error = |code| {
+        ClientError::from(RpcError::RpcResponseError {
+            code,
+            message: "error".to_string(),
+            data: RpcResponseErrorData::Empty,
+        })
+    };
+
+    assert!(is_method_not_found(&error(METHOD_NOT_FOUND)));
+    // rate limits and server errors do not disable the DAS API
+    assert!(!is_method_not_found(&error(429)));
+    assert!(!is_method_not_found(&error(-32603)));
+    assert!(!is_method_not_found(&ClientError::from(RpcError::ForUser(
+        "timeout".to_string()
+    ))));
+}
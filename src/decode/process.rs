@@ -0,0 +1,393 @@
+use anchor_lang::AnchorDeserialize;
+use console::style;
+use mpl_candy_guard::{
+    guards::FreezeEscrow,
+    state::{CandyGuardData, DATA_OFFSET},
+};
+use mpl_candy_machine_core::{
+    constants::{EMPTY_STR, HIDDEN_SECTION, NULL_STRING},
+    CandyMachine,
+};
+use mpl_token_metadata::state::{Metadata, TokenMetadataAccount};
+use sha2::{Digest, Sha256};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+
+use crate::{
+    candy_machine::CANDY_MACHINE_ID,
+    common::*,
+    config::format_timestamp,
+    core_candy_machine::{
+        CoreCandyMachine, CORE_CANDY_GUARD_ID, CORE_CANDY_MACHINE_ID, CORE_HIDDEN_SECTION,
+    },
+    guard::print_guard_set,
+    rate_limit::throttled_client,
+    show::print_with_style,
+};
+
+/// Number of bytes shown of accounts of an unknown type.
+const PREVIEW_LEN: usize = 64;
+
+pub struct DecodeArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub account: String,
+}
+
+pub fn process_decode(args: DecodeArgs) -> Result<()> {
+    let address = Pubkey::from_str(&args.account)
+        .map_err(|_| anyhow!("Failed to parse account address: {}", args.account))?;
+
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
+    let rpc_client = throttled_client(&sugar_config.rpc_url);
+    let account = rpc_client
+        .get_account(&address)
+        .map_err(|_| anyhow!("Account {} does not exist.", address))?;
+    let data = account.data.as_slice();
+
+    println!("{}{} {}", PAPER_EMOJI, style("Account:").dim(), address);
+    println!(" {}", style(":").dim());
+    print_with_style("", "owner", account.owner.to_string());
+    print_with_style(
+        "",
+        "balance",
+        format!("◎ {}", account.lamports as f64 / LAMPORTS_PER_SOL as f64),
+    );
+    print_with_style("", "size", format!("{} bytes", data.len()));
+
+    let discriminator = data.get(..8).unwrap_or_default();
+
+    if (account.owner == CANDY_MACHINE_ID || account.owner == CORE_CANDY_MACHINE_ID)
+        && discriminator == account_discriminator("CandyMachine")
+    {
+        let core = account.owner == CORE_CANDY_MACHINE_ID;
+        let candy_machine = if core {
+            CoreCandyMachine::deserialize(&mut &data[8..])?.to_candy_format()
+        } else {
+            CandyMachine::deserialize(&mut &data[8..])?
+        };
+        print_candy_machine(&candy_machine, data, core);
+    } else if account.owner == mpl_candy_guard::ID
+        && discriminator == account_discriminator("CandyGuard")
+    {
+        print_candy_guard(data)?;
+    } else if account.owner == mpl_candy_guard::ID
+        && discriminator == account_discriminator("FreezeEscrow")
+    {
+        print_freeze_escrow(&FreezeEscrow::try_deserialize(&mut &data[..])?);
+    } else if account.owner == CORE_CANDY_GUARD_ID {
+        // the guards of the core candy guard have a different layout, only the raw data
+        // is shown
+        print_with_style("", "type", "core candy guard (guards are not decoded)");
+        print_with_style(
+            "",
+            "data",
+            hex::encode(&data[..data.len().min(PREVIEW_LEN)]),
+        );
+    } else if account.owner == mpl_token_metadata::ID
+        && data.first() == Some(&(mpl_token_metadata::state::Key::MetadataV1 as u8))
+    {
+        print_metadata(&Metadata::safe_deserialize(data)?);
+    } else {
+        print_with_style("", "type", style("unknown").yellow());
+        print_with_style(
+            "",
+            "data",
+            hex::encode(&data[..data.len().min(PREVIEW_LEN)]),
+        );
+
+        return Err(anyhow!(
+            "Account {} is not a candy machine, candy guard, freeze escrow or metadata account.",
+            address
+        ));
+    }
+
+    Ok(())
+}
+
+/// Discriminator of an Anchor account.
+fn account_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("account:{name}").as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+fn print_candy_machine(candy_machine: &CandyMachine, data: &[u8], core: bool) {
+    let cndy_data = &candy_machine.data;
+
+    print_with_style(
+        "",
+        "type",
+        if core {
+            "core candy machine"
+        } else {
+            "candy machine"
+        },
+    );
+    print_with_style("", "version", format!("{:?}", candy_machine.version));
+    print_with_style(
+        "",
+        "token standard",
+        candy_machine.token_standard.to_string(),
+    );
+    print_with_style("", "features", format!("{:?}", candy_machine.features));
+    print_with_style("", "authority", candy_machine.authority.to_string());
+    print_with_style(
+        "",
+        "mint authority",
+        candy_machine.mint_authority.to_string(),
+    );
+    print_with_style(
+        "",
+        "collection mint",
+        candy_machine.collection_mint.to_string(),
+    );
+    print_with_style(
+        "",
+        "items redeemed",
+        candy_machine.items_redeemed.to_string(),
+    );
+    print_with_style("", "data", EMPTY_STR);
+    print_with_style(
+        ":   ",
+        "items available",
+        cndy_data.items_available.to_string(),
+    );
+    print_with_style(
+        ":   ",
+        "symbol",
+        cndy_data.symbol.trim_end_matches(NULL_STRING),
+    );
+    print_with_style(
+        ":   ",
+        "seller fee basis points",
+        cndy_data.seller_fee_basis_points.to_string(),
+    );
+    print_with_style(":   ", "max supply", cndy_data.max_supply.to_string());
+    print_with_style(":   ", "is mutable", cndy_data.is_mutable.to_string());
+    print_with_style(":   ", "creators", cndy_data.creators.len().to_string());
+
+    for creator in &cndy_data.creators {
+        print_with_style(
+            ":   :   ",
+            &creator.address.to_string(),
+            format!(
+                "{}%{}",
+                creator.percentage_share,
+                if creator.verified { ", verified" } else { "" }
+            ),
+        );
+    }
+
+    match &cndy_data.config_line_settings {
+        Some(settings) => {
+            print_with_style(":   ", "config line settings", EMPTY_STR);
+            print_with_style(":   :   ", "prefix name", &settings.prefix_name);
+            print_with_style(":   :   ", "name length", settings.name_length.to_string());
+            print_with_style(":   :   ", "prefix uri", &settings.prefix_uri);
+            print_with_style(":   :   ", "uri length", settings.uri_length.to_string());
+            print_with_style(
+                ":   :   ",
+                "is sequential",
+                settings.is_sequential.to_string(),
+            );
+        }
+        None => print_with_style(":   ", "config line settings", "none"),
+    }
+
+    match &cndy_data.hidden_settings {
+        Some(settings) => {
+            print_with_style(":   ", "hidden settings", EMPTY_STR);
+            print_with_style(":   :   ", "name", &settings.name);
+            print_with_style(":   :   ", "uri", &settings.uri);
+            print_with_style(":   :   ", "hash", String::from_utf8_lossy(&settings.hash));
+        }
+        None => print_with_style(":   ", "hidden settings", "none"),
+    }
+
+    // the hidden section only exists for candy machines with config lines
+    if cndy_data.config_line_settings.is_none() {
+        return;
+    }
+
+    let hidden_section = if core {
+        CORE_HIDDEN_SECTION
+    } else {
+        HIDDEN_SECTION
+    };
+    let items_available = cndy_data.items_available as usize;
+    let line_size = cndy_data.get_config_line_size();
+    let bitmask_start = hidden_section + 4 + items_available * line_size;
+    let bitmask_len = items_available / 8 + 1;
+    let indices_end = bitmask_start + bitmask_len + items_available * 4;
+
+    print_with_style("", "config lines", EMPTY_STR);
+    print_with_style(":   ", "line size", format!("{line_size} bytes"));
+
+    if data.len() < indices_end {
+        print_with_style(
+            ":   ",
+            "status",
+            style(format!(
+                "account data too small ({} bytes, expected {})",
+                data.len(),
+                indices_end
+            ))
+            .red(),
+        );
+        return;
+    }
+
+    let loaded = u32::from_le_bytes(
+        data[hidden_section..hidden_section + 4]
+            .try_into()
+            .expect("Invalid slice length"),
+    );
+    let bitmask_loaded: u32 = data[bitmask_start..bitmask_start + bitmask_len]
+        .iter()
+        .map(|byte| byte.count_ones())
+        .sum();
+
+    print_with_style(
+        ":   ",
+        "items loaded",
+        format!("{loaded}/{items_available}"),
+    );
+    print_with_style(
+        ":   ",
+        "loaded bitmask",
+        if bitmask_loaded == loaded {
+            bitmask_loaded.to_string()
+        } else {
+            style(format!(
+                "{bitmask_loaded} (does not match the items loaded)"
+            ))
+            .red()
+            .to_string()
+        },
+    );
+    print_with_style(
+        ":   ",
+        "remaining indices",
+        (cndy_data.items_available - candy_machine.items_redeemed).to_string(),
+    );
+}
+
+fn print_candy_guard(data: &[u8]) -> Result<()> {
+    // base (32) + bump (1) + authority (32)
+    let base = Pubkey::try_from(&data[8..40]).expect("Invalid slice length");
+    let authority = Pubkey::try_from(&data[41..73]).expect("Invalid slice length");
+    let guards = CandyGuardData::load(&data[DATA_OFFSET..])?;
+
+    print_with_style("", "type", "candy guard");
+    print_with_style("", "base", base.to_string());
+    print_with_style("", "bump", data[40].to_string());
+    print_with_style("", "authority", authority.to_string());
+    print_with_style("", "default", EMPTY_STR);
+    print_guard_set(&guards.default, ":   ".to_string())?;
+
+    if let Some(groups) = &guards.groups {
+        print_with_style("", "groups", groups.len().to_string());
+
+        for group in groups {
+            print_with_style(":   ", &group.label, EMPTY_STR);
+            print_guard_set(&group.guards, ":   :   ".to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn print_freeze_escrow(escrow: &FreezeEscrow) {
+    print_with_style("", "type", "freeze escrow");
+    print_with_style("", "candy guard", escrow.candy_guard.to_string());
+    print_with_style("", "candy machine", escrow.candy_machine.to_string());
+    print_with_style("", "destination", escrow.destination.to_string());
+    print_with_style("", "authority", escrow.authority.to_string());
+    print_with_style("", "frozen count", escrow.frozen_count.to_string());
+    print_with_style(
+        "",
+        "first mint time",
+        escrow
+            .first_mint_time
+            .map_or("none".to_string(), format_timestamp),
+    );
+    print_with_style(
+        "",
+        "freeze period",
+        format!("{} seconds", escrow.freeze_period),
+    );
+}
+
+fn print_metadata(metadata: &Metadata) {
+    let data = &metadata.data;
+
+    print_with_style("", "type", "metadata");
+    print_with_style("", "mint", metadata.mint.to_string());
+    print_with_style(
+        "",
+        "update authority",
+        metadata.update_authority.to_string(),
+    );
+    print_with_style("", "name", data.name.trim_end_matches(NULL_STRING));
+    print_with_style("", "symbol", data.symbol.trim_end_matches(NULL_STRING));
+    print_with_style("", "uri", data.uri.trim_end_matches(NULL_STRING));
+    print_with_style(
+        "",
+        "seller fee basis points",
+        data.seller_fee_basis_points.to_string(),
+    );
+
+    match &data.creators {
+        Some(creators) => {
+            print_with_style("", "creators", creators.len().to_string());
+
+            for creator in creators {
+                print_with_style(
+                    ":   ",
+                    &creator.address.to_string(),
+                    format!(
+                        "{}%{}",
+                        creator.share,
+                        if creator.verified { ", verified" } else { "" }
+                    ),
+                );
+            }
+        }
+        None => print_with_style("", "creators", "none"),
+    }
+
+    print_with_style(
+        "",
+        "primary sale happened",
+        metadata.primary_sale_happened.to_string(),
+    );
+    print_with_style("", "is mutable", metadata.is_mutable.to_string());
+    print_with_style("", "edition nonce", format!("{:?}", metadata.edition_nonce));
+    print_with_style(
+        "",
+        "token standard",
+        format!("{:?}", metadata.token_standard),
+    );
+    print_with_style("", "collection", format!("{:?}", metadata.collection));
+    print_with_style(
+        "",
+        "collection details",
+        format!("{:?}", metadata.collection_details),
+    );
+    print_with_style("", "uses", format!("{:?}", metadata.uses));
+    print_with_style(
+        "",
+        "programmable config",
+        format!("{:?}", metadata.programmable_config),
+    );
+}
+
+#[test]
+fn test_anchor_discriminators() {
+    // sha256("account:CandyMachine")[..8]
+    assert_eq!(
+        account_discriminator("CandyMachine"),
+        [51, 173, 177, 113, 25, 241, 109, 189]
+    );
+}
@@ -15,9 +15,12 @@ use spl_token::{
 use crate::{
     candy_machine::CANDY_MACHINE_ID,
     common::*,
-    config::ConfigData,
+    config::{ConfigData, Creator as ConfigCreator},
+    core_candy_machine::{collection_plugins, create_collection_ix},
+    dry_run::SendOrPlan,
     pdas::{find_master_edition_pda, find_metadata_pda},
     setup::SugarClient,
+    validate::ValidateParserError,
 };
 
 pub fn create_collection(
@@ -30,7 +33,7 @@ pub fn create_collection(
     let payer = program.payer();
 
     let collection_mint = Keypair::new();
-    let collection_item: &mut CacheItem = match cache.items.get_mut("-1") {
+    let collection_item: &mut CacheItem = match cache.items.get_mut(COLLECTION_CACHE_KEY) {
         Some(item) => item,
         None => {
             return Err(anyhow!("Trying to create and set collection when collection item info isn't in cache! This shouldn't happen!"));
@@ -76,11 +79,8 @@ pub fn create_collection(
         1,
     )?;
 
-    let creator = Creator {
-        address: payer,
-        verified: true,
-        share: 100,
-    };
+    let collection_config = config_data.collection.clone().unwrap_or_default();
+    let creators = collection_creators(collection_config.creators.as_deref(), payer)?;
     let collection_metadata_pubkey = find_metadata_pda(&collection_mint.pubkey());
 
     let create_metadata_account_ix = create_metadata_accounts_v3(
@@ -91,10 +91,12 @@ pub fn create_collection(
         payer,
         payer,
         collection_item.name.clone(),
-        config_data.symbol.clone(),
+        collection_config
+            .symbol
+            .unwrap_or_else(|| config_data.symbol.clone()),
         collection_item.metadata_link.clone(),
-        Some(vec![creator]),
-        0,
+        Some(creators),
+        collection_config.seller_fee_basis_points.unwrap_or(0),
         true,
         true,
         None,
@@ -129,11 +131,131 @@ pub fn create_collection(
         .instruction(create_metadata_account_ix)
         .instruction(create_master_edition_ix);
 
-    let sig = builder.send()?;
+    let sig = builder.send_or_plan("create collection")?;
 
     collection_item.on_chain = true;
+    collection_item.set_status(ItemStatus::Deployed);
     cache.program.collection_mint = collection_mint.pubkey().to_string();
     cache.sync_file()?;
 
     Ok((sig, collection_mint.pubkey()))
 }
+
+/// Returns the creators of the collection NFT: the payer is the only creator when none
+/// are configured and it can only verify itself.
+fn collection_creators(creators: Option<&[ConfigCreator]>, payer: Pubkey) -> Result<Vec<Creator>> {
+    let creators = match creators {
+        Some(creators) if !creators.is_empty() => creators,
+        _ => {
+            return Ok(vec![Creator {
+                address: payer,
+                verified: true,
+                share: 100,
+            }])
+        }
+    };
+
+    check_creator_shares(creators)?;
+
+    Ok(creators
+        .iter()
+        .map(|creator| Creator {
+            address: creator.address,
+            verified: creator.address == payer,
+            share: creator.share,
+        })
+        .collect())
+}
+
+/// Checks that the shares of the collection creators add up to 100.
+fn check_creator_shares(creators: &[ConfigCreator]) -> Result<()> {
+    if !creators.is_empty()
+        && creators
+            .iter()
+            .map(|creator| creator.share as u16)
+            .sum::<u16>()
+            != 100
+    {
+        return Err(ValidateParserError::InvalidCreatorShare.into());
+    }
+
+    Ok(())
+}
+
+/// Create a Core collection with the royalties and plugins of the config.
+pub fn create_core_collection(
+    client: &SugarClient,
+    cache: &mut Cache,
+    config_data: &ConfigData,
+) -> Result<(Signature, Pubkey)> {
+    let program = client.program(CANDY_MACHINE_ID);
+    let payer = program.payer();
+
+    if let Some(creators) = config_data
+        .collection
+        .as_ref()
+        .and_then(|collection| collection.creators.as_deref())
+    {
+        check_creator_shares(creators)?;
+    }
+
+    let collection = Keypair::new();
+    let collection_item: &mut CacheItem = match cache.items.get_mut(COLLECTION_CACHE_KEY) {
+        Some(item) => item,
+        None => {
+            return Err(anyhow!("Trying to create and set collection when collection item info isn't in cache! This shouldn't happen!"));
+        }
+    };
+
+    let create_collection_ix = create_collection_ix(
+        &collection.pubkey(),
+        &payer,
+        collection_item.name.clone(),
+        collection_item.metadata_link.clone(),
+        collection_plugins(config_data),
+    )?;
+
+    let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
+    let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
+
+    let sig = program
+        .request()
+        .instruction(compute_units)
+        .instruction(priority_fee)
+        .instruction(create_collection_ix)
+        .signer(&collection)
+        .send_or_plan("create collection")?;
+
+    collection_item.on_chain = true;
+    collection_item.set_status(ItemStatus::Deployed);
+    cache.program.collection_mint = collection.pubkey().to_string();
+    cache.sync_file()?;
+
+    Ok((sig, collection.pubkey()))
+}
+
+#[test]
+fn test_collection_creators_default_to_payer() {
+    let payer = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+
+    let creators = collection_creators(None, payer).unwrap();
+    assert_eq!(creators.len(), 1);
+    assert!(creators[0].verified);
+
+    let configured = vec![
+        ConfigCreator {
+            address: other,
+            share: 60,
+        },
+        ConfigCreator {
+            address: payer,
+            share: 40,
+        },
+    ];
+    let creators = collection_creators(Some(&configured), payer).unwrap();
+    assert!(!creators[0].verified);
+    assert!(creators[1].verified);
+
+    assert!(collection_creators(Some(&configured[..1]), payer).is_err());
+}
@@ -20,8 +20,17 @@ pub use mpl_token_metadata::state::{
 };
 
 use crate::{
-    cache::*, candy_machine::CANDY_MACHINE_ID, common::*, config::data::*, deploy::errors::*,
-    setup::setup_client, utils::*,
+    cache::*,
+    common::*,
+    config::data::*,
+    confirmation::{commitment, Commitment, ConfirmationTracker, MAX_RESENDS},
+    core_candy_machine::get_candy_machine_program,
+    deploy::{config_line_order, errors::*, item_index},
+    dry_run::{is_dry_run, SendOrPlan},
+    fee_payers::{setup_fee_payer_client, FeePayerPool},
+    journal::{EntryStatus, Journal},
+    rate_limit::{throttle_async, SEND_TRANSACTION},
+    utils::*,
 };
 
 /// The maximum config line bytes per transaction.
@@ -30,7 +39,12 @@ const MAX_TRANSACTION_BYTES: usize = 1000;
 /// The maximum number of config lines per transaction.
 const MAX_TRANSACTION_LINES: usize = 17;
 
+/// The lamports paid for each config line transaction: the fee payer and authority
+/// signatures plus the priority fee.
+pub const CONFIG_LINES_TRANSACTION_COST: u64 = 2 * 5_000 + (COMPUTE_UNITS as u64 * 500) / 1_000_000;
+
 pub struct TxInfo {
+    program_id: Pubkey,
     candy_pubkey: Pubkey,
     payer: Keypair,
     chunk: Vec<(u32, ConfigLine)>,
@@ -41,6 +55,8 @@ pub fn generate_config_lines(
     num_items: u64,
     cache_items: &CacheItems,
     data: &CandyMachineData,
+    selection: Option<&IndexSelection>,
+    order: &[u32],
 ) -> Result<Vec<Vec<(u32, ConfigLine)>>> {
     let mut config_lines: Vec<Vec<(u32, ConfigLine)>> = Vec::new();
     let mut current: Vec<(u32, ConfigLine)> = Vec::new();
@@ -56,17 +72,23 @@ pub fn generate_config_lines(
     let uri_offset = config_line_settings.prefix_uri.len();
 
     for i in 0..num_items {
-        let item = match cache_items.get(&i.to_string()) {
+        // the config line i holds the item at position i of the (shuffled) order
+        let index = item_index(order, i);
+        let item = match cache_items.get(&index.to_string()) {
             Some(item) => item,
             None => {
-                return Err(
-                    DeployError::AddConfigLineFailed(format!("Missing cache item {}", i)).into(),
-                );
+                return Err(DeployError::AddConfigLineFailed(format!(
+                    "Missing cache item {}",
+                    index
+                ))
+                .into());
             }
         };
 
-        if item.on_chain {
-            // if the current item is on-chain already, store the previous
+        let selected = selection.map_or(true, |selection| selection.contains(index));
+
+        if item.on_chain || !selected {
+            // if the current item is on-chain already (or not selected), store the previous
             // items as a transaction since we cannot have gaps in the indices
             // to write the config lines
             if !current.is_empty() {
@@ -101,124 +123,298 @@ pub fn generate_config_lines(
     Ok(config_lines)
 }
 
-/// Send the config lines to the candy machine program.
+/// Journal key of a batch of config lines, formed by its first and last indices.
+pub fn batch_key(chunk: &[(u32, ConfigLine)]) -> String {
+    match (chunk.first(), chunk.last()) {
+        (Some((first, _)), Some((last, _))) => format!("{first}-{last}"),
+        _ => String::new(),
+    }
+}
+
+/// Mark the items of the batches completed in a previous run as on-chain, so they
+/// are not sent again. Batches still pending are sent again, since writing the same
+/// config lines twice is harmless.
+pub fn apply_journal(cache: &mut Cache, journal: &Journal) -> Result<()> {
+    let completed = journal.keys(EntryStatus::Completed);
+
+    if completed.is_empty() {
+        return Ok(());
+    }
+
+    let order = config_line_order(cache);
+
+    for key in &completed {
+        let (first, last) = key
+            .split_once('-')
+            .and_then(|(first, last)| Some((first.parse::<u32>().ok()?, last.parse::<u32>().ok()?)))
+            .ok_or_else(|| anyhow!("Invalid config line batch in journal: {}", key))?;
+
+        for index in first..=last {
+            let index = item_index(&order, index as u64);
+
+            if let Some(item) = cache.items.get_mut(&index.to_string()) {
+                item.on_chain = true;
+                item.set_status(ItemStatus::Deployed);
+            }
+        }
+    }
+
+    println!(
+        "Resuming: {} config line transaction(s) confirmed in the previous run",
+        completed.len()
+    );
+    cache.sync_file()?;
+
+    Ok(())
+}
+
+/// Send the config lines to the candy machine program, recording each batch in the
+/// journal (if any) so an interrupted upload can be resumed.
+///
+/// With the processed commitment, the batches are sent without waiting for them to be
+/// finalized: a background tracker confirms them and the dropped batches are sent again.
 pub async fn upload_config_lines(
     sugar_config: Arc<SugarConfig>,
     candy_pubkey: Pubkey,
     cache: &mut Cache,
     config_lines: Vec<Vec<(u32, ConfigLine)>>,
     interrupted: Arc<AtomicBool>,
+    journal: Option<&Journal>,
+    fee_payers: Option<&FeePayerPool>,
 ) -> Result<Vec<DeployError>> {
     println!(
         "Sending config line(s) in {} transaction(s): (Ctrl+C to abort)",
         config_lines.len()
     );
 
-    let pb = progress_bar_with_style(config_lines.len() as u64);
-
     debug!("Num of config line chunks: {:?}", config_lines.len());
     info!("Uploading config lines in chunks...");
 
-    let mut transactions = Vec::new();
-
-    for chunk in config_lines {
-        let keypair = bs58::encode(sugar_config.keypair.to_bytes()).into_string();
-        let payer = Keypair::from_base58_string(&keypair);
-
-        transactions.push(TxInfo {
-            candy_pubkey,
-            payer,
-            chunk,
-        });
-    }
-
-    let mut handles = Vec::new();
-
-    for tx in transactions.drain(0..cmp::min(transactions.len(), PARALLEL_LIMIT)) {
-        let config = sugar_config.clone();
-        handles.push(tokio::spawn(
-            async move { add_config_lines(config, tx).await },
-        ));
-    }
-
+    // both candy machine programs share the add_config_lines instruction
+    let program_id = get_candy_machine_program(&sugar_config, &candy_pubkey)?;
+    let order = config_line_order(cache);
+    // planned transactions are not sent, so there is nothing to confirm
+    let track = commitment() == Commitment::Processed && !is_dry_run();
+    let mut config_lines = config_lines;
     let mut errors = Vec::new();
+    let mut aborted = false;
+
+    for round in 0..=MAX_RESENDS {
+        let pb = progress_bar_with_style(config_lines.len() as u64);
+        let tracker = track.then(|| ConfirmationTracker::start(sugar_config.rpc_url.clone()));
+        // copies of the batches, sent again if they are not finalized
+        let mut batches: HashMap<String, Vec<(u32, ConfigLine)>> = if track {
+            config_lines
+                .iter()
+                .map(|chunk| (batch_key(chunk), copy_chunk(chunk)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let mut transactions = Vec::new();
+
+        for chunk in config_lines.drain(..) {
+            let payer = match fee_payers {
+                Some(pool) => Keypair::from_bytes(&pool.next().to_bytes())?,
+                None => {
+                    let keypair = bs58::encode(sugar_config.keypair.to_bytes()).into_string();
+                    Keypair::from_base58_string(&keypair)
+                }
+            };
 
-    while !interrupted.load(Ordering::SeqCst) && !handles.is_empty() {
-        match select_all(handles).await {
-            (Ok(res), _index, remaining) => {
-                // independently if the upload was successful or not
-                // we continue to try the remaining ones
-                handles = remaining;
+            transactions.push(TxInfo {
+                program_id,
+                candy_pubkey,
+                payer,
+                chunk,
+            });
+        }
 
-                if res.is_ok() {
-                    let indices = res?;
+        let mut handles = Vec::new();
 
-                    for index in indices {
-                        let item = cache.items.get_mut(&index.to_string()).unwrap();
-                        item.on_chain = true;
+        for tx in transactions.drain(0..cmp::min(transactions.len(), PARALLEL_LIMIT)) {
+            if let Some(journal) = journal {
+                journal.mark_pending(&batch_key(&tx.chunk))?;
+            }
+            let config = sugar_config.clone();
+            handles.push(tokio::spawn(
+                async move { add_config_lines(config, tx).await },
+            ));
+        }
+
+        while !interrupted.load(Ordering::SeqCst) && !handles.is_empty() {
+            match select_all(handles).await {
+                (Ok(res), _index, remaining) => {
+                    // independently if the upload was successful or not
+                    // we continue to try the remaining ones
+                    handles = remaining;
+
+                    if res.is_ok() {
+                        let (indices, signature) = res?;
+                        let key = match (indices.first(), indices.last()) {
+                            (Some(first), Some(last)) => format!("{first}-{last}"),
+                            _ => String::new(),
+                        };
+
+                        match &tracker {
+                            // marked as deployed once finalized
+                            Some(tracker) => tracker.track(signature, key),
+                            None => {
+                                if let Some(journal) = journal {
+                                    journal.mark_completed(&key, None)?;
+                                }
+                                mark_deployed(cache, &order, &indices);
+                            }
+                        }
+                        // updates the progress bar
+                        pb.inc(1);
+                    } else {
+                        // user will need to retry the upload
+                        errors.push(DeployError::AddConfigLineFailed(format!(
+                            "Transaction error: {:?}",
+                            res.err().unwrap()
+                        )));
                     }
-                    // updates the progress bar
-                    pb.inc(1);
-                } else {
+                }
+                (Err(err), _index, remaining) => {
                     // user will need to retry the upload
                     errors.push(DeployError::AddConfigLineFailed(format!(
                         "Transaction error: {:?}",
-                        res.err().unwrap()
+                        err
                     )));
+                    // ignoring all errors
+                    handles = remaining;
                 }
             }
-            (Err(err), _index, remaining) => {
-                // user will need to retry the upload
-                errors.push(DeployError::AddConfigLineFailed(format!(
-                    "Transaction error: {:?}",
-                    err
-                )));
-                // ignoring all errors
-                handles = remaining;
+
+            if !transactions.is_empty() {
+                // if we are half way through, let spawn more transactions
+                if (PARALLEL_LIMIT - handles.len()) > (PARALLEL_LIMIT / 2) {
+                    // saves the progress to the cache file
+                    cache.sync_file()?;
+
+                    for tx in
+                        transactions.drain(0..cmp::min(transactions.len(), PARALLEL_LIMIT / 2))
+                    {
+                        if let Some(journal) = journal {
+                            journal.mark_pending(&batch_key(&tx.chunk))?;
+                        }
+                        let config = sugar_config.clone();
+                        handles.push(tokio::spawn(
+                            async move { add_config_lines(config, tx).await },
+                        ));
+                    }
+                }
             }
         }
 
-        if !transactions.is_empty() {
-            // if we are half way through, let spawn more transactions
-            if (PARALLEL_LIMIT - handles.len()) > (PARALLEL_LIMIT / 2) {
-                // saves the progress to the cache file
-                cache.sync_file()?;
-
-                for tx in transactions.drain(0..cmp::min(transactions.len(), PARALLEL_LIMIT / 2)) {
-                    let config = sugar_config.clone();
-                    handles.push(tokio::spawn(
-                        async move { add_config_lines(config, tx).await },
-                    ));
-                }
+        if !errors.is_empty() {
+            pb.abandon_with_message(format!("{}", style("Deploy failed ").red().bold()));
+        } else if !transactions.is_empty() {
+            pb.abandon_with_message(format!("{}", style("Upload aborted ").red().bold()));
+            aborted = true;
+        } else {
+            pb.finish_with_message(format!(
+                "{}",
+                style("Write config lines successful ").green().bold()
+            ));
+        }
+
+        let tracker = match tracker {
+            Some(tracker) => tracker,
+            None => break,
+        };
+
+        let spinner = spinner_with_style();
+        spinner.set_message("Waiting for the config lines to be finalized...");
+        let confirmations = tracker.finish().await?;
+        spinner.finish_and_clear();
+
+        for key in &confirmations.finalized {
+            if let (Some(journal), false) = (journal, key.is_empty()) {
+                journal.mark_completed(key, None)?;
             }
+            if let Some(chunk) = batches.get(key) {
+                let indices: Vec<u32> = chunk.iter().map(|(index, _)| *index).collect();
+                mark_deployed(cache, &order, &indices);
+            }
+        }
+        cache.sync_file()?;
+
+        if confirmations.failed.is_empty() || aborted {
+            break;
+        }
+
+        if round == MAX_RESENDS {
+            errors.push(DeployError::AddConfigLineFailed(format!(
+                "{} transaction(s) were not finalized.",
+                confirmations.failed.len()
+            )));
+            break;
         }
+
+        println!(
+            "Sending again {} transaction(s) that were not finalized:",
+            confirmations.failed.len()
+        );
+        config_lines = confirmations
+            .failed
+            .iter()
+            .filter_map(|key| batches.remove(key))
+            .collect();
     }
 
-    if !errors.is_empty() {
-        pb.abandon_with_message(format!("{}", style("Deploy failed ").red().bold()));
-    } else if !transactions.is_empty() {
-        pb.abandon_with_message(format!("{}", style("Upload aborted ").red().bold()));
+    // makes sure the cache file is updated
+    cache.sync_file()?;
+
+    if aborted {
         return Err(DeployError::AddConfigLineFailed(
             "Not all config lines were deployed.".to_string(),
         )
         .into());
-    } else {
-        pb.finish_with_message(format!(
-            "{}",
-            style("Write config lines successful ").green().bold()
-        ));
     }
 
-    // makes sure the cache file is updated
-    cache.sync_file()?;
-
     Ok(errors)
 }
 
+/// Marks the items written at the config lines as deployed.
+fn mark_deployed(cache: &mut Cache, order: &[u32], indices: &[u32]) {
+    for index in indices {
+        let index = item_index(order, *index as u64);
+        if let Some(item) = cache.items.get_mut(&index.to_string()) {
+            item.on_chain = true;
+            item.set_status(ItemStatus::Deployed);
+        }
+    }
+}
+
+/// Copy of a batch of config lines (`ConfigLine` does not implement clone).
+fn copy_chunk(chunk: &[(u32, ConfigLine)]) -> Vec<(u32, ConfigLine)> {
+    chunk
+        .iter()
+        .map(|(index, line)| {
+            (
+                *index,
+                ConfigLine {
+                    name: line.name.clone(),
+                    uri: line.uri.clone(),
+                },
+            )
+        })
+        .collect()
+}
+
 /// Send the `add_config_lines` instruction to the candy machine program.
-pub async fn add_config_lines(config: Arc<SugarConfig>, tx_info: TxInfo) -> Result<Vec<u32>> {
-    let client = setup_client(&config)?;
-    let program = client.program(CANDY_MACHINE_ID);
+pub async fn add_config_lines(
+    config: Arc<SugarConfig>,
+    tx_info: TxInfo,
+) -> Result<(Vec<u32>, Signature)> {
+    // waits before the client is created, since it cannot be held across an await
+    throttle_async(SEND_TRANSACTION).await;
+
+    // the payer of the transaction is the wallet itself or a fee payer of the pool
+    let client = setup_fee_payer_client(&config, &tx_info.payer)?;
+    let program = client.program(tx_info.program_id);
 
     // this will be used to update the cache
     let mut indices: Vec<u32> = Vec::new();
@@ -236,20 +432,20 @@ pub async fn add_config_lines(config: Arc<SugarConfig>, tx_info: TxInfo) -> Resu
     let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
     let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(500);
 
-    let _sig = program
+    let sig = program
         .request()
         .instruction(compute_units)
         .instruction(priority_fee)
         .accounts(nft_accounts::AddConfigLines {
             candy_machine: tx_info.candy_pubkey,
-            authority: program.payer(),
+            authority: config.keypair.pubkey(),
         })
         .args(nft_instruction::AddConfigLines {
             index: start_index,
             config_lines,
         })
-        .signer(&tx_info.payer)
-        .send()?;
+        .signer(&config.keypair)
+        .send_or_plan("add config lines")?;
 
-    Ok(indices)
+    Ok((indices, sig))
 }
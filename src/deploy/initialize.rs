@@ -7,6 +7,7 @@ use anchor_client::solana_sdk::{
     system_instruction, system_program,
 };
 use anyhow::Result;
+use console::style;
 use mpl_candy_machine_core::{
     accounts as nft_accounts, instruction as nft_instruction, CandyMachineData, ConfigLineSettings,
     Creator as CandyCreator,
@@ -18,14 +19,18 @@ use mpl_token_metadata::{
     instruction::MetadataDelegateRole, pda::find_metadata_delegate_record_account,
     state::TokenStandard,
 };
+use solana_client::rpc_client::RpcClient;
 use solana_program::native_token::LAMPORTS_PER_SOL;
 
 use crate::{
     common::*,
     config::data::*,
+    core_candy_machine::{initialize_ix, CoreCandyMachineData, CORE_CANDY_MACHINE_ID},
     deploy::errors::*,
+    dry_run::{is_dry_run, SendOrPlan},
     pdas::{find_candy_machine_creator_pda, find_master_edition_pda, find_metadata_pda},
     setup::SugarClient,
+    utils::{request_airdrop_if_needed, simulate_instructions},
 };
 
 /// Create the candy machine data struct.
@@ -131,8 +136,29 @@ pub fn create_candy_machine_data(
     Ok(data)
 }
 
+/// Checks that the payer can pay the rent of the candy machine account.
+async fn check_deploy_balance(rpc_client: &RpcClient, payer: &Pubkey, lamports: u64) -> Result<()> {
+    let balance = request_airdrop_if_needed(rpc_client, payer, lamports).await?;
+
+    if lamports > balance {
+        let error = DeployError::BalanceTooLow(
+            format!("{:.3}", (balance as f64 / LAMPORTS_PER_SOL as f64)),
+            format!("{:.3}", (lamports as f64 / LAMPORTS_PER_SOL as f64)),
+        );
+
+        if !is_dry_run() {
+            return Err(error.into());
+        }
+
+        // the plan of a dry run continues, reporting the missing funds
+        println!("{} {}", WARNING_EMOJI, style(error.to_string()).yellow());
+    }
+
+    Ok(())
+}
+
 /// Send the `initialize_candy_machine` instruction to the candy machine program.
-pub fn initialize_candy_machine<C: Deref<Target = impl Signer> + Clone>(
+pub async fn initialize_candy_machine<C: Deref<Target = impl Signer> + Clone>(
     config_data: &ConfigData,
 
     candy_account: &Keypair,
@@ -154,15 +180,7 @@ pub fn initialize_candy_machine<C: Deref<Target = impl Signer> + Clone>(
         .rpc()
         .get_minimum_balance_for_rent_exemption(candy_account_size)?;
 
-    let balance = program.rpc().get_account(&payer)?.lamports;
-
-    if lamports > balance {
-        return Err(DeployError::BalanceTooLow(
-            format!("{:.3}", (balance as f64 / LAMPORTS_PER_SOL as f64)),
-            format!("{:.3}", (lamports as f64 / LAMPORTS_PER_SOL as f64)),
-        )
-        .into());
-    }
+    check_deploy_balance(&program.rpc(), &payer, lamports).await?;
 
     // required PDAs
 
@@ -216,7 +234,74 @@ pub fn initialize_candy_machine<C: Deref<Target = impl Signer> + Clone>(
             >>::into(config_data.token_standard) as u8,
         });
 
-    let sig = tx.send()?;
+    simulate_instructions(&program.rpc(), &tx.instructions()?, &payer)?;
+
+    tx.send_or_plan("initialize candy machine")
+}
+
+/// Create the core candy machine data struct; royalties and creators are set on the
+/// Core collection.
+pub fn create_core_candy_machine_data(
+    client: &SugarClient,
+    config: &ConfigData,
+    cache: &Cache,
+) -> Result<CoreCandyMachineData> {
+    let data = create_candy_machine_data(client, config, cache)?;
+
+    Ok(CoreCandyMachineData {
+        items_available: data.items_available,
+        max_supply: data.max_supply,
+        is_mutable: data.is_mutable,
+        config_line_settings: data.config_line_settings,
+        hidden_settings: data.hidden_settings,
+    })
+}
+
+/// Send the `initialize` instruction to the core candy machine program.
+pub async fn initialize_core_candy_machine<C: Deref<Target = impl Signer> + Clone>(
+    candy_account: &Keypair,
+    candy_machine_data: CoreCandyMachineData,
+    collection: Pubkey,
+    program: Program<C>,
+) -> Result<Signature> {
+    let payer = program.payer();
+    let candy_account_size = candy_machine_data.get_space_for_candy()?;
+
+    info!(
+        "Initializing core candy machine with account size of: {} and address of: {}",
+        candy_account_size,
+        candy_account.pubkey().to_string()
+    );
+
+    let lamports = program
+        .rpc()
+        .get_minimum_balance_for_rent_exemption(candy_account_size)?;
+
+    check_deploy_balance(&program.rpc(), &payer, lamports).await?;
+
+    let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
+    let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
+
+    let tx = program
+        .request()
+        .instruction(compute_units)
+        .instruction(priority_fee)
+        .instruction(system_instruction::create_account(
+            &payer,
+            &candy_account.pubkey(),
+            lamports,
+            candy_account_size as u64,
+            &CORE_CANDY_MACHINE_ID,
+        ))
+        .signer(candy_account)
+        .instruction(initialize_ix(
+            &candy_account.pubkey(),
+            &payer,
+            &collection,
+            candy_machine_data,
+        )?);
+
+    simulate_instructions(&program.rpc(), &tx.instructions()?, &payer)?;
 
-    Ok(sig)
+    tx.send_or_plan("initialize candy machine")
 }
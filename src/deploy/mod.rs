@@ -3,9 +3,11 @@ pub mod config_lines;
 pub mod errors;
 pub mod initialize;
 pub mod process;
+pub mod shuffle;
 
 pub use collection::*;
 pub use config_lines::*;
 pub use errors::*;
 pub use initialize::*;
 pub use process::*;
+pub use shuffle::*;
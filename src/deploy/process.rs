@@ -20,15 +20,26 @@ use crate::{
     cache::*,
     candy_machine::{get_candy_machine_state, CANDY_MACHINE_ID},
     common::*,
-    config::parser::get_config_data,
+    config::{parser::get_config_data, AssetStandard},
+    core_candy_machine::{get_core_candy_machine_state, CORE_CANDY_MACHINE_ID},
     deploy::{
-        create_candy_machine_data, create_collection, errors::*, generate_config_lines,
-        initialize_candy_machine, upload_config_lines,
+        apply_journal, commit_seed, config_line_order, create_candy_machine_data,
+        create_collection, create_core_candy_machine_data, create_core_collection, errors::*,
+        generate_config_lines, initialize_candy_machine, initialize_core_candy_machine,
+        is_mixed_from, reveal_seed, upload_config_lines, CONFIG_LINES_TRANSACTION_COST,
+        SHUFFLE_REVEAL_SLOTS,
     },
+    dry_run::is_dry_run,
+    fee_payers::{fee_payer_funding, print_swept, sweep_fee_payers, FeePayerPool},
     hash::hash_and_update,
+    hooks::{run_hook, HookEvent},
+    journal::Journal,
+    lock::lock_file,
     pdas::find_metadata_pda,
+    rate_limit::throttled_client,
+    retry::{update_failures, Failure, FailureStage},
     setup::{setup_client, sugar_setup},
-    update::{process_update, UpdateArgs},
+    update::{process_update, UpdateArgs, UpdateFields},
     utils::*,
     validate::parser::{check_name, check_seller_fee_basis_points, check_symbol, check_url},
 };
@@ -40,12 +51,20 @@ pub struct DeployArgs {
     pub rpc_url: Option<String>,
     pub interrupted: Arc<AtomicBool>,
     pub collection_mint: Option<String>,
+    pub indices: Option<String>,
+    pub fee_payers: Option<usize>,
+    pub shuffle: Option<String>,
+    pub append: bool,
+    pub failures: String,
 }
 
 pub async fn process_deploy(args: DeployArgs) -> Result<()> {
+    // the cache is locked for the whole deploy, not only when it is written
+    lock_file(&args.cache)?;
     // loads the cache file (this needs to have been created by
     // the upload command)
     let mut cache = load_cache(&args.cache, false)?;
+    let selection = IndexSelection::parse(args.indices.as_deref())?;
 
     if cache.items.is_empty() {
         println!(
@@ -86,20 +105,112 @@ pub async fn process_deploy(args: DeployArgs) -> Result<()> {
 
     let num_items = config_data.number;
     let hidden = config_data.hidden_settings.is_some();
-    let collection_in_cache = cache.items.get("-1").is_some();
+    let core = config_data.standard == AssetStandard::Core;
+
+    if core && hidden {
+        // the hash of the hidden settings is updated with the candy machine core program
+        return Err(anyhow!(
+            "Hidden settings are not supported with the Core standard"
+        ));
+    }
+
+    let shuffle_seed = match &args.shuffle {
+        Some(seed) => {
+            if hidden {
+                return Err(anyhow!(
+                    "Shuffling is not supported with hidden settings, there are no config lines"
+                ));
+            }
+
+            // the order cannot change once config lines were written
+            let on_chain = cache
+                .items
+                .iter()
+                .any(|(key, item)| key != COLLECTION_CACHE_KEY && item.on_chain);
+
+            match (
+                &cache.program.shuffle_seed,
+                &cache.program.shuffle_commitment,
+            ) {
+                (Some(shuffle_seed), _) if is_mixed_from(shuffle_seed, seed) => {
+                    Some(shuffle_seed.clone())
+                }
+                _ if on_chain => {
+                    return Err(anyhow!(
+                        "Config lines were already deployed with a different order, the \
+                        shuffle seed cannot be changed."
+                    ));
+                }
+                (_, Some(commitment)) => {
+                    let rpc = throttled_client(&sugar_config.rpc_url);
+                    let (shuffle_seed, slot) = reveal_seed(&rpc, seed, commitment)?;
+                    println!(
+                        "Shuffle seed mixed with the blockhash of slot {}: '{}'",
+                        slot, shuffle_seed
+                    );
+                    Some(shuffle_seed)
+                }
+                (_, None) => {
+                    // the seed is committed before the blockhash mixed into it exists, so
+                    // it cannot be chosen to favour an order
+                    let rpc = throttled_client(&sugar_config.rpc_url);
+                    let commitment = commit_seed(seed, rpc.get_slot()?);
+                    println!(
+                        "{}",
+                        style(format!(
+                            "Shuffle seed committed with hash {} at slot {}.",
+                            commitment.hash, commitment.slot
+                        ))
+                        .bold()
+                    );
+                    println!(
+                        "Publish the hash, then run deploy again with the same seed once slot \
+                        {} is finalized.",
+                        commitment.slot + SHUFFLE_REVEAL_SLOTS
+                    );
+
+                    cache.program.shuffle_commitment = Some(commitment);
+                    cache.sync_file()?;
+
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
+
+    let collection_in_cache = cache.items.get(COLLECTION_CACHE_KEY).is_some();
 
     let cache_items_sans_collection = (cache.items.len() - collection_in_cache as usize) as u64;
 
-    if num_items != cache_items_sans_collection {
+    if args.append {
+        if args.shuffle.is_some() || cache.program.shuffle_seed.is_some() {
+            return Err(anyhow!(
+                "Items cannot be appended to shuffled config lines, the order depends on the \
+                number of items"
+            ));
+        }
+
+        count_appended_items(&cache, num_items, hidden)?;
+    } else if num_items != cache_items_sans_collection {
         return Err(anyhow!(
             "Number of items ({}) do not match cache items ({}). 
             Item number in the config should only include asset files, not the collection file.",
             num_items,
             cache_items_sans_collection
         ));
-    } else {
-        check_symbol(&config_data.symbol)?;
-        check_seller_fee_basis_points(config_data.seller_fee_basis_points)?;
+    }
+
+    check_symbol(&config_data.symbol)?;
+    check_seller_fee_basis_points(config_data.seller_fee_basis_points)?;
+
+    if let Some(collection) = &config_data.collection {
+        if let Some(symbol) = &collection.symbol {
+            check_symbol(symbol)?;
+        }
+        if let Some(seller_fee_basis_points) = collection.seller_fee_basis_points {
+            check_seller_fee_basis_points(seller_fee_basis_points)?;
+        }
     }
 
     let total_steps = 2 + if candy_machine_address.is_empty() {
@@ -108,24 +219,30 @@ pub async fn process_deploy(args: DeployArgs) -> Result<()> {
         0
     } - (hidden as u8);
 
+    // data of a candy machine that is only planned (dry run), used instead of the
+    // on-chain account to plan the config lines
+    let mut planned_data = None;
+
     let candy_pubkey = if candy_machine_address.is_empty() {
         let candy_keypair = Keypair::new();
         let candy_pubkey = candy_keypair.pubkey();
 
         // collection_item could be missing when args.collection_mint is provided
-        let collection_item = if let Some(collection_item) = cache.items.get_mut("-1") {
-            Some(collection_item)
-        } else {
-            match args.collection_mint {
-                Some(_) => None, // existing collection provided
-                None => return Err(anyhow!("Missing collection item in cache")),
-            }
-        };
+        let collection_item =
+            if let Some(collection_item) = cache.items.get_mut(COLLECTION_CACHE_KEY) {
+                Some(collection_item)
+            } else {
+                match args.collection_mint {
+                    Some(_) => None, // existing collection provided
+                    None => return Err(anyhow!("Missing collection item in cache")),
+                }
+            };
 
         println!(
-            "\n{} {}Creating collection NFT for candy machine",
+            "\n{} {}Creating collection {} for candy machine",
             style(format!("[1/{}]", total_steps)).bold().dim(),
-            COLLECTION_EMOJI
+            COLLECTION_EMOJI,
+            if core { "asset" } else { "NFT" }
         );
 
         let collection_minted = match collection_item {
@@ -142,10 +259,13 @@ pub async fn process_deploy(args: DeployArgs) -> Result<()> {
             Pubkey::from_str(&collection_str)?
         } else {
             let pb = spinner_with_style();
-            pb.set_message("Creating NFT...");
+            pb.set_message("Creating collection...");
 
-            let (_, collection_mint) =
-                create_collection(&client, candy_pubkey, &mut cache, &config_data)?;
+            let (_, collection_mint) = if core {
+                create_core_collection(&client, &mut cache, &config_data)?
+            } else {
+                create_collection(&client, candy_pubkey, &mut cache, &config_data)?
+            };
 
             pb.finish_and_clear();
             println!(
@@ -167,31 +287,56 @@ pub async fn process_deploy(args: DeployArgs) -> Result<()> {
         let spinner = spinner_with_style();
         spinner.set_message("Creating candy machine...");
 
-        let candy_data = create_candy_machine_data(&client, &config_data, &cache)?;
-        let program = client.program(CANDY_MACHINE_ID);
+        let sig = if core {
+            let candy_data = create_core_candy_machine_data(&client, &config_data, &cache)?;
+            let program = client.program(CORE_CANDY_MACHINE_ID);
 
-        // all good, let's create the candy machine
+            if is_dry_run() {
+                planned_data = Some(candy_data.to_candy_format());
+            }
 
-        let collection_metadata = find_metadata_pda(&collection_mint);
-        let data = program.rpc().get_account_data(&collection_metadata)?;
-        let metadata = Metadata::safe_deserialize(data.as_slice())?;
+            initialize_core_candy_machine(&candy_keypair, candy_data, collection_mint, program)
+                .await?
+        } else {
+            let candy_data = create_candy_machine_data(&client, &config_data, &cache)?;
+            let program = client.program(CANDY_MACHINE_ID);
+
+            // all good, let's create the candy machine
+
+            let update_authority = if is_dry_run() && !collection_minted {
+                // the collection created by sugar has the payer as update authority
+                program.payer()
+            } else {
+                let collection_metadata = find_metadata_pda(&collection_mint);
+                let data = program.rpc().get_account_data(&collection_metadata)?;
+                Metadata::safe_deserialize(data.as_slice())?.update_authority
+            };
+
+            if is_dry_run() {
+                planned_data = Some(candy_data.clone());
+            }
 
-        let sig = initialize_candy_machine(
-            &config_data,
-            &candy_keypair,
-            candy_data,
-            collection_mint,
-            metadata.update_authority,
-            program,
-        )?;
+            initialize_candy_machine(
+                &config_data,
+                &candy_keypair,
+                candy_data,
+                collection_mint,
+                update_authority,
+                program,
+            )
+            .await?
+        };
         info!("Candy machine initialized with sig: {}", sig);
         info!(
             "Candy machine created with address: {}",
             &candy_pubkey.to_string()
         );
 
+        let shuffle_commitment = cache.program.shuffle_commitment.take();
         cache.program = CacheProgram::new_from_cm(&candy_pubkey);
         cache.program.collection_mint = collection_mint.to_string();
+        cache.program.shuffle_seed = shuffle_seed.clone();
+        cache.program.shuffle_commitment = shuffle_commitment;
         cache.sync_file()?;
 
         spinner.finish_and_clear();
@@ -218,7 +363,13 @@ pub async fn process_deploy(args: DeployArgs) -> Result<()> {
             }
         };
 
-        if get_candy_machine_state(&Arc::clone(&sugar_config), &candy_pubkey).is_err() {
+        let candy_machine_exists = if core {
+            get_core_candy_machine_state(&sugar_config, &candy_pubkey).is_ok()
+        } else {
+            get_candy_machine_state(&sugar_config, &candy_pubkey).is_ok()
+        };
+
+        if !candy_machine_exists {
             println!(
                 "\n{} Candy machine {} not found on-chain",
                 WARNING_EMOJI, candy_machine_address
@@ -240,6 +391,9 @@ pub async fn process_deploy(args: DeployArgs) -> Result<()> {
 
     println!("{} {}", style("Candy machine ID:").bold(), candy_pubkey);
 
+    // the hidden settings update takes the config, so the hook is kept aside
+    let hooks = config_data.hooks.clone();
+
     // Hidden Settings check needs to be the last action in this command, so we can
     // update the hash with the final cache state.
     if !hidden {
@@ -256,40 +410,155 @@ pub async fn process_deploy(args: DeployArgs) -> Result<()> {
             PAPER_EMOJI
         );
 
-        let cndy_state = get_candy_machine_state(&sugar_config, &candy_pubkey)?;
-        let cndy_data = cndy_state.data;
+        let cndy_data = if let Some(data) = planned_data {
+            data
+        } else if core {
+            get_core_candy_machine_state(&sugar_config, &candy_pubkey)?
+                .data
+                .to_candy_format()
+        } else {
+            get_candy_machine_state(&sugar_config, &candy_pubkey)?.data
+        };
+
+        let journal = Journal::open("deploy", &candy_pubkey.to_string())?;
+        apply_journal(&mut cache, &journal)?;
+
+        // selected items are written again even if they are already on-chain
+        if let Some(selection) = &selection {
+            for (key, item) in cache.items.iter_mut() {
+                if selection.contains_key(key) {
+                    item.on_chain = false;
+                }
+            }
+        }
+
+        if shuffle_seed.is_some() {
+            cache.program.shuffle_seed = shuffle_seed.clone();
+            cache.sync_file()?;
+        }
+
+        if let Some(seed) = &cache.program.shuffle_seed {
+            println!(
+                "Config lines shuffled with seed '{}' (publish it to allow the order to be \
+                verified against the commitment)",
+                seed
+            );
+        }
+
+        // appended items are written after the ones already loaded
+        let num_lines = if args.append {
+            count_appended_items(&cache, cndy_data.items_available, false)?
+        } else {
+            num_items
+        };
 
-        let config_lines = generate_config_lines(num_items, &cache.items, &cndy_data)?;
+        let order = config_line_order(&cache);
+        let config_lines = generate_config_lines(
+            num_lines,
+            &cache.items,
+            &cndy_data,
+            selection.as_ref(),
+            &order,
+        )?;
 
         if config_lines.is_empty() {
+            journal.finish()?;
+            update_failures(
+                &args.failures,
+                &args.config,
+                &args.cache,
+                None,
+                FailureStage::Deploy,
+                None,
+            )?;
             println!("\nAll config lines deployed.");
         } else {
+            // config line transactions are paid round-robin by the fee payers of the pool
+            let fee_payers = match args.fee_payers {
+                Some(size) => {
+                    let pool = FeePayerPool::load_or_create(size)?;
+                    let funding = fee_payer_funding(
+                        &client.program(CANDY_MACHINE_ID).rpc(),
+                        config_lines.len() as u64,
+                        pool.len(),
+                        CONFIG_LINES_TRANSACTION_COST,
+                    )?;
+                    pool.fund(&sugar_config, funding).await?;
+                    Some(pool)
+                }
+                None => None,
+            };
+
             // clear the interruption handler value ahead of the upload
             args.interrupted.store(false, Ordering::SeqCst);
 
-            let errors = upload_config_lines(
+            let result = upload_config_lines(
                 Arc::clone(&sugar_config),
                 candy_pubkey,
                 &mut cache,
                 config_lines,
                 args.interrupted,
+                Some(&journal),
+                fee_payers.as_ref(),
             )
-            .await?;
+            .await;
 
-            if !errors.is_empty() {
+            if fee_payers.is_some() {
+                let swept = sweep_fee_payers(&sugar_config)?;
+                print_swept(&sugar_config.keypair.pubkey(), swept);
+            }
+
+            let errors = result?;
+
+            // the cache file now holds the outcome of every batch
+            journal.finish()?;
+
+            let error_count = errors.len();
+            let mut unique = HashSet::new();
+
+            for err in errors {
+                unique.insert(err.to_string());
+            }
+
+            // items of the selected config lines that were not written
+            let failed: Vec<isize> = cache
+                .items
+                .iter()
+                .filter(|(key, item)| {
+                    !item.on_chain
+                        && selection
+                            .as_ref()
+                            .map_or(true, |selection| selection.contains_key(key))
+                })
+                // the collection item is not a config line
+                .filter_map(|(key, _)| key.parse::<isize>().ok().filter(|index| *index >= 0))
+                .collect();
+
+            update_failures(
+                &args.failures,
+                &args.config,
+                &args.cache,
+                None,
+                FailureStage::Deploy,
+                (!failed.is_empty()).then(|| Failure {
+                    stage: FailureStage::Deploy,
+                    indices: failed,
+                    error: if unique.is_empty() {
+                        "Deploy interrupted.".to_string()
+                    } else {
+                        unique.iter().cloned().collect::<Vec<String>>().join("; ")
+                    },
+                }),
+            )?;
+
+            if error_count > 0 {
                 let mut message = String::new();
                 write!(
                     message,
                     "Failed to deploy all config lines, {0} error(s) occurred:",
-                    errors.len()
+                    error_count
                 )?;
 
-                let mut unique = HashSet::new();
-
-                for err in errors {
-                    unique.insert(err.to_string());
-                }
-
                 for u in unique {
                     message.push_str(&style("\n=> ").dim().to_string());
                     message.push_str(&u);
@@ -298,6 +567,14 @@ pub async fn process_deploy(args: DeployArgs) -> Result<()> {
                 return Err(DeployError::AddConfigLineFailed(message).into());
             }
         }
+
+        if num_lines < cndy_data.items_available {
+            println!(
+                "\n{}/{} config lines loaded, append the remaining items with 'sugar deploy \
+                --append' (the mint starts once all items are loaded).",
+                num_lines, cndy_data.items_available
+            );
+        }
     } else {
         // If hidden settings are enabled, update the hash value with the new cache file.
         println!("\nCandy machine with hidden settings deployed.");
@@ -316,10 +593,101 @@ pub async fn process_deploy(args: DeployArgs) -> Result<()> {
             new_authority: None,
             config: args.config,
             candy_machine: Some(candy_pubkey.to_string()),
+            fields: UpdateFields::default(),
         };
 
         process_update(update_args)?;
     }
 
+    run_hook(
+        hooks.as_ref(),
+        HookEvent::PostDeploy,
+        json!({
+            "candyMachine": candy_pubkey.to_string(),
+            "collectionMint": cache.program.collection_mint,
+            "items": cache.items.keys().filter(|key| *key != COLLECTION_CACHE_KEY).count(),
+            "hidden": hidden,
+        }),
+    )?;
+
     Ok(())
 }
+
+/// Checks the items of the cache appended to a candy machine with room for `num_items`
+/// items, which is the number of items of the config for hidden settings (updated with
+/// the new items). Returns the number of items of the cache, without the collection.
+fn count_appended_items(cache: &Cache, num_items: u64, hidden: bool) -> Result<u64> {
+    let cache_items = cache
+        .items
+        .keys()
+        .filter(|key| *key != COLLECTION_CACHE_KEY)
+        .count() as u64;
+
+    for index in 0..cache_items {
+        if !cache.items.contains_key(&index.to_string()) {
+            return Err(anyhow!(
+                "Missing cache item {}: appended items must continue from the current count \
+                of items.",
+                index
+            ));
+        }
+    }
+
+    if hidden && num_items != cache_items {
+        // the items available of hidden settings are updated from the config
+        return Err(anyhow!(
+            "Number of items ({}) do not match cache items ({}), update 'number' in the \
+            config to append the new items.",
+            num_items,
+            cache_items
+        ));
+    } else if !hidden && cache_items > num_items {
+        return Err(anyhow!(
+            "The candy machine has room for {} items but the cache has {}: the number of \
+            config lines cannot change once the candy machine is created.",
+            num_items,
+            cache_items
+        ));
+    }
+
+    Ok(cache_items)
+}
+
+#[test]
+fn test_count_appended_items() {
+    let mut cache = Cache::new();
+    let item = |name: &str| CacheItem {
+        name: name.to_string(),
+        image_hash: String::new(),
+        image_link: String::new(),
+        metadata_hash: String::new(),
+        metadata_link: String::new(),
+        on_chain: false,
+        animation_hash: None,
+        animation_link: None,
+        status: ItemStatus::Pending,
+        timestamps: ItemTimestamps::default(),
+    };
+
+    for index in 0..3 {
+        cache
+            .items
+            .insert(index.to_string(), item(&format!("#{index}")));
+    }
+    // the collection is not an item of the candy machine
+    cache
+        .items
+        .insert(COLLECTION_CACHE_KEY.to_string(), item("Collection"));
+
+    assert_eq!(count_appended_items(&cache, 5, false).unwrap(), 3);
+    assert_eq!(count_appended_items(&cache, 3, false).unwrap(), 3);
+    assert!(count_appended_items(&cache, 2, false).is_err());
+
+    // hidden settings grow with the config
+    assert_eq!(count_appended_items(&cache, 3, true).unwrap(), 3);
+    assert!(count_appended_items(&cache, 5, true).is_err());
+
+    // the appended items must continue from the current count
+    cache.items.insert("4".to_string(), item("#4"));
+    assert!(count_appended_items(&cache, 5, false).is_err());
+}
@@ -0,0 +1,141 @@
+use data_encoding::HEXLOWER;
+use sha2::{Digest, Sha256};
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcBlockConfig};
+use solana_transaction_status::TransactionDetails;
+
+use crate::{cache::ShuffleCommitment, common::*};
+
+/// Number of slots (about a minute) between the commitment of the seed and the block
+/// whose hash is mixed into it, so the seed cannot be chosen knowing the blockhash.
+pub const SHUFFLE_REVEAL_SLOTS: u64 = 150;
+
+/// Commits to a shuffle seed at the current slot; the commitment is published before
+/// the blockhash mixed into the seed exists.
+pub fn commit_seed(seed: &str, slot: u64) -> ShuffleCommitment {
+    ShuffleCommitment {
+        hash: seed_hash(seed),
+        slot,
+    }
+}
+
+fn seed_hash(seed: &str) -> String {
+    HEXLOWER.encode(&Sha256::digest(seed.as_bytes()))
+}
+
+/// Seed of the order: the committed seed followed by the hash of the first (finalized)
+/// block produced [`SHUFFLE_REVEAL_SLOTS`] after the commitment.
+pub fn mixed_seed(seed: &str, blockhash: &str) -> String {
+    format!("{seed}:{blockhash}")
+}
+
+/// Whether the order seed was mixed from the specified seed.
+pub fn is_mixed_from(shuffle_seed: &str, seed: &str) -> bool {
+    shuffle_seed
+        .strip_prefix(seed)
+        .map_or(false, |blockhash| blockhash.starts_with(':'))
+}
+
+/// Checks the seed against its commitment and mixes it with the blockhash of the reveal
+/// slot; fails while that block is not finalized.
+pub fn reveal_seed(
+    rpc: &RpcClient,
+    seed: &str,
+    commitment: &ShuffleCommitment,
+) -> Result<(String, u64)> {
+    if seed_hash(seed) != commitment.hash {
+        return Err(anyhow!(
+            "The shuffle seed does not match the committed hash '{}'.",
+            commitment.hash
+        ));
+    }
+
+    let reveal_slot = commitment.slot + SHUFFLE_REVEAL_SLOTS;
+    // skipped slots have no block, the first one produced is used
+    let slot = rpc
+        .get_blocks_with_limit_and_commitment(reveal_slot, 1, CommitmentConfig::finalized())?
+        .first()
+        .copied()
+        .ok_or_else(|| {
+            anyhow!(
+                "The blockhash mixed into the shuffle seed is not available yet, run deploy \
+                again once slot {} is finalized.",
+                reveal_slot
+            )
+        })?;
+
+    let block = rpc.get_block_with_config(
+        slot,
+        RpcBlockConfig {
+            transaction_details: Some(TransactionDetails::None),
+            rewards: Some(false),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+            ..RpcBlockConfig::default()
+        },
+    )?;
+
+    Ok((mixed_seed(seed, &block.blockhash), slot))
+}
+
+/// Returns the order of the items in the config lines: the item at position `i` of the
+/// candy machine is the cache item `order[i]`.
+///
+/// The order is a Fisher-Yates shuffle where the swap at position `i` uses the first 8
+/// bytes (little-endian) of `sha256("<seed>:<i>")`, so anyone with the published seed
+/// can recompute it.
+pub fn shuffled_order(seed: &str, num_items: u64) -> Vec<u32> {
+    let mut order: Vec<u32> = (0..num_items as u32).collect();
+
+    for i in (1..order.len()).rev() {
+        let hash = Sha256::digest(format!("{seed}:{i}").as_bytes());
+        let random = u64::from_le_bytes(hash[..8].try_into().expect("Invalid slice length"));
+        order.swap(i, (random % (i as u64 + 1)) as usize);
+    }
+
+    order
+}
+
+/// Returns the order of the config lines of the cache, shuffled when the candy machine
+/// was deployed with a shuffle seed.
+pub fn config_line_order(cache: &Cache) -> Vec<u32> {
+    let num_items = cache
+        .items
+        .keys()
+        .filter(|key| *key != COLLECTION_CACHE_KEY)
+        .count() as u64;
+
+    match &cache.program.shuffle_seed {
+        Some(seed) => shuffled_order(seed, num_items),
+        None => (0..num_items as u32).collect(),
+    }
+}
+
+/// Index of the cache item written at the config line `index`.
+pub fn item_index(order: &[u32], index: u64) -> u64 {
+    order.get(index as usize).map_or(index, |item| *item as u64)
+}
+
+#[test]
+fn test_shuffled_orders() {
+    let order = shuffled_order("sugar", 100);
+
+    assert_eq!(order, shuffled_order("sugar", 100));
+    assert_ne!(order, shuffled_order("candy", 100));
+    assert_ne!(order, (0..100).collect::<Vec<u32>>());
+
+    let mut sorted = order.clone();
+    sorted.sort_unstable();
+    assert_eq!(sorted, (0..100).collect::<Vec<u32>>());
+
+    assert_eq!(item_index(&order, 5), order[5] as u64);
+    assert_eq!(item_index(&[], 5), 5);
+
+    let commitment = commit_seed("sugar", 10);
+    assert_eq!(commitment.hash.len(), 64);
+    assert_ne!(commitment.hash, commit_seed("candy", 10).hash);
+
+    let seed = mixed_seed("sugar", "blockhash");
+    assert!(is_mixed_from(&seed, "sugar"));
+    assert!(!is_mixed_from(&seed, "suga"));
+    assert!(!is_mixed_from(&seed, "candy"));
+}
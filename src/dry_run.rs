@@ -0,0 +1,389 @@
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use anchor_client::{
+    solana_sdk::{
+        compute_budget::{self, ComputeBudgetInstruction},
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        system_instruction::SystemInstruction,
+        transaction::Transaction,
+    },
+    RequestBuilder,
+};
+use borsh::BorshDeserialize;
+use console::style;
+use lazy_static::lazy_static;
+use solana_client::rpc_client::RpcClient;
+use tabled::{builder::Builder, settings::Style};
+
+use crate::{common::*, costs::record_transaction, upload::format_bytes};
+
+/// Fee (in lamports) of each signature of a transaction.
+const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+/// Compute units of an instruction when the transaction does not set a limit.
+const DEFAULT_INSTRUCTION_COMPUTE_UNITS: u64 = 200_000;
+/// Maximum compute units of a transaction.
+const MAX_COMPUTE_UNITS: u64 = 1_400_000;
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref PLAN: Mutex<Plan> = Mutex::new(Plan::default());
+}
+
+/// Transaction that would be sent by the command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlannedTransaction {
+    description: String,
+    size: usize,
+    signatures: u64,
+    priority_fee: u64,
+}
+
+/// Account that would be created by a system program instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlannedAccount {
+    description: String,
+    address: Pubkey,
+    space: u64,
+    lamports: u64,
+}
+
+/// Files that would be sent to the storage provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlannedUpload {
+    description: String,
+    files: usize,
+    bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct Plan {
+    transactions: Vec<PlannedTransaction>,
+    accounts: Vec<PlannedAccount>,
+    uploads: Vec<PlannedUpload>,
+}
+
+impl Plan {
+    fn is_empty(&self) -> bool {
+        self.transactions.is_empty() && self.accounts.is_empty() && self.uploads.is_empty()
+    }
+
+    fn add_transaction(&mut self, description: &str, transaction: &Transaction) {
+        let message = &transaction.message;
+        let instructions: Vec<Instruction> = message
+            .instructions
+            .iter()
+            .map(|ix| {
+                Instruction::new_with_bytes(
+                    message.account_keys[ix.program_id_index as usize],
+                    &ix.data,
+                    ix.accounts
+                        .iter()
+                        .map(|index| AccountMeta::new(message.account_keys[*index as usize], false))
+                        .collect(),
+                )
+            })
+            .collect();
+
+        for ix in &instructions {
+            if ix.program_id != system_program::ID {
+                continue;
+            }
+
+            if let Ok(SystemInstruction::CreateAccount {
+                lamports, space, ..
+            }) = bincode::deserialize(&ix.data)
+            {
+                self.accounts.push(PlannedAccount {
+                    description: description.to_string(),
+                    address: ix.accounts[1].pubkey,
+                    space,
+                    lamports,
+                });
+            }
+        }
+
+        self.transactions.push(PlannedTransaction {
+            description: description.to_string(),
+            size: bincode::serialized_size(transaction).unwrap_or_default() as usize,
+            // a transaction without signers still needs the signature of the payer
+            signatures: (message.header.num_required_signatures as u64).max(1),
+            priority_fee: priority_fee(&instructions),
+        });
+    }
+}
+
+/// Sets whether commands only plan their transactions instead of sending them.
+pub fn set_dry_run(dry_run: bool) {
+    DRY_RUN.store(dry_run, Ordering::SeqCst);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::SeqCst)
+}
+
+/// Records a transaction with the instructions in the plan; the first signer of the
+/// instructions pays for it.
+pub fn plan_instructions(description: &str, instructions: &[Instruction]) {
+    plan_transaction(
+        description,
+        &Transaction::new_with_payer(instructions, None),
+    );
+}
+
+/// Records the transaction in the plan.
+pub fn plan_transaction(description: &str, transaction: &Transaction) {
+    PLAN.lock()
+        .unwrap()
+        .add_transaction(description, transaction);
+}
+
+/// Records files that would be uploaded to the storage in the plan.
+pub fn plan_upload(description: &str, files: usize, bytes: u64) {
+    PLAN.lock().unwrap().uploads.push(PlannedUpload {
+        description: description.to_string(),
+        files,
+        bytes,
+    });
+}
+
+/// Priority fee (in lamports) set by the compute budget instructions.
+fn priority_fee(instructions: &[Instruction]) -> u64 {
+    let mut unit_limit = None;
+    let mut unit_price = 0;
+
+    for ix in instructions {
+        if ix.program_id != compute_budget::id() {
+            continue;
+        }
+
+        match ComputeBudgetInstruction::try_from_slice(&ix.data) {
+            Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => {
+                unit_limit = Some(units as u64)
+            }
+            Ok(ComputeBudgetInstruction::SetComputeUnitPrice(price)) => unit_price = price,
+            _ => (),
+        }
+    }
+
+    let units = unit_limit.unwrap_or_else(|| {
+        let count = instructions
+            .iter()
+            .filter(|ix| ix.program_id != compute_budget::id())
+            .count() as u64;
+        (count * DEFAULT_INSTRUCTION_COMPUTE_UNITS).min(MAX_COMPUTE_UNITS)
+    });
+
+    // the price is in micro-lamports per compute unit
+    ((units as u128 * unit_price as u128) / 1_000_000) as u64
+}
+
+/// Sends a request of a program, or records it in the plan when running with
+/// `--dry-run` (returning a default signature).
+pub trait SendOrPlan {
+    fn send_or_plan(self, description: &str) -> Result<Signature>;
+}
+
+impl<C: Deref<Target = impl Signer> + Clone> SendOrPlan for RequestBuilder<'_, C> {
+    fn send_or_plan(self, description: &str) -> Result<Signature> {
+        if is_dry_run() {
+            plan_instructions(description, &self.instructions()?);
+            return Ok(Signature::default());
+        }
+
+        let signature = self.send()?;
+        record_transaction(&signature, description);
+
+        Ok(signature)
+    }
+}
+
+/// Sends and confirms a signed transaction, or records it in the plan when running
+/// with `--dry-run` (returning a default signature).
+pub fn send_and_confirm_or_plan(
+    rpc_client: &RpcClient,
+    transaction: &Transaction,
+    description: &str,
+) -> Result<Signature> {
+    if is_dry_run() {
+        plan_transaction(description, transaction);
+        return Ok(Signature::default());
+    }
+
+    let signature = rpc_client.send_and_confirm_transaction(transaction)?;
+    record_transaction(&signature, description);
+
+    Ok(signature)
+}
+
+/// Prints the plan of a dry run. A command stopped by a step that depends on accounts
+/// of the planned transactions still reports the plan up to that step.
+pub fn finish_dry_run(result: Result<()>) -> Result<()> {
+    let plan = PLAN.lock().unwrap();
+
+    if let Err(err) = &result {
+        if plan.is_empty() {
+            return result;
+        }
+
+        println!(
+            "\n{}",
+            style(format!(
+                "Planning stopped before the end of the command: {err}"
+            ))
+            .yellow()
+        );
+    }
+
+    print_plan(&plan);
+
+    Ok(())
+}
+
+fn print_plan(plan: &Plan) {
+    println!("\n{}", style("Dry run: nothing was sent.").bold());
+
+    if plan.is_empty() {
+        println!("The command would not send any transaction.");
+        return;
+    }
+
+    let sol = |lamports: u64| format!("◎ {}", lamports as f64 / LAMPORTS_PER_SOL as f64);
+
+    if !plan.transactions.is_empty() {
+        // transactions are grouped by description, in the order they would be sent
+        let mut groups: Vec<(&str, Vec<&PlannedTransaction>)> = Vec::new();
+
+        for transaction in &plan.transactions {
+            match groups
+                .iter_mut()
+                .find(|(description, _)| *description == transaction.description)
+            {
+                Some((_, transactions)) => transactions.push(transaction),
+                None => groups.push((&transaction.description, vec![transaction])),
+            }
+        }
+
+        let mut builder = Builder::default();
+        builder.push_record(["transaction", "count", "size", "fees", "priority fees"]);
+
+        for (description, transactions) in &groups {
+            builder.push_record([
+                description.to_string(),
+                transactions.len().to_string(),
+                format_bytes(transactions.iter().map(|tx| tx.size as u64).sum()),
+                sol(transactions
+                    .iter()
+                    .map(|tx| tx.signatures * LAMPORTS_PER_SIGNATURE)
+                    .sum()),
+                sol(transactions.iter().map(|tx| tx.priority_fee).sum()),
+            ]);
+        }
+
+        let mut table = builder.build();
+        table.with(Style::psql());
+        println!("\n{}", table);
+    }
+
+    if !plan.accounts.is_empty() {
+        let mut builder = Builder::default();
+        builder.push_record(["account", "address", "space", "rent"]);
+
+        for account in &plan.accounts {
+            builder.push_record([
+                account.description.clone(),
+                account.address.to_string(),
+                format_bytes(account.space),
+                sol(account.lamports),
+            ]);
+        }
+
+        let mut table = builder.build();
+        table.with(Style::psql());
+        println!("\n{}", table);
+    }
+
+    if !plan.uploads.is_empty() {
+        let mut builder = Builder::default();
+        builder.push_record(["upload", "files", "size"]);
+
+        for upload in &plan.uploads {
+            builder.push_record([
+                upload.description.clone(),
+                upload.files.to_string(),
+                format_bytes(upload.bytes),
+            ]);
+        }
+
+        let mut table = builder.build();
+        table.with(Style::psql());
+        println!("\n{}", table);
+    }
+
+    let fees: u64 = plan
+        .transactions
+        .iter()
+        .map(|tx| tx.signatures * LAMPORTS_PER_SIGNATURE + tx.priority_fee)
+        .sum();
+    let rent: u64 = plan.accounts.iter().map(|account| account.lamports).sum();
+
+    println!(
+        "\n{} {} transaction(s), {}, fees {}, rent {}",
+        style("Total:").bold(),
+        plan.transactions.len(),
+        format_bytes(plan.transactions.iter().map(|tx| tx.size as u64).sum()),
+        sol(fees),
+        sol(rent)
+    );
+    println!(
+        "{}",
+        style("Accounts created by the programs are not included in the rent.").dim()
+    );
+}
+
+#[test]
+fn test_planned_fees() {
+    let payer = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+
+    // a local plan, the global one is shared with the other tests
+    let mut plan = Plan::default();
+    plan.add_transaction(
+        "create account",
+        &Transaction::new_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(100_000),
+                ComputeBudgetInstruction::set_compute_unit_price(500_000),
+                system_instruction::create_account(
+                    &payer,
+                    &account,
+                    1_000,
+                    10,
+                    &system_program::ID,
+                ),
+            ],
+            None,
+        ),
+    );
+
+    let transaction = plan.transactions.last().unwrap();
+
+    assert_eq!(transaction.signatures, 2);
+    assert_eq!(transaction.priority_fee, 50_000);
+    assert_eq!(
+        plan.accounts,
+        vec![PlannedAccount {
+            description: "create account".to_string(),
+            address: account,
+            space: 10,
+            lamports: 1_000,
+        }]
+    );
+}
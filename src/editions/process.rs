@@ -0,0 +1,462 @@
+use std::{fs, sync::Arc};
+
+use anchor_client::solana_sdk::{compute_budget::ComputeBudgetInstruction, program_pack::Pack};
+use console::style;
+use mpl_token_metadata::instruction::mint_new_edition_from_master_edition_via_token;
+use solana_client::rpc_client::RpcClient;
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account,
+};
+use spl_token::{
+    instruction::{initialize_mint, mint_to},
+    state::Account as TokenAccount,
+    ID as TOKEN_PROGRAM_ID,
+};
+use tokio::sync::Semaphore;
+
+use crate::{
+    candy_machine::CANDY_MACHINE_ID,
+    common::*,
+    config::SugarConfig,
+    journal::{EntryStatus, Journal},
+    mint::{
+        resolve_in_flight, send_idempotent, write_receipts, InFlightEntry, MintReceipt,
+        ReceiptWriter,
+    },
+    pdas::{find_master_edition_pda, find_metadata_pda, get_master_edition_pda, get_metadata_pda},
+    rate_limit::throttled_client_with_commitment,
+    utils::*,
+};
+
+/// Number of editions tracked by each edition marker account.
+const EDITION_MARKER_BIT_SIZE: u64 = 248;
+
+/// Offset of the ledger in the edition marker account (after the key).
+const EDITION_MARKER_LEDGER_OFFSET: usize = 1;
+
+pub struct EditionsMintArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub master: String,
+    pub count: u64,
+    pub recipients: Option<String>,
+    pub receipts: String,
+}
+
+/// Print edition to mint: the recipient and the edition number assigned to it.
+struct EditionTarget {
+    key: String,
+    recipient: Pubkey,
+    edition: u64,
+}
+
+pub async fn process_editions_mint(args: EditionsMintArgs) -> Result<()> {
+    let master_mint = Pubkey::from_str(&args.master)
+        .map_err(|_| anyhow!("Failed to parse master edition mint: {}", args.master))?;
+
+    if args.count == 0 {
+        return Err(anyhow!("The number of editions must be greater than 0"));
+    }
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(CANDY_MACHINE_ID);
+    let rpc_client = throttled_client_with_commitment(&sugar_config.rpc_url);
+    let payer = program.payer();
+
+    let recipients = match &args.recipients {
+        Some(recipients) => load_recipients(recipients)?,
+        None => vec![payer],
+    };
+
+    println!(
+        "{} {}Loading master edition",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+    println!("{} {}", style("Master edition mint:").bold(), master_mint);
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let (_, master_edition) = get_master_edition_pda(&master_mint, &program)?;
+    let (_, master_metadata) = get_metadata_pda(&master_mint, &program)?;
+
+    // the master edition token is required to print editions
+    let master_token = get_associated_token_address(&payer, &master_mint);
+    let token_data = rpc_client.get_account_data(&master_token).map_err(|_| {
+        anyhow!(
+            "The keypair {} does not hold the master edition token {}",
+            payer,
+            master_mint
+        )
+    })?;
+
+    if TokenAccount::unpack(&token_data)?.amount != 1 {
+        return Err(anyhow!(
+            "The keypair {} does not hold the master edition token {}",
+            payer,
+            master_mint
+        ));
+    }
+
+    if master_metadata.update_authority != payer {
+        warn!(
+            "Payer is not the update authority of the master edition ({})",
+            master_metadata.update_authority
+        );
+    }
+
+    let journal = Arc::new(Journal::open("editions", &master_mint.to_string())?);
+    let mut resent = 0;
+    let mut skipped = 0;
+    let mut keys = Vec::new();
+
+    // editions of an interrupted run are only sent again when their transaction did
+    // not land and can no longer land
+    for recipient in &recipients {
+        for i in 0..args.count {
+            let key = format!("{}:{}", recipient, i);
+
+            match journal.status(&key) {
+                Some(EntryStatus::Completed) => skipped += 1,
+                Some(EntryStatus::Pending) => {
+                    match resolve_in_flight(&rpc_client, journal.detail(&key)).await? {
+                        Some(signature) => {
+                            journal.mark_completed(&key, Some(signature.to_string()))?;
+                            skipped += 1;
+                        }
+                        None => {
+                            resent += 1;
+                            keys.push((key, *recipient));
+                        }
+                    }
+                }
+                None => keys.push((key, *recipient)),
+            }
+        }
+    }
+
+    // edition numbers already printed (on-chain) are never reused
+    let editions = find_free_editions(
+        &rpc_client,
+        &master_mint,
+        keys.len() as u64,
+        master_edition.max_supply,
+    )?;
+
+    let targets: Vec<EditionTarget> = keys
+        .into_iter()
+        .zip(editions)
+        .map(|((key, recipient), edition)| EditionTarget {
+            key,
+            recipient,
+            edition,
+        })
+        .collect();
+
+    pb.finish_with_message("Done");
+
+    println!(
+        "Supply: {}{}",
+        master_edition.supply,
+        master_edition
+            .max_supply
+            .map(|max_supply| format!(" of {}", max_supply))
+            .unwrap_or_default()
+    );
+
+    if skipped > 0 {
+        println!("Skipping {} edition(s) minted by a previous run", skipped);
+    }
+
+    if resent > 0 {
+        println!(
+            "Sending again {} edition(s) of a previous run that did not land",
+            resent
+        );
+    }
+
+    println!(
+        "\n{} {}Minting {} edition(s)",
+        style("[2/2]").bold().dim(),
+        CANDY_EMOJI,
+        targets.len()
+    );
+
+    let receipts = Arc::new(ReceiptWriter::open(&args.receipts)?);
+    let config = Arc::new(sugar_config);
+    let semaphore = Arc::new(Semaphore::new(10));
+    let pb = progress_bar_with_style(targets.len() as u64);
+    let mut tasks = Vec::new();
+
+    for target in targets {
+        let config = config.clone();
+        let journal = journal.clone();
+        let receipts = receipts.clone();
+        let permit = Arc::clone(&semaphore).acquire_owned().await.unwrap();
+        let pb = pb.clone();
+
+        tasks.push(tokio::spawn(async move {
+            let _permit = permit;
+            journal.mark_pending(&target.key)?;
+
+            let in_flight = InFlightEntry {
+                journal: journal.clone(),
+                key: target.key.clone(),
+            };
+            let res = mint_edition(&config, &master_mint, &target, &in_flight).await;
+            pb.inc(1);
+
+            if let Ok((signature, mint)) = &res {
+                let detail = format!("#{} {} {}", target.edition, mint, signature);
+                if let Err(err) = journal.mark_completed(&target.key, Some(detail)) {
+                    warn!("Failed to update the editions journal: {}", err);
+                }
+
+                receipts.append(MintReceipt::new(
+                    Some(target.edition),
+                    *mint,
+                    target.recipient,
+                    *signature,
+                ));
+            }
+
+            res
+        }));
+    }
+
+    let total = tasks.len();
+    let mut error_count = 0;
+
+    for task in tasks {
+        if let Err(err) = task.await.unwrap() {
+            error_count += 1;
+            error!("{:?}, continuing. . .", err);
+        }
+    }
+
+    // the editions are numbered by the master edition, not by a candy machine
+    write_receipts(&rpc_client, &receipts, None, &args.receipts)?;
+
+    if error_count > 0 {
+        pb.abandon_with_message(format!(
+            "{} {} edition(s) failed.",
+            style("Some of the editions failed to mint.").red().bold(),
+            error_count
+        ));
+        // the journal is kept so the failed editions are retried by the next run
+        return Err(SugarError::PartialFailure(format!(
+            "{} {}/{} {}",
+            style("Minted").red().bold(),
+            total - error_count,
+            total,
+            style("of the editions").red().bold()
+        ))
+        .into());
+    }
+
+    pb.finish();
+
+    if let Ok(journal) = Arc::try_unwrap(journal) {
+        journal.finish()?;
+    }
+
+    Ok(())
+}
+
+/// Mint a print edition: creates the new mint with its token in the recipient wallet
+/// and prints the edition from the master edition. The transaction is recorded in the
+/// journal before it is sent, so an interrupted run can check whether it landed.
+async fn mint_edition(
+    config: &SugarConfig,
+    master_mint: &Pubkey,
+    target: &EditionTarget,
+    in_flight: &InFlightEntry,
+) -> Result<(Signature, Pubkey)> {
+    let rpc_client = throttled_client_with_commitment(&config.rpc_url);
+    let payer = config.keypair.pubkey();
+
+    let new_mint = Keypair::new();
+    let recipient_token = get_associated_token_address(&target.recipient, &new_mint.pubkey());
+
+    let min_rent = rpc_client.get_minimum_balance_for_rent_exemption(MINT_LAYOUT as usize)?;
+
+    let instructions = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS),
+        ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE),
+        system_instruction::create_account(
+            &payer,
+            &new_mint.pubkey(),
+            min_rent,
+            MINT_LAYOUT,
+            &TOKEN_PROGRAM_ID,
+        ),
+        initialize_mint(
+            &TOKEN_PROGRAM_ID,
+            &new_mint.pubkey(),
+            &payer,
+            Some(&payer),
+            0,
+        )?,
+        create_associated_token_account(
+            &payer,
+            &target.recipient,
+            &new_mint.pubkey(),
+            &TOKEN_PROGRAM_ID,
+        ),
+        mint_to(
+            &TOKEN_PROGRAM_ID,
+            &new_mint.pubkey(),
+            &recipient_token,
+            &payer,
+            &[],
+            1,
+        )?,
+        mint_new_edition_from_master_edition_via_token(
+            mpl_token_metadata::ID,
+            find_metadata_pda(&new_mint.pubkey()),
+            find_master_edition_pda(&new_mint.pubkey()),
+            find_master_edition_pda(master_mint),
+            new_mint.pubkey(),
+            payer,
+            payer,
+            payer,
+            get_associated_token_address(&payer, master_mint),
+            payer,
+            find_metadata_pda(master_mint),
+            *master_mint,
+            target.edition,
+        ),
+    ];
+
+    let signers: Vec<&(dyn Signer + Sync)> = vec![&config.keypair, &new_mint];
+    let sig = send_idempotent(&rpc_client, &instructions, &signers, Some(in_flight)).await?;
+
+    info!(
+        "Minted edition #{} to {} with sig: {}",
+        target.edition, target.recipient, sig
+    );
+
+    Ok((sig, new_mint.pubkey()))
+}
+
+/// Load the recipients file: one address per line, empty lines and lines starting
+/// with `#` are ignored. An address listed more than once receives editions each time.
+pub fn load_recipients(path: &str) -> Result<Vec<Pubkey>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read recipients file '{}': {}", path, e))?;
+
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            Pubkey::from_str(line)
+                .map_err(|_| anyhow!("Invalid recipient '{}' in '{}'", line, path))
+        })
+        .collect()
+}
+
+fn find_edition_marker_pda(master_mint: &Pubkey, edition: u64) -> Pubkey {
+    let marker = (edition / EDITION_MARKER_BIT_SIZE).to_string();
+
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            master_mint.as_ref(),
+            b"edition",
+            marker.as_bytes(),
+        ],
+        &mpl_token_metadata::ID,
+    )
+    .0
+}
+
+/// Indicates whether the edition is marked as printed in the marker ledger.
+fn is_printed(ledger: &[u8], edition: u64) -> bool {
+    let offset = (edition % EDITION_MARKER_BIT_SIZE) as usize;
+    let mask = 1u8 << (7 - offset % 8);
+
+    ledger
+        .get(EDITION_MARKER_LEDGER_OFFSET + offset / 8)
+        .map(|byte| byte & mask != 0)
+        .unwrap_or(false)
+}
+
+/// Find the lowest `count` edition numbers that were not printed, reading the edition
+/// marker accounts of the master edition.
+fn find_free_editions(
+    rpc_client: &RpcClient,
+    master_mint: &Pubkey,
+    count: u64,
+    max_supply: Option<u64>,
+) -> Result<Vec<u64>> {
+    let mut free = Vec::new();
+    let mut printed = 0;
+    let mut marker = 0;
+
+    while (free.len() as u64) < count {
+        // edition markers are fetched in batches of 100 accounts
+        let markers: Vec<u64> = (marker..marker + 100).collect();
+        let addresses: Vec<Pubkey> = markers
+            .iter()
+            .map(|marker| find_edition_marker_pda(master_mint, marker * EDITION_MARKER_BIT_SIZE))
+            .collect();
+
+        let accounts = rpc_client.get_multiple_accounts(&addresses)?;
+
+        for (marker, account) in markers.iter().zip(accounts) {
+            let first = (marker * EDITION_MARKER_BIT_SIZE).max(1);
+            let last = (marker + 1) * EDITION_MARKER_BIT_SIZE;
+
+            for edition in first..last {
+                if let Some(max_supply) = max_supply {
+                    if edition > max_supply {
+                        return Err(anyhow!(
+                            "Not enough editions available: the max supply is {}, {} edition(s) \
+                            can still be minted",
+                            max_supply,
+                            free.len()
+                        ));
+                    }
+                }
+
+                let taken = account
+                    .as_ref()
+                    .map(|account| is_printed(&account.data, edition))
+                    .unwrap_or(false);
+
+                if taken {
+                    printed += 1;
+                } else {
+                    free.push(edition);
+
+                    if free.len() as u64 == count {
+                        debug!("Printed editions: {}", printed);
+                        return Ok(free);
+                    }
+                }
+            }
+        }
+
+        marker += 100;
+    }
+
+    Ok(free)
+}
+
+#[test]
+fn test_edition_marker_ledger() {
+    // key followed by the ledger: editions 1 and 9 printed
+    let mut data = vec![0u8; 32];
+    data[1] = 0b0100_0000;
+    data[2] = 0b0100_0000;
+
+    assert!(!is_printed(&data, 0));
+    assert!(is_printed(&data, 1));
+    assert!(!is_printed(&data, 2));
+    assert!(is_printed(&data, 9));
+    assert!(is_printed(&data, EDITION_MARKER_BIT_SIZE + 1));
+}
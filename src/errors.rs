@@ -6,7 +6,10 @@ use std::{
 use serde::Serialize;
 use thiserror::Error;
 
-use crate::common::*;
+use crate::{
+    common::*, config::ConfigError, deploy::DeployError, upload::UploadError,
+    validate::ValidateParserError,
+};
 
 #[derive(Debug, Error)]
 pub enum SetupError {
@@ -30,6 +33,9 @@ pub enum CacheError {
 
     #[error("Invalid cache state found.")]
     InvalidState,
+
+    #[error("Cache file version {0} is not supported by this version of sugar (latest supported version is {1}), upgrade sugar to use it.")]
+    UnsupportedVersion(u32, u32),
 }
 
 #[derive(Debug, Error)]
@@ -38,6 +44,111 @@ pub enum CustomCandyError {
     AuthorityMismatch(String, String),
 }
 
+#[derive(Debug, Error)]
+pub enum SugarError {
+    #[error("{0}")]
+    InsufficientFunds(String),
+
+    #[error("{0}")]
+    PartialFailure(String),
+
+    #[error("{0}")]
+    Drift(String),
+}
+
+/// Category of the error of a failed command. Each category has a stable exit code,
+/// so scripts can branch on the type of failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    General,
+    Config,
+    Rpc,
+    InsufficientFunds,
+    Validation,
+    PartialFailure,
+    Drift,
+}
+
+impl ErrorCategory {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorCategory::General => 1,
+            ErrorCategory::Config => 2,
+            ErrorCategory::Rpc => 3,
+            ErrorCategory::InsufficientFunds => 4,
+            ErrorCategory::Validation => 5,
+            ErrorCategory::PartialFailure => 6,
+            ErrorCategory::Drift => 7,
+        }
+    }
+
+    /// Returns the category of an error, looking at every error of the chain.
+    pub fn of(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(error) = cause.downcast_ref::<SugarError>() {
+                return match error {
+                    SugarError::InsufficientFunds(_) => ErrorCategory::InsufficientFunds,
+                    SugarError::PartialFailure(_) => ErrorCategory::PartialFailure,
+                    SugarError::Drift(_) => ErrorCategory::Drift,
+                };
+            }
+
+            // the RPC reports a low balance as a transaction (simulation) error
+            let message = cause.to_string().to_lowercase();
+            if message.contains("insufficient funds") || message.contains("insufficient lamports") {
+                return ErrorCategory::InsufficientFunds;
+            }
+
+            if cause.is::<ConfigError>() {
+                return ErrorCategory::Config;
+            }
+
+            if cause.is::<ValidateParserError>() {
+                return ErrorCategory::Validation;
+            }
+
+            if matches!(
+                cause.downcast_ref::<UploadError>(),
+                Some(UploadError::Incomplete(_))
+            ) || matches!(
+                cause.downcast_ref::<DeployError>(),
+                Some(DeployError::AddConfigLineFailed(_))
+            ) {
+                return ErrorCategory::PartialFailure;
+            }
+
+            if cause.is::<anchor_client::ClientError>()
+                || cause.is::<solana_client::client_error::ClientError>()
+            {
+                return ErrorCategory::Rpc;
+            }
+        }
+
+        ErrorCategory::General
+    }
+}
+
+/// Machine-readable description of the error of a failed command.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub error: ErrorCategory,
+    pub code: i32,
+    pub message: String,
+}
+
+impl ErrorReport {
+    pub fn new(error: &anyhow::Error, message: String) -> Self {
+        let category = ErrorCategory::of(error);
+
+        ErrorReport {
+            error: category,
+            code: category.exit_code(),
+            message,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum FloatConversionError {
     #[error("Conversion failed with an overflow")]
@@ -48,8 +159,8 @@ pub enum FloatConversionError {
 }
 
 #[derive(Debug, Serialize)]
-pub struct ValidateError<'a> {
-    pub path: &'a PathBuf,
+pub struct ValidateError {
+    pub path: PathBuf,
     pub error: String,
 }
 
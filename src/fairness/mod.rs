@@ -0,0 +1,5 @@
+pub mod process;
+pub mod proof;
+
+pub use process::*;
+pub use proof::*;
@@ -0,0 +1,284 @@
+use console::style;
+use mpl_token_metadata::state::Metadata;
+use serde::Serialize;
+
+use crate::{
+    cache::load_cache,
+    common::*,
+    config::get_config_data,
+    fairness::*,
+    pdas::find_candy_machine_creator_pda,
+    rate_limit::throttled_client,
+    utils::{get_cm_creator_metadata, spinner_with_style},
+};
+
+pub struct FairnessProveArgs {
+    pub config: String,
+    pub cache: String,
+    pub proof: String,
+}
+
+pub struct FairnessVerifyArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub proof: String,
+    pub commitment: Option<String>,
+    pub candy_machine: Option<String>,
+    pub report: Option<String>,
+}
+
+/// Outcome of the verification of a minted NFT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RevealStatus {
+    /// Revealed with the item committed for its number.
+    Matched,
+    /// Revealed with an item of the list; its number cannot be read from the name.
+    Committed,
+    /// Revealed with a name and uri that are not in the list (or not for its number).
+    Mismatch,
+    /// Revealed with an item already used by another NFT.
+    Duplicate,
+    /// Still showing the hidden settings.
+    Unrevealed,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VerificationEntry {
+    mint: String,
+    name: String,
+    uri: String,
+    status: RevealStatus,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VerificationReport {
+    candy_machine: String,
+    commitment: String,
+    verified_at: String,
+    entries: Vec<VerificationEntry>,
+}
+
+pub fn process_fairness_prove(args: FairnessProveArgs) -> Result<()> {
+    let config_data = get_config_data(&args.config)?;
+    let hidden_settings = config_data.hidden_settings.ok_or_else(|| {
+        anyhow!("Fairness proofs are only needed for drops with hidden settings.")
+    })?;
+    let (_, offset) = number_pattern(&hidden_settings.name)?;
+
+    if Path::new(&args.proof).exists() {
+        return Err(anyhow!(
+            "The proof '{}' already exists: a new proof has a different commitment, remove \
+            the file only if its commitment was not published.",
+            args.proof
+        ));
+    }
+
+    let cache = load_cache(&args.cache, false)?;
+    let mut items = Vec::new();
+
+    for (key, item) in cache.items.iter() {
+        if key == COLLECTION_CACHE_KEY {
+            continue;
+        }
+
+        if item.metadata_link.is_empty() {
+            return Err(anyhow!(
+                "Item {} was not uploaded, run 'sugar upload' before committing to the order.",
+                key
+            ));
+        }
+
+        items.push(ProofItem {
+            number: key.parse::<u32>()? + offset,
+            name: item.name.clone(),
+            uri: item.metadata_link.clone(),
+        });
+    }
+
+    let proof = FairnessProof::new(
+        cache.program.candy_machine.clone(),
+        hidden_settings.name,
+        hidden_settings.uri,
+        items,
+    );
+    serde_json::to_writer_pretty(File::create(&args.proof)?, &proof)?;
+
+    println!(
+        "{} {}",
+        COMPLETE_EMOJI,
+        style(format!(
+            "Committed to the order of {} item(s).",
+            proof.items.len()
+        ))
+        .green()
+        .bold()
+    );
+    println!("\nCommitment: {}", style(&proof.commitment).bold());
+    println!(
+        "\nPublish the commitment before the mint and keep '{}' private until the reveal; \
+        then publish the file so anyone can run 'sugar fairness verify'.",
+        args.proof
+    );
+
+    Ok(())
+}
+
+pub fn process_fairness_verify(args: FairnessVerifyArgs) -> Result<()> {
+    let proof: FairnessProof = serde_json::from_reader(
+        File::open(&args.proof)
+            .map_err(|e| anyhow!("Failed to open the proof file '{}': {}", args.proof, e))?,
+    )?;
+
+    println!(
+        "{} {}Checking the commitment",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    if !proof.is_consistent() {
+        return Err(anyhow!(
+            "The items of '{}' do not match its commitment {}.",
+            args.proof,
+            proof.commitment
+        ));
+    }
+
+    if let Some(commitment) = &args.commitment {
+        if !commitment.eq_ignore_ascii_case(&proof.commitment) {
+            return Err(anyhow!(
+                "The proof commitment {} does not match the published commitment {}.",
+                proof.commitment,
+                commitment
+            ));
+        }
+    }
+
+    println!("Commitment {} matches the items.", proof.commitment);
+
+    let candy_machine = args
+        .candy_machine
+        .clone()
+        .unwrap_or_else(|| proof.candy_machine.clone());
+    let candy_pubkey = Pubkey::from_str(&candy_machine)
+        .map_err(|_| CacheError::InvalidCandyMachineAddress(candy_machine.clone()))?;
+
+    println!(
+        "\n{} {}Checking the revealed NFTs",
+        style("[2/2]").bold().dim(),
+        PAPER_EMOJI
+    );
+
+    let pb = spinner_with_style();
+    pb.set_message("Loading NFTs...");
+
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
+    let client = throttled_client(&sugar_config.rpc_url);
+    let (creator, _) = find_candy_machine_creator_pda(&candy_pubkey);
+    let metadata = get_cm_creator_metadata(&client, &creator.to_string(), 0)?;
+
+    pb.finish_and_clear();
+
+    let entries = verify_reveal(&proof, metadata.iter().map(|(_, m)| m))?;
+    let count = |status: RevealStatus| entries.iter().filter(|e| e.status == status).count();
+
+    println!("NFTs minted:   {}", entries.len());
+    println!("  matched:     {}", count(RevealStatus::Matched));
+    println!("  committed:   {}", count(RevealStatus::Committed));
+    println!("  unrevealed:  {}", count(RevealStatus::Unrevealed));
+    println!("  mismatch:    {}", count(RevealStatus::Mismatch));
+    println!("  duplicate:   {}", count(RevealStatus::Duplicate));
+
+    let failed: Vec<&VerificationEntry> = entries
+        .iter()
+        .filter(|e| matches!(e.status, RevealStatus::Mismatch | RevealStatus::Duplicate))
+        .collect();
+
+    for entry in &failed {
+        println!(
+            "  {} {} ({:?}): {} {}",
+            style("->").red(),
+            entry.mint,
+            entry.status,
+            entry.name,
+            entry.uri
+        );
+    }
+
+    if let Some(report) = &args.report {
+        serde_json::to_writer_pretty(
+            File::create(report)?,
+            &VerificationReport {
+                candy_machine,
+                commitment: proof.commitment.clone(),
+                verified_at: chrono::Utc::now().to_rfc3339(),
+                entries,
+            },
+        )?;
+        println!("\nReport saved to '{}'.", report);
+    }
+
+    if failed.is_empty() {
+        println!(
+            "\n{} {}",
+            COMPLETE_EMOJI,
+            style("The reveal matches the committed order.")
+                .green()
+                .bold()
+        );
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} NFT(s) do not match the committed order.",
+            failed.len()
+        ))
+    }
+}
+
+/// Checks the name and uri of each minted NFT against the committed list.
+fn verify_reveal<'a>(
+    proof: &FairnessProof,
+    metadata: impl Iterator<Item = &'a Metadata>,
+) -> Result<Vec<VerificationEntry>> {
+    let (pattern, _) = number_pattern(&proof.hidden_name)?;
+    let by_number: HashMap<u32, &ProofItem> =
+        proof.items.iter().map(|item| (item.number, item)).collect();
+    let mut used: HashMap<(String, String), String> = HashMap::new();
+    let mut entries = Vec::new();
+
+    for metadata in metadata {
+        let mint = metadata.mint.to_string();
+        let name = metadata.data.name.trim_matches(char::from(0)).to_string();
+        let uri = metadata.data.uri.trim_matches(char::from(0)).to_string();
+
+        let status = if uri == proof.hidden_uri {
+            RevealStatus::Unrevealed
+        } else if let Some(previous) = used.insert((name.clone(), uri.clone()), mint.clone()) {
+            debug!("{} revealed with the same item as {}", mint, previous);
+            RevealStatus::Duplicate
+        } else {
+            let number = pattern
+                .captures(&name)
+                .and_then(|captures| captures[1].parse::<u32>().ok());
+            let committed = |item: &&ProofItem| item.name == name && item.uri == uri;
+
+            match number.and_then(|number| by_number.get(&number)) {
+                Some(item) if committed(item) => RevealStatus::Matched,
+                Some(_) => RevealStatus::Mismatch,
+                None if proof.items.iter().any(|item| committed(&item)) => RevealStatus::Committed,
+                None => RevealStatus::Mismatch,
+            }
+        };
+
+        entries.push(VerificationEntry {
+            mint,
+            name,
+            uri,
+            status,
+        });
+    }
+
+    Ok(entries)
+}
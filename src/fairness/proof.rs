@@ -0,0 +1,130 @@
+use rand::RngCore;
+use regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::common::*;
+
+/// Version of the commitment scheme.
+pub const PROOF_VERSION: u32 = 1;
+
+/// An item of the ordered asset list: the NFT `number` of the drop is revealed with the
+/// `name` and `uri`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofItem {
+    pub number: u32,
+    pub name: String,
+    pub uri: String,
+}
+
+/// Pre-mint commitment of a hidden drop. The file is kept private until the reveal,
+/// since it contains the mapping; only the commitment is published before the mint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FairnessProof {
+    pub version: u32,
+    pub candy_machine: String,
+    /// Name of the hidden settings (e.g. "Sugar #$ID+1$"), used to number the NFTs.
+    pub hidden_name: String,
+    /// Uri of the hidden settings, identifying the NFTs not revealed yet.
+    pub hidden_uri: String,
+    /// Random value (hex) preventing the commitment from being brute-forced.
+    pub salt: String,
+    /// SHA-256 (hex) of the salt and the ordered asset list.
+    pub commitment: String,
+    pub created_at: String,
+    pub items: Vec<ProofItem>,
+}
+
+impl FairnessProof {
+    pub fn new(
+        candy_machine: String,
+        hidden_name: String,
+        hidden_uri: String,
+        items: Vec<ProofItem>,
+    ) -> Self {
+        let mut salt = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let salt = hex::encode(salt);
+
+        FairnessProof {
+            version: PROOF_VERSION,
+            candy_machine,
+            hidden_name,
+            hidden_uri,
+            commitment: commitment(&salt, &items),
+            salt,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            items,
+        }
+    }
+
+    /// Indicates whether the commitment matches the salt and items of the proof.
+    pub fn is_consistent(&self) -> bool {
+        commitment(&self.salt, &self.items) == self.commitment
+    }
+}
+
+/// Commitment of the ordered list: `sha256(salt "\n" (number "\t" name "\t" uri "\n")*)`,
+/// with the items sorted by number.
+pub fn commitment(salt: &str, items: &[ProofItem]) -> String {
+    let mut sorted: Vec<&ProofItem> = items.iter().collect();
+    sorted.sort_by_key(|item| item.number);
+
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(b"\n");
+
+    for item in sorted {
+        hasher.update(format!("{}\t{}\t{}\n", item.number, item.name, item.uri).as_bytes());
+    }
+
+    hex::encode(hasher.finalize())
+}
+
+/// Returns the pattern matching the number in the name of the hidden NFTs and the
+/// offset of the numbers ($ID$ starts at 0 and $ID+1$ at 1).
+pub fn number_pattern(hidden_name: &str) -> Result<(Regex, u32)> {
+    let parts: Vec<&str> = hidden_name.split('$').collect();
+
+    let offset = match parts.get(1) {
+        Some(&"ID") => 0,
+        Some(&"ID+1") => 1,
+        _ => {
+            return Err(anyhow!(
+                "The hidden settings name '{}' must number the NFTs with $ID$ or $ID+1$.",
+                hidden_name
+            ))
+        }
+    };
+
+    let pattern = Regex::new(&format!(
+        "^{}([0-9]+){}$",
+        regex::escape(parts[0]),
+        regex::escape(parts.get(2).unwrap_or(&""))
+    ))?;
+
+    Ok((pattern, offset))
+}
+
+#[test]
+fn test_fairness_commitment() {
+    let item = |number: u32| ProofItem {
+        number,
+        name: format!("Sugar #{number}"),
+        uri: format!("https://arweave.net/{number}"),
+    };
+
+    let items = vec![item(2), item(1)];
+    let commitment_value = commitment("salt", &items);
+
+    // the order of the list in the file does not matter
+    assert_eq!(commitment_value, commitment("salt", &[item(1), item(2)]));
+    assert_ne!(commitment_value, commitment("other", &items));
+    assert_ne!(commitment_value, commitment("salt", &[item(1), item(3)]));
+
+    let (pattern, offset) = number_pattern("Sugar #$ID+1$").unwrap();
+    assert_eq!(offset, 1);
+    assert_eq!(&pattern.captures("Sugar #12").unwrap()[1], "12");
+    assert!(number_pattern("Sugar").is_err());
+}
@@ -0,0 +1,238 @@
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anchor_client::solana_sdk::message::Message;
+use console::style;
+use solana_client::rpc_client::RpcClient;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+
+use crate::{
+    common::*, config::SugarConfig, dry_run::send_and_confirm_or_plan, journal::STATE_DIR,
+    rate_limit::throttled_client, setup::SugarClient, utils::*,
+};
+
+/// File with the keypairs of the fee payer pool, kept until they are swept.
+pub const FEE_PAYERS_FILE: &str = "fee-payers.json";
+
+/// The maximum number of transfers per funding transaction.
+const TRANSFERS_PER_TRANSACTION: usize = 10;
+
+/// A pool of funded keypairs paying the transaction fees (and rent) of a command
+/// round-robin, so parallel transactions do not write-lock the same fee payer.
+pub struct FeePayerPool {
+    keypairs: Vec<Arc<Keypair>>,
+    next: AtomicUsize,
+}
+
+impl FeePayerPool {
+    /// Loads the keypairs of the pool file, generating new keypairs until the pool has
+    /// `size` of them. The file is written before the keypairs are funded so leftover
+    /// funds can always be swept.
+    pub fn load_or_create(size: usize) -> Result<Self> {
+        if size == 0 {
+            return Err(anyhow!("The fee payer pool needs at least one keypair."));
+        }
+
+        let mut keypairs = load_fee_payers()?;
+
+        while keypairs.len() < size {
+            keypairs.push(Keypair::new());
+        }
+
+        save_fee_payers(&keypairs)?;
+
+        Ok(Self {
+            keypairs: keypairs.into_iter().take(size).map(Arc::new).collect(),
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Tops up the balance of each fee payer to `lamports` from the wallet of the config.
+    pub async fn fund(&self, sugar_config: &SugarConfig, lamports: u64) -> Result<()> {
+        let rpc_client = throttled_client(&sugar_config.rpc_url);
+        let payer = sugar_config.keypair.pubkey();
+        let mut transfers = Vec::new();
+
+        for keypair in &self.keypairs {
+            let balance = rpc_client.get_balance(&keypair.pubkey())?;
+
+            if balance < lamports {
+                transfers.push(system_instruction::transfer(
+                    &payer,
+                    &keypair.pubkey(),
+                    lamports - balance,
+                ));
+            }
+        }
+
+        if transfers.is_empty() {
+            return Ok(());
+        }
+
+        let total = self.keypairs.len() as u64 * lamports;
+        check_balance(&rpc_client, &payer, total, "fund the fee payers").await?;
+
+        println!(
+            "Funding {} fee payer(s) with ◎ {} each",
+            transfers.len(),
+            lamports as f64 / LAMPORTS_PER_SOL as f64
+        );
+
+        let pb = spinner_with_style();
+        pb.set_message("Sending funding transaction(s)...");
+
+        for chunk in transfers.chunks(TRANSFERS_PER_TRANSACTION) {
+            let tx = Transaction::new_signed_with_payer(
+                chunk,
+                Some(&payer),
+                &[&sugar_config.keypair],
+                rpc_client.get_latest_blockhash()?,
+            );
+            send_and_confirm_or_plan(&rpc_client, &tx, "fund fee payers")?;
+        }
+
+        pb.finish_and_clear();
+
+        Ok(())
+    }
+
+    /// Returns the next fee payer of the pool.
+    pub fn next(&self) -> Arc<Keypair> {
+        let index = self.next.fetch_add(1, Ordering::SeqCst) % self.keypairs.len();
+        self.keypairs[index].clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keypairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keypairs.is_empty()
+    }
+}
+
+/// Returns the lamports to fund each of the `size` fee payers with for `operations`
+/// transactions costing `cost` lamports each, keeping them rent exempt.
+pub fn fee_payer_funding(
+    rpc_client: &RpcClient,
+    operations: u64,
+    size: usize,
+    cost: u64,
+) -> Result<u64> {
+    let per_payer = (operations + size as u64 - 1) / size as u64;
+    let rent_exempt = rpc_client.get_minimum_balance_for_rent_exemption(0)?;
+
+    Ok(per_payer * cost + rent_exempt)
+}
+
+/// Creates a client whose transactions are paid by the fee payer; the wallet of the
+/// config still signs as the authority.
+pub fn setup_fee_payer_client(
+    sugar_config: &SugarConfig,
+    fee_payer: &Keypair,
+) -> Result<SugarClient> {
+    setup_client(&SugarConfig {
+        rpc_url: sugar_config.rpc_url.clone(),
+        keypair: Keypair::from_bytes(&fee_payer.to_bytes())?,
+        read_only: false,
+    })
+}
+
+/// Returns the lamports transferred back to the wallet of the config from the fee
+/// payers. The pool file is removed once every fee payer is empty.
+pub fn sweep_fee_payers(sugar_config: &SugarConfig) -> Result<u64> {
+    let keypairs = load_fee_payers()?;
+
+    if keypairs.is_empty() {
+        return Ok(0);
+    }
+
+    let rpc_client = throttled_client(&sugar_config.rpc_url);
+    let wallet = sugar_config.keypair.pubkey();
+    let mut swept = 0;
+    let mut failed = 0;
+
+    for keypair in &keypairs {
+        match sweep_fee_payer(&rpc_client, keypair, &wallet) {
+            Ok(lamports) => swept += lamports,
+            Err(err) => {
+                failed += 1;
+                warn!("Failed to sweep fee payer {}: {}", keypair.pubkey(), err);
+            }
+        }
+    }
+
+    if failed == 0 {
+        fs::remove_file(fee_payers_path())?;
+    } else {
+        println!(
+            "{}",
+            style(format!(
+                "{failed} fee payer(s) could not be swept, run 'sugar fee-payers sweep' to retry."
+            ))
+            .yellow()
+        );
+    }
+
+    Ok(swept)
+}
+
+/// Transfers the balance of the fee payer (less the transaction fee) to the wallet.
+fn sweep_fee_payer(rpc_client: &RpcClient, keypair: &Keypair, wallet: &Pubkey) -> Result<u64> {
+    let balance = rpc_client.get_balance(&keypair.pubkey())?;
+    let blockhash = rpc_client.get_latest_blockhash()?;
+
+    // the fee does not depend on the amount transferred
+    let ix = system_instruction::transfer(&keypair.pubkey(), wallet, balance);
+    let message = Message::new(&[ix], Some(&keypair.pubkey()));
+    let fee = rpc_client.get_fee_for_message(&message)?;
+
+    if balance <= fee {
+        return Ok(0);
+    }
+
+    let ix = system_instruction::transfer(&keypair.pubkey(), wallet, balance - fee);
+    let tx =
+        Transaction::new_signed_with_payer(&[ix], Some(&keypair.pubkey()), &[keypair], blockhash);
+    send_and_confirm_or_plan(rpc_client, &tx, "sweep fee payers")?;
+
+    Ok(balance - fee)
+}
+
+fn fee_payers_path() -> PathBuf {
+    Path::new(STATE_DIR).join(FEE_PAYERS_FILE)
+}
+
+/// Returns the keypairs of the pool file.
+pub fn load_fee_payers() -> Result<Vec<Keypair>> {
+    let path = fee_payers_path();
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let keypairs: Vec<Vec<u8>> = serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|e| anyhow!("Failed to parse fee payer file '{}': {}", path.display(), e))?;
+
+    keypairs
+        .iter()
+        .map(|bytes| {
+            Keypair::from_bytes(bytes)
+                .map_err(|e| anyhow!("Invalid keypair in '{}': {}", path.display(), e))
+        })
+        .collect()
+}
+
+fn save_fee_payers(keypairs: &[Keypair]) -> Result<()> {
+    fs::create_dir_all(STATE_DIR)?;
+
+    let bytes: Vec<Vec<u8>> = keypairs.iter().map(|k| k.to_bytes().to_vec()).collect();
+    fs::write(fee_payers_path(), serde_json::to_string(&bytes)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use console::style;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+
+use crate::{common::*, fee_payers::*, rate_limit::throttled_client, utils::*};
+
+pub struct FeePayersShowArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+}
+
+pub struct FeePayersSweepArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+}
+
+pub fn process_fee_payers_show(args: FeePayersShowArgs) -> Result<()> {
+    let keypairs = load_fee_payers()?;
+
+    if keypairs.is_empty() {
+        println!("No fee payers to show.");
+        return Ok(());
+    }
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let rpc_client = throttled_client(&sugar_config.rpc_url);
+    let mut total = 0;
+
+    println!("{} {}", style("Fee payers:").bold(), keypairs.len());
+
+    for keypair in &keypairs {
+        let balance = rpc_client.get_balance(&keypair.pubkey())?;
+        total += balance;
+        println!(
+            "  {} ◎ {}",
+            keypair.pubkey(),
+            balance as f64 / LAMPORTS_PER_SOL as f64
+        );
+    }
+
+    println!(
+        "\n{} ◎ {}",
+        style("Total balance:").bold(),
+        total as f64 / LAMPORTS_PER_SOL as f64
+    );
+
+    Ok(())
+}
+
+pub fn process_fee_payers_sweep(args: FeePayersSweepArgs) -> Result<()> {
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+
+    let pb = spinner_with_style();
+    pb.set_message("Sweeping fee payers...");
+
+    let swept = sweep_fee_payers(&sugar_config)?;
+
+    pb.finish_and_clear();
+    print_swept(&sugar_config.keypair.pubkey(), swept);
+
+    Ok(())
+}
+
+/// Prints the lamports returned to the wallet by the fee payers.
+pub fn print_swept(wallet: &Pubkey, swept: u64) {
+    if swept > 0 {
+        println!(
+            "Returned ◎ {} from the fee payers to {}",
+            swept as f64 / LAMPORTS_PER_SOL as f64,
+            wallet
+        );
+    }
+}
@@ -173,7 +173,7 @@ pub fn initialize<C: Deref<Target = impl Signer> + Clone>(
             },
             label: label.to_owned(),
         });
-    let sig = builder.send()?;
+    let sig = builder.send_or_plan("initialize freeze")?;
 
     Ok(sig)
 }
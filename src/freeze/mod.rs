@@ -21,6 +21,7 @@ use crate::{
     cache::load_cache,
     common::*,
     config::{get_config_data, Cluster, ConfigData, SugarConfig},
+    dry_run::SendOrPlan,
     pdas::*,
     setup::get_rpc_url,
     utils::{
@@ -11,7 +11,12 @@ use mpl_token_metadata::{
 };
 
 use super::*;
-use crate::config::TokenStandard;
+use crate::{
+    config::TokenStandard,
+    rate_limit::{
+        throttle_async, throttled_client, throttled_client_with_timeout, SEND_TRANSACTION,
+    },
+};
 
 pub struct ThawArgs {
     pub keypair: Option<String>,
@@ -86,7 +91,7 @@ pub async fn process_thaw(args: ThawArgs) -> Result<()> {
     let client = setup_client(&sugar_config)?;
     let program = client.program(mpl_candy_guard::ID);
     let rpc_url = get_rpc_url(args.rpc_url.clone());
-    let rpc_client = RpcClient::new(&rpc_url);
+    let rpc_client = throttled_client(&rpc_url);
 
     // candy guard id specified takes precedence over the one from the cache
     let candy_guard_id = match args.candy_guard {
@@ -293,7 +298,8 @@ pub async fn process_thaw(args: ThawArgs) -> Result<()> {
             &nft,
             &args.label,
             freeze_guard,
-        )?;
+        )
+        .await?;
 
         pb.finish_with_message(format!(
             "{} {}",
@@ -314,9 +320,9 @@ pub async fn process_thaw(args: ThawArgs) -> Result<()> {
     let pb = spinner_with_style();
     pb.set_message("Searching...");
 
-    let solana_cluster: Cluster = get_cluster(program.rpc())?;
+    let solana_cluster: Cluster = get_cluster(&program.rpc())?;
     let rpc_url = get_rpc_url(args.rpc_url);
-    let client = RpcClient::new_with_timeout(
+    let client = throttled_client_with_timeout(
         &rpc_url,
         Duration::from_secs(if let Some(timeout) = args.timeout {
             timeout
@@ -540,6 +546,7 @@ pub async fn process_thaw(args: ThawArgs) -> Result<()> {
                 &label,
                 guard,
             )
+            .await
             .map_err(|e| {
                 failed_thaws.lock().unwrap().push(FailedThaw {
                     nft: nft.clone(),
@@ -586,7 +593,7 @@ pub async fn process_thaw(args: ThawArgs) -> Result<()> {
     Ok(())
 }
 
-fn thaw_nft(
+async fn thaw_nft(
     config: Arc<SugarConfig>,
     candy_guard_id: &Pubkey,
     candy_machine_id: &Pubkey,
@@ -595,6 +602,9 @@ fn thaw_nft(
     label: &Option<String>,
     freeze_guard: GuardType,
 ) -> Result<Signature> {
+    // waits before the client is created, since it cannot be held across an await
+    throttle_async(SEND_TRANSACTION).await;
+
     let client = setup_client(&config)?;
     let program = client.program(mpl_candy_guard::ID);
 
@@ -712,7 +722,8 @@ fn thaw_nft(
             },
             label: label.to_owned(),
         });
-    let sig = builder.send()?;
+
+    let sig = builder.send_or_plan("thaw")?;
 
     Ok(sig)
 }
@@ -218,7 +218,7 @@ pub fn unlock_funds<C: Deref<Target = impl Signer> + Clone>(
             },
             label: label.to_owned(),
         });
-    let sig = builder.send()?;
+    let sig = builder.send_or_plan("unlock funds")?;
 
     Ok(sig)
 }
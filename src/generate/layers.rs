@@ -0,0 +1,191 @@
+use std::{collections::HashSet, fs, path::PathBuf};
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, rngs::StdRng, SeedableRng};
+
+use crate::common::*;
+
+/// Maximum number of attempts to find a new unique combination for each item.
+const MAX_ATTEMPTS: usize = 1000;
+
+/// Configuration of the trait layers.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraitsConfig {
+    /// Name of the items, followed by the item number.
+    pub name: String,
+    #[serde(default)]
+    pub symbol: String,
+    #[serde(default)]
+    pub description: String,
+    /// Number of items to generate.
+    pub number: u64,
+    /// Seed of the random generator; a random seed is used when not specified.
+    pub seed: Option<u64>,
+    /// Layers from the bottom to the top of the image.
+    pub layers: Vec<LayerConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerConfig {
+    /// Name of the layer, used as the trait type.
+    pub name: String,
+    /// Directory of the layer images, defaults to the name of the layer.
+    pub directory: Option<String>,
+    /// Rarity weight of each trait (file name without extension), defaults to 1.
+    #[serde(default)]
+    pub weights: HashMap<String, u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub name: String,
+    pub traits: Vec<Trait>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trait {
+    pub name: String,
+    pub path: PathBuf,
+    pub weight: u32,
+}
+
+/// Load the traits of each layer from the PNG files of its directory.
+pub fn load_layers(layers_dir: &Path, config: &TraitsConfig) -> Result<Vec<Layer>> {
+    let mut layers = Vec::with_capacity(config.layers.len());
+
+    for layer_config in &config.layers {
+        let directory = layers_dir.join(
+            layer_config
+                .directory
+                .as_ref()
+                .unwrap_or(&layer_config.name),
+        );
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(&directory)
+            .map_err(|e| anyhow!("Failed to read layer directory {:?}: {}", directory, e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .map_or(false, |ext| ext.eq_ignore_ascii_case("png"))
+            })
+            .collect();
+        paths.sort();
+
+        let traits: Vec<Trait> = paths
+            .into_iter()
+            .map(|path| {
+                let name = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or_default()
+                    .to_string();
+                let weight = *layer_config.weights.get(&name).unwrap_or(&1);
+                Trait { name, path, weight }
+            })
+            .collect();
+
+        for name in layer_config.weights.keys() {
+            if !traits.iter().any(|t| t.name == *name) {
+                return Err(anyhow!(
+                    "Trait '{}' of layer '{}' has a weight but no image in {:?}",
+                    name,
+                    layer_config.name,
+                    directory
+                ));
+            }
+        }
+
+        if traits.iter().all(|t| t.weight == 0) {
+            return Err(anyhow!(
+                "Layer '{}' has no traits with a positive weight",
+                layer_config.name
+            ));
+        }
+
+        layers.push(Layer {
+            name: layer_config.name.clone(),
+            traits,
+        });
+    }
+
+    Ok(layers)
+}
+
+/// Select the trait of each layer for the specified number of items, skipping
+/// combinations that were already selected. Returns the trait indices of each item
+/// and the number of duplicated combinations discarded.
+pub fn generate_combinations(
+    layers: &[Layer],
+    number: u64,
+    seed: u64,
+) -> Result<(Vec<Vec<usize>>, usize)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let distributions = layers
+        .iter()
+        .map(|layer| WeightedIndex::new(layer.traits.iter().map(|t| t.weight)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut seen = HashSet::new();
+    let mut combinations = Vec::with_capacity(number as usize);
+    let mut duplicates = 0;
+
+    while (combinations.len() as u64) < number {
+        let mut found = false;
+
+        for _ in 0..MAX_ATTEMPTS {
+            let combination: Vec<usize> = distributions
+                .iter()
+                .map(|distribution| distribution.sample(&mut rng))
+                .collect();
+
+            if seen.insert(combination.clone()) {
+                combinations.push(combination);
+                found = true;
+                break;
+            }
+
+            duplicates += 1;
+        }
+
+        if !found {
+            return Err(anyhow!(
+                "Could only generate {} unique combinations out of {}, add more traits or \
+                reduce the number of items",
+                combinations.len(),
+                number
+            ));
+        }
+    }
+
+    Ok((combinations, duplicates))
+}
+
+#[test]
+fn test_combinations_are_unique_and_deterministic() {
+    let layer = |name: &str, traits: &[&str]| Layer {
+        name: name.to_string(),
+        traits: traits
+            .iter()
+            .map(|t| Trait {
+                name: t.to_string(),
+                path: PathBuf::from(format!("{t}.png")),
+                weight: 1,
+            })
+            .collect(),
+    };
+    let layers = vec![
+        layer("Background", &["Blue", "Red"]),
+        layer("Body", &["Cat", "Dog", "Fox"]),
+    ];
+
+    let (first, _) = generate_combinations(&layers, 6, 42).unwrap();
+    let (second, _) = generate_combinations(&layers, 6, 42).unwrap();
+    assert_eq!(first, second);
+
+    let unique: HashSet<&Vec<usize>> = first.iter().collect();
+    assert_eq!(unique.len(), 6);
+
+    assert!(generate_combinations(&layers, 7, 42).is_err());
+}
@@ -0,0 +1,5 @@
+pub mod layers;
+pub mod process;
+
+pub use layers::*;
+pub use process::*;
@@ -0,0 +1,165 @@
+use std::fs;
+
+use console::style;
+use image::{imageops, RgbaImage};
+use rayon::prelude::*;
+
+use crate::{
+    common::*,
+    generate::{generate_combinations, load_layers, Layer, TraitsConfig},
+    utils::*,
+    validate::format::{Attribute, FileAttr, Metadata, Property},
+};
+
+pub struct GenerateArgs {
+    pub layers: String,
+    pub traits: String,
+    pub output: String,
+    pub number: Option<u64>,
+    pub seed: Option<u64>,
+}
+
+pub fn process_generate(args: GenerateArgs) -> Result<()> {
+    println!(
+        "{} {}Loading trait layers",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let file = File::open(&args.traits)
+        .map_err(|e| anyhow!("Failed to open traits config '{}': {}", args.traits, e))?;
+    let config: TraitsConfig = serde_json::from_reader(file)
+        .map_err(|e| anyhow!("Failed to parse traits config '{}': {}", args.traits, e))?;
+
+    let layers = load_layers(Path::new(&args.layers), &config)?;
+    let number = args.number.unwrap_or(config.number);
+    let seed = args
+        .seed
+        .or(config.seed)
+        .unwrap_or_else(rand::random::<u64>);
+
+    let combinations: u64 = layers
+        .iter()
+        .map(|layer| layer.traits.len() as u64)
+        .product();
+
+    println!(
+        "Found {} layers with {} possible combinations",
+        layers.len(),
+        combinations
+    );
+    println!("{} {}", style("Seed:").bold(), seed);
+
+    let output = Path::new(&args.output);
+
+    if output.exists() && fs::read_dir(output)?.next().is_some() {
+        return Err(anyhow!(
+            "Output directory '{}' is not empty",
+            output.display()
+        ));
+    }
+
+    fs::create_dir_all(output)?;
+
+    let (items, duplicates) = generate_combinations(&layers, number, seed)?;
+
+    println!(
+        "\n{} {}Generating {} assets",
+        style("[2/2]").bold().dim(),
+        ASSETS_EMOJI,
+        items.len()
+    );
+
+    let pb = progress_bar_with_style(items.len() as u64);
+
+    items
+        .par_iter()
+        .enumerate()
+        .try_for_each(|(index, combination)| -> Result<()> {
+            let image = compose_image(&layers, combination)?;
+            image.save(output.join(format!("{index}.png")))?;
+
+            let metadata = create_metadata(&config, &layers, index, combination);
+            let file = File::create(output.join(format!("{index}.json")))?;
+            serde_json::to_writer_pretty(file, &metadata)?;
+
+            pb.inc(1);
+            Ok(())
+        })?;
+
+    pb.finish_and_clear();
+
+    println!(
+        "\n{}Generated {} assets in '{}' ({} duplicate combinations discarded)",
+        COMPLETE_EMOJI,
+        items.len(),
+        output.display(),
+        duplicates
+    );
+
+    Ok(())
+}
+
+/// Composite the images of the traits, from the bottom to the top layer.
+fn compose_image(layers: &[Layer], combination: &[usize]) -> Result<RgbaImage> {
+    let mut base: Option<RgbaImage> = None;
+
+    for (layer, index) in layers.iter().zip(combination) {
+        let path = &layer.traits[*index].path;
+        let image = image::open(path)
+            .map_err(|e| anyhow!("Failed to open image {:?}: {}", path, e))?
+            .to_rgba8();
+
+        base = match base {
+            Some(mut base) => {
+                if base.dimensions() != image.dimensions() {
+                    return Err(anyhow!(
+                        "Image {:?} does not have the same dimensions as the layers below it",
+                        path
+                    ));
+                }
+                imageops::overlay(&mut base, &image, 0, 0);
+                Some(base)
+            }
+            None => Some(image),
+        };
+    }
+
+    base.ok_or_else(|| anyhow!("No layers to compose"))
+}
+
+fn create_metadata(
+    config: &TraitsConfig,
+    layers: &[Layer],
+    index: usize,
+    combination: &[usize],
+) -> Metadata {
+    let image = format!("{index}.png");
+
+    Metadata {
+        name: format!("{}{}", config.name, index + 1),
+        symbol: Some(config.symbol.clone()),
+        description: config.description.clone(),
+        image: image.clone(),
+        attributes: Some(
+            layers
+                .iter()
+                .zip(combination)
+                .map(|(layer, index)| Attribute {
+                    trait_type: layer.name.clone(),
+                    value: layer.traits[*index].name.clone(),
+                })
+                .collect(),
+        ),
+        properties: Property {
+            files: vec![FileAttr {
+                uri: image,
+                file_type: "image/png".to_string(),
+                cdn: false,
+            }],
+            creators: None,
+            category: Some("image".to_string()),
+        },
+        ..Default::default()
+    }
+}
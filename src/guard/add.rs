@@ -9,7 +9,16 @@ use mpl_candy_guard::{
 };
 use mpl_candy_machine_core::constants::EMPTY_STR;
 
-use crate::{cache::load_cache, candy_machine::*, common::*, config::get_config_data, utils::*};
+use crate::{
+    cache::load_cache,
+    candy_machine::CANDY_MACHINE_ID,
+    common::*,
+    config::print_guard_dates,
+    core_candy_machine::{get_candy_machine_program, CORE_CANDY_MACHINE_ID},
+    dry_run::SendOrPlan,
+    guard::{check_guard_accounts, load_guards},
+    utils::*,
+};
 
 pub struct GuardAddArgs {
     pub keypair: Option<String>,
@@ -18,6 +27,7 @@ pub struct GuardAddArgs {
     pub config: String,
     pub candy_machine: Option<String>,
     pub candy_guard: Option<String>,
+    pub from_file: Option<String>,
 }
 
 pub fn process_guard_add(args: GuardAddArgs) -> Result<()> {
@@ -67,22 +77,29 @@ pub fn process_guard_add(args: GuardAddArgs) -> Result<()> {
     };
 
     let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
-    let config_data = get_config_data(&args.config)?;
     let client = setup_client(&sugar_config)?;
+    // the guard set of the core candy guard has a different layout, so the guards of the
+    // config cannot be serialized for it
+    if get_candy_machine_program(&sugar_config, &candy_machine_id)? == CORE_CANDY_MACHINE_ID {
+        return Err(anyhow!(
+            "Adding guards to a core candy machine is not supported, the core candy guard \
+            uses a different guard layout than the candy guard program"
+        ));
+    }
+
     let payer = sugar_config.keypair;
     let program = client.program(mpl_candy_guard::ID);
 
     let candy_guard = if candy_guard_id.is_empty() {
         println!("\n[2/3] {}Initializing a candy guard", GUARD_EMOJI);
+
+        let (data, resolved_dates) = load_guards(&args.config, args.from_file.as_deref())?;
+        print_guard_dates(&data);
+        check_guard_accounts(&program, &data)?;
+
         let pb = spinner_with_style();
         pb.set_message("Initializing...");
 
-        let data = if let Some(guards) = &config_data.guards {
-            guards.to_guard_format()?
-        } else {
-            return Err(anyhow!("Missing guards configuration."));
-        };
-
         let base = Keypair::new();
         let (candy_guard, _) = Pubkey::find_program_address(
             &[b"candy_guard", base.pubkey().as_ref()],
@@ -111,10 +128,11 @@ pub fn process_guard_add(args: GuardAddArgs) -> Result<()> {
             })
             .signer(&base);
 
-        let sig = tx.send()?;
+        let sig = tx.send_or_plan("initialize candy guard")?;
 
         pb.finish_and_clear();
         println!("{} {}", style("Signature:").bold(), sig);
+        resolved_dates.save()?;
 
         candy_guard
     } else {
@@ -129,18 +147,16 @@ pub fn process_guard_add(args: GuardAddArgs) -> Result<()> {
             }
         };
 
+        let (data, resolved_dates) = load_guards(&args.config, args.from_file.as_deref())?;
+        print_guard_dates(&data);
+        check_guard_accounts(&program, &data)?;
+
         let pb = spinner_with_style();
         pb.set_message("Connecting...");
 
         // validates that the account exists
         let _candy_guard = program.rpc().get_account(&candy_guard_id)?;
 
-        let data = if let Some(guards) = &config_data.guards {
-            guards.to_guard_format()?
-        } else {
-            return Err(anyhow!("Missing guards configuration."));
-        };
-
         let mut serialized_data = vec![0; data.size()];
         data.save(&mut serialized_data)?;
 
@@ -162,9 +178,10 @@ pub fn process_guard_add(args: GuardAddArgs) -> Result<()> {
                 data: serialized_data,
             });
 
-        tx.send()?;
+        tx.send_or_plan("update candy guard")?;
 
         pb.finish_with_message("Done");
+        resolved_dates.save()?;
 
         candy_guard_id
     };
@@ -194,7 +211,7 @@ pub fn process_guard_add(args: GuardAddArgs) -> Result<()> {
         })
         .args(Wrap {});
 
-    let sig = tx.send()?;
+    let sig = tx.send_or_plan("wrap")?;
 
     pb.finish_and_clear();
     println!("{} {}", style("Signature:").bold(), sig);
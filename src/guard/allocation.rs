@@ -0,0 +1,381 @@
+use std::fs;
+
+use anchor_client::solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::AccountMeta,
+};
+use console::style;
+use mpl_candy_guard::{
+    accounts::Route as RouteAccount,
+    guards::Allocation,
+    instruction::Route,
+    instructions::RouteArgs,
+    state::{CandyGuard, CandyGuardData, GuardType, DATA_OFFSET},
+};
+
+use crate::{
+    common::*,
+    config::{
+        get_config_data, set_path, Allocation as AllocationConfig, CandyGuardData as GuardConfig,
+    },
+    dry_run::{is_dry_run, SendOrPlan},
+    guard::{find_allocation_tracker_pda, get_wrap_ids, process_guard_update, GuardUpdateArgs},
+    lock::lock_file,
+    utils::*,
+};
+
+pub struct GuardAllocationInitArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_machine: Option<String>,
+    pub candy_guard: Option<String>,
+    pub label: Option<String>,
+}
+
+pub struct GuardAllocationResetArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub config: String,
+    pub candy_machine: Option<String>,
+    pub candy_guard: Option<String>,
+    pub label: Option<String>,
+}
+
+/// Returns the allocation guard of a group (or of the default guard set), falling
+/// back to the default guards like the candy guard does when minting.
+fn find_allocation(data: &CandyGuardData, label: &Option<String>) -> Result<Allocation> {
+    let allocation = match label {
+        Some(label) => {
+            let group = data
+                .groups
+                .iter()
+                .flatten()
+                .find(|group| &group.label == label)
+                .ok_or_else(|| anyhow!("Group '{}' not found in the candy guard.", label))?;
+
+            group
+                .guards
+                .allocation
+                .clone()
+                .or_else(|| data.default.allocation.clone())
+        }
+        None => data.default.allocation.clone(),
+    };
+
+    allocation.ok_or_else(|| {
+        anyhow!(
+            "The {} guard set has no allocation guard.",
+            label.as_deref().unwrap_or("default")
+        )
+    })
+}
+
+pub fn process_guard_allocation_init(args: GuardAllocationInitArgs) -> Result<()> {
+    println!(
+        "{} {}Loading candy guard",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let (candy_machine_id, candy_guard_id) =
+        get_wrap_ids(&args.cache, args.candy_machine, args.candy_guard)?;
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(mpl_candy_guard::ID);
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let account_data = program.rpc().get_account_data(&candy_guard_id)?;
+    let data = CandyGuardData::load(&account_data[DATA_OFFSET..])?;
+    let allocation = find_allocation(&data, &args.label)?;
+    let tracker = find_allocation_tracker_pda(allocation.id, &candy_guard_id, &candy_machine_id);
+
+    pb.finish_and_clear();
+
+    println!("{} {}", style("Candy guard ID:").bold(), candy_guard_id);
+    println!(
+        "{} {} (id {}, limit {})",
+        style("Allocation tracker:").bold(),
+        tracker,
+        allocation.id,
+        allocation.limit
+    );
+
+    println!(
+        "\n{} {}Initializing allocation tracker",
+        style("[2/2]").bold().dim(),
+        COMPUTER_EMOJI
+    );
+
+    if program.rpc().get_account(&tracker).is_ok() {
+        println!(
+            "{}",
+            style("The allocation tracker is already initialized.").dim()
+        );
+        return Ok(());
+    }
+
+    // only the candy guard authority can initialize the tracker
+    let candy_guard: CandyGuard = program.account(candy_guard_id)?;
+
+    if candy_guard.authority != program.payer() {
+        return Err(anyhow!(
+            "Only the candy guard authority ({}) can initialize the allocation tracker.",
+            candy_guard.authority
+        ));
+    }
+
+    let pb = spinner_with_style();
+    pb.set_message("Sending route transaction...");
+
+    let signature = program
+        .request()
+        .instruction(ComputeBudgetInstruction::set_compute_unit_limit(
+            COMPUTE_UNITS,
+        ))
+        .instruction(ComputeBudgetInstruction::set_compute_unit_price(
+            PRIORITY_FEE,
+        ))
+        .accounts(RouteAccount {
+            candy_guard: candy_guard_id,
+            candy_machine: candy_machine_id,
+            payer: program.payer(),
+        })
+        .accounts(vec![
+            AccountMeta::new(tracker, false),
+            AccountMeta::new_readonly(program.payer(), true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ])
+        .args(Route {
+            args: RouteArgs {
+                guard: GuardType::Allocation,
+                data: Vec::new(),
+            },
+            label: args.label,
+        })
+        .send_or_plan("initialize allocation tracker")?;
+
+    pb.finish_with_message(format!("{} {}", style("Signature:").bold(), signature));
+
+    Ok(())
+}
+
+/// Returns the allocation guard to reset and its path in the config: the allocation of the
+/// group takes precedence over the default one.
+fn allocation_to_reset<'a>(
+    guards: &'a GuardConfig,
+    label: Option<&str>,
+) -> Result<(String, &'a AllocationConfig)> {
+    match label {
+        Some(label) => {
+            let (index, group) = guards
+                .groups
+                .iter()
+                .flatten()
+                .enumerate()
+                .find(|(_, group)| group.label == label)
+                .ok_or_else(|| anyhow!("Group '{}' not found in the config.", label))?;
+
+            match &group.guards.allocation {
+                Some(allocation) => Ok((
+                    format!("guards.groups[{index}].guards.allocation.id"),
+                    allocation,
+                )),
+                None => match &guards.default.allocation {
+                    Some(allocation) => {
+                        Ok(("guards.default.allocation.id".to_string(), allocation))
+                    }
+                    None => Err(anyhow!("Group '{}' has no allocation guard.", label)),
+                },
+            }
+        }
+        None => match &guards.default.allocation {
+            Some(allocation) => Ok(("guards.default.allocation.id".to_string(), allocation)),
+            None => Err(anyhow!("The default guard set has no allocation guard.")),
+        },
+    }
+}
+
+/// Returns the (sorted) allocation ids of the guard sets: ids in use by any guard set
+/// share the same trackers.
+fn used_allocation_ids(guards: &GuardConfig) -> Vec<u8> {
+    let mut used: Vec<u8> = guards
+        .groups
+        .iter()
+        .flatten()
+        .filter_map(|group| group.guards.allocation.as_ref())
+        .chain(guards.default.allocation.as_ref())
+        .map(|allocation| allocation.id)
+        .collect();
+    used.sort_unstable();
+    used
+}
+
+/// Returns the first id after `current` that is not used by a guard set and has no
+/// allocation tracker yet.
+fn next_allocation_id(
+    current: u8,
+    used: &[u8],
+    mut has_tracker: impl FnMut(u8) -> Result<bool>,
+) -> Result<u8> {
+    for id in (current..=u8::MAX).skip(1) {
+        if used.binary_search(&id).is_err() && !has_tracker(id)? {
+            return Ok(id);
+        }
+    }
+
+    Err(anyhow!("No allocation id available after {}.", current))
+}
+
+/// The candy guard cannot reset the count of an allocation tracker, so a reset moves
+/// the guard set to a new allocation id: the new guards are sent to the candy guard, the
+/// config is updated once they are set and the tracker of the new id is initialized.
+pub fn process_guard_allocation_reset(args: GuardAllocationResetArgs) -> Result<()> {
+    println!(
+        "{} {}Selecting a new allocation id",
+        style("[1/3]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let (candy_machine_id, candy_guard_id) = get_wrap_ids(
+        &args.cache,
+        args.candy_machine.clone(),
+        args.candy_guard.clone(),
+    )?;
+
+    let config_data = get_config_data(&args.config)?;
+    let guards = config_data
+        .guards
+        .as_ref()
+        .ok_or_else(|| anyhow!("Missing guards configuration."))?;
+
+    let (path, current) = allocation_to_reset(guards, args.label.as_deref())?;
+    let used = used_allocation_ids(guards);
+
+    let sugar_config = sugar_setup(args.keypair.clone(), args.rpc_url.clone())?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(mpl_candy_guard::ID);
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let rpc_client = program.rpc();
+    let new_id = next_allocation_id(current.id, &used, |id| {
+        let tracker = find_allocation_tracker_pda(id, &candy_guard_id, &candy_machine_id);
+        Ok(rpc_client
+            .get_account_with_commitment(&tracker, rpc_client.commitment())?
+            .value
+            .is_some())
+    })?;
+
+    pb.finish_and_clear();
+
+    lock_file(&args.config)?;
+    let content = fs::read_to_string(&args.config)
+        .map_err(|e| anyhow!("Failed to read '{}': {}", args.config, e))?;
+    let updated = set_path(&content, &path, &json!(new_id))?;
+
+    // the candy guard is updated from a copy of the config with the new id, which only
+    // replaces the config once the update succeeded
+    let updated_config = format!("{}.allocation", args.config);
+    fs::write(&updated_config, updated)
+        .map_err(|e| anyhow!("Failed to write '{}': {}", updated_config, e))?;
+
+    println!(
+        "{} {} -> {} ({})",
+        style("Allocation id:").bold(),
+        current.id,
+        new_id,
+        path
+    );
+
+    println!(
+        "\n{} {}Updating candy guard\n",
+        style("[2/3]").bold().dim(),
+        COMPUTER_EMOJI
+    );
+
+    let result = process_guard_update(GuardUpdateArgs {
+        keypair: args.keypair.clone(),
+        rpc_url: args.rpc_url.clone(),
+        cache: args.cache.clone(),
+        config: updated_config.clone(),
+        candy_guard: Some(candy_guard_id.to_string()),
+        from_file: None,
+    });
+
+    // a dry run plans the transactions without changing the config file
+    if result.is_ok() && !is_dry_run() {
+        fs::rename(&updated_config, &args.config)
+            .map_err(|e| anyhow!("Failed to write '{}': {}", args.config, e))?;
+    } else {
+        fs::remove_file(&updated_config)
+            .map_err(|e| anyhow!("Failed to remove '{}': {}", updated_config, e))?;
+    }
+    result?;
+
+    println!(
+        "\n{} {}Initializing the new allocation tracker\n",
+        style("[3/3]").bold().dim(),
+        COMPUTER_EMOJI
+    );
+
+    process_guard_allocation_init(GuardAllocationInitArgs {
+        keypair: args.keypair,
+        rpc_url: args.rpc_url,
+        cache: args.cache,
+        candy_machine: Some(candy_machine_id.to_string()),
+        candy_guard: Some(candy_guard_id.to_string()),
+        label: args.label,
+    })
+}
+
+#[test]
+fn test_allocation_reset() {
+    use crate::config::{Group, GuardSet};
+
+    let allocation = |id| AllocationConfig { id, limit: 100 };
+    let mut guards = GuardConfig {
+        default: GuardSet {
+            allocation: Some(allocation(0)),
+            ..GuardSet::default()
+        },
+        groups: Some(vec![
+            Group {
+                label: "public".to_string(),
+                guards: GuardSet::default(),
+            },
+            Group {
+                label: "vip".to_string(),
+                guards: GuardSet {
+                    allocation: Some(allocation(3)),
+                    ..GuardSet::default()
+                },
+            },
+        ]),
+    };
+
+    let (path, current) = allocation_to_reset(&guards, Some("vip")).unwrap();
+    assert_eq!(path, "guards.groups[1].guards.allocation.id");
+    assert_eq!(current.id, 3);
+
+    // groups without allocation use the default one
+    let (path, current) = allocation_to_reset(&guards, Some("public")).unwrap();
+    assert_eq!(path, "guards.default.allocation.id");
+    assert_eq!(current.id, 0);
+    assert!(allocation_to_reset(&guards, Some("og")).is_err());
+
+    // the ids of the other guard sets and the ones with a tracker are skipped
+    let used = used_allocation_ids(&guards);
+    assert_eq!(used, vec![0, 3]);
+    assert_eq!(next_allocation_id(0, &used, |id| Ok(id == 1)).unwrap(), 2);
+    assert_eq!(next_allocation_id(2, &used, |_| Ok(false)).unwrap(), 4);
+    assert!(next_allocation_id(u8::MAX, &used, |_| Ok(false)).is_err());
+    assert!(next_allocation_id(0, &used, |_| Err(anyhow!("rpc error"))).is_err());
+
+    guards.default.allocation = None;
+    assert!(allocation_to_reset(&guards, None).is_err());
+}
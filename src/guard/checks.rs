@@ -0,0 +1,312 @@
+use std::rc::Rc;
+
+use anchor_client::solana_sdk::{account::Account, program_pack::Pack};
+use console::style;
+use mpl_candy_guard::state::{CandyGuardData, GuardSet};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id, instruction::create_associated_token_account,
+};
+use spl_token::state::{Account as TokenAccount, Mint};
+
+use crate::{common::*, dry_run::SendOrPlan, utils::*};
+
+/// Token guard of a guard set that transfers tokens to a destination token account.
+struct TokenTransfer {
+    guard: &'static str,
+    amount: u64,
+    mint: Pubkey,
+    destination_ata: Pubkey,
+}
+
+/// Checks the accounts referenced by the token guards (tokenPayment, freezeTokenPayment,
+/// token2022Payment, tokenBurn and nftPayment) before they are written on-chain: the
+/// mints must exist, the destination token accounts must hold the mint tokens and the
+/// amounts are shown in whole tokens so decimal mistakes are caught before minting.
+pub fn check_guard_accounts(program: &Program<Rc<Keypair>>, data: &CandyGuardData) -> Result<()> {
+    check_guard_set_accounts(program, "default", &data.default)?;
+
+    if let Some(groups) = &data.groups {
+        for group in groups {
+            check_guard_set_accounts(program, &group.label, &group.guards)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn check_guard_set_accounts(
+    program: &Program<Rc<Keypair>>,
+    label: &str,
+    guard_set: &GuardSet,
+) -> Result<()> {
+    let mut transfers = Vec::new();
+
+    if let Some(token_payment) = &guard_set.token_payment {
+        transfers.push(TokenTransfer {
+            guard: "tokenPayment",
+            amount: token_payment.amount,
+            mint: token_payment.mint,
+            destination_ata: token_payment.destination_ata,
+        });
+    }
+
+    if let Some(freeze_token_payment) = &guard_set.freeze_token_payment {
+        transfers.push(TokenTransfer {
+            guard: "freezeTokenPayment",
+            amount: freeze_token_payment.amount,
+            mint: freeze_token_payment.mint,
+            destination_ata: freeze_token_payment.destination_ata,
+        });
+    }
+
+    if let Some(token2022_payment) = &guard_set.token2022_payment {
+        transfers.push(TokenTransfer {
+            guard: "token2022Payment",
+            amount: token2022_payment.amount,
+            mint: token2022_payment.mint,
+            destination_ata: token2022_payment.destination_ata,
+        });
+    }
+
+    for transfer in transfers {
+        let (token_program, mint) = get_mint(program, transfer.guard, label, &transfer.mint)?;
+        check_amount(transfer.guard, label, transfer.amount, &mint)?;
+        check_destination_ata(program, &transfer, label, &token_program)?;
+    }
+
+    if let Some(token_burn) = &guard_set.token_burn {
+        let (_, mint) = get_mint(program, "tokenBurn", label, &token_burn.mint)?;
+        check_amount("tokenBurn", label, token_burn.amount, &mint)?;
+    }
+
+    if let Some(nft_payment) = &guard_set.nft_payment {
+        let (_, mint) = get_mint(
+            program,
+            "nftPayment",
+            label,
+            &nft_payment.required_collection,
+        )?;
+
+        if mint.decimals != 0 {
+            return Err(anyhow!(
+                "The nftPayment required collection {} ({}) is not an NFT mint ({} decimals).",
+                nft_payment.required_collection,
+                label,
+                mint.decimals
+            ));
+        }
+
+        // the NFT is transferred to the ATA of the destination, created at mint time
+        if let Some(account) = get_account(program, &nft_payment.destination)? {
+            if account.owner == spl_token::ID {
+                return Err(anyhow!(
+                    "The nftPayment destination {} ({}) is a token account: use the address \
+                    of the wallet that will receive the NFTs.",
+                    nft_payment.destination,
+                    label
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn get_account(program: &Program<Rc<Keypair>>, address: &Pubkey) -> Result<Option<Account>> {
+    let rpc = program.rpc();
+    Ok(rpc
+        .get_account_with_commitment(address, rpc.commitment())?
+        .value)
+}
+
+/// Returns the token program and the state of a mint.
+fn get_mint(
+    program: &Program<Rc<Keypair>>,
+    guard: &str,
+    label: &str,
+    address: &Pubkey,
+) -> Result<(Pubkey, Mint)> {
+    let account = get_account(program, address)?.ok_or_else(|| {
+        anyhow!(
+            "The {} mint {} ({}) does not exist: check the mint address and the RPC cluster.",
+            guard,
+            address,
+            label
+        )
+    })?;
+
+    if account.data.len() < Mint::LEN {
+        return Err(anyhow!(
+            "The {} mint {} ({}) is not a token mint account.",
+            guard,
+            address,
+            label
+        ));
+    }
+
+    // token-2022 mints may have extensions after the base mint data
+    let mint = Mint::unpack_from_slice(&account.data[..Mint::LEN]).map_err(|_| {
+        anyhow!(
+            "The {} mint {} ({}) is not a token mint account.",
+            guard,
+            address,
+            label
+        )
+    })?;
+
+    Ok((account.owner, mint))
+}
+
+/// Shows the amount in whole tokens, asking for confirmation when it is less than
+/// one token (amounts are specified in the smallest unit of the mint).
+fn check_amount(guard: &str, label: &str, amount: u64, mint: &Mint) -> Result<()> {
+    let unit = token_unit(mint.decimals);
+
+    println!(
+        "{} {} tokens ({} base units, {} decimals)",
+        style(format!("{guard} amount ({label}):")).bold(),
+        whole_tokens(amount, mint.decimals),
+        amount,
+        mint.decimals
+    );
+
+    // a unit that does not fit in a u64 is more than any amount
+    if unit.map_or(true, |unit| amount < unit) {
+        let unit_text = unit.map_or_else(|| format!("10^{}", mint.decimals), |u| u.to_string());
+        let theme = get_dialoguer_theme();
+        let prompt = format!(
+            "The {guard} amount is less than one token, amounts are in base units \
+            (1 token = {unit_text}). Continue?"
+        );
+
+        if !confirm_with_theme(&theme, &prompt)? {
+            return Err(anyhow!(
+                "Update the {} amount of '{}' in the config file: {} tokens = {} base units.",
+                guard,
+                label,
+                amount,
+                unit.map_or(unit_text, |unit| amount.saturating_mul(unit).to_string())
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Base units of one token, `None` when it does not fit in a u64.
+fn token_unit(decimals: u8) -> Option<u64> {
+    10u64.checked_pow(decimals as u32)
+}
+
+/// Amount in whole tokens.
+fn whole_tokens(amount: u64, decimals: u8) -> f64 {
+    amount as f64 / 10f64.powi(decimals as i32)
+}
+
+/// Checks that the destination token account holds tokens of the mint, creating it
+/// when it is the missing ATA of the payer.
+fn check_destination_ata(
+    program: &Program<Rc<Keypair>>,
+    transfer: &TokenTransfer,
+    label: &str,
+    token_program: &Pubkey,
+) -> Result<()> {
+    let account = match get_account(program, &transfer.destination_ata)? {
+        Some(account) => account,
+        None => return create_destination_ata(program, transfer, label, token_program),
+    };
+
+    if account.owner != *token_program || account.data.len() < TokenAccount::LEN {
+        return Err(anyhow!(
+            "The {} destination {} ({}) is not a token account: use the associated token \
+            account of the wallet receiving the payments.",
+            transfer.guard,
+            transfer.destination_ata,
+            label
+        ));
+    }
+
+    let token_account = TokenAccount::unpack_from_slice(&account.data[..TokenAccount::LEN])?;
+
+    if token_account.mint != transfer.mint {
+        return Err(anyhow!(
+            "The {} destination {} ({}) holds tokens of mint {}, not of the payment mint {}.",
+            transfer.guard,
+            transfer.destination_ata,
+            label,
+            token_account.mint,
+            transfer.mint
+        ));
+    }
+
+    Ok(())
+}
+
+fn create_destination_ata(
+    program: &Program<Rc<Keypair>>,
+    transfer: &TokenTransfer,
+    label: &str,
+    token_program: &Pubkey,
+) -> Result<()> {
+    let payer = program.payer();
+    let payer_ata =
+        get_associated_token_address_with_program_id(&payer, &transfer.mint, token_program);
+
+    if payer_ata != transfer.destination_ata {
+        return Err(anyhow!(
+            "The {} destination {} ({}) does not exist: create it with 'sugar ata create \
+            --mint {} --owner <WALLET>' and use the associated token account of the receiving \
+            wallet.",
+            transfer.guard,
+            transfer.destination_ata,
+            label,
+            transfer.mint
+        ));
+    }
+
+    let theme = get_dialoguer_theme();
+    let prompt = format!(
+        "The {} destination {} does not exist, create it?",
+        transfer.guard, transfer.destination_ata
+    );
+
+    if !confirm_with_theme(&theme, &prompt)? {
+        return Err(anyhow!(
+            "The {} destination {} ({}) does not exist.",
+            transfer.guard,
+            transfer.destination_ata,
+            label
+        ));
+    }
+
+    let pb = spinner_with_style();
+    pb.set_message("Creating destination token account...");
+
+    let sig = program
+        .request()
+        .instruction(create_associated_token_account(
+            &payer,
+            &payer,
+            &transfer.mint,
+            token_program,
+        ))
+        .send_or_plan("create token account")?;
+
+    pb.finish_and_clear();
+    println!("{} {}", style("Signature:").bold(), sig);
+
+    Ok(())
+}
+
+#[test]
+fn test_token_amounts() {
+    assert_eq!(token_unit(0), Some(1));
+    assert_eq!(token_unit(9), Some(1_000_000_000));
+    assert_eq!(token_unit(19), Some(10_000_000_000_000_000_000));
+    assert_eq!(token_unit(20), None);
+    assert_eq!(token_unit(u8::MAX), None);
+
+    assert_eq!(whole_tokens(1_500_000_000, 9), 1.5);
+    assert_eq!(whole_tokens(5, 0), 5.0);
+    assert!(whole_tokens(u64::MAX, u8::MAX) < 1.0);
+}
@@ -0,0 +1,220 @@
+use std::{fs, str::FromStr};
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::Result;
+use console::style;
+use mpl_candy_guard::state::{CandyGuardData, DATA_OFFSET};
+
+use crate::{
+    cache::load_cache,
+    common::*,
+    config::{get_config_data, set_path, CandyGuardData as GuardConfig},
+    dry_run::is_dry_run,
+    utils::*,
+};
+
+pub struct GuardExportArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_guard: Option<String>,
+    pub output: String,
+}
+
+pub fn process_guard_export(args: GuardExportArgs) -> Result<()> {
+    println!("[1/1] {}Loading candy guard", LOOKING_GLASS_EMOJI);
+
+    // the candy guard id specified takes precedence over the one from the cache
+
+    let candy_guard_id = if let Some(candy_guard) = args.candy_guard {
+        candy_guard
+    } else {
+        let cache = load_cache(&args.cache, false)?;
+        cache.program.candy_guard
+    };
+
+    if candy_guard_id.is_empty() {
+        return Err(anyhow!("Missing candy guard id."));
+    }
+
+    let candy_guard_id = match Pubkey::from_str(&candy_guard_id) {
+        Ok(candy_guard_id) => candy_guard_id,
+        Err(_) => {
+            let error = anyhow!("Failed to parse candy guard id: {}", candy_guard_id);
+            error!("{:?}", error);
+            return Err(error);
+        }
+    };
+
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(mpl_candy_guard::ID);
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let account_data = program.rpc().get_account_data(&candy_guard_id)?;
+    let data = CandyGuardData::load(&account_data[DATA_OFFSET..])?;
+
+    pb.finish_and_clear();
+
+    let guards = GuardConfig::from_guard_format(&data);
+    let mut value = serde_json::to_value(&guards)?;
+    // only the guards enabled are written
+    remove_nulls(&mut value);
+
+    fs::write(&args.output, serde_json::to_string_pretty(&value)?)?;
+
+    println!("{} {}", style("Candy guard ID:").bold(), candy_guard_id);
+    println!("\nGuards exported to '{}'.", args.output);
+
+    Ok(())
+}
+
+/// Relative dates of the guards ("in 2h") resolved when the guards were loaded.
+pub struct ResolvedDates {
+    file: String,
+    /// Path of the guards in the file.
+    prefix: &'static str,
+    dates: Vec<(String, String)>,
+}
+
+impl ResolvedDates {
+    /// Writes the resolved dates to the file once the guards are on-chain, so the dates
+    /// are evaluated once and later commands see the ones that were written.
+    pub fn save(&self) -> Result<()> {
+        if self.dates.is_empty() || is_dry_run() {
+            return Ok(());
+        }
+
+        let mut text = fs::read_to_string(&self.file)?;
+
+        for (path, date) in &self.dates {
+            text = set_path(
+                &text,
+                &format!("{}{}", self.prefix, path),
+                &Value::String(date.clone()),
+            )?;
+        }
+
+        fs::write(&self.file, text)
+            .map_err(|e| anyhow!("Failed to write '{}': {}", self.file, e))?;
+
+        println!(
+            "\nRelative dates written to '{}' as the dates they resolved to.",
+            self.file
+        );
+
+        Ok(())
+    }
+}
+
+/// Returns the guards from a guard file (the `guards` section of a config file) or,
+/// when no file is specified, from the config file. Relative dates are resolved once,
+/// the returned `ResolvedDates` writes them back to the file.
+pub fn load_guards(
+    config: &str,
+    guards_file: Option<&str>,
+) -> Result<(CandyGuardData, ResolvedDates)> {
+    let (mut guards, file, prefix) = match guards_file {
+        Some(guards_file) => {
+            let content = fs::read_to_string(guards_file)
+                .map_err(|e| anyhow!("Failed to read guard file '{}': {}", guards_file, e))?;
+
+            let guards = serde_json::from_str::<GuardConfig>(&content)
+                .map_err(|e| anyhow!("Failed to parse guard file '{}': {}", guards_file, e))?;
+            (guards, guards_file, "")
+        }
+        None => {
+            let guards = get_config_data(config)?
+                .guards
+                .ok_or_else(|| anyhow!("Missing guards configuration."))?;
+            (guards, config, "guards.")
+        }
+    };
+
+    let dates = guards.resolve_relative_dates()?;
+
+    Ok((
+        guards.to_guard_format()?,
+        ResolvedDates {
+            file: file.to_string(),
+            prefix,
+            dates,
+        },
+    ))
+}
+
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => (),
+    }
+}
+
+#[test]
+fn test_guard_file_without_nulls() {
+    let guards: GuardConfig = serde_json::from_value(json!({
+        "default": {
+            "solPayment": {
+                "value": 1.5,
+                "destination": "11111111111111111111111111111111"
+            }
+        },
+        "groups": null
+    }))
+    .unwrap();
+
+    let mut value = serde_json::to_value(&guards).unwrap();
+    remove_nulls(&mut value);
+
+    assert_eq!(
+        value,
+        json!({
+            "default": {
+                "solPayment": {
+                    "value": 1.5,
+                    "destination": "11111111111111111111111111111111"
+                }
+            }
+        })
+    );
+}
+
+#[test]
+fn test_resolve_relative_dates() {
+    let mut guards: GuardConfig = serde_json::from_value(json!({
+        "default": {
+            "startDate": { "date": "in 2h" },
+            "endDate": { "date": "2024-07-01T18:00:00Z" }
+        },
+        "groups": [
+            { "label": "wl", "guards": { "endDate": { "date": "+1d" } } }
+        ]
+    }))
+    .unwrap();
+
+    let resolved = guards.resolve_relative_dates().unwrap();
+    let paths: Vec<&str> = resolved.iter().map(|(path, _)| path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec!["default.startDate.date", "groups[0].guards.endDate.date"]
+    );
+
+    // resolved dates are absolute, resolving again changes nothing
+    let start_date = guards.default.start_date.as_ref().unwrap().date.clone();
+    assert_eq!(start_date, resolved[0].1);
+    assert!(guards.resolve_relative_dates().unwrap().is_empty());
+
+    let text = set_path(
+        r#"{"guards": {"default": {"startDate": {"date": "in 2h"}}}}"#,
+        &format!("guards.{}", resolved[0].0),
+        &Value::String(start_date.clone()),
+    )
+    .unwrap();
+    assert!(text.contains(&start_date));
+}
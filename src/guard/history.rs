@@ -0,0 +1,123 @@
+use std::fs;
+
+use chrono::Utc;
+use mpl_candy_guard::state::{CandyGuardData, DATA_OFFSET};
+use serde::Serialize;
+
+use crate::{common::*, dry_run::is_dry_run, journal::STATE_DIR};
+
+/// Maximum number of snapshots kept for each candy guard.
+pub const GUARD_HISTORY_LIMIT: usize = 20;
+
+/// On-chain guard configuration saved before an update.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardSnapshot {
+    /// Time when the snapshot was taken (RFC 3339).
+    pub created: String,
+    /// Serialized guard data (base64), as expected by the update instruction.
+    pub data: String,
+}
+
+impl GuardSnapshot {
+    /// Takes a snapshot of the guard data of a candy guard account.
+    pub fn from_account_data(account_data: &[u8]) -> Result<Self> {
+        let data = CandyGuardData::load(&account_data[DATA_OFFSET..])?;
+        let mut serialized_data = vec![0; data.size()];
+        data.save(&mut serialized_data)?;
+
+        Ok(GuardSnapshot {
+            created: Utc::now().to_rfc3339(),
+            data: base64::encode(serialized_data),
+        })
+    }
+
+    pub fn guard_data(&self) -> Result<Vec<u8>> {
+        base64::decode(&self.data).map_err(|e| anyhow!("Invalid guard snapshot data: {}", e))
+    }
+}
+
+/// History file of a candy guard, stored in the state directory.
+pub fn guard_history_path(candy_guard: &Pubkey) -> PathBuf {
+    Path::new(STATE_DIR).join(format!("guard-history-{candy_guard}.json"))
+}
+
+/// Returns the snapshots of a candy guard, oldest first.
+pub fn load_guard_history(candy_guard: &Pubkey) -> Result<Vec<GuardSnapshot>> {
+    let path = guard_history_path(candy_guard);
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    serde_json::from_str(&content).map_err(|e| {
+        anyhow!(
+            "Failed to parse guard history file '{}': {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+/// Replaces the snapshots of a candy guard, keeping the most recent ones.
+pub fn save_guard_history(candy_guard: &Pubkey, history: &[GuardSnapshot]) -> Result<()> {
+    if is_dry_run() {
+        return Ok(());
+    }
+
+    let history = &history[history.len().saturating_sub(GUARD_HISTORY_LIMIT)..];
+
+    fs::create_dir_all(STATE_DIR)?;
+    fs::write(
+        guard_history_path(candy_guard),
+        serde_json::to_string_pretty(history)?,
+    )?;
+
+    Ok(())
+}
+
+/// Replaces the most recent snapshot of the history with the configuration it restores
+/// over, returning the snapshot to restore; a rollback can then itself be rolled back.
+pub fn swap_latest_snapshot(
+    history: &mut Vec<GuardSnapshot>,
+    current: GuardSnapshot,
+) -> Option<GuardSnapshot> {
+    let snapshot = history.pop()?;
+    history.push(current);
+    Some(snapshot)
+}
+
+/// Adds a snapshot to the history of a candy guard.
+pub fn push_guard_snapshot(candy_guard: &Pubkey, snapshot: GuardSnapshot) -> Result<()> {
+    let mut history = load_guard_history(candy_guard)?;
+    history.push(snapshot);
+    save_guard_history(candy_guard, &history)
+}
+
+#[test]
+fn test_guard_snapshot() {
+    let guards = crate::config::CandyGuardData::default()
+        .to_guard_format()
+        .unwrap();
+    let mut serialized_data = vec![0; guards.size()];
+    guards.save(&mut serialized_data).unwrap();
+
+    let mut account_data = vec![0; DATA_OFFSET];
+    account_data.extend_from_slice(&serialized_data);
+
+    let snapshot = GuardSnapshot::from_account_data(&account_data).unwrap();
+    assert_eq!(snapshot.guard_data().unwrap(), serialized_data);
+
+    let previous = GuardSnapshot {
+        created: "previous".to_string(),
+        data: String::new(),
+    };
+    let mut history = vec![previous];
+    let restored = swap_latest_snapshot(&mut history, snapshot).unwrap();
+
+    assert_eq!(restored.created, "previous");
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].guard_data().unwrap(), serialized_data);
+    assert!(swap_latest_snapshot(&mut Vec::new(), restored).is_none());
+}
@@ -0,0 +1,241 @@
+use std::str::FromStr;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::Result;
+use console::style;
+use mpl_candy_guard::{
+    guards::{AllocationTracker, MintCounter},
+    state::{CandyGuardData, GuardSet, DATA_OFFSET},
+};
+use solana_client::rpc_client::RpcClient;
+
+use crate::{candy_machine::*, common::*, guard::get_wrap_ids, utils::*};
+
+pub struct GuardLimitsArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub wallet: String,
+    pub candy_machine: Option<String>,
+    pub candy_guard: Option<String>,
+}
+
+/// Address of the mint counter of a wallet for the mint limit guard `id`.
+pub fn find_mint_counter_pda(
+    id: u8,
+    wallet: &Pubkey,
+    candy_guard_id: &Pubkey,
+    candy_machine_id: &Pubkey,
+) -> Pubkey {
+    let seeds = &[
+        MintCounter::PREFIX_SEED,
+        &[id],
+        wallet.as_ref(),
+        candy_guard_id.as_ref(),
+        candy_machine_id.as_ref(),
+    ];
+
+    Pubkey::find_program_address(seeds, &mpl_candy_guard::ID).0
+}
+
+/// Address of the allocation tracker for the allocation guard `id`.
+pub fn find_allocation_tracker_pda(
+    id: u8,
+    candy_guard_id: &Pubkey,
+    candy_machine_id: &Pubkey,
+) -> Pubkey {
+    let seeds = &[
+        AllocationTracker::PREFIX_SEED,
+        &[id],
+        candy_guard_id.as_ref(),
+        candy_machine_id.as_ref(),
+    ];
+
+    Pubkey::find_program_address(seeds, &mpl_candy_guard::ID).0
+}
+
+pub fn process_guard_limits(args: GuardLimitsArgs) -> Result<()> {
+    println!(
+        "{} {}Loading candy machine and candy guard",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let wallet = Pubkey::from_str(&args.wallet)
+        .map_err(|_| anyhow!("Failed to parse wallet address: {}", args.wallet))?;
+    let (candy_machine_id, candy_guard_id) =
+        get_wrap_ids(&args.cache, args.candy_machine, args.candy_guard)?;
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(mpl_candy_guard::ID);
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let candy_machine = get_candy_machine_state(&sugar_config, &candy_machine_id)?;
+    let account_data = program.rpc().get_account_data(&candy_guard_id)?;
+    let data = CandyGuardData::load(&account_data[DATA_OFFSET..])?;
+
+    pb.finish_and_clear();
+
+    println!("{} {}", style("Candy machine ID:").bold(), candy_machine_id);
+    println!("{} {}", style("Candy guard ID:").bold(), candy_guard_id);
+    println!("{} {}", style("Wallet:").bold(), wallet);
+
+    println!(
+        "\n{} {}Fetching mint counters",
+        style("[2/2]").bold().dim(),
+        COMPUTER_EMOJI
+    );
+
+    let items_remaining = candy_machine
+        .data
+        .items_available
+        .saturating_sub(candy_machine.items_redeemed);
+
+    // when there are groups, mints use one of the groups and the guards of a group
+    // take precedence over the default guards
+    let guard_sets = match &data.groups {
+        Some(groups) if !groups.is_empty() => groups
+            .iter()
+            .map(|group| {
+                (
+                    group.label.clone(),
+                    merge_guard_sets(&group.guards, &data.default),
+                )
+            })
+            .collect(),
+        _ => vec![("default".to_string(), data.default.clone())],
+    };
+
+    let rpc_client = program.rpc();
+
+    for (label, guard_set) in guard_sets {
+        println!("\n{}", style(format!("Guard set: {label}")).bold());
+
+        let mut remaining = items_remaining;
+
+        if let Some(mint_limit) = &guard_set.mint_limit {
+            let pda =
+                find_mint_counter_pda(mint_limit.id, &wallet, &candy_guard_id, &candy_machine_id);
+            // the counter is created on the first mint
+            let count = match get_optional_account_data(&rpc_client, &pda)? {
+                Some(data) => MintCounter::try_deserialize(&mut data.as_slice())?.count,
+                None => 0,
+            };
+            let left = mint_limit.limit.saturating_sub(count);
+
+            println!(
+                "  mint limit (id {}): {}/{} minted by the wallet, {} left",
+                mint_limit.id, count, mint_limit.limit, left
+            );
+            remaining = remaining.min(left as u64);
+        }
+
+        if let Some(allocation) = &guard_set.allocation {
+            let pda =
+                find_allocation_tracker_pda(allocation.id, &candy_guard_id, &candy_machine_id);
+            let count = match get_optional_account_data(&rpc_client, &pda)? {
+                Some(data) => AllocationTracker::try_deserialize(&mut data.as_slice())?.count,
+                None => 0,
+            };
+            let left = allocation.limit.saturating_sub(count);
+
+            println!(
+                "  allocation (id {}): {}/{} minted in the group, {} left",
+                allocation.id, count, allocation.limit, left
+            );
+            remaining = remaining.min(left as u64);
+        }
+
+        if let Some(redeemed_amount) = &guard_set.redeemed_amount {
+            let left = redeemed_amount
+                .maximum
+                .saturating_sub(candy_machine.items_redeemed);
+
+            println!(
+                "  redeemed amount: {}/{} redeemed, {} left",
+                candy_machine.items_redeemed, redeemed_amount.maximum, left
+            );
+            remaining = remaining.min(left);
+        }
+
+        println!(
+            "  {} {}",
+            style("mints left for the wallet:").bold(),
+            style(remaining).green().bold()
+        );
+    }
+
+    Ok(())
+}
+
+/// Data of an account, `None` when it does not exist; RPC errors are returned rather than
+/// read as an account not created yet.
+fn get_optional_account_data(rpc_client: &RpcClient, pubkey: &Pubkey) -> Result<Option<Vec<u8>>> {
+    Ok(rpc_client
+        .get_account_with_commitment(pubkey, rpc_client.commitment())?
+        .value
+        .map(|account| account.data))
+}
+
+/// Returns the limit guards of a group, falling back to the default guards.
+fn merge_guard_sets(group: &GuardSet, default: &GuardSet) -> GuardSet {
+    let mut guard_set = group.clone();
+
+    if guard_set.mint_limit.is_none() {
+        guard_set.mint_limit = default.mint_limit.clone();
+    }
+
+    if guard_set.allocation.is_none() {
+        guard_set.allocation = default.allocation.clone();
+    }
+
+    if guard_set.redeemed_amount.is_none() {
+        guard_set.redeemed_amount = default.redeemed_amount.clone();
+    }
+
+    guard_set
+}
+
+#[test]
+fn test_limit_pdas() {
+    let wallet = Pubkey::new_unique();
+    let candy_guard_id = Pubkey::new_unique();
+    let candy_machine_id = Pubkey::new_unique();
+
+    // same seeds as the remaining accounts of the mint instruction
+    let (expected, _) = Pubkey::find_program_address(
+        &[
+            b"mint_limit",
+            &[3],
+            wallet.as_ref(),
+            candy_guard_id.as_ref(),
+            candy_machine_id.as_ref(),
+        ],
+        &mpl_candy_guard::ID,
+    );
+    assert_eq!(
+        find_mint_counter_pda(3, &wallet, &candy_guard_id, &candy_machine_id),
+        expected
+    );
+    assert_ne!(
+        find_mint_counter_pda(4, &wallet, &candy_guard_id, &candy_machine_id),
+        expected
+    );
+
+    let (expected, _) = Pubkey::find_program_address(
+        &[
+            b"allocation",
+            &[1],
+            candy_guard_id.as_ref(),
+            candy_machine_id.as_ref(),
+        ],
+        &mpl_candy_guard::ID,
+    );
+    assert_eq!(
+        find_allocation_tracker_pda(1, &candy_guard_id, &candy_machine_id),
+        expected
+    );
+}
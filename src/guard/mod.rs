@@ -1,11 +1,25 @@
 pub mod add;
+pub mod allocation;
+pub mod checks;
+pub mod export;
+pub mod history;
+pub mod limits;
 pub mod remove;
+pub mod rollback;
 pub mod show;
 pub mod update;
 pub mod withdraw;
+pub mod wrap;
 
 pub use add::*;
+pub use allocation::*;
+pub use checks::*;
+pub use export::*;
+pub use history::*;
+pub use limits::*;
 pub use remove::*;
+pub use rollback::*;
 pub use show::*;
 pub use update::*;
 pub use withdraw::*;
+pub use wrap::*;
@@ -5,7 +5,7 @@ use anyhow::Result;
 use console::style;
 use mpl_candy_guard::{accounts::Unwrap as UnwrapAccount, instruction::Unwrap};
 
-use crate::{cache::load_cache, candy_machine::*, common::*, utils::*};
+use crate::{cache::load_cache, candy_machine::*, common::*, dry_run::SendOrPlan, utils::*};
 
 pub struct GuardRemoveArgs {
     pub keypair: Option<String>,
@@ -79,7 +79,7 @@ pub fn process_guard_remove(args: GuardRemoveArgs) -> Result<()> {
         })
         .args(Unwrap {});
 
-    let sig = tx.send()?;
+    let sig = tx.send_or_plan("unwrap")?;
 
     pb.finish_and_clear();
     println!("{} {}", style("Signature:").bold(), sig);
@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::Result;
+use console::style;
+use mpl_candy_guard::state::CandyGuardData;
+
+use crate::{
+    cache::load_cache,
+    common::*,
+    guard::{
+        load_guard_history, save_guard_history, send_guard_update, swap_latest_snapshot,
+        GuardSnapshot,
+    },
+    utils::*,
+};
+
+pub struct GuardRollbackArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_guard: Option<String>,
+}
+
+pub fn process_guard_rollback(args: GuardRollbackArgs) -> Result<()> {
+    println!(
+        "{} {}Loading candy guard",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    // the candy guard id specified takes precedence over the one from the cache
+
+    let candy_guard_id = if let Some(candy_guard) = args.candy_guard {
+        candy_guard
+    } else {
+        let cache = load_cache(&args.cache, false)?;
+        cache.program.candy_guard
+    };
+
+    if candy_guard_id.is_empty() {
+        return Err(anyhow!("Missing candy guard id."));
+    }
+
+    let candy_guard_id = match Pubkey::from_str(&candy_guard_id) {
+        Ok(candy_guard_id) => candy_guard_id,
+        Err(_) => {
+            let error = anyhow!("Failed to parse candy guard id: {}", candy_guard_id);
+            error!("{:?}", error);
+            return Err(error);
+        }
+    };
+
+    let mut history = load_guard_history(&candy_guard_id)?;
+
+    if history.is_empty() {
+        return Err(anyhow!(
+            "No previous configuration found for candy guard {}.",
+            candy_guard_id
+        ));
+    }
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(mpl_candy_guard::ID);
+    let payer = sugar_config.keypair;
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+    // make sure the account exists on-chain
+    let account = program.rpc().get_account(&candy_guard_id)?;
+    pb.finish_with_message("Done");
+
+    // the configuration replaced by the rollback takes the place of the one restored
+    let current = GuardSnapshot::from_account_data(&account.data)?;
+    let snapshot = swap_latest_snapshot(&mut history, current)
+        .ok_or_else(|| anyhow!("No previous configuration found."))?;
+    let data = snapshot.guard_data()?;
+    // make sure the snapshot is a valid guard configuration
+    CandyGuardData::load(&data)?;
+
+    println!("{} {}", style("Candy guard ID:").bold(), candy_guard_id);
+    println!(
+        "{} {}",
+        style("Configuration from:").bold(),
+        snapshot.created
+    );
+
+    let theme = get_dialoguer_theme();
+    if !confirm_with_theme(
+        &theme,
+        "Restore this configuration? Use 'sugar guard show' to review the current one",
+    )? {
+        return Err(anyhow!("Rollback cancelled."));
+    }
+
+    println!(
+        "\n{} {}Restoring configuration",
+        style("[2/2]").bold().dim(),
+        COMPUTER_EMOJI
+    );
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let sig = send_guard_update(&program, &candy_guard_id, &payer, data)?;
+
+    pb.finish_and_clear();
+    println!("{} {}", style("Signature:").bold(), sig);
+
+    save_guard_history(&candy_guard_id, &history)?;
+    println!(
+        "\nReplaced configuration saved, use {} again to restore it.",
+        style("sugar guard rollback").bold()
+    );
+
+    Ok(())
+}
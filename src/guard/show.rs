@@ -2,13 +2,14 @@ use std::str::FromStr;
 
 use anchor_client::solana_sdk::pubkey::Pubkey;
 use anyhow::Result;
-use chrono::NaiveDateTime;
 use console::style;
 use mpl_candy_guard::state::{CandyGuard, CandyGuardData, GuardSet, DATA_OFFSET};
 use mpl_candy_machine_core::constants::EMPTY_STR;
 use solana_program::native_token::LAMPORTS_PER_SOL;
 
-use crate::{cache::load_cache, common::*, show::print_with_style, utils::*};
+use crate::{
+    cache::load_cache, common::*, config::format_timestamp, show::print_with_style, utils::*,
+};
 
 pub struct GuardShowArgs {
     pub keypair: Option<String>,
@@ -42,7 +43,7 @@ pub fn process_guard_show(args: GuardShowArgs) -> Result<()> {
         }
     };
 
-    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
     let client = setup_client(&sugar_config)?;
     let program = client.program(mpl_candy_guard::ID);
 
@@ -102,7 +103,7 @@ pub fn process_guard_show(args: GuardShowArgs) -> Result<()> {
     Ok(())
 }
 
-fn print_guard_set(guard_set: &GuardSet, padding: String) -> Result<()> {
+pub fn print_guard_set(guard_set: &GuardSet, padding: String) -> Result<()> {
     // bot tax
     if let Some(bot_tax) = &guard_set.bot_tax {
         print_with_style(&padding, "bot tax", EMPTY_STR.to_string());
@@ -170,17 +171,11 @@ fn print_guard_set(guard_set: &GuardSet, padding: String) -> Result<()> {
     // start date
     if let Some(start_date) = &guard_set.start_date {
         print_with_style(&padding, "start date", EMPTY_STR.to_string());
-        if let Some(date) = NaiveDateTime::from_timestamp_opt(start_date.date, 0) {
-            print_with_style(
-                &format!("{}:   ", padding),
-                "date",
-                date.format("%a %B %e %Y %H:%M:%S UTC").to_string(),
-            );
-        } else {
-            // this should not happen, but adding a message so it can be
-            // flag to the user
-            print_with_style(&format!("{}:   ", padding), "date", "<parse error>");
-        }
+        print_with_style(
+            &format!("{}:   ", padding),
+            "date",
+            format_timestamp(start_date.date),
+        );
     } else {
         print_with_style(&padding, "start date", "none".to_string());
     }
@@ -234,17 +229,11 @@ fn print_guard_set(guard_set: &GuardSet, padding: String) -> Result<()> {
     // end date
     if let Some(end_date) = &guard_set.end_date {
         print_with_style(&padding, "end date", EMPTY_STR.to_string());
-        if let Some(date) = NaiveDateTime::from_timestamp_opt(end_date.date, 0) {
-            print_with_style(
-                &format!("{}:   ", padding),
-                "date",
-                date.format("%a %B %e %Y %H:%M:%S UTC").to_string(),
-            );
-        } else {
-            // this should not happen, but adding a message so it can be
-            // flag to the user
-            print_with_style(&format!("{}:   ", padding), "date", "<parse error>");
-        }
+        print_with_style(
+            &format!("{}:   ", padding),
+            "date",
+            format_timestamp(end_date.date),
+        );
     } else {
         print_with_style(&padding, "end date", "none".to_string());
     }
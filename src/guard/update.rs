@@ -1,11 +1,18 @@
-use std::str::FromStr;
+use std::{rc::Rc, str::FromStr};
 
 use anchor_client::solana_sdk::{compute_budget::ComputeBudgetInstruction, pubkey::Pubkey};
 use anyhow::Result;
 use console::style;
 use mpl_candy_guard::{accounts::Update as UpdateAccount, instruction::Update};
 
-use crate::{cache::load_cache, common::*, config::get_config_data, utils::*};
+use crate::{
+    cache::load_cache,
+    common::*,
+    config::print_guard_dates,
+    dry_run::SendOrPlan,
+    guard::{check_guard_accounts, load_guards, push_guard_snapshot, GuardSnapshot},
+    utils::*,
+};
 
 pub struct GuardUpdateArgs {
     pub keypair: Option<String>,
@@ -13,6 +20,7 @@ pub struct GuardUpdateArgs {
     pub cache: String,
     pub config: String,
     pub candy_guard: Option<String>,
+    pub from_file: Option<String>,
 }
 
 pub fn process_guard_update(args: GuardUpdateArgs) -> Result<()> {
@@ -52,7 +60,7 @@ pub fn process_guard_update(args: GuardUpdateArgs) -> Result<()> {
     let pb = spinner_with_style();
     pb.set_message("Connecting...");
     // make sure the account exists on-chain
-    let _account = program.rpc().get_account(&candy_guard_id)?;
+    let account = program.rpc().get_account(&candy_guard_id)?;
     pb.finish_with_message("Done");
 
     println!("{} {}", style("Candy guard ID:").bold(), candy_guard_id);
@@ -63,19 +71,42 @@ pub fn process_guard_update(args: GuardUpdateArgs) -> Result<()> {
         COMPUTER_EMOJI
     );
 
-    let config_data = get_config_data(&args.config)?;
-    let data = if let Some(guards) = &config_data.guards {
-        guards.to_guard_format()?
-    } else {
-        return Err(anyhow!("Missing guards configuration."));
-    };
+    let (data, resolved_dates) = load_guards(&args.config, args.from_file.as_deref())?;
+    print_guard_dates(&data);
+    check_guard_accounts(&program, &data)?;
 
     let mut serialized_data = vec![0; data.size()];
     data.save(&mut serialized_data)?;
 
+    // the current configuration is saved so the update can be rolled back
+    let snapshot = GuardSnapshot::from_account_data(&account.data)?;
+
     let pb = spinner_with_style();
     pb.set_message("Connecting...");
 
+    let sig = send_guard_update(&program, &candy_guard_id, &payer, serialized_data)?;
+
+    pb.finish_and_clear();
+    println!("{} {}", style("Signature:").bold(), sig);
+
+    // only recorded once the update succeeded, a failed update leaves nothing to restore
+    push_guard_snapshot(&candy_guard_id, snapshot)?;
+    resolved_dates.save()?;
+    println!(
+        "\nPrevious configuration saved, use {} to restore it.",
+        style("sugar guard rollback").bold()
+    );
+
+    Ok(())
+}
+
+/// Sends the update instruction replacing the guard data of a candy guard.
+pub fn send_guard_update(
+    program: &Program<Rc<Keypair>>,
+    candy_guard_id: &Pubkey,
+    payer: &Keypair,
+    data: Vec<u8>,
+) -> Result<Signature> {
     let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
     let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
 
@@ -84,19 +115,12 @@ pub fn process_guard_update(args: GuardUpdateArgs) -> Result<()> {
         .instruction(compute_units)
         .instruction(priority_fee)
         .accounts(UpdateAccount {
-            candy_guard: candy_guard_id,
+            candy_guard: *candy_guard_id,
             authority: payer.pubkey(),
             payer: payer.pubkey(),
             system_program: system_program::ID,
         })
-        .args(Update {
-            data: serialized_data,
-        });
-
-    let sig = tx.send()?;
+        .args(Update { data });
 
-    pb.finish_and_clear();
-    println!("{} {}", style("Signature:").bold(), sig);
-
-    Ok(())
+    tx.send_or_plan("update candy guard")
 }
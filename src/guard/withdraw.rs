@@ -6,7 +6,7 @@ use console::style;
 use mpl_candy_guard::{accounts::Withdraw as WithdrawAccount, instruction::Withdraw};
 use solana_program::native_token::LAMPORTS_PER_SOL;
 
-use crate::{cache::load_cache, common::*, utils::*};
+use crate::{cache::load_cache, common::*, dry_run::SendOrPlan, utils::*};
 
 pub struct GuardWithdrawArgs {
     pub keypair: Option<String>,
@@ -70,7 +70,7 @@ pub fn process_guard_withdraw(args: GuardWithdrawArgs) -> Result<()> {
         })
         .args(Withdraw {});
 
-    let sig = tx.send()?;
+    let sig = tx.send_or_plan("withdraw candy guard")?;
 
     pb.finish_and_clear();
     println!("{} {}", style("Signature:").bold(), sig);
@@ -0,0 +1,233 @@
+use std::str::FromStr;
+
+use anchor_client::solana_sdk::{compute_budget::ComputeBudgetInstruction, pubkey::Pubkey};
+use anyhow::Result;
+use console::style;
+use mpl_candy_guard::{
+    accounts::{Unwrap as UnwrapAccount, Wrap as WrapAccount},
+    instruction::{Unwrap, Wrap},
+    state::CandyGuard,
+};
+
+use crate::{cache::load_cache, candy_machine::*, common::*, dry_run::SendOrPlan, utils::*};
+
+pub struct GuardWrapArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_machine: Option<String>,
+    pub candy_guard: Option<String>,
+}
+
+pub fn process_guard_wrap(args: GuardWrapArgs) -> Result<()> {
+    println!(
+        "{} {}Loading candy machine and candy guard",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let (candy_machine_id, candy_guard_id) =
+        get_wrap_ids(&args.cache, args.candy_machine, args.candy_guard)?;
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(mpl_candy_guard::ID);
+    let payer = sugar_config.keypair.pubkey();
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let candy_machine = get_candy_machine_state(&sugar_config, &candy_machine_id)?;
+    let candy_guard: CandyGuard = program.account(candy_guard_id)?;
+
+    pb.finish_and_clear();
+
+    println!("{} {}", style("Candy machine ID:").bold(), candy_machine_id);
+    println!("{} {}", style("Candy guard ID:").bold(), candy_guard_id);
+    println!(
+        "{} {}",
+        style("Current mint authority:").bold(),
+        candy_machine.mint_authority
+    );
+
+    if candy_machine.mint_authority == candy_guard_id {
+        println!("\nThe candy guard is already the mint authority of the candy machine.");
+        return Ok(());
+    }
+
+    assert_correct_authority(&payer, &candy_machine.authority)?;
+
+    if candy_guard.authority != payer {
+        return Err(anyhow!(
+            "The keypair {} is not the authority of the candy guard ({})",
+            payer,
+            candy_guard.authority
+        ));
+    }
+
+    if candy_machine.mint_authority != candy_machine.authority {
+        println!(
+            "\n{}The candy machine is wrapped by a different account ({}), it will be replaced.",
+            WARNING_EMOJI, candy_machine.mint_authority
+        );
+    }
+
+    println!("\n{} {}Wrapping", style("[2/2]").bold().dim(), WRAP_EMOJI);
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
+    let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
+
+    let sig = program
+        .request()
+        .instruction(compute_units)
+        .instruction(priority_fee)
+        .accounts(WrapAccount {
+            candy_guard: candy_guard_id,
+            authority: payer,
+            candy_machine: candy_machine_id,
+            candy_machine_program: CANDY_MACHINE_ID,
+            candy_machine_authority: payer,
+        })
+        .args(Wrap {})
+        .send_or_plan("wrap")?;
+
+    pb.finish_and_clear();
+    println!("{} {}", style("Signature:").bold(), sig);
+
+    println!("\nThe candy guard is now the mint authority of the candy machine.");
+
+    Ok(())
+}
+
+pub struct GuardUnwrapArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_machine: Option<String>,
+    pub candy_guard: Option<String>,
+}
+
+pub fn process_guard_unwrap(args: GuardUnwrapArgs) -> Result<()> {
+    println!(
+        "{} {}Loading candy machine and candy guard",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let (candy_machine_id, candy_guard_id) =
+        get_wrap_ids(&args.cache, args.candy_machine, args.candy_guard)?;
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(mpl_candy_guard::ID);
+    let payer = sugar_config.keypair.pubkey();
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let candy_machine = get_candy_machine_state(&sugar_config, &candy_machine_id)?;
+    let candy_guard: CandyGuard = program.account(candy_guard_id)?;
+
+    pb.finish_and_clear();
+
+    println!("{} {}", style("Candy machine ID:").bold(), candy_machine_id);
+    println!("{} {}", style("Candy guard ID:").bold(), candy_guard_id);
+    println!(
+        "{} {}",
+        style("Current mint authority:").bold(),
+        candy_machine.mint_authority
+    );
+
+    if candy_machine.mint_authority != candy_guard_id {
+        if candy_machine.mint_authority == candy_machine.authority {
+            println!("\nThe candy machine is not wrapped, nothing to unwrap.");
+            return Ok(());
+        }
+
+        return Err(anyhow!(
+            "The candy machine is wrapped by {}, not by the candy guard {}",
+            candy_machine.mint_authority,
+            candy_guard_id
+        ));
+    }
+
+    assert_correct_authority(&payer, &candy_machine.authority)?;
+
+    if candy_guard.authority != payer {
+        return Err(anyhow!(
+            "The keypair {} is not the authority of the candy guard ({})",
+            payer,
+            candy_guard.authority
+        ));
+    }
+
+    println!(
+        "\n{} {}Unwrapping",
+        style("[2/2]").bold().dim(),
+        UNWRAP_EMOJI
+    );
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
+    let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
+
+    let sig = program
+        .request()
+        .instruction(compute_units)
+        .instruction(priority_fee)
+        .accounts(UnwrapAccount {
+            candy_guard: candy_guard_id,
+            authority: payer,
+            candy_machine: candy_machine_id,
+            candy_machine_authority: payer,
+            candy_machine_program: CANDY_MACHINE_ID,
+        })
+        .args(Unwrap {})
+        .send_or_plan("unwrap")?;
+
+    pb.finish_and_clear();
+    println!("{} {}", style("Signature:").bold(), sig);
+
+    println!("\nThe candy guard is no longer the mint authority of the candy machine.");
+    println!(
+        "  -> New mint authority: {}",
+        style(format!("{}", payer)).bold()
+    );
+
+    Ok(())
+}
+
+/// Return the candy machine and candy guard ids, where the ids specified take
+/// precedence over the ones from the cache.
+pub fn get_wrap_ids(
+    cache: &str,
+    candy_machine: Option<String>,
+    candy_guard: Option<String>,
+) -> Result<(Pubkey, Pubkey)> {
+    let candy_machine_id = match candy_machine {
+        Some(candy_machine) => candy_machine,
+        None => load_cache(cache, false)?.program.candy_machine,
+    };
+
+    let candy_machine_id = Pubkey::from_str(&candy_machine_id)
+        .map_err(|_| anyhow!("Failed to parse candy machine id: {}", candy_machine_id))?;
+
+    let candy_guard_id = match candy_guard {
+        Some(candy_guard) => candy_guard,
+        None => load_cache(cache, false)?.program.candy_guard,
+    };
+
+    if candy_guard_id.is_empty() {
+        return Err(anyhow!("Missing candy guard id."));
+    }
+
+    let candy_guard_id = Pubkey::from_str(&candy_guard_id)
+        .map_err(|_| anyhow!("Failed to parse candy guard id: {}", candy_guard_id))?;
+
+    Ok((candy_machine_id, candy_guard_id))
+}
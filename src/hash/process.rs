@@ -1,20 +1,26 @@
-use std::{
-    fs::OpenOptions,
-    io::{BufReader, Read},
-};
+use std::fs::OpenOptions;
 
 use console::style;
 use sha2::{Digest, Sha256};
 
 use crate::{
+    cache::load_cache,
+    candy_machine::get_candy_machine_state,
     common::*,
-    config::{get_config_data, ConfigData, HiddenSettings},
+    config::{active_profile, get_config_data, ConfigData, HiddenSettings, PROFILES_KEY},
+    lock::lock_file,
+    update::{process_update, UpdateArgs, UpdateFields},
+    utils::{confirm_with_theme, get_dialoguer_theme, spinner_with_style},
 };
 
 pub struct HashArgs {
     pub config: String,
     pub cache: String,
     pub compare: Option<String>,
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub candy_machine: Option<String>,
+    pub update: bool,
 }
 
 pub fn process_hash(args: HashArgs) -> Result<()> {
@@ -24,17 +30,7 @@ pub fn process_hash(args: HashArgs) -> Result<()> {
     // "Command successful".
 
     if let Some(hash) = args.compare {
-        let mut hasher = Sha256::new();
-
-        let cache_file = File::open(args.cache)?;
-        let mut reader = BufReader::new(cache_file);
-        let mut buffer = Vec::new();
-        // Read file into vector.
-        reader.read_to_end(&mut buffer)?;
-
-        hasher.update(&buffer);
-        let hash_base58 = bs58::encode(&hasher.finalize()).into_string();
-        let expected_hash = hash_base58.chars().take(32).collect::<String>();
+        let expected_hash = cache_hash(&args.cache)?;
         if hash != expected_hash {
             println!(
                 "{} {}",
@@ -52,47 +48,142 @@ pub fn process_hash(args: HashArgs) -> Result<()> {
     }
 
     if let Some(ref hidden_settings) = config_data.hidden_settings {
-        println!(
-            "hash: {}",
-            hash_and_update(
-                hidden_settings.clone(),
-                &args.config,
-                &mut config_data,
-                &args.cache,
-            )?
-        );
+        let hash = hash_and_update(
+            hidden_settings.clone(),
+            &args.config,
+            &mut config_data,
+            &args.cache,
+        )?;
+
+        println!("hash: {}", hash);
         println!(
             "{} {}",
             COMPLETE_EMOJI,
             style("Config file updated with hash!").blue().bold()
         );
-        std::process::exit(0);
+
+        if !args.update {
+            std::process::exit(0);
+        }
+
+        update_on_chain_hash(&args, &hash)
     } else {
         Err(anyhow!("No hidden settings found in config file."))
     }
 }
 
+/// Compares the hash with the one in the on-chain hidden settings and sends an
+/// update transaction (after confirmation) if they differ.
+fn update_on_chain_hash(args: &HashArgs, hash: &str) -> Result<()> {
+    let sugar_config = sugar_setup(args.keypair.clone(), args.rpc_url.clone())?;
+
+    // the candy machine id specified takes precedence over the one from the cache
+    let candy_machine_id = match args.candy_machine {
+        Some(ref candy_machine_id) => candy_machine_id.clone(),
+        None => {
+            let cache = load_cache(&args.cache, false)?;
+            cache.program.candy_machine
+        }
+    };
+
+    let candy_pubkey = Pubkey::from_str(&candy_machine_id)
+        .map_err(|_| CacheError::InvalidCandyMachineAddress(candy_machine_id.clone()))?;
+
+    let pb = spinner_with_style();
+    pb.set_message("Loading candy machine...");
+
+    let candy_machine_state = get_candy_machine_state(&sugar_config, &candy_pubkey)?;
+
+    pb.finish_and_clear();
+
+    let on_chain_hash = match candy_machine_state.data.hidden_settings {
+        Some(hidden_settings) => String::from_utf8_lossy(&hidden_settings.hash).to_string(),
+        None => {
+            return Err(anyhow!(
+                "Candy machine {} does not have hidden settings.",
+                candy_machine_id
+            ))
+        }
+    };
+
+    println!("\n{} {}", style("On-chain hash:").bold(), on_chain_hash);
+
+    if on_chain_hash == hash {
+        println!(
+            "{} {}",
+            COMPLETE_EMOJI,
+            style("On-chain hash is up to date!").blue().bold()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} {}",
+        WARNING_EMOJI,
+        style("On-chain hash does not match the cache file.")
+            .yellow()
+            .bold()
+    );
+
+    if !confirm_with_theme(
+        &get_dialoguer_theme(),
+        "Do you want to update the candy machine with the new hash?",
+    )? {
+        return Err(anyhow!("Update aborted"));
+    }
+
+    println!();
+
+    process_update(UpdateArgs {
+        keypair: args.keypair.clone(),
+        rpc_url: args.rpc_url.clone(),
+        cache: args.cache.clone(),
+        new_authority: None,
+        config: args.config.clone(),
+        candy_machine: Some(candy_machine_id),
+        fields: UpdateFields::default(),
+    })
+}
+
+/// Hashes the content of the cache file. Encrypted caches are decrypted first, so that
+/// the hash does not depend on the encryption (which changes on every write) and matches
+/// the one of the published plaintext cache.
+pub fn cache_hash(cache_file_path: &str) -> Result<String> {
+    let cache = load_cache(cache_file_path, false)?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec_pretty(&cache)?);
+    let hash_base58 = bs58::encode(&hasher.finalize()).into_string();
+
+    // Candy machine only allows for 32 characters so we truncate this hash.
+    Ok(hash_base58.chars().take(32).collect::<String>())
+}
+
 pub fn hash_and_update(
     mut hidden_settings: HiddenSettings,
     config_file: &str,
     config_data: &mut ConfigData,
     cache_file_path: &str,
 ) -> Result<String> {
-    let mut hasher = Sha256::new();
+    lock_file(config_file)?;
+    let hash = cache_hash(cache_file_path)?;
+    hidden_settings.set_hash(hash.clone());
+    config_data.hidden_settings = Some(hidden_settings);
 
-    let cache_file = File::open(cache_file_path)?;
-    let mut reader = BufReader::new(cache_file);
-    let mut buffer = Vec::new();
-    // Read file into vector.
-    reader.read_to_end(&mut buffer)?;
+    // updates the raw config file so that values from profiles are not
+    // written to the top level of the config
+    let mut config: Value = serde_json::from_reader(File::open(config_file)?)?;
 
-    hasher.update(&buffer);
-    let hash_base58 = bs58::encode(&hasher.finalize()).into_string();
+    let profile = active_profile().filter(|p| p.overrides.contains_key("hiddenSettings"));
+    let target = match profile {
+        Some(ref profile) => &mut config[PROFILES_KEY][&profile.name]["hiddenSettings"],
+        None => &mut config["hiddenSettings"],
+    };
 
-    let hash = hash_base58.chars().take(32).collect::<String>();
-    // Candy machine only allows for 32 characters so we truncate this hash.
-    hidden_settings.set_hash(hash.clone());
-    config_data.hidden_settings = Some(hidden_settings);
+    if !target.is_object() {
+        *target = serde_json::to_value(&config_data.hidden_settings)?;
+    }
+    target["hash"] = Value::String(hash.clone());
 
     let file = OpenOptions::new()
         .write(true)
@@ -100,7 +191,34 @@ pub fn hash_and_update(
         .truncate(true)
         .open(Path::new(&config_file))?;
 
-    serde_json::to_writer_pretty(file, &config_data)?;
+    serde_json::to_writer_pretty(file, &config)?;
 
     Ok(hash)
 }
+
+#[test]
+fn test_cache_hash_encrypted() {
+    use crate::cache_encryption::{CacheKey, CACHE_KEYFILE_ENV};
+
+    let dir = std::env::temp_dir().join(format!("sugar-hash-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let keyfile = dir.join("cache.key");
+    std::fs::write(&keyfile, "sugar").unwrap();
+    std::env::set_var(CACHE_KEYFILE_ENV, &keyfile);
+
+    let mut cache = Cache::new();
+    cache.program.candy_machine = Pubkey::new_unique().to_string();
+    let plaintext = dir.join("cache.json");
+    cache.write_to_file(&plaintext).unwrap();
+    let hash = cache_hash(plaintext.to_str().unwrap()).unwrap();
+
+    // every write of an encrypted cache uses a new nonce
+    cache.encryption = Some(CacheKey::new(keyfile.to_str()).unwrap());
+    let encrypted = dir.join("cache.enc.json");
+    cache.write_to_file(&encrypted).unwrap();
+    assert_eq!(cache_hash(encrypted.to_str().unwrap()).unwrap(), hash);
+    cache.write_to_file(&encrypted).unwrap();
+    assert_eq!(cache_hash(encrypted.to_str().unwrap()).unwrap(), hash);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
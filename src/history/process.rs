@@ -0,0 +1,384 @@
+use std::{collections::BTreeMap, fs, io::Write, thread, time::Duration};
+
+use anchor_client::solana_sdk::transaction::VersionedTransaction;
+use chrono::{TimeZone, Utc};
+use console::style;
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::RpcTransactionConfig,
+};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use tabled::{builder::Builder, settings::Style};
+
+use crate::{
+    cache::load_cache,
+    candy_machine::{get_candy_machine_state, CANDY_MACHINE_ID},
+    common::*,
+    core_candy_machine::{
+        get_candy_machine_program, get_core_candy_machine_state, CORE_CANDY_GUARD_ID,
+        CORE_CANDY_MACHINE_ID,
+    },
+    rate_limit::throttled_client_with_commitment,
+    utils::*,
+};
+
+/// Maximum number of signatures returned by the RPC per request.
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+/// Number of attempts to fetch a transaction.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before fetching a transaction again, increased with each attempt.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Log message prefix of anchor instructions.
+const INSTRUCTION_LOG: &str = "Program log: Instruction: ";
+
+/// Header of the history CSV file.
+const HISTORY_HEADER: &str = "date,slot,signature,instructions,signer,status";
+
+pub struct HistoryArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_machine: Option<String>,
+    pub candy_guard: Option<String>,
+    pub output: String,
+}
+
+/// Transaction involving the candy machine or its candy guard.
+struct HistoryEntry {
+    signature: String,
+    slot: u64,
+    block_time: Option<i64>,
+    instructions: Vec<String>,
+    signer: Option<Pubkey>,
+    failed: bool,
+}
+
+pub fn process_history(args: HistoryArgs) -> Result<()> {
+    println!(
+        "{} {}Loading candy machine",
+        style("[1/3]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let candy_machine_id = match args.candy_machine {
+        Some(candy_machine) => candy_machine,
+        None => load_cache(&args.cache, false)?.program.candy_machine,
+    };
+
+    let candy_machine_id = Pubkey::from_str(&candy_machine_id)
+        .map_err(|_| anyhow!("Failed to parse candy machine id: {}", candy_machine_id))?;
+
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
+    let rpc_client = throttled_client_with_commitment(&sugar_config.rpc_url);
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    // the candy machine account is only needed to find the candy guard, so the history of
+    // a closed candy machine is available when its candy guard is specified
+    let candy_guard_id = match args.candy_guard {
+        Some(candy_guard) => Some(
+            Pubkey::from_str(&candy_guard)
+                .map_err(|_| anyhow!("Failed to parse candy guard id: {}", candy_guard))?,
+        ),
+        None => {
+            let core =
+                get_candy_machine_program(&sugar_config, &candy_machine_id).map_err(|_| {
+                    anyhow!(
+                        "Candy machine {} not found, specify its candy guard with \
+                        --candy-guard if it was closed.",
+                        candy_machine_id
+                    )
+                })? == CORE_CANDY_MACHINE_ID;
+
+            let mint_authority = if core {
+                get_core_candy_machine_state(&sugar_config, &candy_machine_id)?.mint_authority
+            } else {
+                get_candy_machine_state(&sugar_config, &candy_machine_id)?.mint_authority
+            };
+
+            // the mint authority is only a candy guard when the machine is wrapped
+            rpc_client
+                .get_account_with_commitment(&mint_authority, rpc_client.commitment())?
+                .value
+                .filter(|account| {
+                    account.owner == mpl_candy_guard::ID || account.owner == CORE_CANDY_GUARD_ID
+                })
+                .map(|_| mint_authority)
+        }
+    };
+
+    pb.finish_with_message("Done");
+
+    println!(
+        "\n{} {}",
+        style("Candy machine ID:").bold(),
+        candy_machine_id
+    );
+    if let Some(candy_guard_id) = candy_guard_id {
+        println!("{} {}", style("Candy guard ID:").bold(), candy_guard_id);
+    }
+
+    println!(
+        "\n{} {}Fetching transaction history",
+        style("[2/3]").bold().dim(),
+        COMPUTER_EMOJI
+    );
+
+    let programs = [
+        CANDY_MACHINE_ID,
+        CORE_CANDY_MACHINE_ID,
+        mpl_candy_guard::ID,
+        CORE_CANDY_GUARD_ID,
+    ];
+    let addresses: Vec<Pubkey> = std::iter::once(candy_machine_id)
+        .chain(candy_guard_id)
+        .collect();
+    let entries = fetch_history(&rpc_client, &addresses, &programs)?;
+
+    println!(
+        "\n{} {}Exporting {} transaction(s)",
+        style("[3/3]").bold().dim(),
+        PAPER_EMOJI,
+        entries.len()
+    );
+
+    let mut file = fs::File::create(&args.output)
+        .map_err(|e| anyhow!("Failed to create '{}': {}", args.output, e))?;
+    writeln!(file, "{}", HISTORY_HEADER)?;
+
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+
+    for entry in &entries {
+        for instruction in &entry.instructions {
+            *counts.entry(instruction.clone()).or_default() += 1;
+        }
+
+        writeln!(
+            file,
+            "{},{},{},{},{},{}",
+            entry
+                .block_time
+                .and_then(|block_time| Utc.timestamp_opt(block_time, 0).single())
+                .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+            entry.slot,
+            entry.signature,
+            entry.instructions.join(" "),
+            entry
+                .signer
+                .map(|signer| signer.to_string())
+                .unwrap_or_default(),
+            if entry.failed { "failed" } else { "success" }
+        )?;
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["instruction", "transactions"]);
+
+    for (instruction, count) in &counts {
+        builder.push_record([instruction.clone(), count.to_string()]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::psql());
+    println!("\n{}", table);
+
+    println!("\nHistory saved to '{}'", args.output);
+
+    Ok(())
+}
+
+/// Fetches the transactions of the addresses, sorted from the oldest.
+fn fetch_history(
+    rpc_client: &RpcClient,
+    addresses: &[Pubkey],
+    programs: &[Pubkey],
+) -> Result<Vec<HistoryEntry>> {
+    let pb = spinner_with_style();
+    pb.set_message("Fetching signatures...");
+
+    // the same transaction can reference the candy machine and the candy guard
+    let mut signatures: BTreeMap<(u64, String), Option<i64>> = BTreeMap::new();
+
+    for address in addresses {
+        let mut before = None;
+
+        loop {
+            let page = rpc_client.get_signatures_for_address_with_config(
+                address,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: None,
+                    limit: Some(SIGNATURES_PAGE_SIZE),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )?;
+
+            let last = match page.last() {
+                Some(last) => Signature::from_str(&last.signature)?,
+                None => break,
+            };
+            let page_len = page.len();
+
+            for status in page {
+                signatures.insert((status.slot, status.signature), status.block_time);
+            }
+
+            if page_len < SIGNATURES_PAGE_SIZE {
+                break;
+            }
+
+            before = Some(last);
+            pb.set_message(format!(
+                "Fetching signatures... ({} found)",
+                signatures.len()
+            ));
+        }
+    }
+
+    pb.finish_and_clear();
+
+    let pb = progress_bar_with_style(signatures.len() as u64);
+    pb.set_message("Fetching transactions");
+
+    let mut entries = Vec::new();
+
+    for ((slot, signature), block_time) in signatures {
+        let transaction = get_transaction(rpc_client, &Signature::from_str(&signature)?)?;
+        pb.inc(1);
+
+        let meta = match transaction.transaction.meta {
+            Some(meta) => meta,
+            None => continue,
+        };
+
+        let logs: Vec<String> = Option::from(meta.log_messages).unwrap_or_default();
+        let decoded: Option<VersionedTransaction> = transaction.transaction.transaction.decode();
+
+        entries.push(HistoryEntry {
+            signature,
+            slot,
+            block_time: block_time.or(transaction.block_time),
+            instructions: decode_instructions(&logs, programs),
+            signer: decoded.and_then(|tx| tx.message.static_account_keys().first().copied()),
+            failed: meta.err.is_some(),
+        });
+    }
+
+    pb.finish_and_clear();
+
+    Ok(entries)
+}
+
+/// Returns the (distinct) instructions of the programs executed by the transaction,
+/// using the instruction names logged by anchor. Versions of the same instruction
+/// are reported with the same name (e.g. "mintV2" as "mint").
+/// Fetches a transaction, retrying when the request fails: public endpoints often fail
+/// (or rate limit) some of the many requests of a long history.
+fn get_transaction(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+    let mut attempt = 1;
+
+    loop {
+        let result = rpc_client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        );
+
+        match result {
+            Ok(transaction) => return Ok(transaction),
+            Err(err) if attempt < MAX_ATTEMPTS => {
+                debug!(
+                    "Failed to fetch transaction {} (attempt {}): {}",
+                    signature, attempt, err
+                );
+                thread::sleep(RETRY_DELAY * attempt);
+                attempt += 1;
+            }
+            Err(err) => {
+                return Err(anyhow!(
+                    "Failed to fetch transaction {}: {}",
+                    signature,
+                    err
+                ))
+            }
+        }
+    }
+}
+
+fn decode_instructions(logs: &[String], programs: &[Pubkey]) -> Vec<String> {
+    // programs being executed, the last one is the program writing the logs
+    let mut stack: Vec<String> = Vec::new();
+    let mut instructions = Vec::new();
+
+    for log in logs {
+        if let Some(name) = log.strip_prefix(INSTRUCTION_LOG) {
+            let tracked = stack
+                .last()
+                .and_then(|program| Pubkey::from_str(program).ok())
+                .map_or(false, |program| programs.contains(&program));
+
+            if tracked {
+                let name = name.trim().trim_end_matches("V2");
+                let mut chars = name.chars();
+                let name = match chars.next() {
+                    Some(first) => first.to_lowercase().chain(chars).collect::<String>(),
+                    None => continue,
+                };
+
+                if !instructions.contains(&name) {
+                    instructions.push(name);
+                }
+            }
+
+            continue;
+        }
+
+        let words: Vec<&str> = log.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["Program", program, "invoke", ..] => stack.push(program.to_string()),
+            ["Program", _, "success"] | ["Program", _, "failed:", ..] => {
+                stack.pop();
+            }
+            _ => (),
+        }
+    }
+
+    instructions
+}
+
+#[test]
+fn test_history_instructions() {
+    let candy_guard = mpl_candy_guard::ID.to_string();
+    let candy_machine = CANDY_MACHINE_ID.to_string();
+    let logs = vec![
+        format!("Program {candy_guard} invoke [1]"),
+        "Program log: Instruction: MintV2".to_string(),
+        format!("Program {candy_machine} invoke [2]"),
+        "Program log: Instruction: MintV2".to_string(),
+        "Program metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s invoke [3]".to_string(),
+        "Program log: Instruction: Mint".to_string(),
+        "Program metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s success".to_string(),
+        format!("Program {candy_machine} success"),
+        format!("Program {candy_guard} success"),
+        format!("Program {candy_machine} invoke [1]"),
+        "Program log: Instruction: AddConfigLines".to_string(),
+        format!("Program {candy_machine} success"),
+    ];
+
+    assert_eq!(
+        decode_instructions(&logs, &[CANDY_MACHINE_ID, mpl_candy_guard::ID]),
+        vec!["mint".to_string(), "addConfigLines".to_string()]
+    );
+}
@@ -0,0 +1,131 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use console::style;
+
+use crate::{common::*, config::HooksConfig, dry_run::is_dry_run};
+
+/// Lifecycle events with a hook in the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreUpload,
+    PostDeploy,
+    PostMint,
+}
+
+impl HookEvent {
+    /// Name of the event in the config file and in the payload of the hook.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookEvent::PreUpload => "preUpload",
+            HookEvent::PostDeploy => "postDeploy",
+            HookEvent::PostMint => "postMint",
+        }
+    }
+
+    fn command<'a>(&self, hooks: &'a HooksConfig) -> Option<&'a str> {
+        match self {
+            HookEvent::PreUpload => hooks.pre_upload.as_deref(),
+            HookEvent::PostDeploy => hooks.post_deploy.as_deref(),
+            HookEvent::PostMint => hooks.post_mint.as_deref(),
+        }
+    }
+}
+
+/// Runs the hook of the event, writing the payload (with the name of the event) as JSON
+/// to its stdin. A failing `preUpload` hook aborts the command, while the failure of a
+/// post hook is only reported since the operation has already been completed.
+pub fn run_hook(hooks: Option<&HooksConfig>, event: HookEvent, mut payload: Value) -> Result<()> {
+    let command = match hooks.and_then(|hooks| event.command(hooks)) {
+        Some(command) if !command.trim().is_empty() => command,
+        _ => return Ok(()),
+    };
+
+    if is_dry_run() {
+        println!(
+            "{}",
+            style(format!(
+                "The {} hook is not run in a dry run.",
+                event.name()
+            ))
+            .dim()
+        );
+        return Ok(());
+    }
+
+    if let Value::Object(fields) = &mut payload {
+        fields.insert("event".to_string(), json!(event.name()));
+    }
+
+    println!("\n{} {}\n", style(">>>").magenta(), command);
+
+    match execute(command, &payload) {
+        Ok(()) => Ok(()),
+        Err(err) if event == HookEvent::PreUpload => Err(err),
+        Err(err) => {
+            println!("{} {}", WARNING_EMOJI, style(err).yellow());
+            Ok(())
+        }
+    }
+}
+
+fn execute(command: &str, payload: &Value) -> Result<()> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    };
+
+    let mut child = shell
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run hook '{}': {}", command, e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // a hook that does not read the payload closes its input early
+        let _ = stdin.write_all(payload.to_string().as_bytes());
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| anyhow!("Failed to run hook '{}': {}", command, e))?;
+
+    if !status.success() {
+        return Err(anyhow!(
+            "Hook '{}' failed with {}",
+            command,
+            status
+                .code()
+                .map(|c| format!("exit code {c}"))
+                .unwrap_or_else(|| "no exit code".to_string())
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_hook_receives_payload() {
+    let hooks = HooksConfig {
+        pre_upload: Some("grep -q '\"event\":\"preUpload\"'".to_string()),
+        post_mint: Some("exit 3".to_string()),
+        ..Default::default()
+    };
+
+    assert!(run_hook(Some(&hooks), HookEvent::PreUpload, json!({ "items": 10 })).is_ok());
+    // the post hooks do not fail the command
+    assert!(run_hook(Some(&hooks), HookEvent::PostMint, json!({})).is_ok());
+
+    let hooks = HooksConfig {
+        pre_upload: Some("exit 3".to_string()),
+        ..Default::default()
+    };
+    assert!(run_hook(Some(&hooks), HookEvent::PreUpload, json!({})).is_err());
+}
@@ -0,0 +1,104 @@
+use std::{
+    fs::File,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+};
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{anyhow, Result};
+use console::style;
+use lazy_static::lazy_static;
+use serde_json::Value;
+
+use crate::{config::apply_active_profile, constants::WARNING_EMOJI};
+
+/// Key of the expected authority in the config file.
+const EXPECTED_AUTHORITY_KEY: &str = "expectedAuthority";
+
+static IDENTITY_OVERRIDE: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref EXPECTED_AUTHORITY: RwLock<Option<Pubkey>> = RwLock::new(None);
+}
+
+/// Allows commands to run with a keypair other than the expected authority, based on
+/// the `--i-know-what-im-doing` global flag.
+pub fn set_identity_override(override_check: bool) {
+    IDENTITY_OVERRIDE.store(override_check, Ordering::SeqCst);
+}
+
+/// Reads the expected authority pinned in the config file (after the overrides of the
+/// active profile). A missing or unreadable config file pins no authority, its errors
+/// are reported by the commands that use it.
+pub fn load_expected_authority(config_path: &str) -> Result<()> {
+    let mut config: Value = match File::open(config_path)
+        .ok()
+        .and_then(|f| serde_json::from_reader(f).ok())
+    {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+    apply_active_profile(&mut config);
+
+    let expected = match config.get(EXPECTED_AUTHORITY_KEY) {
+        Some(Value::String(pubkey)) => Some(pubkey.parse::<Pubkey>().map_err(|_| {
+            anyhow!("Invalid {EXPECTED_AUTHORITY_KEY} in '{config_path}': {pubkey}")
+        })?),
+        Some(Value::Null) | None => None,
+        Some(_) => {
+            return Err(anyhow!(
+                "Invalid {EXPECTED_AUTHORITY_KEY} in '{config_path}': expected a string"
+            ))
+        }
+    };
+
+    *EXPECTED_AUTHORITY.write().unwrap() = expected;
+
+    Ok(())
+}
+
+/// Checks that the keypair signing the transactions is the expected authority of the
+/// config, unless the check was overridden. Read-only commands are not checked.
+pub fn check_identity(payer: &Pubkey) -> Result<()> {
+    verify_identity(
+        payer,
+        *EXPECTED_AUTHORITY.read().unwrap(),
+        IDENTITY_OVERRIDE.load(Ordering::SeqCst),
+    )
+}
+
+fn verify_identity(payer: &Pubkey, expected: Option<Pubkey>, override_check: bool) -> Result<()> {
+    let expected = match expected {
+        Some(expected) if expected != *payer => expected,
+        _ => return Ok(()),
+    };
+
+    if override_check {
+        println!(
+            "{} {}",
+            WARNING_EMOJI,
+            style(format!(
+                "Running with keypair {payer} instead of the expected authority {expected}."
+            ))
+            .yellow()
+        );
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "The keypair {payer} is not the expected authority {expected} of the config; \
+        use the correct keypair or re-run with --i-know-what-im-doing"
+    ))
+}
+
+#[test]
+fn test_identity_mismatch() {
+    let expected = Pubkey::new_unique();
+
+    assert!(verify_identity(&expected, Some(expected), false).is_ok());
+    assert!(verify_identity(&Pubkey::new_unique(), Some(expected), false).is_err());
+    assert!(verify_identity(&Pubkey::new_unique(), Some(expected), true).is_ok());
+    assert!(verify_identity(&Pubkey::new_unique(), None, false).is_ok());
+}
@@ -0,0 +1,179 @@
+use console::style;
+
+use crate::{
+    cache::{Cache, CacheItem, CacheProgram},
+    candy_machine::{is_config_line_loaded, read_config_line, CANDY_MACHINE_ID},
+    common::*,
+    pdas::get_metadata_pda,
+    utils::*,
+};
+
+pub struct ImportArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_machine: String,
+}
+
+pub fn process_import(args: ImportArgs) -> Result<()> {
+    let candy_machine_id = Pubkey::from_str(&args.candy_machine)
+        .map_err(|_| anyhow!("Failed to parse candy machine id: {}", args.candy_machine))?;
+
+    if Path::new(&args.cache).exists() {
+        let prompt = format!("Cache file '{}' already exists. Overwrite it?", args.cache);
+
+        if !confirm_with_theme(&get_dialoguer_theme(), &prompt)? {
+            return Err(anyhow!("Import aborted"));
+        }
+    }
+
+    println!(
+        "{} {}Loading candy machine",
+        style("[1/2]").bold().dim(),
+        CANDY_EMOJI
+    );
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(CANDY_MACHINE_ID);
+
+    let data = program
+        .rpc()
+        .get_account_data(&candy_machine_id)
+        .map_err(|_| anyhow!("Candy machine {} not found", candy_machine_id))?;
+    let candy_machine = CandyMachine::try_deserialize(&mut data.as_slice())?;
+
+    // the mint authority is a candy guard when the candy machine is wrapped
+    let candy_guard = program
+        .rpc()
+        .get_account(&candy_machine.mint_authority)
+        .map(|account| account.owner == mpl_candy_guard::ID)
+        .unwrap_or(false)
+        .then_some(candy_machine.mint_authority);
+
+    let (_, collection_metadata) = get_metadata_pda(&candy_machine.collection_mint, &program)?;
+
+    pb.finish_with_message("Done");
+
+    println!(
+        "\n{} {}Rebuilding cache",
+        style("[2/2]").bold().dim(),
+        PAPER_EMOJI
+    );
+
+    let mut cache = Cache::new();
+    cache.file_path = args.cache.clone();
+    cache.program = CacheProgram::new_from_cm(&candy_machine_id);
+    cache.program.collection_mint = candy_machine.collection_mint.to_string();
+    cache.program.candy_guard = candy_guard.map(|id| id.to_string()).unwrap_or_default();
+
+    let imported_item = |name: String, uri: String| CacheItem {
+        name,
+        image_hash: String::new(),
+        image_link: String::new(),
+        metadata_hash: String::new(),
+        metadata_link: uri,
+        on_chain: true,
+        animation_hash: None,
+        animation_link: None,
+        status: ItemStatus::Deployed,
+        timestamps: ItemTimestamps::default(),
+    };
+
+    cache.items.insert(
+        COLLECTION_INDEX.to_string(),
+        imported_item(
+            collection_metadata
+                .data
+                .name
+                .trim_matches(char::from(0))
+                .to_string(),
+            collection_metadata
+                .data
+                .uri
+                .trim_matches(char::from(0))
+                .to_string(),
+        ),
+    );
+
+    let items_available = candy_machine.data.items_available;
+    let mut missing = Vec::new();
+
+    if let Some(hidden_settings) = &candy_machine.data.hidden_settings {
+        println!("\nHidden settings enabled, there are no config lines to import:");
+        println!("  name: {}", hidden_settings.name);
+        println!("  uri: {}", hidden_settings.uri);
+    } else if let Some(config_line_settings) = &candy_machine.data.config_line_settings {
+        let pb = progress_bar_with_style(items_available);
+
+        for i in 0..items_available {
+            if !is_config_line_loaded(&data, &candy_machine.data, i as usize)? {
+                missing.push(i);
+            } else {
+                let (name, uri) = read_config_line(&data, &candy_machine.data, i as usize)?;
+                cache.items.insert(
+                    i.to_string(),
+                    imported_item(
+                        config_line_settings.prefix_name.clone() + &name,
+                        config_line_settings.prefix_uri.clone() + &uri,
+                    ),
+                );
+            }
+
+            pb.inc(1);
+        }
+
+        pb.finish_and_clear();
+    }
+
+    cache.sync_file()?;
+
+    println!(
+        "\n{} {}",
+        style("Candy machine ID:").bold(),
+        candy_machine_id
+    );
+    if let Some(candy_guard) = candy_guard {
+        println!("{} {}", style("Candy guard ID:").bold(), candy_guard);
+    }
+    println!(
+        "{} {}",
+        style("Collection mint:").bold(),
+        candy_machine.collection_mint
+    );
+    println!(
+        "{} {} of {}",
+        style("Config lines imported:").bold(),
+        cache.items.len() - 1,
+        if candy_machine.data.hidden_settings.is_some() {
+            0
+        } else {
+            items_available
+        }
+    );
+
+    if !missing.is_empty() {
+        println!(
+            "\n{}{}",
+            WARNING_EMOJI,
+            style(format!(
+                "{} config line(s) are not loaded on-chain (first: item {}); add them to the \
+                cache before running deploy.",
+                missing.len(),
+                missing[0]
+            ))
+            .yellow()
+        );
+    }
+
+    println!(
+        "\n{}Cache saved to '{}'. Image links and hashes are not stored on-chain, so they \
+        are left empty.",
+        COMPLETE_EMOJI, args.cache
+    );
+
+    Ok(())
+}
@@ -0,0 +1,271 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    sync::Mutex,
+};
+
+use chrono::Utc;
+use console::style;
+use serde::Serialize;
+
+use crate::{common::*, dry_run::is_dry_run, utils::*};
+
+/// Directory where sugar keeps the state of in-flight operations.
+pub const STATE_DIR: &str = ".sugar";
+
+/// Status of an operation recorded in the journal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntryStatus {
+    /// The operation was sent, but its outcome is unknown.
+    Pending,
+    /// The operation was confirmed.
+    Completed,
+}
+
+/// Line of the journal file: the first line is the header of the run, every other
+/// line records a status change of an operation.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+enum JournalLine {
+    Header {
+        command: String,
+        started: String,
+    },
+    Entry {
+        key: String,
+        status: EntryStatus,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+}
+
+/// Journal of the operations of a command, stored as JSON lines in the state directory
+/// so an interrupted run can be resumed without repeating the operations already sent.
+/// In a dry run, the journal of a previous run is read but nothing is written.
+pub struct Journal {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+    entries: Mutex<IndexMap<String, (EntryStatus, Option<String>)>>,
+}
+
+impl Journal {
+    /// Opens the journal of `command` for `scope` (e.g., the candy machine id). When a
+    /// previous run was interrupted, the user chooses to resume or discard it.
+    pub fn open(command: &str, scope: &str) -> Result<Journal> {
+        let path = Path::new(STATE_DIR).join(format!("{command}-{scope}.json"));
+        let mut entries = IndexMap::new();
+
+        if path.exists() {
+            let (started, previous) = read_journal(&path)?;
+
+            if !previous.is_empty() && resume_previous(command, &started, &previous)? {
+                entries = previous;
+            } else if !is_dry_run() {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        if is_dry_run() {
+            return Ok(Journal {
+                path,
+                file: Mutex::new(None),
+                entries: Mutex::new(entries),
+            });
+        }
+
+        fs::create_dir_all(STATE_DIR)?;
+
+        let new_file = !path.exists();
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        if new_file {
+            write_line(
+                &mut file,
+                &JournalLine::Header {
+                    command: command.to_string(),
+                    started: Utc::now().to_rfc3339(),
+                },
+            )?;
+        }
+
+        Ok(Journal {
+            path,
+            file: Mutex::new(Some(file)),
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Status of the operation `key` in the journal, if it was recorded.
+    pub fn status(&self, key: &str) -> Option<EntryStatus> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|(status, _)| *status)
+    }
+
+    /// Detail recorded with the operation `key` (e.g., the transaction signature).
+    pub fn detail(&self, key: &str) -> Option<String> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .and_then(|(_, detail)| detail.clone())
+    }
+
+    /// Keys of the operations with the specified status.
+    pub fn keys(&self, status: EntryStatus) -> Vec<String> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, (entry_status, _))| *entry_status == status)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Records that the operation `key` is about to be sent.
+    pub fn mark_pending(&self, key: &str) -> Result<()> {
+        self.record(key, EntryStatus::Pending, None)
+    }
+
+    /// Records the transaction sent for the operation `key` (e.g., its signature), so an
+    /// interrupted run can check whether it landed.
+    pub fn mark_sent(&self, key: &str, detail: String) -> Result<()> {
+        self.record(key, EntryStatus::Pending, Some(detail))
+    }
+
+    /// Records that the operation `key` was confirmed.
+    pub fn mark_completed(&self, key: &str, detail: Option<String>) -> Result<()> {
+        self.record(key, EntryStatus::Completed, detail)
+    }
+
+    /// Removes the journal once the command has finished and its results are saved.
+    pub fn finish(self) -> Result<()> {
+        if self.file.lock().unwrap().is_some() && self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+
+        Ok(())
+    }
+
+    fn record(&self, key: &str, status: EntryStatus, detail: Option<String>) -> Result<()> {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            write_line(
+                file,
+                &JournalLine::Entry {
+                    key: key.to_string(),
+                    status,
+                    detail: detail.clone(),
+                },
+            )?;
+        }
+
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (status, detail));
+
+        Ok(())
+    }
+}
+
+fn write_line(file: &mut File, line: &JournalLine) -> Result<()> {
+    // each line is written with a single call so an interruption cannot leave
+    // a partial entry behind other than the last one
+    let mut content = serde_json::to_string(line)?;
+    content.push('\n');
+    file.write_all(content.as_bytes())?;
+    file.flush()?;
+
+    Ok(())
+}
+
+/// Read a journal file, returning the start date of the run and the latest status of
+/// each operation. Incomplete lines from an interrupted write are ignored.
+fn read_journal(path: &Path) -> Result<(String, IndexMap<String, (EntryStatus, Option<String>)>)> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut started = String::from("unknown");
+    let mut entries = IndexMap::new();
+
+    for line in reader.lines() {
+        match serde_json::from_str::<JournalLine>(&line?) {
+            Ok(JournalLine::Header { started: date, .. }) => started = date,
+            Ok(JournalLine::Entry {
+                key,
+                status,
+                detail,
+            }) => {
+                entries.insert(key, (status, detail));
+            }
+            Err(err) => debug!("Skipping invalid journal line in {:?}: {}", path, err),
+        }
+    }
+
+    Ok((started, entries))
+}
+
+fn resume_previous(
+    command: &str,
+    started: &str,
+    entries: &IndexMap<String, (EntryStatus, Option<String>)>,
+) -> Result<bool> {
+    let pending = entries
+        .values()
+        .filter(|(status, _)| *status == EntryStatus::Pending)
+        .count();
+
+    println!(
+        "{}{}",
+        WARNING_EMOJI,
+        style(format!(
+            "Previous run of `sugar {command}` detected (started {started}): \
+            {} completed, {pending} pending operation(s).",
+            entries.len() - pending
+        ))
+        .yellow()
+        .bold()
+    );
+
+    confirm_with_theme(
+        &get_dialoguer_theme(),
+        "Resume the previous run? (no discards it)",
+    )
+}
+
+#[test]
+fn test_journal_keeps_latest_status() {
+    let dir = std::env::temp_dir().join(format!("sugar-journal-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("test.json");
+
+    let mut file = File::create(&path).unwrap();
+    let header = JournalLine::Header {
+        command: "test".to_string(),
+        started: "now".to_string(),
+    };
+    write_line(&mut file, &header).unwrap();
+
+    for (key, status) in [
+        ("0-9", EntryStatus::Pending),
+        ("10-19", EntryStatus::Pending),
+        ("0-9", EntryStatus::Completed),
+    ] {
+        let entry = JournalLine::Entry {
+            key: key.to_string(),
+            status,
+            detail: None,
+        };
+        write_line(&mut file, &entry).unwrap();
+    }
+    file.write_all(b"{\"key\":\"20-").unwrap();
+
+    let (started, entries) = read_journal(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(started, "now");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries["0-9"].0, EntryStatus::Completed);
+    assert_eq!(entries["10-19"].0, EntryStatus::Pending);
+}
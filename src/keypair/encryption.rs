@@ -0,0 +1,244 @@
+use std::{env, fs::File, num::NonZeroU32, path::Path};
+
+use anchor_client::solana_sdk::{
+    derivation_path::DerivationPath,
+    signature::{
+        generate_seed_from_seed_phrase_and_passphrase, keypair_from_seed,
+        keypair_from_seed_and_derivation_path, read_keypair_file, Keypair,
+    },
+};
+use anyhow::{anyhow, Result};
+use data_encoding::HEXLOWER;
+use dialoguer::{Input, Password};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{ensure_interactive, get_dialoguer_theme};
+
+/// Prefix of the keypair "path" indicating that the keypair should be derived from a
+/// seed phrase entered by the user (optionally followed by `?key=<derivation path>`).
+pub const SEED_PHRASE_PROMPT: &str = "prompt://";
+
+/// Environment variable with the passphrase of encrypted keypair files.
+pub const KEYPAIR_PASSPHRASE_ENV: &str = "SUGAR_KEYPAIR_PASSPHRASE";
+
+/// Version of the encrypted keypair file format.
+const ENCRYPTED_KEYPAIR_VERSION: u8 = 1;
+
+/// Number of PBKDF2 iterations used to derive the encryption key.
+pub const PBKDF2_ITERATIONS: u32 = 600_000;
+
+/// Size of the salt used to derive the encryption key.
+pub const SALT_LEN: usize = 16;
+
+/// Keypair file encrypted with a passphrase (PBKDF2-HMAC-SHA256 + AES-256-GCM).
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EncryptedKeypair {
+    pub version: u8,
+    pub iterations: u32,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Derives a 256-bit key from a passphrase (PBKDF2-HMAC-SHA256).
+pub fn derive_key_bytes(passphrase: &str, salt: &[u8], iterations: u32) -> Result<[u8; 32]> {
+    let iterations =
+        NonZeroU32::new(iterations).ok_or_else(|| anyhow!("Invalid number of iterations"))?;
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+
+    Ok(key)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<LessSafeKey> {
+    let key = derive_key_bytes(passphrase, salt, iterations)?;
+    let key = UnboundKey::new(&AES_256_GCM, &key)
+        .map_err(|_| anyhow!("Failed to create encryption key"))?;
+
+    Ok(LessSafeKey::new(key))
+}
+
+/// Encrypts a keypair with the specified passphrase.
+pub fn encrypt_keypair(keypair: &Keypair, passphrase: &str) -> Result<EncryptedKeypair> {
+    let rng = SystemRandom::new();
+
+    let mut salt = [0u8; SALT_LEN];
+    rng.fill(&mut salt)
+        .map_err(|_| anyhow!("Failed to generate salt"))?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut nonce)
+        .map_err(|_| anyhow!("Failed to generate nonce"))?;
+
+    let key = derive_key(passphrase, &salt, PBKDF2_ITERATIONS)?;
+    let mut in_out = keypair.to_bytes().to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut in_out,
+    )
+    .map_err(|_| anyhow!("Failed to encrypt keypair"))?;
+
+    Ok(EncryptedKeypair {
+        version: ENCRYPTED_KEYPAIR_VERSION,
+        iterations: PBKDF2_ITERATIONS,
+        salt: HEXLOWER.encode(&salt),
+        nonce: HEXLOWER.encode(&nonce),
+        ciphertext: HEXLOWER.encode(&in_out),
+    })
+}
+
+/// Decrypts an encrypted keypair with the specified passphrase.
+pub fn decrypt_keypair(encrypted: &EncryptedKeypair, passphrase: &str) -> Result<Keypair> {
+    if encrypted.version != ENCRYPTED_KEYPAIR_VERSION {
+        return Err(anyhow!(
+            "Unsupported encrypted keypair version: {}",
+            encrypted.version
+        ));
+    }
+
+    let decode = |value: &str| {
+        HEXLOWER
+            .decode(value.as_bytes())
+            .map_err(|_| anyhow!("Invalid encrypted keypair file"))
+    };
+
+    let salt = decode(&encrypted.salt)?;
+    let nonce: [u8; NONCE_LEN] = decode(&encrypted.nonce)?
+        .try_into()
+        .map_err(|_| anyhow!("Invalid encrypted keypair file"))?;
+    let mut in_out = decode(&encrypted.ciphertext)?;
+
+    let key = derive_key(passphrase, &salt, encrypted.iterations)?;
+    let bytes = key
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut in_out,
+        )
+        .map_err(|_| anyhow!("Failed to decrypt keypair: invalid passphrase"))?;
+
+    Keypair::from_bytes(bytes).map_err(|e| anyhow!("Invalid keypair: {}", e))
+}
+
+/// Returns the passphrase from the environment or prompts the user for it.
+pub fn read_passphrase(prompt: &str, confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = env::var(KEYPAIR_PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+
+    ensure_interactive(prompt)?;
+
+    let theme = get_dialoguer_theme();
+    let mut password = Password::with_theme(&theme);
+    password.with_prompt(prompt);
+
+    if confirm {
+        password.with_confirmation("Confirm passphrase", "Passphrases do not match");
+    }
+
+    Ok(password.interact()?)
+}
+
+/// Derives a keypair from a seed phrase entered by the user. The derivation path is taken
+/// from the `?key=` parameter or prompted for when not specified.
+fn read_seed_phrase_keypair(uri: &str) -> Result<Keypair> {
+    ensure_interactive("seed phrase")?;
+
+    let theme = get_dialoguer_theme();
+
+    let seed_phrase = Password::with_theme(&theme)
+        .with_prompt("Seed phrase")
+        .interact()?;
+    let seed_phrase = seed_phrase
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    let passphrase = Password::with_theme(&theme)
+        .with_prompt("Seed phrase passphrase (leave empty for none)")
+        .allow_empty_password(true)
+        .interact()?;
+
+    let derivation_path = match uri
+        .strip_prefix(SEED_PHRASE_PROMPT)
+        .and_then(|query| query.strip_prefix("?key="))
+    {
+        Some(key) => Some(key.to_string()),
+        None => {
+            let path: String = Input::with_theme(&theme)
+                .with_prompt("Derivation path (e.g. m/44'/501'/0'/0', leave empty for none)")
+                .allow_empty(true)
+                .interact_text()?;
+            if path.trim().is_empty() {
+                None
+            } else {
+                Some(path.trim().to_string())
+            }
+        }
+    };
+
+    let seed = generate_seed_from_seed_phrase_and_passphrase(&seed_phrase, &passphrase);
+
+    let keypair = match derivation_path {
+        Some(path) => {
+            let derivation_path = if path.starts_with('m') {
+                DerivationPath::from_absolute_path_str(&path)
+            } else {
+                DerivationPath::from_key_str(&path)
+            }
+            .map_err(|e| anyhow!("Invalid derivation path '{}': {}", path, e))?;
+
+            keypair_from_seed_and_derivation_path(&seed, Some(derivation_path))
+        }
+        None => keypair_from_seed(&seed),
+    }
+    .map_err(|e| anyhow!("Failed to derive keypair from seed phrase: {}", e))?;
+
+    Ok(keypair)
+}
+
+/// Reads a keypair from the specified location, which can be:
+/// - a plain keypair file (JSON array of bytes);
+/// - an encrypted keypair file (the passphrase is read from the environment or prompted for);
+/// - `prompt://[?key=<derivation path>]` to derive the keypair from a seed phrase.
+pub fn read_keypair(location: &str) -> Result<Keypair> {
+    if location.starts_with(SEED_PHRASE_PROMPT) {
+        return read_seed_phrase_keypair(location);
+    }
+
+    let path = shellexpand::tilde(location).to_string();
+
+    let encrypted: Option<EncryptedKeypair> = File::open(Path::new(&path))
+        .ok()
+        .and_then(|f| serde_json::from_reader(f).ok());
+
+    match encrypted {
+        Some(encrypted) => {
+            let passphrase = read_passphrase(&format!("Passphrase for '{}'", location), false)?;
+            decrypt_keypair(&encrypted, &passphrase)
+        }
+        None => read_keypair_file(&path).map_err(|e| anyhow!("{}", e)),
+    }
+}
+
+#[test]
+fn test_encrypt_decrypt_keypair() {
+    let keypair = Keypair::new();
+    let encrypted = encrypt_keypair(&keypair, "sugar").unwrap();
+
+    let decrypted = decrypt_keypair(&encrypted, "sugar").unwrap();
+    assert_eq!(keypair.to_bytes(), decrypted.to_bytes());
+
+    assert!(decrypt_keypair(&encrypted, "salt").is_err());
+}
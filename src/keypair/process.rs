@@ -0,0 +1,56 @@
+use std::{fs::OpenOptions, path::Path};
+
+use anyhow::{anyhow, Result};
+use console::style;
+
+use crate::{
+    common::*,
+    keypair::encryption::{encrypt_keypair, read_keypair, read_passphrase},
+};
+
+pub struct EncryptKeypairArgs {
+    pub keypair: String,
+    pub output: String,
+}
+
+pub fn process_encrypt_keypair(args: EncryptKeypairArgs) -> Result<()> {
+    if Path::new(&args.output).exists() {
+        return Err(anyhow!(
+            "Output file '{}' already exists, choose a different path.",
+            args.output
+        ));
+    }
+
+    let keypair = read_keypair(&args.keypair)?;
+    let passphrase = read_passphrase("New passphrase", true)?;
+
+    if passphrase.is_empty() {
+        return Err(anyhow!("The passphrase cannot be empty."));
+    }
+
+    let encrypted = encrypt_keypair(&keypair, &passphrase)?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let file = options.open(&args.output)?;
+    serde_json::to_writer_pretty(file, &encrypted)?;
+
+    println!(
+        "{} {}",
+        style("Encrypted keypair for:").bold(),
+        keypair.pubkey()
+    );
+    println!("{} {}", style("Saved to:").bold(), args.output);
+    println!(
+        "\n{}",
+        style("You can now remove the plaintext keypair file.").dim()
+    );
+
+    Ok(())
+}
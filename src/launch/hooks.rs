@@ -0,0 +1,134 @@
+use std::{fmt, process::Command, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use console::style;
+
+/// Stages of the launch pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Validate,
+    Upload,
+    Deploy,
+    Verify,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Stage::Validate => "validate",
+            Stage::Upload => "upload",
+            Stage::Deploy => "deploy",
+            Stage::Verify => "verify",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Stage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "validate" => Ok(Stage::Validate),
+            "upload" => Ok(Stage::Upload),
+            "deploy" => Ok(Stage::Deploy),
+            "verify" => Ok(Stage::Verify),
+            _ => Err(anyhow!(
+                "Invalid stage '{s}': must be one of validate, upload, deploy or verify"
+            )),
+        }
+    }
+}
+
+/// Indicates whether a hook runs before or after a stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookTiming {
+    Before,
+    After,
+}
+
+/// External command to run between stages of the launch pipeline.
+#[derive(Debug, Clone)]
+pub struct Hook {
+    pub timing: HookTiming,
+    pub stage: Stage,
+    pub command: String,
+}
+
+impl FromStr for Hook {
+    type Err = anyhow::Error;
+
+    /// Parses a hook in the format `<before|after>-<stage>=<command>`, e.g.
+    /// `before-upload=./generate.sh`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (point, command) = s.split_once('=').ok_or_else(|| {
+            anyhow!("Invalid hook '{s}': expected <before|after>-<stage>=<command>")
+        })?;
+
+        let (timing, stage) = if let Some(stage) = point.strip_prefix("before-") {
+            (HookTiming::Before, stage)
+        } else if let Some(stage) = point.strip_prefix("after-") {
+            (HookTiming::After, stage)
+        } else {
+            return Err(anyhow!(
+                "Invalid hook '{s}': expected <before|after>-<stage>=<command>"
+            ));
+        };
+
+        if command.trim().is_empty() {
+            return Err(anyhow!("Invalid hook '{s}': missing command"));
+        }
+
+        Ok(Hook {
+            timing,
+            stage: Stage::from_str(stage)?,
+            command: command.to_string(),
+        })
+    }
+}
+
+/// Runs the hooks registered for the specified stage and timing, in the order
+/// they were provided. A hook exiting with a non-zero status aborts the launch.
+pub fn run_hooks(hooks: &[Hook], timing: HookTiming, stage: Stage) -> Result<()> {
+    for hook in hooks
+        .iter()
+        .filter(|h| h.timing == timing && h.stage == stage)
+    {
+        println!("\n{} {}\n", style(">>>").magenta(), hook.command);
+
+        let status = if cfg!(windows) {
+            Command::new("cmd").args(["/C", &hook.command]).status()
+        } else {
+            Command::new("sh").args(["-c", &hook.command]).status()
+        }
+        .map_err(|e| anyhow!("Failed to run hook '{}': {}", hook.command, e))?;
+
+        if !status.success() {
+            return Err(anyhow!(
+                "Hook '{}' failed with {}",
+                hook.command,
+                status
+                    .code()
+                    .map(|c| format!("exit code {c}"))
+                    .unwrap_or_else(|| "no exit code".to_string())
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_hook_success() {
+    let hook = Hook::from_str("before-upload=./generate.sh --size 10").unwrap();
+    assert_eq!(hook.timing, HookTiming::Before);
+    assert_eq!(hook.stage, Stage::Upload);
+    assert_eq!(hook.command, "./generate.sh --size 10");
+}
+
+#[test]
+fn test_parse_hook_fail_invalid_stage() {
+    assert!(Hook::from_str("after-mint=echo done").is_err());
+    assert!(Hook::from_str("upload=echo done").is_err());
+    assert!(Hook::from_str("before-deploy=").is_err());
+}
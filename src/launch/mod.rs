@@ -1,3 +1,5 @@
+pub mod hooks;
 pub mod process;
 
+pub use hooks::*;
 pub use process::*;
@@ -1,15 +1,22 @@
-use std::sync::{atomic::AtomicBool, Arc};
+use std::{
+    path::Path,
+    sync::{atomic::AtomicBool, Arc},
+};
 
 use anyhow::Result;
 use console::{style, Style};
-use dialoguer::{theme::ColorfulTheme, Confirm};
+use dialoguer::theme::ColorfulTheme;
 
 use crate::{
-    common::LAUNCH_EMOJI,
-    config::parser::get_config_data,
+    bundlr::{process_bundlr, BundlrArgs},
+    cli::BundlrAction,
+    common::{DEFAULT_FAILURES, LAUNCH_EMOJI, WARNING_EMOJI},
+    config::{parser::get_config_data, UploadMethod},
     create_config::{process_create_config, CreateConfigArgs},
     deploy::{process_deploy, DeployArgs},
-    upload::{process_upload, UploadArgs},
+    launch::hooks::{run_hooks, Hook, HookTiming, Stage},
+    upload::{process_upload, UploadArgs, DEFAULT_MAX_RETRIES},
+    utils::{confirm_with_theme, set_stop_on_warning, stop_on_warning},
     validate::{process_validate, ValidateArgs},
     verify::{process_verify, VerifyArgs},
 };
@@ -22,6 +29,12 @@ pub struct LaunchArgs {
     pub cache: String,
     pub strict: bool,
     pub skip_collection_prompt: bool,
+    pub skip_validate: bool,
+    pub skip_verify: bool,
+    pub collection_mint: Option<String>,
+    pub hooks: Vec<Hook>,
+    pub stop_on_warning: bool,
+    pub withdraw_bundlr: bool,
     pub interrupted: Arc<AtomicBool>,
 }
 
@@ -36,10 +49,10 @@ pub async fn process_launch(args: LaunchArgs) -> Result<()> {
     if let Err(err) = get_config_data(&args.config) {
         // padding
         println!();
-        if Confirm::with_theme(&theme)
-            .with_prompt("Could not load config file. Would you like to create a new config file?")
-            .interact()?
-        {
+        if confirm_with_theme(
+            &theme,
+            "Could not load config file. Would you like to create a new config file?",
+        )? {
             println!("\n{} sugar create-config\n", style(">>>").magenta());
 
             let create_config_args = CreateConfigArgs {
@@ -47,6 +60,7 @@ pub async fn process_launch(args: LaunchArgs) -> Result<()> {
                 keypair: args.keypair.clone(),
                 rpc_url: args.rpc_url.clone(),
                 assets_dir: args.assets_dir.clone(),
+                template: None,
             };
 
             process_create_config(create_config_args)?;
@@ -55,15 +69,56 @@ pub async fn process_launch(args: LaunchArgs) -> Result<()> {
         }
     }
 
-    println!("\n{} sugar validate\n", style(">>>").magenta());
+    let config_data = get_config_data(&args.config)?;
+
+    // the collection might be stored outside of the assets folder or already exist
+    let collection_configured = config_data.collection_path.is_some()
+        || args.collection_mint.is_some()
+        || config_data
+            .collection
+            .as_ref()
+            .map_or(false, |collection| collection.uri.is_some());
+
+    set_stop_on_warning(args.stop_on_warning);
+
+    if args.stop_on_warning
+        && !collection_configured
+        && !Path::new(&args.assets_dir)
+            .join("collection.json")
+            .is_file()
+    {
+        println!(
+            "\n{}{}",
+            WARNING_EMOJI,
+            style("Missing collection files in the assets folder.")
+                .yellow()
+                .bold()
+        );
+        stop_on_warning("missing collection files in the assets folder")?;
+    }
 
-    let validate_args = ValidateArgs {
-        assets_dir: args.assets_dir.clone(),
-        strict: args.strict,
-        skip_collection_prompt: args.skip_collection_prompt,
-    };
+    run_hooks(&args.hooks, HookTiming::Before, Stage::Validate)?;
+
+    if args.skip_validate {
+        println!("\n{} sugar validate (skipping)", style(">>>").magenta());
+    } else {
+        println!("\n{} sugar validate\n", style(">>>").magenta());
 
-    process_validate(validate_args)?;
+        let validate_args = ValidateArgs {
+            assets_dir: args.assets_dir.clone(),
+            config: args.config.clone(),
+            schema: None,
+            strict: args.strict,
+            skip_collection_prompt: args.skip_collection_prompt || collection_configured,
+            report: None,
+            max_errors: None,
+        };
+
+        process_validate(validate_args)?;
+    }
+
+    run_hooks(&args.hooks, HookTiming::After, Stage::Validate)?;
+    run_hooks(&args.hooks, HookTiming::Before, Stage::Upload)?;
 
     println!("\n{} sugar upload\n", style(">>>").magenta());
 
@@ -73,11 +128,21 @@ pub async fn process_launch(args: LaunchArgs) -> Result<()> {
         keypair: args.keypair.clone(),
         rpc_url: args.rpc_url.clone(),
         cache: args.cache.clone(),
+        fail_on_duplicates: args.stop_on_warning,
+        max_bandwidth: None,
+        max_retries: DEFAULT_MAX_RETRIES,
+        verify: false,
+        verify_sample: None,
+        indices: None,
+        failures: DEFAULT_FAILURES.to_string(),
         interrupted: args.interrupted.clone(),
     };
 
     process_upload(upload_args).await?;
 
+    run_hooks(&args.hooks, HookTiming::After, Stage::Upload)?;
+    run_hooks(&args.hooks, HookTiming::Before, Stage::Deploy)?;
+
     println!("\n{} sugar deploy\n", style(">>>").magenta());
 
     let deploy_args = DeployArgs {
@@ -86,20 +151,60 @@ pub async fn process_launch(args: LaunchArgs) -> Result<()> {
         rpc_url: args.rpc_url.clone(),
         cache: args.cache.clone(),
         interrupted: args.interrupted.clone(),
-        collection_mint: None,
+        collection_mint: args.collection_mint.clone(),
+        indices: None,
+        fee_payers: None,
+        shuffle: None,
+        append: false,
+        failures: DEFAULT_FAILURES.to_string(),
     };
 
     process_deploy(deploy_args).await?;
 
-    println!("\n{} sugar verify\n", style(">>>").magenta());
+    run_hooks(&args.hooks, HookTiming::After, Stage::Deploy)?;
+    run_hooks(&args.hooks, HookTiming::Before, Stage::Verify)?;
+
+    if args.skip_verify {
+        println!("\n{} sugar verify (skipping)", style(">>>").magenta());
+    } else {
+        println!("\n{} sugar verify\n", style(">>>").magenta());
+
+        let verify_args = VerifyArgs {
+            keypair: args.keypair.clone(),
+            rpc_url: args.rpc_url.clone(),
+            cache: args.cache.clone(),
+            repair: false,
+            indices: None,
+            links: false,
+            report: None,
+            collection: false,
+            fix: false,
+            interrupted: args.interrupted.clone(),
+        };
+
+        process_verify(verify_args).await?;
+    }
 
-    let verify_args = VerifyArgs {
-        keypair: args.keypair.clone(),
-        rpc_url: args.rpc_url.clone(),
-        cache: args.cache.clone(),
-    };
+    run_hooks(&args.hooks, HookTiming::After, Stage::Verify)?;
 
-    process_verify(verify_args)?;
+    if args.withdraw_bundlr {
+        if config_data.upload_method == UploadMethod::Bundlr {
+            println!("\n{} sugar bundlr withdraw\n", style(">>>").magenta());
+
+            let bundlr_args = BundlrArgs {
+                keypair: args.keypair.clone(),
+                rpc_url: args.rpc_url.clone(),
+                action: BundlrAction::Withdraw,
+            };
+
+            process_bundlr(bundlr_args).await?;
+        } else {
+            println!(
+                "\n{} sugar bundlr withdraw (skipping, upload method is not Bundlr)",
+                style(">>>").magenta()
+            );
+        }
+    }
 
     Ok(())
 }
@@ -1,30 +1,71 @@
 pub mod airdrop;
+pub mod analytics;
+pub mod ata;
+pub mod audit;
+pub mod autotask;
 pub mod bundlr;
 pub mod cache;
+pub mod cache_encryption;
 pub mod candy_machine;
 pub mod cli;
 pub mod collections;
 pub mod common;
+pub mod compat;
+pub mod completions;
 pub mod config;
+pub mod confirmation;
 pub mod constants;
+pub mod core_candy_machine;
+pub mod costs;
 pub mod create_config;
+pub mod das;
+pub mod decode;
 pub mod deploy;
+pub mod dry_run;
+pub mod editions;
 pub mod errors;
+pub mod fairness;
+pub mod fee_payers;
 pub mod freeze;
+pub mod generate;
 pub mod guard;
 pub mod hash;
+pub mod history;
+pub mod hooks;
+pub mod identity;
+pub mod import;
+pub mod journal;
+pub mod keypair;
 pub mod launch;
+pub mod localnet;
+pub mod lock;
+pub mod manifest;
+pub mod metadata;
+pub mod migrate;
 pub mod mint;
+pub mod palette;
 pub mod parse;
 pub mod pdas;
+pub mod preflight;
 pub mod program_errors;
+pub mod rarity;
+pub mod rate_limit;
+pub mod retry;
 pub mod reveal;
+pub mod rotate;
+pub mod self_update;
 pub mod setup;
+pub mod shdw;
 pub mod show;
 pub mod sign;
+pub mod snapshot;
+pub mod thumbnails;
+pub mod token;
 pub mod update;
+pub mod update_metadata;
 pub mod upload;
 pub mod utils;
 pub mod validate;
 pub mod verify;
 pub mod withdraw;
+pub mod workspace;
@@ -0,0 +1,101 @@
+use std::process::Command;
+
+use console::style;
+
+use crate::{
+    candy_machine::CANDY_MACHINE_ID,
+    common::*,
+    config::{LOCALNET_PROFILE, LOCALNET_RPC_URL},
+};
+
+/// Executable of the local validator.
+const TEST_VALIDATOR: &str = "solana-test-validator";
+
+pub struct LocalnetSetupArgs {
+    pub url: String,
+    pub ledger: Option<String>,
+    pub reset: bool,
+    pub run: bool,
+}
+
+/// Programs that sugar interacts with, which must be cloned to the local validator.
+pub fn localnet_programs() -> Vec<(&'static str, Pubkey)> {
+    vec![
+        ("Candy Machine Core", CANDY_MACHINE_ID),
+        ("Candy Guard", mpl_candy_guard::ID),
+        ("Token Metadata", mpl_token_metadata::ID),
+        ("Token Auth Rules", mpl_token_auth_rules::ID),
+    ]
+}
+
+/// Arguments of `solana-test-validator` to clone the programs from the cluster `url`.
+pub fn validator_args(url: &str, ledger: Option<&str>, reset: bool) -> Vec<String> {
+    let mut args = vec!["--url".to_string(), url.to_string()];
+
+    for (_, program_id) in localnet_programs() {
+        args.push("--clone".to_string());
+        args.push(program_id.to_string());
+    }
+
+    if let Some(ledger) = ledger {
+        args.push("--ledger".to_string());
+        args.push(ledger.to_string());
+    }
+
+    if reset {
+        args.push("--reset".to_string());
+    }
+
+    args
+}
+
+pub fn process_localnet_setup(args: LocalnetSetupArgs) -> Result<()> {
+    let validator_args = validator_args(&args.url, args.ledger.as_deref(), args.reset);
+
+    println!("{}", style("Programs cloned from the cluster:").bold());
+    for (name, program_id) in localnet_programs() {
+        println!("  {:<20} {}", name, program_id);
+    }
+
+    println!(
+        "\n{}\n  {} {}",
+        style("Validator command:").bold(),
+        TEST_VALIDATOR,
+        validator_args.join(" ")
+    );
+
+    println!(
+        "\n{}\n  sugar <command> --profile {}  (RPC: {})",
+        style("Point sugar at the local validator with:").bold(),
+        LOCALNET_PROFILE,
+        LOCALNET_RPC_URL
+    );
+
+    if args.run {
+        println!("\n{}Starting {}...\n", LAUNCH_EMOJI, TEST_VALIDATOR);
+
+        let status = Command::new(TEST_VALIDATOR)
+            .args(&validator_args)
+            .status()
+            .map_err(|e| anyhow!("Failed to run {}: {}", TEST_VALIDATOR, e))?;
+
+        if !status.success() {
+            return Err(anyhow!("{} exited with {}", TEST_VALIDATOR, status));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_validator_args_clone_programs() {
+    let args = validator_args("mainnet-beta", Some("test-ledger"), true);
+
+    assert_eq!(args[..2], ["--url", "mainnet-beta"]);
+    assert_eq!(args.iter().filter(|arg| *arg == "--clone").count(), 4);
+    assert!(args.contains(&mpl_candy_guard::ID.to_string()));
+    assert_eq!(
+        args[args.len() - 3..],
+        ["--ledger", "test-ledger", "--reset"]
+    );
+}
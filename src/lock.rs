@@ -0,0 +1,153 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use anyhow::{anyhow, Result};
+use console::style;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::dry_run::is_dry_run;
+
+static FORCE_UNLOCK: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    /// Lock files created by this process, removed when it finishes.
+    static ref HELD_LOCKS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+}
+
+/// Process holding the lock of a file, written to the lock file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LockHolder {
+    pid: u32,
+    command: String,
+    since: String,
+}
+
+impl LockHolder {
+    fn current() -> Self {
+        LockHolder {
+            pid: std::process::id(),
+            command: std::env::args().skip(1).collect::<Vec<String>>().join(" "),
+            since: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Whether the holder is no longer running; only known on Linux, where the other
+    /// platforms always report a running holder.
+    fn is_stale(&self) -> bool {
+        cfg!(target_os = "linux") && !Path::new("/proc").join(self.pid.to_string()).exists()
+    }
+}
+
+/// Removes the lock files left by other processes, based on the `--force-unlock` global
+/// flag.
+pub fn set_force_unlock(force_unlock: bool) {
+    FORCE_UNLOCK.store(force_unlock, Ordering::SeqCst);
+}
+
+/// Path of the lock file of a file (e.g. `cache.json.lock`).
+pub fn lock_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".lock");
+    path.with_file_name(file_name)
+}
+
+/// Acquires the advisory lock of a file for the rest of the process, so another sugar
+/// process cannot write it at the same time. Acquiring a lock already held by this
+/// process succeeds; nothing is locked in a dry run since no file is written.
+pub fn lock_file<P: AsRef<Path>>(path: P) -> Result<()> {
+    if is_dry_run() {
+        return Ok(());
+    }
+
+    let path = path.as_ref();
+    let lock = lock_path(&std::env::current_dir()?.join(path));
+    let mut held = HELD_LOCKS.lock().unwrap();
+
+    if held.contains(&lock) {
+        return Ok(());
+    }
+
+    // a single retry after removing a stale (or forcibly unlocked) lock file
+    for _ in 0..2 {
+        match OpenOptions::new().write(true).create_new(true).open(&lock) {
+            Ok(mut file) => {
+                file.write_all(serde_json::to_string(&LockHolder::current())?.as_bytes())?;
+                held.push(lock);
+                return Ok(());
+            }
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                let holder: Option<LockHolder> = fs::read_to_string(&lock)
+                    .ok()
+                    .and_then(|content| serde_json::from_str(&content).ok());
+
+                let stale = holder.as_ref().map_or(false, LockHolder::is_stale);
+
+                if !stale && !FORCE_UNLOCK.load(Ordering::SeqCst) {
+                    let holder = match holder {
+                        Some(holder) => format!(
+                            " (pid {}, 'sugar {}' since {})",
+                            holder.pid, holder.command, holder.since
+                        ),
+                        None => String::new(),
+                    };
+
+                    return Err(anyhow!(
+                        "Another sugar process{} holds the lock on '{}'. Wait for it to \
+                        finish, or re-run with --force-unlock if it is no longer running.",
+                        holder,
+                        path.display()
+                    ));
+                }
+
+                println!(
+                    "{}",
+                    style(format!("Removing the lock file '{}'.", lock.display())).dim()
+                );
+                fs::remove_file(&lock)?;
+            }
+            Err(err) => {
+                return Err(anyhow!(
+                    "Failed to create the lock file '{}': {}",
+                    lock.display(),
+                    err
+                ))
+            }
+        }
+    }
+
+    Err(anyhow!(
+        "Failed to acquire the lock on '{}'",
+        path.display()
+    ))
+}
+
+/// Removes the lock files created by this process.
+pub fn release_locks() {
+    let mut held = HELD_LOCKS.lock().unwrap();
+
+    for lock in held.drain(..) {
+        if let Err(err) = fs::remove_file(&lock) {
+            tracing::warn!("Failed to remove lock file {}: {}", lock.display(), err);
+        }
+    }
+}
+
+#[test]
+fn test_lock_holder() {
+    assert_eq!(
+        lock_path(Path::new("project/cache.json")),
+        PathBuf::from("project/cache.json.lock")
+    );
+
+    // the current process is never stale
+    assert!(!LockHolder::current().is_stale());
+}
@@ -1,4 +1,5 @@
 use std::{
+    env,
     fs::OpenOptions,
     path::PathBuf,
     str::FromStr,
@@ -9,37 +10,100 @@ use std::{
 };
 
 use anyhow::{anyhow, Result};
-use clap::Parser;
-use console::style;
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use console::{style, user_attended};
 use sugar_cli::{
     airdrop::{process_airdrop, AirdropArgs},
+    analytics::{process_bot_tax, process_earnings, BotTaxArgs, EarningsArgs},
+    ata::{process_ata_check, process_ata_create, AtaCheckArgs, AtaCreateArgs},
+    audit::{process_audit, AuditArgs},
+    autotask::{process_autotask, AutotaskArgs},
     bundlr::{process_bundlr, BundlrArgs},
-    cli::{Cli, CollectionSubcommands, Commands, ConfigSubcommands, FreezeCommand, GuardCommand},
-    collections::{process_set_collection, SetCollectionArgs},
-    constants::{COMPLETE_EMOJI, ERROR_EMOJI},
+    cache_encryption::{
+        process_cache_decrypt, process_cache_encrypt, CacheDecryptArgs, CacheEncryptArgs,
+    },
+    cli::{
+        AnalyticsCommand, AtaCommand, CacheCommand, Cli, CollectionSubcommands, Commands,
+        ConfigSubcommands, EditionsCommand, FairnessCommand, FeePayersCommand, FreezeCommand,
+        GuardAllocationCommand, GuardCommand, KeypairCommand, LocalnetCommand, ManifestCommand,
+        MetadataCommand, ShdwCommand, TokenCommand,
+    },
+    collections::{
+        process_set_collection, process_verify_items, SetCollectionArgs, VerifyItemsArgs,
+    },
+    compat::set_program_check,
+    completions::{process_completions, CompletionsArgs},
+    config::{
+        process_config_get, process_config_set, select_profile, ConfigGetArgs, ConfigSetArgs,
+    },
+    confirmation::set_commitment,
+    constants::{COMPLETE_EMOJI, DEFAULT_CONFIG, DEFAULT_FAILURES, ERROR_EMOJI},
+    costs::{finish_cost_tracking, process_costs, set_cost_command, set_costs_file, CostsArgs},
     create_config::{process_create_config, CreateConfigArgs},
+    decode::{process_decode, DecodeArgs},
     deploy::{process_deploy, DeployArgs},
+    dry_run::{finish_dry_run, is_dry_run, set_dry_run},
+    editions::{process_editions_mint, EditionsMintArgs},
+    errors::ErrorReport,
+    fairness::{
+        process_fairness_prove, process_fairness_verify, FairnessProveArgs, FairnessVerifyArgs,
+    },
+    fee_payers::{
+        process_fee_payers_show, process_fee_payers_sweep, FeePayersShowArgs, FeePayersSweepArgs,
+    },
     freeze::{
         process_initialize, process_thaw, process_unlock_funds, InitializeArgs, ThawArgs,
         UnlockFundsArgs,
     },
+    generate::{process_generate, GenerateArgs},
     guard::{
-        process_guard_add, process_guard_remove, process_guard_show, process_guard_update,
-        process_guard_withdraw, GuardAddArgs, GuardRemoveArgs, GuardShowArgs, GuardUpdateArgs,
-        GuardWithdrawArgs,
+        process_guard_add, process_guard_allocation_init, process_guard_allocation_reset,
+        process_guard_export, process_guard_limits, process_guard_remove, process_guard_rollback,
+        process_guard_show, process_guard_unwrap, process_guard_update, process_guard_withdraw,
+        process_guard_wrap, GuardAddArgs, GuardAllocationInitArgs, GuardAllocationResetArgs,
+        GuardExportArgs, GuardLimitsArgs, GuardRemoveArgs, GuardRollbackArgs, GuardShowArgs,
+        GuardUnwrapArgs, GuardUpdateArgs, GuardWithdrawArgs, GuardWrapArgs,
     },
     hash::{process_hash, HashArgs},
+    history::{process_history, HistoryArgs},
+    identity::{load_expected_authority, set_identity_override},
+    import::{process_import, ImportArgs},
+    keypair::{process_encrypt_keypair, EncryptKeypairArgs},
     launch::{process_launch, LaunchArgs},
-    mint::{process_mint, MintArgs},
+    localnet::{process_localnet_setup, LocalnetSetupArgs},
+    lock::{release_locks, set_force_unlock},
+    manifest::{process_manifest_export, ManifestExportArgs},
+    metadata::{process_metadata_generate, MetadataGenerateArgs},
+    migrate::{process_migrate, MigrateArgs},
+    mint::{process_mint, GuardOptions, MintArgs},
+    palette::select_workflow,
     parse::parse_sugar_errors,
+    preflight::{process_preflight, PreflightArgs},
+    rarity::{process_rarity, RarityArgs},
+    rate_limit::set_rate_limit,
+    retry::{process_retry, RetryArgs},
     reveal::{process_reveal, RevealArgs},
+    rotate::{process_rotate, RotateArgs},
+    self_update::{process_self_update, SelfUpdateArgs},
+    shdw::{
+        process_shdw_list, process_shdw_plan, process_shdw_reclaim, ShdwListArgs, ShdwPlanArgs,
+        ShdwReclaimArgs,
+    },
     show::{process_show, ShowArgs},
     sign::{process_sign, SignArgs},
-    update::{process_set_token_stardard, process_update, SetTokenStandardArgs, UpdateArgs},
-    upload::{process_upload, UploadArgs},
+    snapshot::{process_snapshot, SnapshotArgs},
+    thumbnails::{process_thumbnails, ThumbnailsArgs},
+    token::{process_token_create, TokenCreateArgs},
+    update::{
+        process_set_token_stardard, process_update, SetTokenStandardArgs, UpdateArgs, UpdateFields,
+    },
+    update_metadata::{process_update_metadata, UpdateMetadataArgs},
+    upload::{process_upload, register_storages, UploadArgs},
+    utils::{set_airdrop_if_needed, set_interaction_mode},
     validate::{process_validate, ValidateArgs},
     verify::{process_verify, VerifyArgs},
     withdraw::{process_withdraw, WithdrawArgs},
+    workspace::{process_deploy_all, process_show_all, DeployAllArgs, ShowAllArgs},
 };
 use tracing::subscriber::set_global_default;
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
@@ -77,7 +141,25 @@ fn setup_logging(level: Option<EnvFilter>) -> Result<()> {
 
 #[tokio::main]
 async fn main() {
-    match run().await {
+    let result = run().await;
+    release_locks();
+
+    // a dry run reports what the command would send instead of the result
+    let result = if is_dry_run() {
+        finish_dry_run(result)
+    } else {
+        result
+    };
+
+    // costs are recorded whether the command succeeded or not
+    if let Err(err) = finish_cost_tracking() {
+        println!(
+            "\n{}",
+            style(format!("Could not record the command costs: {err}")).dim()
+        );
+    }
+
+    match result {
         Ok(()) => {
             println!(
                 "\n{}{}",
@@ -94,16 +176,53 @@ async fn main() {
                 style("Error running command (re-run needed):").red(),
                 parsed_err,
             );
+
+            // machine-readable error for scripts
+            let report = ErrorReport::new(&err, parsed_err);
+            if let Ok(json) = serde_json::to_string(&report) {
+                eprintln!("{json}");
+            }
+
             // finished the program with an error code to the OS
-            std::process::exit(1);
+            std::process::exit(report.code);
         }
     }
 }
 
+/// Returns the name of the subcommand run, including nested subcommands (e.g. "guard add").
+fn command_name(matches: &ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+
+    while let Some((name, sub_matches)) = current.subcommand() {
+        names.push(name);
+        current = sub_matches;
+    }
+
+    names.join(" ")
+}
+
 async fn run() -> Result<()> {
     solana_logger::setup_with_default("solana=off");
 
-    let cli = Cli::parse();
+    // without arguments, newcomers pick one of the common workflows from a menu
+    let matches = if env::args_os().len() == 1 && user_attended() {
+        match select_workflow()? {
+            Some(args) => Cli::command().get_matches_from(args),
+            None => std::process::exit(0),
+        }
+    } else {
+        Cli::command().get_matches()
+    };
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    // the script is written to stdout, so nothing else can be printed
+    if let Commands::Completions { shell } = cli.command {
+        process_completions(CompletionsArgs { shell })?;
+        std::process::exit(0);
+    }
+
+    set_cost_command(&command_name(&matches));
 
     let log_level_error: Result<()> = Err(anyhow!(
         "Invalid log level: {:?}.\n Valid levels are: trace, debug, info, warn, error.",
@@ -122,6 +241,23 @@ async fn run() -> Result<()> {
 
     tracing::info!("Lend me some sugar, I am your neighbor.");
 
+    set_interaction_mode(cli.yes, cli.no_input);
+    set_airdrop_if_needed(cli.airdrop_if_needed);
+    set_rate_limit(cli.rpc_provider, cli.rps, &cli.rpc_weights)?;
+    set_program_check(cli.check_programs);
+    set_commitment(cli.commitment);
+    set_dry_run(cli.dry_run);
+    set_identity_override(cli.i_know_what_im_doing);
+    set_force_unlock(cli.force_unlock);
+    set_costs_file(&cli.costs_file);
+    register_storages()?;
+
+    let config_path = cli.command.config_path().unwrap_or(DEFAULT_CONFIG);
+    if let Some(ref profile) = cli.profile {
+        select_profile(profile, config_path)?;
+    }
+    load_expected_authority(config_path)?;
+
     let interrupted = Arc::new(AtomicBool::new(true));
     let ctrl_handler = interrupted.clone();
 
@@ -133,6 +269,7 @@ async fn run() -> Result<()> {
                 ERROR_EMOJI,
                 style("Error running command (re-run needed):").red(),
             );
+            release_locks();
             // finished the program with an error code to the OS
             std::process::exit(1);
         }
@@ -142,6 +279,105 @@ async fn run() -> Result<()> {
     .expect("Error setting Ctrl-C handler");
 
     match cli.command {
+        Commands::Analytics { command } => match command {
+            AnalyticsCommand::BotTax {
+                keypair,
+                rpc_url,
+                cache,
+                candy_machine,
+                start,
+                end,
+            } => process_bot_tax(BotTaxArgs {
+                keypair,
+                rpc_url,
+                cache,
+                candy_machine,
+                start,
+                end,
+            })?,
+            AnalyticsCommand::Earnings {
+                keypair,
+                rpc_url,
+                cache,
+                candy_guard,
+                start,
+                end,
+                csv,
+            } => process_earnings(EarningsArgs {
+                keypair,
+                rpc_url,
+                cache,
+                candy_guard,
+                start,
+                end,
+                csv,
+            })?,
+        },
+        Commands::Ata { command } => match command {
+            AtaCommand::Create {
+                keypair,
+                rpc_url,
+                mint,
+                owner,
+            } => process_ata_create(AtaCreateArgs {
+                keypair,
+                rpc_url,
+                mint,
+                owner,
+            })?,
+            AtaCommand::Check {
+                keypair,
+                rpc_url,
+                config,
+            } => process_ata_check(AtaCheckArgs {
+                keypair,
+                rpc_url,
+                config,
+            })?,
+        },
+        Commands::Audit {
+            keypair,
+            rpc_url,
+            cache,
+            candy_machine,
+            timeout,
+        } => process_audit(AuditArgs {
+            keypair,
+            rpc_url,
+            cache,
+            candy_machine,
+            timeout,
+        })?,
+        Commands::Autotask {
+            keypair,
+            rpc_url,
+            cache,
+            config,
+            candy_machine,
+            candy_guard,
+            label,
+            unwrap,
+            thaw,
+            withdraw,
+            webhook,
+            interval,
+        } => {
+            process_autotask(AutotaskArgs {
+                keypair,
+                rpc_url,
+                cache,
+                config,
+                candy_machine,
+                candy_guard,
+                label,
+                unwrap,
+                thaw,
+                withdraw,
+                webhook,
+                interval,
+            })
+            .await?
+        }
         Commands::Bundlr {
             keypair,
             rpc_url,
@@ -154,6 +390,12 @@ async fn run() -> Result<()> {
             })
             .await?
         }
+        Commands::Cache { command } => match command {
+            CacheCommand::Encrypt { cache, keyfile } => {
+                process_cache_encrypt(CacheEncryptArgs { cache, keyfile })?
+            }
+            CacheCommand::Decrypt { cache } => process_cache_decrypt(CacheDecryptArgs { cache })?,
+        },
         Commands::Collection { command } => match command {
             CollectionSubcommands::Set {
                 keypair,
@@ -170,6 +412,21 @@ async fn run() -> Result<()> {
                 config,
                 candy_machine,
             })?,
+            CollectionSubcommands::VerifyItems {
+                keypair,
+                rpc_url,
+                cache,
+                hash_list,
+                collection_mint,
+                batch_size,
+            } => process_verify_items(VerifyItemsArgs {
+                keypair,
+                rpc_url,
+                cache,
+                hash_list,
+                collection_mint,
+                batch_size,
+            })?,
         },
         Commands::Config { command } => match command {
             ConfigSubcommands::Create {
@@ -177,11 +434,13 @@ async fn run() -> Result<()> {
                 keypair,
                 rpc_url,
                 assets_dir,
+                template,
             } => process_create_config(CreateConfigArgs {
                 config,
                 keypair,
                 rpc_url,
                 assets_dir,
+                template,
             })?,
             ConfigSubcommands::Update {
                 config,
@@ -190,6 +449,10 @@ async fn run() -> Result<()> {
                 cache,
                 new_authority,
                 candy_machine,
+                price,
+                go_live_date,
+                items_available,
+                symbol,
             } => process_update(UpdateArgs {
                 config,
                 keypair,
@@ -197,6 +460,25 @@ async fn run() -> Result<()> {
                 cache,
                 new_authority,
                 candy_machine,
+                fields: UpdateFields {
+                    price,
+                    go_live_date,
+                    items_available,
+                    symbol,
+                },
+            })?,
+            ConfigSubcommands::Get { config, path } => {
+                process_config_get(ConfigGetArgs { config, path })?
+            }
+            ConfigSubcommands::Set {
+                config,
+                path: Some(path),
+                value,
+                ..
+            } => process_config_set(ConfigSetArgs {
+                config,
+                path,
+                value: value.expect("clap requires a value with the path"),
             })?,
             ConfigSubcommands::Set {
                 keypair,
@@ -205,6 +487,7 @@ async fn run() -> Result<()> {
                 token_standard,
                 candy_machine,
                 rule_set,
+                ..
             } => process_set_token_stardard(SetTokenStandardArgs {
                 keypair,
                 rpc_url,
@@ -214,23 +497,92 @@ async fn run() -> Result<()> {
                 rule_set,
             })?,
         },
+        Commands::Completions { .. } => unreachable!("completions are generated before"),
+        Commands::Costs { command } => process_costs(CostsArgs {
+            file: cli.costs_file,
+            command,
+        })?,
+        Commands::Decode {
+            keypair,
+            rpc_url,
+            account,
+        } => process_decode(DecodeArgs {
+            keypair,
+            rpc_url,
+            account,
+        })?,
         Commands::Deploy {
             config,
             keypair,
             rpc_url,
             cache,
             collection_mint,
+            all,
+            workspace,
+            indices,
+            fee_payers,
+            shuffle,
+            append,
         } => {
-            process_deploy(DeployArgs {
+            if all {
+                process_deploy_all(DeployAllArgs {
+                    workspace,
+                    keypair,
+                    rpc_url,
+                    interrupted: interrupted.clone(),
+                })
+                .await?
+            } else {
+                process_deploy(DeployArgs {
+                    config,
+                    keypair,
+                    rpc_url,
+                    cache,
+                    interrupted: interrupted.clone(),
+                    collection_mint,
+                    indices,
+                    fee_payers,
+                    shuffle,
+                    append,
+                    failures: DEFAULT_FAILURES.to_string(),
+                })
+                .await?
+            }
+        }
+        Commands::Fairness { command } => match command {
+            FairnessCommand::Prove {
+                config,
+                cache,
+                proof,
+            } => process_fairness_prove(FairnessProveArgs {
                 config,
+                cache,
+                proof,
+            })?,
+            FairnessCommand::Verify {
                 keypair,
                 rpc_url,
-                cache,
-                interrupted: interrupted.clone(),
-                collection_mint,
-            })
-            .await?
-        }
+                proof,
+                commitment,
+                candy_machine,
+                report,
+            } => process_fairness_verify(FairnessVerifyArgs {
+                keypair,
+                rpc_url,
+                proof,
+                commitment,
+                candy_machine,
+                report,
+            })?,
+        },
+        Commands::FeePayers { command } => match command {
+            FeePayersCommand::Show { keypair, rpc_url } => {
+                process_fee_payers_show(FeePayersShowArgs { keypair, rpc_url })?
+            }
+            FeePayersCommand::Sweep { keypair, rpc_url } => {
+                process_fee_payers_sweep(FeePayersSweepArgs { keypair, rpc_url })?
+            }
+        },
         Commands::Freeze { command } => match command {
             FreezeCommand::Initialize {
                 keypair,
@@ -305,6 +657,19 @@ async fn run() -> Result<()> {
                 token,
             })?,
         },
+        Commands::Generate {
+            layers,
+            traits,
+            output,
+            number,
+            seed,
+        } => process_generate(GenerateArgs {
+            layers,
+            traits,
+            output,
+            number,
+            seed,
+        })?,
         Commands::Guard { command } => match command {
             GuardCommand::Add {
                 keypair,
@@ -313,6 +678,7 @@ async fn run() -> Result<()> {
                 config,
                 candy_machine,
                 candy_guard,
+                from_file,
             } => process_guard_add(GuardAddArgs {
                 keypair,
                 rpc_url,
@@ -320,6 +686,69 @@ async fn run() -> Result<()> {
                 config,
                 candy_machine,
                 candy_guard,
+                from_file,
+            })?,
+            GuardCommand::Allocation { command } => match command {
+                GuardAllocationCommand::Init {
+                    keypair,
+                    rpc_url,
+                    cache,
+                    candy_machine,
+                    candy_guard,
+                    group,
+                } => process_guard_allocation_init(GuardAllocationInitArgs {
+                    keypair,
+                    rpc_url,
+                    cache,
+                    candy_machine,
+                    candy_guard,
+                    label: group,
+                })?,
+                GuardAllocationCommand::Reset {
+                    keypair,
+                    rpc_url,
+                    cache,
+                    config,
+                    candy_machine,
+                    candy_guard,
+                    group,
+                } => process_guard_allocation_reset(GuardAllocationResetArgs {
+                    keypair,
+                    rpc_url,
+                    cache,
+                    config,
+                    candy_machine,
+                    candy_guard,
+                    label: group,
+                })?,
+            },
+            GuardCommand::Export {
+                keypair,
+                rpc_url,
+                cache,
+                candy_guard,
+                output,
+            } => process_guard_export(GuardExportArgs {
+                keypair,
+                rpc_url,
+                cache,
+                candy_guard,
+                output,
+            })?,
+            GuardCommand::Limits {
+                wallet,
+                keypair,
+                rpc_url,
+                cache,
+                candy_machine,
+                candy_guard,
+            } => process_guard_limits(GuardLimitsArgs {
+                keypair,
+                rpc_url,
+                cache,
+                wallet,
+                candy_machine,
+                candy_guard,
             })?,
             GuardCommand::Remove {
                 keypair,
@@ -334,6 +763,43 @@ async fn run() -> Result<()> {
                 candy_machine,
                 candy_guard,
             })?,
+            GuardCommand::Wrap {
+                keypair,
+                rpc_url,
+                cache,
+                candy_machine,
+                candy_guard,
+            } => process_guard_wrap(GuardWrapArgs {
+                keypair,
+                rpc_url,
+                cache,
+                candy_machine,
+                candy_guard,
+            })?,
+            GuardCommand::Unwrap {
+                keypair,
+                rpc_url,
+                cache,
+                candy_machine,
+                candy_guard,
+            } => process_guard_unwrap(GuardUnwrapArgs {
+                keypair,
+                rpc_url,
+                cache,
+                candy_machine,
+                candy_guard,
+            })?,
+            GuardCommand::Rollback {
+                keypair,
+                rpc_url,
+                cache,
+                candy_guard,
+            } => process_guard_rollback(GuardRollbackArgs {
+                keypair,
+                rpc_url,
+                cache,
+                candy_guard,
+            })?,
             GuardCommand::Show {
                 keypair,
                 rpc_url,
@@ -351,12 +817,14 @@ async fn run() -> Result<()> {
                 cache,
                 config,
                 candy_guard,
+                from_file,
             } => process_guard_update(GuardUpdateArgs {
                 keypair,
                 rpc_url,
                 cache,
                 config,
                 candy_guard,
+                from_file,
             })?,
             GuardCommand::Withdraw {
                 keypair,
@@ -374,11 +842,50 @@ async fn run() -> Result<()> {
             config,
             cache,
             compare,
+            update,
+            keypair,
+            rpc_url,
+            candy_machine,
         } => process_hash(HashArgs {
             config,
             cache,
             compare,
+            keypair,
+            rpc_url,
+            candy_machine,
+            update,
+        })?,
+        Commands::History {
+            keypair,
+            rpc_url,
+            cache,
+            candy_machine,
+            candy_guard,
+            output,
+        } => process_history(HistoryArgs {
+            keypair,
+            rpc_url,
+            cache,
+            candy_machine,
+            candy_guard,
+            output,
+        })?,
+        Commands::Import {
+            candy_machine,
+            keypair,
+            rpc_url,
+            cache,
+        } => process_import(ImportArgs {
+            keypair,
+            rpc_url,
+            cache,
+            candy_machine,
         })?,
+        Commands::Keypair { command } => match command {
+            KeypairCommand::Encrypt { keypair, output } => {
+                process_encrypt_keypair(EncryptKeypairArgs { keypair, output })?
+            }
+        },
         Commands::Launch {
             assets_dir,
             config,
@@ -387,6 +894,12 @@ async fn run() -> Result<()> {
             cache,
             strict,
             skip_collection_prompt,
+            skip_validate,
+            skip_verify,
+            collection_mint,
+            hooks,
+            stop_on_warning,
+            withdraw_bundlr,
         } => {
             process_launch(LaunchArgs {
                 assets_dir,
@@ -396,6 +909,74 @@ async fn run() -> Result<()> {
                 cache,
                 strict,
                 skip_collection_prompt,
+                skip_validate,
+                skip_verify,
+                collection_mint,
+                hooks,
+                stop_on_warning,
+                withdraw_bundlr,
+                interrupted: interrupted.clone(),
+            })
+            .await?
+        }
+        Commands::Localnet { command } => match command {
+            LocalnetCommand::Setup {
+                url,
+                ledger,
+                reset,
+                run,
+            } => process_localnet_setup(LocalnetSetupArgs {
+                url,
+                ledger,
+                reset,
+                run,
+            })?,
+        },
+        Commands::Manifest { command } => match command {
+            ManifestCommand::Export {
+                assets_dir,
+                config,
+                cache,
+                output,
+            } => process_manifest_export(ManifestExportArgs {
+                assets_dir,
+                config,
+                cache,
+                output,
+            })?,
+        },
+        Commands::Metadata { command } => match command {
+            MetadataCommand::Generate {
+                template,
+                count,
+                output,
+                name,
+                attributes,
+                overwrite,
+            } => process_metadata_generate(MetadataGenerateArgs {
+                template,
+                count,
+                output,
+                name,
+                attributes,
+                overwrite,
+            })?,
+        },
+        Commands::Migrate {
+            candy_machine,
+            keypair,
+            rpc_url,
+            config,
+            cache,
+            deploy,
+        } => {
+            process_migrate(MigrateArgs {
+                keypair,
+                rpc_url,
+                config,
+                cache,
+                candy_machine,
+                deploy,
                 interrupted: interrupted.clone(),
             })
             .await?
@@ -404,17 +985,33 @@ async fn run() -> Result<()> {
             keypair,
             rpc_url,
             cache,
+            config,
             number,
             receiver,
             candy_machine,
+            receipts,
+            group,
+            allowlist,
+            freeze_period,
+            nft_mint,
+            signer_keypair,
         } => {
             process_mint(MintArgs {
                 keypair,
                 rpc_url,
                 cache,
+                config,
                 number,
                 receiver,
                 candy_machine,
+                receipts,
+                guard_options: GuardOptions {
+                    label: group,
+                    allowlist,
+                    freeze_period,
+                    nft_mint,
+                    signer_keypair,
+                },
             })
             .await?
         }
@@ -424,6 +1021,8 @@ async fn run() -> Result<()> {
             cache,
             candy_machine,
             airdrop_list,
+            receipts,
+            fee_payers,
         } => {
             process_airdrop(AirdropArgs {
                 keypair,
@@ -431,6 +1030,66 @@ async fn run() -> Result<()> {
                 cache,
                 candy_machine,
                 airdrop_list,
+                receipts,
+                fee_payers,
+            })
+            .await?
+        }
+        Commands::Editions { command } => match command {
+            EditionsCommand::Mint {
+                master,
+                count,
+                recipients,
+                keypair,
+                rpc_url,
+                receipts,
+            } => {
+                process_editions_mint(EditionsMintArgs {
+                    keypair,
+                    rpc_url,
+                    master,
+                    count,
+                    recipients,
+                    receipts,
+                })
+                .await?
+            }
+        },
+        Commands::Preflight {
+            keypair,
+            rpc_url,
+            config,
+            cache,
+        } => process_preflight(PreflightArgs {
+            keypair,
+            rpc_url,
+            config,
+            cache,
+        })?,
+        Commands::Rarity {
+            assets_dir,
+            cache,
+            from_cache,
+            output,
+        } => {
+            process_rarity(RarityArgs {
+                assets_dir,
+                cache,
+                from_cache,
+                output,
+            })
+            .await?
+        }
+        Commands::Retry {
+            from,
+            keypair,
+            rpc_url,
+        } => {
+            process_retry(RetryArgs {
+                from,
+                keypair,
+                rpc_url,
+                interrupted: interrupted.clone(),
             })
             .await?
         }
@@ -440,6 +1099,8 @@ async fn run() -> Result<()> {
             cache,
             config,
             timeout,
+            indices,
+            sign,
         } => {
             process_reveal(RevealArgs {
                 keypair,
@@ -447,28 +1108,184 @@ async fn run() -> Result<()> {
                 cache,
                 config,
                 timeout,
+                indices,
+                sign,
             })
             .await?
         }
-        Commands::Show {
+        Commands::Rotate {
             keypair,
             rpc_url,
             cache,
             candy_machine,
-            unminted,
-        } => process_show(ShowArgs {
+            new_update_authority,
+            rule_set,
+            rate_limit,
+            audit_log,
+            timeout,
+        } => process_rotate(RotateArgs {
+            keypair,
+            rpc_url,
+            cache,
+            candy_machine,
+            new_update_authority,
+            rule_set,
+            rate_limit,
+            audit_log,
+            timeout,
+        })?,
+        Commands::SelfUpdate {
+            channel,
+            check,
+            force,
+            public_key,
+        } => {
+            process_self_update(SelfUpdateArgs {
+                channel,
+                check,
+                force,
+                public_key,
+            })
+            .await?
+        }
+        Commands::Shdw { command } => match command {
+            ShdwCommand::List { keypair, rpc_url } => {
+                process_shdw_list(ShdwListArgs { keypair, rpc_url })?
+            }
+            ShdwCommand::Plan {
+                assets_dir,
+                config,
+                keypair,
+                rpc_url,
+                margin,
+                expand,
+            } => {
+                process_shdw_plan(ShdwPlanArgs {
+                    assets_dir,
+                    config,
+                    keypair,
+                    rpc_url,
+                    margin,
+                    expand,
+                })
+                .await?
+            }
+            ShdwCommand::Reclaim {
+                config,
+                keypair,
+                rpc_url,
+                margin,
+            } => {
+                process_shdw_reclaim(ShdwReclaimArgs {
+                    config,
+                    keypair,
+                    rpc_url,
+                    margin,
+                })
+                .await?
+            }
+        },
+        Commands::Show {
             keypair,
             rpc_url,
             cache,
             candy_machine,
             unminted,
+            guards,
+            config,
+            diff,
+            all,
+            workspace,
+        } => {
+            if all {
+                process_show_all(ShowAllArgs {
+                    workspace,
+                    keypair,
+                    rpc_url,
+                })?
+            } else {
+                process_show(ShowArgs {
+                    keypair,
+                    rpc_url,
+                    cache,
+                    candy_machine,
+                    unminted,
+                    guards,
+                    config,
+                    diff,
+                })?
+            }
+        }
+        Commands::Thumbnails {
+            assets_dir,
+            template,
+            ffmpeg,
+        } => process_thumbnails(ThumbnailsArgs {
+            assets_dir,
+            template,
+            ffmpeg,
         })?,
+        Commands::Token { command } => match command {
+            TokenCommand::Create {
+                keypair,
+                rpc_url,
+                config,
+                decimals,
+                supply,
+                metadata,
+                guard,
+                group,
+                amount,
+            } => process_token_create(TokenCreateArgs {
+                keypair,
+                rpc_url,
+                config,
+                decimals,
+                supply,
+                metadata,
+                guard,
+                group,
+                amount,
+            })?,
+        },
+        Commands::UpdateMetadata {
+            keypair,
+            rpc_url,
+            cache,
+            config,
+            candy_machine,
+            from_cache,
+            mapping,
+            update_creators,
+            batch_size,
+            timeout,
+        } => {
+            process_update_metadata(UpdateMetadataArgs {
+                keypair,
+                rpc_url,
+                cache,
+                config,
+                candy_machine,
+                from_cache,
+                mapping,
+                update_creators,
+                batch_size,
+                timeout,
+            })
+            .await?
+        }
         Commands::Upload {
             assets_dir,
             config,
             keypair,
             rpc_url,
             cache,
+            fail_on_duplicates,
+            max_bandwidth,
+            max_retries,
+            verify,
+            verify_sample,
+            indices,
         } => {
             process_upload(UploadArgs {
                 assets_dir,
@@ -476,40 +1293,81 @@ async fn run() -> Result<()> {
                 keypair,
                 rpc_url,
                 cache,
+                fail_on_duplicates,
+                max_bandwidth,
+                max_retries,
+                verify,
+                verify_sample,
+                indices,
+                failures: DEFAULT_FAILURES.to_string(),
                 interrupted: interrupted.clone(),
             })
             .await?
         }
         Commands::Validate {
             assets_dir,
+            config,
+            schema,
             strict,
             skip_collection_prompt,
+            report,
+            max_errors,
         } => process_validate(ValidateArgs {
             assets_dir,
+            config,
+            schema,
             strict,
             skip_collection_prompt,
+            report,
+            max_errors,
         })?,
         Commands::Verify {
             keypair,
             rpc_url,
             cache,
-        } => process_verify(VerifyArgs {
-            keypair,
-            rpc_url,
-            cache,
-        })?,
+            repair,
+            indices,
+            links,
+            report,
+            collection,
+            fix,
+        } => {
+            process_verify(VerifyArgs {
+                keypair,
+                rpc_url,
+                cache,
+                repair,
+                indices,
+                links,
+                report,
+                collection,
+                fix,
+                interrupted: interrupted.clone(),
+            })
+            .await?
+        }
         Commands::Withdraw {
             candy_machine,
             keypair,
             rpc_url,
             list,
             authority,
+            destination,
+            min_balance,
+            created_after,
+            created_before,
+            ids_file,
         } => process_withdraw(WithdrawArgs {
             candy_machine,
             keypair,
             rpc_url,
             list,
             authority,
+            destination,
+            min_balance,
+            created_after,
+            created_before,
+            ids_file,
         })?,
         Commands::Sign {
             keypair,
@@ -527,6 +1385,19 @@ async fn run() -> Result<()> {
             })
             .await?
         }
+        Commands::Snapshot {
+            keypair,
+            rpc_url,
+            cache,
+            candy_machine,
+            output,
+        } => process_snapshot(SnapshotArgs {
+            keypair,
+            rpc_url,
+            cache,
+            candy_machine,
+            output,
+        })?,
     }
 
     Ok(())
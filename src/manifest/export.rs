@@ -0,0 +1,165 @@
+use std::{ffi::OsStr, fs};
+
+use console::style;
+use data_encoding::HEXLOWER;
+use ring::digest::{digest, SHA256};
+use serde::Serialize;
+
+use crate::{
+    cache::load_cache,
+    common::*,
+    config::get_config_data,
+    upload::{
+        get_asset_pairs, get_updated_metadata, methods::template::render_uri, MetadataOverrides,
+    },
+};
+
+/// Name of the manifest file in the output directory.
+const MANIFEST_FILE: &str = "manifest.json";
+
+pub struct ManifestExportArgs {
+    pub assets_dir: String,
+    pub config: String,
+    pub cache: String,
+    pub output: String,
+}
+
+/// File to publish to the storage and the link where it is expected.
+#[derive(Debug, Serialize)]
+pub struct ManifestEntry {
+    pub index: String,
+    #[serde(rename = "type")]
+    pub file_type: String,
+    pub source: String,
+    pub hash: String,
+    pub uri: String,
+}
+
+pub fn process_manifest_export(args: ManifestExportArgs) -> Result<()> {
+    println!(
+        "{} {}Loading cache",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let config_data = get_config_data(&args.config)?;
+    let asset_pairs = get_asset_pairs(&args.assets_dir, config_data.collection_path.as_deref())?;
+    let cache = load_cache(&args.cache, false)?;
+
+    let mut indices: Vec<&isize> = asset_pairs.keys().collect();
+    indices.sort_unstable();
+
+    println!(
+        "\n{} {}Writing manifest",
+        style("[2/2]").bold().dim(),
+        PAPER_EMOJI
+    );
+
+    // metadata files are generated with the links of the media files
+    let metadata_dir = Path::new(&args.output).join("metadata");
+    fs::create_dir_all(&metadata_dir)?;
+
+    let mut entries = Vec::new();
+    let mut stale = 0;
+
+    for index in indices {
+        let pair = &asset_pairs[index];
+        let asset_id = index.to_string();
+
+        let item = cache.items.get(&asset_id).ok_or_else(|| {
+            anyhow!(
+                "Asset {} is missing from the cache file, run 'sugar upload' first",
+                asset_id
+            )
+        })?;
+
+        if item.image_link.is_empty() || item.metadata_link.is_empty() {
+            return Err(anyhow!(
+                "Asset {} does not have links in the cache file, run 'sugar upload' first",
+                asset_id
+            ));
+        }
+
+        entries.push(ManifestEntry {
+            index: asset_id.clone(),
+            file_type: "image".to_string(),
+            source: pair.image.clone(),
+            hash: pair.image_hash.clone(),
+            uri: item.image_link.clone(),
+        });
+
+        if let (Some(animation), Some(link)) = (&pair.animation, &item.animation_link) {
+            entries.push(ManifestEntry {
+                index: asset_id.clone(),
+                file_type: "animation".to_string(),
+                source: animation.clone(),
+                hash: pair.animation_hash.clone().unwrap_or_default(),
+                uri: link.clone(),
+            });
+        }
+
+        let content = get_updated_metadata(
+            &pair.metadata,
+            &item.image_link,
+            &item.animation_link,
+            &MetadataOverrides::new(&config_data, *index),
+        )?;
+        let hash = HEXLOWER.encode(digest(&SHA256, content.as_bytes()).as_ref());
+        // the collection is always uploaded as 'collection.json'
+        let file_name = if *index == COLLECTION_INDEX {
+            "collection.json".to_string()
+        } else {
+            Path::new(&pair.metadata)
+                .file_name()
+                .and_then(OsStr::to_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        // a different link means that the metadata changed after the upload
+        if let Some(template) = &config_data.uri_template {
+            if render_uri(template, &asset_id, &file_name, &hash) != item.metadata_link {
+                stale += 1;
+                warn!(
+                    "Metadata link of asset {} does not match its content",
+                    asset_id
+                );
+            }
+        }
+
+        let source = metadata_dir.join(&file_name);
+        fs::write(&source, content)?;
+
+        entries.push(ManifestEntry {
+            index: asset_id,
+            file_type: "metadata".to_string(),
+            source: source.display().to_string(),
+            hash,
+            uri: item.metadata_link.clone(),
+        });
+    }
+
+    let manifest = Path::new(&args.output).join(MANIFEST_FILE);
+    serde_json::to_writer_pretty(File::create(&manifest)?, &entries)?;
+
+    println!(
+        "\nManifest with {} file(s) written to {}",
+        entries.len(),
+        manifest.display()
+    );
+
+    if stale > 0 {
+        println!(
+            "{}{}",
+            WARNING_EMOJI,
+            style(format!(
+                "{} metadata link(s) do not match the current files, run 'sugar upload' again.",
+                stale
+            ))
+            .yellow()
+            .bold()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,314 @@
+use std::{collections::BTreeMap, fs};
+
+use console::style;
+
+use crate::{
+    common::*,
+    utils::*,
+    validate::format::{Attribute, Metadata},
+};
+
+/// CSV columns that are used as template variables instead of attributes.
+const RESERVED_COLUMNS: [&str; 2] = ["index", "name"];
+
+pub struct MetadataGenerateArgs {
+    pub template: String,
+    pub count: u64,
+    pub output: String,
+    pub name: Option<String>,
+    pub attributes: Option<String>,
+    pub overwrite: bool,
+}
+
+/// Row of the attributes CSV file.
+#[derive(Debug, Default, Clone)]
+pub struct AttributeRow {
+    pub name: Option<String>,
+    pub attributes: Vec<(String, String)>,
+}
+
+pub fn process_metadata_generate(args: MetadataGenerateArgs) -> Result<()> {
+    println!(
+        "{} {}Loading template",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let template: Value = serde_json::from_reader(
+        File::open(&args.template)
+            .map_err(|e| anyhow!("Failed to open template '{}': {}", args.template, e))?,
+    )
+    .map_err(|e| anyhow!("Failed to parse template '{}': {}", args.template, e))?;
+
+    let rows = if let Some(attributes) = &args.attributes {
+        let rows = load_attribute_rows(attributes, args.count)?;
+        println!(
+            "Loaded attributes of {} items from '{}'",
+            rows.len(),
+            attributes
+        );
+        rows
+    } else {
+        HashMap::new()
+    };
+
+    let output = Path::new(&args.output);
+    fs::create_dir_all(output)?;
+
+    if !args.overwrite {
+        if let Some(index) =
+            (0..args.count).find(|index| output.join(format!("{index}.json")).exists())
+        {
+            return Err(anyhow!(
+                "File '{}' already exists, use --overwrite to replace the existing metadata",
+                output.join(format!("{index}.json")).display()
+            ));
+        }
+    }
+
+    println!(
+        "\n{} {}Generating {} metadata files",
+        style("[2/2]").bold().dim(),
+        PAPER_EMOJI,
+        args.count
+    );
+
+    let pb = progress_bar_with_style(args.count);
+    let mut errors = Vec::new();
+
+    for index in 0..args.count {
+        let row = rows.get(&index).cloned().unwrap_or_default();
+        let name = row
+            .name
+            .clone()
+            .or_else(|| {
+                args.name
+                    .as_ref()
+                    .map(|name| format!("{} #{}", name, index + 1))
+            })
+            .unwrap_or_default();
+
+        let mut variables = BTreeMap::new();
+        variables.insert("index".to_string(), index.to_string());
+        variables.insert("number".to_string(), (index + 1).to_string());
+        variables.insert("name".to_string(), name);
+        for (column, value) in &row.attributes {
+            variables.insert(column.clone(), value.clone());
+        }
+
+        match render_metadata(&template, &variables, &row.attributes) {
+            Ok(metadata) => {
+                let file = File::create(output.join(format!("{index}.json")))?;
+                serde_json::to_writer_pretty(file, &metadata)?;
+            }
+            Err(error) => errors.push(format!("{index}.json: {error}")),
+        }
+
+        pb.inc(1);
+    }
+
+    pb.finish_and_clear();
+
+    if !errors.is_empty() {
+        for error in &errors {
+            println!("{} {}", ERROR_EMOJI, style(error).red());
+        }
+        return Err(anyhow!(
+            "{} of {} metadata files failed validation",
+            errors.len(),
+            args.count
+        ));
+    }
+
+    println!(
+        "\n{}Generated {} metadata files in '{}'",
+        COMPLETE_EMOJI,
+        args.count,
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Replace the variables of the template and inject the attributes, returning the
+/// validated metadata.
+pub fn render_metadata(
+    template: &Value,
+    variables: &BTreeMap<String, String>,
+    attributes: &[(String, String)],
+) -> Result<Metadata> {
+    let mut value = template.clone();
+    replace_variables(&mut value, variables);
+
+    let mut metadata: Metadata = serde_json::from_value(value)?;
+
+    if !attributes.is_empty() {
+        let existing = metadata.attributes.get_or_insert_with(Vec::new);
+
+        for (trait_type, value) in attributes {
+            existing.retain(|attribute| attribute.trait_type != *trait_type);
+            existing.push(Attribute {
+                trait_type: trait_type.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+
+    metadata.validate()?;
+
+    Ok(metadata)
+}
+
+/// Replace `{variable}` placeholders in every string of the value, in the order of the
+/// variable names; unknown placeholders are left untouched.
+fn replace_variables(value: &mut Value, variables: &BTreeMap<String, String>) {
+    match value {
+        Value::String(text) => {
+            for (variable, replacement) in variables {
+                let placeholder = format!("{{{variable}}}");
+                if text.contains(&placeholder) {
+                    *text = text.replace(&placeholder, replacement);
+                }
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| replace_variables(value, variables)),
+        Value::Object(map) => map
+            .values_mut()
+            .for_each(|value| replace_variables(value, variables)),
+        _ => (),
+    }
+}
+
+/// Load the attributes CSV file. The first line is the header: an `index` column
+/// selects the item of each row (otherwise rows are assigned in order), a `name`
+/// column sets the item name and every other column is an attribute.
+fn load_attribute_rows(path: &str, count: u64) -> Result<HashMap<u64, AttributeRow>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read attributes file '{}': {}", path, e))?;
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+
+    let header = parse_csv_line(
+        lines
+            .next()
+            .ok_or_else(|| anyhow!("Attributes file '{}' is empty", path))?,
+    );
+
+    let mut rows = HashMap::new();
+
+    for (line_number, line) in lines.enumerate() {
+        let fields = parse_csv_line(line);
+
+        if fields.len() != header.len() {
+            return Err(anyhow!(
+                "Line {} of '{}' has {} columns, expected {}",
+                line_number + 2,
+                path,
+                fields.len(),
+                header.len()
+            ));
+        }
+
+        let mut index = line_number as u64;
+        let mut row = AttributeRow::default();
+
+        for (column, field) in header.iter().zip(fields) {
+            match column.as_str() {
+                "index" => {
+                    index = field.parse().map_err(|_| {
+                        anyhow!("Invalid index '{}' on line {}", field, line_number + 2)
+                    })?;
+                }
+                "name" => row.name = Some(field).filter(|name| !name.is_empty()),
+                _ if !field.is_empty() => row.attributes.push((column.clone(), field)),
+                _ => (),
+            }
+        }
+
+        if index >= count {
+            return Err(anyhow!(
+                "Index {} on line {} is out of range, only {} items are generated",
+                index,
+                line_number + 2,
+                count
+            ));
+        }
+
+        if rows.insert(index, row).is_some() {
+            return Err(anyhow!("Duplicated index {} in '{}'", index, path));
+        }
+    }
+
+    if header
+        .iter()
+        .all(|column| RESERVED_COLUMNS.contains(&column.as_str()))
+    {
+        warn!("Attributes file '{}' has no attribute columns", path);
+    }
+
+    Ok(rows)
+}
+
+/// Split a CSV line into its fields, supporting double-quoted fields.
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+
+    fields.push(field.trim().to_string());
+    fields
+}
+
+#[test]
+fn test_template_variables_and_attributes() {
+    let template = json!({
+        "name": "{name}",
+        "description": "Item {number} of the collection",
+        "image": "{index}.png",
+        "attributes": [{ "trait_type": "Background", "value": "Blue" }],
+        "properties": {
+            "files": [{ "uri": "{index}.png", "type": "image/png" }],
+            "category": "image"
+        }
+    });
+
+    let mut variables = BTreeMap::new();
+    variables.insert("index".to_string(), "7".to_string());
+    variables.insert("number".to_string(), "8".to_string());
+    variables.insert("name".to_string(), "Sugar #8".to_string());
+
+    let attributes = vec![
+        ("Background".to_string(), "Red".to_string()),
+        ("Hat".to_string(), "Crown, gold".to_string()),
+    ];
+
+    let metadata = render_metadata(&template, &variables, &attributes).unwrap();
+
+    assert_eq!(metadata.name, "Sugar #8");
+    assert_eq!(metadata.image, "7.png");
+    assert_eq!(metadata.description, "Item 8 of the collection");
+    assert_eq!(metadata.properties.files[0].uri, "7.png");
+
+    let attributes = metadata.attributes.unwrap();
+    assert_eq!(attributes.len(), 2);
+    assert_eq!(attributes[0].value, "Red");
+
+    assert_eq!(
+        parse_csv_line(r#"3,"Crown, gold","say ""hi""""#),
+        vec!["3", "Crown, gold", "say \"hi\""]
+    );
+}
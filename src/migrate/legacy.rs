@@ -0,0 +1,173 @@
+use borsh::BorshDeserialize;
+use solana_program::pubkey;
+
+use crate::common::*;
+
+/// Candy machine v2 program id.
+pub const LEGACY_CANDY_MACHINE_ID: Pubkey = pubkey!("cndy3Z4yapfJBmL3ShUp5exZKqR3z33thTzeNMm2gRZ");
+
+/// Offset of the config lines in a v2 candy machine account.
+pub const LEGACY_CONFIG_ARRAY_START: usize = 8 + // discriminator
+    32 + // authority
+    32 + // wallet
+    33 + // token mint
+    4 + 6 + // uuid
+    8 + // price
+    8 + // items available
+    9 + // go live date
+    10 + // end settings
+    4 + MAX_SYMBOL_LENGTH + // symbol
+    2 + // seller fee basis points
+    4 + MAX_CREATOR_LIMIT * MAX_CREATOR_LEN + // creators
+    8 + // max supply
+    1 + // is mutable
+    1 + // retain authority
+    1 + // hidden settings option
+    4 + MAX_NAME_LENGTH + // hidden settings name
+    4 + MAX_URI_LENGTH + // hidden settings uri
+    32 + // hidden settings hash
+    4 + // max number of lines
+    8 + // items redeemed
+    1 + // whitelist option
+    1 + // whitelist mint mode
+    1 + // presale
+    9 + // discount price
+    32 + // whitelist mint
+    1 + 32 + 1; // gatekeeper
+
+/// Size of a config line in a v2 candy machine account.
+pub const LEGACY_CONFIG_LINE_SIZE: usize =
+    STRING_LEN_SIZE + MAX_NAME_LENGTH + STRING_LEN_SIZE + MAX_URI_LENGTH;
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct LegacyCandyMachine {
+    pub authority: Pubkey,
+    pub wallet: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    pub items_redeemed: u64,
+    pub data: LegacyCandyMachineData,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct LegacyCandyMachineData {
+    pub uuid: String,
+    pub price: u64,
+    pub symbol: String,
+    pub seller_fee_basis_points: u16,
+    pub max_supply: u64,
+    pub is_mutable: bool,
+    pub retain_authority: bool,
+    pub go_live_date: Option<i64>,
+    pub end_settings: Option<LegacyEndSettings>,
+    pub creators: Vec<LegacyCreator>,
+    pub hidden_settings: Option<LegacyHiddenSettings>,
+    pub whitelist_mint_settings: Option<LegacyWhitelistMintSettings>,
+    pub items_available: u64,
+    pub gatekeeper: Option<LegacyGatekeeperConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub enum LegacyEndSettingType {
+    Date,
+    Amount,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct LegacyEndSettings {
+    pub end_setting_type: LegacyEndSettingType,
+    pub number: u64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct LegacyCreator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct LegacyHiddenSettings {
+    pub name: String,
+    pub uri: String,
+    pub hash: [u8; 32],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub enum LegacyWhitelistMintMode {
+    BurnEveryTime,
+    NeverBurn,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct LegacyWhitelistMintSettings {
+    pub mode: LegacyWhitelistMintMode,
+    pub mint: Pubkey,
+    pub presale: bool,
+    pub discount_price: Option<u64>,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct LegacyGatekeeperConfig {
+    pub gatekeeper_network: Pubkey,
+    pub expire_on_use: bool,
+}
+
+impl LegacyCandyMachine {
+    /// Deserialize the account data of a v2 candy machine.
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < LEGACY_CONFIG_ARRAY_START {
+            return Err(anyhow!("Account is not a candy machine v2"));
+        }
+
+        // the account is zero-padded up to the config lines, so the
+        // struct is deserialized without requiring all bytes to be read
+        let mut slice = &data[8..];
+        Ok(LegacyCandyMachine::deserialize(&mut slice)?)
+    }
+}
+
+/// Read the config lines of a v2 candy machine; lines that were not loaded are `None`.
+pub fn read_legacy_config_lines(
+    data: &[u8],
+    items_available: u64,
+) -> Vec<Option<(String, String)>> {
+    let decode = |bytes: &[u8]| -> String {
+        String::from_utf8_lossy(bytes)
+            .trim_matches(char::from(0))
+            .to_string()
+    };
+
+    (0..items_available as usize)
+        .map(|index| {
+            // skips the number of lines
+            let start =
+                LEGACY_CONFIG_ARRAY_START + STRING_LEN_SIZE + index * LEGACY_CONFIG_LINE_SIZE;
+            let name_start = start + CONFIG_NAME_OFFSET;
+            let uri_start = start + CONFIG_URI_OFFSET;
+
+            if uri_start + MAX_URI_LENGTH > data.len() {
+                return None;
+            }
+
+            let name = decode(&data[name_start..name_start + MAX_NAME_LENGTH]);
+            let uri = decode(&data[uri_start..uri_start + MAX_URI_LENGTH]);
+
+            (!name.is_empty() && !uri.is_empty()).then_some((name, uri))
+        })
+        .collect()
+}
+
+/// Address of the v2 collection PDA, which stores the collection mint.
+pub fn find_legacy_collection_pda(candy_machine: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"collection", candy_machine.as_ref()],
+        &LEGACY_CANDY_MACHINE_ID,
+    )
+    .0
+}
+
+#[test]
+fn test_legacy_config_array_start() {
+    assert_eq!(LEGACY_CONFIG_ARRAY_START, 713);
+    assert_eq!(LEGACY_CONFIG_LINE_SIZE, 240);
+}
@@ -0,0 +1,5 @@
+pub mod legacy;
+pub mod process;
+
+pub use legacy::*;
+pub use process::*;
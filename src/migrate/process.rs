@@ -0,0 +1,465 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use anchor_client::solana_sdk::native_token::LAMPORTS_PER_SOL;
+use chrono::{TimeZone, Utc};
+use console::style;
+
+use crate::{
+    common::*,
+    config::{
+        AddressGate, CandyGuardData, ConfigData, Creator, EndDate, Gatekeeper, Group, GuardSet,
+        HiddenSettings, RedeemedAmount, SolPayment, StartDate, TokenBurn, TokenGate, TokenPayment,
+    },
+    deploy::{process_deploy, DeployArgs},
+    guard::{process_guard_add, GuardAddArgs},
+    lock::lock_file,
+    migrate::legacy::*,
+    utils::*,
+};
+
+/// Label of the group with the whitelist settings.
+const WHITELIST_LABEL: &str = "wl";
+
+/// Label of the group with the public mint settings.
+const PUBLIC_LABEL: &str = "public";
+
+pub struct MigrateArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub config: String,
+    pub cache: String,
+    pub candy_machine: String,
+    pub deploy: bool,
+    pub interrupted: Arc<AtomicBool>,
+}
+
+pub async fn process_migrate(args: MigrateArgs) -> Result<()> {
+    let candy_machine_id = Pubkey::from_str(&args.candy_machine)
+        .map_err(|_| anyhow!("Failed to parse candy machine id: {}", args.candy_machine))?;
+
+    for file in [&args.config, &args.cache] {
+        if Path::new(file).exists() {
+            let prompt = format!("File '{}' already exists. Overwrite it?", file);
+
+            if !confirm_with_theme(&get_dialoguer_theme(), &prompt)? {
+                return Err(anyhow!("Migration aborted"));
+            }
+        }
+    }
+
+    println!(
+        "{} {}Loading candy machine v2",
+        style(format!("[1/{}]", if args.deploy { 3 } else { 2 }))
+            .bold()
+            .dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let sugar_config = sugar_setup(args.keypair.clone(), args.rpc_url.clone())?;
+    let client = setup_client(&sugar_config)?;
+    let rpc_client = client.program(LEGACY_CANDY_MACHINE_ID).rpc();
+
+    let account = rpc_client
+        .get_account(&candy_machine_id)
+        .map_err(|_| anyhow!("Candy machine {} not found", candy_machine_id))?;
+
+    if account.owner != LEGACY_CANDY_MACHINE_ID {
+        return Err(anyhow!(
+            "Account {} is not a candy machine v2 (owner {})",
+            candy_machine_id,
+            account.owner
+        ));
+    }
+
+    let legacy = LegacyCandyMachine::from_account_data(&account.data)?;
+
+    // the collection PDA stores the collection mint (if the candy machine had one)
+    let collection_mint = rpc_client
+        .get_account_data(&find_legacy_collection_pda(&candy_machine_id))
+        .ok()
+        .filter(|data| data.len() >= 40)
+        .and_then(|data| Pubkey::try_from(&data[8..40]).ok());
+
+    println!(
+        "\n{} {}Translating settings",
+        style(format!("[2/{}]", if args.deploy { 3 } else { 2 }))
+            .bold()
+            .dim(),
+        PAPER_EMOJI
+    );
+
+    let (config_data, mut differences) = translate_settings(&legacy);
+
+    let mut cache = Cache::new();
+    cache.file_path = args.cache.clone();
+    // the cache needs every item (and a collection) to deploy the candy machine
+    let mut deployable = collection_mint.is_some();
+
+    if let Some(collection_mint) = collection_mint {
+        cache.program.collection_mint = collection_mint.to_string();
+    } else {
+        differences.push(
+            "The candy machine has no collection: add a collection item to the cache \
+            (or deploy with --collection-mint) before deploying."
+                .to_string(),
+        );
+    }
+
+    if legacy.data.hidden_settings.is_some() {
+        deployable = false;
+        differences.push(
+            "Hidden settings are migrated, but their items are not stored on-chain: \
+            run 'upload' to create the cache before deploying."
+                .to_string(),
+        );
+    } else {
+        let lines = read_legacy_config_lines(&account.data, legacy.data.items_available);
+        let missing = lines.iter().filter(|line| line.is_none()).count();
+
+        for (index, line) in lines.into_iter().enumerate() {
+            if let Some((name, uri)) = line {
+                cache.items.insert(
+                    index.to_string(),
+                    CacheItem {
+                        name,
+                        image_hash: String::new(),
+                        image_link: String::new(),
+                        metadata_hash: String::new(),
+                        metadata_link: uri,
+                        on_chain: false,
+                        animation_hash: None,
+                        animation_link: None,
+                        status: ItemStatus::Uploaded,
+                        timestamps: ItemTimestamps::default(),
+                    },
+                );
+            }
+        }
+
+        if missing > 0 {
+            deployable = false;
+            differences.push(format!(
+                "{} config line(s) were not loaded in the v2 candy machine and are missing \
+                from the cache.",
+                missing
+            ));
+        }
+    }
+
+    lock_file(&args.config)?;
+    let file = File::create(&args.config)
+        .map_err(|e| anyhow!("Failed to create config file '{}': {}", args.config, e))?;
+    serde_json::to_writer_pretty(file, &config_data)?;
+    cache.sync_file()?;
+
+    println!("Config file saved to '{}'", args.config);
+    println!("Cache file saved to '{}'", args.cache);
+
+    if !differences.is_empty() {
+        println!(
+            "\n{}{}",
+            WARNING_EMOJI,
+            style("Settings that could not be migrated automatically:")
+                .yellow()
+                .bold()
+        );
+
+        for difference in &differences {
+            println!("  - {}", difference);
+        }
+    }
+
+    if args.deploy && !deployable {
+        return Err(anyhow!(
+            "The candy machine cannot be deployed automatically: fix the differences \
+            above and run 'sugar deploy' and 'sugar guard add'"
+        ));
+    }
+
+    if args.deploy {
+        println!(
+            "\n{} {}Deploying candy machine v3\n",
+            style("[3/3]").bold().dim(),
+            LAUNCH_EMOJI
+        );
+
+        process_deploy(DeployArgs {
+            config: args.config.clone(),
+            cache: args.cache.clone(),
+            keypair: args.keypair.clone(),
+            rpc_url: args.rpc_url.clone(),
+            interrupted: args.interrupted,
+            collection_mint: collection_mint.map(|mint| mint.to_string()),
+            indices: None,
+            fee_payers: None,
+            shuffle: None,
+            append: false,
+            failures: DEFAULT_FAILURES.to_string(),
+        })
+        .await?;
+
+        println!();
+
+        process_guard_add(GuardAddArgs {
+            keypair: args.keypair,
+            rpc_url: args.rpc_url,
+            cache: args.cache,
+            config: args.config,
+            candy_machine: None,
+            candy_guard: None,
+            from_file: None,
+        })?;
+    } else {
+        println!(
+            "\nReview the config file and run 'sugar deploy' and 'sugar guard add' \
+            (or re-run with --deploy) to create the v3 candy machine."
+        );
+    }
+
+    Ok(())
+}
+
+/// Translate the settings of a v2 candy machine into a v3 config, returning the
+/// settings that could not be translated automatically.
+pub fn translate_settings(legacy: &LegacyCandyMachine) -> (ConfigData, Vec<String>) {
+    let data = &legacy.data;
+    let mut differences = Vec::new();
+
+    let format_date = |timestamp: i64| {
+        Utc.timestamp_opt(timestamp, 0)
+            .single()
+            .map(|date| date.to_rfc3339())
+            .unwrap_or_else(|| timestamp.to_string())
+    };
+
+    let payment = |price: u64, guards: &mut GuardSet| match legacy.token_mint {
+        Some(mint) => {
+            guards.token_payment = Some(TokenPayment {
+                amount: price,
+                mint,
+                destination_ata: legacy.wallet,
+            })
+        }
+        None => {
+            guards.sol_payment = Some(SolPayment {
+                value: price as f64 / LAMPORTS_PER_SOL as f64,
+                destination: legacy.wallet,
+            })
+        }
+    };
+
+    // guards shared by every group
+    let mut default = GuardSet::default();
+
+    if let Some(end_settings) = &data.end_settings {
+        match end_settings.end_setting_type {
+            LegacyEndSettingType::Date => {
+                default.end_date = Some(EndDate {
+                    date: format_date(end_settings.number as i64),
+                })
+            }
+            LegacyEndSettingType::Amount => {
+                default.redeemed_amount = Some(RedeemedAmount {
+                    maximum: end_settings.number,
+                })
+            }
+        }
+    }
+
+    if let Some(gatekeeper) = &data.gatekeeper {
+        default.gatekeeper = Some(Gatekeeper {
+            gatekeeper_network: gatekeeper.gatekeeper_network,
+            expire_on_use: gatekeeper.expire_on_use,
+        });
+    }
+
+    // public mint: without a go live date only the authority could mint
+    let mut public = GuardSet::default();
+    payment(data.price, &mut public);
+
+    match data.go_live_date {
+        Some(go_live_date) => {
+            public.start_date = Some(StartDate {
+                date: format_date(go_live_date),
+            })
+        }
+        None => {
+            public.address_gate = Some(AddressGate {
+                address: legacy.authority,
+            });
+            differences.push(
+                "No go live date: the public mint is restricted to the authority with an \
+                addressGate guard, replace it with a startDate to open the mint."
+                    .to_string(),
+            );
+        }
+    }
+
+    let guards = match &data.whitelist_mint_settings {
+        Some(whitelist) => {
+            let mut allowlist = GuardSet::default();
+            payment(
+                whitelist.discount_price.unwrap_or(data.price),
+                &mut allowlist,
+            );
+
+            match whitelist.mode {
+                LegacyWhitelistMintMode::BurnEveryTime => {
+                    allowlist.token_burn = Some(TokenBurn {
+                        amount: 1,
+                        mint: whitelist.mint,
+                    })
+                }
+                LegacyWhitelistMintMode::NeverBurn => {
+                    allowlist.token_gate = Some(TokenGate {
+                        amount: 1,
+                        mint: whitelist.mint,
+                    })
+                }
+            }
+
+            // presale allows whitelist holders to mint before the go live date
+            if !whitelist.presale {
+                allowlist.start_date = public.start_date.clone();
+                allowlist.address_gate = public.address_gate.clone();
+            }
+
+            differences.push(format!(
+                "Whitelist token {} is mapped to a '{}' group with a {} guard; use an \
+                allowList guard instead for a wallet-based allowlist.",
+                whitelist.mint,
+                WHITELIST_LABEL,
+                match whitelist.mode {
+                    LegacyWhitelistMintMode::BurnEveryTime => "tokenBurn",
+                    LegacyWhitelistMintMode::NeverBurn => "tokenGate",
+                }
+            ));
+
+            CandyGuardData {
+                default,
+                groups: Some(vec![
+                    Group {
+                        label: WHITELIST_LABEL.to_string(),
+                        guards: allowlist,
+                    },
+                    Group {
+                        label: PUBLIC_LABEL.to_string(),
+                        guards: public,
+                    },
+                ]),
+            }
+        }
+        None => {
+            default.sol_payment = public.sol_payment;
+            default.token_payment = public.token_payment;
+            default.start_date = public.start_date;
+            default.address_gate = public.address_gate;
+
+            CandyGuardData {
+                default,
+                groups: None,
+            }
+        }
+    };
+
+    if !data.retain_authority {
+        differences.push(
+            "retainAuthority=false is not supported: v3 items keep the update authority \
+            of the collection."
+                .to_string(),
+        );
+    }
+
+    if legacy.items_redeemed > 0 {
+        differences.push(format!(
+            "{} of {} items were already minted from the v2 candy machine: remove them from \
+            the cache and update 'number' to avoid minting them again.",
+            legacy.items_redeemed, data.items_available
+        ));
+    }
+
+    let config_data = ConfigData {
+        number: data.items_available,
+        symbol: data.symbol.trim_matches(char::from(0)).to_string(),
+        seller_fee_basis_points: data.seller_fee_basis_points,
+        is_mutable: data.is_mutable,
+        is_sequential: false,
+        creators: data
+            .creators
+            .iter()
+            .map(|creator| Creator {
+                address: creator.address,
+                share: creator.share,
+            })
+            .collect(),
+        hidden_settings: data.hidden_settings.as_ref().map(|hidden_settings| {
+            HiddenSettings::new(
+                hidden_settings.name.clone(),
+                hidden_settings.uri.clone(),
+                String::from_utf8_lossy(&hidden_settings.hash).to_string(),
+            )
+        }),
+        guards: Some(guards),
+        max_edition_supply: (data.max_supply > 0).then_some(data.max_supply),
+        ..Default::default()
+    };
+
+    (config_data, differences)
+}
+
+#[test]
+fn test_translate_whitelist_presale() {
+    let authority = Pubkey::new_unique();
+    let whitelist_mint = Pubkey::new_unique();
+
+    let legacy = LegacyCandyMachine {
+        authority,
+        wallet: authority,
+        token_mint: None,
+        items_redeemed: 0,
+        data: LegacyCandyMachineData {
+            uuid: "ABCDEF".to_string(),
+            price: LAMPORTS_PER_SOL,
+            symbol: "SUGAR".to_string(),
+            seller_fee_basis_points: 500,
+            max_supply: 0,
+            is_mutable: true,
+            retain_authority: true,
+            go_live_date: Some(1_700_000_000),
+            end_settings: Some(LegacyEndSettings {
+                end_setting_type: LegacyEndSettingType::Amount,
+                number: 50,
+            }),
+            creators: vec![LegacyCreator {
+                address: authority,
+                verified: true,
+                share: 100,
+            }],
+            hidden_settings: None,
+            whitelist_mint_settings: Some(LegacyWhitelistMintSettings {
+                mode: LegacyWhitelistMintMode::BurnEveryTime,
+                mint: whitelist_mint,
+                presale: true,
+                discount_price: Some(LAMPORTS_PER_SOL / 2),
+            }),
+            items_available: 100,
+            gatekeeper: None,
+        },
+    };
+
+    let (config_data, differences) = translate_settings(&legacy);
+    let guards = config_data.guards.unwrap();
+    let groups = guards.groups.unwrap();
+
+    assert_eq!(config_data.number, 100);
+    assert_eq!(guards.default.redeemed_amount.unwrap().maximum, 50);
+    assert_eq!(groups[0].label, WHITELIST_LABEL);
+    assert_eq!(groups[0].guards.sol_payment.as_ref().unwrap().value, 0.5);
+    assert_eq!(
+        groups[0].guards.token_burn.as_ref().unwrap().mint,
+        whitelist_mint
+    );
+    assert!(groups[0].guards.start_date.is_none());
+    assert!(groups[1].guards.start_date.is_some());
+    assert_eq!(differences.len(), 1);
+}
@@ -0,0 +1,611 @@
+use std::{fs, ops::Deref};
+
+use anchor_client::solana_sdk::{instruction::AccountMeta, keccak};
+use borsh::BorshSerialize;
+use mpl_candy_guard::{
+    accounts::Route as RouteAccount,
+    instruction::Route,
+    instructions::RouteArgs,
+    state::{CandyGuard, CandyGuardData, GuardSet, GuardType, DATA_OFFSET},
+};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::{
+    common::*,
+    dry_run::SendOrPlan,
+    freeze::{find_freeze_pda, initialize},
+    keypair::read_keypair,
+    pdas::find_metadata_pda,
+};
+
+/// Seed of the allowList proof PDA.
+const ALLOW_LIST_SEED: &[u8] = b"allow_list";
+
+/// Seed of the mintLimit counter PDA.
+const MINT_LIMIT_SEED: &[u8] = b"mint_limit";
+
+/// Seed of the allocation tracker PDA.
+const ALLOCATION_SEED: &[u8] = b"allocation";
+
+/// Civic gateway program.
+const GATEWAY_PROGRAM_ID: &str = "gatem74V238djXdzWnJf94Wo1DcnuGkfijbf3AuBhfs";
+
+/// Seed of the gateway token PDA.
+const GATEWAY_SEED: &[u8] = b"gateway";
+
+/// Seed of the gateway network expire feature PDA.
+const GATEWAY_EXPIRE_SEED: &[u8] = b"expire";
+
+/// Options to mint through a candy guard.
+#[derive(Debug, Default, Clone)]
+pub struct GuardOptions {
+    /// Label of the guard group to mint from.
+    pub label: Option<String>,
+    /// Path to the JSON file with the list of allowed addresses.
+    pub allowlist: Option<String>,
+    /// Freeze period (in seconds) used when the freeze escrow needs to be initialized.
+    pub freeze_period: Option<u64>,
+    /// Mint of the NFT used by the nftGate and nftPayment guards.
+    pub nft_mint: Option<String>,
+    /// Path to the keypair of the thirdPartySigner guard co-signer.
+    pub signer_keypair: Option<String>,
+}
+
+/// Candy guard information required to mint.
+pub struct GuardMint {
+    /// Program of the candy guard.
+    pub program_id: Pubkey,
+    pub candy_guard: Pubkey,
+    pub label: Option<String>,
+    pub guards: GuardSet,
+    pub options: GuardOptions,
+    /// Co-signer of the thirdPartySigner guard, when it is not the minter.
+    pub third_party_signer: Option<Keypair>,
+}
+
+/// Load the guards of the candy guard that are active for the specified label: the
+/// guards of the group override the ones of the default guard set.
+pub fn load_guard_mint<C: Deref<Target = impl Signer> + Clone>(
+    program: &Program<C>,
+    candy_guard_id: &Pubkey,
+    options: &GuardOptions,
+) -> Result<GuardMint> {
+    let account_data = program.rpc().get_account_data(candy_guard_id)?;
+    let candy_guard_data = CandyGuardData::load(&account_data[DATA_OFFSET..])?;
+    let groups = candy_guard_data.groups.unwrap_or_default();
+
+    let guards = match &options.label {
+        Some(label) => {
+            let group = groups
+                .into_iter()
+                .find(|group| group.label == *label)
+                .ok_or_else(|| anyhow!("Could not find group with label '{label}'"))?;
+            merge_guards(candy_guard_data.default, group.guards)
+        }
+        None if !groups.is_empty() => {
+            let labels: Vec<String> = groups.into_iter().map(|group| group.label).collect();
+            return Err(anyhow!(
+                "The candy guard has guard groups, use --group to select one of: {}",
+                labels.join(", ")
+            ));
+        }
+        None => candy_guard_data.default,
+    };
+
+    let third_party_signer = load_third_party_signer(&guards, options)?;
+
+    Ok(GuardMint {
+        program_id: program.id(),
+        candy_guard: *candy_guard_id,
+        label: options.label.clone(),
+        guards,
+        options: options.clone(),
+        third_party_signer,
+    })
+}
+
+/// Load the co-signer keypair of the thirdPartySigner guard, checking that it matches
+/// the signer key configured on the guard.
+fn load_third_party_signer(guards: &GuardSet, options: &GuardOptions) -> Result<Option<Keypair>> {
+    let signer_keypair = match &options.signer_keypair {
+        Some(signer_keypair) => signer_keypair,
+        None => return Ok(None),
+    };
+
+    let third_party_signer = match &guards.third_party_signer {
+        Some(third_party_signer) => third_party_signer,
+        None => {
+            warn!("Ignoring signer keypair: thirdPartySigner guard is not enabled");
+            println!(
+                "{}The thirdPartySigner guard is not enabled, ignoring the signer keypair.",
+                WARNING_EMOJI
+            );
+            return Ok(None);
+        }
+    };
+
+    let keypair = read_keypair(signer_keypair).map_err(|e| {
+        anyhow!(
+            "Failed to read signer keypair file: {}, {}",
+            signer_keypair,
+            e
+        )
+    })?;
+
+    if keypair.pubkey() != third_party_signer.signer_key {
+        return Err(anyhow!(
+            "Signer keypair {} does not match the thirdPartySigner guard signer {}",
+            keypair.pubkey(),
+            third_party_signer.signer_key
+        ));
+    }
+
+    Ok(Some(keypair))
+}
+
+/// Combine the default guard set with the guards of a group.
+pub fn merge_guards(default: GuardSet, group: GuardSet) -> GuardSet {
+    GuardSet {
+        bot_tax: group.bot_tax.or(default.bot_tax),
+        sol_payment: group.sol_payment.or(default.sol_payment),
+        token_payment: group.token_payment.or(default.token_payment),
+        start_date: group.start_date.or(default.start_date),
+        third_party_signer: group.third_party_signer.or(default.third_party_signer),
+        token_gate: group.token_gate.or(default.token_gate),
+        gatekeeper: group.gatekeeper.or(default.gatekeeper),
+        end_date: group.end_date.or(default.end_date),
+        allow_list: group.allow_list.or(default.allow_list),
+        mint_limit: group.mint_limit.or(default.mint_limit),
+        nft_payment: group.nft_payment.or(default.nft_payment),
+        redeemed_amount: group.redeemed_amount.or(default.redeemed_amount),
+        address_gate: group.address_gate.or(default.address_gate),
+        nft_gate: group.nft_gate.or(default.nft_gate),
+        nft_burn: group.nft_burn.or(default.nft_burn),
+        token_burn: group.token_burn.or(default.token_burn),
+        freeze_sol_payment: group.freeze_sol_payment.or(default.freeze_sol_payment),
+        freeze_token_payment: group.freeze_token_payment.or(default.freeze_token_payment),
+        program_gate: group.program_gate.or(default.program_gate),
+        allocation: group.allocation.or(default.allocation),
+        token2022_payment: group.token2022_payment.or(default.token2022_payment),
+    }
+}
+
+/// Send the route instructions that the enabled guards require before minting.
+pub fn execute_pre_mint_routes<C: Deref<Target = impl Signer> + Clone>(
+    program: &Program<C>,
+    guard_mint: &GuardMint,
+    candy_machine_id: &Pubkey,
+) -> Result<()> {
+    let options = &guard_mint.options;
+
+    if let Some(allow_list) = &guard_mint.guards.allow_list {
+        let allowlist_file = options.allowlist.as_ref().ok_or_else(|| {
+            anyhow!(
+                "The allowList guard is enabled, use --allowlist to specify the allowed addresses"
+            )
+        })?;
+        let addresses = load_allowlist(allowlist_file)?;
+        let minter = program.payer();
+
+        let (root, proof) = merkle_proof(&addresses, &minter)?;
+
+        if root != allow_list.merkle_root {
+            return Err(anyhow!(
+                "The merkle root of '{}' does not match the allowList guard",
+                allowlist_file
+            ));
+        }
+
+        let proof_pda = find_allow_list_proof_pda(
+            &allow_list.merkle_root,
+            &minter,
+            &guard_mint.candy_guard,
+            candy_machine_id,
+            &guard_mint.program_id,
+        );
+
+        if !account_exists(program, &proof_pda)? {
+            let remaining_accounts = vec![
+                AccountMeta::new(proof_pda, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ];
+
+            let signature = program
+                .request()
+                .accounts(RouteAccount {
+                    candy_guard: guard_mint.candy_guard,
+                    candy_machine: *candy_machine_id,
+                    payer: minter,
+                })
+                .accounts(remaining_accounts)
+                .args(Route {
+                    args: RouteArgs {
+                        guard: GuardType::AllowList,
+                        data: proof.try_to_vec()?,
+                    },
+                    label: guard_mint.label.clone(),
+                })
+                .send_or_plan("route")?;
+
+            info!("AllowList proof validated with signature: {}", signature);
+        }
+    }
+
+    if let Some(freeze_sol_payment) = &guard_mint.guards.freeze_sol_payment {
+        let (freeze_pda, _) = find_freeze_pda(
+            &guard_mint.candy_guard,
+            candy_machine_id,
+            &freeze_sol_payment.destination,
+        );
+
+        if !account_exists(program, &freeze_pda)? {
+            let candy_guard: CandyGuard = program.account(guard_mint.candy_guard)?;
+
+            let period = match options.freeze_period {
+                Some(period) if candy_guard.authority == program.payer() => period,
+                Some(_) => {
+                    return Err(anyhow!(
+                        "The freeze escrow is not initialized and only the candy guard \
+                        authority ({}) can initialize it",
+                        candy_guard.authority
+                    ));
+                }
+                None => {
+                    return Err(anyhow!(
+                        "The freeze escrow is not initialized, use --freeze-period to \
+                        initialize it or run 'sugar freeze initialize'"
+                    ));
+                }
+            };
+
+            let signature = initialize(
+                program,
+                &guard_mint.candy_guard,
+                candy_machine_id,
+                &freeze_sol_payment.destination,
+                &guard_mint.label,
+                period,
+                None,
+            )?;
+
+            info!("Freeze escrow initialized with signature: {}", signature);
+        }
+    }
+
+    Ok(())
+}
+
+/// Return the remaining accounts required by the guards to mint.
+pub fn get_remaining_accounts(
+    guard_mint: &GuardMint,
+    candy_machine_id: &Pubkey,
+    minter: &Pubkey,
+    nft_mint: &Pubkey,
+) -> Result<Vec<AccountMeta>> {
+    let guards = &guard_mint.guards;
+    let options = &guard_mint.options;
+    let candy_guard_id = &guard_mint.candy_guard;
+    let mut remaining_accounts = Vec::new();
+    let mut unsupported = Vec::new();
+
+    // the order of the accounts must follow the order of the guards in the guard set
+
+    if let Some(sol_payment) = &guards.sol_payment {
+        remaining_accounts.push(AccountMeta::new(sol_payment.destination, false));
+    }
+
+    if let Some(token_payment) = &guards.token_payment {
+        remaining_accounts.push(AccountMeta::new(
+            get_associated_token_address(minter, &token_payment.mint),
+            false,
+        ));
+        remaining_accounts.push(AccountMeta::new(token_payment.destination_ata, false));
+    }
+
+    if let Some(third_party_signer) = &guards.third_party_signer {
+        if third_party_signer.signer_key == *minter || guard_mint.third_party_signer.is_some() {
+            remaining_accounts.push(AccountMeta::new_readonly(
+                third_party_signer.signer_key,
+                true,
+            ));
+        } else {
+            return Err(anyhow!(
+                "The thirdPartySigner guard requires the signature of {}, use --signer-keypair \
+                to provide its keypair",
+                third_party_signer.signer_key
+            ));
+        }
+    }
+
+    if let Some(token_gate) = &guards.token_gate {
+        remaining_accounts.push(AccountMeta::new_readonly(
+            get_associated_token_address(minter, &token_gate.mint),
+            false,
+        ));
+    }
+
+    if let Some(gatekeeper) = &guards.gatekeeper {
+        remaining_accounts.push(AccountMeta::new(
+            find_gateway_token_pda(minter, &gatekeeper.gatekeeper_network),
+            false,
+        ));
+
+        if gatekeeper.expire_on_use {
+            let gateway_program = Pubkey::from_str(GATEWAY_PROGRAM_ID)?;
+            remaining_accounts.push(AccountMeta::new_readonly(gateway_program, false));
+            remaining_accounts.push(AccountMeta::new_readonly(
+                Pubkey::find_program_address(
+                    &[gatekeeper.gatekeeper_network.as_ref(), GATEWAY_EXPIRE_SEED],
+                    &gateway_program,
+                )
+                .0,
+                false,
+            ));
+        }
+    }
+
+    if let Some(allow_list) = &guards.allow_list {
+        remaining_accounts.push(AccountMeta::new_readonly(
+            find_allow_list_proof_pda(
+                &allow_list.merkle_root,
+                minter,
+                candy_guard_id,
+                candy_machine_id,
+                &guard_mint.program_id,
+            ),
+            false,
+        ));
+    }
+
+    if let Some(mint_limit) = &guards.mint_limit {
+        remaining_accounts.push(AccountMeta::new(
+            Pubkey::find_program_address(
+                &[
+                    MINT_LIMIT_SEED,
+                    &[mint_limit.id],
+                    minter.as_ref(),
+                    candy_guard_id.as_ref(),
+                    candy_machine_id.as_ref(),
+                ],
+                &guard_mint.program_id,
+            )
+            .0,
+            false,
+        ));
+    }
+
+    if let Some(nft_payment) = &guards.nft_payment {
+        let nft = required_nft_mint(options, "nftPayment")?;
+        remaining_accounts.push(AccountMeta::new(
+            get_associated_token_address(minter, &nft),
+            false,
+        ));
+        remaining_accounts.push(AccountMeta::new(find_metadata_pda(&nft), false));
+        remaining_accounts.push(AccountMeta::new_readonly(nft, false));
+        remaining_accounts.push(AccountMeta::new_readonly(nft_payment.destination, false));
+        remaining_accounts.push(AccountMeta::new(
+            get_associated_token_address(&nft_payment.destination, &nft),
+            false,
+        ));
+        remaining_accounts.push(AccountMeta::new_readonly(
+            spl_associated_token_account::ID,
+            false,
+        ));
+    }
+
+    if guards.nft_gate.is_some() {
+        let nft = required_nft_mint(options, "nftGate")?;
+        remaining_accounts.push(AccountMeta::new_readonly(
+            get_associated_token_address(minter, &nft),
+            false,
+        ));
+        remaining_accounts.push(AccountMeta::new_readonly(find_metadata_pda(&nft), false));
+    }
+
+    if guards.nft_burn.is_some() {
+        unsupported.push("nftBurn");
+    }
+
+    if let Some(token_burn) = &guards.token_burn {
+        remaining_accounts.push(AccountMeta::new(
+            get_associated_token_address(minter, &token_burn.mint),
+            false,
+        ));
+        remaining_accounts.push(AccountMeta::new(token_burn.mint, false));
+    }
+
+    if let Some(freeze_sol_payment) = &guards.freeze_sol_payment {
+        let (freeze_pda, _) = find_freeze_pda(
+            candy_guard_id,
+            candy_machine_id,
+            &freeze_sol_payment.destination,
+        );
+        remaining_accounts.push(AccountMeta::new(freeze_pda, false));
+        remaining_accounts.push(AccountMeta::new_readonly(
+            get_associated_token_address(minter, nft_mint),
+            false,
+        ));
+    }
+
+    if let Some(freeze_token_payment) = &guards.freeze_token_payment {
+        let (freeze_pda, _) = find_freeze_pda(
+            candy_guard_id,
+            candy_machine_id,
+            &freeze_token_payment.destination_ata,
+        );
+        remaining_accounts.push(AccountMeta::new(freeze_pda, false));
+        remaining_accounts.push(AccountMeta::new_readonly(
+            get_associated_token_address(minter, nft_mint),
+            false,
+        ));
+        remaining_accounts.push(AccountMeta::new(
+            get_associated_token_address(minter, &freeze_token_payment.mint),
+            false,
+        ));
+        remaining_accounts.push(AccountMeta::new(
+            get_associated_token_address(&freeze_pda, &freeze_token_payment.mint),
+            false,
+        ));
+    }
+
+    if let Some(allocation) = &guards.allocation {
+        remaining_accounts.push(AccountMeta::new(
+            Pubkey::find_program_address(
+                &[
+                    ALLOCATION_SEED,
+                    &[allocation.id],
+                    candy_guard_id.as_ref(),
+                    candy_machine_id.as_ref(),
+                ],
+                &guard_mint.program_id,
+            )
+            .0,
+            false,
+        ));
+    }
+
+    if guards.token2022_payment.is_some() {
+        unsupported.push("token2022Payment");
+    }
+
+    if !unsupported.is_empty() {
+        return Err(anyhow!(
+            "Minting with the following guard(s) is not supported: {}",
+            unsupported.join(", ")
+        ));
+    }
+
+    Ok(remaining_accounts)
+}
+
+/// Load the list of allowed addresses from a JSON file.
+pub fn load_allowlist(allowlist_file: &str) -> Result<Vec<Pubkey>> {
+    let content = fs::read_to_string(allowlist_file)
+        .map_err(|e| anyhow!("Failed to read allowlist file '{}': {}", allowlist_file, e))?;
+    let addresses: Vec<String> = serde_json::from_str(&content)
+        .map_err(|e| anyhow!("Invalid allowlist file '{}': {}", allowlist_file, e))?;
+
+    addresses
+        .iter()
+        .map(|address| {
+            Pubkey::from_str(address)
+                .map_err(|_| anyhow!("Invalid address in allowlist: {}", address))
+        })
+        .collect()
+}
+
+/// Return the merkle root of the allowlist and the proof for the specified address.
+///
+/// The tree uses keccak256 leaves and sorted pairs, with odd nodes promoted to
+/// the next level.
+pub fn merkle_proof(addresses: &[Pubkey], address: &Pubkey) -> Result<([u8; 32], Vec<[u8; 32]>)> {
+    let mut position = addresses
+        .iter()
+        .position(|a| a == address)
+        .ok_or_else(|| anyhow!("Address {} is not in the allowlist", address))?;
+
+    let mut level: Vec<[u8; 32]> = addresses
+        .iter()
+        .map(|a| keccak::hashv(&[&a.to_bytes()]).0)
+        .collect();
+    let mut proof = Vec::new();
+
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        if sibling < level.len() {
+            proof.push(level[sibling]);
+        }
+
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_pair(left, right),
+                [node] => *node,
+                _ => unreachable!(),
+            })
+            .collect();
+        position /= 2;
+    }
+
+    Ok((level[0], proof))
+}
+
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        keccak::hashv(&[a, b]).0
+    } else {
+        keccak::hashv(&[b, a]).0
+    }
+}
+
+fn required_nft_mint(options: &GuardOptions, guard: &str) -> Result<Pubkey> {
+    let nft_mint = options.nft_mint.as_ref().ok_or_else(|| {
+        anyhow!("The {guard} guard is enabled, use --nft-mint to specify the NFT")
+    })?;
+
+    Pubkey::from_str(nft_mint).map_err(|_| anyhow!("Invalid NFT mint address: {}", nft_mint))
+}
+
+/// Return the gateway token account of the wallet for the gatekeeper network.
+pub fn find_gateway_token_pda(wallet: &Pubkey, gatekeeper_network: &Pubkey) -> Pubkey {
+    // the gateway program accepts multiple tokens per wallet; sugar uses the first one
+    let seed_index = 0u64.to_le_bytes();
+
+    Pubkey::find_program_address(
+        &[
+            wallet.as_ref(),
+            GATEWAY_SEED,
+            &seed_index,
+            gatekeeper_network.as_ref(),
+        ],
+        &Pubkey::from_str(GATEWAY_PROGRAM_ID).unwrap(),
+    )
+    .0
+}
+
+pub fn find_allow_list_proof_pda(
+    merkle_root: &[u8; 32],
+    minter: &Pubkey,
+    candy_guard_id: &Pubkey,
+    candy_machine_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            ALLOW_LIST_SEED,
+            merkle_root,
+            minter.as_ref(),
+            candy_guard_id.as_ref(),
+            candy_machine_id.as_ref(),
+        ],
+        program_id,
+    )
+    .0
+}
+
+fn account_exists<C: Deref<Target = impl Signer> + Clone>(
+    program: &Program<C>,
+    address: &Pubkey,
+) -> Result<bool> {
+    Ok(program
+        .rpc()
+        .get_account_with_commitment(address, CommitmentConfig::confirmed())?
+        .value
+        .is_some())
+}
+
+#[test]
+fn test_merkle_proof_verifies_against_root() {
+    let addresses: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+
+    for address in &addresses {
+        let (root, proof) = merkle_proof(&addresses, address).unwrap();
+
+        let leaf = keccak::hashv(&[&address.to_bytes()]).0;
+        let computed = proof
+            .iter()
+            .fold(leaf, |node, sibling| hash_pair(&node, sibling));
+
+        assert_eq!(computed, root);
+    }
+}
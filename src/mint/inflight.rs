@@ -0,0 +1,208 @@
+use std::{sync::Arc, time::Duration};
+
+use anchor_client::solana_sdk::instruction::Instruction;
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+
+use crate::{
+    common::*,
+    confirmation::commitment,
+    costs::record_transaction,
+    dry_run::{is_dry_run, plan_transaction},
+    journal::Journal,
+};
+
+/// Interval between the status checks (and rebroadcasts) of a transaction in flight.
+const STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The number of times a transaction is signed with a new blockhash, each one only
+/// after the blockhash of the previous attempt expired.
+const MAX_ATTEMPTS: usize = 3;
+
+/// A signed transaction sent to the cluster: it can land until the block height
+/// passes the last valid block height of its blockhash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InFlight {
+    pub signature: Signature,
+    pub last_valid_block_height: u64,
+}
+
+impl InFlight {
+    /// Detail stored in the journal for the transaction.
+    pub fn to_detail(&self) -> String {
+        format!("{}@{}", self.signature, self.last_valid_block_height)
+    }
+
+    pub fn from_detail(detail: &str) -> Option<Self> {
+        let (signature, height) = detail.split_once('@')?;
+
+        Some(InFlight {
+            signature: Signature::from_str(signature).ok()?,
+            last_valid_block_height: height.parse().ok()?,
+        })
+    }
+}
+
+/// Outcome of a transaction in flight.
+#[derive(Debug)]
+pub enum InFlightStatus {
+    Landed(Signature),
+    Failed(String),
+    /// The blockhash expired without the transaction landing.
+    Expired,
+}
+
+/// Journal entry where the transaction of an operation is recorded before it is sent.
+pub struct InFlightEntry {
+    pub journal: Arc<Journal>,
+    pub key: String,
+}
+
+/// Options of how a mint transaction is sent.
+#[derive(Default)]
+pub struct SendOptions {
+    /// Fee payer of the pool paying the transaction instead of the wallet.
+    pub fee_payer: Option<Arc<Keypair>>,
+    pub in_flight: Option<InFlightEntry>,
+}
+
+/// Sends a transaction without ever producing a duplicate: the signed transaction is
+/// rebroadcast until it lands or its blockhash expires, and only then it is signed
+/// again with a new blockhash. The first signer pays for the transaction.
+pub async fn send_idempotent(
+    rpc_client: &RpcClient,
+    instructions: &[Instruction],
+    signers: &[&(dyn Signer + Sync)],
+    in_flight: Option<&InFlightEntry>,
+) -> Result<Signature> {
+    let payer = signers[0].pubkey();
+
+    if is_dry_run() {
+        plan_transaction(
+            "mint",
+            &Transaction::new_with_payer(instructions, Some(&payer)),
+        );
+        return Ok(Signature::default());
+    }
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let (blockhash, last_valid_block_height) =
+            rpc_client.get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())?;
+        let tx = {
+            // the signers are only Send as references to Sync signers
+            let signers: Vec<&dyn Signer> = signers
+                .iter()
+                .map(|signer| *signer as &dyn Signer)
+                .collect();
+            Transaction::new_signed_with_payer(instructions, Some(&payer), &signers, blockhash)
+        };
+
+        let sent = InFlight {
+            signature: tx.signatures[0],
+            last_valid_block_height,
+        };
+
+        // recorded before sending, so an interrupted run knows what to look for
+        if let Some(entry) = in_flight {
+            entry.journal.mark_sent(&entry.key, sent.to_detail())?;
+        }
+
+        match wait_for_outcome(rpc_client, &sent, Some(&tx)).await? {
+            InFlightStatus::Landed(signature) => {
+                record_transaction(&signature, "mint");
+                return Ok(signature);
+            }
+            InFlightStatus::Failed(err) => {
+                // a failed transaction still pays its fee
+                record_transaction(&sent.signature, "mint");
+                return Err(anyhow!("Transaction {} failed: {}", sent.signature, err));
+            }
+            InFlightStatus::Expired => warn!(
+                "Transaction {} expired without landing (attempt {}/{})",
+                sent.signature, attempt, MAX_ATTEMPTS
+            ),
+        }
+    }
+
+    Err(anyhow!(
+        "Transaction did not land after {} attempts, it is safe to retry.",
+        MAX_ATTEMPTS
+    ))
+}
+
+/// Waits for the outcome of a transaction in flight, rebroadcasting it (when available)
+/// until it lands or its blockhash expires.
+pub async fn wait_for_outcome(
+    rpc_client: &RpcClient,
+    in_flight: &InFlight,
+    tx: Option<&Transaction>,
+) -> Result<InFlightStatus> {
+    let config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        ..Default::default()
+    };
+
+    loop {
+        if let Some(tx) = tx {
+            // a rebroadcast has the same signature, so the transaction lands at most once
+            if let Err(err) = rpc_client.send_transaction_with_config(tx, config) {
+                debug!(
+                    "Failed to send transaction {}: {}",
+                    in_flight.signature, err
+                );
+            }
+        }
+
+        tokio::time::sleep(STATUS_INTERVAL).await;
+
+        let block_height =
+            rpc_client.get_block_height_with_commitment(CommitmentConfig::confirmed())?;
+        let status = rpc_client.get_signature_status_with_commitment_and_history(
+            &in_flight.signature,
+            commitment().config(),
+            true,
+        )?;
+
+        match status {
+            Some(Ok(())) => return Ok(InFlightStatus::Landed(in_flight.signature)),
+            Some(Err(err)) => return Ok(InFlightStatus::Failed(err.to_string())),
+            // the status is read after the block height, so the transaction did not
+            // land before the blockhash expired
+            None if block_height > in_flight.last_valid_block_height => {
+                return Ok(InFlightStatus::Expired)
+            }
+            None => (),
+        }
+    }
+}
+
+/// Returns the signature of the transaction recorded in the journal detail of an
+/// interrupted operation if it landed. When nothing was sent, or the transaction can
+/// no longer land, returns `None` and the operation can be sent again safely.
+pub async fn resolve_in_flight(
+    rpc_client: &RpcClient,
+    detail: Option<String>,
+) -> Result<Option<Signature>> {
+    let in_flight = match detail.as_deref().and_then(InFlight::from_detail) {
+        Some(in_flight) => in_flight,
+        None => return Ok(None),
+    };
+
+    match wait_for_outcome(rpc_client, &in_flight, None).await? {
+        InFlightStatus::Landed(signature) => Ok(Some(signature)),
+        InFlightStatus::Failed(_) | InFlightStatus::Expired => Ok(None),
+    }
+}
+
+#[test]
+fn test_in_flight_detail() {
+    let in_flight = InFlight {
+        signature: Signature::new_unique(),
+        last_valid_block_height: 1_234,
+    };
+
+    assert_eq!(
+        InFlight::from_detail(&in_flight.to_detail()),
+        Some(in_flight)
+    );
+    assert_eq!(InFlight::from_detail("RPC timeout"), None);
+}
@@ -1,3 +1,9 @@
+pub mod guards;
+pub mod inflight;
 pub mod process;
+pub mod receipts;
 
+pub use guards::*;
+pub use inflight::*;
 pub use process::*;
+pub use receipts::*;
@@ -1,13 +1,18 @@
-use std::{str::FromStr, sync::Arc};
+use std::{ops::Deref, str::FromStr, sync::Arc};
 
 use anchor_client::solana_sdk::{
     compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    program_pack::Pack,
     pubkey::Pubkey,
     signature::{Keypair, Signature, Signer},
     system_program, sysvar,
 };
 use anyhow::Result;
 use console::style;
+use mpl_candy_guard::{
+    accounts::MintV2 as GuardMintAccounts, instruction::MintV2 as GuardMintInstruction,
+};
 use mpl_candy_machine_core::{
     accounts as nft_accounts, instruction as nft_instruction, AccountVersion, CandyMachine,
 };
@@ -17,19 +22,42 @@ use mpl_token_metadata::{
         find_collection_authority_account, find_metadata_delegate_record_account,
         find_token_record_account,
     },
-    state::{Metadata, TokenMetadataAccount},
+    state::{Metadata, TokenMetadataAccount, MAX_MASTER_EDITION_LEN, MAX_METADATA_LEN},
+};
+use solana_client::{rpc_client::RpcClient, rpc_response::Response};
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
+use spl_token::{
+    state::{Account as TokenAccount, Mint},
+    ID as TOKEN_PROGRAM_ID,
 };
-use solana_client::rpc_response::Response;
-use spl_associated_token_account::get_associated_token_address;
-use spl_token::ID as TOKEN_PROGRAM_ID;
 use tokio::sync::Semaphore;
 
 use crate::{
+    ata::TOKEN_2022_PROGRAM_ID,
     cache::load_cache,
     candy_machine::{CANDY_MACHINE_ID, *},
     common::*,
-    config::{Cluster, SugarConfig},
+    config::{get_config_data, Cluster, SugarConfig},
+    confirmation::{commitment, Commitment, ConfirmationTracker, MAX_RESENDS},
+    core_candy_machine::{
+        get_candy_machine_program, get_core_candy_machine_state, mint_asset_ix,
+        CORE_CANDY_GUARD_ID, CORE_CANDY_MACHINE_ID,
+    },
+    dry_run::is_dry_run,
+    fee_payers::setup_fee_payer_client,
+    hooks::{run_hook, HookEvent},
+    mint::{
+        guards::{
+            execute_pre_mint_routes, get_remaining_accounts, load_guard_mint, GuardMint,
+            GuardOptions,
+        },
+        inflight::{send_idempotent, SendOptions},
+        receipts::{write_receipts, MintReceipt, ReceiptWriter},
+    },
     pdas::*,
+    rate_limit::throttled_client_with_commitment,
     utils::*,
 };
 
@@ -37,15 +65,42 @@ pub struct MintArgs {
     pub keypair: Option<String>,
     pub rpc_url: Option<String>,
     pub cache: String,
+    pub config: String,
     pub number: Option<u64>,
     pub receiver: Option<String>,
     pub candy_machine: Option<String>,
+    pub receipts: String,
+    pub guard_options: GuardOptions,
 }
 
 pub async fn process_mint(args: MintArgs) -> Result<()> {
     let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
     let client = setup_client(&sugar_config)?;
     let program = client.program(CANDY_MACHINE_ID);
+    let rpc_client = throttled_client_with_commitment(&sugar_config.rpc_url);
+
+    // the config file is optional when minting, it is only read for the postMint hook: a
+    // config that does not parse (e.g., one for another candy machine) does not stop the mint
+    let hooks = if Path::new(&args.config).exists() {
+        match get_config_data(&args.config) {
+            Ok(config_data) => config_data.hooks,
+            Err(err) => {
+                warn!("Failed to read the hooks of '{}': {}", args.config, err);
+                println!(
+                    "{} {}",
+                    WARNING_EMOJI,
+                    style(format!(
+                        "Could not read '{}', the postMint hook will not run.",
+                        args.config
+                    ))
+                    .yellow()
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
 
     // the candy machine id specified takes precedence over the one from the cache
 
@@ -76,10 +131,48 @@ pub async fn process_mint(args: MintArgs) -> Result<()> {
     let pb = spinner_with_style();
     pb.set_message("Connecting...");
 
-    let candy_machine_state = Arc::new(get_candy_machine_state(&sugar_config, &candy_pubkey)?);
-    let (_, collection_metadata) =
-        get_metadata_pda(&candy_machine_state.collection_mint, &program)?;
-    let collection_update_authority = collection_metadata.update_authority;
+    let core = get_candy_machine_program(&sugar_config, &candy_pubkey)? == CORE_CANDY_MACHINE_ID;
+
+    let candy_machine_state = Arc::new(if core {
+        get_core_candy_machine_state(&sugar_config, &candy_pubkey)?.to_candy_format()
+    } else {
+        get_candy_machine_state(&sugar_config, &candy_pubkey)?
+    });
+
+    // the collection of Core assets has no metadata account
+    let collection_update_authority = if core {
+        candy_machine_state.authority
+    } else {
+        let (_, collection_metadata) =
+            get_metadata_pda(&candy_machine_state.collection_mint, &program)?;
+        collection_metadata.update_authority
+    };
+
+    // mints through the candy guard when it is the mint authority
+    let guard_program = client.program(mpl_candy_guard::ID);
+    let mint_authority = candy_machine_state.mint_authority;
+    let mint_authority_owner = if mint_authority != sugar_config.keypair.pubkey() {
+        Some(rpc_client.get_account(&mint_authority)?.owner)
+    } else {
+        None
+    };
+
+    if mint_authority_owner == Some(CORE_CANDY_GUARD_ID) {
+        return Err(anyhow!(
+            "Minting through a core candy guard is not supported, its guards have a \
+            different layout than the ones of the candy guard program"
+        ));
+    }
+
+    let guard_mint = if mint_authority_owner == Some(mpl_candy_guard::ID) {
+        Some(Arc::new(load_guard_mint(
+            &guard_program,
+            &mint_authority,
+            &args.guard_options,
+        )?))
+    } else {
+        None
+    };
 
     pb.finish_with_message("Done");
 
@@ -89,11 +182,24 @@ pub async fn process_mint(args: MintArgs) -> Result<()> {
         CANDY_EMOJI
     );
 
+    let payer_pubkey = sugar_config.keypair.pubkey();
     let receiver_pubkey = match args.receiver {
         Some(receiver_id) => Pubkey::from_str(&receiver_id)
             .map_err(|_| anyhow!("Failed to parse receiver pubkey: {}", receiver_id))?,
-        None => sugar_config.keypair.pubkey(),
+        None => payer_pubkey,
     };
+
+    if receiver_pubkey != payer_pubkey {
+        if guard_mint.is_some() {
+            return Err(anyhow!(
+                "The candy guard requires the minter to sign the transaction, \
+                minting to a different receiver is not supported"
+            ));
+        }
+
+        check_receiver(&program, &receiver_pubkey)?;
+        println!("\nPaying from {}", &payer_pubkey);
+    }
     println!("\nMinting to {}", &receiver_pubkey);
 
     let number = args.number.unwrap_or(1);
@@ -105,94 +211,374 @@ pub async fn process_mint(args: MintArgs) -> Result<()> {
         return Err(error);
     }
 
+    let mint_cost = estimate_mint_cost(&rpc_client, guard_mint.as_deref())?;
+    check_balance(
+        &rpc_client,
+        &payer_pubkey,
+        mint_cost * number,
+        &format!("mint {} item(s)", number),
+    )
+    .await?;
+    check_token_payments(&rpc_client, &payer_pubkey, guard_mint.as_deref(), number)?;
+
     info!("Minting NFT from candy machine: {}", &candy_machine_id);
     info!("Candy machine program id: {:?}", CANDY_MACHINE_ID);
 
-    if number == 1 {
-        let pb = spinner_with_style();
-        pb.set_message(format!(
-            "{} item(s) remaining",
-            candy_machine_state.data.items_available - candy_machine_state.items_redeemed
-        ));
-        let config = Arc::new(sugar_config);
-
-        let result = match mint(
-            Arc::clone(&config),
-            candy_pubkey,
-            Arc::clone(&candy_machine_state),
-            collection_update_authority,
-            receiver_pubkey,
-        )
-        .await
-        {
-            Ok((signature, mint)) => {
-                println!("Mint: {mint}");
-                println!("Signature: {signature}");
-                format!("{}", style("Mint success").bold())
-            }
-            Err(err) => {
-                pb.abandon_with_message(format!("{}", style("Mint failed ").red().bold()));
-                error!("{:?}", err);
-                return Err(err);
-            }
-        };
+    let receipts = Arc::new(ReceiptWriter::open(&args.receipts)?);
+    // with the processed commitment, the mints are finalized in the background
+    let track = commitment() == Commitment::Processed && !is_dry_run();
 
-        pb.finish_with_message(result);
-    } else {
-        let pb = progress_bar_with_style(number);
-
-        let mut tasks = Vec::new();
-        let semaphore = Arc::new(Semaphore::new(10));
-        let config = Arc::new(sugar_config);
-
-        for _i in 0..number {
-            let config = config.clone();
-            let permit = Arc::clone(&semaphore).acquire_owned().await.unwrap();
-            let candy_machine_state = candy_machine_state.clone();
-            let pb = pb.clone();
-
-            // Start tasks
-            tasks.push(tokio::spawn(async move {
-                let _permit = permit;
-                let res = mint(
-                    config,
+    if let Some(guard_mint) = &guard_mint {
+        execute_pre_mint_routes(&guard_program, guard_mint, &candy_pubkey)?;
+    }
+
+    let mut minted_items = Vec::new();
+    let mut failure = None;
+    let config = Arc::new(sugar_config);
+    // the mints dropped before being finalized are sent again
+    let mut remaining = number;
+
+    for round in 0..=MAX_RESENDS {
+        let tracker = track.then(|| ConfirmationTracker::start(config.rpc_url.clone()));
+
+        if remaining == 1 {
+            let pb = spinner_with_style();
+            pb.set_message(format!(
+                "{} item(s) remaining",
+                candy_machine_state.data.items_available - candy_machine_state.items_redeemed
+            ));
+
+            let minted = if core {
+                mint_core(
+                    Arc::clone(&config),
+                    candy_pubkey,
+                    Arc::clone(&candy_machine_state),
+                    receiver_pubkey,
+                )
+                .await
+            } else {
+                mint(
+                    Arc::clone(&config),
                     candy_pubkey,
-                    candy_machine_state,
+                    Arc::clone(&candy_machine_state),
                     collection_update_authority,
                     receiver_pubkey,
+                    guard_mint.clone(),
+                    SendOptions::default(),
                 )
-                .await;
-                pb.inc(1);
-                res
-            }));
-        }
+                .await
+            };
+
+            let result = match minted {
+                Ok((signature, mint)) => {
+                    println!("Mint: {mint}");
+                    println!("Signature: {signature}");
+
+                    if let Some(tracker) = &tracker {
+                        tracker.track(signature, mint);
+                    }
+
+                    receipts.append(MintReceipt::new(None, mint, receiver_pubkey, signature));
+                    minted_items.push((mint, signature));
+
+                    format!("{}", style("Mint success").bold())
+                }
+                Err(err) => {
+                    pb.abandon_with_message(format!("{}", style("Mint failed ").red().bold()));
+                    error!("{:?}", err);
+                    return Err(err);
+                }
+            };
+
+            pb.finish_with_message(result);
+        } else {
+            let pb = progress_bar_with_style(remaining);
+
+            let mut tasks = Vec::new();
+            let semaphore = Arc::new(Semaphore::new(10));
+
+            for _ in 0..remaining {
+                let config = config.clone();
+                let receipts = receipts.clone();
+                let guard_mint = guard_mint.clone();
+                let permit = Arc::clone(&semaphore).acquire_owned().await.unwrap();
+                let candy_machine_state = candy_machine_state.clone();
+                let pb = pb.clone();
+
+                // Start tasks
+                tasks.push(tokio::spawn(async move {
+                    let _permit = permit;
+                    let res = if core {
+                        mint_core(
+                            Arc::clone(&config),
+                            candy_pubkey,
+                            candy_machine_state,
+                            receiver_pubkey,
+                        )
+                        .await
+                    } else {
+                        mint(
+                            Arc::clone(&config),
+                            candy_pubkey,
+                            candy_machine_state,
+                            collection_update_authority,
+                            receiver_pubkey,
+                            guard_mint,
+                            SendOptions::default(),
+                        )
+                        .await
+                    };
+                    pb.inc(1);
+
+                    if let Ok((signature, mint)) = &res {
+                        receipts.append(MintReceipt::new(None, *mint, receiver_pubkey, *signature));
+                    }
+
+                    res
+                }));
+            }
 
-        let mut error_count = 0;
+            let mut error_count = 0;
+
+            // Resolve tasks
+            for task in tasks {
+                match task.await.unwrap() {
+                    Ok((signature, mint)) => {
+                        if let Some(tracker) = &tracker {
+                            tracker.track(signature, mint);
+                        }
+                        minted_items.push((mint, signature));
+                    }
+                    Err(e) => {
+                        error_count += 1;
+                        error!("{:?}, continuing. . .", e);
+                    }
+                }
+            }
 
-        // Resolve tasks
-        for task in tasks {
-            let res = task.await.unwrap();
-            if let Err(e) = res {
-                error_count += 1;
-                error!("{:?}, continuing. . .", e);
+            if error_count > 0 {
+                pb.abandon_with_message(format!(
+                    "{} {} items failed.",
+                    style("Some of the items failed to mint.").red().bold(),
+                    error_count
+                ));
+                // the hook still receives the items that were minted
+                failure = Some(anyhow!(
+                    "{} {}/{} {}",
+                    style("Minted").red().bold(),
+                    remaining - error_count,
+                    remaining,
+                    style("of the items").red().bold()
+                ));
+            } else {
+                pb.finish();
             }
         }
 
-        if error_count > 0 {
-            pb.abandon_with_message(format!(
-                "{} {} items failed.",
-                style("Some of the items failed to mint.").red().bold(),
-                error_count
+        let dropped = check_finalized(tracker).await?;
+
+        if dropped.is_empty() {
+            break;
+        }
+
+        // the dropped mints did not redeem an item
+        minted_items.retain(|(mint, _)| !dropped.contains(mint));
+        receipts.discard(&dropped);
+
+        if round == MAX_RESENDS {
+            failure = Some(anyhow!(
+                "{} mint(s) were dropped before being finalized, mint the items again.",
+                dropped.len()
             ));
+            break;
+        }
+
+        println!(
+            "Minting again {} item(s) that were not finalized.",
+            dropped.len()
+        );
+        remaining = dropped.len() as u64;
+    }
+
+    // Core assets are not looked up by their metadata account
+    let candy_machine = (!core).then(|| (&candy_pubkey, candy_machine_state.as_ref()));
+    write_receipts(&rpc_client, &receipts, candy_machine, &args.receipts)?;
+
+    if !minted_items.is_empty() {
+        let mints: Vec<Value> = minted_items
+            .iter()
+            .map(|(mint, signature)| {
+                json!({
+                    "mint": mint.to_string(),
+                    "signature": signature.to_string(),
+                })
+            })
+            .collect();
+
+        run_hook(
+            hooks.as_ref(),
+            HookEvent::PostMint,
+            json!({
+                "candyMachine": candy_machine_id,
+                "receiver": receiver_pubkey.to_string(),
+                "mints": mints,
+            }),
+        )?;
+    }
+
+    if let Some(err) = failure {
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// Waits for the mints sent with the processed commitment to be finalized, returning the
+/// ones that failed or were dropped.
+async fn check_finalized(tracker: Option<ConfirmationTracker<Pubkey>>) -> Result<Vec<Pubkey>> {
+    let tracker = match tracker {
+        Some(tracker) => tracker,
+        None => return Ok(Vec::new()),
+    };
+
+    let pb = spinner_with_style();
+    pb.set_message("Waiting for the mints to be finalized...");
+    let confirmations = tracker.finish().await?;
+    pb.finish_and_clear();
+
+    if !confirmations.failed.is_empty() {
+        println!("\n{}", style("Mints not finalized:").red().bold());
+        for mint in &confirmations.failed {
+            println!("  {} {}", style("->").red(), mint);
+        }
+    }
+
+    Ok(confirmations.failed)
+}
+
+/// Check that the receiver is a wallet that can own the NFT: the token account
+/// is derived from the receiver, so a token account address is a common mistake.
+fn check_receiver<C: Deref<Target = impl Signer> + Clone>(
+    program: &Program<C>,
+    receiver: &Pubkey,
+) -> Result<()> {
+    let account = program
+        .rpc()
+        .get_account_with_commitment(receiver, CommitmentConfig::confirmed())?
+        .value;
+
+    if let Some(account) = account {
+        if account.owner == TOKEN_PROGRAM_ID {
+            return Err(anyhow!(
+                "Receiver {} is a token account, use the address of the wallet owning it",
+                receiver
+            ));
+        }
+
+        if account.executable {
             return Err(anyhow!(
-                "{} {}/{} {}",
-                style("Minted").red().bold(),
-                number - error_count,
-                number,
-                style("of the items").red().bold()
+                "Receiver {} is a program and cannot own the NFT",
+                receiver
             ));
         }
-        pb.finish();
+    }
+
+    Ok(())
+}
+
+/// Estimate the SOL needed to mint one item: the rent of the new accounts, the
+/// transaction fee and the payments required by the guards.
+pub fn estimate_mint_cost(rpc_client: &RpcClient, guard_mint: Option<&GuardMint>) -> Result<u64> {
+    let mut cost = 0;
+
+    for size in [
+        Mint::LEN,
+        TokenAccount::LEN,
+        MAX_METADATA_LEN,
+        MAX_MASTER_EDITION_LEN,
+    ] {
+        cost += rpc_client.get_minimum_balance_for_rent_exemption(size)?;
+    }
+
+    // base fee of the minter and new mint signatures plus the priority fee
+    cost += 2 * 5_000 + (COMPUTE_UNITS as u64 * PRIORITY_FEE) / 1_000_000;
+
+    if let Some(guard_mint) = guard_mint {
+        if let Some(sol_payment) = &guard_mint.guards.sol_payment {
+            cost += sol_payment.lamports;
+        }
+        if let Some(freeze_sol_payment) = &guard_mint.guards.freeze_sol_payment {
+            cost += freeze_sol_payment.lamports;
+        }
+    }
+
+    Ok(cost)
+}
+
+/// Checks that the payer holds the tokens required by the token payment guards to mint
+/// `number` items.
+fn check_token_payments(
+    rpc_client: &RpcClient,
+    payer: &Pubkey,
+    guard_mint: Option<&GuardMint>,
+    number: u64,
+) -> Result<()> {
+    let guards = match guard_mint {
+        Some(guard_mint) => &guard_mint.guards,
+        None => return Ok(()),
+    };
+
+    let mut payments = Vec::new();
+
+    if let Some(token_payment) = &guards.token_payment {
+        payments.push((
+            "tokenPayment",
+            token_payment.mint,
+            token_payment.amount,
+            TOKEN_PROGRAM_ID,
+        ));
+    }
+    if let Some(freeze_token_payment) = &guards.freeze_token_payment {
+        payments.push((
+            "freezeTokenPayment",
+            freeze_token_payment.mint,
+            freeze_token_payment.amount,
+            TOKEN_PROGRAM_ID,
+        ));
+    }
+    if let Some(token2022_payment) = &guards.token2022_payment {
+        payments.push((
+            "token2022Payment",
+            token2022_payment.mint,
+            token2022_payment.amount,
+            TOKEN_2022_PROGRAM_ID,
+        ));
+    }
+
+    for (guard, mint, amount, token_program) in payments {
+        let ata = get_associated_token_address_with_program_id(payer, &mint, &token_program);
+        // a missing token account holds no tokens
+        let balance = rpc_client
+            .get_token_account_balance(&ata)
+            .ok()
+            .and_then(|balance| balance.amount.parse::<u64>().ok())
+            .unwrap_or(0);
+        let required = amount.saturating_mul(number);
+
+        if balance < required {
+            let message = format!(
+                "Insufficient token balance to mint {} item(s): the {} guard requires {} \
+                token(s) of mint {}, the wallet holds {}",
+                number, guard, required, mint, balance
+            );
+
+            // the plan of a dry run continues, reporting the missing tokens
+            if is_dry_run() {
+                println!("{} {}", WARNING_EMOJI, style(message).yellow());
+            } else {
+                return Err(SugarError::InsufficientFunds(message).into());
+            }
+        }
     }
 
     Ok(())
@@ -204,18 +590,87 @@ pub async fn mint(
     candy_machine_state: Arc<CandyMachine>,
     collection_update_authority: Pubkey,
     receiver: Pubkey,
+    guard_mint: Option<Arc<GuardMint>>,
+    send_options: SendOptions,
 ) -> Result<(Signature, Pubkey)> {
-    let client = setup_client(&config)?;
-    let program = client.program(CANDY_MACHINE_ID);
-    let payer = program.payer();
+    let fee_payer = send_options.fee_payer.as_deref();
 
-    if candy_machine_state.mint_authority != payer {
+    if guard_mint.is_none() && candy_machine_state.mint_authority != config.keypair.pubkey() {
         return Err(anyhow!(
             "Payer is not the Candy Machine mint authority, mint disallowed."
         ));
     }
 
     let nft_mint = Keypair::new();
+    let instructions = mint_instructions(
+        &config,
+        &candy_machine_id,
+        &candy_machine_state,
+        &collection_update_authority,
+        &receiver,
+        guard_mint.as_deref(),
+        fee_payer,
+        &nft_mint,
+    )?;
+
+    // the anchor client is not held across the awaits (it is not Send), the transaction
+    // is sent with a separate client
+    let rpc_client = throttled_client_with_commitment(&config.rpc_url);
+    let payer = fee_payer.unwrap_or(&config.keypair).pubkey();
+    let mut signers: Vec<&(dyn Signer + Sync)> =
+        vec![fee_payer.unwrap_or(&config.keypair), &nft_mint];
+
+    if fee_payer.is_some() {
+        signers.push(&config.keypair);
+    }
+
+    if let Some(third_party_signer) = guard_mint
+        .as_ref()
+        .and_then(|guard_mint| guard_mint.third_party_signer.as_ref())
+    {
+        signers.push(third_party_signer);
+    }
+
+    simulate_instructions(&rpc_client, &instructions, &payer)?;
+
+    let sig = send_idempotent(
+        &rpc_client,
+        &instructions,
+        &signers,
+        send_options.in_flight.as_ref(),
+    )
+    .await?;
+
+    check_minted(&rpc_client, &find_metadata_pda(&nft_mint.pubkey()), &sig)?;
+
+    info!("Minted! TxId: {}", sig);
+
+    Ok((sig, nft_mint.pubkey()))
+}
+
+/// Instructions of a candy machine mint, directly or through the candy guard.
+#[allow(clippy::too_many_arguments)]
+fn mint_instructions(
+    config: &SugarConfig,
+    candy_machine_id: &Pubkey,
+    candy_machine_state: &CandyMachine,
+    collection_update_authority: &Pubkey,
+    receiver: &Pubkey,
+    guard_mint: Option<&GuardMint>,
+    fee_payer: Option<&Keypair>,
+    nft_mint: &Keypair,
+) -> Result<Vec<Instruction>> {
+    // a fee payer of the pool pays the fees and rent; the wallet signs as the authority
+    let client = match fee_payer {
+        Some(fee_payer) => setup_fee_payer_client(config, fee_payer)?,
+        None => setup_client(config)?,
+    };
+    let program = client.program(CANDY_MACHINE_ID);
+    let payer = program.payer();
+    let authority = config.keypair.pubkey();
+    let candy_machine_id = *candy_machine_id;
+    let receiver = *receiver;
+
     let metaplex_program_id = Pubkey::from_str(METAPLEX_PROGRAM_ID)?;
     // derive associated token account
     let token = get_associated_token_address(&receiver, &nft_mint.pubkey());
@@ -236,7 +691,7 @@ pub async fn mint(
                 find_metadata_delegate_record_account(
                     &collection_mint,
                     MetadataDelegateRole::Collection,
-                    &collection_update_authority,
+                    collection_update_authority,
                     &authority_pda,
                 )
                 .0,
@@ -265,7 +720,7 @@ pub async fn mint(
             nft_owner: receiver,
             token: Some(token),
             token_record,
-            mint_authority: payer,
+            mint_authority: authority,
             nft_metadata: metadata_pda,
             nft_mint: nft_mint.pubkey(),
             nft_master_edition: master_edition_pda,
@@ -288,7 +743,49 @@ pub async fn mint(
         })
         .args(nft_instruction::MintV2 {});
 
-    let mut mint_ix = mint_ix.instructions()?;
+    let mut mint_ix = if let Some(guard_mint) = guard_mint {
+        let guard_program = client.program(mpl_candy_guard::ID);
+        let remaining_accounts =
+            get_remaining_accounts(guard_mint, &candy_machine_id, &receiver, &nft_mint.pubkey())?;
+
+        guard_program
+            .request()
+            .accounts(GuardMintAccounts {
+                candy_guard: guard_mint.candy_guard,
+                candy_machine_program: CANDY_MACHINE_ID,
+                candy_machine: candy_machine_id,
+                candy_machine_authority_pda: authority_pda,
+                payer,
+                minter: receiver,
+                nft_mint: nft_mint.pubkey(),
+                nft_mint_authority: payer,
+                nft_metadata: metadata_pda,
+                nft_master_edition: master_edition_pda,
+                token: Some(token),
+                token_record,
+                collection_delegate_record,
+                collection_mint,
+                collection_metadata: find_metadata_pda(&collection_mint),
+                collection_master_edition: find_master_edition_pda(&collection_mint),
+                collection_update_authority: metadata.update_authority,
+                token_metadata_program: metaplex_program_id,
+                spl_token_program: TOKEN_PROGRAM_ID,
+                spl_ata_program: Some(spl_associated_token_account::ID),
+                system_program: system_program::id(),
+                sysvar_instructions: sysvar::instructions::ID,
+                recent_slothashes: sysvar::slot_hashes::ID,
+                authorization_rules_program: None,
+                authorization_rules: None,
+            })
+            .accounts(remaining_accounts)
+            .args(GuardMintInstruction {
+                mint_args: Vec::new(),
+                label: guard_mint.label.clone(),
+            })
+            .instructions()?
+    } else {
+        mint_ix.instructions()?
+    };
 
     for account_meta in &mut mint_ix[0].accounts {
         if account_meta.pubkey == nft_mint.pubkey() {
@@ -301,31 +798,75 @@ pub async fn mint(
     let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
     let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
 
-    let builder = program
-        .request()
-        .instruction(compute_units)
-        .instruction(priority_fee)
-        .instruction(mint_ix[0].clone())
-        .signer(&nft_mint);
+    Ok(vec![compute_units, priority_fee, mint_ix.remove(0)])
+}
 
-    let sig = builder.send()?;
+/// Mint a Metaplex Core asset directly from a core candy machine (the payer must be the
+/// mint authority).
+pub async fn mint_core(
+    config: Arc<SugarConfig>,
+    candy_machine_id: Pubkey,
+    candy_machine_state: Arc<CandyMachine>,
+    receiver: Pubkey,
+) -> Result<(Signature, Pubkey)> {
+    let rpc_client = throttled_client_with_commitment(&config.rpc_url);
+    let payer = config.keypair.pubkey();
 
-    if let Err(_) | Ok(Response { value: None, .. }) = program
-        .rpc()
-        .get_account_with_commitment(&metadata_pda, CommitmentConfig::processed())
+    if candy_machine_state.mint_authority != payer {
+        return Err(anyhow!(
+            "Payer is not the Candy Machine mint authority, mint disallowed."
+        ));
+    }
+
+    let asset = Keypair::new();
+    let collection = candy_machine_state.collection_mint;
+
+    let mint_ix = mint_asset_ix(
+        &candy_machine_id,
+        &collection,
+        &payer,
+        &receiver,
+        &asset.pubkey(),
+    )?;
+
+    let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
+    let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
+
+    let instructions = vec![compute_units, priority_fee, mint_ix];
+    let signers: Vec<&(dyn Signer + Sync)> = vec![&config.keypair, &asset];
+
+    simulate_instructions(&rpc_client, &instructions, &payer)?;
+
+    let sig = send_idempotent(&rpc_client, &instructions, &signers, None).await?;
+
+    check_minted(&rpc_client, &asset.pubkey(), &sig)?;
+
+    info!("Minted! TxId: {}", sig);
+
+    Ok((sig, asset.pubkey()))
+}
+
+/// Check that the minted account exists; the transaction succeeds without creating
+/// it when a guard charges the bot tax.
+fn check_minted(rpc_client: &RpcClient, account: &Pubkey, signature: &Signature) -> Result<()> {
+    // planned mints are not sent
+    if is_dry_run() {
+        return Ok(());
+    }
+
+    if let Err(_) | Ok(Response { value: None, .. }) =
+        rpc_client.get_account_with_commitment(account, CommitmentConfig::processed())
     {
-        let cluster_param = match get_cluster(program.rpc()).unwrap_or(Cluster::Mainnet) {
+        let cluster_param = match get_cluster(rpc_client).unwrap_or(Cluster::Mainnet) {
             Cluster::Devnet => "?devnet",
             _ => "",
         };
         return Err(anyhow!(
             "Minting most likely failed with a bot tax. Check the transaction link for more details: https://explorer.solana.com/tx/{}{}",
-            sig.to_string(),
+            signature,
             cluster_param,
         ));
     }
 
-    info!("Minted! TxId: {}", sig);
-
-    Ok((sig, nft_mint.pubkey()))
+    Ok(())
 }
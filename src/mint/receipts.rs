@@ -0,0 +1,273 @@
+use std::{fs::OpenOptions, io::Write, sync::Mutex};
+
+use anchor_client::solana_sdk::signature::Signature;
+use chrono::{SecondsFormat, Utc};
+use mpl_token_metadata::state::{Metadata, TokenMetadataAccount};
+use solana_client::rpc_client::RpcClient;
+
+use crate::{
+    candy_machine::read_config_line, common::*, dry_run::is_dry_run, pdas::find_metadata_pda,
+};
+
+/// Header of the receipts file.
+const RECEIPTS_HEADER: &str = "index,mint,recipient,signature,slot,timestamp";
+
+/// Maximum number of signatures per `getSignatureStatuses` request.
+const MAX_SIGNATURES: usize = 256;
+
+/// Maximum number of accounts per `getMultipleAccounts` request.
+const MAX_ACCOUNTS: usize = 100;
+
+/// Record of a successful mint.
+#[derive(Debug, Clone)]
+pub struct MintReceipt {
+    /// Index of the item minted (the edition number for editions); empty when unknown.
+    pub index: Option<u64>,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub signature: Signature,
+    pub slot: Option<u64>,
+    pub timestamp: String,
+}
+
+impl MintReceipt {
+    /// Create a receipt for a mint transaction. The slot is looked up when the receipts
+    /// are written, together with the receipts of the other mints.
+    pub fn new(index: Option<u64>, mint: Pubkey, recipient: Pubkey, signature: Signature) -> Self {
+        MintReceipt {
+            index,
+            mint,
+            recipient,
+            signature,
+            slot: None,
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        }
+    }
+
+    fn to_csv_line(&self) -> String {
+        format!(
+            "{},{},{},{},{},{}\n",
+            self.index.map(|i| i.to_string()).unwrap_or_default(),
+            self.mint,
+            self.recipient,
+            self.signature,
+            self.slot.map(|s| s.to_string()).unwrap_or_default(),
+            self.timestamp
+        )
+    }
+}
+
+/// Collects the receipts of a run and appends them to a CSV file, one complete line per
+/// write. Nothing is written in a dry run.
+pub struct ReceiptWriter {
+    path: String,
+    receipts: Mutex<Vec<MintReceipt>>,
+}
+
+impl ReceiptWriter {
+    pub fn open(path: &str) -> Result<Self> {
+        if !is_dry_run() {
+            // fails before minting when the file cannot be written
+            open_receipts_file(path)?;
+        }
+
+        Ok(ReceiptWriter {
+            path: path.to_string(),
+            receipts: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub fn append(&self, receipt: MintReceipt) {
+        self.receipts.lock().unwrap().push(receipt);
+    }
+
+    /// Removes the receipts of mints that were not finalized.
+    pub fn discard(&self, mints: &[Pubkey]) {
+        self.receipts
+            .lock()
+            .unwrap()
+            .retain(|receipt| !mints.contains(&receipt.mint));
+    }
+
+    /// Looks up the slot of the mint transactions (and the item index of the candy
+    /// machine mints, when `item_indices` is set) in batches, then writes the receipts.
+    /// Returns the number of receipts written.
+    pub fn write(
+        &self,
+        client: &RpcClient,
+        item_indices: Option<&HashMap<String, u64>>,
+    ) -> Result<usize> {
+        let mut receipts = std::mem::take(&mut *self.receipts.lock().unwrap());
+
+        if receipts.is_empty() || is_dry_run() {
+            return Ok(0);
+        }
+
+        // the receipts are written even if the lookups fail, without slot or index
+        if let Err(err) = resolve_slots(client, &mut receipts) {
+            warn!(
+                "Failed to look up the slot of the mint transactions: {}",
+                err
+            );
+        }
+
+        if let Some(item_indices) = item_indices {
+            if let Err(err) = resolve_item_indices(client, item_indices, &mut receipts) {
+                warn!("Failed to look up the index of the minted items: {}", err);
+            }
+        }
+
+        let mut file = open_receipts_file(&self.path)?;
+
+        for receipt in &receipts {
+            file.write_all(receipt.to_csv_line().as_bytes())?;
+        }
+        file.sync_data()?;
+
+        Ok(receipts.len())
+    }
+}
+
+/// Writes the receipts of the items minted, if any. The item index of the mints from a
+/// candy machine is resolved from its config lines.
+pub fn write_receipts(
+    rpc: &RpcClient,
+    receipts: &ReceiptWriter,
+    candy_machine: Option<(&Pubkey, &CandyMachine)>,
+    path: &str,
+) -> Result<()> {
+    if is_dry_run() {
+        return Ok(());
+    }
+
+    let item_indices = match candy_machine {
+        Some((candy_pubkey, candy_machine)) => match rpc.get_account_data(candy_pubkey) {
+            Ok(data) => config_line_indices(&data, candy_machine),
+            Err(err) => {
+                warn!(
+                    "Failed to load the config lines of the candy machine: {}",
+                    err
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
+    let written = receipts.write(rpc, item_indices.as_ref())?;
+
+    if written > 0 {
+        println!("\n{} mint receipt(s) written to {}", written, path);
+    }
+
+    Ok(())
+}
+
+fn open_receipts_file(path: &str) -> Result<File> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| anyhow!("Failed to open receipts file '{}': {}", path, e))?;
+
+    if file.metadata()?.len() == 0 {
+        file.write_all(format!("{}\n", RECEIPTS_HEADER).as_bytes())?;
+    }
+
+    Ok(file)
+}
+
+fn resolve_slots(client: &RpcClient, receipts: &mut [MintReceipt]) -> Result<()> {
+    for chunk in receipts.chunks_mut(MAX_SIGNATURES) {
+        let signatures: Vec<Signature> = chunk.iter().map(|receipt| receipt.signature).collect();
+        let statuses = client.get_signature_statuses(&signatures)?.value;
+
+        for (receipt, status) in chunk.iter_mut().zip(statuses) {
+            receipt.slot = status.map(|status| status.slot);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the index of the config lines of a candy machine by their uri, used to find the
+/// item index of the minted NFTs. Candy machines with hidden settings share a single uri
+/// between the items, so their index cannot be found.
+fn config_line_indices(data: &[u8], candy_machine: &CandyMachine) -> Option<HashMap<String, u64>> {
+    let settings = candy_machine.data.config_line_settings.as_ref()?;
+    let mut indices = HashMap::new();
+
+    for index in 0..candy_machine.data.items_available {
+        let (_, uri) = read_config_line(data, &candy_machine.data, index as usize).ok()?;
+        indices.insert(settings.prefix_uri.clone() + &uri, index);
+    }
+
+    Some(indices)
+}
+
+fn resolve_item_indices(
+    client: &RpcClient,
+    item_indices: &HashMap<String, u64>,
+    receipts: &mut [MintReceipt],
+) -> Result<()> {
+    for chunk in receipts.chunks_mut(MAX_ACCOUNTS) {
+        let metadata_pubkeys: Vec<Pubkey> = chunk
+            .iter()
+            .map(|receipt| find_metadata_pda(&receipt.mint))
+            .collect();
+        let accounts = client.get_multiple_accounts(&metadata_pubkeys)?;
+
+        for (receipt, account) in chunk.iter_mut().zip(accounts) {
+            if let Some(account) = account {
+                let metadata = Metadata::safe_deserialize(&account.data)?;
+                let uri = metadata.data.uri.trim_matches(char::from(0));
+                receipt.index = item_indices.get(uri).copied();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_receipt_csv_line() {
+    let mut receipt = MintReceipt::new(
+        None,
+        Pubkey::default(),
+        Pubkey::default(),
+        Signature::default(),
+    );
+    let empty = receipt.to_csv_line();
+    assert!(empty.starts_with(&format!(",{},", Pubkey::default())));
+
+    receipt.index = Some(7);
+    receipt.slot = Some(42);
+    let line = receipt.to_csv_line();
+    assert!(line.starts_with("7,"));
+    assert_eq!(line.split(',').nth(4), Some("42"));
+    assert_eq!(line.split(',').count(), RECEIPTS_HEADER.split(',').count());
+}
+
+#[test]
+fn test_discard_receipts() {
+    let writer = ReceiptWriter {
+        path: String::new(),
+        receipts: Mutex::new(Vec::new()),
+    };
+    let dropped = Pubkey::new_unique();
+
+    for mint in [Pubkey::new_unique(), dropped] {
+        writer.append(MintReceipt::new(
+            None,
+            mint,
+            Pubkey::default(),
+            Signature::default(),
+        ));
+    }
+
+    writer.discard(&[dropped]);
+
+    let receipts = writer.receipts.lock().unwrap();
+    assert_eq!(receipts.len(), 1);
+    assert_ne!(receipts[0].mint, dropped);
+}
@@ -0,0 +1,162 @@
+use console::style;
+use dialoguer::{Input, Select};
+
+use crate::{common::*, create_config::ConfigTemplate, utils::get_dialoguer_theme};
+
+/// A common workflow of the menu and the arguments of the command it runs.
+struct Workflow {
+    label: &'static str,
+    args: &'static [&'static str],
+    /// Asks for the value of the last argument, when it depends on the user.
+    prompt: Option<fn() -> Result<String>>,
+}
+
+const WORKFLOWS: &[Workflow] = &[
+    Workflow {
+        label: "Launch a candy machine (create config, upload, deploy and verify)",
+        args: &["launch"],
+        prompt: None,
+    },
+    Workflow {
+        label: "Create a config file (interactive)",
+        args: &["config", "create"],
+        prompt: None,
+    },
+    Workflow {
+        label: "Create a config file from a template",
+        args: &["config", "create", "--template"],
+        prompt: Some(template_name),
+    },
+    Workflow {
+        label: "Validate the assets",
+        args: &["validate"],
+        prompt: None,
+    },
+    Workflow {
+        label: "Upload the assets",
+        args: &["upload"],
+        prompt: None,
+    },
+    Workflow {
+        label: "Deploy the candy machine",
+        args: &["deploy"],
+        prompt: None,
+    },
+    Workflow {
+        label: "Verify the deployment",
+        args: &["verify"],
+        prompt: None,
+    },
+    Workflow {
+        label: "Show the candy machine",
+        args: &["show"],
+        prompt: None,
+    },
+    Workflow {
+        label: "Show the guards",
+        args: &["guard", "show"],
+        prompt: None,
+    },
+    Workflow {
+        label: "Mint from the candy machine",
+        args: &["mint", "--number"],
+        prompt: Some(mint_number),
+    },
+    Workflow {
+        label: "Show the costs of the launch",
+        args: &["costs"],
+        prompt: None,
+    },
+    Workflow {
+        label: "List all commands",
+        args: &["help"],
+        prompt: None,
+    },
+];
+
+/// Shows the menu of common workflows (when `sugar` runs without arguments) and returns
+/// the command line of the selected one, or `None` when the user quits.
+pub fn select_workflow() -> Result<Option<Vec<String>>> {
+    let theme = get_dialoguer_theme();
+
+    println!(
+        "{}\n",
+        style("Welcome to Sugar! Pick a workflow to get started, or run 'sugar help'.").bold()
+    );
+
+    let mut items: Vec<&str> = WORKFLOWS.iter().map(|workflow| workflow.label).collect();
+    items.push("Quit");
+
+    let selection = Select::with_theme(&theme)
+        .with_prompt("What do you want to do?")
+        .items(&items)
+        .default(0)
+        .interact_opt()?;
+
+    let workflow = match selection.and_then(|index| WORKFLOWS.get(index)) {
+        Some(workflow) => workflow,
+        None => return Ok(None),
+    };
+
+    let mut command_args: Vec<String> = workflow.args.iter().map(|arg| arg.to_string()).collect();
+
+    if let Some(prompt) = workflow.prompt {
+        command_args.push(prompt()?);
+    }
+
+    println!(
+        "\n{} sugar {}\n",
+        style(">>>").magenta(),
+        command_args.join(" ")
+    );
+
+    let mut cli_args = vec!["sugar".to_string()];
+    cli_args.extend(command_args);
+
+    Ok(Some(cli_args))
+}
+
+fn template_name() -> Result<String> {
+    let names: Vec<&str> = ConfigTemplate::ALL.iter().map(|t| t.name()).collect();
+
+    let index = Select::with_theme(&get_dialoguer_theme())
+        .with_prompt("Which template?")
+        .items(&names)
+        .default(0)
+        .interact()?;
+
+    Ok(names[index].to_string())
+}
+
+fn mint_number() -> Result<String> {
+    let number: u64 = Input::with_theme(&get_dialoguer_theme())
+        .with_prompt("How many NFTs do you want to mint?")
+        .default(1)
+        .interact_text()?;
+
+    Ok(number.to_string())
+}
+
+#[test]
+fn test_workflow_commands() {
+    use clap::{ErrorKind, Parser};
+
+    use crate::cli::Cli;
+
+    for workflow in WORKFLOWS {
+        let mut cli_args = vec!["sugar".to_string()];
+        cli_args.extend(workflow.args.iter().map(|arg| arg.to_string()));
+
+        // a value the user could enter at the prompt
+        if workflow.prompt.is_some() {
+            cli_args.push(match workflow.args.last() {
+                Some(&"--template") => ConfigTemplate::ALL[0].name().to_string(),
+                _ => "1".to_string(),
+            });
+        }
+
+        if let Err(err) = Cli::try_parse_from(&cli_args) {
+            assert_eq!(err.kind(), ErrorKind::DisplayHelp, "{}", workflow.label);
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use std::{env, fs::File, path::Path};
+use std::{env, fs::File, path::Path, str::FromStr};
 
+use anchor_client::solana_sdk::pubkey::Pubkey;
 use anyhow::{anyhow, Result};
 use console::style;
 use lazy_static::lazy_static;
@@ -53,6 +54,11 @@ pub fn parse_sugar_errors(msg: &str) -> String {
             Regex::new(r"(0x[A-Za-z0-9]+)").expect("Failed to compile parse_client_error regex.");
     }
 
+    // the program log identifies the failing program, so the code is not ambiguous
+    if let Some(decoded) = parse_program_failure(msg) {
+        return decoded;
+    }
+
     let mat = RE.find(msg);
 
     // If there's an RPC error code match in the message, try to parse it, otherwise return the message back.
@@ -65,6 +71,22 @@ pub fn parse_sugar_errors(msg: &str) -> String {
     }
 }
 
+/// Decode the first program failure of the transaction logs (e.g., "Program <id> failed:
+/// custom program error: 0x177b"), which is the innermost program that failed.
+pub fn parse_program_failure(logs: &str) -> Option<String> {
+    lazy_static! {
+        static ref RE: Regex =
+            Regex::new(r"Program (\w+) failed: custom program error: 0x([0-9A-Fa-f]+)")
+                .expect("Failed to compile parse_program_failure regex.");
+    }
+
+    let captures = RE.captures(logs)?;
+    let program_id = Pubkey::from_str(&captures[1]).ok()?;
+    let code = u32::from_str_radix(&captures[2], 16).ok()?;
+
+    decode_program_error(&program_id, code)
+}
+
 fn find_external_program_error(code: String) -> String {
     let code = code.to_uppercase();
 
@@ -0,0 +1,260 @@
+use std::fmt;
+
+use crate::config::{parse_date, Cluster, GuardSet};
+
+/// Grade of a preflight check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Grade {
+    Pass,
+    Warning,
+    Fail,
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Grade::Pass => write!(f, "PASS"),
+            Grade::Warning => write!(f, "WARN"),
+            Grade::Fail => write!(f, "FAIL"),
+        }
+    }
+}
+
+/// Result of a preflight check, with a hint to fix it when it did not pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub grade: Grade,
+    /// Guard set the check applies to ("default" or the label of a group).
+    pub scope: String,
+    pub title: String,
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(scope: &str, title: &str) -> Self {
+        Self {
+            grade: Grade::Pass,
+            scope: scope.to_string(),
+            title: title.to_string(),
+            hint: None,
+        }
+    }
+
+    fn issue(grade: Grade, scope: &str, title: &str, hint: &str) -> Self {
+        Self {
+            grade,
+            scope: scope.to_string(),
+            title: title.to_string(),
+            hint: Some(hint.to_string()),
+        }
+    }
+}
+
+/// Returns the guards a minter goes through in a group: the guards of the group with
+/// the missing ones taken from the default guard set.
+pub fn effective_guards(default: &GuardSet, group: &GuardSet) -> GuardSet {
+    let mut guards = group.clone();
+
+    macro_rules! inherit {
+        ($($field:ident),*) => {
+            $(if guards.$field.is_none() {
+                guards.$field = default.$field.clone();
+            })*
+        };
+    }
+
+    inherit!(
+        bot_tax,
+        sol_payment,
+        token_payment,
+        start_date,
+        third_party_signer,
+        token_gate,
+        gatekeeper,
+        end_date,
+        allow_list,
+        mint_limit,
+        nft_payment,
+        redeemed_amount,
+        address_gate,
+        nft_gate,
+        nft_burn,
+        token_burn,
+        freeze_sol_payment,
+        freeze_token_payment,
+        program_gate,
+        allocation,
+        token2022_payment
+    );
+
+    guards
+}
+
+/// Audits a guard set for common mistakes.
+pub fn check_guard_set(scope: &str, guards: &GuardSet, cluster: &Cluster) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let sol_price = guards
+        .sol_payment
+        .as_ref()
+        .map(|payment| payment.value)
+        .or_else(|| {
+            guards
+                .freeze_sol_payment
+                .as_ref()
+                .map(|payment| payment.value)
+        });
+    let paid = sol_price.map_or(false, |price| price > 0.0)
+        || guards.token_payment.is_some()
+        || guards.freeze_token_payment.is_some()
+        || guards.token2022_payment.is_some()
+        || guards.nft_payment.is_some()
+        || guards.nft_burn.is_some()
+        || guards.token_burn.is_some();
+    // only the minters allowed by the gate can drain the mint
+    let gated = guards.allow_list.is_some()
+        || guards.gatekeeper.is_some()
+        || guards.third_party_signer.is_some()
+        || guards.address_gate.is_some();
+
+    // price
+
+    match sol_price {
+        Some(price) if price <= 0.0 => checks.push(Check::issue(
+            if matches!(cluster, Cluster::Mainnet) {
+                Grade::Fail
+            } else {
+                Grade::Warning
+            },
+            scope,
+            "sol payment of 0",
+            "Set the price in the solPayment guard or remove the guard for a free mint.",
+        )),
+        _ => checks.push(Check::pass(scope, "price")),
+    }
+
+    // bot tax
+
+    if guards.bot_tax.is_some() {
+        checks.push(Check::pass(scope, "bot tax"));
+    } else if gated {
+        checks.push(Check::pass(scope, "gated mint"));
+    } else if paid {
+        checks.push(Check::issue(
+            Grade::Warning,
+            scope,
+            "no bot tax",
+            "Add a botTax guard (e.g. 0.01 SOL with lastInstruction) to charge failed mints.",
+        ));
+    } else {
+        checks.push(Check::issue(
+            Grade::Fail,
+            scope,
+            "free mint without bot tax",
+            "Bots can drain a free mint, add a botTax guard with lastInstruction enabled.",
+        ));
+    }
+
+    // dates
+
+    match &guards.start_date {
+        Some(start_date) => match parse_date(&start_date.date) {
+            Ok(start) => {
+                let end = guards
+                    .end_date
+                    .as_ref()
+                    .and_then(|end_date| parse_date(&end_date.date).ok());
+
+                if end.map_or(false, |end| end <= start) {
+                    checks.push(Check::issue(
+                        Grade::Fail,
+                        scope,
+                        "end date before start date",
+                        "Move the endDate after the startDate, nobody would be able to mint.",
+                    ));
+                } else {
+                    checks.push(Check::pass(scope, "start date"));
+                }
+            }
+            Err(_) => checks.push(Check::issue(
+                Grade::Fail,
+                scope,
+                "invalid start date",
+                "Use a date like \"2024-06-01T18:00:00Z\" in the startDate guard.",
+            )),
+        },
+        None => checks.push(Check::issue(
+            Grade::Warning,
+            scope,
+            "start date missing",
+            "Minting opens as soon as the guards are added, set a startDate guard.",
+        )),
+    }
+
+    // mint limit
+
+    if guards.mint_limit.is_some() {
+        checks.push(Check::pass(scope, "mint limit"));
+    } else {
+        checks.push(Check::issue(
+            Grade::Warning,
+            scope,
+            "mint limit absent",
+            "A single wallet can mint the whole collection, add a mintLimit guard.",
+        ));
+    }
+
+    checks
+}
+
+#[test]
+fn test_free_mint_checks() {
+    use crate::config::{AllowList, BotTax, SolPayment};
+    use anchor_lang::prelude::Pubkey;
+
+    let free = GuardSet {
+        sol_payment: Some(SolPayment {
+            value: 0.0,
+            destination: Pubkey::default(),
+        }),
+        ..Default::default()
+    };
+    let checks = check_guard_set("default", &free, &Cluster::Mainnet);
+    let fails: Vec<&str> = checks
+        .iter()
+        .filter(|check| check.grade == Grade::Fail)
+        .map(|check| check.title.as_str())
+        .collect();
+
+    assert_eq!(fails, vec!["sol payment of 0", "free mint without bot tax"]);
+
+    // the bot tax of the default guard set applies to the groups
+    let default = GuardSet {
+        bot_tax: Some(BotTax {
+            value: 0.01,
+            last_instruction: true,
+        }),
+        ..Default::default()
+    };
+    let guards = effective_guards(&default, &free);
+    let checks = check_guard_set("public", &guards, &Cluster::Devnet);
+
+    assert!(checks.iter().all(|check| check.grade != Grade::Fail));
+
+    // every guard of the default guard set is inherited
+    let default = GuardSet {
+        allow_list: Some(AllowList {
+            merkle_root: "0".repeat(64),
+        }),
+        ..Default::default()
+    };
+    let guards = effective_guards(&default, &free);
+    assert!(guards.allow_list.is_some());
+
+    // a gated free mint is protected without a bot tax
+    let checks = check_guard_set("allowed", &guards, &Cluster::Mainnet);
+    assert!(checks
+        .iter()
+        .all(|check| check.grade != Grade::Fail || check.title == "sol payment of 0"));
+    assert!(checks.iter().any(|check| check.title == "gated mint"));
+}
@@ -0,0 +1,5 @@
+pub mod checks;
+pub mod process;
+
+pub use checks::*;
+pub use process::*;
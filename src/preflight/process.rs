@@ -0,0 +1,189 @@
+use console::style;
+
+use crate::{
+    cache::load_cache,
+    candy_machine::get_candy_machine_state,
+    common::*,
+    config::{get_config_data, Cluster, SugarConfig},
+    core_candy_machine::{
+        get_candy_machine_program, get_core_candy_machine_state, CORE_CANDY_MACHINE_ID,
+    },
+    preflight::*,
+    rate_limit::throttled_client,
+    utils::*,
+};
+
+pub struct PreflightArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub config: String,
+    pub cache: String,
+}
+
+pub fn process_preflight(args: PreflightArgs) -> Result<()> {
+    println!(
+        "{} {}Auditing the guards of '{}'",
+        style("[1/1]").bold().dim(),
+        GUARD_EMOJI,
+        args.config
+    );
+
+    let config_data = get_config_data(&args.config)?;
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    let rpc_client = throttled_client(&sugar_config.rpc_url);
+    let cluster = get_cluster(&rpc_client).unwrap_or(Cluster::Unknown);
+
+    let mut checks = Vec::new();
+
+    match &config_data.guards {
+        Some(guards) => {
+            match &guards.groups {
+                // with groups, minting always goes through one of them
+                Some(groups) if !groups.is_empty() => {
+                    for group in groups {
+                        let group_guards = effective_guards(&guards.default, &group.guards);
+                        checks.extend(check_guard_set(&group.label, &group_guards, &cluster));
+                    }
+                }
+                _ => checks.extend(check_guard_set("default", &guards.default, &cluster)),
+            }
+
+            checks.push(check_wrapped(&sugar_config, &args.cache)?);
+        }
+        None => checks.push(Check {
+            grade: Grade::Fail,
+            scope: "config".to_string(),
+            title: "no guards configured".to_string(),
+            hint: Some(
+                "Without a candy guard only the authority can mint, add a \"guards\" section."
+                    .to_string(),
+            ),
+        }),
+    }
+
+    pb.finish_and_clear();
+
+    print_checks(&checks);
+
+    let fails = checks
+        .iter()
+        .filter(|check| check.grade == Grade::Fail)
+        .count();
+
+    if fails > 0 {
+        return Err(anyhow!(
+            "{} preflight check(s) failed, fix them before launching.",
+            fails
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that the candy machine, once deployed, is wrapped by the candy guard.
+fn check_wrapped(sugar_config: &SugarConfig, cache: &str) -> Result<Check> {
+    let scope = "candy machine".to_string();
+    let cache = if Path::new(cache).exists() {
+        Some(load_cache(cache, false)?)
+    } else {
+        None
+    };
+
+    let candy_machine = match cache
+        .as_ref()
+        .filter(|cache| !cache.program.candy_machine.is_empty())
+    {
+        Some(cache) => Pubkey::from_str(&cache.program.candy_machine)?,
+        None => {
+            return Ok(Check {
+                grade: Grade::Pass,
+                scope,
+                title: "not deployed yet (launch wraps the guards)".to_string(),
+                hint: None,
+            })
+        }
+    };
+
+    let not_wrapped = |hint: &str| Check {
+        grade: Grade::Fail,
+        scope: scope.clone(),
+        title: "guards not wrapped".to_string(),
+        hint: Some(hint.to_string()),
+    };
+
+    let candy_guard = match cache.map(|cache| cache.program.candy_guard) {
+        Some(candy_guard) if !candy_guard.is_empty() => Pubkey::from_str(&candy_guard)?,
+        _ => {
+            return Ok(not_wrapped(
+                "The candy machine has no candy guard, run 'sugar guard add'.",
+            ))
+        }
+    };
+
+    let core = get_candy_machine_program(sugar_config, &candy_machine)? == CORE_CANDY_MACHINE_ID;
+    let mint_authority = if core {
+        get_core_candy_machine_state(sugar_config, &candy_machine)?.mint_authority
+    } else {
+        get_candy_machine_state(sugar_config, &candy_machine)?.mint_authority
+    };
+
+    if mint_authority == candy_guard {
+        Ok(Check {
+            grade: Grade::Pass,
+            scope,
+            title: "guards wrapped".to_string(),
+            hint: None,
+        })
+    } else {
+        Ok(not_wrapped(&format!(
+            "The mint authority is {mint_authority}, anyone with it bypasses the guards. \
+             Run 'sugar guard wrap'."
+        )))
+    }
+}
+
+fn print_checks(checks: &[Check]) {
+    println!();
+
+    for check in checks {
+        let grade = match check.grade {
+            Grade::Pass => style(check.grade.to_string()).green(),
+            Grade::Warning => style(check.grade.to_string()).yellow(),
+            Grade::Fail => style(check.grade.to_string()).red(),
+        }
+        .bold();
+
+        println!(
+            "  {} {} {}",
+            grade,
+            style(format!("[{}]", check.scope)).dim(),
+            check.title
+        );
+
+        if let Some(hint) = &check.hint {
+            println!("       {} {}", style("->").dim(), hint);
+        }
+    }
+
+    let count = |grade: Grade| checks.iter().filter(|check| check.grade == grade).count();
+    let summary = format!(
+        "\n{} passed, {} warning(s), {} failed",
+        count(Grade::Pass),
+        count(Grade::Warning),
+        count(Grade::Fail)
+    );
+
+    let worst = checks.iter().map(|check| check.grade).max();
+    println!(
+        "{}",
+        match worst {
+            Some(Grade::Fail) => style(summary).red().bold(),
+            Some(Grade::Warning) => style(summary).yellow().bold(),
+            _ => style(summary).green().bold(),
+        }
+    );
+}
@@ -1,5 +1,39 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
 use phf::phf_map;
 
+use crate::candy_machine::CANDY_MACHINE_ID;
+
+/// First custom error code of Anchor programs; lower codes are Anchor framework errors.
+const ANCHOR_ERROR_OFFSET: u32 = 6000;
+
+/// Decode a custom error code of a program, returning a message with the name of the
+/// program and the error; `None` when the program or the code is unknown.
+pub fn decode_program_error(program_id: &Pubkey, code: u32) -> Option<String> {
+    let key = format!("{code:X}");
+
+    if *program_id == mpl_token_metadata::ID {
+        return METADATA_ERROR
+            .get(key.as_str())
+            .map(|e| format!("Token Metadata Error: {e}"));
+    }
+
+    let (name, errors) = if *program_id == CANDY_MACHINE_ID {
+        ("Candy Machine", &CANDY_CORE_ERROR)
+    } else if *program_id == mpl_candy_guard::ID {
+        ("Candy Guard", &CANDY_GUARD_ERROR)
+    } else {
+        return None;
+    };
+
+    if code < ANCHOR_ERROR_OFFSET {
+        ANCHOR_ERROR
+            .get(key.as_str())
+            .map(|e| format!("Anchor Error: {e}"))
+    } else {
+        errors.get(key.as_str()).map(|e| format!("{name}: {e}"))
+    }
+}
+
 pub static METADATA_ERROR: phf::Map<&'static str, &'static str> = phf_map! {
     "0" => "InstructionUnpackError: Failed to unpack instruction data",
     "1" => "InstructionPackError: Failed to pack instruction data",
@@ -311,3 +345,16 @@ pub static ANCHOR_ERROR: phf::Map<&'static str, &'static str> = phf_map! {
     "1005" => "TryingToInitPayerAsProgramAccount: You cannot/should not initialize the payer account as a program account",
     "1388" => "Deprecated: The API being used is deprecated and should no longer be used",
 };
+
+#[test]
+fn test_decode_program_error() {
+    assert_eq!(
+        decode_program_error(&mpl_candy_guard::ID, 0x177b).unwrap(),
+        "Candy Guard: CandyMachineEmpty: Candy machine is empty"
+    );
+    assert_eq!(
+        decode_program_error(&CANDY_MACHINE_ID, 0x1776).unwrap(),
+        "Candy Machine: CandyMachineEmpty: Candy machine is empty"
+    );
+    assert!(decode_program_error(&Pubkey::default(), 0x1776).is_none());
+}
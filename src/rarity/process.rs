@@ -0,0 +1,288 @@
+use std::{collections::BTreeMap, fs, io::Write};
+
+use console::style;
+use serde::Serialize;
+
+use crate::{
+    cache::load_cache,
+    common::*,
+    upload::{get_asset_pairs, AssetPair},
+    utils::*,
+};
+
+/// Value used for the trait types that an item does not have.
+const NONE_VALUE: &str = "None";
+
+pub struct RarityArgs {
+    pub assets_dir: String,
+    pub cache: String,
+    pub from_cache: bool,
+    pub output: String,
+}
+
+/// Attributes of an item of the collection.
+pub struct ItemTraits {
+    pub id: String,
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraitRarity {
+    pub trait_type: String,
+    pub value: String,
+    pub count: usize,
+    pub frequency: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemRarity {
+    pub id: String,
+    pub name: String,
+    pub rank: usize,
+    pub score: f64,
+    pub traits: Vec<TraitRarity>,
+}
+
+pub async fn process_rarity(args: RarityArgs) -> Result<()> {
+    println!(
+        "{} {}Loading metadata",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let items = if args.from_cache {
+        load_cache_traits(&args.cache).await?
+    } else {
+        load_asset_traits(&args.assets_dir)?
+    };
+
+    if items.is_empty() {
+        return Err(anyhow!("No items found to calculate the rarity"));
+    }
+
+    println!(
+        "\n{} {}Calculating rarity of {} items",
+        style("[2/2]").bold().dim(),
+        PAPER_EMOJI,
+        items.len()
+    );
+
+    let rarity = compute_rarity(&items);
+
+    if args.output.to_lowercase().ends_with(".csv") {
+        let mut file = File::create(&args.output)?;
+        writeln!(file, "rank,id,name,score")?;
+
+        for item in &rarity {
+            writeln!(
+                file,
+                "{},{},\"{}\",{:.4}",
+                item.rank,
+                item.id,
+                item.name.replace('"', "\"\""),
+                item.score
+            )?;
+        }
+    } else {
+        fs::write(&args.output, serde_json::to_string_pretty(&rarity)?)?;
+    }
+
+    for item in rarity.iter().take(5) {
+        println!(
+            "  {}. {} ({:.2})",
+            item.rank,
+            style(&item.name).bold(),
+            item.score
+        );
+    }
+
+    println!("\n{}Rarity saved to '{}'", COMPLETE_EMOJI, args.output);
+
+    Ok(())
+}
+
+/// Compute the statistical rarity of each item, where the score of an item is the sum
+/// of the inverse frequencies of its traits. Items are returned sorted by rank.
+pub fn compute_rarity(items: &[ItemTraits]) -> Vec<ItemRarity> {
+    let total = items.len();
+
+    // every trait type used in the collection; items without it count as "None"
+    let mut trait_types: Vec<&str> = items
+        .iter()
+        .flat_map(|item| item.attributes.iter().map(|(t, _)| t.as_str()))
+        .collect();
+    trait_types.sort_unstable();
+    trait_types.dedup();
+
+    let item_values = |item: &ItemTraits| -> Vec<(String, String)> {
+        trait_types
+            .iter()
+            .map(|trait_type| {
+                let value = item
+                    .attributes
+                    .iter()
+                    .find(|(t, _)| t == trait_type)
+                    .map(|(_, v)| v.clone())
+                    .unwrap_or_else(|| NONE_VALUE.to_string());
+                (trait_type.to_string(), value)
+            })
+            .collect()
+    };
+
+    let mut counts: BTreeMap<(String, String), usize> = BTreeMap::new();
+
+    for item in items {
+        for key in item_values(item) {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+
+    let mut rarity: Vec<ItemRarity> = items
+        .iter()
+        .map(|item| {
+            let traits: Vec<TraitRarity> = item_values(item)
+                .into_iter()
+                .map(|(trait_type, value)| {
+                    let count = counts[&(trait_type.clone(), value.clone())];
+                    TraitRarity {
+                        trait_type,
+                        value,
+                        count,
+                        frequency: count as f64 / total as f64,
+                    }
+                })
+                .collect();
+
+            ItemRarity {
+                id: item.id.clone(),
+                name: item.name.clone(),
+                rank: 0,
+                score: traits.iter().map(|t| 1.0 / t.frequency).sum(),
+                traits,
+            }
+        })
+        .collect();
+
+    rarity.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    // items with the same score share the same rank
+    for index in 0..rarity.len() {
+        rarity[index].rank = if index > 0 && rarity[index].score == rarity[index - 1].score {
+            rarity[index - 1].rank
+        } else {
+            index + 1
+        };
+    }
+
+    rarity
+}
+
+fn parse_traits(id: String, metadata: &Value) -> ItemTraits {
+    let name = metadata["name"].as_str().unwrap_or_default().to_string();
+    let attributes = metadata["attributes"]
+        .as_array()
+        .map(|attributes| {
+            attributes
+                .iter()
+                .filter_map(|attribute| {
+                    let trait_type = attribute["trait_type"].as_str()?.to_string();
+                    let value = match &attribute["value"] {
+                        Value::String(value) => value.clone(),
+                        Value::Null => return None,
+                        value => value.to_string(),
+                    };
+                    Some((trait_type, value))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    ItemTraits {
+        id,
+        name,
+        attributes,
+    }
+}
+
+fn load_asset_traits(assets_dir: &str) -> Result<Vec<ItemTraits>> {
+    let asset_pairs = get_asset_pairs(assets_dir, None)?;
+    let mut pairs: Vec<(&isize, &AssetPair)> = asset_pairs
+        .iter()
+        .filter(|(index, _)| **index != COLLECTION_INDEX)
+        .collect();
+    pairs.sort_by_key(|(index, _)| **index);
+
+    pairs
+        .into_iter()
+        .map(|(index, pair)| {
+            let metadata: Value = serde_json::from_reader(File::open(&pair.metadata)?)
+                .map_err(|e| anyhow!("Failed to parse {}: {}", pair.metadata, e))?;
+            Ok(parse_traits(index.to_string(), &metadata))
+        })
+        .collect()
+}
+
+async fn load_cache_traits(cache_file: &str) -> Result<Vec<ItemTraits>> {
+    let cache = load_cache(cache_file, false)?;
+    let http_client = HttpClient::new();
+    let items: Vec<(&String, &CacheItem)> = cache
+        .items
+        .iter()
+        .filter(|(key, _)| *key != COLLECTION_CACHE_KEY)
+        .collect();
+
+    let pb = progress_bar_with_style(items.len() as u64);
+    pb.set_message("Fetching metadata");
+
+    let mut traits = Vec::with_capacity(items.len());
+
+    for (key, item) in items {
+        if item.metadata_link.is_empty() {
+            return Err(anyhow!("Missing metadata link for item {}", key));
+        }
+
+        let metadata: Value = http_client
+            .get(&item.metadata_link)
+            .send()
+            .await?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch {}: {}", item.metadata_link, e))?;
+
+        traits.push(parse_traits(key.clone(), &metadata));
+        pb.inc(1);
+    }
+
+    pb.finish_and_clear();
+
+    Ok(traits)
+}
+
+#[test]
+fn test_rarest_item_ranks_first() {
+    let item = |id: &str, background: &str, hat: Option<&str>| ItemTraits {
+        id: id.to_string(),
+        name: format!("Item #{id}"),
+        attributes: std::iter::once(("Background".to_string(), background.to_string()))
+            .chain(hat.map(|hat| ("Hat".to_string(), hat.to_string())))
+            .collect(),
+    };
+
+    let items = vec![
+        item("0", "Blue", None),
+        item("1", "Blue", None),
+        item("2", "Blue", Some("Crown")),
+        item("3", "Red", None),
+    ];
+
+    let rarity = compute_rarity(&items);
+
+    assert_eq!(rarity[0].rank, 1);
+    assert_eq!(rarity[2].rank, 3);
+    assert_eq!(rarity[3].rank, 3);
+    assert!(rarity[0].id == "2" || rarity[0].id == "3");
+    assert_eq!(rarity[0].traits.len(), 2);
+}
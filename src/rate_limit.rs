@@ -0,0 +1,322 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use solana_client::{
+    client_error::Result as ClientResult,
+    http_sender::HttpSender,
+    rpc_client::{RpcClient, RpcClientConfig},
+    rpc_request::RpcRequest,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+
+use crate::{common::*, confirmation::commitment};
+
+/// RPC method names used by the throttled calls.
+pub const GET_MULTIPLE_ACCOUNTS: &str = "getMultipleAccounts";
+pub const GET_PROGRAM_ACCOUNTS: &str = "getProgramAccounts";
+pub const SEND_TRANSACTION: &str = "sendTransaction";
+
+/// Default weights of the RPC methods, reflecting how providers account for
+/// them; methods not listed have a weight of 1.
+const DEFAULT_WEIGHTS: [(&str, f64); 3] = [
+    (GET_PROGRAM_ACCOUNTS, 10.0),
+    (GET_MULTIPLE_ACCOUNTS, 2.0),
+    ("getSignaturesForAddress", 2.0),
+];
+
+lazy_static! {
+    static ref RATE_LIMITER: RwLock<Option<RateLimiter>> = RwLock::new(None);
+    static ref WEIGHTS: RwLock<Vec<MethodWeight>> = RwLock::new(Vec::new());
+}
+
+/// Indicates whether the rate limit was set with `--rpc-provider` or `--rps`.
+static EXPLICIT_LIMIT: AtomicBool = AtomicBool::new(false);
+
+/// RPC providers with a preset requests-per-second limit (free tiers).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcProvider {
+    /// Solana public endpoints (api.mainnet-beta/devnet.solana.com).
+    Public,
+    Helius,
+    QuickNode,
+    /// No rate limit (e.g., a local validator or a dedicated node).
+    Unlimited,
+}
+
+impl RpcProvider {
+    /// Requests per second allowed by the provider tier.
+    pub fn requests_per_second(&self) -> Option<f64> {
+        match self {
+            RpcProvider::Public => Some(10.0),
+            RpcProvider::Helius => Some(10.0),
+            RpcProvider::QuickNode => Some(15.0),
+            RpcProvider::Unlimited => None,
+        }
+    }
+
+    /// Detects the provider from the RPC url.
+    pub fn from_url(rpc_url: &str) -> Option<RpcProvider> {
+        if rpc_url.contains("api.mainnet-beta.solana.com")
+            || rpc_url.contains("api.devnet.solana.com")
+            || rpc_url.contains("api.testnet.solana.com")
+        {
+            Some(RpcProvider::Public)
+        } else if rpc_url.contains("helius") {
+            Some(RpcProvider::Helius)
+        } else if rpc_url.contains("quiknode") || rpc_url.contains("quicknode") {
+            Some(RpcProvider::QuickNode)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for RpcProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "public" => Ok(RpcProvider::Public),
+            "helius" => Ok(RpcProvider::Helius),
+            "quicknode" => Ok(RpcProvider::QuickNode),
+            "unlimited" | "none" => Ok(RpcProvider::Unlimited),
+            _ => Err(anyhow!(
+                "Invalid RPC provider '{s}': expected public, helius, quicknode or unlimited"
+            )),
+        }
+    }
+}
+
+/// Weight of an RPC method, in the format `<method>=<weight>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodWeight {
+    pub method: String,
+    pub weight: f64,
+}
+
+impl FromStr for MethodWeight {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (method, weight) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid method weight '{s}': expected <method>=<weight>"))?;
+
+        let weight: f64 = weight
+            .parse()
+            .map_err(|_| anyhow!("Invalid weight '{weight}' for method '{method}'"))?;
+
+        if weight <= 0.0 {
+            return Err(anyhow!("Weight of method '{method}' must be positive"));
+        }
+
+        Ok(MethodWeight {
+            method: method.to_string(),
+            weight,
+        })
+    }
+}
+
+/// Token bucket limiting the requests per second sent to the RPC, shared by all
+/// tasks of the command. Each request consumes the weight of its method.
+pub struct RateLimiter {
+    rate: f64,
+    weights: HashMap<String, f64>,
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64, weights: &[MethodWeight]) -> Self {
+        let mut method_weights: HashMap<String, f64> = DEFAULT_WEIGHTS
+            .iter()
+            .map(|(method, weight)| (method.to_string(), *weight))
+            .collect();
+
+        for weight in weights {
+            method_weights.insert(weight.method.clone(), weight.weight);
+        }
+
+        RateLimiter {
+            rate: requests_per_second,
+            weights: method_weights,
+            bucket: Mutex::new((requests_per_second, Instant::now())),
+        }
+    }
+
+    pub fn weight(&self, method: &str) -> f64 {
+        // a request can never wait for more tokens than the bucket holds
+        self.weights
+            .get(method)
+            .copied()
+            .unwrap_or(1.0)
+            .min(self.rate)
+    }
+
+    /// Reserves the tokens for a request, returning how long to wait before sending it.
+    pub fn reserve(&self, method: &str) -> Duration {
+        let weight = self.weight(method);
+        let mut bucket = self.bucket.lock().unwrap();
+        let (tokens, last) = &mut *bucket;
+
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.rate).min(self.rate);
+        *last = now;
+        *tokens -= weight;
+
+        if *tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-*tokens / self.rate)
+        }
+    }
+}
+
+/// Sets up the global rate limiter. An explicit `--rps` value takes precedence over
+/// the provider preset; without either, the provider is detected from the RPC url
+/// when the client is set up.
+pub fn set_rate_limit(
+    provider: Option<RpcProvider>,
+    requests_per_second: Option<f64>,
+    weights: &[MethodWeight],
+) -> Result<()> {
+    if let Some(rps) = requests_per_second {
+        if rps <= 0.0 {
+            return Err(anyhow!("Requests per second must be positive"));
+        }
+    }
+
+    let limiter = requests_per_second
+        .or_else(|| provider.and_then(|p| p.requests_per_second()))
+        .map(|rps| RateLimiter::new(rps, weights));
+
+    *RATE_LIMITER.write().unwrap() = limiter;
+    *WEIGHTS.write().unwrap() = weights.to_vec();
+    EXPLICIT_LIMIT.store(
+        provider.is_some() || requests_per_second.is_some(),
+        Ordering::SeqCst,
+    );
+
+    Ok(())
+}
+
+/// Applies the preset of the provider detected from the RPC url, unless the rate limit
+/// was set explicitly or already detected.
+pub fn detect_rate_limit(rpc_url: &str) {
+    if EXPLICIT_LIMIT.load(Ordering::SeqCst) || RATE_LIMITER.read().unwrap().is_some() {
+        return;
+    }
+
+    let limiter = RpcProvider::from_url(rpc_url)
+        .and_then(|provider| provider.requests_per_second())
+        .map(|rps| RateLimiter::new(rps, &WEIGHTS.read().unwrap()));
+
+    *RATE_LIMITER.write().unwrap() = limiter;
+}
+
+/// Blocks until a request of `method` can be sent without exceeding the rate limit.
+pub fn throttle(method: &str) {
+    let wait = match RATE_LIMITER.read().unwrap().as_ref() {
+        Some(limiter) => limiter.reserve(method),
+        None => return,
+    };
+
+    if !wait.is_zero() {
+        thread::sleep(wait);
+    }
+}
+
+/// Async version of [`throttle`], for requests sent from tokio tasks.
+pub async fn throttle_async(method: &str) {
+    let wait = match RATE_LIMITER.read().unwrap().as_ref() {
+        Some(limiter) => limiter.reserve(method),
+        None => return,
+    };
+
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// RPC sender that waits for the rate limiter before each request, so every request of
+/// the clients created by [`throttled_client`] is throttled by its method.
+struct ThrottledSender {
+    sender: HttpSender,
+}
+
+#[async_trait]
+impl RpcSender for ThrottledSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        throttle_async(&request.to_string()).await;
+        self.sender.send(request, params).await
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.sender.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.sender.url()
+    }
+}
+
+/// Creates an RPC client throttled by the global rate limiter.
+pub fn throttled_client(rpc_url: &str) -> RpcClient {
+    RpcClient::new_sender(
+        ThrottledSender {
+            sender: HttpSender::new(rpc_url),
+        },
+        RpcClientConfig::default(),
+    )
+}
+
+/// Creates an RPC client throttled by the global rate limiter, with a request timeout.
+pub fn throttled_client_with_timeout(rpc_url: &str, timeout: Duration) -> RpcClient {
+    RpcClient::new_sender(
+        ThrottledSender {
+            sender: HttpSender::new_with_timeout(rpc_url, timeout),
+        },
+        RpcClientConfig::default(),
+    )
+}
+
+/// Creates an RPC client throttled by the global rate limiter, with the commitment of the
+/// transactions sent by sugar.
+pub fn throttled_client_with_commitment(rpc_url: &str) -> RpcClient {
+    RpcClient::new_sender(
+        ThrottledSender {
+            sender: HttpSender::new(rpc_url),
+        },
+        RpcClientConfig::with_commitment(commitment().config()),
+    )
+}
+
+#[test]
+fn test_rate_limiter_reserves_weighted_tokens() {
+    let weights = vec!["getProgramAccounts=4".parse::<MethodWeight>().unwrap()];
+    let limiter = RateLimiter::new(10.0, &weights);
+
+    assert_eq!(limiter.weight(GET_PROGRAM_ACCOUNTS), 4.0);
+    assert_eq!(limiter.weight(GET_MULTIPLE_ACCOUNTS), 2.0);
+    assert_eq!(limiter.weight(SEND_TRANSACTION), 1.0);
+
+    // the bucket starts full: 10 tokens
+    assert!(limiter.reserve(GET_PROGRAM_ACCOUNTS).is_zero());
+    assert!(limiter.reserve(GET_PROGRAM_ACCOUNTS).is_zero());
+    assert!(limiter.reserve(GET_MULTIPLE_ACCOUNTS).is_zero());
+    // the next request needs to wait for ~0.4s worth of tokens
+    assert!(limiter.reserve(GET_PROGRAM_ACCOUNTS) > Duration::from_millis(300));
+
+    assert_eq!(
+        RpcProvider::from_url("https://mainnet.helius-rpc.com/?api-key=x"),
+        Some(RpcProvider::Helius)
+    );
+    assert!("invalid=0".parse::<MethodWeight>().is_err());
+}
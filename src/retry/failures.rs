@@ -0,0 +1,142 @@
+use std::{fmt, fs};
+
+use console::style;
+use serde::Serialize;
+
+use crate::{common::*, dry_run::is_dry_run};
+
+/// Stage of a command where items failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FailureStage {
+    Upload,
+    Deploy,
+}
+
+impl fmt::Display for FailureStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureStage::Upload => write!(f, "upload"),
+            FailureStage::Deploy => write!(f, "deploy"),
+        }
+    }
+}
+
+/// Items that failed at a stage, with the errors that occurred.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Failure {
+    pub stage: FailureStage,
+    /// Cache keys of the items, including the collection item (`-1`).
+    pub indices: Vec<isize>,
+    pub error: String,
+}
+
+/// Report of the items that failed to upload or deploy, read by `sugar retry`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureReport {
+    pub config: String,
+    pub cache: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assets_dir: Option<String>,
+    pub failures: Vec<Failure>,
+}
+
+impl FailureReport {
+    pub fn load(path: &str) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| anyhow!("Failed to open failures report '{}': {}", path, e))?;
+
+        serde_json::from_reader(file)
+            .map_err(|e| anyhow!("Failed to parse failures report '{}': {}", path, e))
+    }
+
+    /// Indices of the failed items of a stage, in ascending order.
+    pub fn indices(&self, stage: FailureStage) -> Vec<isize> {
+        let mut indices: Vec<isize> = self
+            .failures
+            .iter()
+            .filter(|failure| failure.stage == stage)
+            .flat_map(|failure| failure.indices.iter().copied())
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+
+        indices
+    }
+}
+
+/// Replaces the failures of a stage in the report file at `path`, removing the file once
+/// no failures are left. The failures of the other stages are kept.
+pub fn update_failures(
+    path: &str,
+    config: &str,
+    cache: &str,
+    assets_dir: Option<&str>,
+    stage: FailureStage,
+    failure: Option<Failure>,
+) -> Result<()> {
+    // planned commands do not fail items
+    if is_dry_run() {
+        return Ok(());
+    }
+
+    let mut report = if Path::new(path).exists() {
+        FailureReport::load(path)?
+    } else if failure.is_none() {
+        return Ok(());
+    } else {
+        FailureReport::default()
+    };
+
+    report.failures.retain(|failure| failure.stage != stage);
+    report.failures.extend(failure);
+
+    if report.failures.is_empty() {
+        fs::remove_file(path)?;
+        return Ok(());
+    }
+
+    report.config = config.to_string();
+    report.cache = cache.to_string();
+    if assets_dir.is_some() {
+        report.assets_dir = assets_dir.map(String::from);
+    }
+
+    fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    println!(
+        "\nFailed items saved to '{}', retry them with: {}",
+        path,
+        style(format!("sugar retry --from {path}")).bold()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_failure_indices() {
+    let report = FailureReport {
+        failures: vec![
+            Failure {
+                stage: FailureStage::Deploy,
+                indices: vec![7, 3],
+                error: "Transaction error".to_string(),
+            },
+            Failure {
+                stage: FailureStage::Upload,
+                indices: vec![1, -1],
+                error: "Upload error".to_string(),
+            },
+            Failure {
+                stage: FailureStage::Deploy,
+                indices: vec![3, 4],
+                error: "Blockhash not found".to_string(),
+            },
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(report.indices(FailureStage::Deploy), vec![3, 4, 7]);
+    assert_eq!(report.indices(FailureStage::Upload), vec![-1, 1]);
+}
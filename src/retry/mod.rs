@@ -0,0 +1,5 @@
+pub mod failures;
+pub mod process;
+
+pub use failures::*;
+pub use process::*;
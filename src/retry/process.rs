@@ -0,0 +1,125 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use console::style;
+
+use crate::{
+    common::*,
+    deploy::{process_deploy, DeployArgs},
+    retry::{FailureReport, FailureStage},
+    upload::{process_upload, UploadArgs, DEFAULT_MAX_RETRIES},
+    utils::format_index_ranges,
+};
+
+pub struct RetryArgs {
+    pub from: String,
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub interrupted: Arc<AtomicBool>,
+}
+
+/// Retries the items of a failures report: the failed uploads first, then the failed
+/// config lines. Each step updates the report with the items that fail again.
+pub async fn process_retry(args: RetryArgs) -> Result<()> {
+    let report = FailureReport::load(&args.from)?;
+    let upload_indices = report.indices(FailureStage::Upload);
+    let deploy_indices = report.indices(FailureStage::Deploy);
+
+    if upload_indices.is_empty() && deploy_indices.is_empty() {
+        println!("No failed items in '{}'.", args.from);
+        return Ok(());
+    }
+
+    for failure in &report.failures {
+        println!(
+            "{} {} item(s): {}",
+            style(format!("[{}]", failure.stage)).bold().dim(),
+            failure.indices.len(),
+            style(&failure.error).dim()
+        );
+    }
+
+    if !upload_indices.is_empty() {
+        let assets_dir = report.assets_dir.clone().ok_or_else(|| {
+            anyhow!(
+                "The failures report '{}' does not have the assets directory of the upload",
+                args.from
+            )
+        })?;
+        // the collection item cannot be selected, so the upload resumes every item that
+        // is not uploaded yet
+        let indices = if upload_indices.contains(&COLLECTION_INDEX) {
+            println!("\n{} sugar upload\n", style(">>>").magenta());
+            None
+        } else {
+            let indices = format_index_ranges(&item_indices(&upload_indices));
+            println!(
+                "\n{} sugar upload --indices {}\n",
+                style(">>>").magenta(),
+                indices
+            );
+            Some(indices)
+        };
+
+        process_upload(UploadArgs {
+            assets_dir,
+            config: report.config.clone(),
+            keypair: args.keypair.clone(),
+            rpc_url: args.rpc_url.clone(),
+            cache: report.cache.clone(),
+            fail_on_duplicates: false,
+            max_bandwidth: None,
+            max_retries: DEFAULT_MAX_RETRIES,
+            verify: false,
+            verify_sample: None,
+            indices,
+            failures: args.from.clone(),
+            interrupted: args.interrupted.clone(),
+        })
+        .await?;
+    }
+
+    if !deploy_indices.is_empty() {
+        let indices = format_index_ranges(&item_indices(&deploy_indices));
+
+        println!(
+            "\n{} sugar deploy --indices {}\n",
+            style(">>>").magenta(),
+            indices
+        );
+
+        process_deploy(DeployArgs {
+            config: report.config.clone(),
+            cache: report.cache.clone(),
+            keypair: args.keypair,
+            rpc_url: args.rpc_url,
+            interrupted: args.interrupted,
+            collection_mint: None,
+            indices: Some(indices),
+            fee_payers: None,
+            shuffle: None,
+            append: false,
+            failures: args.from.clone(),
+        })
+        .await?;
+    }
+
+    println!(
+        "\n{}",
+        style(format!(
+            "{} failed item(s) retried successfully.",
+            upload_indices.len() + deploy_indices.len()
+        ))
+        .green()
+        .bold()
+    );
+
+    Ok(())
+}
+
+/// Indices of the items, without the collection item.
+fn item_indices(indices: &[isize]) -> Vec<u64> {
+    indices
+        .iter()
+        .filter_map(|index| u64::try_from(*index).ok())
+        .collect()
+}
@@ -3,12 +3,12 @@ use std::{
     time::Duration,
 };
 
-use anchor_client::solana_sdk::account::Account;
+use anchor_client::solana_sdk::{account::Account, instruction::Instruction};
 use anchor_lang::AnchorDeserialize;
 use console::style;
 use futures::future::join_all;
 use mpl_token_metadata::{
-    instruction::update_metadata_accounts_v2,
+    instruction::{sign_metadata, update_metadata_accounts_v2},
     state::{DataV2, Metadata},
     ID as TOKEN_METADATA_PROGRAM_ID,
 };
@@ -17,11 +17,13 @@ use solana_client::{client_error::ClientError, rpc_client::RpcClient};
 use tokio::sync::Semaphore;
 
 use crate::{
-    cache::load_cache,
+    cache::{load_cache, CacheItems},
     candy_machine::CANDY_MACHINE_ID,
     common::*,
     config::{get_config_data, Cluster},
+    dry_run::send_and_confirm_or_plan,
     pdas::{find_candy_machine_creator_pda, find_metadata_pda},
+    rate_limit::{throttled_client, throttled_client_with_timeout},
     setup::get_rpc_url,
     utils::*,
 };
@@ -32,6 +34,8 @@ pub struct RevealArgs {
     pub cache: String,
     pub config: String,
     pub timeout: Option<u64>,
+    pub indices: Option<String>,
+    pub sign: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -47,6 +51,7 @@ pub struct MetadataUpdateValues {
 struct RevealTx {
     metadata_pubkey: Pubkey,
     result: RevealResult,
+    signed: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -59,6 +64,8 @@ enum RevealResult {
 const DEFAULT_TIMEOUT: u64 = 300;
 
 pub async fn process_reveal(args: RevealArgs) -> Result<()> {
+    let selection = IndexSelection::parse(args.indices.as_deref())?;
+
     println!(
         "{} {}Loading items from the cache",
         style("[1/4]").bold().dim(),
@@ -77,7 +84,7 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
         return Err(anyhow!("Candy machine is not a Hidden Settings mint."));
     };
 
-    let cache = load_cache(&args.cache, false)?;
+    let mut cache = load_cache(&args.cache, false)?;
     let sugar_config = sugar_setup(args.keypair, args.rpc_url.clone())?;
     let anchor_client = setup_client(&sugar_config)?;
     let program = anchor_client.program(CANDY_MACHINE_ID);
@@ -105,7 +112,7 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
 
     let spinner = spinner_with_style();
     spinner.set_message("Loading...");
-    let solana_cluster: Cluster = get_cluster(program.rpc())?;
+    let solana_cluster: Cluster = get_cluster(&program.rpc())?;
     let rpc_url = get_rpc_url(args.rpc_url);
 
     let solana_cluster = if rpc_url.ends_with("8899") {
@@ -116,7 +123,7 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
 
     let metadata_pubkeys = match solana_cluster {
         Cluster::Mainnet | Cluster::Devnet | Cluster::Localnet => {
-            let client = RpcClient::new_with_timeout(
+            let client = throttled_client_with_timeout(
                 &rpc_url,
                 Duration::from_secs(if let Some(timeout) = args.timeout {
                     timeout
@@ -159,7 +166,7 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
     let spinner = spinner_with_style();
 
     let mut futures = Vec::new();
-    let client = RpcClient::new(&rpc_url);
+    let client = throttled_client(&rpc_url);
     let client = Arc::new(client);
 
     // Get all metadata accounts.
@@ -194,24 +201,27 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
     };
 
     // Convert cache to make keys match NFT numbers.
-    let nft_lookup: HashMap<String, &CacheItem> = cache
-        .items
+    let nft_lookup = items_to_reveal(&cache.items, selection.as_ref(), index);
+
+    let items: HashMap<&String, &CacheItem> = nft_lookup
         .iter()
-        .filter(|(k, _)| *k != "-1") // skip collection index
-        .filter(|(_, i)| !i.on_chain) // skip already revealed items
-        .map(|(k, item)| (increment_key(k, index), item)) // Use the index pattern to increment the key.
+        .map(|(num, (_, item))| (num, *item))
         .collect();
-
-    serde_json::to_writer_pretty(File::create("temp.json")?, &nft_lookup)?;
+    serde_json::to_writer_pretty(File::create("temp.json")?, &items)?;
 
     spinner.finish_with_message("Done");
 
     let mut update_values = Vec::new();
 
     println!(
-        "\n{} {}Updating NFT URIs from cache values",
+        "\n{} {}{} from cache values",
         style("[4/4]").bold().dim(),
-        UPLOAD_EMOJI
+        UPLOAD_EMOJI,
+        if args.sign {
+            "Updating and signing NFTs"
+        } else {
+            "Updating NFT URIs"
+        }
     );
 
     let name_prefix_pattern = patterns.first().unwrap_or(&"");
@@ -245,18 +255,14 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
         };
 
         let metadata_pubkey = find_metadata_pda(&m.mint);
-        let new_uri = nft_lookup
-            .get(&num)
-            .filter(|i| !i.on_chain)
-            .ok_or_else(|| anyhow!("No URI found for number: {num}"))?
-            .metadata_link
-            .clone();
-        let new_name = nft_lookup
-            .get(&num)
-            .filter(|i| !i.on_chain)
-            .ok_or_else(|| anyhow!("No name found for number: {num}"))?
-            .name
-            .clone();
+        let item = match nft_lookup.get(&num) {
+            Some((_, item)) => item,
+            // NFTs outside the selection are left as they are
+            None if selection.is_some() => continue,
+            None => return Err(anyhow!("No URI found for number: {num}")),
+        };
+        let new_uri = item.metadata_link.clone();
+        let new_name = item.name.clone();
 
         update_values.push(MetadataUpdateValues {
             metadata_pubkey,
@@ -276,7 +282,8 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
     let pb = progress_bar_with_style(metadata_pubkeys.len() as u64);
     pb.set_message("Updating NFTs... ");
 
-    let cache = Arc::new(Mutex::new(cache));
+    let revealed = Arc::new(Mutex::new(Vec::new()));
+    let sign = args.sign;
 
     for item in update_values {
         let permit = Arc::clone(&sem).acquire_owned().await.unwrap();
@@ -285,7 +292,7 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
         let reveal_results = reveal_results.clone();
         let pb = pb.clone();
 
-        let cache = cache.clone();
+        let revealed = revealed.clone();
         let index = item.index.clone();
 
         tx_tasks.push(tokio::spawn(async move {
@@ -295,13 +302,13 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
             let mut tx = RevealTx {
                 metadata_pubkey,
                 result: RevealResult::Success,
+                signed: false,
             };
 
-            match update_metadata_value(client, keypair, item).await {
-                Ok(_) => {
-                    let mut cache_mutex = cache.lock().unwrap();
-                    let v = cache_mutex.items.get_mut(&index).unwrap();
-                    v.on_chain = true;
+            match update_metadata_value(client, keypair, item, sign).await {
+                Ok(signed) => {
+                    tx.signed = signed;
+                    revealed.lock().unwrap().push(index);
                     reveal_results.lock().unwrap().push(tx);
                 }
                 Err(e) => {
@@ -319,6 +326,17 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
     }
     pb.finish();
 
+    // the revealed items are saved even if some reveals failed, so that they are skipped
+    // when the command is run again
+    let revealed: Vec<String> = revealed
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|num| nft_lookup.get(num).map(|(key, _)| key.to_string()))
+        .collect();
+    mark_revealed(&mut cache.items, &revealed);
+    cache.sync_file()?;
+
     let results = reveal_results.lock().unwrap();
 
     let errors: Vec<&RevealTx> = results
@@ -326,6 +344,11 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
         .filter(|r| matches!(r.result, RevealResult::Failure(_)))
         .collect();
 
+    if sign {
+        let signed = results.iter().filter(|r| r.signed).count();
+        println!("\n{} NFT(s) signed as creator.", signed);
+    }
+
     if !errors.is_empty() {
         println!(
             "{}Some reveals failed. See the reveal cache file for details. Re-run the command.",
@@ -341,6 +364,34 @@ pub async fn process_reveal(args: RevealArgs) -> Result<()> {
     Ok(())
 }
 
+/// Returns the items of the cache to reveal by NFT number (the cache key incremented by
+/// `index`), with their cache key. Items already revealed are skipped unless selected.
+fn items_to_reveal<'a>(
+    items: &'a CacheItems,
+    selection: Option<&IndexSelection>,
+    index: u32,
+) -> HashMap<String, (&'a String, &'a CacheItem)> {
+    items
+        .iter()
+        .filter(|(k, _)| *k != COLLECTION_CACHE_KEY) // skip collection index
+        .filter(|(k, i)| match selection {
+            // selected items are revealed again
+            Some(selection) => selection.contains_key(k),
+            // skip already revealed items
+            None => !i.is_revealed(),
+        })
+        .map(|(k, item)| (increment_key(k, index), (k, item))) // Use the index pattern to increment the key.
+        .collect()
+}
+
+fn mark_revealed(items: &mut CacheItems, keys: &[String]) {
+    for key in keys {
+        if let Some(item) = items.get_mut(key) {
+            item.set_status(ItemStatus::Revealed);
+        }
+    }
+}
+
 async fn async_get_multiple_accounts(
     client: Arc<RpcClient>,
     pubkeys: &[Pubkey],
@@ -348,12 +399,24 @@ async fn async_get_multiple_accounts(
     client.get_multiple_accounts(pubkeys)
 }
 
-async fn update_metadata_value(
-    client: Arc<RpcClient>,
-    update_authority: Arc<Keypair>,
+/// Returns the instructions to update the URI and name of the metadata and, when `sign` is
+/// set, to sign it as creator in the same transaction, together with whether the metadata
+/// is signed.
+fn reveal_instructions(
+    update_authority: &Pubkey,
     value: MetadataUpdateValues,
-) -> Result<(), ClientError> {
+    sign: bool,
+) -> (Vec<Instruction>, bool) {
+    let mut instructions = Vec::new();
     let mut data = value.metadata.data;
+
+    // the update keeps the verified flags, so the creator can sign afterwards
+    let unverified = data
+        .creators
+        .iter()
+        .flatten()
+        .any(|creator| creator.address == *update_authority && !creator.verified);
+
     if data.uri.trim_matches(char::from(0)) != value.new_uri.trim_matches(char::from(0)) {
         data.uri = value.new_uri;
         data.name = value.new_name;
@@ -371,25 +434,50 @@ async fn update_metadata_value(
         let ix = update_metadata_accounts_v2(
             TOKEN_METADATA_PROGRAM_ID,
             value.metadata_pubkey,
-            update_authority.pubkey(),
+            *update_authority,
             None,
             Some(data_v2),
             None,
             None,
         );
+        instructions.push(ix);
+    }
 
+    let signed = sign && unverified;
+
+    if signed {
+        instructions.push(sign_metadata(
+            TOKEN_METADATA_PROGRAM_ID,
+            value.metadata_pubkey,
+            *update_authority,
+        ));
+    }
+
+    (instructions, signed)
+}
+
+/// Updates the URI and name of the metadata and, when `sign` is set, signs it as creator
+/// in the same transaction. Returns whether the metadata was signed.
+async fn update_metadata_value(
+    client: Arc<RpcClient>,
+    update_authority: Arc<Keypair>,
+    value: MetadataUpdateValues,
+    sign: bool,
+) -> Result<bool, ClientError> {
+    let (instructions, signed) = reveal_instructions(&update_authority.pubkey(), value, sign);
+
+    if !instructions.is_empty() {
         let recent_blockhash = client.get_latest_blockhash()?;
         let tx = Transaction::new_signed_with_payer(
-            &[ix],
+            &instructions,
             Some(&update_authority.pubkey()),
             &[&*update_authority],
             recent_blockhash,
         );
-
-        client.send_and_confirm_transaction(&tx)?;
+        send_and_confirm_or_plan(&client, &tx, "reveal")?;
     }
 
-    Ok(())
+    Ok(signed)
 }
 
 fn increment_key(key: &str, index: u32) -> String {
@@ -398,3 +486,117 @@ fn increment_key(key: &str, index: u32) -> String {
         + index)
         .to_string()
 }
+
+#[test]
+fn test_reveal_instructions() {
+    use mpl_token_metadata::state::{Creator, Data, Key};
+
+    let authority = Pubkey::new_unique();
+    let value = |uri: &str, verified: bool| MetadataUpdateValues {
+        metadata_pubkey: Pubkey::new_unique(),
+        metadata: Metadata {
+            key: Key::MetadataV1,
+            update_authority: authority,
+            mint: Pubkey::new_unique(),
+            data: Data {
+                name: "Hidden".to_string(),
+                symbol: String::new(),
+                uri: format!("{:\0<200}", uri),
+                seller_fee_basis_points: 500,
+                creators: Some(vec![
+                    Creator {
+                        address: Pubkey::new_unique(),
+                        verified: true,
+                        share: 0,
+                    },
+                    Creator {
+                        address: authority,
+                        verified,
+                        share: 100,
+                    },
+                ]),
+            },
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection: None,
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        },
+        new_uri: "https://arweave.net/1".to_string(),
+        new_name: "Item #1".to_string(),
+        index: "1".to_string(),
+    };
+
+    // the update and the signature share the transaction
+    let (instructions, signed) = reveal_instructions(&authority, value("hidden", false), true);
+    assert!(signed);
+    assert_eq!(instructions.len(), 2);
+    assert!(instructions
+        .iter()
+        .all(|ix| ix.program_id == TOKEN_METADATA_PROGRAM_ID));
+    assert!(instructions[1]
+        .accounts
+        .iter()
+        .any(|account| account.pubkey == authority && account.is_signer));
+
+    let (instructions, signed) = reveal_instructions(&authority, value("hidden", false), false);
+    assert!(!signed);
+    assert_eq!(instructions.len(), 1);
+
+    // revealed items are only signed, verified creators are not signed again
+    let revealed = "https://arweave.net/1";
+    let (instructions, signed) = reveal_instructions(&authority, value(revealed, false), true);
+    assert!(signed);
+    assert_eq!(instructions.len(), 1);
+
+    let (instructions, signed) = reveal_instructions(&authority, value(revealed, true), true);
+    assert!(!signed);
+    assert!(instructions.is_empty());
+
+    // only the update authority can sign as creator
+    let (_, signed) = reveal_instructions(&Pubkey::new_unique(), value("hidden", false), true);
+    assert!(!signed);
+}
+
+#[test]
+fn test_revealed_items_skipped() {
+    let mut items = CacheItems::new();
+    for key in ["-1", "0", "1", "2"] {
+        items.insert(
+            key.to_string(),
+            CacheItem {
+                name: format!("Item #{key}"),
+                image_hash: String::new(),
+                image_link: String::new(),
+                metadata_hash: String::new(),
+                metadata_link: format!("https://arweave.net/{key}"),
+                on_chain: false,
+                animation_hash: None,
+                animation_link: None,
+                status: ItemStatus::Deployed,
+                timestamps: ItemTimestamps::default(),
+            },
+        );
+    }
+
+    // names are one-indexed, the collection is not revealed
+    let lookup = items_to_reveal(&items, None, 1);
+    let mut numbers: Vec<&String> = lookup.keys().collect();
+    numbers.sort();
+    assert_eq!(numbers, vec!["1", "2", "3"]);
+    assert_eq!(lookup["1"].0, "0");
+
+    mark_revealed(&mut items, &["0".to_string(), "2".to_string()]);
+    assert!(items["0"].timestamps.revealed.is_some());
+
+    // the second pass only reveals the remaining item
+    let lookup = items_to_reveal(&items, None, 1);
+    assert_eq!(lookup.keys().collect::<Vec<_>>(), vec!["2"]);
+
+    // selected items are revealed again
+    let selection = IndexSelection::parse(Some("0-1")).unwrap().unwrap();
+    assert_eq!(items_to_reveal(&items, Some(&selection), 0).len(), 2);
+}
@@ -0,0 +1,316 @@
+use std::{fs, thread::sleep, time::Duration};
+
+use chrono::{SecondsFormat, Utc};
+use console::style;
+use mpl_token_metadata::{
+    instruction::{
+        builders::UpdateBuilder, CollectionDetailsToggle, CollectionToggle, InstructionBuilder,
+        RuleSetToggle, UpdateArgs, UsesToggle,
+    },
+    state::{Metadata, ProgrammableConfig},
+};
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+
+use crate::{
+    cache::load_cache,
+    common::*,
+    dry_run::send_and_confirm_or_plan,
+    pdas::{find_candy_machine_creator_pda, find_master_edition_pda, find_metadata_pda},
+    rate_limit::throttled_client_with_timeout,
+    setup::get_rpc_url,
+    utils::*,
+};
+
+/// Timeout for the GPA call (in seconds).
+const DEFAULT_TIMEOUT: u64 = 300;
+
+pub struct RotateArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_machine: Option<String>,
+    pub new_update_authority: Option<String>,
+    pub rule_set: Option<String>,
+    pub rate_limit: u64,
+    pub audit_log: Option<String>,
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditEntry {
+    mint: String,
+    signature: Option<String>,
+    error: Option<String>,
+    timestamp: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditLog {
+    candy_machine: String,
+    authority: String,
+    new_update_authority: Option<String>,
+    rule_set: Option<String>,
+    changes: Vec<AuditEntry>,
+}
+
+/// Audit log with the signature of the authority over its JSON content.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignedAuditLog {
+    log: AuditLog,
+    signer: String,
+    signature: String,
+}
+
+pub fn process_rotate(args: RotateArgs) -> Result<()> {
+    let new_update_authority = args
+        .new_update_authority
+        .as_ref()
+        .map(|authority| {
+            Pubkey::from_str(authority)
+                .map_err(|_| anyhow!("Invalid new update authority: {}", authority))
+        })
+        .transpose()?;
+
+    let rule_set = args
+        .rule_set
+        .as_ref()
+        .map(|rule_set| {
+            Pubkey::from_str(rule_set).map_err(|_| anyhow!("Invalid rule set: {}", rule_set))
+        })
+        .transpose()?;
+
+    if new_update_authority.is_none() && rule_set.is_none() {
+        return Err(anyhow!(
+            "Specify a new update authority and/or a rule set to rotate"
+        ));
+    }
+
+    if args.rate_limit == 0 {
+        return Err(anyhow!("The rate limit must be greater than zero"));
+    }
+
+    println!(
+        "{} {}Getting minted NFTs",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let candy_machine_id = match args.candy_machine {
+        Some(candy_machine) => candy_machine,
+        None => load_cache(&args.cache, false)?.program.candy_machine,
+    };
+
+    let candy_machine_id = Pubkey::from_str(&candy_machine_id)
+        .map_err(|_| anyhow!("Failed to parse candy machine id: {}", candy_machine_id))?;
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url.clone())?;
+    let authority = sugar_config.keypair.pubkey();
+
+    let pb = spinner_with_style();
+    pb.set_message("Loading...");
+
+    let rpc_url = get_rpc_url(args.rpc_url);
+    let client = throttled_client_with_timeout(
+        &rpc_url,
+        Duration::from_secs(args.timeout.unwrap_or(DEFAULT_TIMEOUT)),
+    );
+    let (creator, _) = find_candy_machine_creator_pda(&candy_machine_id);
+    let minted = get_cm_creator_metadata(&client, &creator.to_string(), 0)?;
+
+    pb.finish_with_message(format!("Found {} NFTs", minted.len()));
+
+    let mut not_authority = 0;
+    let pending: Vec<Metadata> = minted
+        .into_iter()
+        .map(|(_, metadata)| metadata)
+        .filter(|metadata| {
+            if metadata.update_authority != authority {
+                not_authority += 1;
+                return false;
+            }
+            needs_rotation(metadata, new_update_authority, rule_set)
+        })
+        .collect();
+
+    if not_authority > 0 {
+        println!(
+            "\n{}Skipping {} NFT(s) where {} is not the update authority",
+            WARNING_EMOJI, not_authority, authority
+        );
+    }
+
+    if pending.is_empty() {
+        println!("\nNo NFTs to update.");
+        return Ok(());
+    }
+
+    if let Some(rule_set) = rule_set {
+        let non_programmable = pending
+            .iter()
+            .filter(|metadata| metadata.programmable_config.is_none())
+            .count();
+
+        if non_programmable > 0 {
+            return Err(anyhow!(
+                "{} NFT(s) are not programmable, the rule set {} can only be set on pNFTs",
+                non_programmable,
+                rule_set
+            ));
+        }
+    }
+
+    println!("\n{} NFT(s) will be updated:", pending.len());
+    if let Some(new_update_authority) = new_update_authority {
+        println!("  -> update authority: {}", new_update_authority);
+    }
+    if let Some(rule_set) = rule_set {
+        println!("  -> rule set: {}", rule_set);
+    }
+
+    let theme = get_dialoguer_theme();
+    if !confirm_with_theme(&theme, "Do you want to continue?")? {
+        return Err(anyhow!("Operation aborted"));
+    }
+
+    println!(
+        "\n{} {}Updating NFTs",
+        style("[2/2]").bold().dim(),
+        SIGNING_EMOJI
+    );
+
+    let interval = Duration::from_millis(1000 / args.rate_limit);
+    let pb = progress_bar_with_style(pending.len() as u64);
+    let mut changes = Vec::with_capacity(pending.len());
+
+    for metadata in &pending {
+        let result = send_rotation(
+            &client,
+            &sugar_config.keypair,
+            metadata,
+            new_update_authority,
+            rule_set,
+        );
+
+        changes.push(AuditEntry {
+            mint: metadata.mint.to_string(),
+            signature: result.as_ref().ok().map(|sig| sig.to_string()),
+            error: result.as_ref().err().map(|err| err.to_string()),
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        });
+
+        pb.inc(1);
+        sleep(interval);
+    }
+
+    pb.finish_and_clear();
+
+    let failed = changes.iter().filter(|entry| entry.error.is_some()).count();
+
+    let log = AuditLog {
+        candy_machine: candy_machine_id.to_string(),
+        authority: authority.to_string(),
+        new_update_authority: new_update_authority.map(|key| key.to_string()),
+        rule_set: rule_set.map(|key| key.to_string()),
+        changes,
+    };
+
+    let audit_log = args
+        .audit_log
+        .unwrap_or_else(|| format!("sugar-rotate-{}.json", Utc::now().format("%Y%m%d%H%M%S")));
+    write_signed_log(&audit_log, log, &sugar_config.keypair)?;
+
+    println!("\n{} {}", style("Audit log:").bold(), audit_log);
+
+    if failed > 0 {
+        return Err(SugarError::PartialFailure(format!(
+            "{} update(s) failed, see the audit log for details. Re-run the command to retry.",
+            failed
+        ))
+        .into());
+    }
+
+    println!("\n{}Rotation complete!", CONFETTI_EMOJI);
+
+    Ok(())
+}
+
+/// Return whether the NFT does not have the new update authority or rule set yet.
+fn needs_rotation(
+    metadata: &Metadata,
+    new_update_authority: Option<Pubkey>,
+    rule_set: Option<Pubkey>,
+) -> bool {
+    let authority_changed =
+        new_update_authority.map_or(false, |authority| metadata.update_authority != authority);
+
+    let rule_set_changed = rule_set.map_or(false, |rule_set| {
+        !matches!(
+            metadata.programmable_config,
+            Some(ProgrammableConfig::V1 { rule_set: Some(current) }) if current == rule_set
+        )
+    });
+
+    authority_changed || rule_set_changed
+}
+
+fn send_rotation(
+    client: &RpcClient,
+    authority: &Keypair,
+    metadata: &Metadata,
+    new_update_authority: Option<Pubkey>,
+    rule_set: Option<Pubkey>,
+) -> Result<Signature> {
+    let args = UpdateArgs::V1 {
+        new_update_authority,
+        data: None,
+        primary_sale_happened: None,
+        is_mutable: None,
+        collection: CollectionToggle::None,
+        collection_details: CollectionDetailsToggle::None,
+        uses: UsesToggle::None,
+        rule_set: match rule_set {
+            Some(rule_set) => RuleSetToggle::Set(rule_set),
+            None => RuleSetToggle::None,
+        },
+        authorization_data: None,
+    };
+
+    let ix = UpdateBuilder::new()
+        .authority(authority.pubkey())
+        .metadata(find_metadata_pda(&metadata.mint))
+        .mint(metadata.mint)
+        .edition(find_master_edition_pda(&metadata.mint))
+        .payer(authority.pubkey())
+        .build(args)
+        .map_err(|e| anyhow!("Failed to build update instruction: {:?}", e))?
+        .instruction();
+
+    let recent_blockhash = client.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        recent_blockhash,
+    );
+    send_and_confirm_or_plan(client, &tx, "rotate")
+}
+
+/// Write the audit log with a signature of the authority over its JSON content, so it
+/// can be verified that the log was produced by the holder of the keypair.
+fn write_signed_log(path: &str, log: AuditLog, keypair: &Keypair) -> Result<()> {
+    let content = serde_json::to_vec(&log)?;
+    let signature = keypair.sign_message(&content);
+
+    let signed = SignedAuditLog {
+        log,
+        signer: keypair.pubkey().to_string(),
+        signature: signature.to_string(),
+    };
+
+    fs::write(path, serde_json::to_string_pretty(&signed)?)
+        .map_err(|e| anyhow!("Failed to write audit log '{}': {}", path, e))
+}
@@ -0,0 +1,5 @@
+pub mod process;
+pub mod release;
+
+pub use process::*;
+pub use release::*;
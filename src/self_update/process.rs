@@ -0,0 +1,222 @@
+use std::{env, fs};
+
+use console::style;
+use reqwest::header::USER_AGENT;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    common::*,
+    self_update::*,
+    utils::{confirm_with_theme, get_dialoguer_theme, spinner_with_style},
+};
+
+pub struct SelfUpdateArgs {
+    pub channel: ReleaseChannel,
+    pub check: bool,
+    pub force: bool,
+    pub public_key: Option<String>,
+}
+
+pub async fn process_self_update(args: SelfUpdateArgs) -> Result<()> {
+    println!(
+        "{} {}Checking the {} releases",
+        style("[1/3]").bold().dim(),
+        LOOKING_GLASS_EMOJI,
+        args.channel
+    );
+
+    let client = HttpClient::new();
+    let releases: Vec<Release> = get(&client, RELEASES_URL).await?.json().await?;
+
+    let release = latest_release(&releases, args.channel)
+        .ok_or_else(|| anyhow!("No {} release found.", args.channel))?;
+    let version = release.version().expect("Release without a version");
+    let current = ReleaseVersion::current();
+
+    println!("Installed version: {}", current);
+    println!("Latest version:    {}", style(&version).bold());
+
+    if version <= current && !args.force {
+        println!("\nSugar is up to date.");
+        return Ok(());
+    }
+
+    if args.check {
+        println!("\nRun 'sugar self-update' to install {}.", version);
+        return Ok(());
+    }
+
+    // the signature is always verified, with the key pinned in the binary unless another
+    // one is specified
+    let public_key = args
+        .public_key
+        .as_deref()
+        .or(RELEASE_PUBLIC_KEY)
+        .ok_or_else(|| {
+            anyhow!(
+                "This build of sugar has no release key to verify the signature of the binary, \
+                specify one with --public-key."
+            )
+        })?;
+
+    let artifact = artifact_name()?;
+    let binary = release
+        .asset(artifact)
+        .ok_or_else(|| anyhow!("Release {} has no '{}' binary.", version, artifact))?;
+    let checksum = release
+        .checksum_asset(artifact)
+        .ok_or_else(|| anyhow!("Release {} has no checksum for '{}'.", version, artifact))?;
+
+    println!(
+        "\n{} {}Downloading {}",
+        style("[2/3]").bold().dim(),
+        COMPUTER_EMOJI,
+        artifact
+    );
+
+    let pb = spinner_with_style();
+    pb.set_message("Downloading...");
+
+    let bytes = get(&client, &binary.browser_download_url)
+        .await?
+        .bytes()
+        .await?;
+    let checksum_content = get(&client, &checksum.browser_download_url)
+        .await?
+        .text()
+        .await?;
+
+    pb.finish_and_clear();
+
+    let expected = parse_checksum(&checksum_content, artifact).ok_or_else(|| {
+        anyhow!(
+            "Checksum of '{}' not found in '{}'.",
+            artifact,
+            checksum.name
+        )
+    })?;
+    let actual = hex::encode(Sha256::digest(&bytes));
+
+    if actual != expected {
+        return Err(anyhow!(
+            "Checksum mismatch for '{}': expected {}, got {}. The binary was not replaced.",
+            artifact,
+            expected,
+            actual
+        ));
+    }
+
+    println!("{}", style("Checksum verified.").green());
+
+    let signature = release
+        .asset(&format!("{artifact}.sig"))
+        .ok_or_else(|| anyhow!("Release {} has no signature for '{}'.", version, artifact))?;
+    let signature = get(&client, &signature.browser_download_url)
+        .await?
+        .bytes()
+        .await?;
+
+    verify_signature(public_key, &bytes, &signature)?;
+    println!("{}", style("Signature verified.").green());
+
+    println!(
+        "\n{} {}Replacing the binary",
+        style("[3/3]").bold().dim(),
+        PAPER_EMOJI
+    );
+
+    let current_exe = env::current_exe()?;
+
+    if !confirm_with_theme(
+        &get_dialoguer_theme(),
+        &format!(
+            "Replace {} ({}) with {}?",
+            current_exe.display(),
+            current,
+            version
+        ),
+    )? {
+        return Err(anyhow!("Update cancelled."));
+    }
+
+    replace_binary(&current_exe, &bytes)?;
+
+    println!(
+        "{} {}",
+        style(format!("Sugar updated to {}.", version))
+            .green()
+            .bold(),
+        CONFETTI_EMOJI
+    );
+
+    Ok(())
+}
+
+async fn get(client: &HttpClient, url: &str) -> Result<Response> {
+    // the GitHub API rejects requests without a user agent
+    let response = client
+        .get(url)
+        .header(USER_AGENT, format!("sugar/{}", env!("CARGO_PKG_VERSION")))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Request to {} failed: {}", url, response.status()));
+    }
+
+    Ok(response)
+}
+
+/// Verifies the ed25519 signature of the binary with the public key (base58 or hex).
+fn verify_signature(public_key: &str, bytes: &[u8], signature: &[u8]) -> Result<()> {
+    let key = bs58::decode(public_key)
+        .into_vec()
+        .ok()
+        .filter(|key| key.len() == 32)
+        .or_else(|| hex::decode(public_key).ok())
+        .ok_or_else(|| anyhow!("Invalid public key: {}", public_key))?;
+
+    // the signature can be published as raw bytes or as text (hex or base58)
+    let text = String::from_utf8_lossy(signature).trim().to_string();
+    let signature = if signature.len() == 64 {
+        signature.to_vec()
+    } else {
+        hex::decode(&text)
+            .or_else(|_| bs58::decode(&text).into_vec())
+            .map_err(|_| anyhow!("Invalid signature file."))?
+    };
+
+    UnparsedPublicKey::new(&ED25519, key)
+        .verify(bytes, &signature)
+        .map_err(|_| {
+            anyhow!("Invalid signature for the release binary. The binary was not replaced.")
+        })
+}
+
+/// Replaces the running binary: the new binary is written next to it and renamed over
+/// it, so an interrupted update never leaves a partial binary in place.
+fn replace_binary(current_exe: &Path, bytes: &[u8]) -> Result<()> {
+    let new_exe = current_exe.with_extension("new");
+    fs::write(&new_exe, bytes)
+        .map_err(|e| anyhow!("Failed to write '{}': {}", new_exe.display(), e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&new_exe, fs::Permissions::from_mode(0o755))?;
+    }
+
+    // a running binary cannot be overwritten on Windows, but it can be renamed
+    #[cfg(windows)]
+    {
+        let old_exe = current_exe.with_extension("old");
+        let _ = fs::remove_file(&old_exe);
+        fs::rename(current_exe, &old_exe)?;
+    }
+
+    fs::rename(&new_exe, current_exe)
+        .map_err(|e| anyhow!("Failed to replace '{}': {}", current_exe.display(), e))?;
+
+    Ok(())
+}
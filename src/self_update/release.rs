@@ -0,0 +1,272 @@
+use std::{cmp::Ordering, fmt};
+
+use crate::common::*;
+
+/// Releases of the repository, newest first.
+pub const RELEASES_URL: &str = "https://api.github.com/repos/metaplex-foundation/sugar/releases";
+
+/// Public key (base58 or hex) of the ed25519 key signing the release binaries, pinned
+/// when the release is built (`SUGAR_RELEASE_PUBLIC_KEY`).
+pub const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("SUGAR_RELEASE_PUBLIC_KEY");
+
+/// Files listing the SHA-256 checksums of all the artifacts of a release.
+const CHECKSUM_FILES: [&str; 3] = ["checksums.txt", "SHA256SUMS", "sha256sums.txt"];
+
+/// Release channel: stable only considers full releases, beta also pre-releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReleaseChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl FromStr for ReleaseChannel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "stable" => Ok(ReleaseChannel::Stable),
+            "beta" => Ok(ReleaseChannel::Beta),
+            _ => Err(anyhow!(
+                "Invalid release channel '{s}': expected stable or beta"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ReleaseChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReleaseChannel::Stable => write!(f, "stable"),
+            ReleaseChannel::Beta => write!(f, "beta"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    pub fn version(&self) -> Option<ReleaseVersion> {
+        ReleaseVersion::parse(&self.tag_name)
+    }
+
+    pub fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+
+    /// Returns the asset with the checksum of the artifact: either a `<artifact>.sha256`
+    /// file or a checksum file of the whole release.
+    pub fn checksum_asset(&self, artifact: &str) -> Option<&ReleaseAsset> {
+        self.asset(&format!("{artifact}.sha256"))
+            .or_else(|| CHECKSUM_FILES.iter().find_map(|name| self.asset(name)))
+    }
+}
+
+/// Version of a release tag (e.g. "v2.6.1" or "v2.7.0-beta.1").
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseVersion {
+    pub numbers: [u64; 3],
+    pub pre: Option<String>,
+}
+
+impl ReleaseVersion {
+    pub fn parse(tag: &str) -> Option<Self> {
+        let tag = tag.trim().trim_start_matches('v');
+        let (version, pre) = match tag.split_once('-') {
+            Some((version, pre)) => (version, Some(pre.to_string())),
+            None => (tag, None),
+        };
+
+        let mut numbers = [0; 3];
+        let mut parts = version.split('.');
+
+        for number in numbers.iter_mut() {
+            *number = parts.next()?.parse().ok()?;
+        }
+
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Self { numbers, pre })
+    }
+
+    pub fn current() -> Self {
+        Self::parse(env!("CARGO_PKG_VERSION")).expect("Invalid package version")
+    }
+}
+
+impl Ord for ReleaseVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // a pre-release comes before the release of the same version
+        self.numbers
+            .cmp(&other.numbers)
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(pre), Some(other)) => compare_pre_releases(pre, other),
+            })
+    }
+}
+
+/// Compares pre-release identifiers as semver does: numeric identifiers numerically
+/// ("beta.10" after "beta.9") and before alphanumeric ones, a shorter list first.
+fn compare_pre_releases(pre: &str, other: &str) -> Ordering {
+    let mut identifiers = pre.split('.');
+    let mut other_identifiers = other.split('.');
+
+    loop {
+        let ordering = match (identifiers.next(), other_identifiers.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+impl PartialOrd for ReleaseVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for ReleaseVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [major, minor, patch] = self.numbers;
+        write!(f, "v{major}.{minor}.{patch}")?;
+
+        if let Some(pre) = &self.pre {
+            write!(f, "-{pre}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the newest release of the channel with the same major version as the
+/// running binary (other major versions target different on-chain programs).
+pub fn latest_release(releases: &[Release], channel: ReleaseChannel) -> Option<&Release> {
+    let major = ReleaseVersion::current().numbers[0];
+
+    releases
+        .iter()
+        .filter(|release| !release.draft)
+        .filter(|release| channel == ReleaseChannel::Beta || !release.prerelease)
+        .filter_map(|release| release.version().map(|version| (version, release)))
+        .filter(|(version, _)| version.numbers[0] == major)
+        .filter(|(version, _)| channel == ReleaseChannel::Beta || version.pre.is_none())
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, release)| release)
+}
+
+/// Name of the release artifact for the platform of the running binary.
+pub fn artifact_name() -> Result<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Ok("sugar-ubuntu-latest"),
+        ("macos", "aarch64") => Ok("sugar-macos-m1-latest"),
+        ("macos", "x86_64") => Ok("sugar-macos-intel-latest"),
+        ("windows", "x86_64") => Ok("sugar-windows-latest.exe"),
+        (os, arch) => Err(anyhow!(
+            "There is no release binary for {os} ({arch}), build sugar from source instead."
+        )),
+    }
+}
+
+/// Returns the checksum of the artifact from the content of a checksum file, either a
+/// single hash or lines in the format `<hash>  <file name>`.
+pub fn parse_checksum(content: &str, artifact: &str) -> Option<String> {
+    let lines: Vec<Vec<&str>> = content
+        .lines()
+        .map(|line| line.split_whitespace().collect())
+        .filter(|parts: &Vec<&str>| !parts.is_empty())
+        .collect();
+
+    let hash = match lines.as_slice() {
+        [single] if single.len() == 1 => single[0],
+        _ => lines.iter().find_map(|parts| match parts.as_slice() {
+            // sha256sum marks binary files with a '*' before the name
+            [hash, name] if name.trim_start_matches('*') == artifact => Some(*hash),
+            _ => None,
+        })?,
+    };
+
+    Some(hash.to_lowercase())
+}
+
+#[test]
+fn test_release_versions() {
+    let version = |tag| ReleaseVersion::parse(tag).unwrap();
+
+    assert!(version("v2.7.0") > version("v2.6.10"));
+    assert!(version("v2.7.0") > version("v2.7.0-beta.1"));
+    assert!(version("v2.7.0-beta.2") > version("v2.7.0-beta.1"));
+    assert!(version("v2.7.0-beta.10") > version("v2.7.0-beta.9"));
+    assert!(version("v2.7.0-rc.1") > version("v2.7.0-beta.10"));
+    assert!(version("v2.7.0-beta.1.1") > version("v2.7.0-beta.1"));
+    assert!(version("v2.7.0-beta") > version("v2.7.0-1"));
+    assert_eq!(version("v2.7.0-beta.1").to_string(), "v2.7.0-beta.1");
+    assert_eq!(ReleaseVersion::parse("latest"), None);
+
+    let release = |tag: &str, prerelease| Release {
+        tag_name: tag.to_string(),
+        prerelease,
+        draft: false,
+        assets: Vec::new(),
+    };
+    let major = ReleaseVersion::current().numbers[0];
+    let releases = vec![
+        release(&format!("v{major}.99.0-beta.1"), true),
+        release(&format!("v{major}.98.0"), false),
+        release(&format!("v{}.0.0", major + 1), false),
+    ];
+
+    assert_eq!(
+        latest_release(&releases, ReleaseChannel::Stable).map(|r| r.tag_name.clone()),
+        Some(format!("v{major}.98.0"))
+    );
+    assert_eq!(
+        latest_release(&releases, ReleaseChannel::Beta).map(|r| r.tag_name.clone()),
+        Some(format!("v{major}.99.0-beta.1"))
+    );
+}
+
+#[test]
+fn test_release_checksums() {
+    let content = "abc123  sugar-ubuntu-latest\nDEF456 *sugar-macos-m1-latest\n";
+
+    assert_eq!(
+        parse_checksum(content, "sugar-macos-m1-latest"),
+        Some("def456".to_string())
+    );
+    assert_eq!(parse_checksum(content, "sugar-windows-latest.exe"), None);
+    assert_eq!(
+        parse_checksum("abc123\n", "sugar-ubuntu-latest"),
+        Some("abc123".to_string())
+    );
+}
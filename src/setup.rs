@@ -1,17 +1,24 @@
 use std::rc::Rc;
 
 use anchor_client::{
-    solana_sdk::{
-        commitment_config::CommitmentConfig,
-        signature::{keypair::Keypair, read_keypair_file},
-    },
+    solana_sdk::signature::{keypair::Keypair, Signer},
     Client, Cluster,
 };
 use anyhow::{anyhow, Result};
 use console::style;
 use tracing::error;
 
-use crate::{config::data::SugarConfig, constants::DEFAULT_KEYPATH, parse::*};
+use crate::{
+    compat::check_program_versions,
+    config::{data::SugarConfig, profile::active_profile},
+    confirmation::commitment,
+    constants::DEFAULT_KEYPATH,
+    costs::start_cost_tracking,
+    identity::check_identity,
+    keypair::read_keypair,
+    parse::*,
+    rate_limit::detect_rate_limit,
+};
 
 pub type SugarClient = Client<Rc<Keypair>>;
 
@@ -23,67 +30,95 @@ pub fn setup_client(sugar_config: &SugarConfig) -> Result<SugarClient> {
     let key_bytes = sugar_config.keypair.to_bytes();
     let signer = Rc::new(Keypair::from_bytes(&key_bytes)?);
 
-    let opts = CommitmentConfig::confirmed();
+    let opts = commitment().config();
     Ok(Client::new_with_options(cluster, signer, opts))
 }
 
 pub fn sugar_setup(
     keypair_opt: Option<String>,
     rpc_url_opt: Option<String>,
+) -> Result<SugarConfig> {
+    setup(keypair_opt, rpc_url_opt, false)
+}
+
+/// Setup for commands that only read on-chain data: when no keypair is specified and
+/// the default one cannot be read, an ephemeral keypair is used so the client can be
+/// created (it never signs a transaction).
+pub fn sugar_setup_read_only(
+    keypair_opt: Option<String>,
+    rpc_url_opt: Option<String>,
+) -> Result<SugarConfig> {
+    setup(keypair_opt, rpc_url_opt, true)
+}
+
+fn setup(
+    keypair_opt: Option<String>,
+    rpc_url_opt: Option<String>,
+    read_only: bool,
 ) -> Result<SugarConfig> {
     let sol_config_option = parse_solana_config();
 
-    let rpc_url = get_rpc_url(rpc_url_opt);
+    // command line values take precedence over the selected profile
+    let profile = active_profile();
+    let keypair_opt = keypair_opt.or_else(|| profile.as_ref().and_then(|p| p.keypair.clone()));
+    let rpc_url_opt = rpc_url_opt.or_else(|| profile.and_then(|p| p.rpc_url));
 
-    let keypair = match keypair_opt {
-        Some(keypair_path) => match read_keypair_file(&keypair_path) {
-            Ok(keypair) => keypair,
-            Err(e) => {
-                error!("Failed to read keypair file: {}", e);
-                return Err(anyhow!(
-                    "Failed to read keypair file: {}, {}",
-                    keypair_path,
-                    e
-                ));
-            }
-        },
+    let rpc_url = get_rpc_url(rpc_url_opt);
 
+    let specified = keypair_opt.is_some();
+    // only the commands that can send transactions record their costs and must run with
+    // the expected authority: reading on-chain data with another wallet is allowed
+    let track_costs = !read_only;
+    let check_authority = !read_only;
+    let keypair_path = match keypair_opt {
+        Some(keypair_path) => keypair_path,
         None => match sol_config_option {
-            Some(ref sol_config) => match read_keypair_file(&sol_config.keypair_path) {
-                Ok(keypair) => keypair,
-                Err(e) => {
-                    error!(
-                        "Failed to read keypair file: {}, {}",
-                        &sol_config.keypair_path, e
-                    );
-                    return Err(anyhow!(
-                        "Failed to read keypair file: {}, {}",
-                        &sol_config.keypair_path,
-                        e
-                    ));
-                }
-            },
-            None => match read_keypair_file(&*shellexpand::tilde(DEFAULT_KEYPATH)) {
-                Ok(keypair) => keypair,
-                Err(e) => {
-                    error!("Failed to read keypair file: {}, {}", DEFAULT_KEYPATH, e);
-                    return Err(anyhow!(
-                        "Failed to read keypair file: {}, {}",
-                        DEFAULT_KEYPATH,
-                        e
-                    ));
-                }
-            },
+            Some(sol_config) => sol_config.keypair_path,
+            None => DEFAULT_KEYPATH.to_string(),
         },
     };
 
-    Ok(SugarConfig { rpc_url, keypair })
+    let (keypair, read_only) = match read_keypair(&keypair_path) {
+        Ok(keypair) => (keypair, false),
+        // a keypair specified explicitly must be readable
+        Err(_) if read_only && !specified => {
+            println!(
+                "{}",
+                style("No keypair found, running in read-only mode.").dim()
+            );
+            (Keypair::new(), true)
+        }
+        Err(e) => {
+            error!("Failed to read keypair file: {}, {}", keypair_path, e);
+            return Err(anyhow!(
+                "Failed to read keypair file: {}, {}",
+                keypair_path,
+                e
+            ));
+        }
+    };
+
+    if check_authority {
+        check_identity(&keypair.pubkey())?;
+    }
+
+    let sugar_config = SugarConfig {
+        rpc_url,
+        keypair,
+        read_only,
+    };
+    if track_costs {
+        start_cost_tracking(&sugar_config);
+    }
+    check_program_versions(&sugar_config.rpc_url);
+
+    Ok(sugar_config)
 }
 
 pub fn get_rpc_url(rpc_url_opt: Option<String>) -> String {
     let sol_config_option = parse_solana_config();
 
-    match rpc_url_opt {
+    let rpc_url = match rpc_url_opt {
         Some(rpc_url) => rpc_url,
         None => match sol_config_option {
             Some(ref sol_config) => sol_config.json_rpc_url.clone(),
@@ -97,5 +132,42 @@ pub fn get_rpc_url(rpc_url_opt: Option<String>) -> String {
                 std::process::exit(1);
             }
         },
-    }
+    };
+
+    detect_rate_limit(&rpc_url);
+
+    rpc_url
+}
+
+#[test]
+fn test_read_only_setup_identity() {
+    use anchor_client::solana_sdk::{pubkey::Pubkey, signature::write_keypair_file};
+
+    use crate::identity::load_expected_authority;
+
+    let dir = std::env::temp_dir().join(format!("sugar-setup-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let keypair = dir.join("payer.json");
+    write_keypair_file(&Keypair::new(), &keypair).unwrap();
+    let keypair = keypair.to_str().unwrap().to_string();
+    let config = dir.join("config.json");
+    let config = config.to_str().unwrap();
+
+    std::fs::write(
+        config,
+        serde_json::json!({ "expectedAuthority": Pubkey::new_unique().to_string() }).to_string(),
+    )
+    .unwrap();
+    load_expected_authority(config).unwrap();
+
+    let rpc_url = Some("http://localhost:8899".to_string());
+    // a read-only command inspects the candy machine with a non-matching payer
+    let sugar_config = sugar_setup_read_only(Some(keypair.clone()), rpc_url.clone()).unwrap();
+    assert!(!sugar_config.read_only);
+    // the commands that send transactions still require the expected authority
+    assert!(sugar_setup(Some(keypair), rpc_url).is_err());
+
+    std::fs::write(config, "{}").unwrap();
+    load_expected_authority(config).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
 }
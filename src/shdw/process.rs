@@ -0,0 +1,219 @@
+use console::style;
+
+use crate::{
+    common::*,
+    config::{get_config_data, ConfigData},
+    rate_limit::throttled_client,
+    upload::{
+        get_asset_pairs,
+        methods::shdw::{
+            find_storage_accounts, get_storage_info, resize_storage, shdw_endpoint, storage_size,
+            with_margin, StorageResize, DEFAULT_STORAGE_MARGIN,
+        },
+        DataType,
+    },
+    utils::*,
+};
+
+pub struct ShdwListArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+}
+
+pub struct ShdwPlanArgs {
+    pub assets_dir: String,
+    pub config: String,
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub margin: Option<u8>,
+    pub expand: bool,
+}
+
+pub struct ShdwReclaimArgs {
+    pub config: String,
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub margin: Option<u8>,
+}
+
+/// Lists the storage accounts of the keypair, which can be reused by setting the
+/// 'shdwStorageAccount' of the config file.
+pub fn process_shdw_list(args: ShdwListArgs) -> Result<()> {
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let rpc_client = throttled_client(&sugar_config.rpc_url);
+
+    let pb = spinner_with_style();
+    pb.set_message("Looking up storage accounts...");
+
+    let accounts = find_storage_accounts(&rpc_client, &sugar_config.keypair.pubkey())?;
+
+    pb.finish_and_clear();
+
+    if accounts.is_empty() {
+        println!("No storage accounts found for the keypair.");
+    } else {
+        println!("{}", style("Storage accounts:").dim());
+
+        for (pubkey, storage) in accounts {
+            println!("  {} ({})", pubkey, format_bytes(storage));
+        }
+    }
+
+    Ok(())
+}
+
+/// Calculates the storage required by the assets folder and compares it with the
+/// storage account of the config file, expanding the account if requested.
+pub async fn process_shdw_plan(args: ShdwPlanArgs) -> Result<()> {
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let config_data = get_config_data(&args.config)?;
+    let margin = storage_margin(args.margin, &config_data);
+
+    println!(
+        "{} {}Calculating required storage",
+        style("[1/2]").bold().dim(),
+        ASSETS_EMOJI
+    );
+
+    let asset_pairs = get_asset_pairs(&args.assets_dir, config_data.collection_path.as_deref())?;
+    let indices: Vec<isize> = asset_pairs.keys().copied().collect();
+    let size = storage_size(
+        &config_data,
+        &asset_pairs,
+        vec![
+            (DataType::Image, &indices),
+            (DataType::Animation, &indices),
+            (DataType::Metadata, &indices),
+        ],
+    )?;
+    let required = with_margin(size, margin);
+
+    println!(
+        "Assets: {} ({} files)",
+        format_bytes(size),
+        asset_pairs.len()
+    );
+    println!(
+        "Required with {}% margin: {}",
+        margin,
+        format_bytes(required)
+    );
+
+    println!(
+        "\n{} {}Checking storage account",
+        style("[2/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let storage_account = storage_account(&config_data)?;
+    let rpc_client = throttled_client(&sugar_config.rpc_url);
+    let endpoint = shdw_endpoint(&rpc_client)?;
+    let storage_info = get_storage_info(endpoint, &storage_account).await?;
+
+    println!(
+        "Storage account {}: {} reserved, {} used",
+        storage_account,
+        format_bytes(storage_info.reserved_bytes),
+        format_bytes(storage_info.current_usage)
+    );
+
+    if storage_info.reserved_bytes >= required {
+        println!("\n{}", style("Storage account has enough space.").green());
+        return Ok(());
+    }
+
+    let missing = required - storage_info.reserved_bytes;
+
+    if args.expand {
+        let pb = spinner_with_style();
+        pb.set_message(format!("Expanding storage by {}...", format_bytes(missing)));
+        resize_storage(
+            &sugar_config,
+            &storage_account,
+            StorageResize::Increase(missing),
+        )
+        .await?;
+        pb.finish_with_message(format!("Storage expanded by {}", format_bytes(missing)));
+    } else {
+        println!(
+            "\n{} {}",
+            style(format!(
+                "Storage account is short by {}.",
+                format_bytes(missing)
+            ))
+            .yellow(),
+            style("Use --expand to increase it.").dim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Reduces the storage of the storage account to the current usage plus the margin,
+/// returning the unused SHDW stake.
+pub async fn process_shdw_reclaim(args: ShdwReclaimArgs) -> Result<()> {
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let config_data = get_config_data(&args.config)?;
+    let margin = storage_margin(args.margin, &config_data);
+
+    let storage_account = storage_account(&config_data)?;
+    let rpc_client = throttled_client(&sugar_config.rpc_url);
+    let endpoint = shdw_endpoint(&rpc_client)?;
+    let storage_info = get_storage_info(endpoint, &storage_account).await?;
+
+    if storage_info.immutable {
+        return Err(anyhow!(
+            "Storage account {} is immutable and cannot be reduced",
+            storage_account
+        ));
+    }
+
+    let target = with_margin(storage_info.current_usage, margin);
+
+    if storage_info.reserved_bytes <= target {
+        println!("No unused storage to reclaim.");
+        return Ok(());
+    }
+
+    let unused = storage_info.reserved_bytes - target;
+
+    let pb = spinner_with_style();
+    pb.set_message(format!("Reclaiming {} of storage...", format_bytes(unused)));
+    resize_storage(
+        &sugar_config,
+        &storage_account,
+        StorageResize::Decrease(unused),
+    )
+    .await?;
+    pb.finish_with_message(format!("Reclaimed {} of storage", format_bytes(unused)));
+
+    Ok(())
+}
+
+fn storage_margin(margin: Option<u8>, config_data: &ConfigData) -> u8 {
+    margin
+        .or_else(|| config_data.shdw_config.as_ref().and_then(|c| c.margin))
+        .unwrap_or(DEFAULT_STORAGE_MARGIN)
+}
+
+fn storage_account(config_data: &ConfigData) -> Result<Pubkey> {
+    let storage_account = config_data
+        .shdw_storage_account
+        .as_ref()
+        .ok_or_else(|| anyhow!("Missing 'shdwStorageAccount' value in config file."))?;
+
+    Ok(Pubkey::from_str(storage_account)?)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.2} {}", value, UNITS[unit])
+}
@@ -0,0 +1,226 @@
+use console::style;
+use mpl_candy_guard::state::CandyGuardData;
+use mpl_candy_machine_core::{constants::NULL_STRING, CandyMachine};
+use mpl_token_metadata::state::TokenStandard;
+use tabled::{builder::Builder, settings::Style};
+
+use crate::{
+    common::*,
+    config::{CandyGuardData as GuardConfig, ConfigData},
+};
+
+/// A value of the on-chain state that differs from the local config.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Difference {
+    pub field: String,
+    pub config: String,
+    pub on_chain: String,
+}
+
+/// Returns the differences between the config and the on-chain state of the candy
+/// machine and its guards (when wrapped by a candy guard).
+pub fn diff_config(
+    config_data: &ConfigData,
+    candy_machine: &CandyMachine,
+    guards: Option<&CandyGuardData>,
+    core: bool,
+) -> Result<Vec<Difference>> {
+    let mut differences = Vec::new();
+    let on_chain = &candy_machine.data;
+
+    let mut local = json!({
+        "number": config_data.number,
+        "isMutable": config_data.is_mutable,
+        "isSequential": config_data.is_sequential,
+        "hiddenSettings": config_data.hidden_settings.as_ref().map(|settings| json!({
+            "name": settings.name,
+            "uri": settings.uri,
+        })),
+    });
+    let mut remote = json!({
+        "number": on_chain.items_available,
+        "isMutable": on_chain.is_mutable,
+        "isSequential": on_chain
+            .config_line_settings
+            .as_ref()
+            .map_or(config_data.is_sequential, |settings| settings.is_sequential),
+        "hiddenSettings": on_chain.hidden_settings.as_ref().map(|settings| json!({
+            "name": settings.name,
+            "uri": settings.uri,
+        })),
+    });
+
+    // royalties and creators of Core assets are plugins of the collection
+    if !core {
+        let token_standard = if candy_machine.token_standard == TokenStandard::NonFungible as u8 {
+            "nft"
+        } else {
+            "pnft"
+        };
+
+        local["tokenStandard"] = serde_json::to_value(config_data.token_standard)?;
+        remote["tokenStandard"] = json!(token_standard);
+        local["symbol"] = json!(config_data.symbol);
+        remote["symbol"] = json!(on_chain.symbol.trim_end_matches(NULL_STRING));
+        local["sellerFeeBasisPoints"] = json!(config_data.seller_fee_basis_points);
+        remote["sellerFeeBasisPoints"] = json!(on_chain.seller_fee_basis_points);
+        local["creators"] = config_data
+            .creators
+            .iter()
+            .map(
+                |creator| json!({ "address": creator.address.to_string(), "share": creator.share }),
+            )
+            .collect();
+        remote["creators"] = on_chain
+            .creators
+            .iter()
+            .map(|creator| {
+                json!({ "address": creator.address.to_string(), "share": creator.percentage_share })
+            })
+            .collect();
+    }
+
+    diff_values("", &local, &remote, &mut differences);
+
+    // the guards of Core candy machines are not loaded
+    if core {
+        return Ok(differences);
+    }
+
+    match (&config_data.guards, guards) {
+        (Some(local), Some(remote)) => {
+            // both sides use the format of the config, so dates and prices are comparable
+            let local = GuardConfig::from_guard_format(&local.to_guard_format()?);
+            let remote = GuardConfig::from_guard_format(remote);
+
+            diff_values(
+                "guards",
+                &serde_json::to_value(&local)?,
+                &serde_json::to_value(&remote)?,
+                &mut differences,
+            );
+        }
+        (Some(_), None) => differences.push(Difference {
+            field: "guards".to_string(),
+            config: "guards".to_string(),
+            on_chain: "not wrapped by a candy guard".to_string(),
+        }),
+        (None, Some(_)) => differences.push(Difference {
+            field: "guards".to_string(),
+            config: "none".to_string(),
+            on_chain: "candy guard".to_string(),
+        }),
+        (None, None) => (),
+    }
+
+    Ok(differences)
+}
+
+/// Collects the differences between two values, using the path of the fields (e.g.
+/// "guards.default.solPayment.value") to identify them.
+fn diff_values(path: &str, local: &Value, remote: &Value, differences: &mut Vec<Difference>) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+
+    match (local, remote) {
+        (Value::Object(local), Value::Object(remote)) => {
+            let keys = local
+                .keys()
+                .chain(remote.keys().filter(|key| !local.contains_key(*key)));
+
+            for key in keys {
+                diff_values(
+                    &child(key),
+                    local.get(key).unwrap_or(&Value::Null),
+                    remote.get(key).unwrap_or(&Value::Null),
+                    differences,
+                );
+            }
+        }
+        (Value::Array(local_items), Value::Array(remote_items))
+            if local_items.len() == remote_items.len() =>
+        {
+            for (index, (local, remote)) in local_items.iter().zip(remote_items).enumerate() {
+                diff_values(&format!("{path}[{index}]"), local, remote, differences);
+            }
+        }
+        _ if local != remote => differences.push(Difference {
+            field: path.to_string(),
+            config: format_value(local),
+            on_chain: format_value(remote),
+        }),
+        _ => (),
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Null => "none".to_string(),
+        Value::String(value) => value.clone(),
+        _ => value.to_string(),
+    }
+}
+
+/// Prints the differences as a table.
+pub fn print_differences(differences: &[Difference], config: &str) {
+    if differences.is_empty() {
+        println!(
+            "\n{}",
+            style(format!("The on-chain state matches '{config}'."))
+                .green()
+                .bold()
+        );
+        return;
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["field", "config", "on-chain"]);
+
+    for difference in differences {
+        builder.push_record([
+            difference.field.as_str(),
+            difference.config.as_str(),
+            difference.on_chain.as_str(),
+        ]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::psql());
+
+    println!(
+        "\n{}\n",
+        style(format!(
+            "{} difference(s) between the on-chain state and '{config}':",
+            differences.len()
+        ))
+        .yellow()
+        .bold()
+    );
+    println!("{table}");
+}
+
+#[test]
+fn test_value_differences() {
+    let mut differences = Vec::new();
+
+    diff_values(
+        "guards",
+        &json!({ "default": { "solPayment": { "value": 1.0 } }, "groups": null }),
+        &json!({ "default": { "solPayment": { "value": 1.5 } }, "groups": null }),
+        &mut differences,
+    );
+
+    assert_eq!(
+        differences,
+        vec![Difference {
+            field: "guards.default.solPayment.value".to_string(),
+            config: "1.0".to_string(),
+            on_chain: "1.5".to_string(),
+        }]
+    );
+}
@@ -0,0 +1,248 @@
+use std::{collections::HashSet, rc::Rc};
+
+use chrono::Utc;
+use console::style;
+use mpl_candy_guard::{
+    guards::FreezeEscrow,
+    state::{CandyGuardData, GuardSet, DATA_OFFSET},
+};
+use mpl_candy_machine_core::constants::EMPTY_STR;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::{
+    common::*, config::format_timestamp, freeze::find_freeze_pda, guard::print_guard_set,
+    show::print_with_style,
+};
+
+/// Freeze escrow of a freeze guard (freezeSolPayment or freezeTokenPayment).
+struct FreezeGuard<'a> {
+    label: &'a str,
+    destination: Pubkey,
+    /// Payment mint of freezeTokenPayment guards.
+    mint: Option<Pubkey>,
+}
+
+/// Print the guards of the candy guard wrapping the candy machine, with the status of
+/// the freeze escrows.
+pub fn print_guards(
+    program: &Program<Rc<Keypair>>,
+    candy_machine_id: &Pubkey,
+    candy_guard_id: Pubkey,
+    sold_out: bool,
+) -> Result<()> {
+    let account = program.rpc().get_account(&candy_guard_id)?;
+
+    if account.owner != mpl_candy_guard::ID {
+        print_with_style("", "guards", "none (not wrapped by a candy guard)");
+        return Ok(());
+    }
+
+    let data = CandyGuardData::load(&account.data[DATA_OFFSET..])?;
+
+    println!(
+        "\n{}{} {}",
+        GUARD_EMOJI,
+        style("Candy guard ID:").dim(),
+        &candy_guard_id
+    );
+    println!(" {}", style(":").dim());
+    print_with_style("    ", "default", EMPTY_STR.to_string());
+    print_guard_set(&data.default, "    :   ".to_string())?;
+
+    let mut freeze_guards = Vec::new();
+    collect_freeze_guard("default", &data.default, &mut freeze_guards);
+
+    if let Some(groups) = &data.groups {
+        print_with_style("    ", "groups", EMPTY_STR.to_string());
+
+        for group in groups {
+            print_with_style("    :   ", &group.label, EMPTY_STR.to_string());
+            print_guard_set(&group.guards, "    :   :   ".to_string())?;
+            collect_freeze_guard(&group.label, &group.guards, &mut freeze_guards);
+        }
+    }
+
+    unique_escrows(&mut freeze_guards);
+
+    for freeze_guard in freeze_guards {
+        print_freeze_escrow(
+            program,
+            &candy_guard_id,
+            candy_machine_id,
+            &freeze_guard,
+            sold_out,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Keeps the first guard of each freeze escrow and payment mint: the escrow is shared by
+/// the guards with the same destination, in any group.
+fn unique_escrows(freeze_guards: &mut Vec<FreezeGuard>) {
+    let mut escrows = HashSet::new();
+    freeze_guards
+        .retain(|freeze_guard| escrows.insert((freeze_guard.destination, freeze_guard.mint)));
+}
+
+fn collect_freeze_guard<'a>(
+    label: &'a str,
+    guard_set: &GuardSet,
+    freeze_guards: &mut Vec<FreezeGuard<'a>>,
+) {
+    if let Some(guard) = &guard_set.freeze_sol_payment {
+        freeze_guards.push(FreezeGuard {
+            label,
+            destination: guard.destination,
+            mint: None,
+        });
+    }
+
+    if let Some(guard) = &guard_set.freeze_token_payment {
+        freeze_guards.push(FreezeGuard {
+            label,
+            destination: guard.destination_ata,
+            mint: Some(guard.mint),
+        });
+    }
+}
+
+fn print_freeze_escrow(
+    program: &Program<Rc<Keypair>>,
+    candy_guard_id: &Pubkey,
+    candy_machine_id: &Pubkey,
+    freeze_guard: &FreezeGuard,
+    sold_out: bool,
+) -> Result<()> {
+    let (escrow_pda, _) =
+        find_freeze_pda(candy_guard_id, candy_machine_id, &freeze_guard.destination);
+    let padding = ":   ";
+
+    println!(" {}", style(":").dim());
+    print_with_style(
+        "",
+        &format!("freeze escrow ({})", freeze_guard.label),
+        escrow_pda.to_string(),
+    );
+
+    let account = match program.rpc().get_account(&escrow_pda) {
+        Ok(account) => account,
+        Err(_) => {
+            print_with_style(
+                padding,
+                "status",
+                "not initialized (run 'sugar freeze initialize')",
+            );
+            return Ok(());
+        }
+    };
+
+    let escrow = FreezeEscrow::try_deserialize(&mut account.data.as_slice())?;
+
+    print_with_style(padding, "frozen count", escrow.frozen_count.to_string());
+
+    match freeze_guard.mint {
+        Some(mint) => {
+            let escrow_ata = get_associated_token_address(&escrow_pda, &mint);
+            let balance = program
+                .rpc()
+                .get_token_account_balance(&escrow_ata)
+                .map(|balance| balance.ui_amount_string)
+                .unwrap_or_else(|_| "0".to_string());
+            print_with_style(padding, "escrow balance", format!("{balance} ({mint})"));
+        }
+        None => {
+            // the rent of the escrow account is not part of the payments
+            let rent = program
+                .rpc()
+                .get_minimum_balance_for_rent_exemption(account.data.len())?;
+            let lamports = account.lamports.saturating_sub(rent);
+            print_with_style(
+                padding,
+                "escrow balance",
+                format!("◎ {}", lamports as f64 / LAMPORTS_PER_SOL as f64),
+            );
+        }
+    }
+
+    print_with_style(
+        padding,
+        "freeze period",
+        format_duration(escrow.freeze_period),
+    );
+
+    let now = Utc::now().timestamp();
+
+    let thaw = match escrow.first_mint_time {
+        _ if sold_out => "available (candy machine sold out)".to_string(),
+        Some(first_mint_time) => {
+            let end = first_mint_time + escrow.freeze_period;
+            print_with_style(padding, "freeze period end", format_timestamp(end));
+
+            if end <= now {
+                "available".to_string()
+            } else {
+                format!("in {}", format_duration(end - now))
+            }
+        }
+        None => "freeze period starts with the first mint".to_string(),
+    };
+
+    print_with_style(padding, "thaw", thaw);
+    print_with_style(
+        padding,
+        "unlock funds",
+        if escrow.frozen_count == 0 {
+            style("available").green().to_string()
+        } else {
+            format!("after thawing {} NFT(s)", escrow.frozen_count)
+        },
+    );
+
+    Ok(())
+}
+
+/// Formats a number of seconds as days, hours and minutes.
+fn format_duration(seconds: i64) -> String {
+    let days = seconds / 86400;
+    let hours = (seconds % 86400) / 3600;
+    let minutes = (seconds % 3600) / 60;
+
+    match (days, hours) {
+        (0, 0) => format!("{minutes}m"),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h {minutes}m"),
+    }
+}
+
+#[test]
+fn test_freeze_duration() {
+    assert_eq!(format_duration(59), "0m");
+    assert_eq!(format_duration(3 * 3600 + 120), "3h 2m");
+    assert_eq!(format_duration(2 * 86400 + 3600), "2d 1h 0m");
+}
+
+#[test]
+fn test_unique_escrows() {
+    let shared = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let freeze_guard = |label, destination, mint| FreezeGuard {
+        label,
+        destination,
+        mint,
+    };
+
+    // the guards sharing an escrow are not next to each other
+    let mut freeze_guards = vec![
+        freeze_guard("default", shared, None),
+        freeze_guard("wl", other, None),
+        freeze_guard("public", shared, None),
+        freeze_guard("token", shared, Some(mint)),
+    ];
+    unique_escrows(&mut freeze_guards);
+
+    let labels: Vec<&str> = freeze_guards.iter().map(|guard| guard.label).collect();
+    assert_eq!(labels, vec!["default", "wl", "token"]);
+}
@@ -1,3 +1,7 @@
+pub mod diff;
+pub mod guards;
 pub mod process;
 
+pub use diff::*;
+pub use guards::*;
 pub use process::*;
@@ -3,9 +3,10 @@ use std::str::FromStr;
 use anchor_client::solana_sdk::pubkey::Pubkey;
 use anyhow::Result;
 use console::style;
+use mpl_candy_guard::state::{CandyGuardData, DATA_OFFSET};
 use mpl_candy_machine_core::{
     constants::{HIDDEN_SECTION, NULL_STRING},
-    AccountVersion,
+    AccountVersion, CandyMachineData,
 };
 use mpl_token_metadata::state::TokenStandard;
 use tabled::{
@@ -13,7 +14,18 @@ use tabled::{
     settings::{object::Segment, Alignment, Modify, Style},
 };
 
-use crate::{cache::load_cache, candy_machine::*, common::*, utils::*};
+use crate::{
+    cache::load_cache,
+    candy_machine::*,
+    common::*,
+    config::get_config_data,
+    core_candy_machine::{
+        get_candy_machine_program, get_core_candy_machine_state, CORE_CANDY_MACHINE_ID,
+        CORE_HIDDEN_SECTION,
+    },
+    show::{diff_config, print_differences},
+    utils::*,
+};
 
 pub struct ShowArgs {
     pub keypair: Option<String>,
@@ -21,6 +33,9 @@ pub struct ShowArgs {
     pub cache: String,
     pub candy_machine: Option<String>,
     pub unminted: bool,
+    pub guards: bool,
+    pub config: String,
+    pub diff: bool,
 }
 
 // number of indices per line
@@ -42,14 +57,14 @@ pub fn process_show(args: ShowArgs) -> Result<()> {
 
     // the candy machine id specified takes precedence over the one from the cache
 
-    let candy_machine_id = if let Some(candy_machine) = args.candy_machine {
-        candy_machine
+    let (candy_machine_id, cache) = if let Some(candy_machine) = args.candy_machine {
+        (candy_machine, None)
     } else {
         let cache = load_cache(&args.cache, false)?;
-        cache.program.candy_machine
+        (cache.program.candy_machine.clone(), Some(cache))
     };
 
-    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
     let client = setup_client(&sugar_config)?;
     let program = client.program(CANDY_MACHINE_ID);
 
@@ -62,8 +77,17 @@ pub fn process_show(args: ShowArgs) -> Result<()> {
         }
     };
 
-    let (cndy_state, rule_set) = load_candy_machine(&sugar_config, &candy_machine_id)?;
-    let cndy_data = cndy_state.data;
+    let core =
+        get_candy_machine_program(&sugar_config, &candy_machine_id)? == CORE_CANDY_MACHINE_ID;
+
+    let (cndy_state, rule_set) = if core {
+        let cndy_state = get_core_candy_machine_state(&sugar_config, &candy_machine_id)?;
+        (cndy_state.to_candy_format(), None)
+    } else {
+        load_candy_machine(&sugar_config, &candy_machine_id)?
+    };
+    let cndy_data = cndy_state.data.clone();
+    let sold_out = cndy_state.items_redeemed >= cndy_data.items_available;
 
     pb.finish_and_clear();
 
@@ -81,11 +105,17 @@ pub fn process_show(args: ShowArgs) -> Result<()> {
     print_with_style("", "mint authority", cndy_state.mint_authority.to_string());
     print_with_style(
         "",
-        "collection mint",
+        if core {
+            "collection"
+        } else {
+            "collection mint"
+        },
         cndy_state.collection_mint.to_string(),
     );
 
-    if matches!(cndy_state.version, AccountVersion::V1) {
+    if core {
+        print_with_style("", "asset standard", "Core");
+    } else if matches!(cndy_state.version, AccountVersion::V1) {
         print_with_style("", "account version", "V1");
         print_with_style("", "token standard", "NonFungible (NFT)");
         print_with_style("", "rule set", "none");
@@ -111,29 +141,12 @@ pub fn process_show(args: ShowArgs) -> Result<()> {
     print_with_style("", "items redeemed", cndy_state.items_redeemed.to_string());
     print_with_style("", "items available", cndy_data.items_available.to_string());
 
-    print_with_style("", "symbol", cndy_data.symbol.trim_end_matches(NULL_STRING));
-    print_with_style(
-        "",
-        "seller fee basis points",
-        format!(
-            "{}% ({})",
-            cndy_data.seller_fee_basis_points / 100,
-            cndy_data.seller_fee_basis_points
-        ),
-    );
-    print_with_style("", "is mutable", cndy_data.is_mutable.to_string());
-    print_with_style("", "creators", "".to_string());
-
-    let creators = &cndy_data.creators;
-
-    for (index, creator) in creators.iter().enumerate() {
-        let info = format!(
-            "{} ({}%{})",
-            creator.address,
-            creator.percentage_share,
-            if creator.verified { ", verified" } else { "" },
-        );
-        print_with_style(":   ", &(index + 1).to_string(), info);
+    // royalties and creators of Core assets are plugins of the collection
+    if core {
+        print_with_style("", "is mutable", cndy_data.is_mutable.to_string());
+        print_with_style("", "royalties", "collection plugin");
+    } else {
+        print_show_metadata(&cndy_data);
     }
 
     // hidden settings
@@ -192,6 +205,76 @@ pub fn process_show(args: ShowArgs) -> Result<()> {
         print_with_style("", "config line settings", "none");
     }
 
+    // guards and freeze escrows
+
+    if args.guards {
+        if core {
+            print_with_style(
+                "",
+                "guards",
+                "use 'sugar guard show' for Core candy machines",
+            );
+        } else {
+            print_guards(
+                &client.program(mpl_candy_guard::ID),
+                &candy_machine_id,
+                cndy_state.mint_authority,
+                sold_out,
+            )?;
+        }
+    }
+
+    // differences with the config
+
+    if args.diff {
+        let config_data = get_config_data(&args.config)?;
+
+        let guards = if core {
+            if config_data.guards.is_some() {
+                println!(
+                    "\n{}",
+                    style(
+                        "The guards of Core candy machines are not compared, use \
+                        'sugar guard show' to inspect them."
+                    )
+                    .dim()
+                );
+            }
+            None
+        } else {
+            let rpc = program.rpc();
+            // a missing account means that the candy machine is not wrapped, while any
+            // other failure must not be reported as a difference
+            match rpc
+                .get_account_with_commitment(&cndy_state.mint_authority, rpc.commitment())?
+                .value
+            {
+                Some(account) if account.owner == mpl_candy_guard::ID => {
+                    Some(CandyGuardData::load(&account.data[DATA_OFFSET..])?)
+                }
+                _ => None,
+            }
+        };
+
+        let differences = diff_config(&config_data, &cndy_state, guards.as_ref(), core)?;
+        print_differences(&differences, &args.config);
+
+        if !differences.is_empty() {
+            return Err(SugarError::Drift(format!(
+                "The on-chain state differs from '{}' in {} field(s).",
+                args.config,
+                differences.len()
+            ))
+            .into());
+        }
+    }
+
+    // stage of the cache items
+
+    if let Some(cache) = &cache {
+        print_cache_status(cache);
+    }
+
     // unminted indices
 
     if args.unminted {
@@ -201,7 +284,12 @@ pub fn process_show(args: ShowArgs) -> Result<()> {
             LOOKING_GLASS_EMOJI
         );
 
-        let start = HIDDEN_SECTION
+        let hidden_section = if core {
+            CORE_HIDDEN_SECTION
+        } else {
+            HIDDEN_SECTION
+        };
+        let start = hidden_section
             + 4
             + (cndy_data.items_available as usize) * cndy_data.get_config_line_size()
             + (cndy_data
@@ -265,6 +353,61 @@ pub fn process_show(args: ShowArgs) -> Result<()> {
     Ok(())
 }
 
+/// Print the number of cache items at each stage.
+fn print_cache_status(cache: &Cache) {
+    let mut counts: HashMap<ItemStatus, usize> = HashMap::new();
+
+    for item in cache.items.values() {
+        *counts.entry(item.status).or_default() += 1;
+    }
+
+    print_with_style("", "cache items", cache.items.len().to_string());
+
+    for status in [
+        ItemStatus::Pending,
+        ItemStatus::Uploaded,
+        ItemStatus::Deployed,
+        ItemStatus::Verified,
+        ItemStatus::Revealed,
+    ] {
+        if let Some(count) = counts.get(&status) {
+            print_with_style(
+                ":   ",
+                &format!("{:?}", status).to_lowercase(),
+                count.to_string(),
+            );
+        }
+    }
+}
+
+/// Print the metadata fields stored on the candy machine (Token Metadata standard).
+fn print_show_metadata(cndy_data: &CandyMachineData) {
+    print_with_style("", "symbol", cndy_data.symbol.trim_end_matches(NULL_STRING));
+    print_with_style(
+        "",
+        "seller fee basis points",
+        format!(
+            "{}% ({})",
+            cndy_data.seller_fee_basis_points / 100,
+            cndy_data.seller_fee_basis_points
+        ),
+    );
+    print_with_style("", "is mutable", cndy_data.is_mutable.to_string());
+    print_with_style("", "creators", "".to_string());
+
+    let creators = &cndy_data.creators;
+
+    for (index, creator) in creators.iter().enumerate() {
+        let info = format!(
+            "{} ({}%{})",
+            creator.address,
+            creator.percentage_share,
+            if creator.verified { ", verified" } else { "" },
+        );
+        print_with_style(":   ", &(index + 1).to_string(), info);
+    }
+}
+
 pub fn print_with_style<S>(indent: &str, key: &str, value: S)
 where
     S: core::fmt::Display,
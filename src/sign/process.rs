@@ -16,7 +16,6 @@ use anyhow::Error;
 use console::style;
 use mpl_token_metadata::{instruction::sign_metadata, ID as METAPLEX_PROGRAM_ID};
 use retry::{delay::Exponential, retry};
-use solana_client::rpc_client::RpcClient;
 use tokio::sync::Semaphore;
 
 use crate::{
@@ -24,7 +23,10 @@ use crate::{
     candy_machine::CANDY_MACHINE_ID,
     common::*,
     config::{Cluster, SugarConfig},
+    costs::record_transaction,
+    dry_run::{is_dry_run, plan_transaction},
     pdas::{find_candy_machine_creator_pda, find_metadata_pda},
+    rate_limit::{throttled_client_with_commitment, throttled_client_with_timeout},
     setup::{get_rpc_url, setup_client, sugar_setup},
     utils::*,
 };
@@ -100,7 +102,7 @@ pub async fn process_sign(args: SignArgs) -> Result<()> {
         let candy_machine_id = Pubkey::from_str(&candy_machine_id)
             .expect("Failed to parse pubkey from candy machine id.");
 
-        let solana_cluster: Cluster = get_cluster(program.rpc())?;
+        let solana_cluster: Cluster = get_cluster(&program.rpc())?;
         let rpc_url = get_rpc_url(args.rpc_url);
 
         let solana_cluster = if rpc_url.ends_with("8899") {
@@ -111,7 +113,7 @@ pub async fn process_sign(args: SignArgs) -> Result<()> {
 
         let account_keys = match solana_cluster {
             Cluster::Devnet | Cluster::Localnet | Cluster::Mainnet => {
-                let client = RpcClient::new_with_timeout(&rpc_url, Duration::from_secs(300));
+                let client = throttled_client_with_timeout(&rpc_url, Duration::from_secs(300));
                 let (creator, _) = find_candy_machine_creator_pda(&candy_machine_id);
                 let creator = bs58::encode(creator).into_string();
                 get_cm_creator_metadata_accounts(&client, &creator, 0)?
@@ -172,10 +174,9 @@ pub async fn process_sign(args: SignArgs) -> Result<()> {
 }
 
 async fn sign(config: Arc<SugarConfig>, metadata: Pubkey) -> Result<(), Error> {
-    let client = setup_client(&config)?;
-    let program = client.program(CANDY_MACHINE_ID);
+    let rpc_client = throttled_client_with_commitment(&config.rpc_url);
 
-    let recent_blockhash = program.rpc().get_latest_blockhash()?;
+    let recent_blockhash = rpc_client.get_latest_blockhash()?;
 
     let ix = sign_metadata(METAPLEX_PROGRAM_ID, metadata, config.keypair.pubkey());
     let tx = Transaction::new_signed_with_payer(
@@ -185,11 +186,17 @@ async fn sign(config: Arc<SugarConfig>, metadata: Pubkey) -> Result<(), Error> {
         recent_blockhash,
     );
 
+    if is_dry_run() {
+        plan_transaction("sign", &tx);
+        return Ok(());
+    }
+
     // Send tx with retries.
-    retry(
+    let signature = retry(
         Exponential::from_millis_with_factor(250, 2.0).take(3),
-        || program.rpc().send_and_confirm_transaction(&tx),
+        || rpc_client.send_and_confirm_transaction(&tx),
     )?;
+    record_transaction(&signature, "sign");
 
     Ok(())
 }
@@ -0,0 +1,178 @@
+use std::collections::HashSet;
+
+use anchor_client::solana_sdk::program_pack::Pack;
+use chrono::{SecondsFormat, Utc};
+use console::style;
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use spl_token::state::Account as TokenAccount;
+
+use crate::{
+    cache::load_cache,
+    common::*,
+    core_candy_machine::{get_candy_machine_program, CORE_CANDY_MACHINE_ID},
+    pdas::find_candy_machine_creator_pda,
+    rate_limit::throttled_client,
+    setup::sugar_setup_read_only,
+    utils::{get_cm_creator_mint_accounts, progress_bar_with_style, spinner_with_style},
+};
+
+/// Maximum number of accounts per `getMultipleAccounts` request.
+const MAX_ACCOUNTS: usize = 100;
+
+pub struct SnapshotArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub candy_machine: Option<String>,
+    pub output: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotEntry {
+    mint: String,
+    owner: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    candy_machine: String,
+    /// Slot at which the holders were read.
+    slot: u64,
+    taken_at: String,
+    holders: usize,
+    mints: Vec<SnapshotEntry>,
+}
+
+pub fn process_snapshot(args: SnapshotArgs) -> Result<()> {
+    // the candy machine id specified takes precedence over the one from the cache
+    let candy_machine = match args.candy_machine {
+        Some(candy_machine) => candy_machine,
+        None => load_cache(&args.cache, false)?.program.candy_machine,
+    };
+    let candy_pubkey = Pubkey::from_str(&candy_machine)
+        .map_err(|_| CacheError::InvalidCandyMachineAddress(candy_machine.clone()))?;
+
+    // the snapshot only reads accounts, no keypair is needed
+    let sugar_config = sugar_setup_read_only(args.keypair, args.rpc_url)?;
+
+    if get_candy_machine_program(&sugar_config, &candy_pubkey)? == CORE_CANDY_MACHINE_ID {
+        return Err(anyhow!(
+            "Snapshots of Core candy machines are not supported, their assets have no \
+            creator to list them."
+        ));
+    }
+
+    println!(
+        "{} {}Looking up the minted NFTs",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let client = throttled_client(&sugar_config.rpc_url);
+    let slot = client.get_slot()?;
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+    let (creator, _) = find_candy_machine_creator_pda(&candy_pubkey);
+    let mints = get_cm_creator_mint_accounts(&client, &creator.to_string(), 0)?;
+    pb.finish_and_clear();
+
+    println!(
+        "\n{} {}Reading the holders of {} NFT(s)",
+        style("[2/2]").bold().dim(),
+        PAPER_EMOJI,
+        mints.len()
+    );
+
+    let mints = get_holders(&client, &mints)?;
+    let holders = count_holders(&mints);
+
+    let snapshot = Snapshot {
+        candy_machine,
+        slot,
+        taken_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        holders,
+        mints,
+    };
+
+    serde_json::to_writer_pretty(
+        File::create(&args.output)
+            .map_err(|e| anyhow!("Failed to create the snapshot '{}': {}", args.output, e))?,
+        &snapshot,
+    )?;
+
+    println!(
+        "\n{} NFT(s) held by {} wallet(s), snapshot written to {}",
+        snapshot.mints.len(),
+        holders,
+        args.output
+    );
+
+    Ok(())
+}
+
+/// Returns the owner of each NFT, read from the token account holding it. Burned NFTs
+/// (without token account holding it) are skipped.
+fn get_holders(client: &RpcClient, mints: &[Pubkey]) -> Result<Vec<SnapshotEntry>> {
+    let pb = progress_bar_with_style(mints.len() as u64);
+    let mut token_accounts = Vec::with_capacity(mints.len());
+
+    for mint in mints {
+        let largest = client.get_token_largest_accounts(mint)?;
+
+        if let Some(account) = largest
+            .into_iter()
+            .find(|account| account.amount.amount == "1")
+        {
+            token_accounts.push((*mint, Pubkey::from_str(&account.address)?));
+        }
+        pb.inc(1);
+    }
+
+    pb.finish_and_clear();
+
+    let mut entries = Vec::with_capacity(token_accounts.len());
+
+    for chunk in token_accounts.chunks(MAX_ACCOUNTS) {
+        let pubkeys: Vec<Pubkey> = chunk.iter().map(|(_, pubkey)| *pubkey).collect();
+        let accounts = client.get_multiple_accounts(&pubkeys)?;
+
+        for ((mint, _), account) in chunk.iter().zip(accounts) {
+            if let Some(account) = account {
+                let token_account = TokenAccount::unpack(&account.data)?;
+                entries.push(SnapshotEntry {
+                    mint: mint.to_string(),
+                    owner: token_account.owner.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Number of distinct wallets holding the NFTs.
+fn count_holders(entries: &[SnapshotEntry]) -> usize {
+    entries
+        .iter()
+        .map(|entry| entry.owner.as_str())
+        .collect::<HashSet<&str>>()
+        .len()
+}
+
+#[test]
+fn test_count_holders() {
+    let entry = |mint: &str, owner: &str| SnapshotEntry {
+        mint: mint.to_string(),
+        owner: owner.to_string(),
+    };
+
+    assert_eq!(count_holders(&[]), 0);
+    assert_eq!(
+        count_holders(&[entry("a", "alice"), entry("b", "bob"), entry("c", "alice")]),
+        2
+    );
+}
@@ -0,0 +1,3 @@
+pub mod process;
+
+pub use process::*;
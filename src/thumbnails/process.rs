@@ -0,0 +1,205 @@
+use std::{ffi::OsStr, fs, process::Command};
+
+use console::style;
+use serde_json::Map;
+
+use crate::{
+    common::*,
+    upload::{get_base_name, list_files},
+    utils::*,
+};
+
+/// Image extensions recognized by the upload.
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
+
+/// Video extensions from which a frame can be extracted.
+const VIDEO_EXTENSIONS: [&str; 3] = ["mp4", "mov", "webm"];
+
+/// Animation extensions supported by the upload.
+const ANIMATION_EXTENSIONS: [&str; 5] = ["mp3", "mp4", "mov", "webm", "glb"];
+
+pub struct ThumbnailsArgs {
+    pub assets_dir: String,
+    pub template: Option<String>,
+    pub ffmpeg: String,
+}
+
+pub fn process_thumbnails(args: ThumbnailsArgs) -> Result<()> {
+    println!(
+        "{} {}Looking for animation-only assets",
+        style("[1/2]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let files: Vec<String> = list_files(&args.assets_dir, true)?
+        .into_iter()
+        .filter_map(|entry| entry.file_name().to_str().map(String::from))
+        .collect();
+
+    let has_extension = |base_name: &str, extensions: &[&str]| -> Option<String> {
+        files
+            .iter()
+            .find(|file| {
+                get_base_name(file) == base_name && extensions.contains(&extension(file).as_str())
+            })
+            .cloned()
+    };
+
+    let mut pending = Vec::new();
+
+    for file in files.iter().filter(|file| extension(file) == "json") {
+        let base_name = get_base_name(file);
+
+        if has_extension(&base_name, &IMAGE_EXTENSIONS).is_some() {
+            continue;
+        }
+
+        if let Some(animation) = has_extension(&base_name, &ANIMATION_EXTENSIONS) {
+            pending.push((base_name, file.clone(), animation));
+        }
+    }
+
+    if pending.is_empty() {
+        println!("\nAll assets have an image, nothing to do.");
+        return Ok(());
+    }
+
+    let ffmpeg_available = Command::new(&args.ffmpeg)
+        .arg("-version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+
+    if !ffmpeg_available {
+        if args.template.is_none() {
+            return Err(anyhow!(
+                "Could not run '{}' to extract video frames, install ffmpeg or use \
+                --template to provide a poster image",
+                args.ffmpeg
+            ));
+        }
+
+        println!(
+            "{}ffmpeg not found, using the template image for all assets",
+            WARNING_EMOJI
+        );
+    }
+
+    println!(
+        "\n{} {}Generating {} thumbnail(s)",
+        style("[2/2]").bold().dim(),
+        ASSETS_EMOJI,
+        pending.len()
+    );
+
+    let assets_dir = Path::new(&args.assets_dir);
+    let pb = progress_bar_with_style(pending.len() as u64);
+
+    for (base_name, metadata_file, animation) in pending {
+        let animation_path = assets_dir.join(&animation);
+
+        let image =
+            if ffmpeg_available && VIDEO_EXTENSIONS.contains(&extension(&animation).as_str()) {
+                let image = format!("{base_name}.png");
+                extract_frame(&args.ffmpeg, &animation_path, &assets_dir.join(&image))?;
+                image
+            } else if let Some(template) = &args.template {
+                let image = format!("{}.{}", base_name, extension(template));
+                fs::copy(template, assets_dir.join(&image))
+                    .map_err(|e| anyhow!("Failed to copy template '{}': {}", template, e))?;
+                image
+            } else {
+                return Err(anyhow!(
+                    "Cannot extract a frame from '{}', use --template to provide a poster image",
+                    animation
+                ));
+            };
+
+        set_metadata_image(&assets_dir.join(&metadata_file), &image)?;
+        pb.inc(1);
+    }
+
+    pb.finish_and_clear();
+
+    println!("\n{}Thumbnails generated.", COMPLETE_EMOJI);
+
+    Ok(())
+}
+
+fn extension(file: &str) -> String {
+    Path::new(file)
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Return the MIME type of an image from its extension.
+fn image_type(image: &str) -> String {
+    match extension(image).as_str() {
+        "jpg" => "image/jpeg".to_string(),
+        extension => format!("image/{}", extension),
+    }
+}
+
+/// Extract the first frame of a video as a PNG image.
+fn extract_frame(ffmpeg: &str, video: &Path, image: &Path) -> Result<()> {
+    let output = Command::new(ffmpeg)
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(video)
+        .args(["-frames:v", "1"])
+        .arg(image)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to extract a frame from {:?}: {}",
+            video,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Set the image of the metadata file, adding it to the list of files.
+fn set_metadata_image(metadata_file: &Path, image: &str) -> Result<()> {
+    let mut metadata: Value = serde_json::from_reader(File::open(metadata_file)?)
+        .map_err(|e| anyhow!("Failed to parse {:?}: {}", metadata_file, e))?;
+
+    let object = metadata
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Invalid metadata file {:?}", metadata_file))?;
+
+    object.insert("image".to_string(), json!(image));
+
+    let properties = object
+        .entry("properties")
+        .or_insert_with(|| Value::Object(Map::new()));
+
+    if let Some(properties) = properties.as_object_mut() {
+        let files = properties.entry("files").or_insert_with(|| json!([]));
+
+        if let Some(files) = files.as_array_mut() {
+            files.retain(|file| file["uri"] != json!(image));
+            files.insert(
+                0,
+                json!({
+                    "uri": image,
+                    "type": image_type(image),
+                }),
+            );
+        }
+    }
+
+    fs::write(metadata_file, serde_json::to_string_pretty(&metadata)?)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_image_type() {
+    assert_eq!(image_type("0.png"), "image/png");
+    assert_eq!(image_type("poster.JPG"), "image/jpeg");
+    assert_eq!(image_type("poster.jpeg"), "image/jpeg");
+}
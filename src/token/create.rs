@@ -0,0 +1,371 @@
+use std::{fmt, fs};
+
+use anchor_client::solana_sdk::compute_budget::ComputeBudgetInstruction;
+use console::style;
+use mpl_token_metadata::instruction::create_metadata_accounts_v3;
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account,
+};
+use spl_token::{
+    instruction::{initialize_mint, mint_to},
+    ID as TOKEN_PROGRAM_ID,
+};
+
+use crate::{
+    candy_machine::CANDY_MACHINE_ID,
+    common::*,
+    dry_run::{is_dry_run, SendOrPlan},
+    lock::lock_file,
+    pdas::find_metadata_pda,
+    utils::*,
+};
+
+/// Token guards that can be pointed at the created mint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenGuard {
+    TokenPayment,
+    TokenGate,
+    TokenBurn,
+    FreezeTokenPayment,
+}
+
+impl TokenGuard {
+    /// Whether the guard transfers the tokens to a destination token account.
+    fn has_destination(&self) -> bool {
+        matches!(
+            self,
+            TokenGuard::TokenPayment | TokenGuard::FreezeTokenPayment
+        )
+    }
+}
+
+impl FromStr for TokenGuard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tokenPayment" => Ok(TokenGuard::TokenPayment),
+            "tokenGate" => Ok(TokenGuard::TokenGate),
+            "tokenBurn" => Ok(TokenGuard::TokenBurn),
+            "freezeTokenPayment" => Ok(TokenGuard::FreezeTokenPayment),
+            _ => Err(anyhow!(
+                "Invalid guard '{s}': expected tokenPayment, tokenGate, tokenBurn or \
+                 freezeTokenPayment"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TokenGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenGuard::TokenPayment => write!(f, "tokenPayment"),
+            TokenGuard::TokenGate => write!(f, "tokenGate"),
+            TokenGuard::TokenBurn => write!(f, "tokenBurn"),
+            TokenGuard::FreezeTokenPayment => write!(f, "freezeTokenPayment"),
+        }
+    }
+}
+
+/// On-chain metadata of the token.
+#[derive(Debug, Deserialize)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+pub struct TokenCreateArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub config: String,
+    pub decimals: u8,
+    pub supply: u64,
+    pub metadata: Option<String>,
+    pub guard: TokenGuard,
+    pub group: Option<String>,
+    pub amount: Option<u64>,
+}
+
+pub fn process_token_create(args: TokenCreateArgs) -> Result<()> {
+    println!(
+        "{} {}Creating token",
+        style("[1/2]").bold().dim(),
+        COMPUTER_EMOJI
+    );
+
+    let metadata = match &args.metadata {
+        Some(metadata) => {
+            let metadata: TokenMetadata = serde_json::from_str(
+                &fs::read_to_string(metadata)
+                    .map_err(|e| anyhow!("Failed to read '{}': {}", metadata, e))?,
+            )
+            .map_err(|e| anyhow!("Invalid token metadata file '{}': {}", metadata, e))?;
+            Some(metadata)
+        }
+        None => None,
+    };
+
+    let supply = 10u64
+        .checked_pow(args.decimals as u32)
+        .and_then(|unit| args.supply.checked_mul(unit))
+        .ok_or_else(|| {
+            anyhow!(
+                "Supply of {} with {} decimals is too large.",
+                args.supply,
+                args.decimals
+            )
+        })?;
+
+    // the guard is checked before creating anything on-chain
+    let mut config = read_config(&args.config)?;
+    set_token_guard(
+        &mut config.clone(),
+        args.guard,
+        args.group.as_deref(),
+        &Pubkey::default(),
+        &Pubkey::default(),
+        args.amount,
+    )?;
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(CANDY_MACHINE_ID);
+    let payer = program.payer();
+
+    let pb = spinner_with_style();
+    pb.set_message("Sending create token transaction...");
+
+    let mint = Keypair::new();
+    let min_rent = program
+        .rpc()
+        .get_minimum_balance_for_rent_exemption(MINT_LAYOUT as usize)?;
+    let ata = get_associated_token_address(&payer, &mint.pubkey());
+
+    let mut builder = program
+        .request()
+        .instruction(ComputeBudgetInstruction::set_compute_unit_limit(
+            COMPUTE_UNITS,
+        ))
+        .instruction(ComputeBudgetInstruction::set_compute_unit_price(
+            PRIORITY_FEE,
+        ))
+        .instruction(system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            min_rent,
+            MINT_LAYOUT,
+            &TOKEN_PROGRAM_ID,
+        ))
+        .instruction(initialize_mint(
+            &TOKEN_PROGRAM_ID,
+            &mint.pubkey(),
+            &payer,
+            Some(&payer),
+            args.decimals,
+        )?)
+        .instruction(create_associated_token_account(
+            &payer,
+            &payer,
+            &mint.pubkey(),
+            &TOKEN_PROGRAM_ID,
+        ))
+        .signer(&mint);
+
+    if supply > 0 {
+        builder = builder.instruction(mint_to(
+            &TOKEN_PROGRAM_ID,
+            &mint.pubkey(),
+            &ata,
+            &payer,
+            &[],
+            supply,
+        )?);
+    }
+
+    if let Some(metadata) = metadata {
+        builder = builder.instruction(create_metadata_accounts_v3(
+            mpl_token_metadata::ID,
+            find_metadata_pda(&mint.pubkey()),
+            mint.pubkey(),
+            payer,
+            payer,
+            payer,
+            metadata.name,
+            metadata.symbol,
+            metadata.uri,
+            None,
+            0,
+            true,
+            true,
+            None,
+            None,
+            None,
+        ));
+    }
+
+    let signature = builder.send_or_plan("create token")?;
+
+    pb.finish_and_clear();
+
+    println!("{} {}", style("Signature:").bold(), signature);
+    println!("{} {}", style("Mint:").bold(), mint.pubkey());
+    println!("{} {}", style("Token account:").bold(), ata);
+
+    println!(
+        "\n{} {}Updating the {} guard",
+        style("[2/2]").bold().dim(),
+        PAPER_EMOJI,
+        args.guard
+    );
+
+    set_token_guard(
+        &mut config,
+        args.guard,
+        args.group.as_deref(),
+        &mint.pubkey(),
+        &ata,
+        args.amount,
+    )?;
+
+    if is_dry_run() {
+        println!(
+            "{}",
+            style("The config file is not updated in a dry run.").dim()
+        );
+        return Ok(());
+    }
+
+    lock_file(&args.config)?;
+    fs::write(&args.config, serde_json::to_string_pretty(&config)?)
+        .map_err(|e| anyhow!("Failed to write '{}': {}", args.config, e))?;
+
+    println!(
+        "{}",
+        style(format!(
+            "The {} guard of '{}' now uses the token {}.",
+            args.guard,
+            args.config,
+            mint.pubkey()
+        ))
+        .green()
+        .bold()
+    );
+
+    Ok(())
+}
+
+/// Reads the config file as JSON, so fields sugar does not know about are preserved
+/// when the guard is updated.
+fn read_config(config: &str) -> Result<Value> {
+    let content =
+        fs::read_to_string(config).map_err(|e| anyhow!("Failed to read '{}': {}", config, e))?;
+
+    serde_json::from_str(&content).map_err(|e| anyhow!("Invalid config file '{}': {}", config, e))
+}
+
+/// Points the token guard of the default guard set (or of a group) to the mint,
+/// creating the guard when the amount is specified.
+pub fn set_token_guard(
+    config: &mut Value,
+    guard: TokenGuard,
+    group: Option<&str>,
+    mint: &Pubkey,
+    destination_ata: &Pubkey,
+    amount: Option<u64>,
+) -> Result<()> {
+    let guards = config
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("The config file is not a JSON object."))?
+        .entry("guards")
+        .or_insert_with(|| json!({ "default": {} }));
+
+    if guards.is_null() {
+        *guards = json!({ "default": {} });
+    }
+
+    let guard_set = match group {
+        Some(label) => guards
+            .get_mut("groups")
+            .and_then(Value::as_array_mut)
+            .and_then(|groups| {
+                groups
+                    .iter_mut()
+                    .find(|group| group["label"].as_str() == Some(label))
+            })
+            .map(|group| &mut group["guards"])
+            .ok_or_else(|| anyhow!("Group '{}' not found in the config.", label))?,
+        None => &mut guards["default"],
+    };
+
+    if guard_set.is_null() {
+        *guard_set = json!({});
+    }
+
+    let key = guard.to_string();
+    let guard_value = &mut guard_set[key.as_str()];
+
+    if guard_value.is_null() {
+        let amount = amount.ok_or_else(|| {
+            anyhow!(
+                "The guard set has no {} guard, use --amount to add it.",
+                guard
+            )
+        })?;
+        *guard_value = json!({ "amount": amount });
+    } else if let Some(amount) = amount {
+        guard_value["amount"] = json!(amount);
+    }
+
+    guard_value["mint"] = json!(mint.to_string());
+
+    if guard.has_destination() {
+        guard_value["destinationAta"] = json!(destination_ata.to_string());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_token_guard_config() {
+    let mint = Pubkey::new_unique();
+    let ata = Pubkey::new_unique();
+    let mut config = json!({
+        "number": 10,
+        "guards": {
+            "default": { "tokenGate": { "amount": 1, "mint": Pubkey::default().to_string() } },
+            "groups": [{ "label": "holders", "guards": {} }]
+        }
+    });
+
+    set_token_guard(&mut config, TokenGuard::TokenGate, None, &mint, &ata, None).unwrap();
+    assert_eq!(
+        config["guards"]["default"]["tokenGate"],
+        json!({ "amount": 1, "mint": mint.to_string() })
+    );
+
+    assert!(set_token_guard(
+        &mut config,
+        TokenGuard::TokenPayment,
+        Some("holders"),
+        &mint,
+        &ata,
+        None
+    )
+    .is_err());
+
+    set_token_guard(
+        &mut config,
+        TokenGuard::TokenPayment,
+        Some("holders"),
+        &mint,
+        &ata,
+        Some(5),
+    )
+    .unwrap();
+    assert_eq!(
+        config["guards"]["groups"][0]["guards"]["tokenPayment"]["destinationAta"],
+        json!(ata.to_string())
+    );
+    assert_eq!(config["number"], json!(10));
+}
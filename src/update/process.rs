@@ -3,16 +3,28 @@ use std::str::FromStr;
 use anchor_client::solana_sdk::{compute_budget::ComputeBudgetInstruction, pubkey::Pubkey};
 use anyhow::Result;
 use console::style;
+use mpl_candy_guard::{
+    accounts::Update as GuardUpdateAccount,
+    guards::StartDate,
+    instruction::Update as GuardUpdate,
+    state::{CandyGuardData, DATA_OFFSET},
+};
 use mpl_candy_machine_core::{
-    accounts as nft_accounts, instruction as nft_instruction, CandyMachineData,
+    accounts as nft_accounts, instruction as nft_instruction, CandyMachine, CandyMachineData,
 };
 
 use crate::{
     cache::load_cache,
     candy_machine::{get_candy_machine_state, CANDY_MACHINE_ID},
     common::*,
-    config::{data::ConfigData, parser::get_config_data},
+    config::{
+        data::ConfigData, parse_date, parser::get_config_data, price_as_lamports,
+        print_guard_dates, SugarConfig,
+    },
+    dry_run::SendOrPlan,
+    setup::SugarClient,
     utils::{assert_correct_authority, spinner_with_style},
+    validate::parser::check_symbol,
 };
 
 pub struct UpdateArgs {
@@ -22,12 +34,38 @@ pub struct UpdateArgs {
     pub new_authority: Option<String>,
     pub config: String,
     pub candy_machine: Option<String>,
+    pub fields: UpdateFields,
+}
+
+/// Individual fields to update instead of pushing the whole config file.
+#[derive(Debug, Default)]
+pub struct UpdateFields {
+    pub price: Option<f64>,
+    pub go_live_date: Option<String>,
+    pub items_available: Option<u64>,
+    pub symbol: Option<String>,
+}
+
+impl UpdateFields {
+    fn is_empty(&self) -> bool {
+        self.price.is_none()
+            && self.go_live_date.is_none()
+            && self.items_available.is_none()
+            && self.symbol.is_none()
+    }
+
+    fn updates_candy_machine(&self) -> bool {
+        self.items_available.is_some() || self.symbol.is_some()
+    }
+
+    fn updates_candy_guard(&self) -> bool {
+        self.price.is_some() || self.go_live_date.is_some()
+    }
 }
 
 pub fn process_update(args: UpdateArgs) -> Result<()> {
     let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
     let client = setup_client(&sugar_config)?;
-    let config_data = get_config_data(&args.config)?;
 
     // the candy machine id specified takes precedence over the one from the cache
     let candy_machine_id = match args.candy_machine {
@@ -58,7 +96,21 @@ pub fn process_update(args: UpdateArgs) -> Result<()> {
     pb.set_message("Connecting...");
 
     let candy_machine_state = get_candy_machine_state(&sugar_config, &candy_pubkey)?;
-    let candy_machine_data = create_candy_machine_data(&config_data, &candy_machine_state.data)?;
+    // when individual fields are specified, the update starts from the on-chain values
+    let candy_machine_data = if args.fields.is_empty() {
+        let config_data = get_config_data(&args.config)?;
+        Some(create_candy_machine_data(
+            &config_data,
+            &candy_machine_state.data,
+        )?)
+    } else if args.fields.updates_candy_machine() {
+        Some(update_candy_machine_fields(
+            &args.fields,
+            &candy_machine_state,
+        )?)
+    } else {
+        None
+    };
 
     pb.finish_with_message("Done");
 
@@ -74,31 +126,38 @@ pub fn process_update(args: UpdateArgs) -> Result<()> {
     );
 
     let program = client.program(CANDY_MACHINE_ID);
-    let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
-    let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
 
-    let builder = program
-        .request()
-        .instruction(compute_units)
-        .instruction(priority_fee)
-        .accounts(nft_accounts::Update {
-            candy_machine: candy_pubkey,
-            authority: program.payer(),
-        })
-        .args(nft_instruction::Update {
-            data: candy_machine_data,
-        });
+    if let Some(candy_machine_data) = candy_machine_data {
+        let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
+        let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
 
-    let pb = spinner_with_style();
-    pb.set_message("Sending update transaction...");
+        let builder = program
+            .request()
+            .instruction(compute_units)
+            .instruction(priority_fee)
+            .accounts(nft_accounts::Update {
+                candy_machine: candy_pubkey,
+                authority: program.payer(),
+            })
+            .args(nft_instruction::Update {
+                data: candy_machine_data,
+            });
 
-    let update_signature = builder.send()?;
+        let pb = spinner_with_style();
+        pb.set_message("Sending update transaction...");
 
-    pb.finish_with_message(format!(
-        "{} {}",
-        style("Update signature:").bold(),
-        update_signature
-    ));
+        let update_signature = builder.send_or_plan("update candy machine")?;
+
+        pb.finish_with_message(format!(
+            "{} {}",
+            style("Update signature:").bold(),
+            update_signature
+        ));
+    }
+
+    if args.fields.updates_candy_guard() {
+        update_candy_guard_fields(&client, &sugar_config, &args.fields, &candy_machine_state)?;
+    }
 
     if let Some(new_authority) = args.new_authority {
         let pb = spinner_with_style();
@@ -121,7 +180,7 @@ pub fn process_update(args: UpdateArgs) -> Result<()> {
                 new_authority: new_authority_pubkey,
             });
 
-        let authority_signature = builder.send()?;
+        let authority_signature = builder.send_or_plan("set authority")?;
         pb.finish_with_message(format!(
             "{} {}",
             style("Authority signature:").bold(),
@@ -132,6 +191,128 @@ pub fn process_update(args: UpdateArgs) -> Result<()> {
     Ok(())
 }
 
+/// Apply the individual field updates to the on-chain candy machine data,
+/// refusing the changes that are not valid for the current state.
+fn update_candy_machine_fields(
+    fields: &UpdateFields,
+    candy_machine: &CandyMachine,
+) -> Result<CandyMachineData> {
+    let mut data = candy_machine.data.clone();
+    let minted = candy_machine.items_redeemed;
+
+    if let Some(symbol) = &fields.symbol {
+        check_symbol(symbol)?;
+
+        if minted > 0 && *symbol != data.symbol.trim_matches(char::from(0)) {
+            return Err(anyhow!(
+                "Cannot change the symbol: {} item(s) already minted with symbol '{}' and \
+                their metadata would not be updated",
+                minted,
+                data.symbol.trim_matches(char::from(0))
+            ));
+        }
+
+        data.symbol = symbol.clone();
+    }
+
+    if let Some(items_available) = fields.items_available {
+        if data.hidden_settings.is_none() && items_available != data.items_available {
+            return Err(anyhow!(
+                "Cannot change the number of items: the candy machine has {} config line(s) \
+                allocated at creation and only candy machines with hidden settings can change it",
+                data.items_available
+            ));
+        }
+
+        if items_available < minted {
+            return Err(anyhow!(
+                "Cannot set the number of items to {}: {} item(s) already minted",
+                items_available,
+                minted
+            ));
+        }
+
+        data.items_available = items_available;
+    }
+
+    Ok(data)
+}
+
+/// Update the price and go live date on the default guard set of the candy guard
+/// wrapping the candy machine.
+fn update_candy_guard_fields(
+    client: &SugarClient,
+    sugar_config: &SugarConfig,
+    fields: &UpdateFields,
+    candy_machine: &CandyMachine,
+) -> Result<()> {
+    let program = client.program(mpl_candy_guard::ID);
+    let candy_guard_id = candy_machine.mint_authority;
+
+    let account = program.rpc().get_account(&candy_guard_id)?;
+    if account.owner != mpl_candy_guard::ID {
+        return Err(anyhow!(
+            "Cannot update price or go live date: the candy machine mint authority {} is not \
+            a candy guard",
+            candy_guard_id
+        ));
+    }
+
+    let mut data = CandyGuardData::load(&account.data[DATA_OFFSET..])?;
+
+    if let Some(price) = fields.price {
+        match data.default.sol_payment.as_mut() {
+            Some(sol_payment) => sol_payment.lamports = price_as_lamports(price),
+            None => {
+                return Err(anyhow!(
+                    "Cannot update the price: the default guard set has no solPayment guard, \
+                    add it to the config file and run 'sugar guard update'"
+                ));
+            }
+        }
+    }
+
+    if let Some(go_live_date) = &fields.go_live_date {
+        let timestamp = parse_date(go_live_date)?.timestamp();
+        data.default.start_date = Some(StartDate { date: timestamp });
+    }
+
+    print_guard_dates(&data);
+
+    let mut serialized_data = vec![0; data.size()];
+    data.save(&mut serialized_data)?;
+
+    let pb = spinner_with_style();
+    pb.set_message("Sending candy guard update transaction...");
+
+    let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
+    let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
+    let payer = sugar_config.keypair.pubkey();
+
+    let signature = program
+        .request()
+        .instruction(compute_units)
+        .instruction(priority_fee)
+        .accounts(GuardUpdateAccount {
+            candy_guard: candy_guard_id,
+            authority: payer,
+            payer,
+            system_program: system_program::ID,
+        })
+        .args(GuardUpdate {
+            data: serialized_data,
+        })
+        .send_or_plan("update candy guard")?;
+
+    pb.finish_with_message(format!(
+        "{} {}",
+        style("Candy guard signature:").bold(),
+        signature
+    ));
+
+    Ok(())
+}
+
 fn create_candy_machine_data(
     config: &ConfigData,
     candy_machine: &CandyMachineData,
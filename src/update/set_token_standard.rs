@@ -14,6 +14,7 @@ use crate::{
     candy_machine::{get_candy_machine_state, CANDY_MACHINE_ID},
     common::*,
     config::TokenStandard,
+    dry_run::SendOrPlan,
     pdas::{find_candy_machine_creator_pda, find_metadata_pda, get_metadata_pda},
     utils::*,
 };
@@ -148,7 +149,7 @@ pub fn process_set_token_stardard(args: SetTokenStandardArgs) -> Result<()> {
         })
         .args(mpl_candy_machine_core::instruction::SetTokenStandard { token_standard });
 
-    let sig = tx.send()?;
+    let sig = tx.send_or_plan("set token standard")?;
 
     pb.finish_and_clear();
     println!("{} {}", style("Signature:").bold(), sig);
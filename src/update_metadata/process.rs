@@ -0,0 +1,480 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use console::style;
+use mpl_token_metadata::{
+    instruction::update_metadata_accounts_v2,
+    state::{Creator, DataV2, Metadata},
+    ID as TOKEN_METADATA_PROGRAM_ID,
+};
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use tabled::{builder::Builder, settings::Style};
+use tokio::sync::Semaphore;
+
+use crate::{
+    cache::load_cache,
+    common::*,
+    config::{get_config_data, Creator as ConfigCreator},
+    dry_run::{is_dry_run, send_and_confirm_or_plan},
+    pdas::find_candy_machine_creator_pda,
+    rate_limit::{throttled_client, throttled_client_with_timeout},
+    setup::get_rpc_url,
+    utils::*,
+};
+
+/// Timeout for the GPA call (in seconds).
+const DEFAULT_TIMEOUT: u64 = 300;
+
+/// File where the failed updates are written.
+const FAILED_UPDATES_FILE: &str = "sugar-update-metadata-cache.json";
+
+pub struct UpdateMetadataArgs {
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cache: String,
+    pub config: String,
+    pub candy_machine: Option<String>,
+    pub from_cache: bool,
+    pub mapping: Option<String>,
+    pub update_creators: bool,
+    pub batch_size: usize,
+    pub timeout: Option<u64>,
+}
+
+/// New values for a minted NFT, as specified in the mapping file.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct MetadataChange {
+    pub name: Option<String>,
+    pub uri: Option<String>,
+    pub creators: Option<Vec<ConfigCreator>>,
+}
+
+/// Update to send for a minted NFT.
+#[derive(Debug, Clone)]
+struct MetadataUpdate {
+    metadata_pubkey: Pubkey,
+    metadata: Metadata,
+    data: DataV2,
+}
+
+#[derive(Debug, Serialize)]
+struct FailedUpdate {
+    mint: String,
+    error: String,
+}
+
+pub async fn process_update_metadata(args: UpdateMetadataArgs) -> Result<()> {
+    if args.from_cache == args.mapping.is_some() {
+        return Err(anyhow!(
+            "Specify either --from-cache or --mapping as the source of the new values"
+        ));
+    }
+
+    if args.batch_size == 0 {
+        return Err(anyhow!("The batch size must be greater than zero"));
+    }
+
+    println!(
+        "{} {}Loading new metadata values",
+        style("[1/3]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let cache = load_cache(&args.cache, false)?;
+
+    let candy_machine_id = match &args.candy_machine {
+        Some(candy_machine) => candy_machine.clone(),
+        None => cache.program.candy_machine.clone(),
+    };
+
+    let candy_machine_id = Pubkey::from_str(&candy_machine_id)
+        .map_err(|_| anyhow!("Failed to parse candy machine id: {}", candy_machine_id))?;
+
+    let mapping: HashMap<String, MetadataChange> = match &args.mapping {
+        Some(mapping) => {
+            let file = File::open(mapping)
+                .map_err(|e| anyhow!("Failed to open mapping file '{}': {}", mapping, e))?;
+            serde_json::from_reader(file)
+                .map_err(|e| anyhow!("Failed to parse mapping file '{}': {}", mapping, e))?
+        }
+        None => HashMap::new(),
+    };
+
+    // items of the cache indexed by name
+    let cache_items: HashMap<&str, &CacheItem> = if args.from_cache {
+        cache
+            .items
+            .iter()
+            .filter(|(key, _)| *key != COLLECTION_CACHE_KEY)
+            .map(|(_, item)| (item.name.as_str(), item))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let creators = if args.update_creators {
+        Some(get_config_data(&args.config)?.creators)
+    } else {
+        None
+    };
+
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url.clone())?;
+    let update_authority = sugar_config.keypair.pubkey();
+
+    println!(
+        "\n{} {}Getting minted NFTs for candy machine {}",
+        style("[2/3]").bold().dim(),
+        LOOKING_GLASS_EMOJI,
+        candy_machine_id
+    );
+
+    let pb = spinner_with_style();
+    pb.set_message("Loading...");
+
+    let rpc_url = get_rpc_url(args.rpc_url);
+    let client = throttled_client_with_timeout(
+        &rpc_url,
+        Duration::from_secs(args.timeout.unwrap_or(DEFAULT_TIMEOUT)),
+    );
+    let (candy_machine_creator, _) = find_candy_machine_creator_pda(&candy_machine_id);
+    let minted = get_cm_creator_metadata(&client, &candy_machine_creator.to_string(), 0)?;
+
+    pb.finish_with_message(format!("Found {} NFTs", minted.len()));
+
+    let mut updates = Vec::new();
+    let mut not_authority = 0;
+    let mut unchanged = 0;
+    let mut diff = Builder::default();
+    diff.push_record(["Mint", "Field", "Current", "New"]);
+
+    for (metadata_pubkey, metadata) in minted {
+        let mint = metadata.mint.to_string();
+        let name = metadata.data.name.trim_matches(char::from(0)).to_string();
+
+        let mut change = if args.from_cache {
+            MetadataChange {
+                uri: cache_items
+                    .get(name.as_str())
+                    .map(|item| item.metadata_link.clone()),
+                ..Default::default()
+            }
+        } else {
+            match mapping.get(&mint) {
+                Some(change) => change.clone(),
+                None => continue,
+            }
+        };
+
+        if change.creators.is_none() {
+            change.creators = creators.clone();
+        }
+
+        let data = apply_change(&metadata, &change, &candy_machine_creator);
+        let fields = diff_fields(&metadata, &data);
+
+        if fields.is_empty() {
+            unchanged += 1;
+            continue;
+        }
+
+        if metadata.update_authority != update_authority {
+            not_authority += 1;
+            continue;
+        }
+
+        for (field, current, new) in fields {
+            diff.push_record([mint.clone(), field.to_string(), current, new]);
+        }
+
+        updates.push(MetadataUpdate {
+            metadata_pubkey,
+            metadata,
+            data,
+        });
+    }
+
+    if not_authority > 0 {
+        println!(
+            "\n{}Skipping {} NFT(s) where {} is not the update authority",
+            WARNING_EMOJI, not_authority, update_authority
+        );
+    }
+
+    if unchanged > 0 {
+        println!("\n{} NFT(s) already up to date", unchanged);
+    }
+
+    if updates.is_empty() {
+        println!("\nNo NFTs to update.");
+        return Ok(());
+    }
+
+    // a dry run shows the changes and plans the update transactions
+    if is_dry_run() {
+        let mut table = diff.build();
+        table.with(Style::psql());
+        println!("\n{}", table);
+    }
+
+    println!(
+        "\n{} {}Updating {} NFT(s)",
+        style("[3/3]").bold().dim(),
+        UPLOAD_EMOJI,
+        updates.len()
+    );
+
+    let client = Arc::new(throttled_client(&rpc_url));
+    let keypair = Arc::new(sugar_config.keypair);
+    let sem = Arc::new(Semaphore::new(args.batch_size));
+    let failed = Arc::new(Mutex::new(Vec::new()));
+    let mut tx_tasks = Vec::new();
+
+    let pb = progress_bar_with_style(updates.len() as u64);
+    pb.set_message("Updating NFTs... ");
+
+    for update in updates {
+        let permit = Arc::clone(&sem).acquire_owned().await.unwrap();
+        let client = client.clone();
+        let keypair = keypair.clone();
+        let failed = failed.clone();
+        let pb = pb.clone();
+
+        // the update is sent with the blocking client, off the async runtime threads
+        tx_tasks.push(tokio::task::spawn_blocking(move || {
+            // Move permit into the closure so it is dropped when the task is dropped.
+            let _permit = permit;
+            let mint = update.metadata.mint.to_string();
+
+            if let Err(err) = send_update(client, keypair, update) {
+                failed.lock().unwrap().push(FailedUpdate {
+                    mint,
+                    error: err.to_string(),
+                });
+            }
+
+            pb.inc(1);
+        }));
+    }
+
+    for task in tx_tasks {
+        task.await.unwrap();
+    }
+    pb.finish();
+
+    let failed = failed.lock().unwrap();
+
+    if !failed.is_empty() {
+        let f = File::create(FAILED_UPDATES_FILE)
+            .map_err(|e| anyhow!("Failed to create {}: {}", FAILED_UPDATES_FILE, e))?;
+        serde_json::to_writer_pretty(f, &*failed)?;
+
+        return Err(SugarError::PartialFailure(format!(
+            "{} update(s) failed, see '{}' for details. Re-run the command to resume: \
+            NFTs already updated are skipped.",
+            failed.len(),
+            FAILED_UPDATES_FILE
+        ))
+        .into());
+    }
+
+    println!("\n{}Metadata update complete!", CONFETTI_EMOJI);
+
+    Ok(())
+}
+
+/// Return the metadata data with the change applied. The candy machine creator is kept
+/// as the first creator and the verified flag of existing creators is preserved.
+fn apply_change(
+    metadata: &Metadata,
+    change: &MetadataChange,
+    candy_machine_creator: &Pubkey,
+) -> DataV2 {
+    let current = &metadata.data;
+
+    let creators = match &change.creators {
+        Some(new_creators) => {
+            let existing = current.creators.clone().unwrap_or_default();
+            let is_verified = |address: &Pubkey| {
+                existing
+                    .iter()
+                    .any(|creator| creator.address == *address && creator.verified)
+            };
+
+            let mut creators: Vec<Creator> = existing
+                .iter()
+                .filter(|creator| creator.address == *candy_machine_creator)
+                .cloned()
+                .collect();
+
+            creators.extend(new_creators.iter().map(|creator| Creator {
+                address: creator.address,
+                verified: is_verified(&creator.address),
+                share: creator.share,
+            }));
+
+            Some(creators)
+        }
+        None => current.creators.clone(),
+    };
+
+    DataV2 {
+        name: change
+            .name
+            .clone()
+            .unwrap_or_else(|| current.name.trim_matches(char::from(0)).to_string()),
+        symbol: current.symbol.trim_matches(char::from(0)).to_string(),
+        uri: change
+            .uri
+            .clone()
+            .unwrap_or_else(|| current.uri.trim_matches(char::from(0)).to_string()),
+        seller_fee_basis_points: current.seller_fee_basis_points,
+        creators,
+        collection: metadata.collection.clone(),
+        uses: metadata.uses.clone(),
+    }
+}
+
+/// Return the fields that differ between the current metadata and the new data.
+fn diff_fields(metadata: &Metadata, data: &DataV2) -> Vec<(&'static str, String, String)> {
+    let mut fields = Vec::new();
+    let current_name = metadata.data.name.trim_matches(char::from(0));
+    let current_uri = metadata.data.uri.trim_matches(char::from(0));
+
+    if current_name != data.name {
+        fields.push(("name", current_name.to_string(), data.name.clone()));
+    }
+
+    if current_uri != data.uri {
+        fields.push(("uri", current_uri.to_string(), data.uri.clone()));
+    }
+
+    let format_creators = |creators: &Option<Vec<Creator>>| {
+        creators
+            .iter()
+            .flatten()
+            .map(|creator| format!("{}:{}", creator.address, creator.share))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    let current_creators = format_creators(&metadata.data.creators);
+    let new_creators = format_creators(&data.creators);
+
+    if current_creators != new_creators {
+        fields.push(("creators", current_creators, new_creators));
+    }
+
+    fields
+}
+
+fn send_update(
+    client: Arc<RpcClient>,
+    update_authority: Arc<Keypair>,
+    update: MetadataUpdate,
+) -> Result<()> {
+    let ix = update_metadata_accounts_v2(
+        TOKEN_METADATA_PROGRAM_ID,
+        update.metadata_pubkey,
+        update_authority.pubkey(),
+        None,
+        Some(update.data),
+        None,
+        None,
+    );
+
+    let recent_blockhash = client.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&update_authority.pubkey()),
+        &[&*update_authority],
+        recent_blockhash,
+    );
+    send_and_confirm_or_plan(&client, &tx, "update metadata")?;
+
+    Ok(())
+}
+
+#[test]
+fn test_apply_change() {
+    use mpl_token_metadata::state::{Data, Key};
+
+    let candy_machine_creator = Pubkey::new_unique();
+    let verified = Pubkey::new_unique();
+    let added = Pubkey::new_unique();
+
+    let metadata = Metadata {
+        key: Key::MetadataV1,
+        update_authority: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        data: Data {
+            name: format!("{:\0<32}", "Item #1"),
+            symbol: "SGR".to_string(),
+            uri: format!("{:\0<200}", "https://arweave.net/old"),
+            seller_fee_basis_points: 500,
+            creators: Some(vec![
+                Creator {
+                    address: candy_machine_creator,
+                    verified: true,
+                    share: 0,
+                },
+                Creator {
+                    address: verified,
+                    verified: true,
+                    share: 100,
+                },
+            ]),
+        },
+        primary_sale_happened: true,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: None,
+        collection: None,
+        uses: None,
+        collection_details: None,
+        programmable_config: None,
+    };
+
+    // no change
+    let data = apply_change(
+        &metadata,
+        &MetadataChange::default(),
+        &candy_machine_creator,
+    );
+    assert_eq!(data.name, "Item #1");
+    assert_eq!(data.uri, "https://arweave.net/old");
+    assert!(diff_fields(&metadata, &data).is_empty());
+
+    let change = MetadataChange {
+        name: None,
+        uri: Some("https://arweave.net/new".to_string()),
+        creators: Some(vec![
+            ConfigCreator {
+                address: verified,
+                share: 50,
+            },
+            ConfigCreator {
+                address: added,
+                share: 50,
+            },
+        ]),
+    };
+    let data = apply_change(&metadata, &change, &candy_machine_creator);
+    let creators = data.creators.as_ref().unwrap();
+
+    // the candy machine creator stays first and existing creators keep their verification
+    assert_eq!(creators.len(), 3);
+    assert_eq!(creators[0].address, candy_machine_creator);
+    assert!(creators[0].verified);
+    assert!(creators[1].verified);
+    assert_eq!(creators[1].share, 50);
+    assert!(!creators[2].verified);
+
+    let fields: Vec<&str> = diff_fields(&metadata, &data)
+        .into_iter()
+        .map(|(field, _, _)| field)
+        .collect();
+    assert_eq!(fields, vec!["uri", "creators"]);
+}
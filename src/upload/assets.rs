@@ -1,17 +1,29 @@
 use std::{
+    collections::HashSet,
     ffi::OsStr,
     fs::{self, DirEntry, File, OpenOptions},
     io::{BufReader, Read},
+    time::UNIX_EPOCH,
 };
 
 use data_encoding::HEXLOWER;
 use glob::glob;
+use rayon::prelude::*;
 use regex::{Regex, RegexBuilder};
 use ring::digest::{Context, SHA256};
 use serde::Serialize;
 use serde_json;
 
-use crate::{common::*, validate::format::Metadata};
+use crate::{
+    common::*,
+    config::ConfigData,
+    journal::STATE_DIR,
+    utils::progress_bar_with_style,
+    validate::format::{Creator, Metadata},
+};
+
+/// Size of the buffer used to read files while hashing.
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
 
 #[derive(Debug, Clone)]
 pub enum DataType {
@@ -42,23 +54,15 @@ impl AssetPair {
             on_chain: false,
             animation_hash: self.animation_hash,
             animation_link: None,
+            status: ItemStatus::Pending,
+            timestamps: ItemTimestamps::default(),
         }
     }
 }
 
-pub fn get_cache_item<'a>(path: &Path, cache: &'a mut Cache) -> Result<(String, &'a CacheItem)> {
-    let file_stem = String::from(
-        path.file_stem()
-            .and_then(OsStr::to_str)
-            .expect("Failed to get convert path file ext to valid unicode."),
-    );
-
+pub fn get_cache_item(index: isize, cache: &mut Cache) -> Result<(String, &CacheItem)> {
     // id of the asset (to be used to update the cache link)
-    let asset_id = if file_stem == "collection" {
-        String::from("-1")
-    } else {
-        file_stem
-    };
+    let asset_id = index.to_string();
 
     let cache_item: &CacheItem = cache
         .items
@@ -99,22 +103,123 @@ pub fn list_files(assets_dir: &str, include_collection: bool) -> Result<Vec<DirE
                 .is_file();
 
             let path = entry.path();
+            let file_name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_str()
+                .expect("Failed to convert file name to valid unicode.");
             let file_stem = path
                 .file_stem()
                 .unwrap_or_default()
                 .to_str()
                 .expect("Failed to convert file name to valid unicode.");
 
-            let is_collection = include_collection && file_stem == "collection";
-            let is_numeric = file_stem.chars().all(|c| c.is_ascii_digit());
+            let is_hidden = file_name.starts_with('.');
+            let is_order_file = file_name == ASSET_ORDER_FILE;
+            let is_collection = file_stem == "collection";
 
-            is_file && (is_numeric || is_collection)
+            is_file && !is_hidden && !is_order_file && (include_collection || !is_collection)
         });
 
     Ok(files.collect())
 }
 
-pub fn get_asset_pairs(assets_dir: &str) -> Result<HashMap<isize, AssetPair>> {
+/// Returns the base name (file name without the extension) of an asset file.
+pub fn get_base_name(file_name: &str) -> String {
+    Path::new(file_name)
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or(file_name)
+        .to_string()
+}
+
+/// Returns the base names of the metadata files in the order they should be assigned
+/// to indices (the collection is not included).
+///
+/// The order is determined by:
+/// 1. the `order.json` file in the assets directory (a list of base names), if present;
+/// 2. the numeric value of the base names, if all of them are numbers (`0..n`);
+/// 3. the lexicographic order of the base names otherwise.
+pub fn get_asset_order(assets_dir: &str, metadata_filenames: &[String]) -> Result<Vec<String>> {
+    let base_names = metadata_filenames
+        .iter()
+        .map(|f| get_base_name(f))
+        .filter(|b| b != "collection")
+        .collect::<Vec<String>>();
+
+    let order_file = Path::new(assets_dir).join(ASSET_ORDER_FILE);
+
+    if order_file.is_file() {
+        let f = File::open(&order_file)?;
+        let order: Vec<String> = serde_json::from_reader(f).map_err(|e| {
+            anyhow!("Failed to read order file '{ASSET_ORDER_FILE}' with error: {e}")
+        })?;
+
+        let available: HashSet<&String> = base_names.iter().collect();
+        let mut seen = HashSet::new();
+
+        for name in &order {
+            if !available.contains(name) {
+                return Err(anyhow!(
+                    "Asset '{}' listed in '{}' does not have a metadata file.",
+                    name,
+                    ASSET_ORDER_FILE
+                ));
+            }
+            if !seen.insert(name) {
+                return Err(anyhow!(
+                    "Asset '{}' is listed more than once in '{}'.",
+                    name,
+                    ASSET_ORDER_FILE
+                ));
+            }
+        }
+
+        if let Some(missing) = base_names.iter().find(|b| !seen.contains(b)) {
+            return Err(anyhow!(
+                "Metadata file '{}.json' is not listed in '{}'.",
+                missing,
+                ASSET_ORDER_FILE
+            ));
+        }
+
+        return Ok(order);
+    }
+
+    if base_names.iter().all(|b| is_numeric_name(b)) {
+        ensure_sequential_files(metadata_filenames.to_vec())?;
+
+        let mut indices = base_names
+            .iter()
+            .map(|b| b.parse::<usize>())
+            .collect::<std::result::Result<Vec<usize>, _>>()?;
+        indices.sort_unstable();
+
+        Ok(indices.into_iter().map(|i| i.to_string()).collect())
+    } else {
+        let mut names = base_names;
+        names.sort_unstable();
+        Ok(names)
+    }
+}
+
+/// Checks whether the base name is a numeric index.
+fn is_numeric_name(base_name: &str) -> bool {
+    !base_name.is_empty() && base_name.chars().all(|c| c.is_ascii_digit())
+}
+
+pub fn get_asset_pairs(
+    assets_dir: &str,
+    collection_path: Option<&str>,
+) -> Result<HashMap<isize, AssetPair>> {
+    pair_assets(assets_dir, collection_path, &hash_cache_path())
+}
+
+fn pair_assets(
+    assets_dir: &str,
+    collection_path: Option<&str>,
+    hash_cache: &Path,
+) -> Result<HashMap<isize, AssetPair>> {
     // filters out directories and hidden files
     let filtered_files = list_files(assets_dir, true)?;
 
@@ -131,6 +236,21 @@ pub fn get_asset_pairs(assets_dir: &str) -> Result<HashMap<isize, AssetPair>> {
 
     let paths_ref = &paths;
 
+    let metadata_filenames = paths_ref
+        .clone()
+        .into_iter()
+        .filter(|p| p.to_lowercase().ends_with(".json"))
+        .collect::<Vec<String>>();
+
+    let order = get_asset_order(assets_dir, &metadata_filenames)?;
+    let numeric_names = order.iter().all(|b| is_numeric_name(b));
+
+    let indices: HashMap<&str, isize> = order
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.as_str(), index as isize))
+        .collect();
+
     let animation_exists_regex =
         Regex::new("^(.+)\\.((mp3)|(mp4)|(mov)|(webm)|(glb))$").expect("Failed to create regex.");
 
@@ -138,39 +258,41 @@ pub fn get_asset_pairs(assets_dir: &str) -> Result<HashMap<isize, AssetPair>> {
     // invalid file names before entering metadata filename loop
     for x in paths_ref {
         if let Some(captures) = animation_exists_regex.captures(x) {
-            if &captures[1] != "collection" && captures[1].parse::<usize>().is_err() {
-                let error = anyhow!("Couldn't parse filename '{}' to a valid index number.", x);
+            let valid = if numeric_names {
+                captures[1].parse::<usize>().is_ok()
+            } else {
+                indices.contains_key(&captures[1])
+            };
+
+            if &captures[1] != "collection" && !valid {
+                let error = anyhow!(
+                    "Couldn't find a metadata file matching the animation file '{}'.",
+                    x
+                );
                 error!("{:?}", error);
                 return Err(error);
             }
         }
     }
 
-    let metadata_filenames = paths_ref
-        .clone()
-        .into_iter()
-        .filter(|p| p.to_lowercase().ends_with(".json"))
-        .collect::<Vec<String>>();
-
-    ensure_sequential_files(metadata_filenames.clone())?;
-
     for metadata_filename in metadata_filenames {
-        let i = metadata_filename.split('.').next().unwrap();
-        let is_collection_index = i == "collection";
+        let base_name = get_base_name(&metadata_filename);
+        let is_collection_index = base_name == "collection";
 
         let index: isize = if is_collection_index {
             -1
-        } else if let Ok(index) = i.parse::<isize>() {
-            index
+        } else if let Some(index) = indices.get(base_name.as_str()) {
+            *index
         } else {
             let error = anyhow!(
-                "Couldn't parse filename '{}' to a valid index number.",
+                "Couldn't find an index for metadata file '{}'.",
                 metadata_filename
             );
             error!("{:?}", error);
             return Err(error);
         };
 
+        let i = regex::escape(&base_name);
         let img_pattern = format!("^{}\\.((jpg)|(jpeg)|(gif)|(png))$", i);
 
         let img_regex = RegexBuilder::new(&img_pattern)
@@ -187,11 +309,18 @@ pub fn get_asset_pairs(assets_dir: &str) -> Result<HashMap<isize, AssetPair>> {
         let img_filename = if img_filenames.len() != 1 {
             let error = if is_collection_index {
                 anyhow!("Couldn't find the collection image filename.")
-            } else {
+            } else if img_filenames.is_empty()
+                && paths_ref
+                    .iter()
+                    .any(|p| animation_exists_regex.is_match(p) && get_base_name(p) == base_name)
+            {
                 anyhow!(
-                    "Couldn't find an image filename at index {}.",
-                    i.parse::<isize>().unwrap()
+                    "Couldn't find an image filename for asset '{}', run 'sugar thumbnails' \
+                    to generate poster images for animation-only assets.",
+                    base_name
                 )
+            } else {
+                anyhow!("Couldn't find an image filename for asset '{}'.", base_name)
             };
             error!("{:?}", error);
             return Err(error);
@@ -247,34 +376,238 @@ pub fn get_asset_pairs(assets_dir: &str) -> Result<HashMap<isize, AssetPair>> {
             None
         };
 
-        let animation_hash = if let Some(animation_file) = &animation_filename {
-            let encoded_filename = encode(animation_file)?;
-            Some(encoded_filename)
-        } else {
-            None
-        };
-
+        // hashes are computed in parallel once all pairs are known
         let asset_pair = AssetPair {
             name,
-            metadata: metadata_filepath.clone(),
-            metadata_hash: encode(&metadata_filepath)?,
-            image: img_filepath.clone(),
-            image_hash: encode(&img_filepath)?,
-            animation_hash,
+            metadata: metadata_filepath,
+            metadata_hash: String::new(),
+            image: img_filepath,
+            image_hash: String::new(),
+            animation_hash: animation_filename.as_ref().map(|_| String::new()),
             animation: animation_filename,
         };
 
         asset_pairs.insert(index, asset_pair);
     }
 
+    if let Some(collection_path) = collection_path {
+        if asset_pairs.contains_key(&COLLECTION_INDEX) {
+            let error = anyhow!(
+                "Collection files found in the assets folder and in '{}', only one location can be used.",
+                collection_path
+            );
+            error!("{:?}", error);
+            return Err(error);
+        }
+
+        asset_pairs.insert(COLLECTION_INDEX, get_collection_pair(collection_path)?);
+    }
+
+    let mut files = Vec::with_capacity(asset_pairs.len() * 2);
+
+    for pair in asset_pairs.values() {
+        files.push(pair.metadata.clone());
+        files.push(pair.image.clone());
+        if let Some(animation) = &pair.animation {
+            files.push(animation.clone());
+        }
+    }
+
+    let hashes = hash_files_with_cache(hash_cache, &files)?;
+    let get_hash = |file: &str| -> Result<String> {
+        hashes
+            .get(file)
+            .cloned()
+            .ok_or_else(|| anyhow!("Missing hash for file '{}'", file))
+    };
+
+    for pair in asset_pairs.values_mut() {
+        pair.metadata_hash = get_hash(&pair.metadata)?;
+        pair.image_hash = get_hash(&pair.image)?;
+        if let Some(animation) = &pair.animation {
+            pair.animation_hash = Some(get_hash(animation)?);
+        }
+    }
+
     Ok(asset_pairs)
 }
 
+/// Returns the asset pair of a collection stored outside of the assets folder.
+///
+/// The `collection_path` can either be the path of the collection metadata file or a
+/// directory containing a `collection.json` file; the media files must be in the same
+/// directory as the metadata file and share its base name.
+pub fn get_collection_pair(collection_path: &str) -> Result<AssetPair> {
+    let path = Path::new(collection_path);
+
+    let metadata_path = if path.is_dir() {
+        path.join("collection.json")
+    } else {
+        path.to_path_buf()
+    };
+
+    if !metadata_path.is_file() {
+        let error = anyhow!(
+            "Couldn't find the collection metadata file '{}'.",
+            metadata_path.display()
+        );
+        error!("{:?}", error);
+        return Err(error);
+    }
+
+    let directory = metadata_path.parent().unwrap_or_else(|| Path::new("."));
+    let base_name = regex::escape(&get_base_name(&path_to_string(&metadata_path)?));
+
+    let find_media = |pattern: String| -> Result<Option<String>> {
+        let media_regex = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .expect("Failed to create regex.");
+
+        for entry in fs::read_dir(directory)?.filter_map(|entry| entry.ok()) {
+            let file_name = entry.file_name();
+            if media_regex.is_match(&file_name.to_string_lossy()) {
+                return Ok(Some(path_to_string(&entry.path())?));
+            }
+        }
+
+        Ok(None)
+    };
+
+    let image = find_media(format!("^{}\\.((jpg)|(jpeg)|(gif)|(png))$", base_name))?
+        .ok_or_else(|| anyhow!("Couldn't find the collection image filename."))?;
+    let animation = find_media(format!(
+        "^{}\\.((mp3)|(mp4)|(mov)|(webm)|(glb))$",
+        base_name
+    ))?;
+
+    let metadata_file = path_to_string(&metadata_path)?;
+    let m = File::open(&metadata_file)?;
+    let metadata: Metadata = serde_json::from_reader(m)
+        .map_err(|e| anyhow!("Failed to read metadata file '{metadata_file}' with error: {e}"))?;
+
+    Ok(AssetPair {
+        name: metadata.name,
+        metadata: metadata_file,
+        metadata_hash: String::new(),
+        image,
+        image_hash: String::new(),
+        animation_hash: animation.as_ref().map(|_| String::new()),
+        animation,
+    })
+}
+
+/// Hash of a file together with the information used to detect if the file
+/// has changed since the hash was computed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct HashCacheEntry {
+    modified: u128,
+    size: u64,
+    hash: String,
+}
+
+/// Returns the (modified time, size) of a file, used to key the hash cache.
+fn file_signature(file: &str) -> Result<(u128, u64)> {
+    let metadata = fs::metadata(file)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    Ok((modified, metadata.len()))
+}
+
+/// Path of the hash cache, kept in the state folder so the asset discovery never
+/// picks it up as a metadata file.
+fn hash_cache_path() -> PathBuf {
+    Path::new(STATE_DIR).join(HASH_CACHE_FILE)
+}
+
+/// Loads the hash cache. Any problem reading the file results in an empty cache,
+/// since it can always be recomputed.
+fn load_hash_cache(path: &Path) -> HashMap<String, HashCacheEntry> {
+    File::open(path)
+        .ok()
+        .and_then(|f| serde_json::from_reader(BufReader::new(f)).ok())
+        .unwrap_or_default()
+}
+
+/// Computes the SHA-256 hash of the specified files in parallel, reusing the
+/// hashes from previous runs for files whose modified time and size did not change.
+///
+/// Returns a map of file path to hash.
+pub fn hash_files(files: &[String]) -> Result<HashMap<String, String>> {
+    hash_files_with_cache(&hash_cache_path(), files)
+}
+
+fn hash_files_with_cache(cache_path: &Path, files: &[String]) -> Result<HashMap<String, String>> {
+    let mut hash_cache = load_hash_cache(cache_path);
+
+    let pb = progress_bar_with_style(files.len() as u64);
+    pb.set_message("Hashing files ");
+
+    let entries = files
+        .par_iter()
+        .map(|file| {
+            let (modified, size) = file_signature(file)?;
+
+            let hash = match hash_cache.get(file) {
+                Some(entry) if entry.modified == modified && entry.size == size => {
+                    entry.hash.clone()
+                }
+                _ => encode(file)?,
+            };
+
+            pb.inc(1);
+
+            Ok((
+                file.clone(),
+                HashCacheEntry {
+                    modified,
+                    size,
+                    hash,
+                },
+            ))
+        })
+        .collect::<Result<HashMap<String, HashCacheEntry>>>()?;
+
+    pb.finish_and_clear();
+
+    let hashes = entries
+        .iter()
+        .map(|(file, entry)| (file.clone(), entry.hash.clone()))
+        .collect();
+
+    // the entries of the files not hashed in this run are kept
+    hash_cache.extend(entries);
+
+    // failing to persist the cache only means that the hashes will be recomputed
+    if let Err(err) = save_hash_cache(cache_path, &hash_cache) {
+        warn!(
+            "Failed to write hash cache '{}': {}",
+            cache_path.display(),
+            err
+        );
+    }
+
+    Ok(hashes)
+}
+
+fn save_hash_cache(path: &Path, entries: &HashMap<String, HashCacheEntry>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    serde_json::to_writer(File::create(path)?, entries)?;
+
+    Ok(())
+}
+
 pub fn encode(file: &str) -> Result<String> {
     let input = File::open(file)?;
-    let mut reader = BufReader::new(input);
+    let mut reader = BufReader::with_capacity(HASH_BUFFER_SIZE, input);
     let mut context = Context::new(&SHA256);
-    let mut buffer = [0; 1024];
+    let mut buffer = vec![0; HASH_BUFFER_SIZE];
 
     loop {
         let count = reader.read(&mut buffer)?;
@@ -290,19 +623,14 @@ pub fn encode(file: &str) -> Result<String> {
 fn ensure_sequential_files(metadata_filenames: Vec<String>) -> Result<()> {
     let mut metadata_indices = metadata_filenames
         .into_iter()
-        .filter(|f| !f.starts_with("collection"))
+        .filter(|f| get_base_name(f) != "collection")
         .map(|f| {
-            f.split('.')
-                .next()
-                .unwrap()
-                .to_string()
-                .parse::<usize>()
-                .map_err(|_| {
-                    anyhow!(
-                        "Couldn't parse metadata filename '{}' to a valid index number.",
-                        f
-                    )
-                })
+            get_base_name(&f).parse::<usize>().map_err(|_| {
+                anyhow!(
+                    "Couldn't parse metadata filename '{}' to a valid index number.",
+                    f
+                )
+            })
         })
         .collect::<Result<Vec<usize>>>()?;
     metadata_indices.sort_unstable();
@@ -319,10 +647,95 @@ fn ensure_sequential_files(metadata_filenames: Vec<String>) -> Result<()> {
         })
 }
 
+/// Values of the config written into a metadata file before it is uploaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MetadataOverrides {
+    pub external_url: Option<String>,
+    pub seller_fee_basis_points: Option<u16>,
+    pub creators: Option<Vec<Creator>>,
+}
+
+impl MetadataOverrides {
+    /// Returns the overrides of the item at the index; the collection uses its own
+    /// royalties and creators when they are set in the collection settings.
+    pub fn new(config_data: &ConfigData, index: isize) -> Self {
+        let settings = match &config_data.metadata_overrides {
+            Some(settings) => settings,
+            None => return Self::default(),
+        };
+
+        let collection = config_data
+            .collection
+            .as_ref()
+            .filter(|_| index == COLLECTION_INDEX);
+        let seller_fee_basis_points = collection
+            .and_then(|collection| collection.seller_fee_basis_points)
+            .unwrap_or(config_data.seller_fee_basis_points);
+        let creators = collection
+            .and_then(|collection| collection.creators.as_ref())
+            .unwrap_or(&config_data.creators);
+
+        Self {
+            external_url: settings.external_url.clone(),
+            seller_fee_basis_points: settings
+                .seller_fee_basis_points
+                .then_some(seller_fee_basis_points),
+            creators: settings.creators.then(|| {
+                creators
+                    .iter()
+                    .map(|creator| Creator {
+                        address: creator.address.to_string(),
+                        share: creator.share as u16,
+                    })
+                    .collect()
+            }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    fn apply(&self, metadata: &mut Metadata) {
+        if let Some(external_url) = &self.external_url {
+            metadata.external_url = Some(external_url.clone());
+        }
+
+        if let Some(seller_fee_basis_points) = self.seller_fee_basis_points {
+            metadata.seller_fee_basis_points = Some(seller_fee_basis_points);
+        }
+
+        if let Some(creators) = &self.creators {
+            metadata.properties.creators = Some(creators.clone());
+        }
+    }
+}
+
+/// Combines the hash of the metadata files with their overrides, so the metadata is
+/// uploaded again when the overrides of the config change.
+pub fn apply_overrides_to_hashes(
+    asset_pairs: &mut HashMap<isize, AssetPair>,
+    config_data: &ConfigData,
+) -> Result<()> {
+    for (index, pair) in asset_pairs.iter_mut() {
+        let overrides = MetadataOverrides::new(config_data, *index);
+
+        if !overrides.is_empty() {
+            let mut context = Context::new(&SHA256);
+            context.update(pair.metadata_hash.as_bytes());
+            context.update(serde_json::to_string(&overrides)?.as_bytes());
+            pair.metadata_hash = HEXLOWER.encode(context.finish().as_ref());
+        }
+    }
+
+    Ok(())
+}
+
 pub fn get_updated_metadata(
     metadata_file: &str,
     image_link: &str,
     animation_link: &Option<String>,
+    overrides: &MetadataOverrides,
 ) -> Result<String> {
     let mut metadata: Metadata = {
         let m = OpenOptions::new()
@@ -358,9 +771,174 @@ pub fn get_updated_metadata(
         metadata.animation_url = animation_link.clone();
     }
 
+    overrides.apply(&mut metadata);
+
     Ok(serde_json::to_string(&metadata).unwrap())
 }
 
 pub fn is_complete_uri(value: &str) -> bool {
     url::Url::parse(value).is_ok()
 }
+
+#[test]
+fn test_get_asset_order() {
+    let dir = std::env::temp_dir().join(format!("sugar-order-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let assets_dir = dir.to_str().unwrap();
+    let files = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| format!("{name}.json"))
+            .collect::<Vec<String>>()
+    };
+
+    // numeric names are ordered by value, not lexicographically
+    let mut numeric = files(&["10", "collection"]);
+    numeric.extend((0..10).rev().map(|i| format!("{i}.json")));
+    assert_eq!(
+        get_asset_order(assets_dir, &numeric).unwrap(),
+        (0..=10).map(|i| i.to_string()).collect::<Vec<String>>()
+    );
+    assert!(get_asset_order(assets_dir, &files(&["0", "2"])).is_err());
+
+    assert_eq!(
+        get_asset_order(assets_dir, &files(&["wolf", "bear", "collection", "cat"])).unwrap(),
+        vec!["bear", "cat", "wolf"]
+    );
+
+    fs::write(dir.join(ASSET_ORDER_FILE), r#"["wolf", "bear", "cat"]"#).unwrap();
+    assert_eq!(
+        get_asset_order(assets_dir, &files(&["bear", "cat", "wolf"])).unwrap(),
+        vec!["wolf", "bear", "cat"]
+    );
+    // every metadata file is listed exactly once in the order file
+    assert!(get_asset_order(assets_dir, &files(&["bear", "cat", "wolf", "fox"])).is_err());
+    fs::write(
+        dir.join(ASSET_ORDER_FILE),
+        r#"["wolf", "bear", "cat", "cat"]"#,
+    )
+    .unwrap();
+    assert!(get_asset_order(assets_dir, &files(&["bear", "cat", "wolf"])).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_asset_pairs_after_hash_cache_write() {
+    let dir = std::env::temp_dir().join(format!("sugar-assets-{}", std::process::id()));
+    let assets_dir = dir.join("assets");
+    fs::create_dir_all(&assets_dir).unwrap();
+
+    for index in 0..2 {
+        let metadata = json!({
+            "name": format!("Item #{index}"),
+            "description": "",
+            "image": format!("{index}.png"),
+            "properties": { "files": [], "category": "image" }
+        });
+        fs::write(
+            assets_dir.join(format!("{index}.json")),
+            metadata.to_string(),
+        )
+        .unwrap();
+        fs::write(assets_dir.join(format!("{index}.png")), [index as u8]).unwrap();
+    }
+
+    let hash_cache = dir.join(STATE_DIR).join(HASH_CACHE_FILE);
+    let assets_dir = assets_dir.to_str().unwrap();
+
+    let first = pair_assets(assets_dir, None, &hash_cache).unwrap();
+    assert!(hash_cache.is_file());
+
+    // the second run reads the hashes from the cache and finds the same assets
+    let second = pair_assets(assets_dir, None, &hash_cache).unwrap();
+    assert_eq!(first.len(), 2);
+    assert_eq!(second.len(), 2);
+    assert_eq!(first[&0].image_hash, second[&0].image_hash);
+    assert_eq!(list_files(assets_dir, true).unwrap().len(), 4);
+
+    // hashing a single file keeps the entries of the other files
+    let entries = load_hash_cache(&hash_cache).len();
+    let image = Path::new(assets_dir).join("0.png");
+    hash_files_with_cache(&hash_cache, &[image.to_str().unwrap().to_string()]).unwrap();
+    assert_eq!(load_hash_cache(&hash_cache).len(), entries);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_metadata_overrides() {
+    use crate::config::{CollectionConfig, Creator as ConfigCreator, MetadataOverridesConfig};
+
+    let creator = ConfigCreator {
+        address: Pubkey::new_unique(),
+        share: 100,
+    };
+    let mut config_data = ConfigData {
+        seller_fee_basis_points: 500,
+        creators: vec![creator.clone()],
+        collection: Some(CollectionConfig {
+            seller_fee_basis_points: Some(250),
+            ..CollectionConfig::default()
+        }),
+        ..ConfigData::default()
+    };
+
+    // nothing is overridden without the settings of the config
+    assert!(MetadataOverrides::new(&config_data, 0).is_empty());
+
+    config_data.metadata_overrides = Some(MetadataOverridesConfig {
+        external_url: Some("https://example.com".to_string()),
+        seller_fee_basis_points: true,
+        creators: false,
+    });
+    let overrides = MetadataOverrides::new(&config_data, 0);
+    assert_eq!(overrides.seller_fee_basis_points, Some(500));
+    assert!(overrides.creators.is_none());
+
+    // the collection uses the royalties of the collection settings
+    let collection = MetadataOverrides::new(&config_data, COLLECTION_INDEX);
+    assert_eq!(collection.seller_fee_basis_points, Some(250));
+
+    let mut metadata: Metadata = serde_json::from_value(json!({
+        "name": "Item #0",
+        "description": "",
+        "seller_fee_basis_points": 0,
+        "image": "0.png",
+        "properties": { "files": [], "category": "image" }
+    }))
+    .unwrap();
+    overrides.apply(&mut metadata);
+    assert_eq!(metadata.seller_fee_basis_points, Some(500));
+    assert_eq!(
+        metadata.external_url.as_deref(),
+        Some("https://example.com")
+    );
+    assert!(metadata.properties.creators.is_none());
+
+    // the metadata hash changes with the overrides only
+    let pair = AssetPair {
+        name: "Item #0".to_string(),
+        metadata: "0.json".to_string(),
+        metadata_hash: "hash".to_string(),
+        image: "0.png".to_string(),
+        image_hash: "hash".to_string(),
+        animation: None,
+        animation_hash: None,
+    };
+    let hash = |config_data: &ConfigData| {
+        let mut asset_pairs = HashMap::from([(0, pair.clone())]);
+        apply_overrides_to_hashes(&mut asset_pairs, config_data).unwrap();
+        asset_pairs[&0].metadata_hash.clone()
+    };
+
+    let overridden = hash(&config_data);
+    assert_ne!(overridden, pair.metadata_hash);
+    assert_eq!(overridden, hash(&config_data));
+
+    config_data.seller_fee_basis_points = 600;
+    assert_ne!(hash(&config_data), overridden);
+
+    config_data.metadata_overrides = None;
+    assert_eq!(hash(&config_data), pair.metadata_hash);
+}
@@ -0,0 +1,290 @@
+use std::{collections::BTreeMap, fmt};
+
+use console::style;
+
+use crate::{
+    common::*,
+    upload::{AssetPair, DataType, UploadError},
+    utils::stop_on_warning,
+    validate::format::Metadata,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKind {
+    Image,
+    Attributes,
+}
+
+impl fmt::Display for DuplicateKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DuplicateKind::Image => write!(f, "identical images"),
+            DuplicateKind::Attributes => write!(f, "identical attributes"),
+        }
+    }
+}
+
+/// Group of assets that clash with each other.
+#[derive(Debug, Clone)]
+pub struct Duplicate {
+    pub kind: DuplicateKind,
+    pub indices: Vec<isize>,
+}
+
+/// Find the assets that have the same image file content or the exact same set of
+/// attributes. Assets without attributes and the collection are not compared.
+pub fn find_duplicates(asset_pairs: &HashMap<isize, AssetPair>) -> Result<Vec<Duplicate>> {
+    let mut images: BTreeMap<&str, Vec<isize>> = BTreeMap::new();
+    let mut attributes: BTreeMap<Vec<(String, String)>, Vec<isize>> = BTreeMap::new();
+
+    for (index, pair) in asset_pairs
+        .iter()
+        .filter(|(index, _)| **index != COLLECTION_INDEX)
+    {
+        images.entry(&pair.image_hash).or_default().push(*index);
+
+        let metadata: Metadata = serde_json::from_reader(File::open(&pair.metadata)?)
+            .map_err(|e| anyhow!("Error parsing metadata ({}): {}", pair.metadata, e))?;
+
+        let mut traits: Vec<(String, String)> = metadata
+            .attributes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|attribute| (attribute.trait_type, attribute.value))
+            .collect();
+
+        if !traits.is_empty() {
+            traits.sort();
+            attributes.entry(traits).or_default().push(*index);
+        }
+    }
+
+    let group = |kind: DuplicateKind, mut indices: Vec<isize>| {
+        indices.sort_unstable();
+        Duplicate { kind, indices }
+    };
+
+    let mut duplicates: Vec<Duplicate> = images
+        .into_values()
+        .filter(|indices| indices.len() > 1)
+        .map(|indices| group(DuplicateKind::Image, indices))
+        .chain(
+            attributes
+                .into_values()
+                .filter(|indices| indices.len() > 1)
+                .map(|indices| group(DuplicateKind::Attributes, indices)),
+        )
+        .collect();
+
+    duplicates.sort_by_key(|duplicate| duplicate.indices[0]);
+
+    Ok(duplicates)
+}
+
+/// Report the duplicated assets, returning an error when `fail` is set.
+pub fn check_duplicates(asset_pairs: &HashMap<isize, AssetPair>, fail: bool) -> Result<()> {
+    let duplicates = find_duplicates(asset_pairs)?;
+
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+
+    println!(
+        "\n{}{}",
+        WARNING_EMOJI,
+        style(format!(
+            "Found {} group(s) of duplicated assets:",
+            duplicates.len()
+        ))
+        .yellow()
+        .bold()
+    );
+
+    for duplicate in &duplicates {
+        let indices: Vec<String> = duplicate.indices.iter().map(|i| i.to_string()).collect();
+        println!("  - {}: {}", duplicate.kind, indices.join(", "));
+    }
+
+    if fail {
+        return Err(UploadError::DuplicateAssets(duplicates.len()).into());
+    }
+
+    stop_on_warning(&format!(
+        "found {} group(s) of duplicated assets",
+        duplicates.len()
+    ))?;
+
+    println!();
+
+    Ok(())
+}
+
+/// Media uploads removed by the content deduplication.
+#[derive(Debug, Default)]
+pub struct SharedMedia {
+    /// Assets that reuse the upload of another asset in this run: (index, source index).
+    pub pending: Vec<(isize, isize)>,
+    /// Number of assets that reused a link from a previous upload.
+    pub reused: usize,
+}
+
+impl SharedMedia {
+    pub fn count(&self) -> usize {
+        self.pending.len() + self.reused
+    }
+}
+
+/// Removes from `indices` the assets whose media file has the same content hash as
+/// another asset, so each file is uploaded once. Links already in the cache are
+/// reused directly; the others are copied once the upload of the first asset
+/// completes (see [`apply_shared_media`]).
+pub fn deduplicate_media(
+    asset_pairs: &HashMap<isize, AssetPair>,
+    cache: &mut Cache,
+    indices: &mut Vec<isize>,
+    data_type: DataType,
+) -> SharedMedia {
+    let media_hash = |index: &isize| -> Option<String> {
+        asset_pairs.get(index).and_then(|pair| match data_type {
+            DataType::Image => Some(pair.image_hash.clone()),
+            DataType::Animation => pair.animation_hash.clone(),
+            DataType::Metadata => None,
+        })
+    };
+
+    let mut shared = SharedMedia::default();
+
+    if matches!(data_type, DataType::Metadata) {
+        return shared;
+    }
+
+    // links of files uploaded by previous runs
+    let mut uploaded: HashMap<String, String> = HashMap::new();
+
+    for item in cache.items.values() {
+        let (hash, link) = match data_type {
+            DataType::Image => (Some(&item.image_hash), Some(&item.image_link)),
+            _ => (item.animation_hash.as_ref(), item.animation_link.as_ref()),
+        };
+
+        if let (Some(hash), Some(link)) = (hash, link) {
+            if !hash.is_empty() && !link.is_empty() {
+                uploaded.insert(hash.clone(), link.clone());
+            }
+        }
+    }
+
+    let mut sources: HashMap<String, isize> = HashMap::new();
+    indices.sort_unstable();
+
+    indices.retain(|index| {
+        let hash = match media_hash(index) {
+            Some(hash) if !hash.is_empty() => hash,
+            _ => return true,
+        };
+
+        if let Some(link) = uploaded.get(&hash) {
+            if let Some(item) = cache.items.get_mut(&index.to_string()) {
+                match data_type {
+                    DataType::Image => item.image_link = link.clone(),
+                    _ => item.animation_link = Some(link.clone()),
+                }
+                shared.reused += 1;
+                return false;
+            }
+        }
+
+        match sources.get(&hash) {
+            Some(source) => {
+                shared.pending.push((*index, *source));
+                false
+            }
+            None => {
+                sources.insert(hash, *index);
+                true
+            }
+        }
+    });
+
+    shared
+}
+
+/// Copies the links uploaded for the source assets to the assets sharing their media.
+/// Assets whose source failed to upload are left without a link.
+pub fn apply_shared_media(cache: &mut Cache, shared: &SharedMedia, data_type: DataType) {
+    for (index, source) in &shared.pending {
+        let link = match cache.items.get(&source.to_string()) {
+            Some(item) => match data_type {
+                DataType::Image => Some(item.image_link.clone()),
+                _ => item.animation_link.clone(),
+            },
+            None => None,
+        };
+
+        if let (Some(link), Some(item)) = (
+            link.filter(|link| !link.is_empty()),
+            cache.items.get_mut(&index.to_string()),
+        ) {
+            match data_type {
+                DataType::Image => item.image_link = link,
+                _ => item.animation_link = Some(link),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_deduplicate_media() {
+    let pair = |image_hash: &str| AssetPair {
+        name: String::new(),
+        metadata: String::new(),
+        metadata_hash: String::new(),
+        image: String::new(),
+        image_hash: image_hash.to_string(),
+        animation: None,
+        animation_hash: None,
+    };
+
+    let mut asset_pairs = HashMap::new();
+    asset_pairs.insert(0, pair("a"));
+    asset_pairs.insert(1, pair("b"));
+    asset_pairs.insert(2, pair("a"));
+    asset_pairs.insert(3, pair("c"));
+
+    let mut cache = Cache::new();
+    for index in 0..4 {
+        cache
+            .items
+            .insert(index.to_string(), pair("").into_cache_item());
+    }
+    // the file of item 3 was uploaded by a previous run for another item
+    let mut uploaded = pair("c").into_cache_item();
+    uploaded.image_link = "https://c".to_string();
+    cache.items.insert("4".to_string(), uploaded);
+
+    let mut indices = vec![3, 2, 1, 0];
+    let shared = deduplicate_media(&asset_pairs, &mut cache, &mut indices, DataType::Image);
+
+    assert_eq!(indices, vec![0, 1]);
+    assert_eq!(shared.pending, vec![(2, 0)]);
+    assert_eq!(shared.reused, 1);
+    assert_eq!(shared.count(), 2);
+    assert_eq!(cache.items.get("3").unwrap().image_link, "https://c");
+
+    // metadata files are never shared
+    let mut metadata_indices = vec![0, 2];
+    let none = deduplicate_media(
+        &asset_pairs,
+        &mut cache,
+        &mut metadata_indices,
+        DataType::Metadata,
+    );
+    assert_eq!(none.count(), 0);
+    assert_eq!(metadata_indices, vec![0, 2]);
+
+    // the link uploaded for item 0 is copied to item 2 once the upload completes
+    cache.items.get_mut("0").unwrap().image_link = "https://a".to_string();
+    apply_shared_media(&mut cache, &shared, DataType::Image);
+    assert_eq!(cache.items.get("2").unwrap().image_link, "https://a");
+    assert!(cache.items.get("1").unwrap().image_link.is_empty());
+}
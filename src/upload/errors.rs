@@ -27,4 +27,7 @@ pub enum UploadError {
 
     #[error("Metadata file {0} is not formatted correctly for animations.")]
     AnimationFileError(String),
+
+    #[error("Found {0} group(s) of duplicated assets")]
+    DuplicateAssets(usize),
 }
@@ -1,7 +1,13 @@
 use std::{fs, sync::Arc};
 
 use async_trait::async_trait;
+use data_encoding::HEXLOWER;
 use ini::ini;
+use regex::Regex;
+use ring::{
+    digest::{digest, SHA256},
+    hmac,
+};
 use s3::{bucket::Bucket, creds::Credentials, region::Region};
 use tokio::task::JoinHandle;
 
@@ -10,32 +16,64 @@ use crate::{
     config::*,
     upload::{
         assets::{AssetPair, DataType},
-        uploader::{AssetInfo, ParallelUploader, Prepare},
+        uploader::{AssetInfo, ParallelUploader, Prepare, StorageUploader},
     },
 };
 
 // Maximum number of times to retry each individual upload.
 const MAX_RETRY: u8 = 3;
+// Default layout of the object keys.
+const DEFAULT_KEY_LAYOUT: &str = "{directory}/{name}";
+// STS (global) endpoint used to assume roles.
+const STS_HOST: &str = "sts.amazonaws.com";
+// Region used to sign requests to the global STS endpoint.
+const STS_REGION: &str = "us-east-1";
+// Session name of the assumed role.
+const STS_SESSION_NAME: &str = "sugar-upload";
 
 pub struct AWSMethod {
     pub bucket: Arc<Bucket>,
     pub directory: String,
     pub domain: String,
+    pub key_layout: String,
 }
 
 impl AWSMethod {
     pub async fn new(config_data: &ConfigData) -> Result<Self> {
-        let profile = &config_data
+        let aws_config = config_data
             .aws_config
             .as_ref()
-            .ok_or_else(|| anyhow!("AWS values not specified in config file!"))?
-            .profile;
+            .ok_or_else(|| anyhow!("AWS values not specified in config file!"))?;
 
-        let credentials = Credentials::from_profile(Some(profile))?;
-        let region = AWSMethod::load_region(config_data)?;
+        // keys from the sugar credentials file take precedence over the AWS profile
+        let mut credentials = match (&aws_config.access_key_id, &aws_config.secret_access_key) {
+            (Some(access_key), Some(secret_key)) => {
+                Credentials::new(Some(access_key), Some(secret_key), None, None, None)?
+            }
+            _ => Credentials::from_profile(Some(&aws_config.profile))?,
+        };
+        let region = match &aws_config.region {
+            Some(region) => region.parse()?,
+            None => AWSMethod::load_region(config_data)?,
+        };
 
         if let Some(config) = &config_data.aws_config {
-            let domain = if let Some(domain) = &config.domain {
+            if let Some(role_arn) = &config.role_arn {
+                credentials = assume_role(&credentials, role_arn).await?;
+            }
+
+            let domain = if let Some(cloudfront) = &config.cloudfront_domain {
+                // accepts both the distribution domain and its url
+                let cloudfront = if cloudfront.starts_with("https://") {
+                    cloudfront.clone()
+                } else {
+                    format!("https://{}", cloudfront)
+                };
+                match url::Url::parse(&cloudfront) {
+                    Ok(url) => url.to_string(),
+                    Err(error) => return Err(anyhow!("Malformed CloudFront domain ({})", error)),
+                }
+            } else if let Some(domain) = &config.domain {
                 match url::Url::parse(domain) {
                     Ok(url) => url.to_string(),
                     Err(error) => {
@@ -46,10 +84,20 @@ impl AWSMethod {
                 format!("https://{}.s3.amazonaws.com", &config.bucket)
             };
 
+            let mut bucket = Bucket::new(&config.bucket, region, credentials)?;
+
+            if let Some(cache_control) = &config.cache_control {
+                bucket.add_header("Cache-Control", cache_control);
+            }
+
             Ok(Self {
-                bucket: Arc::new(Bucket::new(&config.bucket, region, credentials)?),
+                bucket: Arc::new(bucket),
                 directory: config.directory.clone(),
                 domain,
+                key_layout: config
+                    .key_layout
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_KEY_LAYOUT.to_string()),
             })
         } else {
             Err(anyhow!("Missing AwsConfig 'bucket' value in config file."))
@@ -85,6 +133,7 @@ impl AWSMethod {
         bucket: Arc<Bucket>,
         directory: String,
         domain: String,
+        key_layout: String,
         asset_info: AssetInfo,
     ) -> Result<(String, String)> {
         let data = match asset_info.data_type {
@@ -95,11 +144,8 @@ impl AWSMethod {
 
         // Take care of any spaces in the directory path.
         let directory = directory.replace(' ', "_");
-
-        let path = Path::new(&directory).join(&asset_info.name);
-        let path_str = path
-            .to_str()
-            .ok_or_else(|| anyhow!("Failed to convert S3 bucket directory path to string."))?;
+        let key = object_key(&key_layout, &directory, &asset_info);
+        let path_str = key.as_str();
 
         let mut retry = MAX_RETRY;
         // send data to AWS S3 with a simple retry logic (mitigates dns lookup errors)
@@ -140,6 +186,7 @@ impl Prepare for AWSMethod {
     async fn prepare(
         &self,
         _sugar_config: &SugarConfig,
+        _config_data: &ConfigData,
         _asset_pairs: &HashMap<isize, AssetPair>,
         _asset_indices: Vec<(DataType, &[isize])>,
     ) -> Result<()> {
@@ -148,13 +195,200 @@ impl Prepare for AWSMethod {
     }
 }
 
+impl StorageUploader for AWSMethod {}
+
 #[async_trait]
 impl ParallelUploader for AWSMethod {
     fn upload_asset(&self, asset_info: AssetInfo) -> JoinHandle<Result<(String, String)>> {
         let bucket = self.bucket.clone();
         let directory = self.directory.clone();
         let domain = self.domain.clone();
+        let key_layout = self.key_layout.clone();
+
+        tokio::spawn(async move {
+            AWSMethod::send(bucket, directory, domain, key_layout, asset_info).await
+        })
+    }
+}
+
+/// Object key of an asset from the key layout, without empty path segments (e.g. when
+/// no directory is set).
+fn object_key(key_layout: &str, directory: &str, asset_info: &AssetInfo) -> String {
+    let data_type = match asset_info.data_type {
+        DataType::Image => "images",
+        DataType::Metadata => "metadata",
+        DataType::Animation => "animations",
+    };
+
+    key_layout
+        .replace("{directory}", directory)
+        .replace("{type}", data_type)
+        .replace("{name}", &asset_info.name)
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<&str>>()
+        .join("/")
+}
 
-        tokio::spawn(async move { AWSMethod::send(bucket, directory, domain, asset_info).await })
+/// Percent-encoding of a query string value (RFC 3986), as required by SigV4.
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data.as_bytes()).as_ref().to_vec()
+}
+
+/// SigV4 signing key for the date (YYYYMMDD), region and service.
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let date_key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date);
+    let region_key = hmac_sha256(&date_key, region);
+    let service_key = hmac_sha256(&region_key, service);
+    hmac_sha256(&service_key, "aws4_request")
+}
+
+/// Assume the role with the profile credentials, returning the temporary credentials
+/// of the role session.
+async fn assume_role(credentials: &Credentials, role_arn: &str) -> Result<Credentials> {
+    let access_key = credentials
+        .access_key
+        .as_ref()
+        .ok_or_else(|| anyhow!("Missing AWS access key to assume role"))?;
+    let secret_key = credentials
+        .secret_key
+        .as_ref()
+        .ok_or_else(|| anyhow!("Missing AWS secret key to assume role"))?;
+
+    let now = chrono::Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    // parameters in alphabetical order (canonical query string)
+    let query = format!(
+        "Action=AssumeRole&RoleArn={}&RoleSessionName={}&Version=2011-06-15",
+        uri_encode(role_arn),
+        STS_SESSION_NAME
+    );
+
+    let mut headers = vec![
+        ("host", STS_HOST.to_string()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
     }
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<&str>>()
+        .join(";");
+    let payload_hash = HEXLOWER.encode(digest(&SHA256, b"").as_ref());
+
+    let canonical_request = format!(
+        "GET\n/\n{}\n{}\n{}\n{}",
+        query, canonical_headers, signed_headers, payload_hash
+    );
+    let scope = format!("{}/{}/sts/aws4_request", date, STS_REGION);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        HEXLOWER.encode(digest(&SHA256, canonical_request.as_bytes()).as_ref())
+    );
+    let signature = HEXLOWER.encode(&hmac_sha256(
+        &signing_key(secret_key, &date, STS_REGION, "sts"),
+        &string_to_sign,
+    ));
+
+    let mut request = reqwest::Client::new()
+        .get(format!("https://{}/?{}", STS_HOST, query))
+        .header("x-amz-date", &amz_date)
+        .header(
+            "Authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                access_key, scope, signed_headers, signature
+            ),
+        );
+    if let Some(token) = &credentials.session_token {
+        request = request.header("x-amz-security-token", token);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        return Err(anyhow!(
+            "Failed to assume role {} ({}): {}",
+            role_arn,
+            status,
+            body
+        ));
+    }
+
+    let field = |name: &str| -> Result<String> {
+        let re = Regex::new(&format!("<{0}>([^<]+)</{0}>", name))?;
+        re.captures(&body)
+            .map(|c| c[1].to_string())
+            .ok_or_else(|| anyhow!("Missing {} in the assume role response", name))
+    };
+
+    let access_key = field("AccessKeyId")?;
+    let secret_key = field("SecretAccessKey")?;
+    let session_token = field("SessionToken")?;
+
+    Ok(Credentials::new(
+        Some(&access_key),
+        Some(&secret_key),
+        None,
+        Some(&session_token),
+        None,
+    )?)
+}
+
+#[test]
+fn test_aws_signing_key_and_object_key() {
+    // example from the AWS Signature Version 4 documentation
+    let key = signing_key(
+        "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        "20120215",
+        "us-east-1",
+        "iam",
+    );
+    assert_eq!(
+        HEXLOWER.encode(&key),
+        "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+    );
+    assert_eq!(
+        uri_encode("arn:aws:iam::1:role/x"),
+        "arn%3Aaws%3Aiam%3A%3A1%3Arole%2Fx"
+    );
+
+    let asset_info = AssetInfo {
+        asset_id: "0".to_string(),
+        name: "0.png".to_string(),
+        content: String::new(),
+        data_type: DataType::Image,
+        content_type: "image/png".to_string(),
+    };
+    assert_eq!(object_key(DEFAULT_KEY_LAYOUT, "", &asset_info), "0.png");
+    assert_eq!(
+        object_key("{directory}/{type}/{name}", "drop", &asset_info),
+        "drop/images/0.png"
+    );
 }
@@ -15,9 +15,10 @@ use crate::{
     candy_machine::CANDY_MACHINE_ID,
     common::*,
     config::*,
+    costs::{record_cost, record_transaction, CostKind},
     upload::{
-        assets::{get_updated_metadata, AssetPair, DataType},
-        uploader::{AssetInfo, ParallelUploader, Prepare, MOCK_URI_SIZE},
+        assets::{get_updated_metadata, AssetPair, DataType, MetadataOverrides},
+        uploader::{AssetInfo, ParallelUploader, Prepare, StorageUploader, MOCK_URI_SIZE},
     },
     utils::*,
 };
@@ -34,26 +35,23 @@ const HEADER_SIZE: u64 = 2_000;
 /// Minimum file size for cost calculation
 const MINIMUM_SIZE: u64 = 80_000;
 
+/// Padding applied to the shortfall when funding in padded mode.
+const FUNDING_PADDING: f64 = 1.3;
+
+/// Fee (lamports) of the funding transaction.
+const FUNDING_TX_FEE: u64 = 5_000;
+
 pub struct BundlrMethod {
     pub client: Arc<Bundlr<SolanaSigner>>,
     pub sugar_tag: Tag,
     pubkey: Pubkey,
     node: String,
+    funding_mode: BundlrFundingMode,
 }
 
 impl BundlrMethod {
-    pub async fn new(sugar_config: &SugarConfig, _config_data: &ConfigData) -> Result<Self> {
-        let client = setup_client(sugar_config)?;
-        let program = client.program(CANDY_MACHINE_ID);
-        let solana_cluster: Cluster = get_cluster(program.rpc())?;
-
-        let bundlr_node = match solana_cluster {
-            Cluster::Devnet => BUNDLR_DEVNET,
-            Cluster::Mainnet => BUNDLR_MAINNET,
-            Cluster::Unknown | Cluster::Localnet => {
-                return Err(anyhow!("Bundlr is only supported on devnet or mainnet"));
-            }
-        };
+    pub async fn new(sugar_config: &SugarConfig, config_data: &ConfigData) -> Result<Self> {
+        let bundlr_node = BundlrMethod::get_bundlr_node(sugar_config)?;
 
         let http_client = reqwest::Client::new();
         let bundlr_address =
@@ -78,9 +76,89 @@ impl BundlrMethod {
             pubkey: bundlr_pubkey,
             sugar_tag,
             node: bundlr_node.to_string(),
+            funding_mode: config_data
+                .bundlr_config
+                .as_ref()
+                .map(|c| c.funding_mode)
+                .unwrap_or_default(),
         })
     }
 
+    /// Return the Bundlr node for the cluster of the current RPC.
+    pub fn get_bundlr_node(sugar_config: &SugarConfig) -> Result<&'static str> {
+        let client = setup_client(sugar_config)?;
+        let program = client.program(CANDY_MACHINE_ID);
+        let solana_cluster: Cluster = get_cluster(&program.rpc())?;
+
+        match solana_cluster {
+            Cluster::Devnet => Ok(BUNDLR_DEVNET),
+            Cluster::Mainnet => Ok(BUNDLR_MAINNET),
+            Cluster::Unknown | Cluster::Localnet => {
+                Err(anyhow!("Bundlr is only supported on devnet or mainnet"))
+            }
+        }
+    }
+
+    /// Return the size used by Bundlr to calculate the cost of uploading the specified files.
+    pub fn get_upload_size(
+        config_data: &ConfigData,
+        assets: &HashMap<isize, AssetPair>,
+        asset_indices: Vec<(DataType, &[isize])>,
+    ) -> Result<u64> {
+        let mut total_size = 0;
+
+        for (data_type, indices) in asset_indices {
+            match data_type {
+                DataType::Image => {
+                    for index in indices {
+                        let item = assets.get(index).unwrap();
+                        let path = Path::new(&item.image);
+                        total_size +=
+                            HEADER_SIZE + cmp::max(MINIMUM_SIZE, fs::metadata(path)?.len());
+                    }
+                }
+                DataType::Animation => {
+                    for index in indices {
+                        let item = assets.get(index).unwrap();
+
+                        if let Some(animation) = &item.animation {
+                            let path = Path::new(animation);
+                            total_size +=
+                                HEADER_SIZE + cmp::max(MINIMUM_SIZE, fs::metadata(path)?.len());
+                        }
+                    }
+                }
+                DataType::Metadata => {
+                    let mock_uri = "x".repeat(MOCK_URI_SIZE);
+
+                    for index in indices {
+                        let item = assets.get(index).unwrap();
+                        let animation = if item.animation.is_some() {
+                            Some(mock_uri.clone())
+                        } else {
+                            None
+                        };
+
+                        total_size += HEADER_SIZE
+                            + cmp::max(
+                                MINIMUM_SIZE,
+                                get_updated_metadata(
+                                    &item.metadata,
+                                    &mock_uri.clone(),
+                                    &animation,
+                                    &MetadataOverrides::new(config_data, *index),
+                                )?
+                                .into_bytes()
+                                .len() as u64,
+                            );
+                    }
+                }
+            }
+        }
+
+        Ok(total_size)
+    }
+
     /// Return the solana address for Bundlr.
     async fn get_bundlr_solana_address(http_client: &HttpClient, node: &str) -> Result<String> {
         let url = format!("{}/info", node);
@@ -132,6 +210,8 @@ impl BundlrMethod {
         )?;
 
         println!("{} {sig}", style("Signature:").bold());
+        record_cost(CostKind::Storage, amount, "bundlr funding");
+        record_transaction(&sig, "bundlr funding");
 
         let mut map = HashMap::new();
         map.insert("tx_id", sig.to_string());
@@ -162,7 +242,11 @@ impl BundlrMethod {
     }
 
     /// Return the Bundlr fee for upload based on the data size.
-    async fn get_bundlr_fee(http_client: &HttpClient, node: &str, data_size: u64) -> Result<u64> {
+    pub async fn get_bundlr_fee(
+        http_client: &HttpClient,
+        node: &str,
+        data_size: u64,
+    ) -> Result<u64> {
         let required_amount = http_client
             .get(format!("{node}/price/solana/{data_size}"))
             .send()
@@ -218,55 +302,12 @@ impl Prepare for BundlrMethod {
     async fn prepare(
         &self,
         sugar_config: &SugarConfig,
+        config_data: &ConfigData,
         assets: &HashMap<isize, AssetPair>,
         asset_indices: Vec<(DataType, &[isize])>,
     ) -> Result<()> {
         // calculates the size of the files to upload
-        let mut total_size = 0;
-
-        for (data_type, indices) in asset_indices {
-            match data_type {
-                DataType::Image => {
-                    for index in indices {
-                        let item = assets.get(index).unwrap();
-                        let path = Path::new(&item.image);
-                        total_size +=
-                            HEADER_SIZE + cmp::max(MINIMUM_SIZE, fs::metadata(path)?.len());
-                    }
-                }
-                DataType::Animation => {
-                    for index in indices {
-                        let item = assets.get(index).unwrap();
-
-                        if let Some(animation) = &item.animation {
-                            let path = Path::new(animation);
-                            total_size +=
-                                HEADER_SIZE + cmp::max(MINIMUM_SIZE, fs::metadata(path)?.len());
-                        }
-                    }
-                }
-                DataType::Metadata => {
-                    let mock_uri = "x".repeat(MOCK_URI_SIZE);
-
-                    for index in indices {
-                        let item = assets.get(index).unwrap();
-                        let animation = if item.animation.is_some() {
-                            Some(mock_uri.clone())
-                        } else {
-                            None
-                        };
-
-                        total_size += HEADER_SIZE
-                            + cmp::max(
-                                MINIMUM_SIZE,
-                                get_updated_metadata(&item.metadata, &mock_uri.clone(), &animation)?
-                                    .into_bytes()
-                                    .len() as u64,
-                            );
-                    }
-                }
-            }
-        }
+        let total_size = BundlrMethod::get_upload_size(config_data, assets, asset_indices)?;
 
         info!("Total upload size: {}", total_size);
 
@@ -292,8 +333,29 @@ impl Prepare for BundlrMethod {
         };
 
         if lamports_fee > balance {
-            // calculates the additional amount to fund the wallet, with padding.
-            let amount = ((lamports_fee - balance) as f64 * 1.3).ceil() as u64;
+            let shortfall = lamports_fee - balance;
+
+            // calculates the additional amount to fund the wallet
+            let amount = match self.funding_mode {
+                BundlrFundingMode::Padded => (shortfall as f64 * FUNDING_PADDING).ceil() as u64,
+                BundlrFundingMode::Auto => shortfall,
+                BundlrFundingMode::Manual => {
+                    return Err(anyhow!(
+                        "Insufficient Bundlr balance: {} lamports available, {} lamports \
+                        required (funding mode is 'manual')",
+                        balance,
+                        lamports_fee
+                    ));
+                }
+            };
+
+            check_balance(
+                &rpc_client,
+                &sugar_config.keypair.pubkey(),
+                amount + FUNDING_TX_FEE,
+                "fund the Bundlr upload",
+            )
+            .await?;
 
             BundlrMethod::fund_bundlr_address(
                 rpc_client,
@@ -345,6 +407,24 @@ impl Prepare for BundlrMethod {
     }
 }
 
+#[async_trait]
+impl StorageUploader for BundlrMethod {
+    async fn estimate_cost(
+        &self,
+        _sugar_config: &SugarConfig,
+        config_data: &ConfigData,
+        assets: &HashMap<isize, AssetPair>,
+        asset_indices: Vec<(DataType, &[isize])>,
+    ) -> Result<Option<u64>> {
+        let total_size = BundlrMethod::get_upload_size(config_data, assets, asset_indices)?;
+        let http_client = reqwest::Client::new();
+        let lamports_fee =
+            BundlrMethod::get_bundlr_fee(&http_client, &self.node, total_size).await?;
+
+        Ok(Some(lamports_fee))
+    }
+}
+
 #[async_trait]
 impl ParallelUploader for BundlrMethod {
     fn upload_asset(&self, asset_info: AssetInfo) -> JoinHandle<Result<(String, String)>> {
@@ -2,8 +2,10 @@ pub mod aws;
 pub mod bundlr;
 pub mod nft_storage;
 pub mod pinata;
+pub mod pinning_service;
 pub mod sdrive;
 pub mod shdw;
+pub mod template;
 
 pub use aws::*;
 pub use bundlr::*;
@@ -8,6 +8,7 @@ use std::{
 };
 
 use async_trait::async_trait;
+use console::style;
 use reqwest::{
     header,
     multipart::{Form, Part},
@@ -17,10 +18,13 @@ use tokio::time::{sleep, Duration};
 
 use crate::{common::*, config::*, upload::*};
 
-// API end point.
+// API end point (classic API, being sunset).
 const NFT_STORAGE_API_URL: &str = "https://api.nft.storage";
 // Storage end point.
 const NFT_STORAGE_GATEWAY_URL: &str = "https://nftstorage.link/ipfs";
+// Environment variable with the authentication token, used when the config file does
+// not specify one.
+const NFT_STORAGE_TOKEN_ENV: &str = "NFT_STORAGE_TOKEN";
 // Request time window (ms) to avoid the rate limit.
 const REQUEST_WAIT: u64 = 10000;
 // File size limit (100mb).
@@ -49,6 +53,14 @@ pub struct NftValue {
     pub cid: String,
 }
 
+/// response of compatible upload APIs, which return the cid at the top level
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct StoreCidResponse {
+    /// ipfs cid (file hash)
+    pub cid: String,
+}
+
 /// response after an error
 #[derive(Debug, Deserialize, Default)]
 pub struct StoreNftError {
@@ -68,42 +80,101 @@ pub struct NftError {
 
 pub struct NftStorageMethod {
     client: Arc<Client>,
+    api_url: String,
+    gateway_url: String,
 }
 
 impl NftStorageMethod {
     /// Initialize a new NftStorageHandler.
     pub async fn new(config_data: &ConfigData) -> Result<Self> {
-        if let Some(auth_token) = &config_data.nft_storage_auth_token {
-            let client_builder = Client::builder();
-
-            let mut headers = header::HeaderMap::new();
-            let bearer_value = format!("Bearer {}", auth_token);
-            let mut auth_value = header::HeaderValue::from_str(&bearer_value)?;
-            auth_value.set_sensitive(true);
-            headers.insert(header::AUTHORIZATION, auth_value);
-
-            let client = client_builder.default_headers(headers).build()?;
-
-            let url = format!("{}/", NFT_STORAGE_API_URL);
-            let response = client.get(url).send().await?;
-
-            match response.status() {
-                StatusCode::OK => Ok(Self {
-                    client: Arc::new(client),
-                }),
-                StatusCode::UNAUTHORIZED => {
-                    Err(anyhow!("Invalid nft.storage authentication token."))
-                }
-                code => Err(anyhow!("Could not initialize nft.storage client: {code}")),
-            }
-        } else {
-            Err(anyhow!(
-                "Missing 'nftStorageAuthToken' value in config file."
-            ))
+        let auth_token = match &config_data.nft_storage_auth_token {
+            Some(auth_token) => auth_token.clone(),
+            None => std::env::var(NFT_STORAGE_TOKEN_ENV).map_err(|_| {
+                anyhow!(
+                    "Missing 'nftStorageAuthToken' value in config file (or the {} \
+                    environment variable).",
+                    NFT_STORAGE_TOKEN_ENV
+                )
+            })?,
+        };
+
+        let nft_storage_config = config_data.nft_storage_config.clone().unwrap_or_default();
+        let api_url = nft_storage_config
+            .api_url
+            .unwrap_or_else(|| NFT_STORAGE_API_URL.to_string())
+            .trim_end_matches('/')
+            .to_string();
+        let gateway_url = nft_storage_config
+            .gateway_url
+            .unwrap_or_else(|| NFT_STORAGE_GATEWAY_URL.to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        let client_builder = Client::builder();
+
+        let mut headers = header::HeaderMap::new();
+        let bearer_value = format!("Bearer {}", auth_token);
+        let mut auth_value = header::HeaderValue::from_str(&bearer_value)?;
+        auth_value.set_sensitive(true);
+        headers.insert(header::AUTHORIZATION, auth_value);
+
+        let client = client_builder.default_headers(headers).build()?;
+
+        if api_url != NFT_STORAGE_API_URL {
+            // compatible APIs are not required to implement the status request
+            return Ok(Self {
+                client: Arc::new(client),
+                api_url,
+                gateway_url,
+            });
+        }
+
+        warn!("Using the classic nft.storage API, which is being sunset");
+        println!(
+            "{}",
+            style(
+                "The classic nft.storage API is being sunset: set 'nftStorageConfig.apiUrl' \
+                to a compatible upload API or use the 'pinning_service' upload method."
+            )
+            .yellow()
+        );
+
+        let url = format!("{}/", api_url);
+        let response = client.get(url).send().await?;
+
+        match response.status() {
+            StatusCode::OK => Ok(Self {
+                client: Arc::new(client),
+                api_url,
+                gateway_url,
+            }),
+            StatusCode::UNAUTHORIZED => Err(anyhow!("Invalid nft.storage authentication token.")),
+            code => Err(anyhow!("Could not initialize nft.storage client: {code}")),
         }
     }
 }
 
+/// Extracts the cid from the response of the classic API (`{ ok, value: { cid } }`) or
+/// of a compatible API (`{ cid }`).
+fn parse_cid(body: Value) -> Result<String> {
+    let StoreNftResponse {
+        value: NftValue { cid },
+        ..
+    } = serde_json::from_value(body.clone()).unwrap_or_default();
+
+    if !cid.is_empty() {
+        return Ok(cid);
+    }
+
+    let StoreCidResponse { cid } = serde_json::from_value(body.clone())?;
+
+    if cid.is_empty() {
+        Err(anyhow!("Missing cid in upload response: {}", body))
+    } else {
+        Ok(cid)
+    }
+}
+
 #[async_trait]
 impl Prepare for NftStorageMethod {
     /// Verifies that no file is larger than 100MB (upload of files larger than 100MB are
@@ -111,6 +182,7 @@ impl Prepare for NftStorageMethod {
     async fn prepare(
         &self,
         _sugar_config: &SugarConfig,
+        config_data: &ConfigData,
         asset_pairs: &HashMap<isize, AssetPair>,
         asset_indices: Vec<(DataType, &[isize])>,
     ) -> Result<()> {
@@ -138,9 +210,14 @@ impl Prepare for NftStorageMethod {
                             None
                         };
 
-                        get_updated_metadata(&item.metadata, &mock_uri.clone(), &animation)?
-                            .into_bytes()
-                            .len() as u64
+                        get_updated_metadata(
+                            &item.metadata,
+                            &mock_uri.clone(),
+                            &animation,
+                            &MetadataOverrides::new(config_data, *index),
+                        )?
+                        .into_bytes()
+                        .len() as u64
                     }
                 };
 
@@ -156,6 +233,8 @@ impl Prepare for NftStorageMethod {
     }
 }
 
+impl StorageUploader for NftStorageMethod {}
+
 #[async_trait]
 impl Uploader for NftStorageMethod {
     /// Upload the data to Nft Storage
@@ -207,7 +286,7 @@ impl Uploader for NftStorageMethod {
 
         while !interrupted.load(Ordering::SeqCst) && !batches.is_empty() {
             let batch = batches.remove(0);
-            let mut form = Form::new();
+            let mut files = Vec::new();
 
             for asset_info in &batch {
                 let data = match asset_info.data_type {
@@ -217,33 +296,37 @@ impl Uploader for NftStorageMethod {
                         content.into_bytes()
                     }
                 };
-
-                let file = Part::bytes(data)
-                    .file_name(asset_info.name.clone())
-                    .mime_str(asset_info.content_type.as_str())?;
-                form = form.part("file", file);
+                files.push(data);
             }
 
-            let response = self
-                .client
-                .post(format!("{NFT_STORAGE_API_URL}/upload"))
-                .multipart(form)
-                .send()
-                .await?;
+            let size = files.iter().map(Vec::len).sum();
+            let response = send_with_retry(size, || {
+                let mut form = Form::new();
+
+                for (asset_info, data) in batch.iter().zip(&files) {
+                    let file = Part::bytes(data.clone())
+                        .file_name(asset_info.name.clone())
+                        .mime_str(asset_info.content_type.as_str())?;
+                    form = form.part("file", file);
+                }
+
+                Ok(self
+                    .client
+                    .post(format!("{}/upload", self.api_url))
+                    .multipart(form))
+            })
+            .await?;
             let status = response.status();
 
             if status.is_success() {
                 let body = response.json::<Value>().await?;
-                let StoreNftResponse {
-                    value: NftValue { cid },
-                    ..
-                }: StoreNftResponse = serde_json::from_value(body)?;
+                let cid = parse_cid(body)?;
 
                 // updates the cache content
 
                 for asset_info in batch {
                     let id = asset_info.asset_id.clone();
-                    let uri = format!("{NFT_STORAGE_GATEWAY_URL}/{cid}/{}", asset_info.name);
+                    let uri = format!("{}/{cid}/{}", self.gateway_url, asset_info.name);
                     // cache item to update
                     let item = cache.items.get_mut(&id).unwrap();
 
@@ -258,11 +341,14 @@ impl Uploader for NftStorageMethod {
                 // updates the progress bar
                 progress.inc(1);
             } else {
-                let body = response.json::<Value>().await?;
-                let StoreNftError {
-                    error: NftError { message },
-                    ..
-                }: StoreNftError = serde_json::from_value(body)?;
+                let body = response.text().await?;
+                let message = match serde_json::from_str::<StoreNftError>(&body) {
+                    Ok(StoreNftError {
+                        error: NftError { message },
+                        ..
+                    }) if !message.is_empty() => message,
+                    _ => body,
+                };
 
                 errors.push(UploadError::SendDataFailed(format!(
                     "Error uploading batch ({}): {}",
@@ -278,3 +364,14 @@ impl Uploader for NftStorageMethod {
         Ok(errors)
     }
 }
+
+#[test]
+fn test_nft_storage_response_cid() {
+    let classic = json!({ "ok": true, "value": { "cid": "bafyclassic" } });
+    assert_eq!(parse_cid(classic).unwrap(), "bafyclassic");
+
+    let compatible = json!({ "cid": "bafycompatible" });
+    assert_eq!(parse_cid(compatible).unwrap(), "bafycompatible");
+
+    assert!(parse_cid(json!({ "ok": false })).is_err());
+}
@@ -94,6 +94,7 @@ impl Prepare for PinataMethod {
     async fn prepare(
         &self,
         _sugar_config: &SugarConfig,
+        config_data: &ConfigData,
         asset_pairs: &HashMap<isize, AssetPair>,
         asset_indices: Vec<(DataType, &[isize])>,
     ) -> Result<()> {
@@ -121,9 +122,14 @@ impl Prepare for PinataMethod {
                             None
                         };
 
-                        get_updated_metadata(&item.metadata, &mock_uri.clone(), &animation)?
-                            .into_bytes()
-                            .len() as u64
+                        get_updated_metadata(
+                            &item.metadata,
+                            &mock_uri.clone(),
+                            &animation,
+                            &MetadataOverrides::new(config_data, *index),
+                        )?
+                        .into_bytes()
+                        .len() as u64
                     }
                 };
 
@@ -139,6 +145,8 @@ impl Prepare for PinataMethod {
     }
 }
 
+impl StorageUploader for PinataMethod {}
+
 #[async_trait]
 impl ParallelUploader for PinataMethod {
     /// Returns the number of files that to be send in parallel.
@@ -160,21 +168,17 @@ impl Config {
             DataType::Animation => fs::read(&asset_info.content)?,
         };
 
-        let mut form = Form::new();
-
-        let file = Part::bytes(data)
-            .file_name(asset_info.name.clone())
-            .mime_str(asset_info.content_type.as_str())?;
-        form = form
-            .part("file", file)
-            .text("pinataOptions", "{\"wrapWithDirectory\": true}");
-
-        let response = self
-            .client
-            .post(&self.endpoint)
-            .multipart(form)
-            .send()
-            .await?;
+        let response = send_with_retry(data.len(), || {
+            let file = Part::bytes(data.clone())
+                .file_name(asset_info.name.clone())
+                .mime_str(asset_info.content_type.as_str())?;
+            let form = Form::new()
+                .part("file", file)
+                .text("pinataOptions", "{\"wrapWithDirectory\": true}");
+
+            Ok(self.client.post(&self.endpoint).multipart(form))
+        })
+        .await?;
         let status = response.status();
 
         if status.is_success() {
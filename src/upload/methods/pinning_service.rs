@@ -0,0 +1,357 @@
+use std::{fs, ops::Deref, sync::Arc};
+
+use async_trait::async_trait;
+use data_encoding::BASE32_NOPAD;
+use reqwest::{
+    header,
+    multipart::{Form, Part},
+    Client, StatusCode,
+};
+use ring::digest::{digest, SHA256};
+use tokio::task::JoinHandle;
+
+use crate::{common::*, config::*, upload::*};
+
+// Chunk size of the files, matching the default of IPFS nodes.
+const CHUNK_SIZE: usize = 256 * 1024;
+// Maximum number of links of a DAG node, matching the default of IPFS nodes.
+const MAX_LINKS: usize = 174;
+// Multicodec of raw blocks (file chunks).
+const RAW_CODEC: u8 = 0x55;
+// Multicodec of DAG-PB blocks (file nodes).
+const DAG_PB_CODEC: u8 = 0x70;
+// UnixFS data type of files.
+const UNIXFS_FILE: u64 = 2;
+
+/// Status of a pin request, as defined by the IPFS Pinning Service API.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct PinStatus {
+    /// Identifier of the pin request
+    pub requestid: String,
+    /// One of "queued", "pinning", "pinned" or "failed"
+    pub status: String,
+}
+
+/// Response of the IPFS node after adding a file.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct AddResponse {
+    /// CID of the file
+    pub hash: String,
+}
+
+pub struct Config {
+    client: Client,
+    endpoint: String,
+    ipfs_api_url: Option<String>,
+    origins: Vec<String>,
+    parallel_limit: u16,
+}
+
+/// Upload method for any provider implementing the IPFS Pinning Service API: CIDs are
+/// computed locally and the provider is asked to pin them. The content is provided by
+/// the configured IPFS node (or the `origins`) while the provider fetches it.
+pub struct PinningServiceMethod(Arc<Config>);
+
+impl Deref for PinningServiceMethod {
+    type Target = Arc<Config>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl PinningServiceMethod {
+    /// Initialize a new PinningServiceMethod.
+    pub async fn new(config_data: &ConfigData) -> Result<Self> {
+        let pinning_config = config_data
+            .pinning_service_config
+            .as_ref()
+            .ok_or_else(|| anyhow!("Missing 'pinningServiceConfig' in config file."))?;
+
+        let mut headers = header::HeaderMap::new();
+        let bearer_value = format!("Bearer {}", &pinning_config.access_token);
+        let mut auth_value = header::HeaderValue::from_str(&bearer_value)?;
+        auth_value.set_sensitive(true);
+        headers.insert(header::AUTHORIZATION, auth_value);
+
+        let client = Client::builder().default_headers(headers).build()?;
+        let endpoint = pinning_config.endpoint.trim_end_matches('/').to_string();
+
+        let response = client
+            .get(format!("{endpoint}/pins"))
+            .query(&[("limit", "1")])
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => (),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                return Err(anyhow!("Invalid pinning service access token."))
+            }
+            code => return Err(anyhow!("Could not initialize pinning service: {code}")),
+        }
+
+        let origins = pinning_config.origins.clone().unwrap_or_default();
+
+        if pinning_config.ipfs_api_url.is_none() && origins.is_empty() {
+            warn!("No 'ipfsApiUrl' or 'origins' set, the content must be provided by another node");
+        }
+
+        Ok(Self(Arc::new(Config {
+            client,
+            endpoint,
+            ipfs_api_url: pinning_config
+                .ipfs_api_url
+                .as_ref()
+                .map(|url| url.trim_end_matches('/').to_string()),
+            origins,
+            parallel_limit: pinning_config
+                .parallel_limit
+                .unwrap_or(PARALLEL_LIMIT as u16),
+        })))
+    }
+}
+
+/// A block of the file DAG.
+struct Block {
+    cid: Vec<u8>,
+    /// Size of the file content under the block.
+    file_size: u64,
+    /// Size of the serialized blocks under (and including) the block.
+    tsize: u64,
+}
+
+/// Computes the CID (v1) of a file, as an IPFS node would with its default settings
+/// and raw leaves: 256KiB chunks arranged in a balanced DAG of up to 174 links per node.
+pub fn compute_cid(data: &[u8]) -> String {
+    let leaves: Vec<Block> = if data.is_empty() {
+        vec![raw_block(data)]
+    } else {
+        data.chunks(CHUNK_SIZE).map(raw_block).collect()
+    };
+
+    let mut height = 0;
+    let mut capacity = 1;
+
+    while capacity < leaves.len() {
+        capacity *= MAX_LINKS;
+        height += 1;
+    }
+
+    let root = build_dag(&leaves, height);
+    format!("b{}", BASE32_NOPAD.encode(&root.cid).to_lowercase())
+}
+
+fn raw_block(chunk: &[u8]) -> Block {
+    Block {
+        cid: cid_bytes(RAW_CODEC, chunk),
+        file_size: chunk.len() as u64,
+        tsize: chunk.len() as u64,
+    }
+}
+
+/// Builds the (sub)tree of `height` holding the `leaves`.
+fn build_dag(leaves: &[Block], height: u32) -> Block {
+    if height == 0 {
+        let leaf = &leaves[0];
+        return Block {
+            cid: leaf.cid.clone(),
+            file_size: leaf.file_size,
+            tsize: leaf.tsize,
+        };
+    }
+
+    let children: Vec<Block> = leaves
+        .chunks(MAX_LINKS.pow(height - 1))
+        .map(|leaves| build_dag(leaves, height - 1))
+        .collect();
+    let file_size = children.iter().map(|child| child.file_size).sum();
+
+    // UnixFS data: type, file size and the size of each child
+    let mut unixfs = Vec::new();
+    put_varint_field(&mut unixfs, 1, UNIXFS_FILE);
+    put_varint_field(&mut unixfs, 3, file_size);
+    for child in &children {
+        put_varint_field(&mut unixfs, 4, child.file_size);
+    }
+
+    // DAG-PB node: links first, then the data
+    let mut node = Vec::new();
+    for child in &children {
+        let mut link = Vec::new();
+        put_bytes_field(&mut link, 1, &child.cid);
+        put_bytes_field(&mut link, 2, &[]);
+        put_varint_field(&mut link, 3, child.tsize);
+        put_bytes_field(&mut node, 2, &link);
+    }
+    put_bytes_field(&mut node, 1, &unixfs);
+
+    Block {
+        cid: cid_bytes(DAG_PB_CODEC, &node),
+        file_size,
+        tsize: node.len() as u64 + children.iter().map(|child| child.tsize).sum::<u64>(),
+    }
+}
+
+/// Binary CID (v1) with a SHA-256 multihash.
+fn cid_bytes(codec: u8, block: &[u8]) -> Vec<u8> {
+    let mut cid = vec![0x01, codec, 0x12, 0x20];
+    cid.extend_from_slice(digest(&SHA256, block).as_ref());
+    cid
+}
+
+fn put_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn put_varint_field(buffer: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(buffer, field << 3);
+    put_varint(buffer, value);
+}
+
+fn put_bytes_field(buffer: &mut Vec<u8>, field: u64, value: &[u8]) {
+    put_varint(buffer, (field << 3) | 2);
+    put_varint(buffer, value.len() as u64);
+    buffer.extend_from_slice(value);
+}
+
+#[async_trait]
+impl Prepare for PinningServiceMethod {
+    async fn prepare(
+        &self,
+        _sugar_config: &SugarConfig,
+        _config_data: &ConfigData,
+        _asset_pairs: &HashMap<isize, AssetPair>,
+        _asset_indices: Vec<(DataType, &[isize])>,
+    ) -> Result<()> {
+        // nothing to do here
+        Ok(())
+    }
+}
+
+impl StorageUploader for PinningServiceMethod {}
+
+#[async_trait]
+impl ParallelUploader for PinningServiceMethod {
+    /// Returns the number of files that to be send in parallel.
+    fn parallel_limit(&self) -> usize {
+        self.parallel_limit as usize
+    }
+
+    fn upload_asset(&self, asset_info: AssetInfo) -> JoinHandle<Result<(String, String)>> {
+        let config = self.0.clone();
+        tokio::spawn(async move { config.send(asset_info).await })
+    }
+}
+
+impl Config {
+    async fn send(&self, asset_info: AssetInfo) -> Result<(String, String)> {
+        let data = match asset_info.data_type {
+            DataType::Image => fs::read(&asset_info.content)?,
+            DataType::Metadata => asset_info.content.into_bytes(),
+            DataType::Animation => fs::read(&asset_info.content)?,
+        };
+
+        let cid = compute_cid(&data);
+
+        // makes the content available to the pinning service
+        if let Some(ipfs_api_url) = &self.ipfs_api_url {
+            let response = send_with_retry(data.len(), || {
+                let file = Part::bytes(data.clone())
+                    .file_name(asset_info.name.clone())
+                    .mime_str(asset_info.content_type.as_str())?;
+
+                Ok(Client::new()
+                    .post(format!("{ipfs_api_url}/api/v0/add"))
+                    .query(&[("cid-version", "1"), ("raw-leaves", "true")])
+                    .multipart(Form::new().part("file", file)))
+            })
+            .await?;
+            let status = response.status();
+
+            if !status.is_success() {
+                return Err(anyhow!(UploadError::SendDataFailed(format!(
+                    "Error adding file to IPFS node ({}): {}",
+                    status,
+                    response.text().await?
+                ))));
+            }
+
+            let AddResponse { hash } = response.json().await?;
+
+            if hash != cid {
+                return Err(anyhow!(UploadError::SendDataFailed(format!(
+                    "CID mismatch for file '{}': computed {}, IPFS node returned {}",
+                    asset_info.name, cid, hash
+                ))));
+            }
+        }
+
+        let body = json!({
+            "cid": cid,
+            "name": asset_info.name,
+            "origins": self.origins,
+        });
+
+        let response = send_with_retry(0, || {
+            Ok(self
+                .client
+                .post(format!("{}/pins", self.endpoint))
+                .json(&body))
+        })
+        .await?;
+        let status = response.status();
+
+        if status.is_success() {
+            let PinStatus {
+                requestid,
+                status: pin_status,
+            } = response.json().await?;
+
+            if pin_status == "failed" {
+                return Err(anyhow!(UploadError::SendDataFailed(format!(
+                    "Pin request {} of file '{}' failed",
+                    requestid, asset_info.name
+                ))));
+            }
+
+            Ok((asset_info.asset_id, format!("ipfs://{cid}")))
+        } else {
+            let body = response.json::<Value>().await?;
+            let details = if let Some(details) = &body["error"]["details"].as_str() {
+                details.to_string()
+            } else {
+                body.to_string()
+            };
+            Err(anyhow!(UploadError::SendDataFailed(format!(
+                "Error requesting pin ({}): {}",
+                status, details
+            ))))
+        }
+    }
+}
+
+#[test]
+fn test_pinning_service_cid() {
+    // single chunk files are raw blocks
+    assert_eq!(
+        compute_cid(b"hello world"),
+        "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+    );
+    assert_eq!(
+        compute_cid(b""),
+        "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+    );
+
+    // larger files are DAG-PB nodes linking the chunks
+    let data = vec![7u8; CHUNK_SIZE * 2 + 1];
+    assert_eq!(
+        compute_cid(&data),
+        "bafybeiam4zmyknj3wiatg2raurbn3jqmb7ox4hhxufwb3zqplpvb5gapr4"
+    );
+}
@@ -9,13 +9,12 @@ use crate::{
     config::*,
     upload::{
         assets::{AssetPair, DataType},
-        uploader::{AssetInfo, ParallelUploader, Prepare},
+        throttle::send_with_retry,
+        uploader::{AssetInfo, ParallelUploader, Prepare, StorageUploader},
         UploadError,
     },
 };
 
-// Maximum number of times to retry each individual upload.
-const MAX_RETRY: usize = 3;
 // Base URL for the Sdrive API.
 const BASE_URL: &str = "https://sdrive.app/api/v3";
 
@@ -46,49 +45,39 @@ impl SdriveMethod {
             DataType::Metadata => asset_info.content.into_bytes(),
             DataType::Animation => fs::read(&asset_info.content)?,
         };
-        let data_clone = data.clone(); // Clone the data outside the loop
-        let apikey_clone = apikey.clone(); // Clone the apikey outside the loop
-
         let http_client = reqwest::Client::new();
-        let mut retries = 0;
 
-        loop {
-            let file = Part::bytes(data_clone.clone()) // Use the cloned data
+        let response = send_with_retry(data.len(), || {
+            let file = Part::bytes(data.clone())
                 .file_name(asset_info.name.clone())
                 .mime_str(asset_info.content_type.as_str())?;
-
             let form = Form::new()
                 .part("fileupload", file)
-                .text("apikey", apikey_clone.clone()); // Use the cloned apikey
+                .text("apikey", apikey.clone());
 
-            let response = http_client
+            Ok(http_client
                 .post(format!("{}/upload", &BASE_URL))
-                .multipart(form)
-                .send()
-                .await;
+                .multipart(form))
+        })
+        .await
+        .map_err(|e| anyhow!("Failed to upload to Sdrive: {}", e))?;
 
-            match response {
-                Ok(resp) => {
-                    if resp.status().is_success() {
-                        let upload_response: UploadResponse = resp.json().await?;
-                        if upload_response.status == "success" {
-                            return Ok((asset_info.asset_id.clone(), upload_response.permalink));
-                        }
-                    } else {
-                        return Err(anyhow!(UploadError::SendDataFailed(format!(
-                            "Error uploading file ({}): {}",
-                            resp.status(),
-                            resp.text().await?,
-                        ))));
-                    }
-                }
-                Err(_) => {
-                    retries += 1;
-                    if retries >= MAX_RETRY {
-                        return Err(anyhow::anyhow!("Failed to upload to Sdrive."));
-                    }
-                }
+        if response.status().is_success() {
+            let upload_response: UploadResponse = response.json().await?;
+            if upload_response.status == "success" {
+                Ok((asset_info.asset_id.clone(), upload_response.permalink))
+            } else {
+                Err(anyhow!(UploadError::SendDataFailed(format!(
+                    "Error uploading file: {}",
+                    upload_response.status
+                ))))
             }
+        } else {
+            Err(anyhow!(UploadError::SendDataFailed(format!(
+                "Error uploading file ({}): {}",
+                response.status(),
+                response.text().await?,
+            ))))
         }
     }
 }
@@ -98,6 +87,7 @@ impl Prepare for SdriveMethod {
     async fn prepare(
         &self,
         _sugar_config: &SugarConfig,
+        _config_data: &ConfigData,
         _asset_pairs: &HashMap<isize, AssetPair>,
         _asset_indices: Vec<(DataType, &[isize])>,
     ) -> Result<()> {
@@ -106,6 +96,8 @@ impl Prepare for SdriveMethod {
     }
 }
 
+impl StorageUploader for SdriveMethod {}
+
 #[async_trait]
 impl ParallelUploader for SdriveMethod {
     fn upload_asset(&self, asset_info: AssetInfo) -> JoinHandle<Result<(String, String)>> {
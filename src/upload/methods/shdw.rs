@@ -1,5 +1,9 @@
 use std::{fs, ops::Deref, sync::Arc};
 
+use anchor_client::solana_sdk::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+};
 use async_trait::async_trait;
 use data_encoding::HEXLOWER;
 use reqwest::{
@@ -7,15 +11,24 @@ use reqwest::{
     StatusCode,
 };
 use ring::digest::{Context, SHA256};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
 use solana_program::pubkey;
+use spl_associated_token_account::get_associated_token_address;
 use tokio::task::JoinHandle;
 
 use crate::{
     common::*,
     config::*,
+    dry_run::{is_dry_run, plan_transaction},
+    rate_limit::throttled_client_with_commitment,
     upload::{
-        assets::{get_updated_metadata, AssetPair, DataType},
-        uploader::{AssetInfo, ParallelUploader, Prepare, MOCK_URI_SIZE},
+        assets::{get_updated_metadata, AssetPair, DataType, MetadataOverrides},
+        uploader::{AssetInfo, ParallelUploader, Prepare, StorageUploader, MOCK_URI_SIZE},
         UploadError,
     },
     utils::*,
@@ -29,6 +42,16 @@ const MAINNET_ENDPOINT: &str = "https://shadow-storage.genesysgo.net";
 const DEVNET_ENDPOINT: &str = "https://shadow-storage-dev.genesysgo.net";
 // Shadow Drive files location.
 const SHDW_DRIVE_LOCATION: &str = "https://shdw-drive.genesysgo.net";
+// SHDW token mint, staked to reserve storage.
+const SHDW_TOKEN_MINT: Pubkey = pubkey!("SHDWyBxihqiCj6YekG2GUr7wqKLeLAMK1gHZck9pL6y");
+// Shadow Drive uploader, co-signer of the storage transactions.
+const SHDW_UPLOADER: Pubkey = pubkey!("972oJTFyjmVNsWM4GHEKPWUkY3ibBkJbKQeSP8pNBRdq");
+// Offset of the reserved storage on a storage account (v2).
+const STORAGE_ACCOUNT_STORAGE_OFFSET: usize = 14;
+// Offset of the owner on a storage account (v2).
+const STORAGE_ACCOUNT_OWNER_OFFSET: usize = 22;
+/// Default extra storage (percentage) reserved over the size of the assets.
+pub const DEFAULT_STORAGE_MARGIN: u8 = 10;
 
 #[derive(Debug, Deserialize, Default)]
 #[serde(default)]
@@ -45,6 +68,7 @@ pub struct Config {
     keypair: Keypair,
     storage_account: Pubkey,
     storage_info: StorageInfo,
+    shdw_config: ShdwConfig,
 }
 
 pub struct SHDWMethod(Arc<Config>);
@@ -59,47 +83,21 @@ impl Deref for SHDWMethod {
 impl SHDWMethod {
     pub async fn new(sugar_config: &SugarConfig, config_data: &ConfigData) -> Result<Self> {
         if let Some(pubkey) = &config_data.shdw_storage_account {
-            let client = setup_client(sugar_config)?;
-            let program = client.program(SHADOW_DRIVE_PROGRAM_ID);
-            let solana_cluster: Cluster = get_cluster(program.rpc())?;
-
-            let endpoint = match solana_cluster {
-                Cluster::Devnet => DEVNET_ENDPOINT,
-                Cluster::Mainnet => MAINNET_ENDPOINT,
-                Cluster::Unknown | Cluster::Localnet => {
-                    return Err(anyhow!(
-                        "ShadowDrive is only supported on devnet or mainnet"
-                    ));
-                }
-            };
-
-            let http_client = reqwest::Client::new();
-            let mut json = HashMap::new();
-            json.insert("storage_account", pubkey);
-
-            let response = http_client
-                .post(format!("{endpoint}/storage-account-info"))
-                .json(&json)
-                .send()
-                .await?;
+            let rpc_client = throttled_client_with_commitment(&sugar_config.rpc_url);
+            let endpoint = shdw_endpoint(&rpc_client)?;
+            let storage_account = Pubkey::from_str(pubkey)?;
+            let storage_info = get_storage_info(endpoint, &storage_account).await?;
 
             let key_bytes = sugar_config.keypair.to_bytes();
             let keypair = Keypair::from_bytes(&key_bytes)?;
 
-            match response.status() {
-                StatusCode::OK => {
-                    let body = response.json::<Value>().await?;
-                    let storage_info: StorageInfo = serde_json::from_value(body)?;
-
-                    Ok(Self(Arc::new(Config {
-                        endpoint: endpoint.to_string(),
-                        keypair,
-                        storage_account: Pubkey::from_str(pubkey)?,
-                        storage_info,
-                    })))
-                }
-                code => Err(anyhow!("Could not initialize storage account: {code}")),
-            }
+            Ok(Self(Arc::new(Config {
+                endpoint: endpoint.to_string(),
+                keypair,
+                storage_account,
+                storage_info,
+                shdw_config: config_data.shdw_config.clone().unwrap_or_default(),
+            })))
         } else {
             Err(anyhow!(
                 "Missing 'shdwStorageAccount' value in config file."
@@ -108,69 +106,296 @@ impl SHDWMethod {
     }
 }
 
+/// Shadow Drive endpoint of the cluster.
+pub fn shdw_endpoint(rpc_client: &RpcClient) -> Result<&'static str> {
+    match get_cluster(rpc_client)? {
+        Cluster::Devnet => Ok(DEVNET_ENDPOINT),
+        Cluster::Mainnet => Ok(MAINNET_ENDPOINT),
+        Cluster::Unknown | Cluster::Localnet => Err(anyhow!(
+            "ShadowDrive is only supported on devnet or mainnet"
+        )),
+    }
+}
+
+/// Retrieve the reserved and used storage of the storage account.
+pub async fn get_storage_info(endpoint: &str, storage_account: &Pubkey) -> Result<StorageInfo> {
+    let http_client = reqwest::Client::new();
+    let mut json = HashMap::new();
+    json.insert("storage_account", storage_account.to_string());
+
+    let response = http_client
+        .post(format!("{endpoint}/storage-account-info"))
+        .json(&json)
+        .send()
+        .await?;
+
+    match response.status() {
+        StatusCode::OK => {
+            let body = response.json::<Value>().await?;
+            Ok(serde_json::from_value(body)?)
+        }
+        code => Err(anyhow!("Could not initialize storage account: {code}")),
+    }
+}
+
+/// Storage accounts (v2) owned by `owner`, with their reserved storage in bytes.
+pub fn find_storage_accounts(rpc_client: &RpcClient, owner: &Pubkey) -> Result<Vec<(Pubkey, u64)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            // only storage accounts (v2), other account types share the offset of the owner
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                0,
+                &account_discriminator("StorageAccountV2"),
+            )),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                STORAGE_ACCOUNT_OWNER_OFFSET,
+                owner.as_ref(),
+            )),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..Default::default()
+        },
+        with_context: None,
+    };
+
+    let accounts = rpc_client.get_program_accounts_with_config(&SHADOW_DRIVE_PROGRAM_ID, config)?;
+
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(pubkey, account)| {
+            reserved_storage(&account.data).map(|storage| (pubkey, storage))
+        })
+        .collect())
+}
+
+/// Anchor discriminator of an account type.
+fn account_discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(format!("account:{}", name).as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+/// Reserved storage (in bytes) of a storage account (v2), if the data is large enough.
+fn reserved_storage(data: &[u8]) -> Option<u64> {
+    let offset = STORAGE_ACCOUNT_STORAGE_OFFSET;
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Size of the storage required by the asset files, including the metadata files with
+/// (mock) links of the media files.
+pub fn storage_size(
+    config_data: &ConfigData,
+    assets: &HashMap<isize, AssetPair>,
+    asset_indices: Vec<(DataType, &[isize])>,
+) -> Result<u64> {
+    let mut total_size = 0;
+
+    for (data_type, indices) in asset_indices {
+        match data_type {
+            DataType::Image => {
+                for index in indices {
+                    let item = assets.get(index).unwrap();
+                    let path = Path::new(&item.image);
+                    total_size += fs::metadata(path)?.len();
+                }
+            }
+            DataType::Animation => {
+                for index in indices {
+                    let item = assets.get(index).unwrap();
+
+                    if let Some(animation) = &item.animation {
+                        let path = Path::new(animation);
+                        total_size += fs::metadata(path)?.len();
+                    }
+                }
+            }
+            DataType::Metadata => {
+                let mock_uri = "x".repeat(MOCK_URI_SIZE);
+
+                for index in indices {
+                    let item = assets.get(index).unwrap();
+                    let animation = if item.animation.is_some() {
+                        Some(mock_uri.clone())
+                    } else {
+                        None
+                    };
+
+                    total_size += get_updated_metadata(
+                        &item.metadata,
+                        &mock_uri.clone(),
+                        &animation,
+                        &MetadataOverrides::new(config_data, *index),
+                    )?
+                    .into_bytes()
+                    .len() as u64;
+                }
+            }
+        }
+    }
+
+    Ok(total_size)
+}
+
+/// Size including the margin (percentage).
+pub fn with_margin(size: u64, margin: u8) -> u64 {
+    size + size * margin as u64 / 100
+}
+
+/// Change of the reserved storage of a storage account.
+#[derive(Debug, Clone, Copy)]
+pub enum StorageResize {
+    Increase(u64),
+    Decrease(u64),
+}
+
+/// Change the storage reserved by a storage account: the transaction is signed by the
+/// owner and sent to the Shadow Drive endpoint, which co-signs it as the uploader.
+pub async fn resize_storage(
+    sugar_config: &SugarConfig,
+    storage_account: &Pubkey,
+    resize: StorageResize,
+) -> Result<()> {
+    let rpc_client = throttled_client_with_commitment(&sugar_config.rpc_url);
+    let endpoint = shdw_endpoint(&rpc_client)?;
+
+    let owner = sugar_config.keypair.pubkey();
+    let route = match resize {
+        StorageResize::Increase(_) => "add-storage",
+        StorageResize::Decrease(_) => "reduce-storage",
+    };
+    let instruction = resize_storage_ix(&owner, storage_account, resize);
+
+    let mut tx = Transaction::new_with_payer(&[instruction], Some(&owner));
+    tx.partial_sign(&[&sugar_config.keypair], rpc_client.get_latest_blockhash()?);
+
+    // the uploader signs and sends the transaction, so a dry run only records it
+    if is_dry_run() {
+        plan_transaction(route, &tx);
+        return Ok(());
+    }
+
+    let mut json = HashMap::new();
+    json.insert("transaction", base64::encode(bincode::serialize(&tx)?));
+
+    let response = reqwest::Client::new()
+        .post(format!("{endpoint}/{route}"))
+        .json(&json)
+        .send()
+        .await?;
+
+    match response.status() {
+        StatusCode::OK => Ok(()),
+        code => Err(anyhow!(
+            "Could not resize storage account ({code}): {}",
+            response.text().await?
+        )),
+    }
+}
+
+/// Instruction to change the storage reserved by a storage account.
+fn resize_storage_ix(
+    owner: &Pubkey,
+    storage_account: &Pubkey,
+    resize: StorageResize,
+) -> Instruction {
+    let (storage_config, _) =
+        Pubkey::find_program_address(&[b"storage-config"], &SHADOW_DRIVE_PROGRAM_ID);
+    let (stake_account, _) = Pubkey::find_program_address(
+        &[b"stake-account", storage_account.as_ref()],
+        &SHADOW_DRIVE_PROGRAM_ID,
+    );
+    let owner_ata = get_associated_token_address(owner, &SHDW_TOKEN_MINT);
+
+    let (name, bytes) = match resize {
+        StorageResize::Increase(bytes) => ("increase_storage", bytes),
+        StorageResize::Decrease(bytes) => ("decrease_storage", bytes),
+    };
+
+    let mut accounts = vec![
+        AccountMeta::new(storage_config, false),
+        AccountMeta::new(*storage_account, false),
+    ];
+
+    if let StorageResize::Decrease(_) = resize {
+        let (unstake_info, _) = Pubkey::find_program_address(
+            &[b"unstake-info", storage_account.as_ref()],
+            &SHADOW_DRIVE_PROGRAM_ID,
+        );
+        let (unstake_account, _) = Pubkey::find_program_address(
+            &[b"unstake-account", storage_account.as_ref()],
+            &SHADOW_DRIVE_PROGRAM_ID,
+        );
+        accounts.push(AccountMeta::new(unstake_info, false));
+        accounts.push(AccountMeta::new(unstake_account, false));
+    }
+
+    accounts.extend([
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(owner_ata, false),
+        AccountMeta::new(stake_account, false),
+        AccountMeta::new_readonly(SHDW_TOKEN_MINT, false),
+        AccountMeta::new_readonly(SHDW_UPLOADER, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+    ]);
+
+    if let StorageResize::Decrease(_) = resize {
+        accounts.push(AccountMeta::new_readonly(sysvar::rent::ID, false));
+    }
+
+    // anchor instruction: discriminator followed by the size (u64)
+    let mut data = hash(format!("global:{}", name).as_bytes()).to_bytes()[..8].to_vec();
+    data.extend_from_slice(&bytes.to_le_bytes());
+
+    Instruction {
+        program_id: SHADOW_DRIVE_PROGRAM_ID,
+        accounts,
+        data,
+    }
+}
+
 #[async_trait]
 impl Prepare for SHDWMethod {
     async fn prepare(
         &self,
-        _sugar_config: &SugarConfig,
+        sugar_config: &SugarConfig,
+        config_data: &ConfigData,
         assets: &HashMap<isize, AssetPair>,
         asset_indices: Vec<(DataType, &[isize])>,
     ) -> Result<()> {
         // calculates the size of the files to upload, this assumes that the total
         // storage has enough space to hold the collection as assets might already
         // exist and therefore will be replaced
-        let mut total_size = 0;
-
-        for (data_type, indices) in asset_indices {
-            match data_type {
-                DataType::Image => {
-                    for index in indices {
-                        let item = assets.get(index).unwrap();
-                        let path = Path::new(&item.image);
-                        total_size += fs::metadata(path)?.len();
-                    }
-                }
-                DataType::Animation => {
-                    for index in indices {
-                        let item = assets.get(index).unwrap();
-
-                        if let Some(animation) = &item.animation {
-                            let path = Path::new(animation);
-                            total_size += fs::metadata(path)?.len();
-                        }
-                    }
-                }
-                DataType::Metadata => {
-                    let mock_uri = "x".repeat(MOCK_URI_SIZE);
-
-                    for index in indices {
-                        let item = assets.get(index).unwrap();
-                        let animation = if item.animation.is_some() {
-                            Some(mock_uri.clone())
-                        } else {
-                            None
-                        };
-
-                        total_size +=
-                            get_updated_metadata(&item.metadata, &mock_uri.clone(), &animation)?
-                                .into_bytes()
-                                .len() as u64;
-                    }
-                }
-            }
-        }
+        let total_size = storage_size(config_data, assets, asset_indices)?;
 
         if self.storage_info.reserved_bytes < total_size {
-            let required = total_size - self.storage_info.reserved_bytes;
-            return Err(anyhow!(
-                "Insufficient storage space (additional {required} bytes required)"
-            ));
+            let margin = self.shdw_config.margin.unwrap_or(DEFAULT_STORAGE_MARGIN);
+            let required = with_margin(total_size, margin) - self.storage_info.reserved_bytes;
+
+            if !self.shdw_config.auto_expand {
+                return Err(anyhow!(
+                    "Insufficient storage space (additional {required} bytes required), \
+                    set 'shdwConfig.autoExpand' or run 'sugar shdw plan --expand'"
+                ));
+            }
+
+            println!("Expanding storage account by {} bytes...", required);
+            resize_storage(
+                sugar_config,
+                &self.storage_account,
+                StorageResize::Increase(required),
+            )
+            .await?;
         }
 
         Ok(())
     }
 }
 
+impl StorageUploader for SHDWMethod {}
+
 #[async_trait]
 impl ParallelUploader for SHDWMethod {
     fn upload_asset(&self, asset_info: AssetInfo) -> JoinHandle<Result<(String, String)>> {
@@ -237,3 +462,61 @@ impl Config {
         }
     }
 }
+
+#[test]
+fn test_storage_margin() {
+    assert_eq!(with_margin(1000, 0), 1000);
+    assert_eq!(with_margin(1000, DEFAULT_STORAGE_MARGIN), 1100);
+    assert_eq!(with_margin(0, 50), 0);
+}
+
+#[test]
+fn test_resize_storage_ix() {
+    let owner = Pubkey::new_unique();
+    let storage_account = Pubkey::new_unique();
+
+    let increase = resize_storage_ix(&owner, &storage_account, StorageResize::Increase(1024));
+    assert_eq!(increase.program_id, SHADOW_DRIVE_PROGRAM_ID);
+    assert_eq!(increase.accounts.len(), 9);
+    assert_eq!(increase.accounts[1].pubkey, storage_account);
+    assert_eq!(increase.accounts[2].pubkey, owner);
+    assert!(increase.accounts[2].is_signer && increase.accounts[2].is_writable);
+    assert_eq!(increase.accounts[6].pubkey, SHDW_UPLOADER);
+    assert!(increase.accounts[6].is_signer && !increase.accounts[6].is_writable);
+    assert_eq!(
+        increase.data[..8],
+        hash(b"global:increase_storage").to_bytes()[..8]
+    );
+    assert_eq!(increase.data[8..], 1024u64.to_le_bytes());
+
+    let decrease = resize_storage_ix(&owner, &storage_account, StorageResize::Decrease(512));
+    assert_eq!(decrease.accounts.len(), 12);
+    // the unstake accounts go before the owner, the rent sysvar last
+    let (unstake_info, _) = Pubkey::find_program_address(
+        &[b"unstake-info", storage_account.as_ref()],
+        &SHADOW_DRIVE_PROGRAM_ID,
+    );
+    assert_eq!(decrease.accounts[2].pubkey, unstake_info);
+    assert_eq!(decrease.accounts[4].pubkey, owner);
+    assert_eq!(decrease.accounts[11].pubkey, sysvar::rent::ID);
+    assert_eq!(
+        decrease.data[..8],
+        hash(b"global:decrease_storage").to_bytes()[..8]
+    );
+    assert_eq!(decrease.data[8..], 512u64.to_le_bytes());
+}
+
+#[test]
+fn test_reserved_storage() {
+    let mut data = account_discriminator("StorageAccountV2").to_vec();
+    data.extend_from_slice(&[0u8; 6]);
+    data.extend_from_slice(&4096u64.to_le_bytes());
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+
+    assert_eq!(data.len(), STORAGE_ACCOUNT_OWNER_OFFSET + 32);
+    assert_eq!(reserved_storage(&data), Some(4096));
+    assert_eq!(
+        reserved_storage(&data[..STORAGE_ACCOUNT_OWNER_OFFSET - 1]),
+        None
+    );
+}
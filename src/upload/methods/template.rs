@@ -0,0 +1,170 @@
+use std::{
+    ffi::OsStr,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use async_trait::async_trait;
+use data_encoding::HEXLOWER;
+use ring::digest::{digest, SHA256};
+
+use crate::{common::*, config::*, upload::*};
+
+// Placeholders supported by the URI template.
+const HASH_PLACEHOLDER: &str = "{hash}";
+const EXT_PLACEHOLDER: &str = "{ext}";
+const NAME_PLACEHOLDER: &str = "{name}";
+const INDEX_PLACEHOLDER: &str = "{index}";
+
+/// Upload method for files hosted by an external pipeline (e.g. a self-hosted CDN):
+/// no file is sent, the links are generated from the URI template. The files to
+/// publish are listed by `sugar manifest export`.
+pub struct TemplateMethod {
+    template: String,
+}
+
+impl TemplateMethod {
+    /// Initialize a new TemplateMethod.
+    pub fn new(config_data: &ConfigData) -> Result<Self> {
+        let template = config_data
+            .uri_template
+            .clone()
+            .ok_or_else(|| anyhow!("Missing 'uriTemplate' value in config file."))?;
+
+        // links need to be unique for each file
+        if ![HASH_PLACEHOLDER, NAME_PLACEHOLDER, INDEX_PLACEHOLDER]
+            .iter()
+            .any(|placeholder| template.contains(placeholder))
+        {
+            return Err(anyhow!(
+                "Invalid 'uriTemplate' value: it must contain one of the {}, {} or {} placeholders",
+                HASH_PLACEHOLDER,
+                NAME_PLACEHOLDER,
+                INDEX_PLACEHOLDER
+            ));
+        }
+
+        url::Url::parse(&render_uri(&template, "0", "0.png", "0"))
+            .map_err(|e| anyhow!("Invalid 'uriTemplate' value '{}': {}", template, e))?;
+
+        Ok(Self { template })
+    }
+}
+
+/// Render the link of a file from the URI template.
+pub fn render_uri(template: &str, asset_id: &str, file_name: &str, hash: &str) -> String {
+    let path = Path::new(file_name);
+    let name = path.file_stem().and_then(OsStr::to_str).unwrap_or_default();
+    let ext = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| format!(".{}", ext))
+        .unwrap_or_default();
+
+    template
+        .replace(HASH_PLACEHOLDER, hash)
+        .replace(EXT_PLACEHOLDER, &ext)
+        .replace(NAME_PLACEHOLDER, name)
+        .replace(INDEX_PLACEHOLDER, asset_id)
+}
+
+/// SHA-256 hash of the content of an asset: the file for media assets and the
+/// (updated) content for metadata.
+pub fn content_hash(asset_info: &AssetInfo) -> Result<String> {
+    match asset_info.data_type {
+        DataType::Image | DataType::Animation => encode(&asset_info.content),
+        DataType::Metadata => {
+            Ok(HEXLOWER.encode(digest(&SHA256, asset_info.content.as_bytes()).as_ref()))
+        }
+    }
+}
+
+#[async_trait]
+impl Prepare for TemplateMethod {
+    async fn prepare(
+        &self,
+        _sugar_config: &SugarConfig,
+        _config_data: &ConfigData,
+        _asset_pairs: &HashMap<isize, AssetPair>,
+        _asset_indices: Vec<(DataType, &[isize])>,
+    ) -> Result<()> {
+        // nothing to do here
+        Ok(())
+    }
+}
+
+impl StorageUploader for TemplateMethod {}
+
+#[async_trait]
+impl Uploader for TemplateMethod {
+    /// Generate the links of the assets from the URI template.
+    async fn upload(
+        &self,
+        _sugar_config: &SugarConfig,
+        cache: &mut Cache,
+        data_type: DataType,
+        assets: &mut Vec<AssetInfo>,
+        progress: &ProgressBar,
+        interrupted: Arc<AtomicBool>,
+    ) -> Result<Vec<UploadError>> {
+        let mut errors = Vec::new();
+
+        for asset_info in assets.iter() {
+            if interrupted.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let hash = match content_hash(asset_info) {
+                Ok(hash) => hash,
+                Err(err) => {
+                    errors.push(UploadError::SendDataFailed(format!(
+                        "Error reading file '{}': {}",
+                        asset_info.name, err
+                    )));
+                    continue;
+                }
+            };
+            let uri = render_uri(
+                &self.template,
+                &asset_info.asset_id,
+                &asset_info.name,
+                &hash,
+            );
+            // cache item to update
+            let item = cache.items.get_mut(&asset_info.asset_id).unwrap();
+
+            match data_type {
+                DataType::Image => item.image_link = uri,
+                DataType::Metadata => item.metadata_link = uri,
+                DataType::Animation => item.animation_link = Some(uri),
+            }
+
+            progress.inc(1);
+        }
+
+        // syncs cache (checkpoint)
+        cache.sync_file()?;
+
+        Ok(errors)
+    }
+}
+
+#[test]
+fn test_render_uri_template() {
+    assert_eq!(
+        render_uri("https://cdn.example.com/{hash}{ext}", "3", "3.png", "ab12"),
+        "https://cdn.example.com/ab12.png"
+    );
+    assert_eq!(
+        render_uri(
+            "https://cdn.example.com/{index}/{name}{ext}",
+            "-1",
+            "collection.json",
+            "ab12"
+        ),
+        "https://cdn.example.com/-1/collection.json"
+    );
+}
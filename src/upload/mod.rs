@@ -1,11 +1,21 @@
 pub mod assets;
+pub mod duplicates;
 pub mod errors;
 pub mod methods;
+pub mod optimize;
 pub mod process;
+pub mod stats;
+pub mod throttle;
 pub mod uploader;
+pub mod verify;
 
 pub use assets::*;
+pub use duplicates::*;
 pub use errors::*;
 pub use methods::*;
+pub use optimize::*;
 pub use process::*;
+pub use stats::*;
+pub use throttle::*;
 pub use uploader::*;
+pub use verify::*;
@@ -0,0 +1,298 @@
+use std::{
+    ffi::OsStr,
+    fs::{self, File},
+    io::BufWriter,
+};
+
+use image::{
+    codecs::{
+        jpeg::JpegEncoder,
+        png::{CompressionType, FilterType as PngFilter, PngEncoder},
+        webp::{WebPEncoder, WebPQuality},
+    },
+    imageops::FilterType,
+    ColorType, DynamicImage, GenericImageView, ImageEncoder,
+};
+use rayon::prelude::*;
+
+use crate::{
+    common::*,
+    config::OptimizationConfig,
+    journal::STATE_DIR,
+    upload::{encode, AssetPair},
+    utils::progress_bar_with_style,
+    validate::format::Metadata,
+};
+
+/// Name of the folder (inside the state folder) where the optimized files are written.
+pub const OPTIMIZED_DIR: &str = "optimized";
+
+/// Default quality used to compress JPEG and WebP images.
+const DEFAULT_QUALITY: u8 = 85;
+
+/// Summary of the optimization pass.
+#[derive(Debug, Default)]
+pub struct OptimizationReport {
+    pub optimized: usize,
+    pub original_size: u64,
+    pub optimized_size: u64,
+}
+
+/// Optimize the images of the asset pairs according to the config. The optimized
+/// images (and their updated metadata files when the format changes) are written to
+/// the optimized folder and the asset pairs are updated to point to them; original
+/// files are never modified.
+pub fn optimize_assets(
+    asset_pairs: &mut HashMap<isize, AssetPair>,
+    config: &OptimizationConfig,
+) -> Result<OptimizationReport> {
+    optimize_assets_to(
+        &Path::new(STATE_DIR).join(OPTIMIZED_DIR),
+        asset_pairs,
+        config,
+    )
+}
+
+fn optimize_assets_to(
+    output_dir: &Path,
+    asset_pairs: &mut HashMap<isize, AssetPair>,
+    config: &OptimizationConfig,
+) -> Result<OptimizationReport> {
+    if let Some(quality) = config.quality {
+        if quality == 0 || quality > 100 {
+            return Err(anyhow!(
+                "Invalid optimization quality {}, it must be between 1 and 100",
+                quality
+            ));
+        }
+    }
+
+    fs::create_dir_all(output_dir)?;
+
+    let total = asset_pairs
+        .values()
+        .filter(|pair| is_optimizable(&pair.image))
+        .count();
+    let pb = progress_bar_with_style(total as u64);
+    pb.set_message("Optimizing images");
+
+    let results = asset_pairs
+        .par_iter_mut()
+        .filter(|(_, pair)| is_optimizable(&pair.image))
+        .map(|(_, pair)| {
+            let result = optimize_pair(pair, output_dir, config);
+            pb.inc(1);
+            result
+        })
+        .collect::<Result<Vec<(u64, u64)>>>()?;
+
+    pb.finish_and_clear();
+
+    let mut report = OptimizationReport::default();
+
+    for (original_size, optimized_size) in results {
+        report.optimized += 1;
+        report.original_size += original_size;
+        report.optimized_size += optimized_size;
+    }
+
+    Ok(report)
+}
+
+fn is_optimizable(path: &str) -> bool {
+    matches!(extension(path).as_str(), "png" | "jpg" | "jpeg" | "webp")
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Optimize the image of an asset pair, returning the original and optimized sizes.
+fn optimize_pair(
+    pair: &mut AssetPair,
+    output_dir: &Path,
+    config: &OptimizationConfig,
+) -> Result<(u64, u64)> {
+    let source = Path::new(&pair.image);
+    let original_size = fs::metadata(source)?.len();
+    let source_ext = extension(&pair.image);
+    let target_ext = if config.webp {
+        "webp".to_string()
+    } else {
+        source_ext.clone()
+    };
+
+    let stem = source
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .ok_or_else(|| anyhow!("Invalid image file name: {}", pair.image))?;
+    let target = output_dir.join(format!("{stem}.{target_ext}"));
+
+    // reuses the optimized image when it is newer than the original
+    let up_to_date = fs::metadata(&target)
+        .and_then(|target| Ok(target.modified()? >= fs::metadata(source)?.modified()?))
+        .unwrap_or(false);
+
+    if !up_to_date {
+        let mut image = image::open(source)
+            .map_err(|e| anyhow!("Failed to open image {}: {}", pair.image, e))?;
+
+        if let Some(max_dimension) = config.max_dimension {
+            let (width, height) = image.dimensions();
+            if width > max_dimension || height > max_dimension {
+                image = image.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+            }
+        }
+
+        write_image(&image, &target, &target_ext, config.quality)?;
+    }
+
+    let optimized_size = fs::metadata(&target)?.len();
+    let image_path = path_to_string(&target)?;
+    pair.image_hash = encode(&image_path)?;
+
+    if target_ext != source_ext {
+        let metadata_path = output_dir.join(format!("{stem}.json"));
+        write_converted_metadata(pair, &metadata_path, &target, &target_ext)?;
+        pair.metadata = path_to_string(&metadata_path)?;
+        pair.metadata_hash = encode(&pair.metadata)?;
+    }
+
+    pair.image = image_path;
+
+    Ok((original_size, optimized_size))
+}
+
+fn write_image(
+    image: &DynamicImage,
+    target: &Path,
+    format: &str,
+    quality: Option<u8>,
+) -> Result<()> {
+    let writer = BufWriter::new(File::create(target)?);
+    let quality = quality.unwrap_or(DEFAULT_QUALITY);
+
+    match format {
+        "jpg" | "jpeg" => {
+            let rgb = image.to_rgb8();
+            JpegEncoder::new_with_quality(writer, quality).write_image(
+                rgb.as_raw(),
+                rgb.width(),
+                rgb.height(),
+                ColorType::Rgb8,
+            )?;
+        }
+        "png" => {
+            let rgba = image.to_rgba8();
+            PngEncoder::new_with_quality(writer, CompressionType::Best, PngFilter::Adaptive)
+                .write_image(rgba.as_raw(), rgba.width(), rgba.height(), ColorType::Rgba8)?;
+        }
+        "webp" => {
+            let rgba = image.to_rgba8();
+            WebPEncoder::new_with_quality(writer, WebPQuality::lossy(quality)).write_image(
+                rgba.as_raw(),
+                rgba.width(),
+                rgba.height(),
+                ColorType::Rgba8,
+            )?;
+        }
+        _ => return Err(anyhow!("Unsupported image format: {}", format)),
+    }
+
+    Ok(())
+}
+
+/// Write a copy of the metadata file with the image file name and type updated to
+/// the converted format.
+fn write_converted_metadata(
+    pair: &AssetPair,
+    metadata_path: &Path,
+    image: &Path,
+    format: &str,
+) -> Result<()> {
+    let mut metadata: Metadata = serde_json::from_reader(File::open(&pair.metadata)?)
+        .map_err(|e| anyhow!("Error parsing metadata ({}): {}", pair.metadata, e))?;
+
+    let image_name = image
+        .file_name()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_string();
+
+    for file in &mut metadata.properties.files {
+        if file.uri == metadata.image {
+            file.uri = image_name.clone();
+            file.file_type = format!("image/{format}");
+        }
+    }
+
+    metadata.image = image_name;
+
+    fs::write(metadata_path, serde_json::to_string_pretty(&metadata)?)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_optimized_files_outside_assets() {
+    let dir = std::env::temp_dir().join(format!("sugar-optimize-{}", std::process::id()));
+    let assets_dir = dir.join("assets");
+    let output_dir = dir.join(STATE_DIR).join(OPTIMIZED_DIR);
+    fs::create_dir_all(&assets_dir).unwrap();
+
+    let image = assets_dir.join("0.png");
+    let metadata = assets_dir.join("0.json");
+    image::RgbaImage::new(8, 8).save(&image).unwrap();
+    fs::write(
+        &metadata,
+        json!({
+            "name": "Item #0",
+            "description": "",
+            "image": "0.png",
+            "properties": { "files": [{ "uri": "0.png", "type": "image/png" }], "category": "image" }
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let image_path = path_to_string(&image).unwrap();
+    let metadata_path = path_to_string(&metadata).unwrap();
+    let mut asset_pairs = HashMap::new();
+    asset_pairs.insert(
+        0,
+        AssetPair {
+            name: "Item #0".to_string(),
+            metadata_hash: encode(&metadata_path).unwrap(),
+            metadata: metadata_path,
+            image_hash: encode(&image_path).unwrap(),
+            image: image_path,
+            animation: None,
+            animation_hash: None,
+        },
+    );
+
+    let config = OptimizationConfig {
+        max_dimension: Some(4),
+        quality: None,
+        webp: true,
+    };
+    let report = optimize_assets_to(&output_dir, &mut asset_pairs, &config).unwrap();
+    assert_eq!(report.optimized, 1);
+
+    // the converted files are written to the output folder, the assets folder is untouched
+    let pair = &asset_pairs[&0];
+    assert_eq!(Path::new(&pair.image), output_dir.join("0.webp"));
+    assert_eq!(Path::new(&pair.metadata), output_dir.join("0.json"));
+    assert_eq!(fs::read_dir(&assets_dir).unwrap().count(), 2);
+
+    let converted: Value =
+        serde_json::from_str(&fs::read_to_string(&pair.metadata).unwrap()).unwrap();
+    assert_eq!(converted["image"], "0.webp");
+    assert_eq!(converted["properties"]["files"][0]["type"], "image/webp");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
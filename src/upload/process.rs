@@ -8,14 +8,20 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::Instant,
 };
 
 use console::style;
+use solana_program::native_token::LAMPORTS_PER_SOL;
 
 use crate::{
     cache::{load_cache, Cache},
     common::*,
-    config::{get_config_data, SugarConfig},
+    config::{get_config_data, CollectionConfig, ConfigData, SugarConfig, UploadMethod},
+    dry_run::{is_dry_run, plan_upload},
+    hooks::{run_hook, HookEvent},
+    lock::lock_file,
+    retry::{update_failures, Failure, FailureStage},
     upload::*,
     utils::*,
     validate::format::Metadata,
@@ -27,6 +33,13 @@ pub struct UploadArgs {
     pub keypair: Option<String>,
     pub rpc_url: Option<String>,
     pub cache: String,
+    pub fail_on_duplicates: bool,
+    pub max_bandwidth: Option<u64>,
+    pub max_retries: usize,
+    pub verify: bool,
+    pub verify_sample: Option<usize>,
+    pub indices: Option<String>,
+    pub failures: String,
     pub interrupted: Arc<AtomicBool>,
 }
 
@@ -40,6 +53,9 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
     let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
     let config_data = get_config_data(&args.config)?;
 
+    set_upload_limits(args.max_bandwidth, args.max_retries);
+    let selection = IndexSelection::parse(args.indices.as_deref())?;
+
     // loading assets
     println!(
         "{} {}Loading assets",
@@ -47,158 +63,134 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
         ASSETS_EMOJI
     );
 
-    let pb = spinner_with_style();
-    pb.enable_steady_tick(120);
-    pb.set_message("Reading files...");
-    let asset_pairs = get_asset_pairs(&args.assets_dir)?;
+    let mut asset_pairs =
+        get_asset_pairs(&args.assets_dir, config_data.collection_path.as_deref())?;
 
-    // creates/loads the cache
-    let mut cache = load_cache(&args.cache, true)?;
-    if asset_pairs.get(&-1).is_none() {
-        cache.items.remove("-1");
+    if let Some(collection) = &config_data.collection {
+        apply_collection_config(collection, &mut asset_pairs)?;
     }
 
-    // list of indices to upload
-    let mut indices = AssetType {
-        image: Vec::new(),
-        metadata: Vec::new(),
-        animation: Vec::new(),
-    };
+    check_duplicates(&asset_pairs, args.fail_on_duplicates)?;
 
-    for (index, pair) in &asset_pairs {
-        // checks if we have complete URIs in the metadata file;
-        // if true, no upload is necessary and we will use the
-        // existing URIs
-
-        let m: Metadata = {
-            let m = OpenOptions::new()
-                .read(true)
-                .open(&pair.metadata)
-                .map_err(|e| {
-                    anyhow!(
-                        "Failed to read metadata file '{}' with error: {}",
-                        &pair.metadata,
-                        e
-                    )
-                })?;
-            serde_json::from_reader(&m)?
-        };
+    if let Some(optimizations) = &config_data.optimizations {
+        let report = optimize_assets(&mut asset_pairs, optimizations)?;
 
-        if m.properties.creators.is_some() {
-            println!("The creators field is deprecated in the JSON metadata, it should be set in the config file instead.")
+        if report.optimized > 0 {
+            println!(
+                "Optimized {} image(s): {} KB -> {} KB",
+                report.optimized,
+                report.original_size / 1024,
+                report.optimized_size / 1024
+            );
         }
+    }
 
-        // retrieve the existing image uri from the metadata
-        let existing_image = if is_complete_uri(&m.image) {
-            m.image.clone()
-        } else {
-            String::new()
-        };
-
-        // retrieve the existing animation uri from the metadata
-        let existing_animation = match m.animation_url {
-            Some(ref url) => {
-                if is_complete_uri(url) {
-                    url.clone()
-                } else {
-                    String::new()
-                }
-            }
-            None => String::new(),
-        };
-
-        match cache.items.get_mut(&index.to_string()) {
-            Some(item) => {
-                let image_changed = (!item.image_hash.eq(&pair.image_hash)
-                    || item.image_link.is_empty())
-                    && existing_image.is_empty();
+    apply_overrides_to_hashes(&mut asset_pairs, &config_data)?;
 
-                let animation_changed = (!item.animation_hash.eq(&pair.animation_hash)
-                    || (item.animation_link.is_none() && pair.animation.is_some()))
-                    && existing_animation.is_empty();
-
-                let metadata_changed =
-                    !item.metadata_hash.eq(&pair.metadata_hash) || item.metadata_link.is_empty();
+    let pb = spinner_with_style();
+    pb.enable_steady_tick(120);
+    pb.set_message("Reading files...");
 
-                if image_changed {
-                    // triggers the image upload
-                    item.image_hash = pair.image_hash.clone();
-                    item.image_link = String::new();
-                    indices.image.push(*index);
-                } else if !existing_image.is_empty() {
-                    item.image_hash = pair.image_hash.clone();
-                    item.image_link = existing_image;
-                }
+    // creates/loads the cache
+    // the cache is locked for the whole upload, not only when it is written
+    lock_file(&args.cache)?;
+    let mut cache = load_cache(&args.cache, true)?;
+    match config_data.collection.as_ref() {
+        Some(collection @ CollectionConfig { uri: Some(uri), .. }) => {
+            set_collection_uri(&mut cache, collection, uri)?
+        }
+        _ if asset_pairs.get(&COLLECTION_INDEX).is_none() => {
+            cache.items.remove(COLLECTION_CACHE_KEY);
+        }
+        _ => (),
+    }
 
-                if animation_changed {
-                    // triggers the animation upload
-                    item.animation_hash = pair.animation_hash.clone();
-                    item.animation_link = None;
-                    indices.animation.push(*index);
-                } else if !existing_animation.is_empty() {
-                    item.animation_hash = pair.animation_hash.clone();
-                    item.animation_link = Some(existing_animation);
-                }
+    // items outside the selection are left as they are
+    let unselected: Vec<(String, CacheItem)> = match &selection {
+        Some(selection) => cache
+            .items
+            .iter()
+            .filter(|(key, _)| !selection.contains_key(key))
+            .map(|(key, item)| (key.clone(), item.clone()))
+            .collect(),
+        None => Vec::new(),
+    };
 
-                if metadata_changed || image_changed || animation_changed {
-                    // triggers the metadata upload
-                    item.metadata_hash = pair.metadata_hash.clone();
-                    item.metadata_link = String::new();
-                    item.on_chain = false;
-                    // we need to upload metadata only
-                    indices.metadata.push(*index);
-                }
-            }
-            None => {
-                let mut item = pair.clone().into_cache_item();
+    let mut indices = get_upload_indices(&asset_pairs, &mut cache)?;
 
-                // check if we need to upload the image
-                if existing_image.is_empty() {
-                    indices.image.push(*index);
-                } else {
-                    item.image_hash = pair.image_hash.clone();
-                    item.image_link = existing_image;
-                }
+    if let Some(selection) = &selection {
+        select_upload_indices(
+            &asset_pairs,
+            &mut cache,
+            &mut indices,
+            selection,
+            unselected,
+        );
+    }
 
-                // and we might need to upload the animation
-                if pair.animation.is_some() {
-                    if existing_animation.is_empty() {
-                        indices.animation.push(*index);
-                    } else {
-                        item.animation_hash = pair.animation_hash.clone();
-                        item.animation_link = Some(existing_animation);
-                    }
-                }
+    // files with identical content are uploaded once and their link is shared
+    let shared_images = deduplicate_media(
+        &asset_pairs,
+        &mut cache,
+        &mut indices.image,
+        DataType::Image,
+    );
+    let shared_animations = deduplicate_media(
+        &asset_pairs,
+        &mut cache,
+        &mut indices.animation,
+        DataType::Animation,
+    );
 
-                indices.metadata.push(*index);
-                cache.items.insert(index.to_string(), item);
-            }
-        }
+    for (index, pair) in &asset_pairs {
         // sanity check: verifies that both symbol and seller-fee-basis-points are the
-        // same as the ones in the config file
+        // same as the ones in the config file (or the collection settings)
+        let collection = config_data
+            .collection
+            .as_ref()
+            .filter(|_| *index == COLLECTION_INDEX);
+        let expected_symbol = collection
+            .and_then(|collection| collection.symbol.clone())
+            .unwrap_or_else(|| config_data.symbol.clone());
+        let expected_seller_fee = collection
+            .and_then(|collection| collection.seller_fee_basis_points)
+            .unwrap_or(config_data.seller_fee_basis_points);
+        // royalties rewritten from the config do not need to match
+        let check_seller_fee = MetadataOverrides::new(&config_data, *index)
+            .seller_fee_basis_points
+            .is_none();
+
         let f = File::open(Path::new(&pair.metadata))?;
         match serde_json::from_reader(f) {
             Ok(metadata) => {
                 let metadata: Metadata = metadata;
+
+                if metadata.properties.creators.is_some() {
+                    println!("The creators field is deprecated in the JSON metadata, it should be set in the config file instead.")
+                }
+
                 // symbol check, but only if the asset actually has the value
                 if let Some(symbol) = metadata.symbol {
-                    if config_data.symbol.ne(&symbol) {
+                    if expected_symbol.ne(&symbol) {
                         return Err(UploadError::MismatchValue(
                             "symbol".to_string(),
                             pair.metadata.clone(),
-                            config_data.symbol,
+                            expected_symbol,
                             symbol,
                         )
                         .into());
                     }
                 }
                 // seller-fee-basis-points check, but only if the asset actually has the value
-                if let Some(seller_fee_basis_points) = metadata.seller_fee_basis_points {
-                    if config_data.seller_fee_basis_points != seller_fee_basis_points {
+                if let Some(seller_fee_basis_points) = metadata
+                    .seller_fee_basis_points
+                    .filter(|_| check_seller_fee)
+                {
+                    if expected_seller_fee != seller_fee_basis_points {
                         return Err(UploadError::MismatchValue(
                             "seller_fee_basis_points".to_string(),
                             pair.metadata.clone(),
-                            config_data.seller_fee_basis_points.to_string(),
+                            expected_seller_fee.to_string(),
                             seller_fee_basis_points.to_string(),
                         )
                         .into());
@@ -229,6 +221,14 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
 
     println!("+--------------------+");
 
+    if shared_images.count() + shared_animations.count() > 0 {
+        println!(
+            "Deduplicated {} image and {} animation upload(s) with identical content",
+            shared_images.count(),
+            shared_animations.count()
+        );
+    }
+
     // this should never happen, since every time we update the image file we
     // need to update the metadata
     if indices.image.len() > indices.metadata.len() {
@@ -242,11 +242,31 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
     let need_upload =
         !indices.image.is_empty() || !indices.metadata.is_empty() || !indices.animation.is_empty();
 
+    // the storage is not initialized in a dry run, since it can require funding
+    if is_dry_run() {
+        plan_asset_uploads(&asset_pairs, &indices);
+        return Ok(());
+    }
+
     // ready to upload data
 
     let mut errors = Vec::new();
 
     if need_upload {
+        run_hook(
+            config_data.hooks.as_ref(),
+            HookEvent::PreUpload,
+            json!({
+                "assetsDir": args.assets_dir,
+                "cache": args.cache,
+                "uploadMethod": config_data.upload_method.to_string(),
+                "items": asset_pairs.len(),
+                "images": indices.image.len(),
+                "animations": indices.animation.len(),
+                "metadata": indices.metadata.len(),
+            }),
+        )?;
+
         let total_steps = if indices.animation.is_empty() { 4 } else { 5 };
         println!(
             "\n{} {}Initializing upload",
@@ -261,16 +281,29 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
 
         pb.finish_with_message("Connected");
 
-        storage
-            .prepare(
+        let asset_indices = vec![
+            (DataType::Image, indices.image.as_slice()),
+            (DataType::Animation, indices.animation.as_slice()),
+            (DataType::Metadata, indices.metadata.as_slice()),
+        ];
+
+        if let Some(lamports) = storage
+            .estimate_cost(
                 &sugar_config,
+                &config_data,
                 &asset_pairs,
-                vec![
-                    (DataType::Image, &indices.image),
-                    (DataType::Animation, &indices.animation),
-                    (DataType::Metadata, &indices.metadata),
-                ],
+                asset_indices.clone(),
             )
+            .await?
+        {
+            println!(
+                "Estimated storage cost: ◎ {}",
+                lamports as f64 / LAMPORTS_PER_SOL as f64
+            );
+        }
+
+        storage
+            .prepare(&sugar_config, &config_data, &asset_pairs, asset_indices)
             .await?;
 
         // clear the interruption handler value ahead of the upload
@@ -291,6 +324,7 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
             errors.extend(
                 upload_data(
                     &sugar_config,
+                    &config_data,
                     &asset_pairs,
                     &mut cache,
                     &indices.image,
@@ -301,10 +335,14 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
                 .await?,
             );
 
+            apply_shared_media(&mut cache, &shared_images, DataType::Image);
+
             // updates the list of metadata indices since the image upload
             // might fail - removes any index that the image upload failed
             if !indices.metadata.is_empty() {
-                for index in indices.image {
+                let shared = shared_images.pending.iter().map(|(index, _)| *index);
+
+                for index in indices.image.into_iter().chain(shared) {
                     let item = cache.items.get(&index.to_string()).unwrap();
 
                     if item.image_link.is_empty() {
@@ -327,6 +365,7 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
             errors.extend(
                 upload_data(
                     &sugar_config,
+                    &config_data,
                     &asset_pairs,
                     &mut cache,
                     &indices.animation,
@@ -337,10 +376,14 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
                 .await?,
             );
 
+            apply_shared_media(&mut cache, &shared_animations, DataType::Animation);
+
             // updates the list of metadata indices since the image upload
             // might fail - removes any index that the animation upload failed
             if !indices.metadata.is_empty() {
-                for index in indices.animation {
+                let shared = shared_animations.pending.iter().map(|(index, _)| *index);
+
+                for index in indices.animation.into_iter().chain(shared) {
                     let item = cache.items.get(&index.to_string()).unwrap();
 
                     if item.animation_link.is_none() {
@@ -368,6 +411,7 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
             errors.extend(
                 upload_data(
                     &sugar_config,
+                    &config_data,
                     &asset_pairs,
                     &mut cache,
                     &indices.metadata,
@@ -409,14 +453,21 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
     // sanity check
 
     let mut count = 0;
-
-    for (index, item) in &cache.items.0 {
-        let asset_pair = asset_pairs.get(&isize::from_str(index)?).ok_or_else(|| {
-            anyhow!(
-                "cache item {} does not have a corresponding asset pair",
-                index
-            )
-        })?;
+    let mut uploaded = false;
+    let mut incomplete = Vec::new();
+
+    for (index, item) in cache.items.0.iter_mut() {
+        let asset_pair = match asset_pairs.get(&isize::from_str(index)?) {
+            Some(asset_pair) => asset_pair,
+            // the collection metadata was uploaded beforehand (collection uri)
+            None if index == COLLECTION_CACHE_KEY => continue,
+            None => {
+                return Err(anyhow!(
+                    "cache item {} does not have a corresponding asset pair",
+                    index
+                ))
+            }
+        };
 
         // we first check that the asset has an animation file; if there is one,
         // we need to check that the cache item has the link and the link is not empty
@@ -435,9 +486,20 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
         if !(item.image_link.is_empty() || item.metadata_link.is_empty() || missing_animation_link)
         {
             count += 1;
+
+            if item.status == ItemStatus::Pending {
+                item.set_status(ItemStatus::Uploaded);
+                uploaded = true;
+            }
+        } else {
+            incomplete.push(isize::from_str(index)?);
         }
     }
 
+    if uploaded {
+        cache.sync_file()?;
+    }
+
     println!(
         "\n{}",
         style(format!(
@@ -449,20 +511,38 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
     );
 
     if count != asset_pairs.len() {
-        let message = if !errors.is_empty() {
+        let error_count = errors.len();
+        let mut unique = HashSet::new();
+
+        for err in errors {
+            unique.insert(err.to_string());
+        }
+
+        update_failures(
+            &args.failures,
+            &args.config,
+            &args.cache,
+            Some(&args.assets_dir),
+            FailureStage::Upload,
+            Some(Failure {
+                stage: FailureStage::Upload,
+                indices: incomplete,
+                error: if unique.is_empty() {
+                    "Not all files were uploaded.".to_string()
+                } else {
+                    unique.iter().cloned().collect::<Vec<String>>().join("; ")
+                },
+            }),
+        )?;
+
+        let message = if !unique.is_empty() {
             let mut message = String::new();
             write!(
                 message,
                 "Failed to upload all files, {0} error(s) occurred:",
-                errors.len()
+                error_count
             )?;
 
-            let mut unique = HashSet::new();
-
-            for err in errors {
-                unique.insert(err.to_string());
-            }
-
             for u in unique {
                 message.push_str(&style("\n=> ").dim().to_string());
                 message.push_str(&u);
@@ -476,12 +556,336 @@ pub async fn process_upload(args: UploadArgs) -> Result<()> {
         return Err(UploadError::Incomplete(message).into());
     }
 
+    update_failures(
+        &args.failures,
+        &args.config,
+        &args.cache,
+        Some(&args.assets_dir),
+        FailureStage::Upload,
+        None,
+    )?;
+
+    if args.verify {
+        println!(
+            "\n{}",
+            style(match args.verify_sample {
+                Some(sample) => format!("Verifying a sample of {} uploaded asset(s)...", sample),
+                None => "Verifying all uploaded assets...".to_string(),
+            })
+            .dim()
+        );
+
+        let (total, failures) =
+            verify_upload(&asset_pairs, &cache, &config_data, args.verify_sample).await?;
+
+        if !failures.is_empty() {
+            let mut message = format!(
+                "{} of {} uploaded file(s) do not match the local files:",
+                failures.len(),
+                total
+            );
+
+            for failure in &failures {
+                message.push_str(&style("\n=> ").dim().to_string());
+                message.push_str(&failure.to_string());
+            }
+
+            return Err(UploadError::Incomplete(message).into());
+        }
+
+        println!(
+            "{}",
+            style(format!("{} uploaded file(s) verified.", total)).bold()
+        );
+    }
+
     Ok(())
 }
 
+/// Applies the collection settings of the config to the collection asset pair.
+fn apply_collection_config(
+    collection: &CollectionConfig,
+    asset_pairs: &mut HashMap<isize, AssetPair>,
+) -> Result<()> {
+    if collection.uri.is_some() {
+        // the collection files are not uploaded when the metadata is already online
+        if asset_pairs.remove(&COLLECTION_INDEX).is_some() {
+            println!(
+                "{}",
+                style("Collection uri set in the config, the collection files are ignored.").dim()
+            );
+        }
+        return Ok(());
+    }
+
+    let pair = match asset_pairs.get_mut(&COLLECTION_INDEX) {
+        Some(pair) => pair,
+        None if collection.image.is_some() || collection.name.is_some() => {
+            return Err(anyhow!(
+                "Collection settings found in the config but there is no collection metadata \
+                file, set 'collectionPath' or add 'collection.json' to the assets folder."
+            ))
+        }
+        None => return Ok(()),
+    };
+
+    if let Some(name) = &collection.name {
+        pair.name = name.clone();
+    }
+
+    if let Some(image) = &collection.image {
+        if !Path::new(image).is_file() {
+            return Err(anyhow!("Couldn't find the collection image '{}'.", image));
+        }
+
+        let hashes = hash_files(&[image.clone()])?;
+        pair.image = image.clone();
+        pair.image_hash = hashes
+            .get(image)
+            .cloned()
+            .ok_or_else(|| anyhow!("Missing hash for file '{}'", image))?;
+    }
+
+    Ok(())
+}
+
+/// Adds the collection item for a collection metadata uploaded beforehand; the
+/// deployment state of the item is kept while the uri does not change.
+fn set_collection_uri(cache: &mut Cache, collection: &CollectionConfig, uri: &str) -> Result<()> {
+    let name = collection
+        .name
+        .clone()
+        .ok_or_else(|| anyhow!("The collection name is required with the collection uri."))?;
+
+    if let Some(item) = cache.items.get_mut(COLLECTION_CACHE_KEY) {
+        if item.metadata_link == uri && item.name == name {
+            return Ok(());
+        }
+    }
+
+    let mut item = CacheItem {
+        name,
+        image_hash: String::new(),
+        image_link: String::new(),
+        metadata_hash: String::new(),
+        metadata_link: uri.to_string(),
+        on_chain: false,
+        animation_hash: None,
+        animation_link: None,
+        status: ItemStatus::Pending,
+        timestamps: ItemTimestamps::default(),
+    };
+    item.set_status(ItemStatus::Uploaded);
+    cache.items.insert(COLLECTION_CACHE_KEY.to_string(), item);
+
+    Ok(())
+}
+
+/// Restricts the upload to the selected indices, which are uploaded again even if
+/// their files did not change. The cache items outside the selection are restored.
+fn select_upload_indices(
+    asset_pairs: &HashMap<isize, AssetPair>,
+    cache: &mut Cache,
+    indices: &mut AssetType,
+    selection: &IndexSelection,
+    unselected: Vec<(String, CacheItem)>,
+) {
+    for (key, item) in unselected {
+        cache.items.insert(key, item);
+    }
+
+    let selected = |index: &isize| *index >= 0 && selection.contains(*index as u64);
+
+    indices.image.retain(selected);
+    indices.animation.retain(selected);
+    indices.metadata.retain(selected);
+
+    for (index, pair) in asset_pairs.iter().filter(|(index, _)| selected(index)) {
+        let item = cache.items.get_mut(&index.to_string()).unwrap();
+        item.image_link = String::new();
+        item.metadata_link = String::new();
+        item.on_chain = false;
+        item.set_status(ItemStatus::Pending);
+
+        if !indices.image.contains(index) {
+            indices.image.push(*index);
+        }
+
+        if pair.animation.is_some() {
+            item.animation_link = None;
+
+            if !indices.animation.contains(index) {
+                indices.animation.push(*index);
+            }
+        }
+
+        if !indices.metadata.contains(index) {
+            indices.metadata.push(*index);
+        }
+    }
+}
+
+/// Records the files that would be uploaded in the plan of a dry run.
+fn plan_asset_uploads(asset_pairs: &HashMap<isize, AssetPair>, indices: &AssetType) {
+    let file_size = |path: &str| {
+        std::fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .unwrap_or_default()
+    };
+
+    let uploads: [(&str, &Vec<isize>, fn(&AssetPair) -> Option<&str>); 3] = [
+        ("images", &indices.image, |pair| Some(pair.image.as_str())),
+        ("animations", &indices.animation, |pair| {
+            pair.animation.as_deref()
+        }),
+        ("metadata", &indices.metadata, |pair| {
+            Some(pair.metadata.as_str())
+        }),
+    ];
+
+    for (description, indices, path) in uploads {
+        if indices.is_empty() {
+            continue;
+        }
+
+        let bytes = indices
+            .iter()
+            .filter_map(|index| asset_pairs.get(index).and_then(path))
+            .map(file_size)
+            .sum();
+
+        plan_upload(description, indices.len(), bytes);
+    }
+}
+
+/// Return the indices of the assets that need to be uploaded, updating the
+/// cache items to reflect the current state of the asset files.
+pub fn get_upload_indices(
+    asset_pairs: &HashMap<isize, AssetPair>,
+    cache: &mut Cache,
+) -> Result<AssetType> {
+    // list of indices to upload
+    let mut indices = AssetType {
+        image: Vec::new(),
+        metadata: Vec::new(),
+        animation: Vec::new(),
+    };
+
+    for (index, pair) in asset_pairs {
+        // checks if we have complete URIs in the metadata file;
+        // if true, no upload is necessary and we will use the
+        // existing URIs
+
+        let m: Metadata = {
+            let m = OpenOptions::new()
+                .read(true)
+                .open(&pair.metadata)
+                .map_err(|e| {
+                    anyhow!(
+                        "Failed to read metadata file '{}' with error: {}",
+                        &pair.metadata,
+                        e
+                    )
+                })?;
+            serde_json::from_reader(&m)?
+        };
+
+        // retrieve the existing image uri from the metadata
+        let existing_image = if is_complete_uri(&m.image) {
+            m.image.clone()
+        } else {
+            String::new()
+        };
+
+        // retrieve the existing animation uri from the metadata
+        let existing_animation = match m.animation_url {
+            Some(ref url) => {
+                if is_complete_uri(url) {
+                    url.clone()
+                } else {
+                    String::new()
+                }
+            }
+            None => String::new(),
+        };
+
+        match cache.items.get_mut(&index.to_string()) {
+            Some(item) => {
+                let image_changed = (!item.image_hash.eq(&pair.image_hash)
+                    || item.image_link.is_empty())
+                    && existing_image.is_empty();
+
+                let animation_changed = (!item.animation_hash.eq(&pair.animation_hash)
+                    || (item.animation_link.is_none() && pair.animation.is_some()))
+                    && existing_animation.is_empty();
+
+                let metadata_changed =
+                    !item.metadata_hash.eq(&pair.metadata_hash) || item.metadata_link.is_empty();
+
+                if image_changed {
+                    // triggers the image upload
+                    item.image_hash = pair.image_hash.clone();
+                    item.image_link = String::new();
+                    indices.image.push(*index);
+                } else if !existing_image.is_empty() {
+                    item.image_hash = pair.image_hash.clone();
+                    item.image_link = existing_image;
+                }
+
+                if animation_changed {
+                    // triggers the animation upload
+                    item.animation_hash = pair.animation_hash.clone();
+                    item.animation_link = None;
+                    indices.animation.push(*index);
+                } else if !existing_animation.is_empty() {
+                    item.animation_hash = pair.animation_hash.clone();
+                    item.animation_link = Some(existing_animation);
+                }
+
+                if metadata_changed || image_changed || animation_changed {
+                    // triggers the metadata upload
+                    item.metadata_hash = pair.metadata_hash.clone();
+                    item.metadata_link = String::new();
+                    item.on_chain = false;
+                    item.set_status(ItemStatus::Pending);
+                    // we need to upload metadata only
+                    indices.metadata.push(*index);
+                }
+            }
+            None => {
+                let mut item = pair.clone().into_cache_item();
+
+                // check if we need to upload the image
+                if existing_image.is_empty() {
+                    indices.image.push(*index);
+                } else {
+                    item.image_hash = pair.image_hash.clone();
+                    item.image_link = existing_image;
+                }
+
+                // and we might need to upload the animation
+                if pair.animation.is_some() {
+                    if existing_animation.is_empty() {
+                        indices.animation.push(*index);
+                    } else {
+                        item.animation_hash = pair.animation_hash.clone();
+                        item.animation_link = Some(existing_animation);
+                    }
+                }
+
+                indices.metadata.push(*index);
+                cache.items.insert(index.to_string(), item);
+            }
+        }
+    }
+
+    Ok(indices)
+}
+
 /// Upload the data to the selected storage.
 async fn upload_data(
     sugar_config: &SugarConfig,
+    config_data: &ConfigData,
     asset_pairs: &HashMap<isize, AssetPair>,
     cache: &mut Cache,
     indices: &[isize],
@@ -521,7 +925,7 @@ async fn upload_data(
 
         extension = String::from(ext);
 
-        paths.push(file_path);
+        paths.push((*index, file_path));
     }
 
     let content_type = match data_type {
@@ -534,19 +938,26 @@ async fn upload_data(
 
     println!("\nSending data: (Ctrl+C to abort)");
 
-    let pb = progress_bar_with_style(paths.len() as u64);
-
     let mut assets = Vec::new();
 
-    for file_path in paths {
+    for (index, file_path) in paths {
         // path to the media/metadata file
         let path = Path::new(&file_path);
-        let file_name = String::from(
-            path.file_name()
-                .and_then(OsStr::to_str)
-                .expect("Filed to get file name."),
-        );
-        let (asset_id, cache_item) = get_cache_item(path, cache)?;
+        // the collection is always uploaded as 'collection.<ext>', independently
+        // of where its files are located
+        let file_name = if index == COLLECTION_INDEX {
+            format!(
+                "collection.{}",
+                path.extension().and_then(OsStr::to_str).unwrap_or_default()
+            )
+        } else {
+            String::from(
+                path.file_name()
+                    .and_then(OsStr::to_str)
+                    .expect("Filed to get file name."),
+            )
+        };
+        let (asset_id, cache_item) = get_cache_item(index, cache)?;
 
         let content = match data_type {
             // replaces the media link without modifying the original file to avoid
@@ -555,6 +966,7 @@ async fn upload_data(
                 &file_path,
                 &cache_item.image_link,
                 &cache_item.animation_link,
+                &MetadataOverrides::new(config_data, index),
             )?,
             _ => file_path.clone(),
         };
@@ -568,6 +980,29 @@ async fn upload_data(
         });
     }
 
+    // the remaining data is reported with the time estimated from previous uploads
+    let total_bytes: u64 = assets.iter().map(asset_size).sum();
+    let estimate = load_throughput(&config_data.upload_method).map(|throughput| {
+        format!(
+            " (about {} at {}/s)",
+            format_duration(throughput.estimate(total_bytes)),
+            format_bytes(throughput.bytes_per_second as u64)
+        )
+    });
+    println!(
+        "{}",
+        style(format!(
+            "{} file(s), {} remaining{}",
+            assets.len(),
+            format_bytes(total_bytes),
+            estimate.unwrap_or_default()
+        ))
+        .dim()
+    );
+
+    let pb = progress_bar_with_style(assets.len() as u64);
+    let started = Instant::now();
+
     let errors = uploader
         .upload(
             sugar_config,
@@ -575,7 +1010,7 @@ async fn upload_data(
             data_type,
             &mut assets,
             &pb,
-            interrupted,
+            interrupted.clone(),
         )
         .await?;
 
@@ -583,6 +1018,16 @@ async fn upload_data(
         pb.abandon_with_message(format!("{}", style("Upload failed ").red().bold()));
     } else {
         pb.finish_with_message(format!("{}", style("Upload successful ").green().bold()));
+
+        // only complete batches are used to measure the throughput of the provider
+        if !interrupted.load(Ordering::SeqCst) && config_data.upload_method != UploadMethod::Custom
+        {
+            if let Err(err) =
+                save_throughput(&config_data.upload_method, total_bytes, started.elapsed())
+            {
+                warn!("Failed to save the upload throughput: {}", err);
+            }
+        }
     }
 
     // makes sure the cache file is updated
@@ -0,0 +1,184 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{
+    common::*,
+    config::UploadMethod,
+    journal::STATE_DIR,
+    upload::{AssetInfo, DataType},
+};
+
+/// Name of the file (in the state directory) with the measured upload throughput.
+const UPLOAD_STATS_FILE: &str = "upload-stats.json";
+// Weight of the latest batch in the average throughput of a provider.
+const BATCH_WEIGHT: f64 = 0.3;
+// Batches smaller than this (bytes) are too short to give a meaningful throughput.
+const MIN_SAMPLE_BYTES: u64 = 64 * 1024;
+
+/// Throughput measured for an upload method across previous uploads.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Throughput {
+    /// Moving average of the bytes uploaded per second.
+    pub bytes_per_second: f64,
+    /// Number of batches used to compute the average.
+    pub batches: u64,
+}
+
+impl Throughput {
+    /// Adds the throughput of a batch to the average, giving more weight to recent
+    /// batches since the conditions of the provider change over time.
+    pub fn record(previous: Option<Throughput>, bytes: u64, elapsed: Duration) -> Throughput {
+        let sample = bytes as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+
+        match previous {
+            Some(previous) => Throughput {
+                bytes_per_second: previous.bytes_per_second * (1.0 - BATCH_WEIGHT)
+                    + sample * BATCH_WEIGHT,
+                batches: previous.batches + 1,
+            },
+            None => Throughput {
+                bytes_per_second: sample,
+                batches: 1,
+            },
+        }
+    }
+
+    /// Estimated time to upload `bytes`.
+    pub fn estimate(&self, bytes: u64) -> Duration {
+        Duration::from_secs_f64(bytes as f64 / self.bytes_per_second.max(f64::EPSILON))
+    }
+}
+
+fn load_stats() -> HashMap<String, Throughput> {
+    fs::read_to_string(Path::new(STATE_DIR).join(UPLOAD_STATS_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Returns the throughput measured in previous uploads with the upload method.
+pub fn load_throughput(upload_method: &UploadMethod) -> Option<Throughput> {
+    load_stats().get(&upload_method.to_string()).copied()
+}
+
+/// Adds the throughput of a batch to the stats of the upload method.
+pub fn save_throughput(upload_method: &UploadMethod, bytes: u64, elapsed: Duration) -> Result<()> {
+    if bytes < MIN_SAMPLE_BYTES {
+        return Ok(());
+    }
+
+    let mut stats = load_stats();
+    let key = upload_method.to_string();
+    let throughput = Throughput::record(stats.get(&key).copied(), bytes, elapsed);
+    stats.insert(key, throughput);
+
+    fs::create_dir_all(STATE_DIR)?;
+    fs::write(
+        Path::new(STATE_DIR).join(UPLOAD_STATS_FILE),
+        serde_json::to_string_pretty(&stats)?,
+    )?;
+
+    Ok(())
+}
+
+/// Number of bytes sent to upload the asset.
+pub fn asset_size(asset: &AssetInfo) -> u64 {
+    match asset.data_type {
+        DataType::Metadata => asset.content.len() as u64,
+        _ => fs::metadata(&asset.content)
+            .map(|metadata| metadata.len())
+            .unwrap_or_default(),
+    }
+}
+
+/// Keeps track of the bytes uploaded in the current batch to show the transfer
+/// rate and the remaining time.
+pub struct ThroughputTracker {
+    started: Instant,
+    total: u64,
+    sent: u64,
+}
+
+impl ThroughputTracker {
+    pub fn new(total: u64) -> Self {
+        Self {
+            started: Instant::now(),
+            total,
+            sent: 0,
+        }
+    }
+
+    pub fn add(&mut self, bytes: u64) {
+        self.sent += bytes;
+    }
+
+    /// Progress message with the current rate and the estimated remaining time.
+    pub fn message(&self) -> String {
+        let elapsed = self.started.elapsed();
+
+        if self.sent == 0 || elapsed.is_zero() {
+            return String::new();
+        }
+
+        let throughput = Throughput::record(None, self.sent, elapsed);
+
+        format!(
+            "{}/s, ETA {} ",
+            format_bytes(throughput.bytes_per_second as u64),
+            format_duration(throughput.estimate(self.total.saturating_sub(self.sent)))
+        )
+    }
+}
+
+/// Formats a number of bytes using decimal units (e.g. "12.4 MB").
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = "";
+
+    for next in UNITS {
+        if value < 1000.0 {
+            break;
+        }
+        value /= 1000.0;
+        unit = next;
+    }
+
+    format!("{value:.1} {unit}")
+}
+
+/// Formats a duration as "hh:mm:ss".
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
+
+#[test]
+fn test_throughput_estimates() {
+    let first = Throughput::record(None, 10_000_000, Duration::from_secs(10));
+    assert_eq!(first.bytes_per_second, 1_000_000.0);
+    assert_eq!(first.estimate(60_000_000), Duration::from_secs(60));
+
+    let second = Throughput::record(Some(first), 20_000_000, Duration::from_secs(10));
+    assert_eq!(second.batches, 2);
+    assert!((second.bytes_per_second - 1_300_000.0).abs() < 1e-6);
+
+    assert_eq!(format_bytes(999), "999 B");
+    assert_eq!(format_bytes(12_400_000), "12.4 MB");
+    assert_eq!(format_duration(Duration::from_secs(3725)), "01:02:05");
+}
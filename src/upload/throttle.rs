@@ -0,0 +1,144 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use tokio::time::sleep;
+
+use crate::common::*;
+
+/// Default number of times each upload request is retried.
+pub const DEFAULT_MAX_RETRIES: usize = 3;
+// Delay (ms) before the first retry, doubled on every attempt.
+const BASE_BACKOFF: u64 = 1000;
+// Maximum delay (ms) between retries.
+const MAX_BACKOFF: u64 = 30000;
+
+lazy_static! {
+    static ref BANDWIDTH: RwLock<Option<BandwidthLimiter>> = RwLock::new(None);
+}
+
+static MAX_RETRIES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_RETRIES);
+
+/// Limits the average upload rate shared by all concurrent uploads.
+struct BandwidthLimiter {
+    bytes_per_second: f64,
+    /// Instant when the next upload can start.
+    next: Mutex<Instant>,
+}
+
+impl BandwidthLimiter {
+    fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second as f64,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reserves the time to send `bytes`, returning how long to wait before sending them.
+    fn reserve(&self, bytes: usize) -> Duration {
+        let mut next = self.next.lock().unwrap();
+        let now = Instant::now();
+        let start = (*next).max(now);
+
+        *next = start + Duration::from_secs_f64(bytes as f64 / self.bytes_per_second);
+
+        start - now
+    }
+}
+
+/// Sets the upload limits of the HTTP-based upload methods: the maximum bandwidth
+/// (in KB/s) and the number of retries of each request.
+pub fn set_upload_limits(max_bandwidth: Option<u64>, max_retries: usize) {
+    *BANDWIDTH.write().unwrap() = max_bandwidth
+        .filter(|kbps| *kbps > 0)
+        .map(|kbps| BandwidthLimiter::new(kbps * 1024));
+    MAX_RETRIES.store(max_retries, Ordering::SeqCst);
+}
+
+/// Waits until `bytes` can be sent without exceeding the maximum bandwidth.
+pub async fn throttle_upload(bytes: usize) {
+    let wait = match BANDWIDTH.read().unwrap().as_ref() {
+        Some(limiter) => limiter.reserve(bytes),
+        None => return,
+    };
+
+    if !wait.is_zero() {
+        sleep(wait).await;
+    }
+}
+
+/// Delay before the retry `attempt` (starting at 0), with a random jitter to avoid
+/// concurrent uploads retrying at the same time.
+fn backoff(attempt: u32) -> Duration {
+    let delay = BASE_BACKOFF
+        .saturating_mul(2u64.saturating_pow(attempt))
+        .min(MAX_BACKOFF);
+    let jitter = rand::thread_rng().gen_range(0..BASE_BACKOFF / 4);
+
+    Duration::from_millis(delay + jitter)
+}
+
+/// Server errors and rate limit responses are transient and worth retrying.
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Sends an upload request of `bytes`, retrying connection errors and transient
+/// responses with exponential backoff. The request is rebuilt on every attempt since
+/// multipart bodies cannot be cloned.
+pub async fn send_with_retry<F>(bytes: usize, request: F) -> Result<Response>
+where
+    F: Fn() -> Result<RequestBuilder>,
+{
+    let max_retries = MAX_RETRIES.load(Ordering::SeqCst) as u32;
+    let mut attempt = 0;
+
+    loop {
+        throttle_upload(bytes).await;
+
+        match request()?.send().await {
+            Ok(response) if !is_retryable(response.status()) || attempt >= max_retries => {
+                return Ok(response)
+            }
+            Err(error) if attempt >= max_retries => return Err(error.into()),
+            Ok(response) => warn!(
+                "Upload request failed ({}), retrying ({}/{})",
+                response.status(),
+                attempt + 1,
+                max_retries
+            ),
+            Err(error) => warn!(
+                "Upload request failed ({}), retrying ({}/{})",
+                error,
+                attempt + 1,
+                max_retries
+            ),
+        }
+
+        sleep(backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+#[test]
+fn test_upload_backoff_and_bandwidth() {
+    assert!(backoff(0) >= Duration::from_millis(BASE_BACKOFF));
+    assert!(backoff(2) >= Duration::from_millis(4 * BASE_BACKOFF));
+    assert!(backoff(10) < Duration::from_millis(MAX_BACKOFF + BASE_BACKOFF));
+
+    assert!(is_retryable(StatusCode::BAD_GATEWAY));
+    assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+    assert!(!is_retryable(StatusCode::UNAUTHORIZED));
+
+    // 1KB/s: the second upload waits for the first one to be sent
+    let limiter = BandwidthLimiter::new(1024);
+    assert!(limiter.reserve(512).is_zero());
+    assert!(limiter.reserve(512) > Duration::from_millis(400));
+}
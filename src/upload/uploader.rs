@@ -3,24 +3,26 @@ use std::{
     collections::HashMap,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, RwLock,
     },
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use console::style;
 use futures::future::select_all;
 pub use indicatif::ProgressBar;
+use lazy_static::lazy_static;
 use tokio::task::JoinHandle;
 
 use crate::{
     cache::Cache,
-    config::{ConfigData, SugarConfig, UploadMethod},
+    config::{ConfigData, ConfigError, SugarConfig, UploadMethod},
     constants::PARALLEL_LIMIT,
     upload::{
         assets::{AssetPair, DataType},
         methods::*,
+        stats::{asset_size, ThroughputTracker},
         UploadError,
     },
 };
@@ -28,6 +30,11 @@ use crate::{
 // Size of the mock media URI for cost calculations.
 pub const MOCK_URI_SIZE: usize = 100;
 
+lazy_static! {
+    static ref STORAGE_REGISTRY: RwLock<HashMap<String, StorageFactory>> =
+        RwLock::new(HashMap::new());
+}
+
 /// Struct representing an asset ready for upload. An `AssetInfo` can represent
 /// a physical file, in which case the `content` will correspond to the name
 /// of the file; or an in-memory asset, in which case the `content` will correspond
@@ -64,6 +71,7 @@ pub trait Prepare {
     /// # Arguments
     ///
     /// * `sugar_config` - The current sugar configuration
+    /// * `config_data` - The config, with the overrides written into the metadata files
     /// * `asset_pairs` - Mapping of `index` to an `AssetPair`
     /// * `asset_indices` - Vector with the information of which asset pair indices will be upload grouped by type.
     ///
@@ -73,6 +81,7 @@ pub trait Prepare {
     async fn prepare(
         &self,
         sugar_config: &SugarConfig,
+        config_data: &ConfigData,
         asset_pairs: &HashMap<isize, AssetPair>,
         asset_indices: Vec<(DataType, &[isize])>,
     ) -> Result<()>;
@@ -200,6 +209,12 @@ impl<T: ParallelUploader> Uploader for T {
         let limit = self.parallel_limit();
         let mut handles = Vec::new();
 
+        let sizes: HashMap<String, u64> = assets
+            .iter()
+            .map(|asset| (asset.asset_id.clone(), asset_size(asset)))
+            .collect();
+        let mut tracker = ThroughputTracker::new(sizes.values().sum());
+
         for task in assets.drain(0..cmp::min(assets.len(), limit)) {
             handles.push(self.upload_asset(task));
         }
@@ -222,7 +237,9 @@ impl<T: ParallelUploader> Uploader for T {
                             DataType::Metadata => item.metadata_link = link,
                             DataType::Animation => item.animation_link = Some(link),
                         }
-                        // updates the progress bar
+                        // updates the progress bar with the transfer rate
+                        tracker.add(sizes.get(&val.0).copied().unwrap_or_default());
+                        progress.set_message(tracker.message());
                         progress.inc(1);
                     } else {
                         // user will need to retry the upload
@@ -268,29 +285,138 @@ impl<T: ParallelUploader> Uploader for T {
     }
 }
 
+/// Storage provider used by the upload.
+///
+/// Built-in upload methods implement this trait, and providers that sugar does not
+/// implement (e.g., an internal corporate storage) can be added by implementing it
+/// and registering a [`StorageFactory`] with [`register_storage`] before the config
+/// file is parsed: the provider is then selected by using its name as the `uploadMethod`
+/// of the config file, and names of unregistered providers are rejected.
+///
+/// The [`prepare`](Prepare::prepare) and [`upload`](Uploader::upload) steps are
+/// inherited from the [`Uploader`] trait (or its [`ParallelUploader`] implementation).
+#[async_trait]
+pub trait StorageUploader: Uploader + Send + Sync {
+    /// Returns the estimated cost (in lamports) of uploading the specified files, or
+    /// `None` if the provider is not paid from the wallet. It must not have any side
+    /// effect, since it is called before [`prepare`](Prepare::prepare) funds the upload.
+    async fn estimate_cost(
+        &self,
+        _sugar_config: &SugarConfig,
+        _config_data: &ConfigData,
+        _asset_pairs: &HashMap<isize, AssetPair>,
+        _asset_indices: Vec<(DataType, &[isize])>,
+    ) -> Result<Option<u64>> {
+        Ok(None)
+    }
+}
+
+/// Function creating the uploader of a registered storage provider from the config.
+/// Initialization that needs to be async can be done in [`prepare`](Prepare::prepare).
+pub type StorageFactory = fn(&SugarConfig, &ConfigData) -> Result<Box<dyn StorageUploader>>;
+
+/// Registers a storage provider under `name`, replacing any provider previously
+/// registered with the same name. Built-in upload methods cannot be replaced.
+pub fn register_storage(name: &str, factory: StorageFactory) -> Result<()> {
+    if !matches!(UploadMethod::from_name(name), UploadMethod::Plugin(_)) {
+        return Err(anyhow!("'{}' is a built-in upload method.", name));
+    }
+
+    STORAGE_REGISTRY
+        .write()
+        .unwrap()
+        .insert(name.to_string(), factory);
+
+    Ok(())
+}
+
+/// Storage providers added to the built-in upload methods, registered by
+/// [`register_storages`] when sugar starts. Forks add their providers here, e.g.
+/// `("corp_storage", CorpStorage::factory)`.
+const STORAGES: &[(&str, StorageFactory)] = &[];
+
+/// Registers the storage providers of [`STORAGES`]; it must run before the config file
+/// is parsed, since unregistered upload methods are rejected.
+pub fn register_storages() -> Result<()> {
+    for (name, factory) in STORAGES {
+        register_storage(name, *factory)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the names of the registered storage providers, sorted.
+pub fn registered_storages() -> Vec<String> {
+    let mut names: Vec<String> = STORAGE_REGISTRY.read().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+}
+
 /// Returns a new uploader trait object based on the configuration `uploadMethod`.
 ///
 /// This function acts as a *factory* function for uploader objects.
 pub async fn initialize(
     sugar_config: &SugarConfig,
     config_data: &ConfigData,
-) -> Result<Box<dyn Uploader>> {
-    Ok(match config_data.upload_method {
-        UploadMethod::AWS => Box::new(AWSMethod::new(config_data).await?) as Box<dyn Uploader>,
-        UploadMethod::Bundlr => {
-            Box::new(BundlrMethod::new(sugar_config, config_data).await?) as Box<dyn Uploader>
+) -> Result<Box<dyn StorageUploader>> {
+    Ok(match &config_data.upload_method {
+        UploadMethod::AWS => {
+            Box::new(AWSMethod::new(config_data).await?) as Box<dyn StorageUploader>
         }
+        UploadMethod::Bundlr => Box::new(BundlrMethod::new(sugar_config, config_data).await?)
+            as Box<dyn StorageUploader>,
         UploadMethod::NftStorage => {
-            Box::new(NftStorageMethod::new(config_data).await?) as Box<dyn Uploader>
-        }
-        UploadMethod::SHDW => {
-            Box::new(shdw::SHDWMethod::new(sugar_config, config_data).await?) as Box<dyn Uploader>
+            Box::new(NftStorageMethod::new(config_data).await?) as Box<dyn StorageUploader>
         }
+        UploadMethod::SHDW => Box::new(shdw::SHDWMethod::new(sugar_config, config_data).await?)
+            as Box<dyn StorageUploader>,
         UploadMethod::Pinata => {
-            Box::new(pinata::PinataMethod::new(config_data).await?) as Box<dyn Uploader>
+            Box::new(pinata::PinataMethod::new(config_data).await?) as Box<dyn StorageUploader>
+        }
+        UploadMethod::PinningService => {
+            Box::new(pinning_service::PinningServiceMethod::new(config_data).await?)
+                as Box<dyn StorageUploader>
         }
         UploadMethod::Sdrive => {
-            Box::new(sdrive::SdriveMethod::new(config_data).await?) as Box<dyn Uploader>
+            Box::new(sdrive::SdriveMethod::new(config_data).await?) as Box<dyn StorageUploader>
+        }
+        UploadMethod::Custom => {
+            Box::new(template::TemplateMethod::new(config_data)?) as Box<dyn StorageUploader>
+        }
+        UploadMethod::Plugin(name) => {
+            let factory = STORAGE_REGISTRY
+                .read()
+                .unwrap()
+                .get(name)
+                .copied()
+                .ok_or_else(|| ConfigError::InvalidUploadMethod(name.clone()))?;
+
+            factory(sugar_config, config_data)?
         }
     })
 }
+
+#[test]
+fn test_storage_registry() {
+    assert_eq!(UploadMethod::from_name("aws"), UploadMethod::AWS);
+    assert_eq!(
+        UploadMethod::from_name("corp_storage"),
+        UploadMethod::Plugin("corp_storage".to_string())
+    );
+
+    assert!(register_storage("bundlr", |_, _| Err(anyhow!("not available"))).is_err());
+    assert!(register_storage("corp_storage", |_, _| Err(anyhow!("not available"))).is_ok());
+    assert!(registered_storages().contains(&"corp_storage".to_string()));
+    assert!(register_storages().is_ok());
+
+    // only registered storage providers are accepted in the config file
+    assert_eq!(
+        serde_json::from_str::<UploadMethod>("\"corp_storage\"").unwrap(),
+        UploadMethod::Plugin("corp_storage".to_string())
+    );
+    assert!(serde_json::from_str::<UploadMethod>("\"bundlrr\"").is_err());
+    assert_eq!(
+        serde_json::from_str::<UploadMethod>("\"shdw\"").unwrap(),
+        UploadMethod::SHDW
+    );
+}
@@ -0,0 +1,194 @@
+use std::fmt::{self, Display};
+
+use data_encoding::HEXLOWER;
+use futures::{stream, StreamExt};
+use rand::seq::SliceRandom;
+use reqwest::Client;
+use ring::digest::{digest, SHA256};
+
+use crate::{
+    cache::Cache, common::*, config::ConfigData, upload::*, utils::progress_bar_with_style,
+};
+
+/// An uploaded file to compare with its local content.
+struct VerifyTarget {
+    index: String,
+    data_type: DataType,
+    link: String,
+    size: usize,
+    hash: String,
+}
+
+/// An uploaded file that does not match its local content.
+#[derive(Debug)]
+pub struct VerifyFailure {
+    pub index: String,
+    pub data_type: DataType,
+    pub link: String,
+    pub reason: String,
+}
+
+impl Display for VerifyFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {} ({}): {}",
+            self.data_type, self.index, self.link, self.reason
+        )
+    }
+}
+
+/// Fetches the uploaded files of a sample of assets (all assets when `sample` is
+/// `None`) and compares them with the local files, returning the files that are
+/// missing, truncated or with a different content.
+pub async fn verify_upload(
+    asset_pairs: &HashMap<isize, AssetPair>,
+    cache: &Cache,
+    config_data: &ConfigData,
+    sample: Option<usize>,
+) -> Result<(usize, Vec<VerifyFailure>)> {
+    let mut indices: Vec<&String> = cache.items.keys().collect();
+
+    if let Some(sample) = sample {
+        indices.shuffle(&mut rand::thread_rng());
+        indices.truncate(sample);
+    }
+
+    let mut targets = Vec::new();
+
+    for index in indices {
+        let item = cache.items.get(index).unwrap();
+        let asset_index = isize::from_str(index)?;
+        let pair = asset_pairs.get(&asset_index).ok_or_else(|| {
+            anyhow!(
+                "cache item {} does not have a corresponding asset pair",
+                index
+            )
+        })?;
+
+        targets.push(VerifyTarget::new(
+            index,
+            DataType::Image,
+            &item.image_link,
+            std::fs::read(&pair.image)?,
+        ));
+
+        if let (Some(animation), Some(animation_link)) = (&pair.animation, &item.animation_link) {
+            targets.push(VerifyTarget::new(
+                index,
+                DataType::Animation,
+                animation_link,
+                std::fs::read(animation)?,
+            ));
+        }
+
+        let metadata = get_updated_metadata(
+            &pair.metadata,
+            &item.image_link,
+            &item.animation_link,
+            &MetadataOverrides::new(config_data, asset_index),
+        )?;
+        targets.push(VerifyTarget::new(
+            index,
+            DataType::Metadata,
+            &item.metadata_link,
+            metadata.into_bytes(),
+        ));
+    }
+
+    let total = targets.len();
+    let client = Client::new();
+    let pb = progress_bar_with_style(total as u64);
+
+    let failures: Vec<VerifyFailure> = stream::iter(targets)
+        .map(|target| {
+            let client = &client;
+            let pb = &pb;
+            async move {
+                let result = target.verify(client).await;
+                pb.inc(1);
+                result
+            }
+        })
+        .buffer_unordered(PARALLEL_LIMIT)
+        .filter_map(|failure| async move { failure })
+        .collect()
+        .await;
+
+    pb.finish_and_clear();
+
+    Ok((total, failures))
+}
+
+impl VerifyTarget {
+    fn new(index: &str, data_type: DataType, link: &str, content: Vec<u8>) -> Self {
+        VerifyTarget {
+            index: index.to_string(),
+            data_type,
+            link: link.to_string(),
+            size: content.len(),
+            hash: HEXLOWER.encode(digest(&SHA256, &content).as_ref()),
+        }
+    }
+
+    async fn verify(self, client: &Client) -> Option<VerifyFailure> {
+        let reason = match self.fetch(client).await {
+            Ok(content) if content.len() != self.size => Some(format!(
+                "size mismatch (expected {} bytes, got {} bytes)",
+                self.size,
+                content.len()
+            )),
+            Ok(content) if HEXLOWER.encode(digest(&SHA256, &content).as_ref()) != self.hash => {
+                Some("content hash mismatch".to_string())
+            }
+            Ok(_) => None,
+            Err(error) => Some(error.to_string()),
+        };
+
+        reason.map(|reason| VerifyFailure {
+            index: self.index,
+            data_type: self.data_type,
+            link: self.link,
+            reason,
+        })
+    }
+
+    async fn fetch(&self, client: &Client) -> Result<Vec<u8>> {
+        if self.link.is_empty() {
+            return Err(anyhow!("missing link"));
+        }
+
+        let response = client.get(gateway_url(&self.link)).send().await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            return Err(anyhow!("could not fetch file ({})", status));
+        }
+
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// Resolves `ipfs://` and `ar://` links to their HTTP gateway.
+fn gateway_url(link: &str) -> String {
+    if let Some(path) = link.strip_prefix("ipfs://") {
+        format!("{IPFS_GATEWAY}/{path}")
+    } else if let Some(path) = link.strip_prefix("ar://") {
+        format!("{ARWEAVE_GATEWAY}/{path}")
+    } else {
+        link.to_string()
+    }
+}
+
+#[test]
+fn test_verify_gateway_url() {
+    assert_eq!(
+        gateway_url("ipfs://bafy/0.png"),
+        "https://ipfs.io/ipfs/bafy/0.png"
+    );
+    assert_eq!(gateway_url("ar://abc"), "https://arweave.net/abc");
+    assert_eq!(
+        gateway_url("https://arweave.net/abc?ext=png"),
+        "https://arweave.net/abc?ext=png"
+    );
+}
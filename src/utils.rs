@@ -1,10 +1,18 @@
-use std::{ops::Deref, str::FromStr, thread::sleep, time::Duration};
+use std::{
+    ops::Deref,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    thread::sleep,
+    time::Duration,
+};
 
 pub use anchor_client::solana_sdk::hash::Hash;
 use anchor_client::{
     solana_sdk::{
         account::Account,
         commitment_config::{CommitmentConfig, CommitmentLevel},
+        instruction::Instruction,
+        native_token::LAMPORTS_PER_SOL,
         program_pack::{IsInitialized, Pack},
         pubkey::Pubkey,
     },
@@ -12,18 +20,42 @@ use anchor_client::{
 };
 pub use anyhow::{anyhow, Result};
 use console::{style, Style};
-use dialoguer::theme::ColorfulTheme;
+use dialoguer::{theme::ColorfulTheme, Confirm};
 pub use indicatif::{ProgressBar, ProgressStyle};
-use mpl_token_metadata::ID as TOKEN_METADATA_PROGRAM_ID;
+use mpl_token_metadata::{
+    state::{Metadata, TokenMetadataAccount},
+    ID as TOKEN_METADATA_PROGRAM_ID,
+};
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::{
     rpc_client::RpcClient,
-    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionConfig},
     rpc_filter::{Memcmp, RpcFilterType},
 };
 use spl_token::state::{Account as SplAccount, Mint};
 
-use crate::{common::*, config::data::Cluster};
+use crate::{
+    common::*, config::data::Cluster, das::get_creator_mints, dry_run::is_dry_run,
+    parse::parse_program_failure, pdas::find_metadata_pda,
+};
+
+/// Indicates whether confirmation prompts should be automatically accepted.
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Indicates whether user input is disabled (prompts fail instead of waiting for input).
+static NO_INPUT: AtomicBool = AtomicBool::new(false);
+
+/// Indicates whether a warning stops the command instead of letting it continue.
+static STOP_ON_WARNING: AtomicBool = AtomicBool::new(false);
+
+/// Indicates whether SOL should be requested from the devnet faucet when the balance is low.
+static AIRDROP_IF_NEEDED: AtomicBool = AtomicBool::new(false);
+
+/// Maximum amount requested from the devnet faucet in a single airdrop.
+const MAX_AIRDROP_LAMPORTS: u64 = 2 * LAMPORTS_PER_SOL;
+
+/// Number of attempts to confirm an airdrop (one per second).
+const AIRDROP_CONFIRM_RETRIES: u64 = 30;
 
 /// Hash for devnet cluster
 pub const DEVNET_HASH: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
@@ -32,15 +64,18 @@ pub const DEVNET_HASH: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
 pub const MAINNET_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
 
 /// Return the environment of the current connected RPC.
-pub fn get_cluster(rpc_client: RpcClient) -> Result<Cluster> {
+pub fn get_cluster(rpc_client: &RpcClient) -> Result<Cluster> {
     let devnet_hash = Hash::from_str(DEVNET_HASH).unwrap();
     let mainnet_hash = Hash::from_str(MAINNET_HASH).unwrap();
     let genesis_hash = rpc_client.get_genesis_hash()?;
+    let url = rpc_client.url();
 
     Ok(if genesis_hash == devnet_hash {
         Cluster::Devnet
     } else if genesis_hash == mainnet_hash {
         Cluster::Mainnet
+    } else if url.contains("localhost") || url.contains("127.0.0.1") {
+        Cluster::Localnet
     } else {
         Cluster::Unknown
     })
@@ -140,6 +175,193 @@ pub fn get_dialoguer_theme() -> ColorfulTheme {
     }
 }
 
+/// Sets how prompts are handled, based on the `--yes` and `--no-input` global flags.
+pub fn set_interaction_mode(assume_yes: bool, no_input: bool) {
+    ASSUME_YES.store(assume_yes, Ordering::SeqCst);
+    NO_INPUT.store(no_input, Ordering::SeqCst);
+}
+
+/// Sets whether warnings stop the command, based on the `--stop-on-warning` flag of launch.
+pub fn set_stop_on_warning(stop_on_warning: bool) {
+    STOP_ON_WARNING.store(stop_on_warning, Ordering::SeqCst);
+}
+
+/// Fails after a warning was reported when running with `--stop-on-warning`.
+pub fn stop_on_warning(warning: &str) -> Result<()> {
+    if STOP_ON_WARNING.load(Ordering::SeqCst) {
+        return Err(anyhow!("Stopped on warning: {}", warning));
+    }
+
+    Ok(())
+}
+
+/// Sets whether the devnet faucet is used to fund the payer, based on the
+/// `--airdrop-if-needed` global flag.
+pub fn set_airdrop_if_needed(airdrop_if_needed: bool) {
+    AIRDROP_IF_NEEDED.store(airdrop_if_needed, Ordering::SeqCst);
+}
+
+/// Checks that the payer has enough SOL to cover the estimated cost of an action; `action`
+/// describes what the SOL is needed for. On devnet, the missing SOL is requested from the
+/// faucet when running with `--airdrop-if-needed`.
+pub async fn check_balance(
+    rpc_client: &RpcClient,
+    payer: &Pubkey,
+    required: u64,
+    action: &str,
+) -> Result<()> {
+    let balance = request_airdrop_if_needed(rpc_client, payer, required).await?;
+
+    if balance >= required {
+        return Ok(());
+    }
+
+    let shortfall = required - balance;
+
+    // the plan of a dry run continues, reporting the missing funds
+    if is_dry_run() {
+        println!(
+            "{} {}",
+            WARNING_EMOJI,
+            style(format!(
+                "Insufficient balance to {}: need ◎ {:.4} more SOL",
+                action,
+                shortfall as f64 / LAMPORTS_PER_SOL as f64
+            ))
+            .yellow()
+        );
+        return Ok(());
+    }
+
+    let devnet = matches!(get_cluster(rpc_client), Ok(Cluster::Devnet));
+
+    Err(SugarError::InsufficientFunds(format!(
+        "Insufficient balance to {}: need ◎ {:.4} more SOL (balance ◎ {:.4}, estimated cost ◎ {:.4}){}",
+        action,
+        shortfall as f64 / LAMPORTS_PER_SOL as f64,
+        balance as f64 / LAMPORTS_PER_SOL as f64,
+        required as f64 / LAMPORTS_PER_SOL as f64,
+        if devnet {
+            ", use --airdrop-if-needed to request SOL from the devnet faucet"
+        } else {
+            ""
+        }
+    ))
+    .into())
+}
+
+/// Returns the balance of the payer. On devnet, the SOL missing to reach `required` is
+/// first requested from the faucet when running with `--airdrop-if-needed`.
+pub async fn request_airdrop_if_needed(
+    rpc_client: &RpcClient,
+    payer: &Pubkey,
+    required: u64,
+) -> Result<u64> {
+    let balance = rpc_client.get_balance(payer)?;
+
+    if balance >= required
+        || is_dry_run()
+        || !AIRDROP_IF_NEEDED.load(Ordering::SeqCst)
+        || !matches!(get_cluster(rpc_client), Ok(Cluster::Devnet))
+    {
+        return Ok(balance);
+    }
+
+    // requests whole SOL amounts to leave some margin for transaction fees
+    let mut remaining =
+        ((required - balance) as f64 / LAMPORTS_PER_SOL as f64).ceil() as u64 * LAMPORTS_PER_SOL;
+
+    println!(
+        "Requesting ◎ {} from the devnet faucet",
+        remaining / LAMPORTS_PER_SOL
+    );
+
+    while remaining > 0 {
+        let amount = remaining.min(MAX_AIRDROP_LAMPORTS);
+        let signature = rpc_client
+            .request_airdrop(payer, amount)
+            .map_err(|e| anyhow!("Devnet airdrop failed: {}", e))?;
+
+        let mut confirmed = false;
+        for _ in 0..AIRDROP_CONFIRM_RETRIES {
+            if rpc_client.confirm_transaction(&signature)? {
+                confirmed = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        if !confirmed {
+            return Err(anyhow!("Devnet airdrop {} was not confirmed", signature));
+        }
+
+        remaining -= amount;
+    }
+
+    Ok(rpc_client.get_balance(payer)?)
+}
+
+/// Simulates a transaction with the instructions before it is sent, so a program error is
+/// reported with a decoded message (e.g., "Candy machine is empty") instead of its code.
+pub fn simulate_instructions(
+    rpc_client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+) -> Result<()> {
+    // planned transactions can depend on accounts that were not created
+    if is_dry_run() {
+        return Ok(());
+    }
+
+    let transaction = Transaction::new_with_payer(instructions, Some(payer));
+    let result = rpc_client
+        .simulate_transaction_with_config(
+            &transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..Default::default()
+            },
+        )?
+        .value;
+
+    if let Some(err) = result.err {
+        let logs = result.logs.unwrap_or_default().join("\n");
+        debug!("Transaction simulation logs:\n{}", logs);
+
+        let reason = parse_program_failure(&logs).unwrap_or_else(|| err.to_string());
+        return Err(anyhow!("Transaction simulation failed: {}", reason));
+    }
+
+    Ok(())
+}
+
+/// Returns an error if user input is disabled; `action` describes what required the input.
+pub fn ensure_interactive(action: &str) -> Result<()> {
+    if NO_INPUT.load(Ordering::SeqCst) {
+        return Err(anyhow!(
+            "User input required ({}) but running with --no-input. Use --yes to accept prompts automatically.",
+            action
+        ));
+    }
+
+    Ok(())
+}
+
+/// Asks the user to confirm an action. The prompt is automatically accepted when running
+/// with `--yes` and fails when running with `--no-input`.
+pub fn confirm_with_theme(theme: &ColorfulTheme, prompt: &str) -> Result<bool> {
+    if ASSUME_YES.load(Ordering::SeqCst) {
+        println!("{} {}", prompt, style("yes (--yes)").dim());
+        return Ok(true);
+    }
+
+    ensure_interactive(prompt)?;
+
+    Ok(Confirm::with_theme(theme).with_prompt(prompt).interact()?)
+}
+
 pub fn assert_correct_authority(user_keypair: &Pubkey, update_authority: &Pubkey) -> Result<()> {
     if user_keypair != update_authority {
         return Err(anyhow!(
@@ -160,11 +382,18 @@ pub fn f64_to_u64_safe(f: f64) -> Result<u64, FloatConversionError> {
     Ok(f.trunc() as u64)
 }
 
+/// Maximum number of accounts per `getMultipleAccounts` request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
 pub fn get_cm_creator_metadata_accounts(
     client: &RpcClient,
     creator: &str,
     position: usize,
 ) -> Result<Vec<Pubkey>> {
+    if let Some(mints) = get_creator_mints(client, &Pubkey::from_str(creator)?, position) {
+        return Ok(mints.iter().map(find_metadata_pda).collect());
+    }
+
     let accounts = get_cm_creator_accounts(client, creator, position)?
         .into_iter()
         .map(|(pubkey, _account)| pubkey)
@@ -178,6 +407,10 @@ pub fn get_cm_creator_mint_accounts(
     creator: &str,
     position: usize,
 ) -> Result<Vec<Pubkey>> {
+    if let Some(mints) = get_creator_mints(client, &Pubkey::from_str(creator)?, position) {
+        return Ok(mints);
+    }
+
     let accounts = get_cm_creator_accounts(client, creator, position)?
         .into_iter()
         .map(|(_, account)| account.data[33..65].to_vec())
@@ -192,6 +425,35 @@ pub fn get_cm_creator_mint_accounts(
     Ok(accounts)
 }
 
+pub fn get_cm_creator_metadata(
+    client: &RpcClient,
+    creator: &str,
+    position: usize,
+) -> Result<Vec<(Pubkey, Metadata)>> {
+    if let Some(mints) = get_creator_mints(client, &Pubkey::from_str(creator)?, position) {
+        // the DAS API lists the mints, the metadata accounts are then fetched in batches
+        let metadata_pubkeys: Vec<Pubkey> = mints.iter().map(find_metadata_pda).collect();
+        let mut metadata = Vec::with_capacity(metadata_pubkeys.len());
+
+        for chunk in metadata_pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = client.get_multiple_accounts(chunk)?;
+
+            for (pubkey, account) in chunk.iter().zip(accounts) {
+                if let Some(account) = account {
+                    metadata.push((*pubkey, Metadata::safe_deserialize(&account.data)?));
+                }
+            }
+        }
+
+        return Ok(metadata);
+    }
+
+    get_cm_creator_accounts(client, creator, position)?
+        .into_iter()
+        .map(|(pubkey, account)| Ok((pubkey, Metadata::safe_deserialize(&account.data)?)))
+        .collect()
+}
+
 fn get_cm_creator_accounts(
     client: &RpcClient,
     creator: &str,
@@ -242,3 +504,106 @@ fn get_cm_creator_accounts(
 
     Ok(results)
 }
+
+/// Selection of item indices given as a list of indices and inclusive ranges,
+/// e.g. "0-999,1500,2000-2100".
+#[derive(Debug, Clone, Default)]
+pub struct IndexSelection(Vec<(u64, u64)>);
+
+impl IndexSelection {
+    /// Parses an optional selection, where `None` selects every index.
+    pub fn parse(selection: Option<&str>) -> Result<Option<Self>> {
+        selection.map(Self::from_str).transpose()
+    }
+
+    pub fn contains(&self, index: u64) -> bool {
+        self.0
+            .iter()
+            .any(|(first, last)| (*first..=*last).contains(&index))
+    }
+
+    /// Checks a cache key, which is never selected when it is not numeric (e.g. the
+    /// collection item).
+    pub fn contains_key(&self, key: &str) -> bool {
+        key.parse::<u64>()
+            .map(|index| self.contains(index))
+            .unwrap_or(false)
+    }
+}
+
+impl FromStr for IndexSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(selection: &str) -> Result<Self> {
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| anyhow!("Invalid index '{}' in selection '{}'", value, selection))
+        };
+
+        let mut ranges = Vec::new();
+
+        for part in selection.split(',').filter(|part| !part.trim().is_empty()) {
+            let range = match part.split_once('-') {
+                Some((first, last)) => (parse(first)?, parse(last)?),
+                None => (parse(part)?, parse(part)?),
+            };
+
+            if range.0 > range.1 {
+                return Err(anyhow!("Invalid range '{}' in selection", part.trim()));
+            }
+
+            ranges.push(range);
+        }
+
+        if ranges.is_empty() {
+            return Err(anyhow!("Empty index selection"));
+        }
+
+        Ok(IndexSelection(ranges))
+    }
+}
+
+/// Formats a sorted list of indices as a selection, e.g. "0-2,5".
+pub fn format_index_ranges(indices: &[u64]) -> String {
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+
+    for index in indices {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == *index => *last = *index,
+            Some((_, last)) if *last == *index => (),
+            _ => ranges.push((*index, *index)),
+        }
+    }
+
+    ranges
+        .iter()
+        .map(|(first, last)| {
+            if first == last {
+                first.to_string()
+            } else {
+                format!("{first}-{last}")
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+#[test]
+fn test_index_selection() {
+    let selection = IndexSelection::from_str("0-999, 1500,2000-2100").unwrap();
+    assert!(selection.contains(0));
+    assert!(selection.contains(999));
+    assert!(!selection.contains(1000));
+    assert!(selection.contains(1500));
+    assert!(selection.contains(2100));
+    assert!(!selection.contains_key("-1"));
+
+    assert!(IndexSelection::from_str("10-5").is_err());
+    assert!(IndexSelection::from_str("a").is_err());
+    assert!(IndexSelection::from_str("").is_err());
+
+    assert_eq!(format_index_ranges(&[0, 1, 2, 5, 7, 8]), "0-2,5,7-8");
+    assert_eq!(format_index_ranges(&[]), "");
+}
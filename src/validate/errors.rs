@@ -9,14 +9,23 @@ pub enum ValidateParserError {
     #[error("Invalid assets directory")]
     InvalidAssetsDirectory,
 
-    #[error("Name exceeds 32 chars.")]
-    NameTooLong,
+    #[error("Name exceeds 32 bytes ({0} bytes in {1} chars).")]
+    NameTooLong(usize, usize),
 
-    #[error("Symbol exceeds 10 chars.")]
-    SymbolTooLong,
+    #[error("Symbol exceeds 10 bytes ({0} bytes in {1} chars).")]
+    SymbolTooLong(usize, usize),
 
-    #[error("Url exceeds 200 chars.")]
-    UrlTooLong,
+    #[error("Url exceeds 200 bytes ({0} bytes).")]
+    UrlTooLong(usize),
+
+    #[error("{0} '{1}' contains zero-width or invisible characters.")]
+    InvisibleCharacters(String, String),
+
+    #[error("{0} '{1}' has leading or trailing whitespace.")]
+    SurroundingWhitespace(String, String),
+
+    #[error("Url '{0}' contains non-ASCII characters: they must be percent-encoded.")]
+    NonAsciiUrl(String),
 
     #[error("Creator address: '{0}' is invalid.")]
     InvalidCreatorAddress(String),
@@ -59,4 +68,42 @@ pub enum ValidateParserError {
 
     #[error("Invalid category '{0}': must be one of: {1}")]
     InvalidCategory(String, String),
+
+    #[error("Metadata does not match the config file: {0}")]
+    ConfigMismatch(String),
+
+    #[error("Metadata does not match the attribute schema: {0}")]
+    SchemaMismatch(String),
+}
+
+impl ValidateParserError {
+    /// Identifier of the validation rule that produced the error, used in reports.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            ValidateParserError::MissingOrEmptyAssetsDirectory
+            | ValidateParserError::InvalidAssetsDirectory => "assets-directory",
+            ValidateParserError::NameTooLong(..) => "name-length",
+            ValidateParserError::SymbolTooLong(..) => "symbol-length",
+            ValidateParserError::UrlTooLong(..) => "url-length",
+            ValidateParserError::InvisibleCharacters(..) => "invisible-characters",
+            ValidateParserError::SurroundingWhitespace(..) => "surrounding-whitespace",
+            ValidateParserError::NonAsciiUrl(..) => "non-ascii-url",
+            ValidateParserError::InvalidCreatorAddress(..) => "creator-address",
+            ValidateParserError::InvalidCreatorShare => "creator-share",
+            ValidateParserError::InvalidSellerFeeBasisPoints(..) => "seller-fee-basis-points",
+            ValidateParserError::MissingAnimationUrl
+            | ValidateParserError::MissingExternalUrl
+            | ValidateParserError::MissingCollection
+            | ValidateParserError::MissingCreators
+            | ValidateParserError::MissingSellerFeeBasisPoints => "missing-field",
+            ValidateParserError::UnexpectedFilesFound
+            | ValidateParserError::NoAssetsFound
+            | ValidateParserError::RedundantFile(..)
+            | ValidateParserError::FileOutOfRange(..)
+            | ValidateParserError::NonContinuousSeries => "asset-files",
+            ValidateParserError::InvalidCategory(..) => "category",
+            ValidateParserError::ConfigMismatch(..) => "config-mismatch",
+            ValidateParserError::SchemaMismatch(..) => "schema",
+        }
+    }
 }
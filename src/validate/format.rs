@@ -25,20 +25,30 @@ pub struct Metadata {
 
 impl Metadata {
     pub fn validate(&mut self) -> Result<(), ValidateParserError> {
-        parser::check_name(&self.name)?;
-        parser::check_url(&self.image)?;
+        match self.validate_all().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Run all the checks, collecting every violation instead of stopping at the first.
+    pub fn validate_all(&mut self) -> Vec<ValidateParserError> {
+        let mut errors = Vec::new();
+
+        errors.extend(parser::check_name(&self.name).err());
+        errors.extend(parser::check_url(&self.image).err());
 
         // If users are using the old format, we do validation on those values.
         if let Some(sfbp) = &self.seller_fee_basis_points {
-            parser::check_seller_fee_basis_points(*sfbp)?;
+            errors.extend(parser::check_seller_fee_basis_points(*sfbp).err());
         }
         if let Some(symbol) = &self.symbol {
-            parser::check_symbol(symbol)?;
+            errors.extend(parser::check_symbol(symbol).err());
         }
 
         if let Some(creators) = &self.properties.creators {
-            parser::check_creators_shares(creators)?;
-            parser::check_creators_addresses(creators)?;
+            errors.extend(parser::check_creators_shares(creators).err());
+            errors.extend(parser::check_creators_addresses(creators).err());
         }
 
         if self.properties.category.is_none() {
@@ -53,22 +63,25 @@ impl Metadata {
                 WARNING_EMOJI, &self.name, category
             );
         }
-        parser::check_category(
-            self.properties
-                .category
-                .as_ref()
-                .expect("unreachable, should never throw"),
-        )?;
+        errors.extend(
+            parser::check_category(
+                self.properties
+                    .category
+                    .as_ref()
+                    .expect("unreachable, should never throw"),
+            )
+            .err(),
+        );
 
         if let Some(animation_url) = &self.animation_url {
-            parser::check_url(animation_url)?;
+            errors.extend(parser::check_url(animation_url).err());
         }
 
         if let Some(external_url) = &self.external_url {
-            parser::check_url(external_url)?;
+            errors.extend(parser::check_url(external_url).err());
         }
 
-        Ok(())
+        errors
     }
 }
 
@@ -80,7 +93,7 @@ pub struct Property {
     pub category: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize, Default, Serialize)]
+#[derive(Debug, Clone, Deserialize, Default, Serialize, PartialEq, Eq)]
 pub struct Creator {
     pub address: String,
     pub share: u16,
@@ -1,9 +1,14 @@
-use std::{collections::HashSet, path::PathBuf};
+use std::{collections::HashSet, fs, path::PathBuf};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use jsonschema::JSONSchema;
 use regex::Regex;
+use serde_json::{json, Value};
 
-use crate::validate::ValidateParserError;
+use crate::{
+    config::ConfigData,
+    validate::{Metadata, ValidateParserError},
+};
 
 pub fn validate_continuous_assets(paths: &[PathBuf]) -> Result<()> {
     // Checking the assets are a proper series starting at 0 and ending at n-1
@@ -61,6 +66,90 @@ pub fn validate_continuous_assets(paths: &[PathBuf]) -> Result<()> {
     Ok(())
 }
 
+/// Compare the symbol, seller fee basis points and creators of a metadata file against
+/// the config file, returning one line per divergent field. The candy machine uses the
+/// values from the config on-chain, so a divergence is silently replaced at mint.
+pub fn check_config_consistency(metadata: &Metadata, config: &ConfigData) -> Vec<String> {
+    let mut diff = Vec::new();
+
+    if let Some(symbol) = &metadata.symbol {
+        if symbol != &config.symbol {
+            diff.push(format!(
+                "symbol: \"{}\" (metadata) != \"{}\" (config)",
+                symbol, config.symbol
+            ));
+        }
+    }
+
+    if let Some(seller_fee_basis_points) = metadata.seller_fee_basis_points {
+        if seller_fee_basis_points != config.seller_fee_basis_points {
+            diff.push(format!(
+                "seller_fee_basis_points: {} (metadata) != {} (config)",
+                seller_fee_basis_points, config.seller_fee_basis_points
+            ));
+        }
+    }
+
+    if let Some(creators) = &metadata.properties.creators {
+        let mut metadata_creators: Vec<(String, u16)> = creators
+            .iter()
+            .map(|c| (c.address.clone(), c.share))
+            .collect();
+        let mut config_creators: Vec<(String, u16)> = config
+            .creators
+            .iter()
+            .map(|c| (c.address.to_string(), c.share as u16))
+            .collect();
+
+        metadata_creators.sort();
+        config_creators.sort();
+
+        if metadata_creators != config_creators {
+            let format_creators = |creators: &[(String, u16)]| {
+                creators
+                    .iter()
+                    .map(|(address, share)| format!("{} ({}%)", address, share))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            };
+            diff.push(format!(
+                "creators: [{}] (metadata) != [{}] (config)",
+                format_creators(&metadata_creators),
+                format_creators(&config_creators)
+            ));
+        }
+    }
+
+    diff
+}
+
+/// Load and compile the JSON Schema used to validate the `attributes` and `properties`
+/// of the metadata files.
+pub fn load_attribute_schema(path: &str) -> Result<JSONSchema> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read schema file '{}': {}", path, e))?;
+    let schema: Value = serde_json::from_str(&content)
+        .map_err(|e| anyhow!("Failed to parse schema file '{}': {}", path, e))?;
+
+    JSONSchema::compile(&schema).map_err(|e| anyhow!("Invalid schema file '{}': {}", path, e))
+}
+
+/// Validate the `attributes` and `properties` of a metadata file against the schema,
+/// returning one line per violation prefixed by its location.
+pub fn check_attribute_schema(schema: &JSONSchema, metadata: &Value) -> Vec<String> {
+    let instance = json!({
+        "attributes": metadata.get("attributes").cloned().unwrap_or(Value::Null),
+        "properties": metadata.get("properties").cloned().unwrap_or(Value::Null),
+    });
+
+    match schema.validate(&instance) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .map(|error| format!("{}: {}", error.instance_path, error))
+            .collect(),
+    }
+}
+
 #[test]
 fn test_validate_continuous_assets_success() {
     let paths = vec![
@@ -157,3 +246,73 @@ fn test_validate_continuous_assets_fail_no_assets_found() {
         "Unexpected files found in assets directory"
     );
 }
+
+#[test]
+fn test_check_config_consistency() {
+    use crate::{config::Creator as ConfigCreator, validate::Creator};
+    use anchor_client::solana_sdk::pubkey::Pubkey;
+
+    let address = Pubkey::new_unique();
+    let config = ConfigData {
+        symbol: "SUGAR".to_string(),
+        seller_fee_basis_points: 500,
+        creators: vec![ConfigCreator {
+            address,
+            share: 100,
+        }],
+        ..Default::default()
+    };
+
+    let mut metadata = Metadata {
+        symbol: Some("SUGAR".to_string()),
+        seller_fee_basis_points: Some(500),
+        ..Default::default()
+    };
+    metadata.properties.creators = Some(vec![Creator {
+        address: address.to_string(),
+        share: 100,
+    }]);
+    assert!(check_config_consistency(&metadata, &config).is_empty());
+
+    metadata.symbol = Some("CANDY".to_string());
+    metadata.seller_fee_basis_points = Some(250);
+    let diff = check_config_consistency(&metadata, &config);
+    assert_eq!(diff.len(), 2);
+    assert_eq!(
+        diff[0],
+        "symbol: \"CANDY\" (metadata) != \"SUGAR\" (config)"
+    );
+}
+
+#[test]
+fn test_check_attribute_schema() {
+    let schema = JSONSchema::compile(&json!({
+        "type": "object",
+        "properties": {
+            "attributes": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "trait_type": { "enum": ["Background", "Eyes"] }
+                    }
+                }
+            }
+        }
+    }))
+    .unwrap();
+
+    let valid = json!({
+        "attributes": [{ "trait_type": "Eyes", "value": "Blue" }],
+        "properties": { "files": [] }
+    });
+    assert!(check_attribute_schema(&schema, &valid).is_empty());
+
+    let invalid = json!({
+        "attributes": [{ "trait_type": "Eyse", "value": "Blue" }],
+        "properties": { "files": [] }
+    });
+    let errors = check_attribute_schema(&schema, &invalid);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("/attributes/0/trait_type"));
+}
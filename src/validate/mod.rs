@@ -3,9 +3,11 @@ pub mod format;
 pub mod helpers;
 pub mod parser;
 pub mod process;
+pub mod report;
 
 pub use errors::*;
 pub use format::*;
 pub use helpers::*;
 pub use parser::*;
 pub use process::*;
+pub use report::*;
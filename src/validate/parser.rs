@@ -8,23 +8,69 @@ use crate::{
     validate::{errors::ValidateParserError, Creator},
 };
 
+/// Characters that are not rendered but are stored on-chain, making names that look
+/// identical differ (zero-width space and non-joiner, word joiner, BOM, soft hyphen).
+const INVISIBLE_CHARACTERS: [char; 5] =
+    ['\u{200B}', '\u{200C}', '\u{2060}', '\u{FEFF}', '\u{00AD}'];
+
+/// Zero-width joiner, only valid inside emoji sequences.
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
 pub fn check_name(name: &str) -> Result<(), ValidateParserError> {
+    // the on-chain limit is in bytes: multi-byte characters (e.g. emoji) count more than once
     if name.len() > MAX_NAME_LENGTH {
-        return Err(ValidateParserError::NameTooLong);
+        return Err(ValidateParserError::NameTooLong(
+            name.len(),
+            name.chars().count(),
+        ));
     }
-    Ok(())
+    check_text("Name", name)
 }
 
 pub fn check_symbol(symbol: &str) -> Result<(), ValidateParserError> {
     if symbol.len() > MAX_SYMBOL_LENGTH {
-        return Err(ValidateParserError::SymbolTooLong);
+        return Err(ValidateParserError::SymbolTooLong(
+            symbol.len(),
+            symbol.chars().count(),
+        ));
     }
-    Ok(())
+    check_text("Symbol", symbol)
 }
 
 pub fn check_url(url: &str) -> Result<(), ValidateParserError> {
     if url.len() > MAX_URI_LENGTH {
-        return Err(ValidateParserError::UrlTooLong);
+        return Err(ValidateParserError::UrlTooLong(url.len()));
+    }
+    if !url.is_ascii() {
+        return Err(ValidateParserError::NonAsciiUrl(url.to_string()));
+    }
+    Ok(())
+}
+
+/// Checks for invisible characters and surrounding whitespace, which are stored as-is
+/// on-chain and render inconsistently on marketplaces.
+fn check_text(field: &str, value: &str) -> Result<(), ValidateParserError> {
+    let chars: Vec<char> = value.chars().collect();
+    // a joiner is part of an emoji sequence when it sits between two non-ASCII characters
+    let stray_joiner = chars.iter().enumerate().any(|(i, c)| {
+        *c == ZERO_WIDTH_JOINER
+            && (i == 0
+                || i == chars.len() - 1
+                || chars[i - 1].is_ascii()
+                || chars[i + 1].is_ascii())
+    });
+
+    if stray_joiner || value.contains(&INVISIBLE_CHARACTERS[..]) {
+        return Err(ValidateParserError::InvisibleCharacters(
+            field.to_string(),
+            value.escape_unicode().to_string(),
+        ));
+    }
+    if value.trim() != value {
+        return Err(ValidateParserError::SurroundingWhitespace(
+            field.to_string(),
+            value.to_string(),
+        ));
     }
     Ok(())
 }
@@ -71,3 +117,37 @@ pub fn check_category(category: &str) -> Result<(), ValidateParserError> {
 
     Ok(())
 }
+
+#[test]
+fn test_check_name_unicode() {
+    assert!(check_name("Sugar #1").is_ok());
+    // 8 emoji are 32 bytes
+    assert!(check_name(&"\u{1F36C}".repeat(8)).is_ok());
+    assert!(matches!(
+        check_name(&"\u{1F36C}".repeat(9)),
+        Err(ValidateParserError::NameTooLong(36, 9))
+    ));
+    assert!(matches!(
+        check_name("Sugar\u{200B} #1"),
+        Err(ValidateParserError::InvisibleCharacters(_, _))
+    ));
+    assert!(matches!(
+        check_name("Sugar\u{200D}#1"),
+        Err(ValidateParserError::InvisibleCharacters(_, _))
+    ));
+    // family emoji sequence
+    assert!(check_name("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}").is_ok());
+    assert!(matches!(
+        check_name("Sugar #1 "),
+        Err(ValidateParserError::SurroundingWhitespace(_, _))
+    ));
+}
+
+#[test]
+fn test_check_url_ascii() {
+    assert!(check_url("https://arweave.net/caf%C3%A9.png").is_ok());
+    assert!(matches!(
+        check_url("https://arweave.net/café.png"),
+        Err(ValidateParserError::NonAsciiUrl(_))
+    ));
+}
@@ -1,21 +1,35 @@
 use std::{
-    fs::File,
+    ffi::OsStr,
+    fs,
     path::Path,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
 };
 
 use anyhow::Result;
 use console::{style, Style};
-use dialoguer::{theme::ColorfulTheme, Confirm};
+use dialoguer::theme::ColorfulTheme;
 use glob::glob;
 use rayon::prelude::*;
 
-use crate::{common::*, utils::*, validate::*};
+use crate::{
+    common::*,
+    config::{get_config_data, ConfigData},
+    upload::{get_asset_order, get_base_name},
+    utils::*,
+    validate::*,
+};
 
 pub struct ValidateArgs {
     pub assets_dir: String,
+    pub config: String,
+    pub schema: Option<String>,
     pub strict: bool,
     pub skip_collection_prompt: bool,
+    pub report: Option<String>,
+    pub max_errors: Option<usize>,
 }
 
 pub fn process_validate(args: ValidateArgs) -> Result<()> {
@@ -60,14 +74,34 @@ pub fn process_validate(args: ValidateArgs) -> Result<()> {
                 ..get_dialoguer_theme()
             };
 
-            if !Confirm::with_theme(&theme).with_prompt("Do you want to continue without automatically setting the candy machine collection?").interact()? {
+            stop_on_warning("missing collection files in the assets folder")?;
+
+            if !confirm_with_theme(&theme, "Do you want to continue without automatically setting the candy machine collection?")? {
                 return Err(anyhow!("Operation aborted"));
             }
             println!();
         }
     }
 
+    // the candy machine overrides the symbol, royalties and creators on-chain with the
+    // values from the config file, so metadata files are checked against it when present
+    let config_data: Option<ConfigData> = if Path::new(&args.config).is_file() {
+        Some(get_config_data(&args.config)?)
+    } else {
+        info!(
+            "Config file '{}' not found, skipping the consistency check.",
+            args.config
+        );
+        None
+    };
+
+    let schema = match &args.schema {
+        Some(schema) => Some(load_attribute_schema(schema)?),
+        None => None,
+    };
+
     let errors = Arc::new(Mutex::new(Vec::new()));
+    let mismatches = Arc::new(Mutex::new(Vec::new()));
 
     let path = assets_dir.join("*.json");
     let pattern = path
@@ -76,70 +110,189 @@ pub fn process_validate(args: ValidateArgs) -> Result<()> {
 
     // Unwrapping here because we know the pattern is valid and GlobErrors should
     // be rare or impossible to produce.
-    let paths: Vec<PathBuf> = glob(pattern).unwrap().map(Result::unwrap).collect();
+    let paths: Vec<PathBuf> = glob(pattern)
+        .unwrap()
+        .map(Result::unwrap)
+        .filter(|path| path.file_name() != Some(OsStr::new(ASSET_ORDER_FILE)))
+        .collect();
+
+    let metadata_filenames = paths
+        .iter()
+        .filter_map(|path| path.file_name().and_then(OsStr::to_str))
+        .map(String::from)
+        .collect::<Vec<String>>();
+
+    let numeric_names = metadata_filenames.iter().all(|f| {
+        let base_name = get_base_name(f);
+        base_name == "collection" || base_name.chars().all(|c| c.is_ascii_digit())
+    });
 
-    // Validating continuous assets in directory
-    validate_continuous_assets(&paths)?;
+    if numeric_names && !assets_dir.join(ASSET_ORDER_FILE).is_file() {
+        // Validating continuous assets in directory
+        validate_continuous_assets(&paths)?;
+    } else {
+        // Validating that named assets can be ordered
+        get_asset_order(&args.assets_dir, &metadata_filenames)?;
+    }
 
     let pb = spinner_with_style();
     pb.enable_steady_tick(120);
     pb.set_message(format!("Validating {} metadata file(s)...", paths.len()));
 
+    let violations = Mutex::new(Vec::new());
+    let error_count = AtomicUsize::new(0);
+    let missing_category = AtomicUsize::new(0);
+    let truncated = AtomicBool::new(false);
+    let max_errors = error_limit(args.max_errors);
+
+    // records a violation, returning false once the maximum number of errors is reached
+    let record = |path: &PathBuf, rule: &str, message: String| -> bool {
+        if error_count.fetch_add(1, Ordering::SeqCst) >= max_errors {
+            truncated.store(true, Ordering::SeqCst);
+            return false;
+        }
+
+        error!("{}: {}", path.display(), message);
+        violations.lock().unwrap().push(Violation {
+            file: path.display().to_string(),
+            rule: rule.to_string(),
+            message: message.clone(),
+        });
+        errors.lock().unwrap().push(ValidateError {
+            path: path.clone(),
+            error: message,
+        });
+
+        true
+    };
+
     paths.par_iter().for_each(|path| {
-        let errors = errors.clone();
-        let f = match File::open(path) {
-            Ok(f) => f,
+        if error_count.load(Ordering::SeqCst) >= max_errors {
+            truncated.store(true, Ordering::SeqCst);
+            return;
+        }
+
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
             Err(error) => {
-                error!("{}: {}", path.display(), error);
-                errors.lock().unwrap().push(ValidateError {
-                    path,
-                    error: error.to_string(),
-                });
+                record(path, "read", error.to_string());
                 return;
             }
         };
 
-        let mut metadata = match serde_json::from_reader::<File, Metadata>(f) {
+        let mut metadata = match serde_json::from_str::<Metadata>(&content) {
             Ok(metadata) => metadata,
             Err(error) => {
-                error!("{}: {}", path.display(), error);
-                errors.lock().unwrap().push(ValidateError {
-                    path,
-                    error: error.to_string(),
-                });
+                record(path, "parse", error.to_string());
                 return;
             }
         };
 
-        // To be replaced with the strict validator once JSON standard is finalized.
-        if args.strict {
-            match metadata.validate() {
-                Ok(()) => {}
-                Err(e) => {
-                    error!("{}: {}", path.display(), e);
-                    errors.lock().unwrap().push(ValidateError {
-                        path,
-                        error: e.to_string(),
-                    });
+        if let Some(config_data) = &config_data {
+            let is_collection = path.file_name() == Some(OsStr::new("collection.json"));
+            let diff = check_config_consistency(&metadata, config_data);
+
+            if !is_collection && !diff.is_empty() {
+                for line in &diff {
+                    let error = ValidateParserError::ConfigMismatch(line.clone());
+                    if !record(path, error.rule(), error.to_string()) {
+                        return;
+                    }
                 }
+                mismatches.lock().unwrap().push((path, diff));
             }
-        } else {
-            match metadata.validate() {
-                Ok(()) => {}
-                Err(e) => {
-                    error!("{}: {}", path.display(), e);
-                    errors.lock().unwrap().push(ValidateError {
-                        path,
-                        error: e.to_string(),
-                    });
+        }
+
+        if let Some(schema) = &schema {
+            // the raw value is used so fields not mapped by the metadata struct are checked
+            let schema_errors = serde_json::from_str::<Value>(&content)
+                .map(|value| check_attribute_schema(schema, &value))
+                .unwrap_or_default();
+
+            for line in schema_errors {
+                let error = ValidateParserError::SchemaMismatch(line);
+                if !record(path, error.rule(), error.to_string()) {
+                    return;
                 }
             }
         }
+
+        // the category is defaulted with a warning when it is missing
+        if metadata.properties.category.is_none() {
+            missing_category.fetch_add(1, Ordering::SeqCst);
+        }
+
+        // To be replaced with the strict validator once JSON standard is finalized.
+        for error in metadata.validate_all() {
+            if !record(path, error.rule(), error.to_string()) {
+                return;
+            }
+        }
     });
 
     pb.finish();
 
-    if !errors.lock().unwrap().is_empty() {
+    let mut mismatches = mismatches.lock().unwrap();
+
+    if !mismatches.is_empty() {
+        mismatches.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        println!(
+            "\n{}{}",
+            WARNING_EMOJI,
+            style(format!(
+                "{} metadata file(s) do not match '{}':",
+                mismatches.len(),
+                args.config
+            ))
+            .yellow()
+            .bold()
+        );
+
+        for (path, diff) in mismatches.iter() {
+            println!("\n{}", style(path.display()).bold());
+            for line in diff {
+                println!("  {} {}", style("~").yellow(), line);
+            }
+        }
+
+        stop_on_warning(&format!(
+            "{} metadata file(s) do not match the config",
+            mismatches.len()
+        ))?;
+    }
+
+    let missing_category = missing_category.load(Ordering::SeqCst);
+
+    if missing_category > 0 {
+        stop_on_warning(&format!(
+            "{} metadata file(s) are missing `properties.category`",
+            missing_category
+        ))?;
+    }
+
+    let truncated = truncated.load(Ordering::SeqCst);
+    let violations = violations.into_inner().unwrap();
+
+    if let Some(report) = &args.report {
+        ValidateReport::new(paths.len(), truncated, violations.clone()).write(report)?;
+        println!("\nValidation report written to {}", report);
+    }
+
+    if truncated {
+        println!(
+            "\n{}{}",
+            WARNING_EMOJI,
+            style(format!(
+                "Validation stopped after {} error(s), other files were not checked.",
+                max_errors
+            ))
+            .yellow()
+            .bold()
+        );
+    }
+
+    if !violations.is_empty() {
         log_errors("validate_errors", errors)?;
         return Err(anyhow!(
             "Validation error: see 'validate_errors.json' file for details"
@@ -152,3 +305,20 @@ pub fn process_validate(args: ValidateArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// Number of errors after which the validation stops; `--max-errors 0` (as not setting
+/// it) means no limit.
+fn error_limit(max_errors: Option<usize>) -> usize {
+    match max_errors {
+        None | Some(0) => usize::MAX,
+        Some(max_errors) => max_errors,
+    }
+}
+
+#[test]
+fn test_error_limit() {
+    assert_eq!(error_limit(None), usize::MAX);
+    assert_eq!(error_limit(Some(0)), usize::MAX);
+    assert_eq!(error_limit(Some(1)), 1);
+    assert_eq!(error_limit(Some(25)), 25);
+}
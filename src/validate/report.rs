@@ -0,0 +1,48 @@
+use std::fs::File;
+
+use anyhow::Result;
+use serde::Serialize;
+
+/// A single validation failure of a metadata file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    pub file: String,
+    pub rule: String,
+    pub message: String,
+}
+
+/// Summary of a validation run, written by `sugar validate --report`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateReport {
+    /// Number of metadata files checked.
+    pub files: usize,
+    /// Number of files with at least one violation.
+    pub invalid_files: usize,
+    /// Indicates whether the run stopped at the `--max-errors` limit.
+    pub truncated: bool,
+    pub violations: Vec<Violation>,
+}
+
+impl ValidateReport {
+    pub fn new(files: usize, truncated: bool, mut violations: Vec<Violation>) -> Self {
+        violations.sort_by(|a, b| a.file.cmp(&b.file));
+
+        let mut invalid: Vec<&str> = violations.iter().map(|v| v.file.as_str()).collect();
+        invalid.dedup();
+
+        ValidateReport {
+            files,
+            invalid_files: invalid.len(),
+            truncated,
+            violations,
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<()> {
+        let f = File::create(path)?;
+        serde_json::to_writer_pretty(f, self)?;
+
+        Ok(())
+    }
+}
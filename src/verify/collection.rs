@@ -0,0 +1,303 @@
+use std::fmt;
+
+use console::style;
+use mpl_token_metadata::{
+    instruction::{set_and_verify_collection, set_and_verify_sized_collection_item},
+    state::Collection,
+};
+use tabled::{builder::Builder, settings::Style};
+
+use crate::{
+    candy_machine::{get_candy_machine_state, CANDY_MACHINE_ID},
+    common::*,
+    core_candy_machine::{get_candy_machine_program, CORE_CANDY_MACHINE_ID},
+    dry_run::SendOrPlan,
+    pdas::{find_candy_machine_creator_pda, find_master_edition_pda, get_metadata_pda},
+    utils::*,
+};
+
+/// Problem with the collection of a minted NFT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionIssue {
+    /// The collection field is not set.
+    Missing,
+    /// The collection field is set to the expected collection, but not verified.
+    Unverified,
+    /// The collection field is set to another collection.
+    Other { collection: Pubkey, verified: bool },
+}
+
+impl CollectionIssue {
+    /// Whether setAndVerifyCollection can fix the issue; a verified collection must be
+    /// unverified by its authority first.
+    pub fn is_fixable(&self) -> bool {
+        !matches!(self, CollectionIssue::Other { verified: true, .. })
+    }
+}
+
+impl fmt::Display for CollectionIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectionIssue::Missing => write!(f, "missing collection"),
+            CollectionIssue::Unverified => write!(f, "unverified collection"),
+            CollectionIssue::Other {
+                collection,
+                verified: true,
+            } => write!(f, "verified in collection {collection}"),
+            CollectionIssue::Other {
+                collection,
+                verified: false,
+            } => write!(f, "unverified collection {collection}"),
+        }
+    }
+}
+
+/// Minted NFT with a collection issue.
+#[derive(Debug)]
+pub struct CollectionMember {
+    pub metadata: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub update_authority: Pubkey,
+    pub issue: CollectionIssue,
+}
+
+pub fn collection_issue(
+    collection: &Option<Collection>,
+    collection_mint: &Pubkey,
+) -> Option<CollectionIssue> {
+    match collection {
+        None => Some(CollectionIssue::Missing),
+        Some(collection) if collection.key == *collection_mint => {
+            (!collection.verified).then_some(CollectionIssue::Unverified)
+        }
+        Some(collection) => Some(CollectionIssue::Other {
+            collection: collection.key,
+            verified: collection.verified,
+        }),
+    }
+}
+
+/// Checks that every NFT minted from the candy machine is a verified member of its
+/// collection, setting and verifying the collection of the others when `fix` is set.
+pub fn verify_collection(
+    keypair: Option<String>,
+    rpc_url: Option<String>,
+    candy_machine: &str,
+    fix: bool,
+) -> Result<()> {
+    // fixing the collection requires the signature of the collection authority
+    let sugar_config = if fix {
+        sugar_setup(keypair, rpc_url)?
+    } else {
+        sugar_setup_read_only(keypair, rpc_url)?
+    };
+
+    println!(
+        "{} {}Loading candy machine",
+        style("[1/3]").bold().dim(),
+        CANDY_EMOJI
+    );
+
+    let candy_machine_id = Pubkey::from_str(candy_machine)
+        .map_err(|_| CacheError::InvalidCandyMachineAddress(candy_machine.to_string()))?;
+
+    let pb = spinner_with_style();
+    pb.set_message("Connecting...");
+
+    if get_candy_machine_program(&sugar_config, &candy_machine_id)? == CORE_CANDY_MACHINE_ID {
+        pb.finish_and_clear();
+        return Err(anyhow!(
+            "The collection of Core assets is set by the candy machine program, \
+            there are no collection fields to verify"
+        ));
+    }
+
+    let candy_machine_state = get_candy_machine_state(&sugar_config, &candy_machine_id)?;
+    let collection_mint = candy_machine_state.collection_mint;
+
+    pb.finish_with_message("Done");
+    println!("{} {}", style("Collection mint:").bold(), collection_mint);
+
+    println!(
+        "\n{} {}Getting minted NFTs",
+        style("[2/3]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let pb = spinner_with_style();
+    pb.set_message("Loading...");
+
+    let client = setup_client(&sugar_config)?;
+    let program = client.program(CANDY_MACHINE_ID);
+    let rpc_client = throttled_client_with_commitment(&sugar_config.rpc_url);
+    let (creator, _) = find_candy_machine_creator_pda(&candy_machine_id);
+    let minted = get_cm_creator_metadata(&rpc_client, &creator.to_string(), 0)?;
+
+    pb.finish_with_message(format!("Found {} NFTs", minted.len()));
+
+    println!(
+        "\n{} {}Checking collection membership",
+        style("[3/3]").bold().dim(),
+        PAPER_EMOJI
+    );
+
+    let members: Vec<CollectionMember> = minted
+        .iter()
+        .filter_map(|(pubkey, metadata)| {
+            collection_issue(&metadata.collection, &collection_mint).map(|issue| CollectionMember {
+                metadata: *pubkey,
+                mint: metadata.mint,
+                name: metadata.data.name.trim_matches(char::from(0)).to_string(),
+                update_authority: metadata.update_authority,
+                issue,
+            })
+        })
+        .collect();
+
+    if members.is_empty() {
+        println!(
+            "\n{}All {} NFTs are verified members of the collection.",
+            COMPLETE_EMOJI,
+            minted.len()
+        );
+        return Ok(());
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["Mint", "Name", "Issue"]);
+
+    for member in &members {
+        builder.push_record([
+            member.mint.to_string(),
+            member.name.clone(),
+            member.issue.to_string(),
+        ]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::psql());
+    println!("\n{}", table);
+
+    if !fix {
+        println!("\nRe-run with --fix to set and verify the collection of the NFTs.");
+        return Err(anyhow!(
+            "{} of {} NFTs are not verified members of the collection",
+            members.len(),
+            minted.len()
+        ));
+    }
+
+    let payer = sugar_config.keypair.pubkey();
+    let (collection_metadata_pubkey, collection_metadata) =
+        get_metadata_pda(&collection_mint, &program)?;
+    if collection_metadata.update_authority != payer {
+        return Err(anyhow!(
+            "The keypair {} is not the update authority {} of the collection",
+            payer,
+            collection_metadata.update_authority
+        ));
+    }
+
+    let collection_master_edition = find_master_edition_pda(&collection_mint);
+    // sized collections keep track of the number of verified items
+    let sized = collection_metadata.collection_details.is_some();
+
+    let mut skipped = 0;
+    let mut errors = Vec::new();
+
+    println!("\nSetting and verifying the collection of the NFTs:");
+    let pb = progress_bar_with_style(members.len() as u64);
+
+    for member in &members {
+        pb.inc(1);
+
+        // the update authority of the item must also sign the instruction
+        if !member.issue.is_fixable() || member.update_authority != payer {
+            skipped += 1;
+            continue;
+        }
+
+        let set_and_verify = if sized {
+            set_and_verify_sized_collection_item
+        } else {
+            set_and_verify_collection
+        };
+        let ix = set_and_verify(
+            mpl_token_metadata::ID,
+            member.metadata,
+            payer,
+            payer,
+            payer,
+            collection_mint,
+            collection_metadata_pubkey,
+            collection_master_edition,
+            None,
+        );
+
+        if let Err(err) = program
+            .request()
+            .instruction(ix)
+            .send_or_plan("set and verify collection")
+        {
+            errors.push((member.mint, err));
+        }
+    }
+
+    pb.finish();
+
+    for (mint, err) in &errors {
+        error!(
+            "Failed to set and verify the collection of {}: {}",
+            mint, err
+        );
+        println!("- {}: {}", mint, err);
+    }
+
+    if skipped > 0 {
+        println!(
+            "{}{} NFT(s) skipped: verified in another collection or with an update \
+            authority other than {}",
+            WARNING_EMOJI, skipped, payer
+        );
+    }
+
+    if !errors.is_empty() || skipped > 0 {
+        return Err(anyhow!(
+            "{} of {} NFTs could not be fixed",
+            errors.len() + skipped,
+            members.len()
+        ));
+    }
+
+    println!(
+        "\n{}Collection of {} NFTs set and verified.",
+        COMPLETE_EMOJI,
+        members.len()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_collection_issues() {
+    let collection_mint = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let collection = |key, verified| Some(Collection { verified, key });
+
+    assert_eq!(
+        collection_issue(&None, &collection_mint),
+        Some(CollectionIssue::Missing)
+    );
+    assert_eq!(
+        collection_issue(&collection(collection_mint, false), &collection_mint),
+        Some(CollectionIssue::Unverified)
+    );
+    assert_eq!(
+        collection_issue(&collection(collection_mint, true), &collection_mint),
+        None
+    );
+
+    let issue = collection_issue(&collection(other, true), &collection_mint).unwrap();
+    assert!(!issue.is_fixable());
+}
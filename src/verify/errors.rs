@@ -6,4 +6,8 @@ pub enum VerifyError {
     FailedToGetAccountData(String),
     #[error("{0} mismatch (expected='{1}', found='{2}')")]
     Mismatch(String, String, String),
+    #[error("zero-length config line")]
+    EmptyConfigLine,
+    #[error("duplicate {0} '{1}'")]
+    Duplicate(String, String),
 }
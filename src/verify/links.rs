@@ -0,0 +1,245 @@
+use std::{fs, time::Duration};
+
+use console::style;
+use futures::{stream, StreamExt};
+use reqwest::{header, redirect::Policy, Client};
+use serde::Serialize;
+
+use crate::{cache::Cache, common::*, utils::*};
+
+// IPFS gateways tried (in order) for `ipfs://` and gateway links.
+const IPFS_GATEWAYS: &[&str] = &[
+    IPFS_GATEWAY,
+    "https://dweb.link/ipfs",
+    "https://nftstorage.link/ipfs",
+    "https://gateway.pinata.cloud/ipfs",
+];
+// Arweave gateways tried (in order) for `ar://` and arweave.net links.
+const ARWEAVE_GATEWAYS: &[&str] = &[ARWEAVE_GATEWAY, "https://ar-io.net"];
+// Timeout of each request (in seconds).
+const LINK_TIMEOUT: u64 = 30;
+// Maximum number of redirects followed.
+const MAX_REDIRECTS: usize = 10;
+
+/// A link of a cache item that could not be fetched from any gateway.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenLink {
+    pub index: String,
+    pub kind: String,
+    pub link: String,
+    pub reason: String,
+}
+
+/// Result of the link check, written to the report file.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkReport {
+    pub checked: usize,
+    pub broken: Vec<BrokenLink>,
+    /// Indices to upload again, in the format of the `--indices` option.
+    pub reupload: String,
+}
+
+/// Checks that the image, animation and metadata links of the cache items can be
+/// fetched, trying alternate gateways before reporting a link as broken.
+pub async fn check_links(cache: &Cache, selection: Option<&IndexSelection>) -> Result<LinkReport> {
+    let mut links = Vec::new();
+
+    for (index, item) in &cache.items {
+        if let Some(selection) = selection {
+            if !selection.contains_key(index) {
+                continue;
+            }
+        }
+
+        links.push((index.clone(), "image", item.image_link.clone()));
+
+        if let Some(animation_link) = &item.animation_link {
+            links.push((index.clone(), "animation", animation_link.clone()));
+        }
+
+        links.push((index.clone(), "metadata", item.metadata_link.clone()));
+    }
+
+    let client = Client::builder()
+        .timeout(Duration::from_secs(LINK_TIMEOUT))
+        .redirect(Policy::limited(MAX_REDIRECTS))
+        .build()?;
+    let pb = progress_bar_with_style(links.len() as u64);
+    let checked = links.len();
+
+    let mut broken: Vec<BrokenLink> = stream::iter(links)
+        .map(|(index, kind, link)| {
+            let client = &client;
+            let pb = &pb;
+            async move {
+                let result = check_link(client, &link).await;
+                pb.inc(1);
+                result.err().map(|reason| BrokenLink {
+                    index,
+                    kind: kind.to_string(),
+                    link,
+                    reason,
+                })
+            }
+        })
+        .buffer_unordered(PARALLEL_LIMIT)
+        .filter_map(|broken| async move { broken })
+        .collect()
+        .await;
+
+    pb.finish_and_clear();
+
+    broken.sort_by_key(|link| link.index.parse::<i64>().unwrap_or(i64::MAX));
+
+    let mut indices: Vec<u64> = broken
+        .iter()
+        .filter_map(|link| link.index.parse::<u64>().ok())
+        .collect();
+    indices.dedup();
+
+    Ok(LinkReport {
+        checked,
+        broken,
+        reupload: format_index_ranges(&indices),
+    })
+}
+
+/// Prints the broken links and writes the report file.
+pub fn print_link_report(report: &LinkReport, report_file: Option<&str>) -> Result<()> {
+    if report.broken.is_empty() {
+        println!(
+            "\n{}",
+            style(format!("All {} links are reachable.", report.checked))
+                .green()
+                .bold()
+        );
+    } else {
+        println!(
+            "\n{}",
+            style(format!(
+                "{} of {} links are broken:",
+                report.broken.len(),
+                report.checked
+            ))
+            .red()
+            .bold()
+        );
+
+        for link in &report.broken {
+            println!(
+                "  {} {} ({}): {}",
+                link.index,
+                link.kind,
+                link.link,
+                style(&link.reason).dim()
+            );
+        }
+
+        if !report.reupload.is_empty() {
+            println!(
+                "\nUpload the items again with: {}",
+                style(format!("sugar upload --indices {}", report.reupload)).bold()
+            );
+        }
+    }
+
+    if let Some(report_file) = report_file {
+        fs::write(report_file, serde_json::to_string_pretty(report)?)?;
+        println!("\nReport saved to '{report_file}'.");
+    }
+
+    Ok(())
+}
+
+/// Fetches the first byte of a link, trying each candidate gateway in order.
+async fn check_link(client: &Client, link: &str) -> std::result::Result<(), String> {
+    if link.is_empty() {
+        return Err("missing link".to_string());
+    }
+
+    let mut reason = String::new();
+
+    for url in candidate_urls(link) {
+        let response = client
+            .get(&url)
+            .header(header::RANGE, "bytes=0-0")
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => reason = format!("{} ({})", response.status(), url),
+            Err(error) => reason = format!("{} ({})", error, url),
+        }
+    }
+
+    Err(reason)
+}
+
+/// Returns the URLs to try for a link: the link itself (resolved for `ipfs://` and
+/// `ar://` links) followed by the same content on alternate gateways.
+fn candidate_urls(link: &str) -> Vec<String> {
+    let alternates = |gateways: &[&str], path: &str| -> Vec<String> {
+        gateways
+            .iter()
+            .map(|gateway| format!("{gateway}/{path}"))
+            .collect()
+    };
+
+    if let Some(path) = link.strip_prefix("ipfs://") {
+        return alternates(IPFS_GATEWAYS, path);
+    }
+
+    if let Some(path) = link.strip_prefix("ar://") {
+        return alternates(ARWEAVE_GATEWAYS, path);
+    }
+
+    let mut urls = vec![link.to_string()];
+
+    if let Ok(url) = url::Url::parse(link) {
+        let host = url.host_str().unwrap_or_default();
+        let path = url.path().trim_start_matches('/');
+        let query = url.query().map(|q| format!("?{q}")).unwrap_or_default();
+
+        if let Some(path) = path.strip_prefix("ipfs/") {
+            urls.extend(alternates(IPFS_GATEWAYS, &format!("{path}{query}")));
+        } else if host == "arweave.net" || host.ends_with(".arweave.net") {
+            urls.extend(alternates(ARWEAVE_GATEWAYS, &format!("{path}{query}")));
+        }
+    }
+
+    let mut unique = Vec::with_capacity(urls.len());
+
+    for url in urls {
+        if !unique.contains(&url) {
+            unique.push(url);
+        }
+    }
+
+    unique
+}
+
+#[test]
+fn test_link_candidate_urls() {
+    assert_eq!(
+        candidate_urls("ipfs://bafy/0.png")[0],
+        "https://ipfs.io/ipfs/bafy/0.png"
+    );
+    assert_eq!(
+        candidate_urls("https://gateway.pinata.cloud/ipfs/bafy").len(),
+        IPFS_GATEWAYS.len()
+    );
+    assert_eq!(
+        candidate_urls("https://arweave.net/abc?ext=png"),
+        vec![
+            "https://arweave.net/abc?ext=png",
+            "https://ar-io.net/abc?ext=png"
+        ]
+    );
+    assert_eq!(
+        candidate_urls("https://cdn.example.com/0.png"),
+        vec!["https://cdn.example.com/0.png"]
+    );
+}
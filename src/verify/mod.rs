@@ -1,5 +1,9 @@
+pub mod collection;
 pub mod errors;
+pub mod links;
 pub mod process;
 
+pub use collection::*;
 pub use errors::*;
+pub use links::*;
 pub use process::*;
@@ -1,26 +1,38 @@
-use std::{thread, time::Duration};
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    thread,
+    time::Duration,
+};
 
 use anchor_lang::AccountDeserialize;
 use borsh::BorshDeserialize;
 use console::style;
-use mpl_candy_machine_core::{constants::HIDDEN_SECTION, CandyMachine};
+use mpl_candy_machine_core::CandyMachine;
 use mpl_token_metadata::state::Metadata;
 
 use crate::{
     cache::*,
-    candy_machine::CANDY_MACHINE_ID,
+    candy_machine::{read_config_line, CANDY_MACHINE_ID},
     common::*,
     config::Cluster,
-    constants::{CANDY_EMOJI, PAPER_EMOJI},
+    constants::{CANDY_EMOJI, PAPER_EMOJI, WARNING_EMOJI},
+    deploy::{config_line_order, generate_config_lines, item_index, upload_config_lines},
     pdas::find_metadata_pda,
     utils::*,
-    verify::VerifyError,
+    verify::{check_links, print_link_report, verify_collection, VerifyError},
 };
 
 pub struct VerifyArgs {
     pub keypair: Option<String>,
     pub rpc_url: Option<String>,
     pub cache: String,
+    pub repair: bool,
+    pub indices: Option<String>,
+    pub links: bool,
+    pub report: Option<String>,
+    pub collection: bool,
+    pub fix: bool,
+    pub interrupted: Arc<AtomicBool>,
 }
 
 #[derive(Debug)]
@@ -29,8 +41,8 @@ pub struct OnChainItem {
     pub uri: String,
 }
 
-pub fn process_verify(args: VerifyArgs) -> Result<()> {
-    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+pub async fn process_verify(args: VerifyArgs) -> Result<()> {
+    let selection = IndexSelection::parse(args.indices.as_deref())?;
 
     // loads the cache file (this needs to have been created by
     // the upload command)
@@ -48,6 +60,41 @@ pub fn process_verify(args: VerifyArgs) -> Result<()> {
         return Err(CacheError::CacheFileNotFound(args.cache).into());
     }
 
+    // checks the links of the cache items only, without the candy machine
+    if args.links {
+        println!(
+            "{} {}Checking links",
+            style("[1/1]").bold().dim(),
+            PAPER_EMOJI
+        );
+
+        let report = check_links(&cache, selection.as_ref()).await?;
+        print_link_report(&report, args.report.as_deref())?;
+
+        return if report.broken.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("{} broken link(s) found.", report.broken.len()))
+        };
+    }
+
+    // checks the collection of the minted NFTs only, without the config lines
+    if args.collection {
+        return verify_collection(
+            args.keypair,
+            args.rpc_url,
+            &cache.program.candy_machine,
+            args.fix,
+        );
+    }
+
+    // the repair writes the config lines again, signed by the keypair
+    let sugar_config = Arc::new(if args.repair {
+        sugar_setup(args.keypair, args.rpc_url)?
+    } else {
+        sugar_setup_read_only(args.keypair, args.rpc_url)?
+    });
+
     println!(
         "{} {}Loading candy machine",
         style("[1/2]").bold().dim(),
@@ -93,8 +140,12 @@ pub fn process_verify(args: VerifyArgs) -> Result<()> {
         println!("\nHidden settings enabled. No config items to verify.");
     } else if let Some(config_line_settings) = &candy_machine.data.config_line_settings {
         let num_items = candy_machine.data.items_available;
+        let order = config_line_order(&cache);
         let cache_items = &mut cache.items;
         let mut errors = Vec::new();
+        // cache indices of each name and uri, used to find duplicates
+        let mut names: HashMap<String, Vec<u64>> = HashMap::new();
+        let mut uris: HashMap<String, Vec<u64>> = HashMap::new();
 
         println!("Verifying {} config line(s): (Ctrl+C to abort)", num_items);
         let pb = progress_bar_with_style(num_items);
@@ -105,46 +156,69 @@ pub fn process_verify(args: VerifyArgs) -> Result<()> {
             0
         };
 
-        let line_size = candy_machine.data.get_config_line_size();
-        let name_length = config_line_settings.name_length as usize;
-        let uri_length = config_line_settings.uri_length as usize;
-
         for i in 0..num_items {
-            let name_start = HIDDEN_SECTION + STRING_LEN_SIZE + line_size * (i as usize);
-            let name_end = name_start + name_length;
-
-            let uri_start = name_end;
-            let uri_end = uri_start + uri_length;
-
-            let name_error = format!("Failed to decode name for item {}", i);
-            let name = String::from_utf8(data[name_start..name_end].to_vec())
-                .expect(&name_error)
-                .trim_matches(char::from(0))
-                .to_string();
+            // the cache item written at the config line (shuffled deploys)
+            let index = item_index(&order, i);
+
+            if !selection
+                .as_ref()
+                .map_or(true, |selection| selection.contains(index))
+            {
+                pb.inc(1);
+                continue;
+            }
 
-            let uri_error = format!("Failed to decode uri for item {}", i);
-            let uri = String::from_utf8(data[uri_start..uri_end].to_vec())
-                .expect(&uri_error)
-                .trim_matches(char::from(0))
-                .to_string();
+            let (name, uri) = read_config_line(&data, &candy_machine.data, i as usize)?;
 
             let on_chain_item = OnChainItem {
                 name: config_line_settings.prefix_name.to_string() + &name,
                 uri: config_line_settings.prefix_uri.to_string() + &uri,
             };
             let cache_item = cache_items
-                .get_mut(&i.to_string())
+                .get_mut(&index.to_string())
                 .expect("Failed to get item from config.");
 
-            if let Err(err) = items_match(cache_item, &on_chain_item) {
+            if name.is_empty() || uri.is_empty() {
                 cache_item.on_chain = false;
-                errors.push((i.to_string(), err.to_string()));
+                cache_item.set_status(ItemStatus::Uploaded);
+                errors.push((
+                    format!("Item {}", index),
+                    VerifyError::EmptyConfigLine.to_string(),
+                ));
+            } else {
+                names
+                    .entry(on_chain_item.name.clone())
+                    .or_default()
+                    .push(index);
+                uris.entry(on_chain_item.uri.clone())
+                    .or_default()
+                    .push(index);
+
+                if let Err(err) = items_match(cache_item, &on_chain_item) {
+                    cache_item.on_chain = false;
+                    cache_item.set_status(ItemStatus::Uploaded);
+                    errors.push((format!("Item {}", index), err.to_string()));
+                } else if cache_item.status < ItemStatus::Verified {
+                    cache_item.set_status(ItemStatus::Verified);
+                }
             }
 
             pb.inc(1);
             thread::sleep(Duration::from_micros(step));
         }
 
+        let duplicate_uris = find_duplicates(uris);
+        let duplicate_uri_count = duplicate_uris.len();
+
+        for (uri, indices) in duplicate_uris {
+            errors.push((
+                format!("Items {}", join_indices(&indices)),
+                VerifyError::Duplicate("uri".to_string(), uri).to_string(),
+            ));
+        }
+
+        let duplicate_names = find_duplicates(names);
+
         if !errors.is_empty() {
             pb.abandon_with_message(format!("{}", style("Verification failed ").red().bold()));
             cache.sync_file()?;
@@ -153,15 +227,83 @@ pub fn process_verify(args: VerifyArgs) -> Result<()> {
             println!("\nInvalid items found: ");
 
             for e in errors {
-                println!("- Item {}: {}", e.0, e.1);
+                println!("- {}: {}", e.0, e.1);
+            }
+
+            // the repair only writes the config lines that differ from the cache
+            if duplicate_uri_count > 0 {
+                println!(
+                    "\n{}Duplicate uris cannot be fixed by `verify --repair`: fix the items in \
+                    the cache and deploy a new candy machine.",
+                    WARNING_EMOJI
+                );
+            }
+
+            if args.repair {
+                let config_lines = generate_config_lines(
+                    num_items,
+                    &cache.items,
+                    &candy_machine.data,
+                    None,
+                    &order,
+                )?;
+
+                if config_lines.is_empty() {
+                    println!("\nNo config lines to repair from the cache.");
+                    return Err(anyhow!("{} invalid item(s) found.", total));
+                }
+
+                println!("\nRepairing config lines from the cache:");
+                let errors = upload_config_lines(
+                    Arc::clone(&sugar_config),
+                    candy_machine_pubkey,
+                    &mut cache,
+                    config_lines,
+                    args.interrupted,
+                    None,
+                    None,
+                )
+                .await?;
+
+                if !errors.is_empty() {
+                    return Err(anyhow!(
+                        "Failed to repair config lines, {} error(s) occurred.",
+                        errors.len()
+                    ));
+                }
+
+                println!("\nConfig lines repaired - re-run `verify` to confirm.");
+                return Ok(());
+            }
+
+            if total > duplicate_uri_count {
+                println!("\nCache updated - re-run `deploy` or `verify --repair`.");
             }
-            println!("\nCache updated - re-run `deploy`.");
             return Err(anyhow!("{} invalid item(s) found.", total));
         } else {
             pb.finish_with_message(format!(
                 "{}",
                 style("Config line verification successful ").green().bold()
             ));
+            // records the verified status of the items
+            cache.sync_file()?;
+        }
+
+        let duplicate_count = duplicate_names.len();
+
+        for (name, indices) in duplicate_names {
+            println!(
+                "{}Items {} share the same name '{}'",
+                WARNING_EMOJI,
+                join_indices(&indices),
+                name
+            );
+        }
+
+        if duplicate_count > 0 {
+            stop_on_warning(&format!(
+                "{duplicate_count} name(s) shared by several items"
+            ))?;
         }
     } else {
         return Err(anyhow!(
@@ -175,12 +317,13 @@ pub fn process_verify(args: VerifyArgs) -> Result<()> {
         );
     } else {
         let collection_mint_cache = cache.program.collection_mint.clone();
-        let collection_needs_deploy = if let Some(collection_item) = cache.items.get("-1") {
-            !collection_item.on_chain
-        } else {
-            false
-        };
-        let collection_item = cache.items.get_mut("-1");
+        let collection_needs_deploy =
+            if let Some(collection_item) = cache.items.get(COLLECTION_CACHE_KEY) {
+                !collection_item.on_chain
+            } else {
+                false
+            };
+        let collection_item = cache.items.get_mut(COLLECTION_CACHE_KEY);
 
         let collection_metadata = find_metadata_pda(&candy_machine.collection_mint);
         let data = program.rpc().get_account_data(&collection_metadata)?;
@@ -191,6 +334,7 @@ pub fn process_verify(args: VerifyArgs) -> Result<()> {
             cache.program.collection_mint = metadata.mint.to_string();
             if let Some(collection_item) = collection_item {
                 collection_item.on_chain = false;
+                collection_item.set_status(ItemStatus::Uploaded);
             }
             cache.sync_file()?;
             println!("Cache updated - re-run `deploy`.");
@@ -205,7 +349,7 @@ pub fn process_verify(args: VerifyArgs) -> Result<()> {
         }
     }
 
-    let cluster = match get_cluster(program.rpc())? {
+    let cluster = match get_cluster(&program.rpc())? {
         Cluster::Devnet => "devnet",
         Cluster::Mainnet => "mainnet",
         Cluster::Localnet => "localnet",
@@ -243,3 +387,45 @@ fn items_match(cache_item: &CacheItem, on_chain_item: &OnChainItem) -> Result<()
 
     Ok(())
 }
+
+/// Return the values that appear at more than one index, ordered by their first index.
+fn find_duplicates(values: HashMap<String, Vec<u64>>) -> Vec<(String, Vec<u64>)> {
+    let mut duplicates: Vec<(String, Vec<u64>)> = values
+        .into_iter()
+        .filter(|(_, indices)| indices.len() > 1)
+        .map(|(value, mut indices)| {
+            // shuffled deploys do not write the items in the order of the cache
+            indices.sort_unstable();
+            (value, indices)
+        })
+        .collect();
+    duplicates.sort_by_key(|(_, indices)| indices[0]);
+
+    duplicates
+}
+
+fn join_indices(indices: &[u64]) -> String {
+    indices
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[test]
+fn test_find_duplicates_ordered_by_first_index() {
+    let mut values = HashMap::new();
+    values.insert("a".to_string(), vec![4, 6]);
+    values.insert("b".to_string(), vec![1]);
+    values.insert("c".to_string(), vec![5, 2, 3]);
+
+    let duplicates = find_duplicates(values);
+
+    assert_eq!(
+        duplicates,
+        vec![
+            ("c".to_string(), vec![2, 3, 5]),
+            ("a".to_string(), vec![4, 6]),
+        ]
+    );
+}
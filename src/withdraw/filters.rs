@@ -0,0 +1,160 @@
+use std::{collections::HashSet, fs};
+
+use anchor_client::solana_sdk::{account::Account, native_token::LAMPORTS_PER_SOL};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+
+use crate::{analytics::TimeRange, common::*};
+
+/// Maximum number of signatures returned per page.
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+/// Filters selecting which candy machines are listed or drained.
+#[derive(Debug, Default)]
+pub struct WithdrawFilter {
+    pub min_lamports: Option<u64>,
+    pub created: Option<TimeRange>,
+    pub ids: Option<HashSet<Pubkey>>,
+}
+
+impl WithdrawFilter {
+    pub fn new(
+        min_balance: Option<f64>,
+        created_after: Option<String>,
+        created_before: Option<String>,
+        ids_file: Option<String>,
+    ) -> Result<Self> {
+        let created = if created_after.is_some() || created_before.is_some() {
+            Some(TimeRange::parse(created_after, created_before)?)
+        } else {
+            None
+        };
+
+        Ok(WithdrawFilter {
+            min_lamports: min_balance.map(|sol| (sol * LAMPORTS_PER_SOL as f64) as u64),
+            created,
+            ids: ids_file.as_deref().map(load_id_list).transpose()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min_lamports.is_none() && self.created.is_none() && self.ids.is_none()
+    }
+
+    /// Keep the candy machines matching every filter. The creation date filter is
+    /// applied last, since it requires fetching the transaction history of each account.
+    pub fn apply(
+        &self,
+        rpc_client: &RpcClient,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        let mut filtered = Vec::new();
+
+        for (pubkey, account) in accounts {
+            if let Some(min_lamports) = self.min_lamports {
+                if account.lamports < min_lamports {
+                    continue;
+                }
+            }
+
+            if let Some(ids) = &self.ids {
+                if !ids.contains(&pubkey) {
+                    continue;
+                }
+            }
+
+            if let Some(created) = &self.created {
+                match get_creation_time(rpc_client, &pubkey)? {
+                    Some(timestamp) if created.contains(timestamp) => (),
+                    _ => continue,
+                }
+            }
+
+            filtered.push((pubkey, account));
+        }
+
+        Ok(filtered)
+    }
+}
+
+/// Load a list of candy machine ids: one id per line, empty lines and lines starting
+/// with `#` are ignored.
+pub fn load_id_list(path: &str) -> Result<HashSet<Pubkey>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read ID list '{}': {}", path, e))?;
+
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            Pubkey::from_str(line).map_err(|_| anyhow!("Invalid id '{}' in '{}'", line, path))
+        })
+        .collect()
+}
+
+/// Block time of the oldest transaction of the account, i.e., when it was created.
+pub fn get_creation_time(rpc_client: &RpcClient, address: &Pubkey) -> Result<Option<i64>> {
+    let mut before = None;
+    let mut oldest = None;
+
+    loop {
+        let page = rpc_client.get_signatures_for_address_with_config(
+            address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(SIGNATURES_PAGE_SIZE),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?;
+
+        let last = match page.last() {
+            Some(last) => last,
+            None => break,
+        };
+
+        oldest = last.block_time.or(oldest);
+
+        if page.len() < SIGNATURES_PAGE_SIZE {
+            break;
+        }
+
+        before = Some(Signature::from_str(&last.signature)?);
+    }
+
+    Ok(oldest)
+}
+
+#[test]
+fn test_filter_by_balance_and_ids() {
+    let small = Pubkey::new_unique();
+    let large = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+
+    let account = |lamports: u64| Account {
+        lamports,
+        ..Default::default()
+    };
+
+    let filter = WithdrawFilter {
+        min_lamports: Some(LAMPORTS_PER_SOL),
+        ids: Some(HashSet::from([small, large])),
+        created: None,
+    };
+
+    // the creation date filter is not set, so no RPC call is made
+    let rpc_client = RpcClient::new("http://localhost:8899".to_string());
+    let filtered = filter
+        .apply(
+            &rpc_client,
+            vec![
+                (small, account(LAMPORTS_PER_SOL / 2)),
+                (large, account(2 * LAMPORTS_PER_SOL)),
+                (other, account(3 * LAMPORTS_PER_SOL)),
+            ],
+        )
+        .unwrap();
+
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].0, large);
+}
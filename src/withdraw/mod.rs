@@ -1,3 +1,5 @@
+pub mod filters;
 pub mod process;
 
+pub use filters::*;
 pub use process::*;
@@ -4,6 +4,7 @@ pub use anchor_client::{
     solana_sdk::{
         commitment_config::{CommitmentConfig, CommitmentLevel},
         compute_budget::ComputeBudgetInstruction,
+        instruction::Instruction,
         native_token::LAMPORTS_PER_SOL,
         pubkey::Pubkey,
         signature::{Keypair, Signature, Signer},
@@ -12,8 +13,9 @@ pub use anchor_client::{
     },
     Client, Program,
 };
+use anchor_lang::{InstructionData, ToAccountMetas};
 use console::{style, Style};
-use dialoguer::{theme::ColorfulTheme, Confirm};
+use dialoguer::theme::ColorfulTheme;
 use mpl_candy_machine_core::{accounts as nft_accounts, instruction as nft_instruction};
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::{
@@ -24,9 +26,13 @@ use solana_client::{
 use crate::{
     candy_machine::CANDY_MACHINE_ID,
     common::*,
+    core_candy_machine::CORE_CANDY_MACHINE_ID,
+    dry_run::SendOrPlan,
     parse::parse_sugar_errors,
-    setup::{setup_client, sugar_setup},
+    rate_limit::throttled_client_with_commitment,
+    setup::{setup_client, sugar_setup, sugar_setup_read_only},
     utils::*,
+    withdraw::WithdrawFilter,
 };
 
 pub struct WithdrawArgs {
@@ -35,6 +41,11 @@ pub struct WithdrawArgs {
     pub rpc_url: Option<String>,
     pub list: bool,
     pub authority: Option<String>,
+    pub destination: Option<String>,
+    pub min_balance: Option<f64>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub ids_file: Option<String>,
 }
 
 #[derive(Debug)]
@@ -44,6 +55,22 @@ struct WithdrawError {
 }
 
 pub fn process_withdraw(args: WithdrawArgs) -> Result<()> {
+    let destination = args
+        .destination
+        .as_deref()
+        .map(|destination| {
+            Pubkey::from_str(destination)
+                .map_err(|_| anyhow!("Failed to parse destination: {}", destination))
+        })
+        .transpose()?;
+
+    let filter = WithdrawFilter::new(
+        args.min_balance,
+        args.created_after,
+        args.created_before,
+        args.ids_file,
+    )?;
+
     // (1) Setting up connection
 
     println!(
@@ -55,10 +82,18 @@ pub fn process_withdraw(args: WithdrawArgs) -> Result<()> {
     let pb = spinner_with_style();
     pb.set_message("Connecting...");
 
-    let (program, payer, authority) = setup_withdraw(args.keypair, args.rpc_url, args.authority)?;
+    let (program, payer, authority) =
+        setup_withdraw(args.keypair, args.rpc_url, args.authority, args.list)?;
 
     pb.finish_with_message("Connected");
 
+    // funds already return to the payer when it is the destination
+    let destination = destination.filter(|destination| *destination != payer);
+
+    if let Some(destination) = destination {
+        println!("Reclaimed funds will be transferred to {}", destination);
+    }
+
     // if --authority is specified and it does not match the keypair,
     // then we cannot withdraw
     let list = args.list || (payer != authority);
@@ -83,14 +118,21 @@ pub fn process_withdraw(args: WithdrawArgs) -> Result<()> {
             let pb = spinner_with_style();
             pb.set_message("Draining candy machine...");
 
-            do_withdraw(Rc::new(program), candy_machine, payer)?;
+            let candy_machine_program = program.rpc().get_account(&candy_machine)?.owner;
+            do_withdraw(
+                Rc::new(program),
+                candy_machine,
+                candy_machine_program,
+                payer,
+                destination,
+            )?;
 
             pb.finish_with_message("Done");
         }
         None => {
-            let config = RpcProgramAccountsConfig {
+            let config = |offset: usize| RpcProgramAccountsConfig {
                 filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
-                    16, // key
+                    offset, // key
                     authority.as_ref(),
                 ))]),
                 account_config: RpcAccountInfoConfig {
@@ -108,9 +150,21 @@ pub fn process_withdraw(args: WithdrawArgs) -> Result<()> {
             pb.set_message("Looking up candy machines...");
 
             let program = Rc::new(program);
-            let accounts = program
-                .rpc()
-                .get_program_accounts_with_config(&program.id(), config)?;
+            let rpc_client = throttled_client_with_commitment(&program.rpc().url());
+            let mut accounts =
+                rpc_client.get_program_accounts_with_config(&CANDY_MACHINE_ID, config(16))?;
+
+            // core candy machines store the authority right after the discriminator
+            accounts.extend(
+                rpc_client.get_program_accounts_with_config(&CORE_CANDY_MACHINE_ID, config(8))?,
+            );
+
+            let accounts = if filter.is_empty() {
+                accounts
+            } else {
+                pb.set_message("Filtering candy machines...");
+                filter.apply(&rpc_client, accounts)?
+            };
 
             pb.finish_and_clear();
 
@@ -122,8 +176,13 @@ pub fn process_withdraw(args: WithdrawArgs) -> Result<()> {
             });
 
             println!(
-                "\nFound {} candy machines, total amount: ◎ {}",
+                "\nFound {} candy machines{}, total amount: ◎ {}",
                 accounts.len(),
+                if filter.is_empty() {
+                    ""
+                } else {
+                    " matching the filters"
+                },
                 total / LAMPORTS_PER_SOL as f64
             );
 
@@ -150,16 +209,20 @@ pub fn process_withdraw(args: WithdrawArgs) -> Result<()> {
 
                     println!("{}\n", style(warning).bold().yellow());
 
+                    if !filter.is_empty() {
+                        println!(
+                            "Only the {} candy machine(s) matching the filters will be drained.\n",
+                            accounts.len()
+                        );
+                    }
+
                     let theme = ColorfulTheme {
                         success_prefix: style("✔".to_string()).yellow().force_styling(true),
                         values_style: Style::new().yellow(),
                         ..get_dialoguer_theme()
                     };
 
-                    if !Confirm::with_theme(&theme)
-                        .with_prompt("Do you want to continue?")
-                        .interact()?
-                    {
+                    if !confirm_with_theme(&theme, "Do you want to continue?")? {
                         return Err(anyhow!("Withdraw aborted"));
                     }
 
@@ -168,8 +231,15 @@ pub fn process_withdraw(args: WithdrawArgs) -> Result<()> {
                     let mut error_messages = Vec::new();
 
                     accounts.iter().for_each(|account| {
-                        let (candy_machine, _account) = account;
-                        do_withdraw(program.clone(), *candy_machine, payer).unwrap_or_else(|e| {
+                        let (candy_machine, account) = account;
+                        do_withdraw(
+                            program.clone(),
+                            *candy_machine,
+                            account.owner,
+                            payer,
+                            destination,
+                        )
+                        .unwrap_or_else(|e| {
                             not_drained += 1;
                             error!("Error: {}", e);
                             let error_message = parse_sugar_errors(&e.to_string());
@@ -214,8 +284,21 @@ fn setup_withdraw(
     keypair: Option<String>,
     rpc_url: Option<String>,
     authority_opt: Option<String>,
+    list: bool,
 ) -> Result<(Program<Rc<Keypair>>, Pubkey, Pubkey)> {
-    let sugar_config = sugar_setup(keypair, rpc_url)?;
+    // listing does not need a keypair
+    let sugar_config = if list {
+        sugar_setup_read_only(keypair, rpc_url)?
+    } else {
+        sugar_setup(keypair, rpc_url)?
+    };
+
+    if sugar_config.read_only && authority_opt.is_none() {
+        return Err(anyhow!(
+            "No keypair found, use --authority to list the candy machines of a wallet."
+        ));
+    }
+
     let client = setup_client(&sugar_config)?;
     let program = client.program(CANDY_MACHINE_ID);
     let payer = program.payer();
@@ -228,23 +311,42 @@ fn setup_withdraw(
     Ok((program, payer, authority))
 }
 
+/// Withdraw the funds of the candy machine; when a destination is specified, the
+/// reclaimed lamports are transferred to it in the same transaction. Both candy
+/// machine programs share the withdraw instruction.
 fn do_withdraw<C: Deref<Target = impl Signer> + Clone>(
     program: Rc<Program<C>>,
     candy_machine: Pubkey,
+    candy_machine_program: Pubkey,
     payer: Pubkey,
+    destination: Option<Pubkey>,
 ) -> Result<()> {
     let compute_units = ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNITS);
     let priority_fee = ComputeBudgetInstruction::set_compute_unit_price(PRIORITY_FEE);
-    program
+
+    // the withdraw instruction is built explicitly so the transfer can follow it
+    let withdraw = Instruction {
+        program_id: candy_machine_program,
+        accounts: nft_accounts::Withdraw {
+            candy_machine,
+            authority: payer,
+        }
+        .to_account_metas(None),
+        data: nft_instruction::Withdraw {}.data(),
+    };
+
+    let mut builder = program
         .request()
         .instruction(compute_units)
         .instruction(priority_fee)
-        .accounts(nft_accounts::Withdraw {
-            candy_machine,
-            authority: payer,
-        })
-        .args(nft_instruction::Withdraw {})
-        .send()?;
+        .instruction(withdraw);
+
+    if let Some(destination) = destination {
+        let lamports = program.rpc().get_balance(&candy_machine)?;
+        builder = builder.instruction(system_instruction::transfer(&payer, &destination, lamports));
+    }
+
+    builder.send_or_plan("withdraw")?;
 
     Ok(())
 }
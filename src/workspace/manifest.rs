@@ -0,0 +1,60 @@
+use std::{collections::HashSet, fs};
+
+use serde::Serialize;
+
+use crate::common::*;
+
+/// Manifest listing the candy machines of a project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceManifest {
+    pub candy_machines: Vec<WorkspaceEntry>,
+}
+
+/// A candy machine of the workspace, identified by its config and cache files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceEntry {
+    pub name: String,
+    pub config: String,
+    pub cache: String,
+}
+
+/// Load the workspace manifest, resolving the config and cache paths relative
+/// to the directory of the manifest.
+pub fn load_workspace(workspace_path: &str) -> Result<Vec<WorkspaceEntry>> {
+    let path = Path::new(workspace_path);
+
+    if !path.is_file() {
+        return Err(anyhow!("Workspace manifest not found: {}", workspace_path));
+    }
+
+    let manifest: WorkspaceManifest = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| anyhow!("Invalid workspace manifest '{}': {}", workspace_path, e))?;
+
+    if manifest.candy_machines.is_empty() {
+        return Err(anyhow!(
+            "Workspace manifest '{}' has no candy machines",
+            workspace_path
+        ));
+    }
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut names = HashSet::new();
+
+    manifest
+        .candy_machines
+        .into_iter()
+        .map(|entry| {
+            if !names.insert(entry.name.clone()) {
+                return Err(anyhow!("Duplicate workspace entry name: {}", entry.name));
+            }
+
+            Ok(WorkspaceEntry {
+                config: path_to_string(&base_dir.join(&entry.config))?,
+                cache: path_to_string(&base_dir.join(&entry.cache))?,
+                name: entry.name,
+            })
+        })
+        .collect()
+}
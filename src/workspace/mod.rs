@@ -0,0 +1,5 @@
+pub mod manifest;
+pub mod process;
+
+pub use manifest::*;
+pub use process::*;
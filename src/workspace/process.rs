@@ -0,0 +1,178 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use console::style;
+use tabled::{builder::Builder, settings::Style};
+
+use crate::{
+    cache::load_cache,
+    candy_machine::get_candy_machine_state,
+    common::*,
+    deploy::{process_deploy, DeployArgs},
+    utils::*,
+    workspace::load_workspace,
+};
+
+pub struct DeployAllArgs {
+    pub workspace: String,
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+    pub interrupted: Arc<AtomicBool>,
+}
+
+pub struct ShowAllArgs {
+    pub workspace: String,
+    pub keypair: Option<String>,
+    pub rpc_url: Option<String>,
+}
+
+pub async fn process_deploy_all(args: DeployAllArgs) -> Result<()> {
+    let entries = load_workspace(&args.workspace)?;
+    let total = entries.len();
+    let mut results = Vec::with_capacity(total);
+
+    for (i, entry) in entries.iter().enumerate() {
+        println!(
+            "{} {}Deploying '{}'\n",
+            style(format!("[{}/{}]", i + 1, total)).bold().dim(),
+            CANDY_EMOJI,
+            entry.name
+        );
+
+        let deploy_args = DeployArgs {
+            config: entry.config.clone(),
+            cache: entry.cache.clone(),
+            keypair: args.keypair.clone(),
+            rpc_url: args.rpc_url.clone(),
+            interrupted: args.interrupted.clone(),
+            collection_mint: None,
+            indices: None,
+            fee_payers: None,
+            shuffle: None,
+            append: false,
+            failures: DEFAULT_FAILURES.to_string(),
+        };
+
+        let result = process_deploy(deploy_args).await;
+
+        if let Err(err) = &result {
+            println!("\n{} {}", style("Error:").red().bold(), err);
+        }
+
+        // padding
+        println!();
+        results.push(result);
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["Name", "Candy machine", "Status"]);
+
+    let mut failed = 0;
+
+    for (entry, result) in entries.iter().zip(results) {
+        let candy_machine = load_cache(&entry.cache, false)
+            .map(|cache| cache.program.candy_machine)
+            .unwrap_or_default();
+        let status = match result {
+            Ok(()) => style("deployed").green().to_string(),
+            Err(_) => {
+                failed += 1;
+                style("failed").red().to_string()
+            }
+        };
+
+        builder.push_record([entry.name.clone(), candy_machine, status]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::psql());
+    println!("{}", table);
+
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} of {} candy machine(s) failed to deploy",
+            failed,
+            total
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn process_show_all(args: ShowAllArgs) -> Result<()> {
+    println!(
+        "{} {}Looking up candy machines",
+        style("[1/1]").bold().dim(),
+        LOOKING_GLASS_EMOJI
+    );
+
+    let entries = load_workspace(&args.workspace)?;
+    let sugar_config = sugar_setup(args.keypair, args.rpc_url)?;
+
+    let pb = progress_bar_with_style(entries.len() as u64);
+
+    let mut builder = Builder::default();
+    builder.push_record(["Name", "Candy machine", "Items", "Minted", "Remaining"]);
+
+    let mut total_available = 0;
+    let mut total_redeemed = 0;
+
+    for entry in &entries {
+        let candy_machine = load_cache(&entry.cache, false)
+            .map(|cache| cache.program.candy_machine)
+            .unwrap_or_default();
+
+        let state = match Pubkey::from_str(&candy_machine) {
+            Ok(pubkey) => get_candy_machine_state(&sugar_config, &pubkey).ok(),
+            Err(_) => None,
+        };
+
+        match state {
+            Some(state) => {
+                let available = state.data.items_available;
+                let redeemed = state.items_redeemed;
+                total_available += available;
+                total_redeemed += redeemed;
+
+                builder.push_record([
+                    entry.name.clone(),
+                    candy_machine,
+                    available.to_string(),
+                    redeemed.to_string(),
+                    (available - redeemed).to_string(),
+                ]);
+            }
+            None => {
+                let status = if candy_machine.is_empty() {
+                    "not deployed"
+                } else {
+                    "not found"
+                };
+                builder.push_record([
+                    entry.name.clone(),
+                    style(status).red().to_string(),
+                    "-".to_string(),
+                    "-".to_string(),
+                    "-".to_string(),
+                ]);
+            }
+        }
+
+        pb.inc(1);
+    }
+
+    pb.finish_and_clear();
+
+    builder.push_record([
+        style("Total").bold().to_string(),
+        String::new(),
+        total_available.to_string(),
+        total_redeemed.to_string(),
+        (total_available - total_redeemed).to_string(),
+    ]);
+
+    let mut table = builder.build();
+    table.with(Style::psql());
+    println!("\n{}", table);
+
+    Ok(())
+}